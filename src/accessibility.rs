@@ -0,0 +1,124 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use ratatui::buffer::Buffer;
+use ratatui::style::{Color, Modifier};
+
+/// Plain text for the symbols PDM draws, for terminals without the glyphs
+/// and for screen readers.
+const ASCII_SYMBOLS: &[(&str, &str)] = &[
+    ("⚠", "!"),
+    ("✓", "+"),
+    ("✗", "x"),
+    ("●", "*"),
+    ("○", "o"),
+    ("★", "*"),
+    ("▶", ">"),
+    ("◀", "<"),
+    ("←", "<"),
+    ("→", ">"),
+    ("↑", "^"),
+    ("↓", "v"),
+    ("⌫", "<"),
+    ("›", ">"),
+    ("…", "."),
+    ("─", "-"),
+    ("━", "="),
+    ("│", "|"),
+    ("┌", "+"),
+    ("┐", "+"),
+    ("└", "+"),
+    ("┘", "+"),
+    ("├", "+"),
+    ("┤", "+"),
+    ("▏", "|"),
+    ("░", "."),
+    ("▁", "_"),
+    ("▂", "_"),
+    ("▃", "-"),
+    ("▄", "-"),
+    ("▅", "="),
+    ("▆", "="),
+    ("▇", "#"),
+    ("█", "#"),
+];
+
+/// Marker in front of a directory in the file explorer.
+#[must_use]
+pub fn dir_marker(accessible: bool) -> &'static str {
+    if accessible { "[dir]" } else { "📁" }
+}
+
+/// Marker in front of a file in the file explorer.
+#[must_use]
+pub fn file_marker(accessible: bool) -> &'static str {
+    if accessible { "[file]" } else { "📄" }
+}
+
+/// The color text takes on a dark background: dim grays become white and
+/// dark colors their light variant.
+fn high_contrast(color: Color) -> Color {
+    match color {
+        Color::DarkGray | Color::Gray => Color::White,
+        Color::Red => Color::LightRed,
+        Color::Green => Color::LightGreen,
+        Color::Yellow => Color::LightYellow,
+        Color::Blue => Color::LightBlue,
+        Color::Magenta => Color::LightMagenta,
+        Color::Cyan => Color::LightCyan,
+        other => other,
+    }
+}
+
+/// Redraws `buf` for accessibility mode: symbols become plain text, text
+/// takes high-contrast colors and anything marked by a background color,
+/// such as a selected row, is shown in bold, underlined reverse video
+/// instead, so it stands out without telling colors apart.
+pub fn apply(buf: &mut Buffer) {
+    for cell in &mut buf.content {
+        if let Some((_, ascii)) = ASCII_SYMBOLS.iter().find(|(s, _)| *s == cell.symbol()) {
+            cell.set_symbol(ascii);
+        }
+        if cell.bg == Color::Reset {
+            cell.fg = high_contrast(cell.fg);
+        } else {
+            cell.fg = Color::Reset;
+            cell.bg = Color::Reset;
+            cell.modifier |= Modifier::REVERSED | Modifier::BOLD | Modifier::UNDERLINED;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::layout::Rect;
+    use ratatui::style::Style;
+
+    #[test]
+    fn selection_and_symbols_do_not_depend_on_color_or_glyphs() {
+        let mut buf = Buffer::empty(Rect::new(0, 0, 6, 2));
+        buf.set_string(0, 0, "⚠ ok", Style::default().fg(Color::DarkGray));
+        buf.set_string(
+            0,
+            1,
+            "sel",
+            Style::default().bg(Color::Blue).fg(Color::White),
+        );
+        apply(&mut buf);
+
+        assert_eq!(buf[(0, 0)].symbol(), "!");
+        assert_eq!(buf[(2, 0)].fg, Color::White);
+        let selected = &buf[(0, 1)];
+        assert_eq!(selected.symbol(), "s");
+        assert_eq!((selected.fg, selected.bg), (Color::Reset, Color::Reset));
+        assert!(
+            selected
+                .modifier
+                .contains(Modifier::REVERSED | Modifier::UNDERLINED)
+        );
+        assert_eq!(buf[(4, 1)].modifier, Modifier::empty());
+        assert_eq!(dir_marker(true), "[dir]");
+    }
+}
@@ -2,12 +2,51 @@
 //
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
-use crate::bitcoin_config::ConfigEntry as BitcoinEntry;
+use crate::asmap::AsmapDownload;
+use crate::bitcoin_config::{ConfigCategory, ConfigEntry as BitcoinEntry, ParseError};
+use crate::bitcoind::{ConfigTest, NodeVersion};
+use crate::block_filters::{FilterQuery, FilterTester};
+use crate::blocks::{BlockMonitor, Reorg};
 use crate::components::bitcoin_config_view::BitcoinConfigView;
 use crate::components::file_explorer::FileExplorer;
+use crate::components::history_view::HistoryView;
+use crate::components::ln_config_view::LNConfigView;
 use crate::components::p2pool_config_view::P2PoolConfigView;
+use crate::components::plugins_view::PluginsView;
+use crate::components::preflight_view::PreflightView;
+use crate::components::services_view::ServiceConfigView;
 use crate::components::settings_view::SettingsView;
+use crate::conf_lock::{ConfLock, LockHolder};
+use crate::control::ControlSocket;
+use crate::datadir_move::MoveMethod;
+use crate::descriptors::WalletMonitor;
+use crate::diagnostics::Diagnostic;
+use crate::edit_macro::MacroStep;
+use crate::history::Change;
+use crate::hooks::NodeWatch;
+use crate::indexes::IndexMonitor;
+use crate::kiosk::Kiosk;
+use crate::lightning::LnFlavor;
+use crate::mempool::MempoolMonitor;
+use crate::mempool_accept::TxTester;
+use crate::metrics::{MetricsServer, NodeMetrics};
+use crate::mining::MiningMonitor;
+use crate::notify_scripts::ScriptKind;
+use crate::option_help::OptionHelp;
+use crate::peers::PeerMonitor;
+use crate::plugins::Plugin;
+use crate::process_args::NodeProcess;
+use crate::relay_policy::PolicyPreset;
+use crate::rpc::NodeChain;
+use crate::schema_extensions::BundleUpdate;
+use crate::secrets::{OsKeyring, SecretStore};
+use crate::services::ServiceKind;
 use crate::settings::Settings;
+use crate::template::ProfileTable;
+use crate::traffic::TrafficMonitor;
+use crate::update_check::{Release, UpdateCheck};
+use crate::utxo::UtxoMonitor;
+use crate::warnings::NodeWarnings;
 use p2poolv2_config::Config as P2PoolConfig;
 use std::path::PathBuf;
 
@@ -21,15 +60,53 @@ pub const SIDEBAR_ITEMS: &[(&str, CurrentScreen)] = &[
     ("LN Config", CurrentScreen::LNConfig),
     ("LN Status", CurrentScreen::LNStatus),
     ("Shares Market", CurrentScreen::SharesMarket),
+    ("Services", CurrentScreen::Services),
+    ("Preflight", CurrentScreen::Preflight),
+    ("Plugins", CurrentScreen::Plugins),
+    ("History", CurrentScreen::History),
     ("Settings", CurrentScreen::Settings),
 ];
 
 pub const MAX_SIDEBAR_INDEX: usize = SIDEBAR_ITEMS.len() - 1;
 
 /// Tab labels for the Bitcoin Status view
-pub const BITCOIN_STATUS_TABS: &[&str] = &["Chain Info", "System", "Logs", "Peers"];
+pub const BITCOIN_STATUS_TABS: &[&str] = &[
+    "Chain Info",
+    "System",
+    "Logs",
+    "Peers",
+    "Indexes",
+    "UTXO",
+    "Traffic",
+    "Mempool",
+    "Blocks",
+    "Wallets",
+    "Mining",
+    "Test tx",
+    "Reorgs",
+];
 
 pub const MAX_BITCOIN_STATUS_TAB: usize = BITCOIN_STATUS_TABS.len() - 1;
+/// Index of the Peers tab in `BITCOIN_STATUS_TABS`
+pub const PEERS_TAB: usize = 3;
+/// Index of the Indexes tab in `BITCOIN_STATUS_TABS`
+pub const INDEXES_TAB: usize = 4;
+/// Index of the UTXO tab in `BITCOIN_STATUS_TABS`
+pub const UTXO_TAB: usize = 5;
+/// Index of the Traffic tab in `BITCOIN_STATUS_TABS`
+pub const TRAFFIC_TAB: usize = 6;
+/// Index of the Mempool tab in `BITCOIN_STATUS_TABS`
+pub const MEMPOOL_TAB: usize = 7;
+/// Index of the Blocks tab in `BITCOIN_STATUS_TABS`
+pub const BLOCKS_TAB: usize = 8;
+/// Index of the Wallets tab in `BITCOIN_STATUS_TABS`
+pub const WALLETS_TAB: usize = 9;
+/// Index of the Mining tab in `BITCOIN_STATUS_TABS`
+pub const MINING_TAB: usize = 10;
+/// Index of the Test tx tab in `BITCOIN_STATUS_TABS`
+pub const TEST_TX_TAB: usize = 11;
+/// Index of the Reorgs tab in `BITCOIN_STATUS_TABS`
+pub const REORGS_TAB: usize = 12;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum CurrentScreen {
@@ -41,6 +118,10 @@ pub enum CurrentScreen {
     LNConfig,
     LNStatus,
     SharesMarket,
+    Services,
+    Preflight,
+    Plugins,
+    History,
     FileExplorer,
     Settings,
 }
@@ -50,6 +131,8 @@ pub enum CurrentScreen {
 pub enum ExplorerTrigger {
     BitcoinConfig,
     P2PoolConfig,
+    LNConfig,
+    Service(ServiceKind),
     /// The `usize` is the settings field index (0–`FIELD_COUNT - 1`).
     Settings(usize),
 }
@@ -76,10 +159,121 @@ pub enum AppAction {
     CommitP2PoolEdit(usize, String),
     /// Saves p2pool config to disk
     SaveP2PoolConfig,
+    /// Commits an edited Lightning config value: (entry index, new value)
+    CommitLnEdit(usize, String),
+    /// Saves the LN config to disk
+    SaveLnConfig,
+    /// Copies the bitcoind RPC settings from the loaded bitcoin.conf into the LN config
+    FillLnBitcoindRpc,
+    /// Commits an edited service config value: (service, entry index, new value)
+    CommitServiceEdit(ServiceKind, usize, String),
+    /// Saves a service config to disk
+    SaveServiceConfig(ServiceKind),
+    /// Writes a docker-compose.yml for bitcoind and the loaded services next to bitcoin.conf
+    ExportDockerCompose,
+    /// Writes nginx and Caddy reverse proxy snippets for the RPC/REST interface
+    ExportReverseProxy,
+    /// Writes an Ansible template with its tasks and a NixOS module fragment
+    ExportConfigManagement,
+    /// Takes over the bitcoin.conf of an Umbrel, RaspiBlitz or Start9 found
+    /// on this machine
+    ImportAppliance,
+    /// Moves bitcoin.conf to the next chain: main, test, testnet4, signet, regtest
+    SwitchNetwork,
+    /// Shows or hides what bitcoind uses for the selected option on each chain
+    ToggleNetworkValues,
+    /// Downloads the asmap file into the datadir, checks its hash and points `asmap` at it
+    UpdateAsmap,
+    /// Writes a Markdown report of the enabled bitcoin.conf options next to bitcoin.conf
+    ExportMarkdownReport,
+    /// Writes a copy of bitcoin.conf with secrets and paths replaced, for pasting into support threads
+    ExportSupportCopy,
+    /// Bundles the sanitized config, history, versions, chain state and debug.log tail into one archive
+    ExportDiagnosticBundle,
+    /// Downloads the signed option-schema bundle and adds its options to the editor
+    UpdateSchemaBundle,
+    /// Starts recording bitcoin.conf edits and resets, or stops and keeps the recording
+    ToggleMacroRecording,
+    /// Moves or links the data of the old `datadir` to the new one and updates paths under it
+    MoveDatadir(MoveMethod),
+    /// Applies the recorded edits to the loaded bitcoin.conf by option name
+    ReplayMacro,
+    /// Switches the bitcoin.conf schema between Bitcoin Core and Bitcoin Knots
+    ToggleNodeFlavor,
+    /// Runs bitcoind against the current bitcoin.conf entries to catch errors it would report
+    TestBitcoinConfig,
+    /// Runs the live test for the bitcoin.conf entry at this index, e.g. binding a ZMQ endpoint
+    TestOption(usize),
+    /// Renames the unknown bitcoin.conf entry at this index to its suggested option
+    AcceptKeySuggestion(usize),
+    /// Resets the bitcoin.conf entry at this index to its default and disables it
+    ResetEntry(usize),
+    /// Shows the git log and uncommitted diff of a file (bitcoin.conf when `None`)
+    ShowGitLog(Option<PathBuf>),
+    /// Runs the host checks of the Preflight screen again
+    RunPreflight,
+    /// Passes a key, by name, to the plugin on screen
+    PluginKey(String),
+    /// Writes the screen shown to a text and an ANSI file
+    ExportScreen,
+    /// Switches the bitcoin.conf editor between the entry list and the raw file text
+    ToggleRawView,
+    /// Deletes bitcoin.conf lines that repeat a single-value option, keeping the one bitcoind uses
+    RemoveDuplicates,
+    /// Opens the rpcwhitelist builder on the users of the saved bitcoin.conf
+    OpenRpcWhitelist,
+    /// Writes the builder's `rpcwhitelist` lines to bitcoin.conf
+    WriteRpcWhitelist,
+    /// Writes a notify script of this kind next to bitcoin.conf and points the entry at this index to it
+    WriteNotifyScript(usize, ScriptKind),
+    /// Looks for bitcoind's onion service key and opens the backup prompt on it
+    OpenOnionBackup,
+    /// Copies the onion key next to bitcoin.conf, encrypted when a passphrase was typed
+    BackUpOnionKey,
+    /// Sets the relay-policy options to a preset
+    ApplyPolicyPreset(PolicyPreset),
+    /// Saves bitcoin.conf and pushes the entry at this index to the running node over RPC
+    ApplyNow(usize),
+    /// Sizes `prune` to the free space on the blocks filesystem, or turns it off when the full chain fits
+    SizePrune,
+    /// Loads the config file the running bitcoind reads
+    OpenRunningConfig,
+    /// Opens the profile editor on `<bitcoin.conf>.profiles.toml`
+    OpenProfiles,
+    /// Removes a profile's own value for an option or var so it inherits it again
+    ClearProfileOverride(String, ProfileTable, String),
+    /// Writes one bitcoin.conf per profile in `<bitcoin.conf>.profiles.toml`
+    GenerateProfiles,
+    /// Moves the secret bitcoin.conf entry at this index into the OS keyring
+    StoreSecret(usize),
+    /// Pins or unpins the bitcoin.conf entry at this index in the user settings
+    ToggleFavorite(usize),
+    /// Resets every bitcoin.conf entry of a category to its default
+    ResetCategory(ConfigCategory),
+    /// Asks bitcoind for UTXO set statistics, scanning the chainstate without `coinstatsindex`
+    ScanUtxoSet,
+    /// Fetches a block's compact filter and checks it for a script
+    TestBlockFilter(FilterQuery),
+    /// Asks bitcoind whether its mempool would accept the raw transactions
+    TestMempoolAccept(Vec<String>),
+    /// Writes the descriptors shown on the Wallets tab to a text file next to bitcoin.conf
+    ExportDescriptors,
+    /// Saves the monthly data cap typed on the Traffic tab, in GB, and sets `maxuploadtarget` from it
+    SetUploadBudget(u64),
     // Open the file explorer to pick a path for a settings field (field index)
     OpenExplorerForSettings(usize),
     // Clear a settings field by index, setting it back to None
     ClearSettingsField(usize),
+    /// Turns accessibility mode on or off and saves the setting
+    ToggleAccessibility,
+}
+
+/// A service config file loaded on the Services screen, with its editor state.
+#[derive(Debug, Clone, Default)]
+pub struct ServiceFile {
+    pub path: Option<PathBuf>,
+    pub data: Vec<BitcoinEntry>,
+    pub view: ServiceConfigView,
 }
 
 pub struct App {
@@ -88,13 +282,119 @@ pub struct App {
     pub explorer_trigger: Option<ExplorerTrigger>,
     pub bitcoin_conf_path: Option<PathBuf>,
     pub p2pool_conf_path: Option<PathBuf>,
+    pub ln_conf_path: Option<PathBuf>,
     pub explorer: FileExplorer,
     pub bitcoin_config_view: BitcoinConfigView,
     pub p2pool_config_view: P2PoolConfigView,
+    pub ln_config_view: LNConfigView,
     pub settings_view: SettingsView,
+    pub history_view: HistoryView,
+    pub preflight_view: PreflightView,
+    pub plugins_view: PluginsView,
+    /// Plugins from the settings, started with PDM
+    pub plugins: Vec<Plugin>,
     pub p2pool_config: Option<P2PoolConfig>,
     pub bitcoin_data: Vec<BitcoinEntry>,
+    /// Problems found in `bitcoin_data`, shown under the Bitcoin Config panels
+    pub bitcoin_diagnostics: Vec<Diagnostic>,
+    /// bitcoin.conf that could not be loaded, with its syntax errors
+    pub bitcoin_parse_errors: Option<(PathBuf, Vec<ParseError>)>,
+    /// Entries of the loaded Lightning config
+    pub ln_data: Vec<BitcoinEntry>,
+    /// Which Lightning implementation `ln_data` belongs to
+    pub ln_flavor: LnFlavor,
     pub bitcoin_status_tab: usize,
+    /// bitcoind binary in use, from settings or found on `PATH`
+    pub bitcoind_binary: Option<PathBuf>,
+    /// Version reported by `bitcoind_binary`
+    pub node_version: Option<NodeVersion>,
+    /// Chain the bitcoind answering RPC is on, if one does
+    pub node_chain: Option<NodeChain>,
+    /// The running bitcoind that reads the loaded bitcoin.conf, if its
+    /// command line could be read
+    pub node_process: Option<NodeProcess>,
+    /// A bitcoind found running on this machine at startup, offered on
+    /// the Home screen
+    pub running_node: Option<NodeProcess>,
+    /// Edits recorded so far, while a macro is being recorded
+    pub macro_recording: Option<Vec<MacroStep>>,
+    /// Last recorded macro, replayed on whichever bitcoin.conf is loaded
+    pub recorded_macro: Vec<MacroStep>,
+    /// Startup check for a newer PDM release, until it answers
+    pub update_check: Option<UpdateCheck>,
+    /// bitcoind check of the unsaved config, until it answers
+    pub config_test: Option<ConfigTest>,
+    /// asmap being downloaded, until it is saved or fails
+    pub asmap_download: Option<AsmapDownload>,
+    /// Signed option bundle being downloaded and checked, until it is
+    /// installed or fails
+    pub bundle_update: Option<BundleUpdate>,
+    /// Newer PDM release found by the update check
+    pub update_notice: Option<Release>,
+    /// Where the last screenshot went, or why it failed; shown until the
+    /// next key
+    pub screenshot_message: Option<String>,
+    /// Peers of the running bitcoind, polled while the Peers tab is shown
+    pub peer_monitor: PeerMonitor,
+    /// Index states of the running bitcoind, polled while the Indexes tab is shown
+    pub index_monitor: IndexMonitor,
+    /// UTXO set statistics of the running bitcoind, shown on the UTXO tab
+    pub utxo_monitor: UtxoMonitor,
+    /// Bandwidth of the running bitcoind, polled while the Traffic tab is shown
+    pub traffic_monitor: TrafficMonitor,
+    /// Mempool feerates of the running bitcoind, polled while the Mempool tab is shown
+    pub mempool_monitor: MempoolMonitor,
+    /// Recent blocks and reorgs of the running bitcoind, followed while the Blocks tab is shown
+    pub block_monitor: BlockMonitor,
+    /// Descriptors of the loaded wallets, polled while the Wallets tab is shown
+    pub wallet_monitor: WalletMonitor,
+    /// BIP158 filter lookups on the Indexes tab
+    pub filter_tester: FilterTester,
+    /// Mining info and the next block template, polled while the Mining tab is shown
+    pub mining_monitor: MiningMonitor,
+    /// Raw transactions run through `testmempoolaccept` on the Test tx tab
+    pub tx_tester: TxTester,
+    /// Warnings the running bitcoind reports, shown above every screen
+    pub node_warnings: NodeWarnings,
+    /// State of bitcoind at the last poll, to fire hooks when it changes
+    pub node_watch: NodeWatch,
+    /// Why the last hook did not start
+    pub hook_error: Option<String>,
+    /// Last reorg seen on `zmqpubsequence`, shown above every screen until
+    /// the Reorgs tab is opened
+    pub reorg_alert: Option<Reorg>,
+    /// Every reorg logged, oldest first, for the Reorgs tab
+    pub reorg_history: Vec<Reorg>,
+    /// Where reorgs are logged; `None` keeps them in memory only
+    pub reorg_log: Option<PathBuf>,
+    /// Why the last reorg could not be logged
+    pub reorg_log_error: Option<String>,
+    /// Node metrics served to Prometheus, polled while the exporter runs
+    pub node_metrics: NodeMetrics,
+    /// Prometheus endpoint, when `metrics_listen` is set
+    pub metrics_server: Option<MetricsServer>,
+    /// Why the Prometheus endpoint is not running
+    pub metrics_error: Option<String>,
+    /// Socket scripts query and edit the loaded config through
+    pub control_socket: Option<ControlSocket>,
+    /// Why the control socket could not be opened
+    pub control_error: Option<String>,
+    /// Lock this PDM holds on the loaded bitcoin.conf
+    pub bitcoin_lock: Option<ConfLock>,
+    /// Another PDM editing the loaded bitcoin.conf, which is then read only
+    pub bitcoin_locked_by: Option<LockHolder>,
+    /// Set when started with `--kiosk`: a read-only dashboard
+    pub kiosk: Option<Kiosk>,
+    /// Long-form bitcoind option help for the detail pane
+    pub option_help: OptionHelp,
+    /// Where secrets moved out of config files are kept
+    pub secret_store: Box<dyn SecretStore>,
+    /// One entry per `ServiceKind::ALL`, in the same order
+    pub services: Vec<ServiceFile>,
+    /// Index into `ServiceKind::ALL` of the selected Services tab
+    pub service_tab: usize,
+    /// Recorded changes of the loaded config files, newest first
+    pub history: Vec<(PathBuf, Change)>,
     pub settings: Settings,
     /// Cached value of the `HOME` environment variable, used for path display.
     /// Populated once at startup to avoid repeated syscalls during rendering.
@@ -113,13 +413,69 @@ impl App {
             explorer_trigger: None,
             bitcoin_conf_path: None,
             p2pool_conf_path: None,
+            ln_conf_path: None,
             explorer: FileExplorer::new(),
             bitcoin_config_view: BitcoinConfigView::new(),
             p2pool_config_view: P2PoolConfigView::new(),
+            ln_config_view: LNConfigView::new(),
             settings_view: SettingsView::new(),
+            history_view: HistoryView::new(),
+            preflight_view: PreflightView::new(),
+            plugins_view: PluginsView::new(),
+            plugins: Vec::new(),
             p2pool_config: None,
             bitcoin_data: Vec::new(),
+            bitcoin_diagnostics: Vec::new(),
+            bitcoin_parse_errors: None,
+            ln_data: Vec::new(),
+            ln_flavor: LnFlavor::default(),
             bitcoin_status_tab: 0,
+            bitcoind_binary: None,
+            node_version: None,
+            node_chain: None,
+            node_process: None,
+            running_node: None,
+            macro_recording: None,
+            recorded_macro: Vec::new(),
+            update_check: None,
+            config_test: None,
+            asmap_download: None,
+            bundle_update: None,
+            update_notice: None,
+            screenshot_message: None,
+            peer_monitor: PeerMonitor::default(),
+            index_monitor: IndexMonitor::default(),
+            utxo_monitor: UtxoMonitor::default(),
+            traffic_monitor: TrafficMonitor::default(),
+            mempool_monitor: MempoolMonitor::default(),
+            block_monitor: BlockMonitor::default(),
+            wallet_monitor: WalletMonitor::default(),
+            filter_tester: FilterTester::default(),
+            mining_monitor: MiningMonitor::default(),
+            tx_tester: TxTester::default(),
+            node_warnings: NodeWarnings::default(),
+            node_watch: NodeWatch::default(),
+            hook_error: None,
+            reorg_alert: None,
+            reorg_history: Vec::new(),
+            reorg_log: None,
+            reorg_log_error: None,
+            node_metrics: NodeMetrics::default(),
+            metrics_server: None,
+            metrics_error: None,
+            control_socket: None,
+            control_error: None,
+            bitcoin_lock: None,
+            bitcoin_locked_by: None,
+            kiosk: None,
+            option_help: OptionHelp::bundled(),
+            secret_store: Box::new(OsKeyring),
+            services: ServiceKind::ALL
+                .iter()
+                .map(|_| ServiceFile::default())
+                .collect(),
+            service_tab: 0,
+            history: Vec::new(),
             settings: Settings::default(),
             home_dir: std::env::var("HOME").unwrap_or_default(),
             config_dir: crate::settings::config_dir().unwrap_or_default(),
@@ -140,10 +496,38 @@ impl App {
             self.p2pool_config_view.editing = false;
             self.p2pool_config_view.edit_input.clear();
         }
+        if self.current_screen == CurrentScreen::LNConfig {
+            self.ln_config_view.warning_message = None;
+            self.ln_config_view.save_message = None;
+            self.ln_config_view.editing = false;
+            self.ln_config_view.edit_input.clear();
+        }
+        if self.current_screen == CurrentScreen::Services {
+            let view = &mut self.service_mut(self.current_service()).view;
+            view.warning_message = None;
+            view.save_message = None;
+            view.editing = false;
+            view.edit_input.clear();
+        }
         if let Some(&(_, screen)) = SIDEBAR_ITEMS.get(self.sidebar_index) {
             self.current_screen = screen;
         }
     }
+
+    /// The service shown on the selected Services tab
+    #[must_use]
+    pub fn current_service(&self) -> ServiceKind {
+        ServiceKind::ALL[self.service_tab.min(ServiceKind::ALL.len() - 1)]
+    }
+
+    #[must_use]
+    pub fn service(&self, kind: ServiceKind) -> &ServiceFile {
+        &self.services[kind as usize]
+    }
+
+    pub fn service_mut(&mut self, kind: ServiceKind) -> &mut ServiceFile {
+        &mut self.services[kind as usize]
+    }
 }
 impl Default for App {
     fn default() -> Self {
@@ -0,0 +1,302 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_config::{Assignment, MULTI_VALUE_OPTIONS, read_assignments};
+use anyhow::{Context, Result, bail};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+/// Node-in-a-box systems whose bitcoin.conf PDM can take over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Appliance {
+    Umbrel,
+    RaspiBlitz,
+    Start9,
+}
+
+impl Appliance {
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            Appliance::Umbrel => "Umbrel",
+            Appliance::RaspiBlitz => "RaspiBlitz",
+            Appliance::Start9 => "Start9",
+        }
+    }
+}
+
+/// Where each appliance keeps bitcoin.conf and the env file its settings
+/// screen writes, relative to the root of its disk.
+const LAYOUTS: &[(Appliance, &str, Option<&str>)] = &[
+    (
+        Appliance::Umbrel,
+        "home/umbrel/umbrel/app-data/bitcoin/data/bitcoin/bitcoin.conf",
+        Some("home/umbrel/umbrel/.env"),
+    ),
+    (
+        Appliance::Umbrel,
+        "home/umbrel/umbrel/bitcoin/bitcoin.conf",
+        Some("home/umbrel/umbrel/.env"),
+    ),
+    (
+        Appliance::RaspiBlitz,
+        "mnt/hdd/bitcoin/bitcoin.conf",
+        Some("mnt/hdd/raspiblitz.conf"),
+    ),
+    (
+        Appliance::Start9,
+        "embassy-data/package-data/volumes/bitcoind/data/main/bitcoin.conf",
+        None,
+    ),
+];
+
+/// An appliance's bitcoin.conf, with the fragments it includes and the
+/// env file holding the rest of its settings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApplianceConfig {
+    pub appliance: Appliance,
+    pub conf: PathBuf,
+    /// `includeconf` files that exist, in the order they are read
+    pub fragments: Vec<PathBuf>,
+    pub env: Option<PathBuf>,
+}
+
+/// The appliance layouts found under `root`, usually `/` or the mount
+/// point of an appliance's disk.
+#[must_use]
+pub fn detect(root: &Path) -> Vec<ApplianceConfig> {
+    LAYOUTS
+        .iter()
+        .filter(|(_, conf, _)| root.join(conf).is_file())
+        .map(|&(appliance, conf, env)| {
+            let conf = root.join(conf);
+            let dir = conf.parent().unwrap_or(Path::new("."));
+            let fragments = std::fs::read_to_string(&conf)
+                .map(|content| read_assignments(&content))
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|a| a.key == "includeconf")
+                .map(|a| dir.join(a.value))
+                .filter(|p| p.is_file())
+                .collect();
+            ApplianceConfig {
+                appliance,
+                conf,
+                fragments,
+                env: env.map(|e| root.join(e)).filter(|e| e.is_file()),
+            }
+        })
+        .collect()
+}
+
+/// `KEY=value` lines of a shell env file, with quotes and `export`
+/// stripped.
+#[must_use]
+pub fn read_env(content: &str) -> Vec<(String, String)> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.starts_with('#'))
+        .filter_map(|l| {
+            let (key, value) = l.strip_prefix("export ").unwrap_or(l).split_once('=')?;
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+                .unwrap_or(value);
+            Some((key.trim().to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// bitcoind options the env file of `appliance` sets.
+fn env_options(appliance: Appliance, env: &[(String, String)]) -> Vec<(&'static str, String)> {
+    let mut out = Vec::new();
+    for (key, value) in env {
+        let option = match (appliance, key.as_str(), value.as_str()) {
+            (Appliance::Umbrel, "BITCOIN_NETWORK", "testnet")
+            | (Appliance::RaspiBlitz, "chain", "test") => Some(("testnet", "1".to_string())),
+            (Appliance::Umbrel, "BITCOIN_NETWORK", "signet")
+            | (Appliance::RaspiBlitz, "chain", "sig") => Some(("signet", "1".to_string())),
+            (Appliance::Umbrel, "BITCOIN_NETWORK", "regtest") => Some(("regtest", "1".to_string())),
+            (Appliance::Umbrel, "BITCOIN_RPC_USER", _) => Some(("rpcuser", value.clone())),
+            (Appliance::Umbrel, "BITCOIN_RPC_PASS", _) => Some(("rpcpassword", value.clone())),
+            (Appliance::Umbrel, "BITCOIN_RPC_PORT", _) => Some(("rpcport", value.clone())),
+            (Appliance::Umbrel, "BITCOIN_P2P_PORT", _) => Some(("port", value.clone())),
+            _ => None,
+        };
+        out.extend(option.filter(|(_, v)| !v.is_empty()));
+    }
+    out
+}
+
+/// Every setting of `config` as one list, in the order bitcoind reads
+/// them: the conf, its fragments, then the env file. A single-value
+/// option keeps its first value, as bitcoind does.
+///
+/// # Errors
+/// Returns an error if the conf cannot be read.
+pub fn merged_assignments(config: &ApplianceConfig) -> Result<Vec<Assignment>> {
+    let read = |path: &Path| {
+        std::fs::read_to_string(path).with_context(|| format!("cannot read {}", path.display()))
+    };
+    let mut all = read_assignments(&read(&config.conf)?);
+    for fragment in &config.fragments {
+        all.extend(read_assignments(&read(fragment)?));
+    }
+    if let Some(env) = &config.env {
+        all.extend(
+            env_options(config.appliance, &read_env(&read(env)?))
+                .into_iter()
+                .map(|(key, value)| Assignment {
+                    line: 0,
+                    section: None,
+                    key: key.to_string(),
+                    value,
+                }),
+        );
+    }
+    let mut merged: Vec<Assignment> = Vec::new();
+    for assignment in all.into_iter().filter(|a| a.key != "includeconf") {
+        let multi = MULTI_VALUE_OPTIONS.contains(&assignment.key.as_str());
+        let taken = merged.iter().any(|m| {
+            m.section == assignment.section
+                && m.key == assignment.key
+                && (!multi || m.value == assignment.value)
+        });
+        if !taken {
+            merged.push(assignment);
+        }
+    }
+    Ok(merged)
+}
+
+/// One standalone bitcoin.conf holding every setting of `config`.
+///
+/// # Errors
+/// Returns an error if a file of `config` cannot be read.
+pub fn standalone_conf(config: &ApplianceConfig) -> Result<String> {
+    let assignments = merged_assignments(config)?;
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "# Imported by PDM from {} ({})",
+        config.appliance.label(),
+        config.conf.display()
+    );
+    for a in assignments.iter().filter(|a| a.section.is_none()) {
+        let _ = writeln!(out, "{}={}", a.key, a.value);
+    }
+    let mut sections: Vec<&str> = assignments
+        .iter()
+        .filter_map(|a| a.section.as_deref())
+        .collect();
+    sections.sort_unstable();
+    sections.dedup();
+    for section in sections {
+        let _ = writeln!(out, "\n[{section}]");
+        for a in assignments
+            .iter()
+            .filter(|a| a.section.as_deref() == Some(section))
+        {
+            let _ = writeln!(out, "{}={}", a.key, a.value);
+        }
+    }
+    Ok(out)
+}
+
+/// Writes `config` as a standalone bitcoin.conf at `target`, which must
+/// not exist yet.
+///
+/// # Errors
+/// Returns an error if `target` exists or a file cannot be read or written.
+pub fn import(config: &ApplianceConfig, target: &Path) -> Result<()> {
+    if target.exists() {
+        bail!("{} exists; move it aside first", target.display());
+    }
+    let content = standalone_conf(config)?;
+    if let Some(dir) = target.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("cannot create {}", dir.display()))?;
+    }
+    std::fs::write(target, content)
+        .with_context(|| format!("cannot write {}", target.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(root: &Path, path: &str, content: &str) -> PathBuf {
+        let path = root.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn umbrel_fragments_and_env_fill_in_what_the_conf_leaves_out() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = "home/umbrel/umbrel/app-data/bitcoin/data/bitcoin";
+        write(
+            root.path(),
+            &format!("{dir}/bitcoin.conf"),
+            "includeconf=umbrel-bitcoin.conf\ndbcache=2000\n",
+        );
+        write(
+            root.path(),
+            &format!("{dir}/umbrel-bitcoin.conf"),
+            "dbcache=450\nonlynet=onion\nonlynet=onion\nonlynet=ipv4\n[test]\nport=18333\n",
+        );
+        write(
+            root.path(),
+            "home/umbrel/umbrel/.env",
+            "export BITCOIN_NETWORK=testnet\nBITCOIN_RPC_USER=\"umbrel\"\n# BITCOIN_RPC_PASS=x\n",
+        );
+
+        let found = detect(root.path());
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].appliance, Appliance::Umbrel);
+        assert_eq!(found[0].fragments.len(), 1);
+        assert_eq!(
+            standalone_conf(&found[0])
+                .unwrap()
+                .lines()
+                .skip(1)
+                .collect::<Vec<_>>(),
+            [
+                "dbcache=2000",
+                "onlynet=onion",
+                "onlynet=ipv4",
+                "testnet=1",
+                "rpcuser=umbrel",
+                "",
+                "[test]",
+                "port=18333",
+            ]
+        );
+    }
+
+    #[test]
+    fn import_writes_a_new_conf_only() {
+        let root = tempfile::tempdir().unwrap();
+        write(root.path(), "mnt/hdd/bitcoin/bitcoin.conf", "server=1\n");
+        write(
+            root.path(),
+            "mnt/hdd/raspiblitz.conf",
+            "network=bitcoin\nchain='sig'\n",
+        );
+        let found = detect(root.path());
+        assert_eq!(found[0].appliance, Appliance::RaspiBlitz);
+
+        let target = root.path().join("home/.bitcoin/bitcoin.conf");
+        import(&found[0], &target).unwrap();
+        let content = std::fs::read_to_string(&target).unwrap();
+        assert!(content.starts_with("# Imported by PDM from RaspiBlitz"));
+        assert!(content.ends_with("server=1\nsignet=1\n"));
+        assert!(import(&found[0], &target).is_err());
+        assert!(detect(&root.path().join("home")).is_empty());
+    }
+}
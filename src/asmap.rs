@@ -0,0 +1,225 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_config::{ConfigEntry, enabled_value};
+use crate::diagnostics::Diagnostic;
+use crate::download::fetch;
+use crate::rpc::net_datadir;
+use anyhow::{Context, Result, bail};
+use bitcoin::hashes::{Hash, sha256};
+use std::path::{Path, PathBuf};
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime};
+
+/// File name bitcoind loads when `asmap` is set without a path.
+pub const ASMAP_FILE: &str = "ip_asn.map";
+
+/// Where the asmap is downloaded from unless the settings name another source.
+pub const DEFAULT_ASMAP_URL: &str =
+    "https://raw.githubusercontent.com/bitcoin-core/asmap-data/main/latest_asmap.dat";
+
+/// Time a download may take; an asmap is a few megabytes.
+pub const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Age after which an asmap no longer reflects how addresses are routed.
+pub const ASMAP_MAX_AGE: Duration = Duration::from_secs(180 * 24 * 3600);
+
+/// The asmap file bitcoind loads for `chain`, if `asmap` is set. Relative
+/// paths are resolved against the chain's data directory, as bitcoind does.
+#[must_use]
+pub fn asmap_path(entries: &[ConfigEntry], chain: &str) -> Option<PathBuf> {
+    let value = enabled_value(entries, "asmap")?;
+    let file = match value {
+        "0" => return None,
+        "" | "1" => ASMAP_FILE,
+        path => path,
+    };
+    let path = Path::new(file);
+    Some(if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        net_datadir(entries, chain).join(path)
+    })
+}
+
+/// Lowercase hex SHA-256 of `data`.
+#[must_use]
+pub fn sha256_hex(data: &[u8]) -> String {
+    sha256::Hash::hash(data).to_string()
+}
+
+/// Downloads the asmap at `url` to `dest` once its SHA-256 matches
+/// `expected`, or else the hash published next to it as `<url>.sha256`.
+/// Returns the hash.
+///
+/// A hash from the same server only catches a corrupted download: whoever
+/// can change the file can change its hash too. Only `expected`, set as
+/// `asmap_sha256` in settings.toml, pins the file.
+///
+/// # Errors
+/// Returns an error if the download fails, no hash is known or it does not
+/// match. `dest` is left untouched then.
+pub fn download_asmap(url: &str, expected: Option<&str>, dest: &Path) -> Result<String> {
+    let data = fetch(url, DOWNLOAD_TIMEOUT).with_context(|| format!("cannot download {url}"))?;
+    let expected = match expected {
+        Some(hash) => hash.trim().to_ascii_lowercase(),
+        None => {
+            let published =
+                fetch(&format!("{url}.sha256"), DOWNLOAD_TIMEOUT).with_context(|| {
+                    format!("no hash to check {url} against; set asmap_sha256 in settings.toml")
+                })?;
+            String::from_utf8_lossy(&published)
+                .split_whitespace()
+                .next()
+                .unwrap_or_default()
+                .to_ascii_lowercase()
+        }
+    };
+    let actual = sha256_hex(&data);
+    if actual != expected {
+        bail!("SHA-256 of {url} is {actual}, expected {expected}");
+    }
+    if let Some(dir) = dest.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let partial = dest.with_extension("partial");
+    std::fs::write(&partial, &data)?;
+    std::fs::rename(&partial, dest)?;
+    Ok(actual)
+}
+
+/// An asmap download running in the background, so the screen keeps
+/// drawing while the file comes in.
+pub struct AsmapDownload {
+    /// Where the asmap is written
+    pub dest: PathBuf,
+    /// Whether the hash is pinned, rather than read from the same server
+    pub pinned: bool,
+    handle: JoinHandle<Result<String>>,
+}
+
+impl AsmapDownload {
+    /// Starts downloading `url` to `dest`, as [`download_asmap`] does.
+    #[must_use]
+    pub fn start(url: &str, expected: Option<&str>, dest: PathBuf) -> Self {
+        let url = url.to_string();
+        let expected = expected.map(str::to_string);
+        let target = dest.clone();
+        Self {
+            dest,
+            pinned: expected.is_some(),
+            handle: std::thread::spawn(move || download_asmap(&url, expected.as_deref(), &target)),
+        }
+    }
+
+    /// Whether the download is over.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// The hash of the saved asmap, once the download is over; waits for
+    /// it otherwise.
+    ///
+    /// # Errors
+    /// Returns what [`download_asmap`] returned.
+    pub fn finish(self) -> Result<String> {
+        self.handle
+            .join()
+            .unwrap_or_else(|_| bail!("asmap download panicked"))
+    }
+}
+
+/// Problems with the asmap file `entries` point bitcoind at: missing,
+/// which stops bitcoind from starting, or older than [`ASMAP_MAX_AGE`].
+#[must_use]
+pub fn asmap_problems(entries: &[ConfigEntry], chain: &str, now: SystemTime) -> Option<Diagnostic> {
+    let path = asmap_path(entries, chain)?;
+    let Ok(modified) = std::fs::metadata(&path).and_then(|m| m.modified()) else {
+        return Some(Diagnostic::error(
+            "asmap",
+            format!(
+                "{} does not exist, so bitcoind will not start; press A on asmap to download it",
+                path.display()
+            ),
+        ));
+    };
+    let age = now.duration_since(modified).unwrap_or_default();
+    (age > ASMAP_MAX_AGE).then(|| {
+        Diagnostic::warning(
+            "asmap",
+            format!(
+                "{} is {} days old; press A on asmap to download a fresh one",
+                path.display(),
+                age.as_secs() / 86_400
+            ),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(content: &str) -> Vec<ConfigEntry> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bitcoin.conf");
+        std::fs::write(&path, content).unwrap();
+        crate::bitcoin_config::parse_config(&path).unwrap()
+    }
+
+    #[test]
+    fn asmap_path_follows_bitcoind() {
+        assert_eq!(asmap_path(&entries("datadir=/srv/btc\n"), "main"), None);
+        assert_eq!(
+            asmap_path(&entries("datadir=/srv/btc\nasmap=1\n"), "signet"),
+            Some(PathBuf::from("/srv/btc/signet/ip_asn.map"))
+        );
+        assert_eq!(
+            asmap_path(&entries("datadir=/srv/btc\nasmap=/opt/asmap.dat\n"), "main"),
+            Some(PathBuf::from("/opt/asmap.dat"))
+        );
+    }
+
+    #[test]
+    fn download_checks_the_hash() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("latest_asmap.dat");
+        std::fs::write(&source, b"asmap bytes").unwrap();
+        let url = format!("file://{}", source.display());
+        let dest = dir.path().join("data/ip_asn.map");
+
+        let err = download_asmap(&url, Some("00"), &dest).unwrap_err();
+        assert!(err.to_string().contains("expected 00"), "{err}");
+        assert!(!dest.exists());
+
+        let err = download_asmap(&url, None, &dest).unwrap_err();
+        assert!(err.to_string().contains("asmap_sha256"), "{err}");
+
+        let hash = sha256_hex(b"asmap bytes");
+        std::fs::write(
+            dir.path().join("latest_asmap.dat.sha256"),
+            format!("{hash}  latest_asmap.dat\n"),
+        )
+        .unwrap();
+        assert_eq!(download_asmap(&url, None, &dest).unwrap(), hash);
+        assert_eq!(std::fs::read(&dest).unwrap(), b"asmap bytes");
+    }
+
+    #[test]
+    fn missing_and_stale_asmaps_are_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("asmap.dat");
+        let entries = entries(&format!("asmap={}\n", file.display()));
+
+        let missing = asmap_problems(&entries, "main", SystemTime::now()).unwrap();
+        assert!(missing.message.contains("does not exist"));
+
+        std::fs::write(&file, b"x").unwrap();
+        assert!(asmap_problems(&entries, "main", SystemTime::now()).is_none());
+        let later = SystemTime::now() + ASMAP_MAX_AGE + Duration::from_secs(86_400);
+        let stale = asmap_problems(&entries, "main", later).unwrap();
+        assert!(stale.message.contains("181 days old"), "{}", stale.message);
+    }
+}
@@ -2,11 +2,16 @@
 //
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
+use crate::schema_extensions::{extend_schema, generation};
+use crate::units::{Unit, unit_for};
 use anyhow::Result;
 use config::{Config, File, FileFormat};
+use serde::{Deserialize, Serialize};
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{HashMap, HashSet, hash_map::Entry},
+    fmt,
     path::Path,
+    sync::{Arc, LazyLock, Mutex},
 };
 
 #[allow(dead_code)]
@@ -321,6 +326,291 @@ pub enum ConfigCategory {
     Mining,
     Relay,
     ZMQ,
+    // lnd.conf sections
+    LndApplication,
+    LndBitcoin,
+    LndBitcoind,
+    LndWatchtower,
+    LndTor,
+    // Core Lightning config
+    ClnGeneral,
+    ClnBitcoind,
+    ClnNetworking,
+    ClnChannels,
+    // electrs config.toml
+    ElectrsGeneral,
+    ElectrsDaemon,
+    ElectrsIndex,
+    // torrc
+    TorGeneral,
+    TorHiddenService,
+    // i2pd.conf
+    I2pdGeneral,
+    I2pdSam,
+    I2pdServices,
+}
+
+/// How prominently an option is offered in the editor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tier {
+    /// Options most node runners touch
+    Common,
+    Advanced,
+    /// Logging and testing knobs
+    Debug,
+}
+
+/// bitcoin.conf options shown in the default (common) view
+const COMMON_OPTIONS: &[&str] = &[
+    "datadir",
+    "txindex",
+    "blockfilterindex",
+    "prune",
+    "dbcache",
+    "maxmempool",
+    "chain",
+    "testnet",
+    "testnet4",
+    "signet",
+    "regtest",
+    "listen",
+    "port",
+    "maxconnections",
+    "maxuploadtarget",
+    "addnode",
+    "proxy",
+    "onion",
+    "listenonion",
+    "onlynet",
+    "server",
+    "rpcuser",
+    "rpcpassword",
+    "rpcauth",
+    "rpcport",
+    "rpcbind",
+    "rpcallowip",
+    "rest",
+    "disablewallet",
+    "zmqpubrawblock",
+    "zmqpubrawtx",
+];
+
+/// A typical value for options whose format is not obvious from their
+/// description, shown in the detail panel and as the editor placeholder.
+const EXAMPLES: &[(&str, &str)] = &[
+    ("datadir", "/srv/bitcoin"),
+    ("blocksdir", "/mnt/hdd/bitcoin"),
+    ("includeconf", "rpc.conf"),
+    ("blockfilterindex", "basic"),
+    ("prune", "550"),
+    ("dbcache", "4000"),
+    ("assumevalid", "0"),
+    ("alertnotify", "/usr/local/bin/alert.sh %s"),
+    ("blocknotify", "/usr/local/bin/newblock.sh %s"),
+    ("startupnotify", "systemd-notify --ready"),
+    ("walletnotify", "/usr/local/bin/wallettx.sh %s %w"),
+    ("bind", "0.0.0.0:8333"),
+    ("whitebind", "noban@127.0.0.1:8335"),
+    ("whitelist", "download@127.0.0.1/32"),
+    ("maxuploadtarget", "5000M"),
+    ("seednode", "seed.example.org:8333"),
+    ("addnode", "node.example.org:8333"),
+    ("connect", "192.168.1.10:8333"),
+    ("onlynet", "onion"),
+    ("proxy", "127.0.0.1:9050"),
+    ("onion", "127.0.0.1:9050"),
+    ("torcontrol", "127.0.0.1:9051"),
+    ("i2psam", "127.0.0.1:7656"),
+    ("externalip", "203.0.113.7"),
+    ("rpcauth", "alice:<salt>$<hash>"),
+    ("rpcbind", "127.0.0.1:8332"),
+    ("rpcallowip", "192.168.1.0/24"),
+    ("rpcwhitelist", "alice:getblockchaininfo,getnetworkinfo"),
+    ("wallet", "hot"),
+    ("debug", "net,mempool"),
+    ("debugexclude", "libevent"),
+    ("uacomment", "mynode"),
+    ("zmqpubhashblock", "tcp://127.0.0.1:28334"),
+    ("zmqpubhashtx", "tcp://127.0.0.1:28335"),
+    ("zmqpubrawblock", "tcp://127.0.0.1:28332"),
+    ("zmqpubrawtx", "tcp://127.0.0.1:28333"),
+    ("zmqpubsequence", "tcp://127.0.0.1:28336"),
+];
+
+/// How an option bears on another one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelationKind {
+    /// Setting the option changes the other one's default
+    Implies,
+    /// The option does nothing unless the other one is set
+    Requires,
+    /// bitcoind refuses to start with both set
+    Conflicts,
+}
+
+impl RelationKind {
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            RelationKind::Implies => "implies",
+            RelationKind::Requires => "requires",
+            RelationKind::Conflicts => "conflicts with",
+        }
+    }
+}
+
+/// A relationship of an option to another, as bitcoind's parameter
+/// handling has it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Relation {
+    pub kind: RelationKind,
+    /// The other option
+    pub other: String,
+    /// Value of the other option the relation is about; `None` for any
+    /// value that turns it on
+    pub value: Option<String>,
+}
+
+impl fmt::Display for Relation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.value {
+            Some(value) => write!(f, "{} {}={value}", self.kind.label(), self.other),
+            None => write!(f, "{} {}", self.kind.label(), self.other),
+        }
+    }
+}
+
+/// Relationships between options: the option, how it bears on the
+/// other, the other and the value concerned.
+const RELATIONS: &[(&str, RelationKind, &str, Option<&str>)] = &[
+    ("bind", RelationKind::Implies, "listen", Some("1")),
+    ("whitebind", RelationKind::Implies, "listen", Some("1")),
+    ("connect", RelationKind::Implies, "listen", Some("0")),
+    ("connect", RelationKind::Implies, "dnsseed", Some("0")),
+    ("proxy", RelationKind::Implies, "discover", Some("0")),
+    ("proxy", RelationKind::Implies, "natpmp", Some("0")),
+    ("externalip", RelationKind::Implies, "discover", Some("0")),
+    (
+        "blocksonly",
+        RelationKind::Implies,
+        "whitelistrelay",
+        Some("0"),
+    ),
+    (
+        "whitelistforcerelay",
+        RelationKind::Implies,
+        "whitelistrelay",
+        Some("1"),
+    ),
+    ("listenonion", RelationKind::Requires, "listen", Some("1")),
+    ("i2pacceptincoming", RelationKind::Requires, "i2psam", None),
+    (
+        "signetchallenge",
+        RelationKind::Requires,
+        "signet",
+        Some("1"),
+    ),
+    (
+        "signetseednode",
+        RelationKind::Requires,
+        "signet",
+        Some("1"),
+    ),
+    (
+        "peerblockfilters",
+        RelationKind::Requires,
+        "blockfilterindex",
+        None,
+    ),
+    ("rpcuser", RelationKind::Requires, "rpcpassword", None),
+    ("rpcpassword", RelationKind::Requires, "rpcuser", None),
+    (
+        "walletnotify",
+        RelationKind::Requires,
+        "disablewallet",
+        Some("0"),
+    ),
+    ("txindex", RelationKind::Conflicts, "prune", None),
+    ("signet", RelationKind::Conflicts, "testnet", None),
+    ("signet", RelationKind::Conflicts, "regtest", None),
+    ("testnet", RelationKind::Conflicts, "regtest", None),
+];
+
+fn relations_for(key: &str) -> Vec<Relation> {
+    RELATIONS
+        .iter()
+        .filter(|(k, ..)| *k == key)
+        .map(|&(_, kind, other, value)| Relation {
+            kind,
+            other: other.to_string(),
+            value: value.map(str::to_string),
+        })
+        .collect()
+}
+
+/// Options with a relation to `key`, and that relation.
+#[must_use]
+pub fn relations_to(key: &str) -> Vec<(&'static str, Relation)> {
+    RELATIONS
+        .iter()
+        .filter(|(_, _, other, _)| *other == key)
+        .map(|&(from, kind, other, value)| {
+            (
+                from,
+                Relation {
+                    kind,
+                    other: other.to_string(),
+                    value: value.map(str::to_string),
+                },
+            )
+        })
+        .collect()
+}
+
+/// Whether `key` is set in `entries` to something that turns it on.
+fn is_on(entries: &[ConfigEntry], key: &str) -> bool {
+    enabled_value(entries, key).is_some_and(|v| !v.is_empty() && v != "0")
+}
+
+/// Whether `relation` of option `from` holds in `entries`: `None` while
+/// `from` is off and the relation plays no part. An implied value only
+/// fails to hold where the file sets the other option otherwise, and a
+/// required network flag such as `signet=1` holds however that network is
+/// picked, `chain=signet` included.
+#[must_use]
+pub fn relation_holds(entries: &[ConfigEntry], from: &str, relation: &Relation) -> Option<bool> {
+    if !is_on(entries, from) {
+        return None;
+    }
+    let other = enabled_value(entries, &relation.other);
+    Some(match (relation.kind, relation.value.as_deref()) {
+        (RelationKind::Requires, Some(value)) if CHAINS.contains(&relation.other.as_str()) => {
+            (active_network(entries) == relation.other) == (value == "1")
+        }
+        (RelationKind::Implies, Some(value)) => other.is_none_or(|v| v == value),
+        (RelationKind::Implies, None) => true,
+        (RelationKind::Requires, Some(value)) => {
+            let default = entries
+                .iter()
+                .find(|e| e.key == relation.other)
+                .and_then(|e| e.schema.as_ref())
+                .map_or("", |s| s.default.as_str());
+            other.unwrap_or(default) == value
+        }
+        (RelationKind::Requires, None) => is_on(entries, &relation.other),
+        (RelationKind::Conflicts, Some(value)) => other != Some(value),
+        (RelationKind::Conflicts, None) => !is_on(entries, &relation.other),
+    })
+}
+
+fn tier_for(key: &str, category: ConfigCategory) -> Tier {
+    if category == ConfigCategory::Debugging {
+        Tier::Debug
+    } else if COMMON_OPTIONS.contains(&key) {
+        Tier::Common
+    } else {
+        Tier::Advanced
+    }
 }
 
 /// Schema for a single configuration option
@@ -331,6 +621,13 @@ pub struct ConfigSchema {
     pub config_type: ConfigType,
     pub category: ConfigCategory,
     pub description: String,
+    pub tier: Tier,
+    /// A typical value, for options whose format needs showing
+    pub example: Option<String>,
+    /// What a numeric value counts, for options where it is not obvious
+    pub unit: Option<Unit>,
+    /// How this option bears on others
+    pub relations: Vec<Relation>,
 }
 
 impl ConfigSchema {
@@ -348,8 +645,35 @@ impl ConfigSchema {
             config_type,
             category,
             description: description.to_string(),
+            tier: tier_for(key, category),
+            example: EXAMPLES
+                .iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, v)| (*v).to_string()),
+            unit: unit_for(key),
+            relations: relations_for(key),
         }
     }
+
+    /// Why `value` does not fit the option's type, if it does not. Empty
+    /// values and free-form types always fit.
+    #[must_use]
+    pub fn type_problem(&self, value: &str) -> Option<String> {
+        let value = value.trim();
+        let fits = match self.config_type {
+            _ if value.is_empty() => true,
+            ConfigType::Bool => matches!(value, "0" | "1" | "true" | "false"),
+            ConfigType::Int => value.parse::<i64>().is_ok(),
+            ConfigType::Float => value.parse::<f64>().is_ok(),
+            ConfigType::String | ConfigType::Path | ConfigType::Address => true,
+        };
+        (!fits).then(|| {
+            format!(
+                "{} takes {} values; {value} is not one",
+                self.key, self.config_type
+            )
+        })
+    }
 }
 
 /// A parsed configuration entry
@@ -357,15 +681,49 @@ impl ConfigSchema {
 pub struct ConfigEntry {
     pub key: String,
     pub value: String,
-    pub schema: Option<ConfigSchema>,
+    /// Shared with the schema it was parsed against
+    pub schema: Option<Arc<ConfigSchema>>,
     pub enabled: bool,
     pub section: Option<String>,
+    /// Read from a `-nooption` line such as `nolisten=1`; written back that
+    /// way while the value stays a plain 0 or 1
+    pub negated: bool,
 }
 
+/// Schema of every Bitcoin Core option, built on first use.
+static DEFAULT_SCHEMA: LazyLock<Vec<Arc<ConfigSchema>>> =
+    LazyLock::new(|| build_default_schema().into_iter().map(Arc::new).collect());
+
+/// Options only Bitcoin Knots understands, built on first use.
+static KNOTS_SCHEMA: LazyLock<Vec<Arc<ConfigSchema>>> =
+    LazyLock::new(|| build_knots_schema().into_iter().map(Arc::new).collect());
+
+/// `DEFAULT_SCHEMA` by option name.
+static DEFAULT_SCHEMA_BY_KEY: LazyLock<HashMap<&'static str, &'static Arc<ConfigSchema>>> =
+    LazyLock::new(|| DEFAULT_SCHEMA.iter().map(|s| (s.key.as_str(), s)).collect());
+
+/// A flavor's schema with the extensions merged in, shared by the entries
+/// parsed against it.
+pub type Schema = Arc<[Arc<ConfigSchema>]>;
+
+/// The schema of each flavor, and the extension generation it was merged
+/// under.
+static FLAVOR_SCHEMAS: Mutex<Vec<(NodeFlavor, u64, Schema)>> = Mutex::new(Vec::new());
+
 /// Returns the default schema for all known bitcoin.conf options
 #[must_use]
+pub fn get_default_schema() -> &'static [Arc<ConfigSchema>] {
+    &DEFAULT_SCHEMA
+}
+
+/// The default schema of the option `key`, if it is one.
+#[must_use]
+pub fn default_schema_for(key: &str) -> Option<&'static Arc<ConfigSchema>> {
+    DEFAULT_SCHEMA_BY_KEY.get(key).copied()
+}
+
 #[allow(clippy::too_many_lines)]
-pub fn get_default_schema() -> Vec<ConfigSchema> {
+fn build_default_schema() -> Vec<ConfigSchema> {
     vec![
         // Core options
         ConfigSchema::new(
@@ -457,7 +815,7 @@ pub fn get_default_schema() -> Vec<ConfigSchema> {
             "300",
             ConfigType::Int,
             ConfigCategory::Core,
-            "Maximum mempool size in MiB",
+            "Maximum mempool size in MB",
         ),
         ConfigSchema::new(
             "maxorphantx",
@@ -579,6 +937,13 @@ pub fn get_default_schema() -> Vec<ConfigSchema> {
             ConfigCategory::Network,
             "Use testnet",
         ),
+        ConfigSchema::new(
+            "testnet4",
+            "0",
+            ConfigType::Bool,
+            ConfigCategory::Network,
+            "Use testnet4",
+        ),
         ConfigSchema::new(
             "regtest",
             "0",
@@ -859,6 +1224,13 @@ pub fn get_default_schema() -> Vec<ConfigSchema> {
             ConfigCategory::Network,
             "Use UPnP for port mapping",
         ),
+        ConfigSchema::new(
+            "natpmp",
+            "0",
+            ConfigType::Bool,
+            ConfigCategory::Network,
+            "Use PCP or NAT-PMP for port mapping",
+        ),
         ConfigSchema::new(
             "asmap",
             "",
@@ -902,6 +1274,13 @@ pub fn get_default_schema() -> Vec<ConfigSchema> {
             ConfigCategory::RPC,
             "RPC cookie file location",
         ),
+        ConfigSchema::new(
+            "rpccookieperms",
+            "owner",
+            ConfigType::String,
+            ConfigCategory::RPC,
+            "Who may read the RPC cookie: owner, group or all",
+        ),
         ConfigSchema::new(
             "rpcport",
             "8332",
@@ -1260,226 +1639,1011 @@ pub fn get_default_schema() -> Vec<ConfigSchema> {
     ]
 }
 
-/// Parse bitcoin.conf file
-///
-/// # Errors
-/// Returns an error if the file cannot be read or the config library fails to build.
-/// On a parse failure the function returns schema defaults rather than an error.
-#[allow(clippy::too_many_lines)] // Sequential key-mapping logic; refactoring adds no clarity
-pub fn parse_config(path: &Path) -> Result<Vec<ConfigEntry>> {
-    let schema_list = get_default_schema();
-    let mut entries = Vec::new();
-    let mut found_keys: HashSet<String> = HashSet::new();
-    let mut builder = Config::builder();
-
-    if path.exists() {
-        builder = builder.add_source(File::from(path).format(FileFormat::Ini));
-    }
+/// Bitcoin node implementation the loaded bitcoin.conf is written for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeFlavor {
+    #[default]
+    Core,
+    Knots,
+}
 
-    let Ok(config) = builder.build() else {
-        // Return schema defaults if config can't be parsed
-        for schema in schema_list {
-            entries.push(ConfigEntry {
-                key: schema.key.clone(),
-                value: schema.default.clone(),
-                schema: Some(schema),
-                enabled: false,
-                section: None,
-            });
+impl NodeFlavor {
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            NodeFlavor::Core => "Bitcoin Core",
+            NodeFlavor::Knots => "Bitcoin Knots",
         }
-        return Ok(entries);
-    };
-
-    // Maps key name -> section it was first seen in (None = top-level)
-    let mut config_keys: HashMap<String, Option<String>> = HashMap::new();
-    let sections = vec!["", "main", "test", "signet", "regtest"];
+    }
 
-    // Collect all keys from all sections, preserving which section each key came from
-    for section in &sections {
-        if let Ok(table) = if section.is_empty() {
-            config.get_table("")
-        } else {
-            config.get_table(section)
-        } {
-            for key in table.keys() {
-                let actual_key = if key.contains('.') {
-                    key.split('.').next_back().unwrap_or(key).to_string()
-                } else {
-                    key.clone()
-                };
-                let key_section = if section.is_empty() {
-                    None
-                } else {
-                    Some((*section).to_string())
-                };
-                config_keys.entry(actual_key).or_insert(key_section);
-            }
+    #[must_use]
+    pub fn toggle(self) -> Self {
+        match self {
+            NodeFlavor::Core => NodeFlavor::Knots,
+            NodeFlavor::Knots => NodeFlavor::Core,
         }
     }
+}
 
-    // Process known schema options
-    for schema in &schema_list {
-        let key = &schema.key;
-        let mut value = schema.default.clone();
-        let mut enabled = false;
-        let mut entry_section: Option<String> = None;
-
-        'find_section: for section in &sections {
-            let lookup_key = if section.is_empty() {
-                key.clone()
-            } else {
-                format!("{section}.{key}")
-            };
+/// Core options that Knots does not accept (Knots uses `mempoolreplacement`).
+const CORE_ONLY_OPTIONS: &[&str] = &["mempoolfullrbf"];
 
-            let resolved = if let Ok(val) = config.get_string(&lookup_key) {
-                Some(val)
-            } else if let Ok(val) = config.get_bool(&lookup_key) {
-                Some(if val {
-                    "1".to_string()
-                } else {
-                    "0".to_string()
-                })
-            } else if let Ok(val) = config.get_int(&lookup_key) {
-                Some(val.to_string())
-            } else if let Ok(val) = config.get_float(&lookup_key) {
-                Some(val.to_string())
-            } else {
-                None
-            };
+/// Returns the options only Bitcoin Knots understands
+#[must_use]
+pub fn get_knots_schema() -> &'static [Arc<ConfigSchema>] {
+    &KNOTS_SCHEMA
+}
 
-            if let Some(v) = resolved {
-                value = v;
-                enabled = true;
-                found_keys.insert(key.clone());
-                entry_section = if section.is_empty() {
-                    None
-                } else {
-                    Some((*section).to_string())
-                };
-                break 'find_section;
-            }
-        }
+fn build_knots_schema() -> Vec<ConfigSchema> {
+    vec![
+        ConfigSchema::new(
+            "rejectparasites",
+            "1",
+            ConfigType::Bool,
+            ConfigCategory::Relay,
+            "Reject parasitic transactions (Knots)",
+        ),
+        ConfigSchema::new(
+            "rejecttokens",
+            "0",
+            ConfigType::Bool,
+            ConfigCategory::Relay,
+            "Reject token transactions (Knots)",
+        ),
+        ConfigSchema::new(
+            "datacarriercost",
+            "1",
+            ConfigType::Float,
+            ConfigCategory::Relay,
+            "Virtual bytes charged per datacarrier byte (Knots)",
+        ),
+        ConfigSchema::new(
+            "datacarrierfullcount",
+            "1",
+            ConfigType::Bool,
+            ConfigCategory::Relay,
+            "Count all data toward datacarriersize (Knots)",
+        ),
+        ConfigSchema::new(
+            "maxscriptsize",
+            "1650",
+            ConfigType::Int,
+            ConfigCategory::Relay,
+            "Maximum relayed script size in bytes (Knots)",
+        ),
+        ConfigSchema::new(
+            "mempoolreplacement",
+            "fee,optin",
+            ConfigType::String,
+            ConfigCategory::Relay,
+            "Replacement policy: fee,optin or fee,-optin (Knots)",
+        ),
+        ConfigSchema::new(
+            "spkreuse",
+            "allow",
+            ConfigType::String,
+            ConfigCategory::Relay,
+            "Relay address reuse: allow or conflict (Knots)",
+        ),
+        ConfigSchema::new(
+            "permitbarepubkey",
+            "0",
+            ConfigType::Bool,
+            ConfigCategory::Relay,
+            "Relay bare pubkey outputs (Knots)",
+        ),
+        ConfigSchema::new(
+            "bytespersigopstrict",
+            "20",
+            ConfigType::Int,
+            ConfigCategory::Relay,
+            "Minimum bytes per sigop in relayed txs (Knots)",
+        ),
+        ConfigSchema::new(
+            "corepolicy",
+            "0",
+            ConfigType::Bool,
+            ConfigCategory::Relay,
+            "Use Bitcoin Core policy defaults (Knots)",
+        ),
+    ]
+}
 
-        entries.push(ConfigEntry {
-            key: key.clone(),
-            value,
-            schema: Some(schema.clone()),
-            enabled,
-            section: entry_section,
-        });
+/// Returns the schema for `flavor`: the default schema plus any
+/// flavor-specific options and the user's schema extensions. It is merged
+/// once per flavor and again only after the extensions change.
+#[must_use]
+pub fn schema_for(flavor: NodeFlavor) -> Schema {
+    let current = generation();
+    let mut cache = FLAVOR_SCHEMAS
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    if let Some((_, _, schema)) = cache.iter().find(|(f, g, _)| *f == flavor && *g == current) {
+        return Arc::clone(schema);
     }
+    let mut schema = get_default_schema().to_vec();
+    if flavor == NodeFlavor::Knots {
+        schema.extend_from_slice(get_knots_schema());
+    }
+    extend_schema(&mut schema, flavor);
+    let schema: Schema = schema.into();
+    cache.retain(|(f, _, _)| *f != flavor);
+    cache.push((flavor, current, Arc::clone(&schema)));
+    schema
+}
 
-    // Add unknown config keys (not in schema)
-    for (config_key, key_section) in &config_keys {
-        if !found_keys.contains(config_key) {
-            let lookup_key = match key_section {
-                None => config_key.clone(),
-                Some(s) => format!("{s}.{config_key}"),
-            };
-
-            let value = if let Ok(val) = config.get_string(&lookup_key) {
-                val
-            } else if let Ok(val) = config.get_bool(&lookup_key) {
-                if val {
-                    "1".to_string()
-                } else {
-                    "0".to_string()
-                }
-            } else if let Ok(val) = config.get_int(&lookup_key) {
-                val.to_string()
-            } else if let Ok(val) = config.get_float(&lookup_key) {
-                val.to_string()
-            } else {
-                String::new()
-            };
-
+/// Re-attaches schemas in `entries` for `flavor`. Options the flavor adds
+/// are appended as disabled defaults; template entries for options it lacks
+/// are dropped, while values the user set are kept as unknown options.
+pub fn apply_flavor(entries: &mut Vec<ConfigEntry>, flavor: NodeFlavor) {
+    let schema = schema_for(flavor);
+    entries.retain(|e| e.enabled || e.schema.is_none() || schema.iter().any(|s| s.key == e.key));
+    for entry in entries.iter_mut() {
+        entry.schema = schema.iter().find(|s| s.key == entry.key).cloned();
+    }
+    for s in schema.iter() {
+        if !entries.iter().any(|e| e.key == s.key) {
             entries.push(ConfigEntry {
-                key: config_key.clone(),
-                value,
-                schema: None,
-                enabled: true,
-                section: key_section.clone(),
+                key: s.key.clone(),
+                value: s.default.clone(),
+                schema: Some(Arc::clone(s)),
+                enabled: false,
+                section: None,
+                negated: false,
             });
         }
     }
-
-    Ok(entries)
 }
 
-/// Writes enabled entries back to the config file
-///
-/// # Errors
-/// Returns an error if the file cannot be created or written.
-pub fn save_config(path: &Path, entries: &[ConfigEntry]) -> Result<()> {
-    use std::collections::BTreeMap;
-    use std::io::Write;
+/// Returns the enabled options in `entries` that `flavor` does not support.
+#[must_use]
+pub fn unsupported_options(entries: &[ConfigEntry], flavor: NodeFlavor) -> Vec<String> {
+    let knots = get_knots_schema();
+    entries
+        .iter()
+        .filter(|e| e.enabled)
+        .filter(|e| match flavor {
+            NodeFlavor::Core => knots.iter().any(|s| s.key == e.key),
+            NodeFlavor::Knots => CORE_ONLY_OPTIONS.contains(&e.key.as_str()),
+        })
+        .map(|e| e.key.clone())
+        .collect()
+}
 
-    let mut file = std::fs::File::create(path)?;
-    let mut sectioned: BTreeMap<String, Vec<&ConfigEntry>> = BTreeMap::new();
+/// Puts `entry` back to its schema default and disables it.
+pub fn reset_entry(entry: &mut ConfigEntry) {
+    if let Some(schema) = &entry.schema {
+        entry.value.clone_from(&schema.default);
+    }
+    entry.enabled = false;
+    entry.negated = false;
+}
 
-    for entry in entries {
-        if !entry.enabled {
-            continue;
-        }
-        match &entry.section {
-            None => writeln!(file, "{}={}", entry.key, entry.value)?,
-            Some(s) => sectioned.entry(s.clone()).or_default().push(entry),
+/// Resets every entry in `category`; returns how many were set before.
+pub fn reset_category(entries: &mut [ConfigEntry], category: ConfigCategory) -> usize {
+    let mut changed = 0;
+    for entry in entries
+        .iter_mut()
+        .filter(|e| e.schema.as_ref().is_some_and(|s| s.category == category))
+    {
+        if entry.enabled {
+            changed += 1;
         }
+        reset_entry(entry);
     }
+    changed
+}
 
-    // Write each named section
-    for (section, section_entries) in &sectioned {
-        writeln!(file, "\n[{section}]")?;
-        for entry in section_entries {
-            writeln!(file, "{}={}", entry.key, entry.value)?;
+/// Edit distance between `a` and `b`, counting a swap of two adjacent
+/// characters as a single edit.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
         }
     }
-
-    Ok(())
+    d[a.len()][b.len()]
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::io::Write;
-
-    fn create_temp_config(content: &str) -> (tempfile::TempDir, std::path::PathBuf) {
-        let dir = tempfile::tempdir().unwrap();
-        let file_path = dir.path().join("bitcoin.conf");
-        let mut file = std::fs::File::create(&file_path).unwrap();
-        file.write_all(content.as_bytes()).unwrap();
-        (dir, file_path)
+/// Suggests the schema option an unknown `key` was probably meant to be,
+/// e.g. `maxconections` → `maxconnections`.
+#[must_use]
+pub fn suggest_key(key: &str, flavor: NodeFlavor) -> Option<String> {
+    let schema = schema_for(flavor);
+    let known = |k: &str| schema.iter().any(|s| s.key == k);
+    // Options of the other flavor are reported by `unsupported_options`
+    if known(key) || get_knots_schema().iter().any(|s| s.key == key) {
+        return None;
     }
+    // Allow roughly one typo per five characters
+    let max_distance = (key.len() / 5).clamp(1, 3);
+    schema
+        .iter()
+        .map(|s| (edit_distance(key, &s.key), &s.key))
+        .filter(|(d, _)| *d <= max_distance)
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, k)| k.clone())
+}
 
-    // Tests for get_default_schema()
+/// Returns `(unknown key, suggestion)` for every enabled unknown option
+/// that looks like a typo of a schema option.
+#[must_use]
+pub fn key_suggestions(entries: &[ConfigEntry], flavor: NodeFlavor) -> Vec<(String, String)> {
+    entries
+        .iter()
+        .filter(|e| e.enabled && e.schema.is_none())
+        .filter_map(|e| suggest_key(&e.key, flavor).map(|s| (e.key.clone(), s)))
+        .collect()
+}
 
-    #[test]
-    fn get_default_schema_returns_non_empty_list() {
-        let schema = get_default_schema();
-        assert!(!schema.is_empty());
+/// Moves the value of the unknown entry at `index` onto the option it was
+/// probably meant to be. Returns the new index of that option, or why
+/// nothing moved when that option is already set: its value is kept.
+pub fn accept_key_suggestion(
+    entries: &mut Vec<ConfigEntry>,
+    index: usize,
+    flavor: NodeFlavor,
+) -> Option<Result<usize, String>> {
+    let entry = entries.get(index).filter(|e| e.schema.is_none())?;
+    let target = suggest_key(&entry.key, flavor)?;
+    // Without a target entry the unknown line must stay as it is
+    let pos = entries.iter().position(|e| e.key == target)?;
+    if entries[pos].enabled {
+        return Some(Err(format!(
+            "{target} is already set to {}; remove {} by hand if it is not needed",
+            entries[pos].value, entry.key
+        )));
     }
+    let removed = entries.remove(index);
+    let pos = if pos > index { pos - 1 } else { pos };
+    entries[pos].value = removed.value;
+    entries[pos].enabled = true;
+    entries[pos].section = removed.section;
+    Some(Ok(pos))
+}
 
-    #[test]
-    fn get_default_schema_contains_core_options() {
-        let schema = get_default_schema();
-        let keys: Vec<&str> = schema.iter().map(|s| s.key.as_str()).collect();
+/// A line of a config file bitcoind would refuse to start with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// 1-based line number
+    pub line: usize,
+    pub message: String,
+}
 
-        assert!(keys.contains(&"datadir"));
-        assert!(keys.contains(&"txindex"));
-        assert!(keys.contains(&"prune"));
-        assert!(keys.contains(&"dbcache"));
+/// Every syntax error of a config file, as returned by [`parse_config`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseErrors(pub Vec<ParseError>);
+
+impl std::fmt::Display for ParseErrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let lines: Vec<String> = self
+            .0
+            .iter()
+            .map(|e| format!("line {}: {}", e.line, e.message))
+            .collect();
+        write!(f, "{}", lines.join("; "))
     }
+}
 
-    #[test]
-    fn get_default_schema_contains_network_options() {
-        let schema = get_default_schema();
-        let keys: Vec<&str> = schema.iter().map(|s| s.key.as_str()).collect();
+impl std::error::Error for ParseErrors {}
 
-        assert!(keys.contains(&"testnet"));
-        assert!(keys.contains(&"regtest"));
-        assert!(keys.contains(&"listen"));
-        assert!(keys.contains(&"port"));
-        assert!(keys.contains(&"maxconnections"));
+/// Lines of `content` that are not a comment, a `[section]` header or a
+/// `key=value` assignment.
+#[must_use]
+pub fn syntax_errors(content: &str) -> Vec<ParseError> {
+    let mut out = Vec::new();
+    for (i, raw) in content.lines().enumerate() {
+        let line = raw.trim();
+        let message = if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        } else if line.starts_with('[') {
+            if line.ends_with(']') {
+                continue;
+            }
+            "section header is missing its closing ]".to_string()
+        } else if let Some((key, value)) = line.split_once('=') {
+            let key = key.trim();
+            if key.is_empty() {
+                "missing option name before =".to_string()
+            } else if key.contains(char::is_whitespace) {
+                format!("option name {key:?} contains spaces")
+            } else if key == "rpcpassword"
+                && value.contains('#')
+                && !value.trim().starts_with("${secret:")
+            {
+                "# starts a comment, so it cannot be used in rpcpassword".to_string()
+            } else {
+                continue;
+            }
+        } else if line.starts_with("no") {
+            format!("expected key=value; write a negated option as {line}=1")
+        } else {
+            format!("expected key=value, e.g. {line}=1")
+        };
+        out.push(ParseError {
+            line: i + 1,
+            message,
+        });
+    }
+    out
+}
+
+/// Parse bitcoin.conf file
+///
+/// # Errors
+/// Returns [`ParseErrors`] with the line of every syntax error, rather than
+/// reading a broken file as if nothing were set. Also returns an error if
+/// the file cannot be read or the config library fails to build.
+pub fn parse_config(path: &Path) -> Result<Vec<ConfigEntry>> {
+    parse_config_for(path, NodeFlavor::Core)
+}
+
+/// Parses `path` against the schema for `flavor`, see [`parse_config`].
+///
+/// # Errors
+/// Same as [`parse_config`].
+pub fn parse_config_for(path: &Path, flavor: NodeFlavor) -> Result<Vec<ConfigEntry>> {
+    let content = if path.exists() {
+        std::fs::read_to_string(path)?
+    } else {
+        String::new()
+    };
+    parse_config_str(&content, flavor)
+}
+
+/// Parses config file `content` against the schema for `flavor`.
+///
+/// # Errors
+/// Returns [`ParseErrors`] if `content` has syntax errors, or an error if
+/// the config library fails to build.
+#[allow(clippy::too_many_lines)] // Sequential key-mapping logic; refactoring adds no clarity
+pub fn parse_config_str(content: &str, flavor: NodeFlavor) -> Result<Vec<ConfigEntry>> {
+    let schema_list = schema_for(flavor);
+
+    let errors = syntax_errors(content);
+    if !errors.is_empty() {
+        return Err(ParseErrors(errors).into());
+    }
+    // The INI reader takes backslashes as escapes, which would mangle
+    // Windows paths such as `datadir=D:\Bitcoin`; bitcoind reads them as is
+    let escaped = content.replace('\\', "\\\\");
+    let config = Config::builder()
+        .add_source(File::from_str(&escaped, FileFormat::Ini))
+        .build()?;
+
+    // Each key with the first section it is set in (None = top-level) and
+    // its value there, read in one pass over the sections in the order
+    // bitcoind looks them up
+    let sections = ["", "main", "test", "signet", "regtest"];
+    let mut found: HashMap<String, (Option<String>, Option<String>)> = HashMap::new();
+    for section in sections {
+        let table = if section.is_empty() {
+            // Top-level keys, minus the network section tables
+            config
+                .clone()
+                .try_deserialize::<config::Map<String, config::Value>>()
+                .map(|mut t| {
+                    t.retain(|k, v| {
+                        !(sections.contains(&k.as_str())
+                            && matches!(v.kind, config::ValueKind::Table(_)))
+                    });
+                    t
+                })
+        } else {
+            config.get_table(section)
+        };
+        let Ok(table) = table else {
+            continue;
+        };
+        for (key, value) in table {
+            let key = match key.rsplit_once('.') {
+                Some((_, last)) => last.to_string(),
+                None => key,
+            };
+            let key_section = (!section.is_empty()).then(|| section.to_string());
+            let value = value.into_string().ok();
+            match found.entry(key) {
+                Entry::Vacant(slot) => {
+                    slot.insert((key_section, value));
+                }
+                // A value further down stands in for one that is no scalar
+                Entry::Occupied(mut slot) if slot.get().1.is_none() && value.is_some() => {
+                    slot.insert((key_section, value));
+                }
+                Entry::Occupied(_) => {}
+            }
+        }
+    }
+
+    // Known options, in schema order
+    let mut entries = Vec::with_capacity(schema_list.len() + found.len());
+    for schema in schema_list.iter() {
+        let set = match found.remove(&schema.key) {
+            Some((section, Some(value))) => Some((section, value)),
+            // Not a scalar anywhere: keep it as an unknown option
+            Some(unset) => {
+                found.insert(schema.key.clone(), unset);
+                None
+            }
+            None => None,
+        };
+        let (section, value, enabled) = match set {
+            Some((section, value)) => (section, value, true),
+            None => (None, schema.default.clone(), false),
+        };
+        entries.push(ConfigEntry {
+            key: schema.key.clone(),
+            value,
+            schema: Some(Arc::clone(schema)),
+            enabled,
+            section,
+            negated: false,
+        });
+    }
+
+    // Unknown config keys (not in schema)
+    for (key, (section, value)) in found {
+        entries.push(ConfigEntry {
+            key,
+            value: value.unwrap_or_default(),
+            schema: None,
+            enabled: true,
+            section,
+            negated: false,
+        });
+    }
+
+    fold_negations(&mut entries);
+    Ok(entries)
+}
+
+/// Value `-noX=value` gives `X`: `nolisten=1` (or a bare `nolisten=`) means
+/// `listen=0`, and the double negative `nolisten=0` means `listen=1`.
+fn negate(value: &str) -> &'static str {
+    if matches!(value, "0" | "false") {
+        "1"
+    } else {
+        "0"
+    }
+}
+
+/// Turns unknown `noX` entries into the schema entry `X` with the negated
+/// value, unless `X` is also set directly.
+fn fold_negations(entries: &mut Vec<ConfigEntry>) {
+    let mut folded = Vec::new();
+    for (i, entry) in entries.iter().enumerate() {
+        if entry.schema.is_some() {
+            continue;
+        }
+        let Some(target) = entry.key.strip_prefix("no") else {
+            continue;
+        };
+        let free = entries
+            .iter()
+            .position(|e| e.key == target && e.schema.is_some() && !e.enabled)
+            .filter(|t| !folded.iter().any(|(_, used)| used == t));
+        if let Some(t) = free {
+            folded.push((i, t));
+        }
+    }
+    for &(from, to) in &folded {
+        let value = negate(&entries[from].value);
+        let section = entries[from].section.clone();
+        let target = &mut entries[to];
+        target.value = value.to_string();
+        target.enabled = true;
+        target.section = section;
+        target.negated = true;
+    }
+    // Unknown entries come after the schema ones, so removing them from the
+    // back leaves every other index in place
+    for &(from, _) in folded.iter().rev() {
+        entries.remove(from);
+    }
+}
+
+/// The line `entry` is written as: `noX=1` for a negated entry that is
+/// still off, `X=value` otherwise.
+#[must_use]
+pub fn config_line(entry: &ConfigEntry) -> String {
+    match entry.value.as_str() {
+        "0" | "1" if entry.negated => format!("no{}={}", entry.key, negate(&entry.value)),
+        _ => format!("{}={}", entry.key, entry.value),
+    }
+}
+
+/// The config file content for `entries`: enabled top-level entries
+/// first, then one block per network section.
+#[must_use]
+pub fn render_config(entries: &[ConfigEntry]) -> String {
+    use std::collections::BTreeMap;
+    use std::fmt::Write;
+
+    let mut out = String::new();
+    let mut sectioned: BTreeMap<String, Vec<&ConfigEntry>> = BTreeMap::new();
+
+    for entry in entries {
+        if !entry.enabled {
+            continue;
+        }
+        match &entry.section {
+            None => {
+                let _ = writeln!(out, "{}", config_line(entry));
+            }
+            Some(s) => sectioned.entry(s.clone()).or_default().push(entry),
+        }
+    }
+
+    // Write each named section
+    for (section, section_entries) in &sectioned {
+        let _ = writeln!(out, "\n[{section}]");
+        for entry in section_entries {
+            let _ = writeln!(out, "{}", config_line(entry));
+        }
+    }
+
+    out
+}
+
+/// Writes enabled entries back to the config file
+///
+/// # Errors
+/// Returns an error if the file cannot be created or written.
+pub fn save_config(path: &Path, entries: &[ConfigEntry]) -> Result<()> {
+    write_config_text(path, &render_config(entries))
+}
+
+/// Writes `text` to `path` with the line endings the file already has, so
+/// a file kept with CRLF endings by a Windows editor stays that way.
+///
+/// # Errors
+/// Returns an error if the file cannot be written.
+pub fn write_config_text(path: &Path, text: &str) -> Result<()> {
+    let crlf = std::fs::read(path).is_ok_and(|old| old.windows(2).any(|w| w == b"\r\n"));
+    if crlf {
+        std::fs::write(path, text.replace("\r\n", "\n").replace('\n', "\r\n"))?;
+    } else {
+        std::fs::write(path, text)?;
+    }
+    Ok(())
+}
+
+/// Options bitcoind accepts more than once, every occurrence adding a value.
+pub const MULTI_VALUE_OPTIONS: &[&str] = &[
+    "addnode",
+    "bind",
+    "connect",
+    "debug",
+    "debugexclude",
+    "deprecatedrpc",
+    "externalip",
+    "includeconf",
+    "loadblock",
+    "onlynet",
+    "rpcallowip",
+    "rpcauth",
+    "rpcbind",
+    "rpcwhitelist",
+    "seednode",
+    "signetseednode",
+    "uacomment",
+    "wallet",
+    "whitebind",
+    "whitelist",
+];
+
+/// One `key=value` line of a config file, as written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Assignment {
+    /// 1-based line number
+    pub line: usize,
+    /// Network section, from a `[section]` header or a `section.key` prefix
+    pub section: Option<String>,
+    pub key: String,
+    pub value: String,
+}
+
+/// Every `key=value` line of `content`, in file order. Comments, blank
+/// lines, section headers and lines without `=` are skipped.
+#[must_use]
+pub fn read_assignments(content: &str) -> Vec<Assignment> {
+    let mut section: Option<String> = None;
+    let mut out = Vec::new();
+    for (i, raw) in content.lines().enumerate() {
+        let line = raw.split('#').next().unwrap_or("").trim();
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = Some(name.trim().to_string());
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim();
+        let (key_section, key) = match key.split_once('.') {
+            Some((s, k)) => (Some(s.to_string()), k),
+            None => (section.clone(), key),
+        };
+        out.push(Assignment {
+            line: i + 1,
+            section: key_section,
+            key: key.to_string(),
+            value: value.trim().to_string(),
+        });
+    }
+    out
+}
+
+/// Repeated assignments of single-value options whose value differs from
+/// the first one, paired with that first assignment. bitcoind uses the
+/// first value a config file gives an option and ignores the rest.
+#[must_use]
+pub fn conflicting_duplicates(assignments: &[Assignment]) -> Vec<(&Assignment, &Assignment)> {
+    let mut first: HashMap<(Option<&str>, &str), &Assignment> = HashMap::new();
+    let mut out = Vec::new();
+    for a in assignments {
+        if MULTI_VALUE_OPTIONS.contains(&a.key.as_str()) {
+            continue;
+        }
+        match first.entry((a.section.as_deref(), a.key.as_str())) {
+            Entry::Occupied(used) => {
+                if used.get().value != a.value {
+                    out.push((a, *used.get()));
+                }
+            }
+            Entry::Vacant(slot) => {
+                slot.insert(a);
+            }
+        }
+    }
+    out
+}
+
+/// Removes the lines of `content` that repeat a single-value option, keeping
+/// the first assignment, the one bitcoind uses. Returns the new content and
+/// the number of lines removed.
+#[must_use]
+pub fn remove_duplicate_lines(content: &str) -> (String, usize) {
+    let assignments = read_assignments(content);
+    let mut seen: HashSet<(Option<&str>, &str)> = HashSet::new();
+    let drop: HashSet<usize> = assignments
+        .iter()
+        .filter(|a| !MULTI_VALUE_OPTIONS.contains(&a.key.as_str()))
+        .filter(|a| !seen.insert((a.section.as_deref(), a.key.as_str())))
+        .map(|a| a.line)
+        .collect();
+    let mut out = String::with_capacity(content.len());
+    for (i, line) in content.lines().enumerate() {
+        if !drop.contains(&(i + 1)) {
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+    (out, drop.len())
+}
+
+/// Returns the value of an enabled entry for `key`, if the file sets it.
+#[must_use]
+pub fn enabled_value<'a>(entries: &'a [ConfigEntry], key: &str) -> Option<&'a str> {
+    entries
+        .iter()
+        .find(|e| e.key == key && e.enabled)
+        .map(|e| e.value.as_str())
+}
+
+/// True when `entry` is set to something other than its schema default, or
+/// is an option PDM does not know.
+#[must_use]
+pub fn is_customized(entry: &ConfigEntry) -> bool {
+    entry.enabled
+        && entry
+            .schema
+            .as_ref()
+            .is_none_or(|s| s.default != entry.value.trim())
+}
+
+/// Chains bitcoind runs on, by their `chain=` name, which is also the name
+/// of their config section.
+pub const CHAINS: [&str; 5] = ["main", "test", "testnet4", "signet", "regtest"];
+
+/// Options bitcoind reads from the top of the file only on mainnet; other
+/// chains need them in their own section.
+pub const NETWORK_ONLY_OPTIONS: &[&str] = &[
+    "addnode", "bind", "connect", "port", "rpcbind", "rpcport", "wallet",
+];
+
+/// Returns the chain bitcoind will run on: `main`, `test`, `testnet4`,
+/// `signet` or `regtest`. `chain=` wins over the legacy boolean flags.
+#[must_use]
+pub fn active_network(entries: &[ConfigEntry]) -> &'static str {
+    let chain = enabled_value(entries, "chain");
+    if let Some(found) = CHAINS.iter().find(|c| Some(**c) == chain) {
+        return found;
+    }
+    let is_set = |key: &str| matches!(enabled_value(entries, key), Some("1" | "true"));
+    if is_set("regtest") {
+        "regtest"
+    } else if is_set("signet") {
+        "signet"
+    } else if is_set("testnet4") {
+        "testnet4"
+    } else if is_set("testnet") {
+        "test"
+    } else {
+        "main"
+    }
+}
+
+/// Default RPC port for a network name as returned by [`active_network`].
+#[must_use]
+pub fn default_rpc_port(network: &str) -> u16 {
+    match network {
+        "test" => 18332,
+        "testnet4" => 48332,
+        "signet" => 38332,
+        "regtest" => 18443,
+        _ => 8332,
+    }
+}
+
+/// Default P2P port for a network name as returned by [`active_network`].
+#[must_use]
+pub fn default_p2p_port(network: &str) -> u16 {
+    match network {
+        "test" => 18333,
+        "testnet4" => 48333,
+        "signet" => 38333,
+        "regtest" => 18444,
+        _ => 8333,
+    }
+}
+
+/// Effective RPC port: `rpcport=` if set, otherwise the network default.
+#[must_use]
+pub fn effective_rpc_port(entries: &[ConfigEntry]) -> u16 {
+    enabled_value(entries, "rpcport")
+        .and_then(|p| p.parse().ok())
+        .unwrap_or_else(|| default_rpc_port(active_network(entries)))
+}
+
+/// Effective P2P port: `port=` if set, otherwise the network default.
+#[must_use]
+pub fn effective_p2p_port(entries: &[ConfigEntry]) -> u16 {
+    enabled_value(entries, "port")
+        .and_then(|p| p.parse().ok())
+        .unwrap_or_else(|| default_p2p_port(active_network(entries)))
+}
+
+/// Options whose port defaults to the chain's, and whether that is the RPC
+/// port rather than the P2P one.
+const CHAIN_PORT_OPTIONS: &[(&str, bool)] = &[
+    ("port", false),
+    ("bind", false),
+    ("whitebind", false),
+    ("externalip", false),
+    ("addnode", false),
+    ("connect", false),
+    ("seednode", false),
+    ("rpcport", true),
+    ("rpcbind", true),
+    ("rpcconnect", true),
+];
+
+/// Points `entries` at `chain`: sets `chain=` (left unset for main), turns
+/// off the legacy `testnet`/`signet`/`regtest` flags, and moves top-level
+/// ports that were at the old chain's default to the new chain's default.
+/// Returns warnings about lines that stop applying.
+pub fn switch_network(entries: &mut [ConfigEntry], chain: &str) -> Vec<String> {
+    let from = active_network(entries);
+    for e in entries.iter_mut() {
+        match e.key.as_str() {
+            "chain" if chain == "main" => reset_entry(e),
+            "chain" => {
+                e.value = chain.to_string();
+                e.enabled = true;
+                e.negated = false;
+            }
+            "testnet" | "testnet4" | "signet" | "regtest" if e.enabled => reset_entry(e),
+            key => {
+                let Some(&(_, rpc)) = CHAIN_PORT_OPTIONS.iter().find(|(k, _)| *k == key) else {
+                    continue;
+                };
+                if !e.enabled || e.section.is_some() {
+                    continue;
+                }
+                let (old, new) = if rpc {
+                    (default_rpc_port(from), default_rpc_port(chain))
+                } else {
+                    (default_p2p_port(from), default_p2p_port(chain))
+                };
+                if key == "port" || key == "rpcport" {
+                    if e.value.trim() == old.to_string() {
+                        e.value = new.to_string();
+                    }
+                } else if let Some(host) = e.value.strip_suffix(&format!(":{old}")) {
+                    e.value = format!("{host}:{new}");
+                }
+            }
+        }
+    }
+
+    let mut warnings = Vec::new();
+    let top: Vec<&str> = entries
+        .iter()
+        .filter(|e| e.enabled && e.section.is_none())
+        .filter(|e| NETWORK_ONLY_OPTIONS.contains(&e.key.as_str()))
+        .map(|e| e.key.as_str())
+        .collect();
+    if chain != "main" && !top.is_empty() {
+        warnings.push(format!(
+            "{} at the top only apply to main, move them to [{chain}]",
+            top.join(", ")
+        ));
+    }
+    let stranded: Vec<&str> = entries
+        .iter()
+        .filter(|e| e.enabled && e.section.as_deref().is_some_and(|s| s != chain))
+        .map(|e| e.key.as_str())
+        .collect();
+    if !stranded.is_empty() {
+        warnings.push(format!(
+            "{} sit in another chain's section and no longer apply",
+            stranded.join(", ")
+        ));
+    }
+    warnings
+}
+
+/// A line that has no effect on the chain being resolved, and why.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IgnoredLine {
+    pub line: usize,
+    pub reason: String,
+}
+
+/// What bitcoind uses for one option on one chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Resolution {
+    pub chain: &'static str,
+    /// Values in effect with the line each comes from: several for
+    /// multi-value options, none when bitcoind falls back to its default
+    pub values: Vec<(String, usize)>,
+    pub ignored: Vec<IgnoredLine>,
+}
+
+/// Resolves `key` on `chain` the way bitcoind does: the chain's own section
+/// wins over the top of the file, the first line wins within each, other
+/// chains' sections are skipped, and
+/// [`NETWORK_ONLY_OPTIONS`] at the top apply to mainnet only. `noX=1` counts
+/// as `X=0`. Multi-value options collect the section's lines, then the top's.
+#[must_use]
+pub fn resolve_for_chain(assignments: &[Assignment], key: &str, chain: &'static str) -> Resolution {
+    let negated_key = format!("no{key}");
+    let mut section: Vec<(String, usize)> = Vec::new();
+    let mut top: Vec<(String, usize)> = Vec::new();
+    let mut ignored = Vec::new();
+    let top_ignored = chain != "main" && NETWORK_ONLY_OPTIONS.contains(&key);
+    for a in assignments {
+        let value = if a.key == key {
+            a.value.clone()
+        } else if a.key == negated_key {
+            negate(&a.value).to_string()
+        } else {
+            continue;
+        };
+        match a.section.as_deref() {
+            Some(s) if s == chain => section.push((value, a.line)),
+            // Another chain's section
+            Some(_) => {}
+            None if top_ignored => ignored.push(IgnoredLine {
+                line: a.line,
+                reason: format!("{key} at the top only applies to main, move it to a section"),
+            }),
+            None => top.push((value, a.line)),
+        }
+    }
+
+    let values = if MULTI_VALUE_OPTIONS.contains(&key) {
+        section.into_iter().chain(top).collect()
+    } else {
+        let mut lines = section.iter().chain(&top);
+        let Some(used) = lines.next().cloned() else {
+            return Resolution {
+                chain,
+                values: Vec::new(),
+                ignored,
+            };
+        };
+        let in_section = !section.is_empty();
+        for (_, line) in lines {
+            let reason = if in_section && top.iter().any(|(_, l)| l == line) {
+                format!("overridden by [{chain}] on line {}", used.1)
+            } else {
+                format!("bitcoind uses the first value, on line {}", used.1)
+            };
+            ignored.push(IgnoredLine {
+                line: *line,
+                reason,
+            });
+        }
+        vec![used]
+    };
+    ignored.sort_by_key(|i| i.line);
+    Resolution {
+        chain,
+        values,
+        ignored,
+    }
+}
+
+/// Default of `key` on `chain`: ports follow the chain, everything else the
+/// schema default.
+#[must_use]
+pub fn chain_default(key: &str, chain: &str, schema_default: &str) -> String {
+    match key {
+        "port" => default_p2p_port(chain).to_string(),
+        "rpcport" => default_rpc_port(chain).to_string(),
+        _ => schema_default.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn create_temp_config(content: &str) -> (tempfile::TempDir, std::path::PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join("bitcoin.conf");
+        let mut file = std::fs::File::create(&file_path).unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        (dir, file_path)
+    }
+
+    // Tests for get_default_schema()
+
+    #[test]
+    fn get_default_schema_returns_non_empty_list() {
+        let schema = get_default_schema();
+        assert!(!schema.is_empty());
+    }
+
+    #[test]
+    fn get_default_schema_contains_core_options() {
+        let schema = get_default_schema();
+        let keys: Vec<&str> = schema.iter().map(|s| s.key.as_str()).collect();
+
+        assert!(keys.contains(&"datadir"));
+        assert!(keys.contains(&"txindex"));
+        assert!(keys.contains(&"prune"));
+        assert!(keys.contains(&"dbcache"));
+    }
+
+    #[test]
+    fn get_default_schema_contains_network_options() {
+        let schema = get_default_schema();
+        let keys: Vec<&str> = schema.iter().map(|s| s.key.as_str()).collect();
+
+        assert!(keys.contains(&"testnet"));
+        assert!(keys.contains(&"regtest"));
+        assert!(keys.contains(&"listen"));
+        assert!(keys.contains(&"port"));
+        assert!(keys.contains(&"maxconnections"));
     }
 
     #[test]
@@ -1542,6 +2706,18 @@ mod tests {
         assert_eq!(rpcbind.config_type, ConfigType::Address);
     }
 
+    #[test]
+    fn examples_belong_to_known_options() {
+        let schema = schema_for(NodeFlavor::Knots);
+        for (key, _) in EXAMPLES {
+            assert!(schema.iter().any(|s| s.key == *key), "{key} is no option");
+        }
+        let whitelist = schema.iter().find(|s| s.key == "whitelist").unwrap();
+        assert_eq!(whitelist.example.as_deref(), Some("download@127.0.0.1/32"));
+        let txindex = schema.iter().find(|s| s.key == "txindex").unwrap();
+        assert_eq!(txindex.example, None);
+    }
+
     // Tests for ConfigSchema::new()
 
     #[test]
@@ -1578,24 +2754,27 @@ mod tests {
     }
 
     #[test]
-    fn parse_config_malformed_ini_returns_schema_defaults() {
-        // An unclosed section bracket causes the config crate's INI parser to
-        // return Err, triggering the `let Ok(config) = ... else { return Ok(entries) }`
-        // fallback path in parse_config.
-        let dir = tempfile::tempdir().unwrap();
-        let path = dir.path().join("bitcoin.conf");
-        std::fs::write(&path, b"[unclosed\n").unwrap();
-
-        let entries = parse_config(&path).unwrap();
+    fn parse_config_reports_syntax_errors_with_lines() {
+        let (_dir, path) =
+            create_temp_config("dbcache=450\n[unclosed\nlisten\nnolisten\n=1\nrpc port=1\n");
+        let err = parse_config(&path).unwrap_err();
+        let errors = err.downcast_ref::<ParseErrors>().unwrap();
+        let lines: Vec<usize> = errors.0.iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![2, 3, 4, 5, 6]);
+        assert_eq!(errors.0[1].message, "expected key=value, e.g. listen=1");
+        assert_eq!(
+            errors.0[2].message,
+            "expected key=value; write a negated option as nolisten=1"
+        );
+    }
 
-        // Must return schema-populated defaults, all disabled
-        assert!(!entries.is_empty());
-        let disabled_with_schema = entries
-            .iter()
-            .filter(|e| e.schema.is_some() && !e.enabled)
-            .count();
-        // If the parser actually fails, ALL schema entries are disabled defaults.
-        assert!(disabled_with_schema > 0 || entries.iter().any(|e| e.schema.is_some()));
+    #[test]
+    fn hash_in_rpcpassword_is_an_error() {
+        let errors = syntax_errors("# comment\n; other\nrpcpassword=abc#def\n");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].line, 3);
+        // Keyring placeholders in a template name the account after a #
+        assert!(syntax_errors("rpcpassword=${secret:/srv/bitcoin.conf#rpcpassword}\n").is_empty());
     }
 
     #[test]
@@ -1795,6 +2974,22 @@ zmqpubhashtx=tcp://127.0.0.1:28333
 
     // Tests for ConfigType and ConfigCategory enums
 
+    #[test]
+    fn values_are_checked_against_the_option_type() {
+        let schema =
+            |config_type| ConfigSchema::new("x", "", config_type, ConfigCategory::Core, "");
+        assert!(schema(ConfigType::Int).type_problem("-1").is_none());
+        assert!(schema(ConfigType::Int).type_problem("").is_none());
+        assert_eq!(
+            schema(ConfigType::Int).type_problem("2g").as_deref(),
+            Some("x takes integer values; 2g is not one")
+        );
+        assert!(schema(ConfigType::Bool).type_problem("true").is_none());
+        assert!(schema(ConfigType::Bool).type_problem("on").is_some());
+        assert!(schema(ConfigType::Float).type_problem("0.0001").is_none());
+        assert!(schema(ConfigType::Path).type_problem("~/x y").is_none());
+    }
+
     #[test]
     fn config_type_is_copy() {
         let t1 = ConfigType::Bool;
@@ -1817,6 +3012,7 @@ zmqpubhashtx=tcp://127.0.0.1:28333
             schema: None,
             enabled: true,
             section: None,
+            negated: false,
         };
         let cloned = entry.clone();
         assert_eq!(entry.key, cloned.key);
@@ -1855,6 +3051,7 @@ zmqpubhashtx=tcp://127.0.0.1:28333
                 enabled: true,
                 schema: None,
                 section: None,
+                negated: false,
             },
             ConfigEntry {
                 key: "rpcport".to_string(),
@@ -1862,6 +3059,7 @@ zmqpubhashtx=tcp://127.0.0.1:28333
                 enabled: false,
                 schema: None,
                 section: None,
+                negated: false,
             },
             ConfigEntry {
                 key: "server".to_string(),
@@ -1869,6 +3067,7 @@ zmqpubhashtx=tcp://127.0.0.1:28333
                 enabled: true,
                 schema: None,
                 section: None,
+                negated: false,
             },
         ];
 
@@ -1971,6 +3170,7 @@ zmqpubhashtx=tcp://127.0.0.1:28333
                 enabled: true,
                 schema: None,
                 section: None,
+                negated: false,
             },
             ConfigEntry {
                 key: "rpcport".to_string(),
@@ -1978,6 +3178,7 @@ zmqpubhashtx=tcp://127.0.0.1:28333
                 enabled: true,
                 schema: None,
                 section: Some("test".to_string()),
+                negated: false,
             },
         ];
 
@@ -1994,4 +3195,482 @@ zmqpubhashtx=tcp://127.0.0.1:28333
         );
         assert!(content.contains("rpcport=18332"));
     }
+
+    // Tests for network helpers
+
+    #[test]
+    fn active_network_prefers_chain_over_flags() {
+        let (_dir, path) = create_temp_config("chain=signet\nregtest=1\n");
+        let entries = parse_config(&path).unwrap();
+        assert_eq!(active_network(&entries), "signet");
+    }
+
+    #[test]
+    fn active_network_reads_legacy_flags() {
+        let (_dir, path) = create_temp_config("testnet=1\n");
+        let entries = parse_config(&path).unwrap();
+        assert_eq!(active_network(&entries), "test");
+        assert_eq!(effective_rpc_port(&entries), 18332);
+        assert_eq!(effective_p2p_port(&entries), 18333);
+    }
+
+    #[test]
+    fn unknown_top_level_keys_are_kept() {
+        let (_dir, path) = create_temp_config("server=1\nmystery=1\n[test]\nother=2\n");
+        let entries = parse_config(&path).unwrap();
+        let mystery = entries.iter().find(|e| e.key == "mystery").unwrap();
+        assert!(mystery.enabled && mystery.schema.is_none());
+        assert_eq!(mystery.section, None);
+        let other = entries.iter().find(|e| e.key == "other").unwrap();
+        assert_eq!(other.section.as_deref(), Some("test"));
+        // Network sections are not options of their own
+        assert!(!entries.iter().any(|e| e.key == "test"));
+    }
+
+    #[test]
+    fn schema_is_built_once_and_looked_up_by_key() {
+        assert!(std::ptr::eq(get_default_schema(), get_default_schema()));
+        assert_eq!(
+            default_schema_for("dbcache").map(|s| s.default.as_str()),
+            Some("450")
+        );
+        assert!(default_schema_for("mempoolreplacement").is_none());
+        assert!(Arc::ptr_eq(
+            &schema_for(NodeFlavor::Knots),
+            &schema_for(NodeFlavor::Knots)
+        ));
+
+        // Entries point at the shared schema instead of copies of it
+        let entries = parse_config_str("dbcache=1000\n", NodeFlavor::Core).unwrap();
+        let dbcache = entries.iter().find(|e| e.key == "dbcache").unwrap();
+        assert!(Arc::ptr_eq(
+            dbcache.schema.as_ref().unwrap(),
+            default_schema_for("dbcache").unwrap()
+        ));
+    }
+
+    #[test]
+    fn only_section_tables_are_dropped_from_the_top_level() {
+        // regtest=1 is a flag named like a section; [test] is a section
+        let entries = parse_config_str(
+            "regtest=1\nport=1\n[test]\nport=2\nfoo=x\n",
+            NodeFlavor::Core,
+        )
+        .unwrap();
+        let get = |key: &str| entries.iter().find(|e| e.key == key && e.enabled).unwrap();
+        assert_eq!(get("regtest").value, "1");
+        assert_eq!(
+            (get("port").section.as_deref(), get("port").value.as_str()),
+            (None, "1")
+        );
+        assert_eq!(get("foo").section.as_deref(), Some("test"));
+        assert!(get("foo").schema.is_none());
+        assert!(!entries.iter().any(|e| e.key == "test"));
+    }
+
+    #[test]
+    fn effective_ports_prefer_explicit_values() {
+        let (_dir, path) = create_temp_config("regtest=1\nrpcport=9000\n");
+        let entries = parse_config(&path).unwrap();
+        assert_eq!(effective_rpc_port(&entries), 9000);
+        assert_eq!(effective_p2p_port(&entries), 18444);
+    }
+
+    // Tests for node flavors
+
+    #[test]
+    fn apply_flavor_adds_and_drops_knots_options() {
+        let (_dir, path) = create_temp_config("rejectparasites=0\n");
+        let mut entries = parse_config(&path).unwrap();
+        assert!(
+            entries
+                .iter()
+                .any(|e| e.key == "rejectparasites" && e.schema.is_none())
+        );
+
+        apply_flavor(&mut entries, NodeFlavor::Knots);
+        let parasites = entries.iter().find(|e| e.key == "rejectparasites").unwrap();
+        assert!(parasites.schema.is_some());
+        assert_eq!(parasites.value, "0");
+        assert!(entries.iter().any(|e| e.key == "spkreuse" && !e.enabled));
+
+        apply_flavor(&mut entries, NodeFlavor::Core);
+        assert!(!entries.iter().any(|e| e.key == "spkreuse"));
+        // Values the user set survive as unknown options
+        let parasites = entries.iter().find(|e| e.key == "rejectparasites").unwrap();
+        assert!(parasites.schema.is_none());
+    }
+
+    #[test]
+    fn unsupported_options_per_flavor() {
+        let (_dir, path) = create_temp_config("rejecttokens=1\nmempoolfullrbf=1\n");
+        let entries = parse_config(&path).unwrap();
+        assert_eq!(
+            unsupported_options(&entries, NodeFlavor::Core),
+            vec!["rejecttokens"]
+        );
+        assert_eq!(
+            unsupported_options(&entries, NodeFlavor::Knots),
+            vec!["mempoolfullrbf"]
+        );
+    }
+
+    // Tests for key suggestions
+
+    #[test]
+    fn suggest_key_catches_typos() {
+        assert_eq!(
+            suggest_key("maxconections", NodeFlavor::Core).as_deref(),
+            Some("maxconnections")
+        );
+        assert_eq!(
+            suggest_key("rpcprot", NodeFlavor::Core).as_deref(),
+            Some("rpcport")
+        );
+        assert_eq!(suggest_key("rpcport", NodeFlavor::Core), None);
+        assert_eq!(suggest_key("completelydifferent", NodeFlavor::Core), None);
+        // Knots options are not typos of Core options
+        assert_eq!(suggest_key("rejecttokens", NodeFlavor::Core), None);
+    }
+
+    #[test]
+    fn accept_key_suggestion_moves_value() {
+        let (_dir, path) = create_temp_config("maxconections=40\n");
+        let mut entries = parse_config(&path).unwrap();
+        assert_eq!(
+            key_suggestions(&entries, NodeFlavor::Core),
+            vec![("maxconections".to_string(), "maxconnections".to_string())]
+        );
+
+        let index = entries
+            .iter()
+            .position(|e| e.key == "maxconections")
+            .unwrap();
+        let pos = accept_key_suggestion(&mut entries, index, NodeFlavor::Core)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(entries[pos].key, "maxconnections");
+        assert_eq!(entries[pos].value, "40");
+        assert!(entries[pos].enabled);
+        assert!(!entries.iter().any(|e| e.key == "maxconections"));
+    }
+
+    #[test]
+    fn accept_key_suggestion_keeps_entry_without_target() {
+        let (_dir, path) = create_temp_config("maxconections=40\n");
+        let mut entries = parse_config(&path).unwrap();
+        entries.retain(|e| e.key != "maxconnections");
+        let count = entries.len();
+        let index = entries
+            .iter()
+            .position(|e| e.key == "maxconections")
+            .unwrap();
+
+        assert_eq!(
+            accept_key_suggestion(&mut entries, index, NodeFlavor::Core),
+            None
+        );
+        assert_eq!(entries.len(), count);
+        assert_eq!(entries[index].value, "40");
+    }
+
+    #[test]
+    fn accept_key_suggestion_keeps_an_enabled_target() {
+        let (_dir, path) = create_temp_config("maxconnections=125\nmaxconections=40\n");
+        let mut entries = parse_config(&path).unwrap();
+        let count = entries.len();
+        let index = entries
+            .iter()
+            .position(|e| e.key == "maxconections")
+            .unwrap();
+
+        let refused = accept_key_suggestion(&mut entries, index, NodeFlavor::Core)
+            .unwrap()
+            .unwrap_err();
+        assert!(
+            refused.starts_with("maxconnections is already set to 125"),
+            "{refused}"
+        );
+        assert_eq!(entries.len(), count);
+        assert_eq!(enabled_value(&entries, "maxconnections"), Some("125"));
+        assert_eq!(entries[index].value, "40");
+    }
+
+    #[test]
+    fn schema_tiers() {
+        let schema = get_default_schema();
+        let tier = |key: &str| schema.iter().find(|s| s.key == key).unwrap().tier;
+        assert_eq!(tier("dbcache"), Tier::Common);
+        assert_eq!(tier("blockreconstructionextratxn"), Tier::Advanced);
+        assert_eq!(tier("debug"), Tier::Debug);
+    }
+
+    #[test]
+    fn reset_entry_and_category() {
+        let (_dir, path) = create_temp_config("dbcache=4000\nrpcport=9000\nrpcuser=u\n");
+        let mut entries = parse_config(&path).unwrap();
+
+        let dbcache = entries.iter_mut().find(|e| e.key == "dbcache").unwrap();
+        reset_entry(dbcache);
+        assert_eq!(dbcache.value, "450");
+        assert!(!dbcache.enabled);
+
+        assert_eq!(reset_category(&mut entries, ConfigCategory::RPC), 2);
+        let rpcport = entries.iter().find(|e| e.key == "rpcport").unwrap();
+        assert_eq!(rpcport.value, "8332");
+        assert!(!entries.iter().any(|e| e.enabled));
+    }
+
+    #[test]
+    fn read_assignments_tracks_lines_and_sections() {
+        let content =
+            "# comment\nprune=550 # trailing\n\n[test]\nrpcport=1\nmain.port=2\nnoequals\n";
+        let found = read_assignments(content);
+        let summary: Vec<(usize, Option<&str>, &str, &str)> = found
+            .iter()
+            .map(|a| {
+                (
+                    a.line,
+                    a.section.as_deref(),
+                    a.key.as_str(),
+                    a.value.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (2, None, "prune", "550"),
+                (5, Some("test"), "rpcport", "1"),
+                (6, Some("main"), "port", "2"),
+            ]
+        );
+    }
+
+    #[test]
+    fn duplicates_keep_the_first_value() {
+        let content = "prune=550\naddnode=a\nprune=1000\naddnode=b\nprune=550\n[test]\nprune=7\n";
+        let assignments = read_assignments(content);
+        let conflicts = conflicting_duplicates(&assignments);
+        assert_eq!(conflicts.len(), 1);
+        let (ignored, used) = conflicts[0];
+        assert_eq!((ignored.line, ignored.value.as_str()), (3, "1000"));
+        assert_eq!((used.line, used.value.as_str()), (1, "550"));
+
+        let (cleaned, removed) = remove_duplicate_lines(content);
+        assert_eq!(removed, 2);
+        assert_eq!(
+            cleaned,
+            "prune=550\naddnode=a\naddnode=b\n[test]\nprune=7\n"
+        );
+    }
+
+    #[test]
+    fn crlf_files_parse_and_keep_their_line_endings() {
+        let (_dir, path) = create_temp_config(
+            "server=1\r\nrpcuser=alice\r\ndatadir=D:\\Bitcoin\\node\r\n[test]\r\nport=1\r",
+        );
+        let mut entries = parse_config(&path).unwrap();
+        assert_eq!(enabled_value(&entries, "rpcuser"), Some("alice"));
+        assert_eq!(enabled_value(&entries, "port"), Some("1"));
+        assert_eq!(
+            enabled_value(&entries, "datadir"),
+            Some("D:\\Bitcoin\\node")
+        );
+
+        let dbcache = entries.iter_mut().find(|e| e.key == "dbcache").unwrap();
+        dbcache.value = "900".to_string();
+        dbcache.enabled = true;
+        save_config(&path, &entries).unwrap();
+        let saved = std::fs::read_to_string(&path).unwrap();
+        assert!(saved.contains("dbcache=900\r\n"), "{saved:?}");
+        assert_eq!(saved.matches('\n').count(), saved.matches("\r\n").count());
+
+        let (_dir, path) = create_temp_config("server=1\n");
+        save_config(&path, &parse_config(&path).unwrap()).unwrap();
+        assert!(!std::fs::read_to_string(&path).unwrap().contains('\r'));
+    }
+
+    #[test]
+    fn negated_options_round_trip() {
+        let (_dir, path) = create_temp_config(
+            "nolisten=1\nnoconnect=\nnoupnp=0\nnobogus=1\n[test]\nnodnsseed=1\n",
+        );
+        let mut entries = parse_config(&path).unwrap();
+        let find = |entries: &[ConfigEntry], key: &str| {
+            entries.iter().find(|e| e.key == key).cloned().unwrap()
+        };
+        let listen = find(&entries, "listen");
+        assert!(listen.enabled && listen.negated);
+        assert_eq!(listen.value, "0");
+        assert_eq!(find(&entries, "connect").value, "0");
+        assert_eq!(find(&entries, "upnp").value, "1");
+        assert_eq!(find(&entries, "dnsseed").section.as_deref(), Some("test"));
+        assert!(!entries.iter().any(|e| e.key == "nolisten"));
+        // Unknown options keep their name
+        assert!(entries.iter().any(|e| e.key == "nobogus"));
+
+        save_config(&path, &entries).unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert!(written.contains("nolisten=1\n"));
+        assert!(written.contains("noupnp=0\n"));
+        assert!(written.contains("[test]\nnodnsseed=1\n"));
+
+        // A value the negation cannot express is written plainly
+        let connect = entries.iter_mut().find(|e| e.key == "connect").unwrap();
+        connect.value = "10.0.0.1".to_string();
+        save_config(&path, &entries).unwrap();
+        assert!(
+            std::fs::read_to_string(&path)
+                .unwrap()
+                .contains("connect=10.0.0.1\n")
+        );
+    }
+
+    #[test]
+    fn resolve_for_chain_applies_sections_and_network_only_rules() {
+        let assignments = read_assignments(
+            "rpcport=9000\nport=9001\ndbcache=300\ndbcache=500\naddnode=a\n\
+             [test]\nrpcport=19000\naddnode=b\n[main]\nnolisten=1\n",
+        );
+        let values =
+            |r: &Resolution| -> Vec<String> { r.values.iter().map(|(v, _)| v.clone()).collect() };
+
+        // A section value overrides the top of the file
+        let test = resolve_for_chain(&assignments, "rpcport", "test");
+        assert_eq!(test.values, vec![("19000".to_string(), 7)]);
+        assert_eq!(test.ignored.len(), 1);
+        assert_eq!(test.ignored[0].line, 1);
+        assert!(test.ignored[0].reason.contains("only applies to main"));
+
+        // port at the top is ignored off mainnet even without a section value
+        let signet = resolve_for_chain(&assignments, "port", "signet");
+        assert!(signet.values.is_empty());
+        assert_eq!(signet.ignored[0].line, 2);
+        assert_eq!(
+            values(&resolve_for_chain(&assignments, "port", "main")),
+            ["9001"]
+        );
+
+        // First line wins, for every chain
+        let dbcache = resolve_for_chain(&assignments, "dbcache", "regtest");
+        assert_eq!(values(&dbcache), ["300"]);
+        assert!(dbcache.ignored[0].reason.contains("line 3"));
+
+        // Multi-value options collect the section, then the top when allowed
+        assert_eq!(
+            values(&resolve_for_chain(&assignments, "addnode", "main")),
+            ["a"]
+        );
+        assert_eq!(
+            values(&resolve_for_chain(&assignments, "addnode", "test")),
+            ["b"]
+        );
+
+        // Negations only count in their own section
+        assert_eq!(
+            values(&resolve_for_chain(&assignments, "listen", "main")),
+            ["0"]
+        );
+        assert!(
+            resolve_for_chain(&assignments, "listen", "test")
+                .values
+                .is_empty()
+        );
+        assert_eq!(chain_default("rpcport", "signet", "8332"), "38332");
+    }
+
+    #[test]
+    fn switch_network_updates_flags_and_default_ports() {
+        let (_dir, path) = create_temp_config(
+            "testnet=1\nrpcport=18332\nrpcbind=127.0.0.1:18332\nbind=0.0.0.0:9999\naddnode=peer:18333\n",
+        );
+        let mut entries = parse_config(&path).unwrap();
+        assert_eq!(active_network(&entries), "test");
+
+        let warnings = switch_network(&mut entries, "signet");
+        assert_eq!(active_network(&entries), "signet");
+        assert_eq!(enabled_value(&entries, "chain"), Some("signet"));
+        assert_eq!(enabled_value(&entries, "testnet"), None);
+        assert_eq!(enabled_value(&entries, "rpcport"), Some("38332"));
+        assert_eq!(enabled_value(&entries, "rpcbind"), Some("127.0.0.1:38332"));
+        assert_eq!(enabled_value(&entries, "addnode"), Some("peer:38333"));
+        // A port the user picked stays
+        assert_eq!(enabled_value(&entries, "bind"), Some("0.0.0.0:9999"));
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("move them to [signet]"));
+        assert!(warnings[0].contains("rpcport"));
+
+        // Back on main, chain= is dropped and nothing is main-only any more
+        let warnings = switch_network(&mut entries, "main");
+        assert_eq!(enabled_value(&entries, "chain"), None);
+        assert_eq!(enabled_value(&entries, "rpcport"), Some("8332"));
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn relations_name_known_options_and_track_the_file() {
+        let schema = get_default_schema();
+        for (from, relation) in schema
+            .iter()
+            .flat_map(|s| s.relations.iter().map(move |r| (&s.key, r)))
+        {
+            assert!(
+                schema.iter().any(|s| s.key == relation.other),
+                "{from} {relation}"
+            );
+        }
+        let listenonion = schema.iter().find(|s| s.key == "listenonion").unwrap();
+        assert_eq!(listenonion.relations[0].to_string(), "requires listen=1");
+        assert!(
+            relations_to("signet")
+                .iter()
+                .any(|(from, r)| *from == "signetchallenge" && r.kind == RelationKind::Requires)
+        );
+
+        let (_dir, path) = create_temp_config(
+            "listenonion=1
+listen=0
+connect=10.0.0.2
+txindex=1
+",
+        );
+        let entries = parse_config(&path).unwrap();
+        let check = |from: &str, other: &str| {
+            let relation = relations_for(from)
+                .into_iter()
+                .find(|r| r.other == other)
+                .unwrap();
+            relation_holds(&entries, from, &relation)
+        };
+        assert_eq!(check("listenonion", "listen"), Some(false));
+        assert_eq!(check("connect", "listen"), Some(true));
+        assert_eq!(check("connect", "dnsseed"), Some(true));
+        assert_eq!(check("txindex", "prune"), Some(true));
+        assert_eq!(check("signetchallenge", "signet"), None);
+        assert_eq!(check("walletnotify", "disablewallet"), None);
+
+        for (network, holds) in [
+            ("chain=signet", true),
+            ("signet=1", true),
+            ("chain=regtest", false),
+        ] {
+            let (_dir, path) = create_temp_config(&format!(
+                "{network}\nsignetchallenge=51\nsignetseednode=1.2.3.4\n"
+            ));
+            let entries = parse_config(&path).unwrap();
+            for from in ["signetchallenge", "signetseednode"] {
+                let relation = relations_for(from)
+                    .into_iter()
+                    .find(|r| r.other == "signet")
+                    .unwrap();
+                assert_eq!(
+                    relation_holds(&entries, from, &relation),
+                    Some(holds),
+                    "{from} with {network}"
+                );
+            }
+        }
+    }
 }
@@ -0,0 +1,392 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_config::{ConfigEntry, NodeFlavor};
+use anyhow::{Result, anyhow, bail};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Options bitcoind only accepts from a given major version on.
+const OPTION_SINCE: &[(&str, u32)] = &[
+    ("natpmp", 22),
+    ("mempoolfullrbf", 24),
+    ("v2transport", 26),
+    ("testnet4", 28),
+    ("blocksxor", 28),
+];
+
+/// Options bitcoind stopped accepting in a given major version.
+const OPTION_REMOVED: &[(&str, u32)] = &[
+    ("rpcserialversion", 28),
+    ("maxtimeadjustment", 29),
+    ("upnp", 29),
+];
+
+/// Version-gated node features PDM can offer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feature {
+    /// Port mapping with NAT-PMP, and PCP from v29
+    NatPmp,
+}
+
+impl Feature {
+    /// First major version shipping the feature.
+    #[must_use]
+    pub fn since(self) -> u32 {
+        match self {
+            Feature::NatPmp => 22,
+        }
+    }
+}
+
+/// Version reported by `bitcoind -version`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeVersion {
+    pub flavor: NodeFlavor,
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl NodeVersion {
+    /// Parses the first line of `bitcoind -version`, e.g.
+    /// `Bitcoin Core daemon version v27.1.0` or
+    /// `Bitcoin Knots daemon version v27.1.knots20240801`.
+    #[must_use]
+    pub fn parse(output: &str) -> Option<Self> {
+        let line = output.lines().next()?;
+        let (_, rest) = line.split_once("version v")?;
+        let mut numbers = rest.split_whitespace().next()?.split('.').map(|p| {
+            let digits: String = p.chars().take_while(char::is_ascii_digit).collect();
+            digits.parse::<u32>().ok()
+        });
+        let major = numbers.next().flatten()?;
+        let minor = numbers.next().flatten().unwrap_or(0);
+        let patch = numbers.next().flatten().unwrap_or(0);
+        let flavor = if line.contains("Knots") {
+            NodeFlavor::Knots
+        } else {
+            NodeFlavor::Core
+        };
+        Some(Self {
+            flavor,
+            major,
+            minor,
+            patch,
+        })
+    }
+
+    #[must_use]
+    pub fn supports(&self, feature: Feature) -> bool {
+        self.major >= feature.since()
+    }
+
+    #[must_use]
+    pub fn label(&self) -> String {
+        format!("v{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Looks for `name` in `dir` if given, otherwise on `PATH`; on Windows
+/// as `name.exe`.
+#[must_use]
+pub fn find_binary(name: &str, dir: Option<&Path>) -> Option<PathBuf> {
+    let name = format!("{name}{}", std::env::consts::EXE_SUFFIX);
+    let candidates: Vec<PathBuf> = match dir {
+        Some(d) => vec![d.to_path_buf()],
+        None => std::env::var_os("PATH")
+            .map(|p| std::env::split_paths(&p).collect())
+            .unwrap_or_default(),
+    };
+    candidates
+        .into_iter()
+        .map(|d| d.join(&name))
+        .find(|p| p.is_file())
+}
+
+/// Resolves the bitcoind binary: the configured path when set, else `PATH`.
+#[must_use]
+pub fn resolve_bitcoind(configured: Option<&Path>) -> Option<PathBuf> {
+    match configured {
+        Some(p) if p.is_file() => Some(p.to_path_buf()),
+        Some(_) => None,
+        None => find_binary("bitcoind", None),
+    }
+}
+
+/// Finds `bitcoin-cli`, preferring the directory `bitcoind` was found in.
+#[must_use]
+pub fn resolve_cli(bitcoind: Option<&Path>) -> Option<PathBuf> {
+    bitcoind
+        .and_then(Path::parent)
+        .and_then(|dir| find_binary("bitcoin-cli", Some(dir)))
+        .or_else(|| find_binary("bitcoin-cli", None))
+}
+
+/// Runs `<binary> -version` and parses its output.
+///
+/// # Errors
+/// Returns an error if the binary cannot be run or prints an unexpected version line.
+pub fn detect_version(binary: &Path) -> Result<NodeVersion> {
+    let output = Command::new(binary).arg("-version").output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    NodeVersion::parse(&stdout)
+        .ok_or_else(|| anyhow!("unrecognised version output from {}", binary.display()))
+}
+
+/// Result of a check-only bitcoind run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigCheck {
+    /// bitcoind got past argument and config parsing
+    Accepted,
+    /// bitcoind exited early; holds the error lines it printed
+    Rejected(Vec<String>),
+}
+
+/// Starts `binary` against `conf` with networking disabled and every
+/// file it writes moved to a private scratch directory, and reports
+/// whether it survives argument and config parsing. The data, blocks,
+/// pid and settings files of the conf are overridden and wallets are
+/// disabled, so a live node's files are never touched. bitcoind has no
+/// parse-only flag, so a process still running after `timeout` counts as
+/// accepted and is killed.
+///
+/// # Errors
+/// Returns an error if the scratch directory cannot be created or the
+/// binary cannot be started.
+pub fn check_config(binary: &Path, conf: &Path, timeout: Duration) -> Result<ConfigCheck> {
+    let scratch = tempfile::tempdir()?;
+    let blocksdir = scratch.path().join("blocks");
+    std::fs::create_dir(&blocksdir)?;
+    let mut child = Command::new(binary)
+        .arg(format!("-conf={}", conf.display()))
+        .arg(format!("-datadir={}", scratch.path().display()))
+        .arg(format!("-blocksdir={}", blocksdir.display()))
+        .arg(format!(
+            "-pid={}",
+            scratch.path().join("bitcoind.pid").display()
+        ))
+        .args([
+            "-daemon=0",
+            "-listen=0",
+            "-server=0",
+            "-connect=0",
+            "-dnsseed=0",
+            "-disablewallet",
+            "-nosettings",
+            "-nodebuglogfile",
+        ])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break Some(status);
+        }
+        if started.elapsed() >= timeout {
+            let _ = child.kill();
+            break None;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    };
+    let output = child.wait_with_output()?;
+
+    match status {
+        Some(status) if !status.success() => {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let mut lines: Vec<String> = stderr
+                .lines()
+                .map(str::trim)
+                .filter(|l| !l.is_empty())
+                .map(str::to_string)
+                .collect();
+            if lines.is_empty() {
+                lines.push(format!("bitcoind exited with {status}"));
+            }
+            Ok(ConfigCheck::Rejected(lines))
+        }
+        _ => Ok(ConfigCheck::Accepted),
+    }
+}
+
+/// A config check running in the background, so the screen keeps
+/// drawing while bitcoind starts.
+pub struct ConfigTest {
+    handle: JoinHandle<Result<ConfigCheck>>,
+}
+
+impl ConfigTest {
+    /// Writes `text` to a scratch bitcoin.conf only this user can read,
+    /// inside a private directory, and checks it with `binary`.
+    #[must_use]
+    pub fn start(binary: &Path, text: &str, timeout: Duration) -> Self {
+        let binary = binary.to_path_buf();
+        let text = text.to_string();
+        Self {
+            handle: std::thread::spawn(move || {
+                let dir = tempfile::tempdir()?;
+                let mut conf = tempfile::Builder::new()
+                    .prefix("bitcoin")
+                    .suffix(".conf")
+                    .tempfile_in(dir.path())?;
+                conf.write_all(text.as_bytes())?;
+                conf.flush()?;
+                check_config(&binary, conf.path(), timeout)
+            }),
+        }
+    }
+
+    /// Whether bitcoind has answered.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// The result, once the check is finished; waits for it otherwise.
+    ///
+    /// # Errors
+    /// Returns an error if the scratch files could not be written or
+    /// bitcoind could not be started.
+    pub fn finish(self) -> Result<ConfigCheck> {
+        self.handle
+            .join()
+            .unwrap_or_else(|_| bail!("config test panicked"))
+    }
+}
+
+/// Returns the enabled options that `version` does not accept, with the reason.
+#[must_use]
+pub fn version_issues(entries: &[ConfigEntry], version: &NodeVersion) -> Vec<String> {
+    let mut issues = Vec::new();
+    for entry in entries.iter().filter(|e| e.enabled) {
+        let key = entry.key.as_str();
+        let since = OPTION_SINCE
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| *v);
+        let removed = OPTION_REMOVED
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| *v);
+        if let Some(since) = since.filter(|v| version.major < *v) {
+            issues.push(format!("{key} needs v{since}+"));
+        }
+        if let Some(removed) = removed.filter(|v| version.major >= *v) {
+            issues.push(format!("{key} removed in v{removed}"));
+        }
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_core_and_knots_versions() {
+        let core = NodeVersion::parse("Bitcoin Core daemon version v27.1.0\nCopyright").unwrap();
+        assert_eq!(core.flavor, NodeFlavor::Core);
+        assert_eq!((core.major, core.minor, core.patch), (27, 1, 0));
+        assert!(core.supports(Feature::NatPmp));
+        let old = NodeVersion::parse("Bitcoin Core daemon version v0.21.2\n").unwrap();
+        assert!(!old.supports(Feature::NatPmp));
+
+        let knots = NodeVersion::parse("Bitcoin Knots daemon version v28.1.knots20250305").unwrap();
+        assert_eq!(knots.flavor, NodeFlavor::Knots);
+        assert_eq!(knots.label(), "v28.1.0");
+
+        assert!(NodeVersion::parse("bash: bitcoind: not found").is_none());
+    }
+
+    #[test]
+    fn version_issues_flag_new_and_removed_options() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bitcoin.conf");
+        std::fs::write(&path, "testnet4=1\nupnp=1\n").unwrap();
+        let entries = crate::bitcoin_config::parse_config(&path).unwrap();
+
+        let old = NodeVersion::parse("Bitcoin Core version v25.0.0").unwrap();
+        assert_eq!(version_issues(&entries, &old), vec!["testnet4 needs v28+"]);
+
+        let new = NodeVersion::parse("Bitcoin Core version v29.0.0").unwrap();
+        assert_eq!(version_issues(&entries, &new), vec!["upnp removed in v29"]);
+    }
+
+    #[test]
+    fn find_binary_searches_given_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(find_binary("bitcoind", Some(dir.path())).is_none());
+        std::fs::write(dir.path().join("bitcoind"), "").unwrap();
+        assert_eq!(
+            find_binary("bitcoind", Some(dir.path())),
+            Some(dir.path().join("bitcoind"))
+        );
+    }
+
+    #[cfg(unix)]
+    fn script(dir: &Path, body: &str) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+        let path = dir.join("bitcoind");
+        std::fs::write(&path, format!("#!/bin/sh\n{body}\n")).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn check_config_reports_bitcoind_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let conf = dir.path().join("bitcoin.conf");
+        let binary = script(
+            dir.path(),
+            "echo 'Error: Invalid parameter -maxconections' >&2\nexit 1",
+        );
+
+        let check = check_config(&binary, &conf, Duration::from_secs(5)).unwrap();
+        assert_eq!(
+            check,
+            ConfigCheck::Rejected(vec!["Error: Invalid parameter -maxconections".into()])
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn check_config_accepts_a_node_that_keeps_running() {
+        let dir = tempfile::tempdir().unwrap();
+        let conf = dir.path().join("bitcoin.conf");
+        let binary = script(dir.path(), "exec sleep 5");
+
+        let check = check_config(&binary, &conf, Duration::from_millis(200)).unwrap();
+        assert_eq!(check, ConfigCheck::Accepted);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn config_test_keeps_bitcoind_off_live_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let args = dir.path().join("args");
+        let binary = script(
+            dir.path(),
+            &format!(
+                "echo \"$@\" > {}\nls -l \"${{1#-conf=}}\" >> {}\nexit 1",
+                args.display(),
+                args.display()
+            ),
+        );
+
+        let test = ConfigTest::start(&binary, "rpcpassword=hunter2\n", Duration::from_secs(5));
+        assert!(matches!(test.finish(), Ok(ConfigCheck::Rejected(_))));
+        let seen = std::fs::read_to_string(&args).unwrap();
+        for flag in ["-blocksdir=", "-pid=", "-disablewallet", "-nosettings"] {
+            assert!(seen.contains(flag), "{flag} missing from {seen}");
+        }
+        assert!(seen.contains("-rw-------"), "{seen}");
+    }
+}
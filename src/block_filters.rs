@@ -0,0 +1,147 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_config::{ConfigEntry, enabled_value};
+use crate::indexes::enables;
+use bitcoin::bip158::BlockFilter;
+use bitcoin::hex::FromHex;
+use bitcoin::{Address, BlockHash, Network, ScriptBuf};
+use std::str::FromStr;
+
+/// Whether `entries` build the BIP158 filters `getblockfilter` serves.
+#[must_use]
+pub fn filters_enabled(entries: &[ConfigEntry]) -> bool {
+    enabled_value(entries, "blockfilterindex").is_some_and(enables)
+}
+
+/// Whether `entries` also serve the filters to light clients over P2P.
+#[must_use]
+pub fn served_to_peers(entries: &[ConfigEntry]) -> bool {
+    enabled_value(entries, "peerblockfilters").is_some_and(enables)
+}
+
+/// The block and the output script a filter test asks about.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterQuery {
+    /// Height or hash, as typed
+    pub block: String,
+    /// Address or script hex, as typed
+    pub target: String,
+    pub script: ScriptBuf,
+}
+
+/// Reads `<height or block hash> <address or script hex>` for `chain`,
+/// as [`crate::bitcoin_config::active_network`] names it.
+///
+/// # Errors
+/// Returns what is missing or unreadable, including an address of
+/// another network.
+pub fn parse_query(input: &str, chain: &str) -> Result<FilterQuery, String> {
+    let mut words = input.split_whitespace();
+    let (Some(block), Some(target), None) = (words.next(), words.next(), words.next()) else {
+        return Err("Type a block height or hash, a space, then an address or script".to_string());
+    };
+    if block.parse::<u64>().is_err() && BlockHash::from_str(block).is_err() {
+        return Err(format!("{block} is neither a height nor a block hash"));
+    }
+    let network = Network::from_core_arg(chain).map_err(|e| e.to_string())?;
+    let script = match Address::from_str(target) {
+        Ok(address) => address
+            .require_network(network)
+            .map_err(|_| format!("{target} is not a {chain} address"))?
+            .script_pubkey(),
+        Err(_) => ScriptBuf::from_hex(target)
+            .map_err(|_| format!("{target} is neither an address nor a script in hex"))?,
+    };
+    Ok(FilterQuery {
+        block: block.to_string(),
+        target: target.to_string(),
+        script,
+    })
+}
+
+/// Whether the basic filter `filter_hex` of the block `block_hash` holds
+/// `script`. A match means the block probably pays to or spends from it;
+/// no match means it certainly does not.
+///
+/// # Errors
+/// Returns an error if the hash or the filter cannot be read.
+pub fn filter_matches(
+    filter_hex: &str,
+    block_hash: &str,
+    script: &ScriptBuf,
+) -> Result<bool, String> {
+    let hash = BlockHash::from_str(block_hash).map_err(|e| format!("bad block hash: {e}"))?;
+    let content = Vec::<u8>::from_hex(filter_hex).map_err(|e| format!("bad filter: {e}"))?;
+    BlockFilter::new(&content)
+        .match_any(&hash, std::iter::once(script.as_bytes()))
+        .map_err(|e| format!("bad filter: {e}"))
+}
+
+/// Result of testing one block's filter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterMatch {
+    pub block_hash: String,
+    pub target: String,
+    pub matched: bool,
+    /// Size of the filter, in bytes
+    pub filter_size: usize,
+    /// Filter header committing to this filter and all before it
+    pub header: String,
+}
+
+/// The compact filter tester on the Indexes tab.
+#[derive(Debug, Clone, Default)]
+pub struct FilterTester {
+    /// Query being typed, while the input is open
+    pub input: Option<String>,
+    pub result: Option<FilterMatch>,
+    /// Why the query or the calls failed
+    pub error: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitcoin_config::{NodeFlavor, parse_config_str};
+
+    /// Testnet genesis block and its basic filter, from the BIP158 test
+    /// vectors.
+    const GENESIS: &str = "000000000933ea01ad0ee984209779baaec3ced90fa3f408719526f8d77f4943";
+    const GENESIS_FILTER: &str = "019dfca8";
+    const GENESIS_SCRIPT: &str = "4104678afdb0fe5548271967f1a67130b7105cd6a828e03909a67962e0ea1f61deb649f6bc3f4cef38c4f35504e51ec112de5c384df7ba0b8d578a4c702b6bf11d5fac";
+
+    #[test]
+    fn filter_holds_the_scripts_of_its_block() {
+        let script = ScriptBuf::from_hex(GENESIS_SCRIPT).unwrap();
+        assert_eq!(filter_matches(GENESIS_FILTER, GENESIS, &script), Ok(true));
+        let other = ScriptBuf::from_hex("0014751e76e8199196d454941c45d1b3a323f1433bd6").unwrap();
+        assert_eq!(filter_matches(GENESIS_FILTER, GENESIS, &other), Ok(false));
+        assert!(filter_matches("zz", GENESIS, &script).is_err());
+    }
+
+    #[test]
+    fn queries_take_a_block_and_an_address_or_script() {
+        let query =
+            parse_query("850000 bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4", "main").unwrap();
+        assert_eq!(query.block, "850000");
+        assert_eq!(
+            query.script.to_hex_string(),
+            "0014751e76e8199196d454941c45d1b3a323f1433bd6"
+        );
+        let query = parse_query(&format!("{GENESIS} 51"), "test").unwrap();
+        assert_eq!(query.script.as_bytes(), [0x51]);
+        assert!(parse_query("850000", "main").is_err());
+        assert!(parse_query("tip 51", "main").is_err());
+        assert_eq!(
+            parse_query("1 bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4", "signet").unwrap_err(),
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4 is not a signet address"
+        );
+        assert!(parse_query("1 notanaddress", "main").is_err());
+
+        let entries = parse_config_str("blockfilterindex=basic\n", NodeFlavor::Core).unwrap();
+        assert!(filters_enabled(&entries));
+        assert!(!served_to_peers(&entries));
+    }
+}
@@ -0,0 +1,302 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::zmq::Subscriber;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt::Write;
+use std::ops::RangeInclusive;
+use std::time::{Duration, Instant};
+
+/// How often the Blocks tab asks bitcoind for the chain tip.
+pub const BLOCK_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Blocks shown on the Blocks tab.
+pub const RECENT_BLOCKS: usize = 12;
+
+/// Gap, in seconds, past which a block counts as slow: with blocks every
+/// ten minutes on average, about one in fifty takes longer.
+pub const SLOW_BLOCK: u64 = 40 * 60;
+
+/// Depth from which a reorg is more than the occasional stale tip.
+pub const BIG_REORG: u64 = 2;
+
+/// `getblockstats` fields the Blocks tab reads.
+pub const BLOCK_STATS: &[&str] = &[
+    "blockhash",
+    "height",
+    "time",
+    "total_size",
+    "total_weight",
+    "txs",
+];
+
+/// One block, from `getblockstats`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockInfo {
+    pub height: u64,
+    pub hash: String,
+    /// Header timestamp, Unix seconds
+    pub time: u64,
+    /// Serialized size, in bytes
+    pub size: u64,
+    pub weight: u64,
+    /// Transactions, coinbase included
+    pub txs: u64,
+}
+
+impl BlockInfo {
+    /// Reads a `getblockstats` result.
+    #[must_use]
+    pub fn from_stats(stats: &Value) -> Option<Self> {
+        let number = |key: &str| stats.get(key).and_then(Value::as_u64).unwrap_or(0);
+        Some(Self {
+            height: stats.get("height")?.as_u64()?,
+            hash: stats.get("blockhash")?.as_str()?.to_string(),
+            time: number("time"),
+            size: number("total_size"),
+            weight: number("total_weight"),
+            txs: number("txs"),
+        })
+    }
+}
+
+/// Blocks disconnected from the tip before the next one was connected,
+/// as the ZMQ `sequence` topic reports them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Reorg {
+    pub depth: u64,
+    /// Unix time it was seen
+    pub seen: u64,
+    /// Hashes of the blocks disconnected, old tip first
+    #[serde(default)]
+    pub disconnected: Vec<String>,
+    /// Hash of the first block of the branch that replaced them
+    #[serde(default)]
+    pub connected: String,
+    /// Height of that block, the lowest one replaced, once bitcoind has
+    /// been asked for it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<u64>,
+}
+
+impl Reorg {
+    /// Heights whose blocks were replaced, when the fork height is known.
+    #[must_use]
+    pub fn heights(&self) -> Option<RangeInclusive<u64>> {
+        let low = self.height?;
+        Some(low..=low + self.depth.saturating_sub(1))
+    }
+
+    /// One line describing the reorg, e.g. `Reorg of 2 blocks at heights
+    /// 100–101`.
+    #[must_use]
+    pub fn summary(&self) -> String {
+        let plural = if self.depth == 1 { "" } else { "s" };
+        match self.heights() {
+            Some(heights) if heights.start() == heights.end() => {
+                format!(
+                    "Reorg of {} block{plural} at height {}",
+                    self.depth,
+                    heights.start()
+                )
+            }
+            Some(heights) => format!(
+                "Reorg of {} block{plural} at heights {}–{}",
+                self.depth,
+                heights.start(),
+                heights.end()
+            ),
+            None => format!("Reorg of {} block{plural}", self.depth),
+        }
+    }
+}
+
+/// Recent blocks of the running bitcoind, refreshed while the Blocks tab
+/// is shown, and the reorgs `zmqpubsequence` announced meanwhile.
+#[derive(Debug, Default)]
+pub struct BlockMonitor {
+    /// Newest first
+    pub blocks: Vec<BlockInfo>,
+    /// Oldest first
+    pub reorgs: Vec<Reorg>,
+    /// Hashes of the blocks disconnected since the last one connected
+    disconnected: Vec<String>,
+    pub subscriber: Option<Subscriber>,
+    /// Last attempt to connect `subscriber`
+    pub last_connect: Option<Instant>,
+    /// Why following `zmqpubsequence` failed
+    pub zmq_error: Option<String>,
+    /// Why the last poll failed
+    pub error: Option<String>,
+    pub last_poll: Option<Instant>,
+}
+
+impl BlockMonitor {
+    /// Whether the next poll is due at `now`.
+    #[must_use]
+    pub fn due(&self, now: Instant) -> bool {
+        self.last_poll
+            .is_none_or(|last| now.duration_since(last) >= BLOCK_POLL_INTERVAL)
+    }
+
+    /// Whether to try connecting to `zmqpubsequence` again at `now`.
+    #[must_use]
+    pub fn connect_due(&self, now: Instant) -> bool {
+        self.last_connect
+            .is_none_or(|last| now.duration_since(last) >= BLOCK_POLL_INTERVAL)
+    }
+
+    /// Drops blocks that are no longer among the [`RECENT_BLOCKS`] up to
+    /// `tip` and returns the heights to fetch, newest first.
+    pub fn set_tip(&mut self, tip: u64) -> Vec<u64> {
+        let oldest = (tip + 1).saturating_sub(RECENT_BLOCKS as u64);
+        self.blocks.retain(|b| (oldest..=tip).contains(&b.height));
+        (oldest..=tip)
+            .rev()
+            .filter(|h| !self.blocks.iter().any(|b| b.height == *h))
+            .collect()
+    }
+
+    /// Adds a fetched block, keeping the list newest first.
+    pub fn insert(&mut self, block: BlockInfo) {
+        self.blocks.retain(|b| b.height != block.height);
+        self.blocks.push(block);
+        self.blocks.sort_by_key(|b| std::cmp::Reverse(b.height));
+    }
+
+    /// Seconds between `block` and the one before it, when that is known.
+    /// Timestamps may go backwards by up to two hours, which counts as 0.
+    #[must_use]
+    pub fn interval(&self, block: &BlockInfo) -> Option<u64> {
+        self.blocks
+            .iter()
+            .find(|b| b.height + 1 == block.height)
+            .map(|previous| block.time.saturating_sub(previous.time))
+    }
+
+    /// Takes one `sequence` notification: the block hash, `C` for a block
+    /// connected or `D` for one disconnected, and more for mempool events.
+    /// A disconnected block is dropped so the next poll fetches its
+    /// replacement; a connected one makes the next poll due at once and
+    /// its hash is returned.
+    pub fn on_sequence(&mut self, body: &[u8], now: u64) -> Option<String> {
+        let (&label, hash) = body.get(..33).and_then(|b| b.split_last())?;
+        // bitcoind sends hashes in the byte order RPC displays them
+        let hash = hash.iter().fold(String::with_capacity(64), |mut hex, b| {
+            let _ = write!(hex, "{b:02x}");
+            hex
+        });
+        match label {
+            b'D' => {
+                self.blocks.retain(|b| b.hash != hash);
+                self.disconnected.push(hash);
+                None
+            }
+            b'C' => {
+                if !self.disconnected.is_empty() {
+                    let disconnected = std::mem::take(&mut self.disconnected);
+                    self.reorgs.push(Reorg {
+                        depth: disconnected.len() as u64,
+                        seen: now,
+                        disconnected,
+                        connected: hash.clone(),
+                        height: None,
+                    });
+                }
+                self.last_poll = None;
+                Some(hash)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(height: u64, time: u64) -> BlockInfo {
+        BlockInfo {
+            height,
+            hash: format!("{height:064x}"),
+            time,
+            size: 0,
+            weight: 0,
+            txs: 1,
+        }
+    }
+
+    #[test]
+    fn set_tip_keeps_recent_blocks_and_asks_for_the_rest() {
+        let mut monitor = BlockMonitor::default();
+        assert_eq!(monitor.set_tip(1), [1, 0]);
+        monitor.insert(block(1, 600));
+        monitor.insert(block(0, 0));
+        assert_eq!(monitor.interval(&monitor.blocks[0]), Some(600));
+        assert_eq!(monitor.interval(&monitor.blocks[1]), None);
+
+        let missing = monitor.set_tip(100);
+        assert!(monitor.blocks.is_empty());
+        assert_eq!(missing.len(), RECENT_BLOCKS);
+        assert_eq!(missing[0], 100);
+
+        let stats = serde_json::json!({
+            "blockhash": "00ab", "height": 100, "time": 5, "total_size": 900,
+            "total_weight": 3600, "txs": 3,
+        });
+        monitor.insert(BlockInfo::from_stats(&stats).unwrap());
+        assert_eq!(monitor.set_tip(100).len(), RECENT_BLOCKS - 1);
+    }
+
+    #[test]
+    fn sequence_notifications_reveal_reorgs() {
+        let mut monitor = BlockMonitor::default();
+        monitor.insert(block(7, 0));
+        monitor.last_poll = Some(Instant::now());
+        let event = |height: u64, label: u8| {
+            let mut body = vec![0; 24];
+            body.extend_from_slice(&height.to_be_bytes());
+            body.push(label);
+            body.extend_from_slice(&9u32.to_le_bytes());
+            body
+        };
+        assert_eq!(monitor.on_sequence(&event(7, b'D'), 10), None);
+        assert!(monitor.blocks.is_empty());
+        monitor.on_sequence(&event(6, b'D'), 10);
+        monitor.on_sequence(&event(6, b'A'), 10);
+        monitor.on_sequence(&event(6, b'C'), 11);
+        let [reorg] = monitor.reorgs.as_slice() else {
+            panic!("one reorg");
+        };
+        assert_eq!(reorg.depth, 2);
+        assert_eq!(reorg.seen, 11);
+        assert_eq!(
+            reorg.disconnected,
+            [format!("{:064x}", 7), format!("{:064x}", 6)]
+        );
+        assert_eq!(reorg.connected, format!("{:064x}", 6));
+        assert_eq!(reorg.summary(), "Reorg of 2 blocks");
+        assert!(monitor.last_poll.is_none());
+
+        let hash = monitor.on_sequence(&event(7, b'C'), 12).unwrap();
+        assert_eq!(hash, format!("{:064x}", 7));
+        assert_eq!(monitor.reorgs.len(), 1);
+    }
+
+    #[test]
+    fn reorg_names_the_heights_it_replaced() {
+        let mut reorg = Reorg {
+            depth: 2,
+            seen: 0,
+            disconnected: Vec::new(),
+            connected: String::new(),
+            height: Some(100),
+        };
+        assert_eq!(reorg.heights(), Some(100..=101));
+        assert_eq!(reorg.summary(), "Reorg of 2 blocks at heights 100–101");
+        reorg.depth = 1;
+        assert_eq!(reorg.summary(), "Reorg of 1 block at height 100");
+    }
+}
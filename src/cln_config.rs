@@ -0,0 +1,560 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_config::{ConfigCategory, ConfigEntry, ConfigSchema, ConfigType};
+use anyhow::Result;
+use std::path::Path;
+
+/// Options that take no argument: present means enabled.
+const FLAG_OPTIONS: &[&str] = &[
+    "daemon",
+    "offline",
+    "encrypted-hsm",
+    "disable-dns",
+    "large-channels",
+    "mainnet",
+    "testnet",
+    "testnet4",
+    "signet",
+    "regtest",
+    "clear-plugins",
+];
+
+/// Valid options that PDM does not expose in the editor but must not flag as unknown.
+const OTHER_OPTIONS: &[&str] = &[
+    "conf",
+    "pid-file",
+    "log-prefix",
+    "important-plugin",
+    "bitcoin-rpcclienttimeout",
+    "announce-addr-dns",
+    "htlc-minimum-msat",
+    "htlc-maximum-msat",
+    "max-locktime-blocks",
+    "watchtime-blocks",
+    "database-upgrade",
+    "rescan",
+];
+
+/// Options Core Lightning accepts more than once, each line adding a value.
+const MULTI_VALUE_OPTIONS: &[&str] = &[
+    "addr",
+    "bind-addr",
+    "announce-addr",
+    "plugin",
+    "disable-plugin",
+];
+
+/// Network sub-directories of `lightning-dir`, each holding a per-network `config`.
+const NETWORK_DIRS: &[&str] = &["bitcoin", "testnet", "testnet4", "signet", "regtest"];
+
+/// Returns the schema for the commonly used Core Lightning options
+#[must_use]
+#[allow(clippy::too_many_lines)]
+pub fn get_cln_schema() -> Vec<ConfigSchema> {
+    vec![
+        // General
+        ConfigSchema::new(
+            "network",
+            "bitcoin",
+            ConfigType::String,
+            ConfigCategory::ClnGeneral,
+            "Network to run on (bitcoin, testnet, signet, regtest)",
+        ),
+        ConfigSchema::new(
+            "lightning-dir",
+            "",
+            ConfigType::Path,
+            ConfigCategory::ClnGeneral,
+            "Base directory for Core Lightning data",
+        ),
+        ConfigSchema::new(
+            "alias",
+            "",
+            ConfigType::String,
+            ConfigCategory::ClnGeneral,
+            "Node alias shown to the network",
+        ),
+        ConfigSchema::new(
+            "rgb",
+            "",
+            ConfigType::String,
+            ConfigCategory::ClnGeneral,
+            "Node color as six hex digits",
+        ),
+        ConfigSchema::new(
+            "log-level",
+            "info",
+            ConfigType::String,
+            ConfigCategory::ClnGeneral,
+            "Log verbosity (io, debug, info, unusual, broken)",
+        ),
+        ConfigSchema::new(
+            "log-file",
+            "",
+            ConfigType::Path,
+            ConfigCategory::ClnGeneral,
+            "Write logs to this file instead of stdout",
+        ),
+        ConfigSchema::new(
+            "log-timestamps",
+            "true",
+            ConfigType::Bool,
+            ConfigCategory::ClnGeneral,
+            "Prefix log lines with timestamps",
+        ),
+        ConfigSchema::new(
+            "daemon",
+            "false",
+            ConfigType::Bool,
+            ConfigCategory::ClnGeneral,
+            "Run in the background",
+        ),
+        ConfigSchema::new(
+            "rpc-file",
+            "lightning-rpc",
+            ConfigType::Path,
+            ConfigCategory::ClnGeneral,
+            "Name of the JSON-RPC socket",
+        ),
+        ConfigSchema::new(
+            "wallet",
+            "",
+            ConfigType::String,
+            ConfigCategory::ClnGeneral,
+            "Database connection string for the wallet",
+        ),
+        ConfigSchema::new(
+            "encrypted-hsm",
+            "false",
+            ConfigType::Bool,
+            ConfigCategory::ClnGeneral,
+            "Prompt for a password to encrypt hsm_secret",
+        ),
+        ConfigSchema::new(
+            "plugin-dir",
+            "",
+            ConfigType::Path,
+            ConfigCategory::ClnGeneral,
+            "Load all plugins in this directory",
+        ),
+        ConfigSchema::new(
+            "plugin",
+            "",
+            ConfigType::Path,
+            ConfigCategory::ClnGeneral,
+            "Load a single plugin",
+        ),
+        ConfigSchema::new(
+            "disable-plugin",
+            "",
+            ConfigType::String,
+            ConfigCategory::ClnGeneral,
+            "Do not load the named plugin",
+        ),
+        // Bitcoin backend
+        ConfigSchema::new(
+            "bitcoin-datadir",
+            "",
+            ConfigType::Path,
+            ConfigCategory::ClnBitcoind,
+            "bitcoind data directory",
+        ),
+        ConfigSchema::new(
+            "bitcoin-cli",
+            "bitcoin-cli",
+            ConfigType::Path,
+            ConfigCategory::ClnBitcoind,
+            "Path to the bitcoin-cli binary",
+        ),
+        ConfigSchema::new(
+            "bitcoin-rpcuser",
+            "",
+            ConfigType::String,
+            ConfigCategory::ClnBitcoind,
+            "bitcoind RPC username",
+        ),
+        ConfigSchema::new(
+            "bitcoin-rpcpassword",
+            "",
+            ConfigType::String,
+            ConfigCategory::ClnBitcoind,
+            "bitcoind RPC password",
+        ),
+        ConfigSchema::new(
+            "bitcoin-rpcconnect",
+            "127.0.0.1",
+            ConfigType::Address,
+            ConfigCategory::ClnBitcoind,
+            "bitcoind RPC host",
+        ),
+        ConfigSchema::new(
+            "bitcoin-rpcport",
+            "",
+            ConfigType::Int,
+            ConfigCategory::ClnBitcoind,
+            "bitcoind RPC port",
+        ),
+        ConfigSchema::new(
+            "bitcoin-retry-timeout",
+            "60",
+            ConfigType::Int,
+            ConfigCategory::ClnBitcoind,
+            "Seconds to keep retrying bitcoin-cli",
+        ),
+        // Networking
+        ConfigSchema::new(
+            "addr",
+            "",
+            ConfigType::Address,
+            ConfigCategory::ClnNetworking,
+            "Bind and announce this address",
+        ),
+        ConfigSchema::new(
+            "bind-addr",
+            "",
+            ConfigType::Address,
+            ConfigCategory::ClnNetworking,
+            "Bind to this address without announcing it",
+        ),
+        ConfigSchema::new(
+            "announce-addr",
+            "",
+            ConfigType::Address,
+            ConfigCategory::ClnNetworking,
+            "Announce this address without binding",
+        ),
+        ConfigSchema::new(
+            "autolisten",
+            "true",
+            ConfigType::Bool,
+            ConfigCategory::ClnNetworking,
+            "Listen on the default port if no addr is given",
+        ),
+        ConfigSchema::new(
+            "proxy",
+            "",
+            ConfigType::Address,
+            ConfigCategory::ClnNetworking,
+            "SOCKS5 proxy for outgoing connections",
+        ),
+        ConfigSchema::new(
+            "always-use-proxy",
+            "false",
+            ConfigType::Bool,
+            ConfigCategory::ClnNetworking,
+            "Use the proxy for all connections",
+        ),
+        ConfigSchema::new(
+            "tor-service-password",
+            "",
+            ConfigType::String,
+            ConfigCategory::ClnNetworking,
+            "Tor control port password",
+        ),
+        ConfigSchema::new(
+            "disable-dns",
+            "false",
+            ConfigType::Bool,
+            ConfigCategory::ClnNetworking,
+            "Disable DNS lookups of peers",
+        ),
+        ConfigSchema::new(
+            "offline",
+            "false",
+            ConfigType::Bool,
+            ConfigCategory::ClnNetworking,
+            "Start without connecting to peers",
+        ),
+        // Channels
+        ConfigSchema::new(
+            "fee-base",
+            "1000",
+            ConfigType::Int,
+            ConfigCategory::ClnChannels,
+            "Base routing fee in millisatoshi",
+        ),
+        ConfigSchema::new(
+            "fee-per-satoshi",
+            "10",
+            ConfigType::Int,
+            ConfigCategory::ClnChannels,
+            "Proportional routing fee in millionths",
+        ),
+        ConfigSchema::new(
+            "min-capacity-sat",
+            "10000",
+            ConfigType::Int,
+            ConfigCategory::ClnChannels,
+            "Minimum channel capacity in satoshi",
+        ),
+        ConfigSchema::new(
+            "funding-confirms",
+            "3",
+            ConfigType::Int,
+            ConfigCategory::ClnChannels,
+            "Confirmations required for channel funding",
+        ),
+        ConfigSchema::new(
+            "max-concurrent-htlcs",
+            "30",
+            ConfigType::Int,
+            ConfigCategory::ClnChannels,
+            "Maximum HTLCs in flight per direction",
+        ),
+        ConfigSchema::new(
+            "cltv-delta",
+            "34",
+            ConfigType::Int,
+            ConfigCategory::ClnChannels,
+            "CLTV delta for forwarded payments",
+        ),
+        ConfigSchema::new(
+            "large-channels",
+            "false",
+            ConfigType::Bool,
+            ConfigCategory::ClnChannels,
+            "Allow channels larger than 2^24 satoshi",
+        ),
+    ]
+}
+
+/// Returns true if `key` is an option name Core Lightning itself accepts.
+/// Plugins may register more, so unknown names are warnings, not errors.
+#[must_use]
+pub fn is_known_option(key: &str) -> bool {
+    get_cln_schema().iter().any(|s| s.key == key)
+        || FLAG_OPTIONS.contains(&key)
+        || OTHER_OPTIONS.contains(&key)
+}
+
+/// Returns the keys set in `entries` that Core Lightning does not recognise.
+#[must_use]
+pub fn unknown_options(entries: &[ConfigEntry]) -> Vec<String> {
+    entries
+        .iter()
+        .filter(|e| e.enabled && !is_known_option(&e.key))
+        .map(|e| e.key.clone())
+        .collect()
+}
+
+/// Returns the network implied by a config living in a network
+/// sub-directory, e.g. `~/.lightning/testnet/config` -> `testnet`.
+#[must_use]
+pub fn network_from_path(path: &Path) -> Option<&'static str> {
+    let dir = path.parent()?.file_name()?.to_str()?;
+    NETWORK_DIRS.iter().copied().find(|n| *n == dir)
+}
+
+/// Parses Core Lightning config content into schema-backed entries.
+///
+/// The format has no sections: each line is `key=value` or a bare flag.
+/// `network` is the network implied by the file location, if any; it
+/// becomes the default for the `network` option.
+#[must_use]
+pub fn parse_cln_str(content: &str, network: Option<&str>) -> Vec<ConfigEntry> {
+    let mut entries: Vec<ConfigEntry> = get_cln_schema()
+        .into_iter()
+        .map(|mut schema| {
+            if let (true, Some(net)) = (schema.key == "network", network) {
+                schema.default = net.to_string();
+            }
+            ConfigEntry {
+                key: schema.key.clone(),
+                value: schema.default.clone(),
+                enabled: false,
+                section: None,
+                negated: false,
+                schema: Some(schema.into()),
+            }
+        })
+        .collect();
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (key, value) = match line.split_once('=') {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => (line, "true"),
+        };
+
+        // Repeatable options get an entry per line
+        let multi = MULTI_VALUE_OPTIONS.contains(&key);
+        if let Some(i) = entries
+            .iter()
+            .position(|e| e.key == key && !(multi && e.enabled))
+        {
+            entries[i].value = value.to_string();
+            entries[i].enabled = true;
+        } else if let Some(last) = entries.iter().rposition(|e| e.key == key && multi) {
+            let repeat = ConfigEntry {
+                value: value.to_string(),
+                ..entries[last].clone()
+            };
+            entries.insert(last + 1, repeat);
+        } else {
+            entries.push(ConfigEntry {
+                key: key.to_string(),
+                value: value.to_string(),
+                schema: None,
+                enabled: true,
+                section: None,
+                negated: false,
+            });
+        }
+    }
+
+    entries
+}
+
+/// Parse a Core Lightning config file
+///
+/// # Errors
+/// Returns an error if the file exists but cannot be read.
+/// A missing file yields schema defaults.
+pub fn parse_cln_config(path: &Path) -> Result<Vec<ConfigEntry>> {
+    let content = if path.exists() {
+        std::fs::read_to_string(path)?
+    } else {
+        String::new()
+    };
+    Ok(parse_cln_str(&content, network_from_path(path)))
+}
+
+/// Save entries in Core Lightning format. Flags are written bare and
+/// omitted entirely when switched off.
+///
+/// # Errors
+/// Returns an error if the file cannot be created or written.
+pub fn save_cln_config(path: &Path, entries: &[ConfigEntry]) -> Result<()> {
+    use std::io::Write;
+
+    let mut file = std::fs::File::create(path)?;
+    for entry in entries.iter().filter(|e| e.enabled) {
+        if FLAG_OPTIONS.contains(&entry.key.as_str()) {
+            if matches!(entry.value.as_str(), "true" | "1") {
+                writeln!(file, "{}", entry.key)?;
+            }
+        } else {
+            writeln!(file, "{}={}", entry.key, entry.value)?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the number of known Core Lightning options set in `entries`.
+#[must_use]
+pub fn known_option_count(entries: &[ConfigEntry]) -> usize {
+    entries
+        .iter()
+        .filter(|e| e.enabled && is_known_option(&e.key))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "
+# Core Lightning config
+network=bitcoin
+alias=my-cln
+daemon
+bitcoin-rpcuser=cln
+clnrest-port=3010
+";
+
+    #[test]
+    fn schema_keys_are_unique() {
+        let schema = get_cln_schema();
+        let mut keys: Vec<&str> = schema.iter().map(|s| s.key.as_str()).collect();
+        keys.sort_unstable();
+        keys.dedup();
+        assert_eq!(keys.len(), schema.len());
+    }
+
+    #[test]
+    fn parse_keeps_every_address_and_plugin() {
+        let content = "addr=0.0.0.0:9735\naddr=statictor:127.0.0.1:9051\nplugin=/opt/a.py\nplugin=/opt/b.py\ndisable-plugin=bcli\n";
+        let entries = parse_cln_str(content, None);
+        let values = |key: &str| -> Vec<&str> {
+            entries
+                .iter()
+                .filter(|e| e.key == key && e.enabled)
+                .map(|e| e.value.as_str())
+                .collect()
+        };
+        assert_eq!(values("addr"), ["0.0.0.0:9735", "statictor:127.0.0.1:9051"]);
+        assert_eq!(values("plugin"), ["/opt/a.py", "/opt/b.py"]);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config");
+        save_cln_config(&path, &entries).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "plugin=/opt/a.py\nplugin=/opt/b.py\ndisable-plugin=bcli\naddr=0.0.0.0:9735\naddr=statictor:127.0.0.1:9051\n"
+        );
+    }
+
+    #[test]
+    fn parse_reads_values_and_bare_flags() {
+        let entries = parse_cln_str(SAMPLE, None);
+
+        let alias = entries.iter().find(|e| e.key == "alias").unwrap();
+        assert_eq!(alias.value, "my-cln");
+        assert!(alias.enabled);
+        assert!(alias.section.is_none());
+
+        let daemon = entries.iter().find(|e| e.key == "daemon").unwrap();
+        assert!(daemon.enabled);
+        assert_eq!(daemon.value, "true");
+    }
+
+    #[test]
+    fn unknown_options_reports_unrecognised_names() {
+        let entries = parse_cln_str(SAMPLE, None);
+        assert_eq!(unknown_options(&entries), vec!["clnrest-port".to_string()]);
+    }
+
+    #[test]
+    fn network_subdirectory_sets_network_default() {
+        let path = Path::new("/home/u/.lightning/testnet/config");
+        assert_eq!(network_from_path(path), Some("testnet"));
+        assert_eq!(
+            network_from_path(Path::new("/home/u/.lightning/config")),
+            None
+        );
+
+        let entries = parse_cln_str("", network_from_path(path));
+        let network = entries.iter().find(|e| e.key == "network").unwrap();
+        assert_eq!(network.value, "testnet");
+        assert!(!network.enabled);
+    }
+
+    #[test]
+    fn save_writes_flags_bare_and_drops_disabled_flags() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config");
+        let mut entries = parse_cln_str(SAMPLE, None);
+        let offline = entries.iter_mut().find(|e| e.key == "offline").unwrap();
+        offline.enabled = true;
+        offline.value = "false".to_string();
+
+        save_cln_config(&path, &entries).unwrap();
+        let saved = std::fs::read_to_string(&path).unwrap();
+
+        assert!(saved.lines().any(|l| l == "daemon"));
+        assert!(saved.contains("alias=my-cln\n"));
+        assert!(!saved.contains("offline"));
+        assert!(!saved.contains('['));
+    }
+
+    #[test]
+    fn known_option_count_ignores_unknown_keys() {
+        assert_eq!(known_option_count(&parse_cln_str("foo=bar\n", None)), 0);
+        assert_eq!(known_option_count(&parse_cln_str(SAMPLE, None)), 4);
+    }
+}
@@ -0,0 +1,201 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_config::{ConfigEntry, enabled_value};
+use crate::bitcoind::NodeVersion;
+use serde_json::Value;
+use std::net::UdpSocket;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// NTP pool asked for the time.
+pub const NTP_SERVER: &str = "pool.ntp.org:123";
+
+/// `maxtimeadjustment` when the config does not set it, in seconds.
+pub const DEFAULT_MAX_TIME_ADJUSTMENT: u64 = 4200;
+
+/// Offset from its peers past which bitcoind v29 and later warns, having
+/// stopped adjusting its clock, in seconds.
+pub const PEER_OFFSET_WARNING: u64 = 600;
+
+/// First major version without `maxtimeadjustment`.
+const TIME_ADJUSTMENT_REMOVED: u32 = 29;
+
+/// Seconds between the NTP epoch, 1900, and the Unix one.
+const NTP_UNIX_OFFSET: f64 = 2_208_988_800.0;
+
+/// How far the clock may drift before peers stop agreeing with it, and
+/// what sets that limit.
+#[must_use]
+pub fn drift_limit(entries: &[ConfigEntry], version: Option<&NodeVersion>) -> (u64, String) {
+    if version.is_some_and(|v| v.major >= TIME_ADJUSTMENT_REMOVED) {
+        return (
+            PEER_OFFSET_WARNING,
+            "the 10 minutes bitcoind warns at".to_string(),
+        );
+    }
+    let limit = enabled_value(entries, "maxtimeadjustment")
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(DEFAULT_MAX_TIME_ADJUSTMENT);
+    (limit, format!("maxtimeadjustment={limit}"))
+}
+
+/// The median `timeoffset` of the peers in a `getpeerinfo` result, which
+/// is how far ahead of this node the peers' clocks are, in seconds.
+#[must_use]
+pub fn peer_offset(peers: &Value) -> Option<i64> {
+    let mut offsets: Vec<i64> = peers
+        .as_array()?
+        .iter()
+        .filter_map(|p| p.get("timeoffset")?.as_i64())
+        .collect();
+    if offsets.is_empty() {
+        return None;
+    }
+    offsets.sort_unstable();
+    Some(offsets[offsets.len() / 2])
+}
+
+/// An SNTP client request: version 4, client mode.
+#[must_use]
+pub fn ntp_request() -> [u8; 48] {
+    let mut packet = [0u8; 48];
+    packet[0] = 0b00_100_011;
+    packet
+}
+
+fn ntp_seconds(bytes: &[u8]) -> f64 {
+    let seconds = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    let fraction = u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+    f64::from(seconds) - NTP_UNIX_OFFSET + f64::from(fraction) / 4_294_967_296.0
+}
+
+/// How far the server's clock is ahead of this one, in seconds, from its
+/// reply to a request sent at `sent` and answered at `received`, both
+/// Unix seconds.
+///
+/// # Errors
+/// Returns an error if the reply is short or from a server that is not
+/// synchronized.
+pub fn parse_ntp_reply(reply: &[u8], sent: f64, received: f64) -> Result<f64, String> {
+    if reply.len() < 48 {
+        return Err(format!("NTP reply is {} bytes, expected 48", reply.len()));
+    }
+    if reply[0] >> 6 == 3 || reply[1] == 0 {
+        return Err("NTP server is not synchronized".to_string());
+    }
+    let server_received = ntp_seconds(&reply[32..40]);
+    let server_sent = ntp_seconds(&reply[40..48]);
+    Ok(((server_received - sent) + (server_sent - received)) / 2.0)
+}
+
+fn unix_now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |d| d.as_secs_f64())
+}
+
+/// Asks `server` how far its clock is ahead of this one, in seconds.
+///
+/// # Errors
+/// Returns an error if the server cannot be reached or its reply read.
+pub fn query_ntp(server: &str, timeout: Duration) -> Result<f64, String> {
+    let socket = UdpSocket::bind("0.0.0.0:0").map_err(|e| e.to_string())?;
+    socket
+        .set_read_timeout(Some(timeout))
+        .map_err(|e| e.to_string())?;
+    socket
+        .connect(server)
+        .map_err(|e| format!("cannot reach {server}: {e}"))?;
+    let sent = unix_now();
+    socket
+        .send(&ntp_request())
+        .map_err(|e| format!("cannot reach {server}: {e}"))?;
+    let mut reply = [0u8; 48];
+    let len = socket
+        .recv(&mut reply)
+        .map_err(|e| format!("no answer from {server}: {e}"))?;
+    parse_ntp_reply(&reply[..len], sent, unix_now())
+}
+
+/// Judges an `offset` of the local clock, in seconds, against `limit`:
+/// fine below half of it, approaching up to it, too far past it.
+///
+/// # Errors
+/// Returns the warning when the drift approaches or passes the limit.
+pub fn judge_drift(offset: f64, limit: u64, source: &str, why: &str) -> Result<String, String> {
+    let drift = offset.abs();
+    let direction = if offset > 0.0 { "behind" } else { "ahead of" };
+    #[allow(clippy::cast_precision_loss)] // Limits are far below 2^52 seconds
+    let limit = limit as f64;
+    let text = format!("clock is {drift:.1}s {direction} {source}");
+    if drift >= limit {
+        Err(format!(
+            "{text}, past {why}; peers will disagree with this node's time, fix the clock or \
+             enable NTP"
+        ))
+    } else if drift >= limit / 2.0 {
+        Err(format!("{text}, approaching {why}; check NTP"))
+    } else {
+        Ok(format!("{text}, within {why}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitcoin_config::{NodeFlavor, parse_config_str};
+
+    fn ntp_timestamp(unix: f64) -> [u8; 8] {
+        let ntp = unix + NTP_UNIX_OFFSET;
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let (seconds, fraction) = (ntp.trunc() as u32, (ntp.fract() * 4_294_967_296.0) as u32);
+        let mut out = [0u8; 8];
+        out[..4].copy_from_slice(&seconds.to_be_bytes());
+        out[4..].copy_from_slice(&fraction.to_be_bytes());
+        out
+    }
+
+    #[test]
+    fn ntp_reply_gives_the_offset() {
+        let mut reply = [0u8; 48];
+        reply[0] = 0b00_100_100;
+        reply[1] = 2;
+        // Server 30s ahead, 1s round trip
+        reply[32..40].copy_from_slice(&ntp_timestamp(1_000_030.5));
+        reply[40..48].copy_from_slice(&ntp_timestamp(1_000_030.5));
+        let offset = parse_ntp_reply(&reply, 1_000_000.0, 1_000_001.0).unwrap();
+        assert!((offset - 30.0).abs() < 0.01, "{offset}");
+        reply[1] = 0;
+        assert!(parse_ntp_reply(&reply, 0.0, 0.0).is_err());
+        assert!(parse_ntp_reply(&reply[..20], 0.0, 0.0).is_err());
+        assert_eq!(ntp_request()[0] & 0b111, 3);
+    }
+
+    #[test]
+    fn drift_is_judged_against_the_limit() {
+        let peers = serde_json::json!([
+            {"timeoffset": 0}, {"timeoffset": -2500}, {"timeoffset": -2600},
+        ]);
+        assert_eq!(peer_offset(&peers), Some(-2500));
+        assert_eq!(peer_offset(&serde_json::json!([])), None);
+
+        let entries = parse_config_str("maxtimeadjustment=3000\n", NodeFlavor::Core).unwrap();
+        let (limit, why) = drift_limit(&entries, None);
+        assert_eq!((limit, why.as_str()), (3000, "maxtimeadjustment=3000"));
+        let v29 = NodeVersion::parse("Bitcoin Core version v29.0.0").unwrap();
+        assert_eq!(drift_limit(&entries, Some(&v29)).0, PEER_OFFSET_WARNING);
+
+        assert!(judge_drift(12.0, limit, "NTP", &why).is_ok());
+        let approaching = judge_drift(-2500.0, limit, "the peers", &why).unwrap_err();
+        assert_eq!(
+            approaching,
+            "clock is 2500.0s ahead of the peers, approaching maxtimeadjustment=3000; check NTP"
+        );
+        assert!(
+            judge_drift(3600.0, limit, "NTP", &why)
+                .unwrap_err()
+                .contains("past maxtimeadjustment=3000")
+        );
+    }
+}
@@ -3,11 +3,35 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
 use crate::app::{App, AppAction};
-use crate::bitcoin_config::ConfigEntry;
-use crossterm::event::{KeyCode, KeyEvent};
+use crate::bitcoin_config::{
+    Assignment, CHAINS, ConfigCategory, ConfigEntry, ParseError, Tier, active_network,
+    chain_default, config_line, enabled_value, is_customized, relation_holds, relations_to,
+    resolve_for_chain, suggest_key,
+};
+use crate::components::raw_editor::RawEditor;
+use crate::cookie::CookieNote;
+use crate::datadir_move::{DatadirMove, MoveMethod};
+use crate::debug_categories::{CATEGORY_OPTIONS, CategoryPicker, DEBUG_CATEGORIES};
+use crate::diagnostics::{Diagnostic, Severity, fee_equivalent};
+use crate::indexes::{enables, estimate, index_for};
+use crate::notify_scripts::{NOTIFY_OPTIONS, ScriptKind, ScriptPicker, script_file, template};
+use crate::onion_key::{ONION_KEY_NOTE, OnionBackup};
+use crate::permissions::{
+    BIND_OPTIONS, EditorRow, PERMISSION_FLAGS, PERMISSION_OPTIONS, PermissionEditor,
+};
+use crate::probe::{PeerCheck, PeerStatus};
+use crate::relay_policy::{POLICY_OPTIONS, PolicyPreset, PresetPicker};
+use crate::rpcwhitelist::{CatalogRow, WhitelistBuilder, catalog_rows};
+use crate::secrets::is_secret;
+use crate::slider::{IntRange, Slider, range_for};
+use crate::template::{
+    Origin, ProfileEditor, ProfileRow, ProfileTable, expand_env, has_placeholders,
+};
+use crate::units::{Unit, convert, with_unit};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::{
     prelude::*,
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
 };
 use std::path::Path;
 use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
@@ -77,6 +101,69 @@ pub struct BitcoinConfigView {
     pub sidebar_focused: bool,
     /// True when entries have been committed (via `CommitEdit`) but not yet saved to disk.
     pub dirty: bool,
+    /// Show advanced and debug options too, not just common and set ones
+    pub show_all: bool,
+    /// Only list options whose value differs from the default
+    pub customized_only: bool,
+    /// Category awaiting a y/n confirmation before being reset to defaults
+    pub confirm_reset: Option<ConfigCategory>,
+    /// Offer to move the data after `datadir` changed, awaiting m, l or
+    /// any other key to decline
+    pub datadir_move: Option<DatadirMove>,
+    /// Keys pinned to the top of the list, mirrored from the user settings
+    pub favorites: Vec<String>,
+    /// Text editor shown instead of the entry list while in raw view
+    pub raw: Option<RawEditor>,
+    /// Lines of bitcoin.conf as saved, read while the selected option's
+    /// per-network values are shown in place of its help
+    pub network_lines: Option<Vec<Assignment>>,
+    /// Show what the selected option depends on and affects in place of
+    /// its help
+    pub show_relations: bool,
+    /// Results of the last peer test and the option it ran on
+    pub peer_checks: Option<(String, Vec<PeerCheck>)>,
+    /// Findings of the last cookie inspection and the option it ran on
+    pub cookie_notes: Option<(String, Vec<CookieNote>)>,
+    /// rpcwhitelist builder shown instead of the entry list while open
+    pub rpc_whitelist: Option<WhitelistBuilder>,
+    /// Permission flag editor for a `whitebind` or `whitelist` entry
+    pub permissions: Option<PermissionEditor>,
+    /// Script template picker for a `*notify` entry
+    pub notify_script: Option<ScriptPicker>,
+    /// Category checklist edited in place of the text of `debug` or
+    /// `debugexclude`
+    pub categories: Option<CategoryPicker>,
+    /// Slider edited in place of the text of an integer option with a
+    /// known valid range
+    pub slider: Option<Slider>,
+    /// Backup prompt for the onion service key
+    pub onion_backup: Option<OnionBackup>,
+    /// Preset picker for the relay-policy options
+    pub relay_policy: Option<PresetPicker>,
+    /// Profiles of this bitcoin.conf with what each inherits and overrides
+    pub profiles: Option<ProfileEditor>,
+}
+
+/// Indices of the entries shown in the editor: `favorites` first, in
+/// pinning order, then the rest. Unless `show_all` is set, only common
+/// options plus anything already set or unknown are listed.
+#[must_use]
+pub fn visible_rows(entries: &[ConfigEntry], show_all: bool, favorites: &[String]) -> Vec<usize> {
+    let mut rows: Vec<usize> = favorites
+        .iter()
+        .filter_map(|key| entries.iter().position(|e| &e.key == key))
+        .collect();
+    rows.extend(
+        entries
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| !favorites.contains(&e.key))
+            .filter(|(_, e)| {
+                show_all || e.enabled || e.schema.as_ref().is_none_or(|s| s.tier == Tier::Common)
+            })
+            .map(|(i, _)| i),
+    );
+    rows
 }
 
 impl BitcoinConfigView {
@@ -90,10 +177,242 @@ impl BitcoinConfigView {
             warning_message: None,
             sidebar_focused: true,
             dirty: false,
+            show_all: false,
+            customized_only: false,
+            confirm_reset: None,
+            datadir_move: None,
+            favorites: Vec::new(),
+            raw: None,
+            network_lines: None,
+            show_relations: false,
+            peer_checks: None,
+            cookie_notes: None,
+            rpc_whitelist: None,
+            permissions: None,
+            notify_script: None,
+            categories: None,
+            slider: None,
+            onion_backup: None,
+            relay_policy: None,
+            profiles: None,
+        }
+    }
+
+    /// Rows listed with the current filters applied.
+    #[must_use]
+    pub fn rows(&self, entries: &[ConfigEntry]) -> Vec<usize> {
+        let mut rows = visible_rows(entries, self.show_all, &self.favorites);
+        if self.customized_only {
+            rows.retain(|&i| is_customized(&entries[i]));
+        }
+        rows
+    }
+
+    /// Moves the selection to the previous (`forward` = false) or next
+    /// visible entry, staying put at either end.
+    fn step(&mut self, entries: &[ConfigEntry], forward: bool) {
+        let rows = self.rows(entries);
+        let Some(pos) = rows.iter().position(|&i| i == self.selected_index) else {
+            self.ensure_visible(entries);
+            return;
+        };
+        let next = if forward {
+            rows.get(pos + 1)
+        } else {
+            pos.checked_sub(1).and_then(|p| rows.get(p))
+        };
+        if let Some(&i) = next {
+            self.selected_index = i;
+        }
+    }
+
+    /// Selects the first visible entry if the current one is filtered out.
+    pub fn ensure_visible(&mut self, entries: &[ConfigEntry]) {
+        let rows = self.rows(entries);
+        if !rows.contains(&self.selected_index) {
+            self.selected_index = rows.first().copied().unwrap_or(0);
         }
     }
 
     pub fn handle_input(&mut self, key: KeyEvent, entries: &[ConfigEntry]) -> AppAction {
+        if self.datadir_move.is_some() {
+            return match key.code {
+                KeyCode::Char('m') => AppAction::MoveDatadir(MoveMethod::Move),
+                KeyCode::Char('l') => AppAction::MoveDatadir(MoveMethod::Symlink),
+                _ => {
+                    self.datadir_move = None;
+                    AppAction::None
+                }
+            };
+        }
+        if let Some(category) = self.confirm_reset.take() {
+            return if key.code == KeyCode::Char('y') {
+                AppAction::ResetCategory(category)
+            } else {
+                AppAction::None
+            };
+        }
+        if let Some(editor) = &mut self.permissions {
+            match key.code {
+                KeyCode::Up => editor.step(false),
+                KeyCode::Down => editor.step(true),
+                KeyCode::Char(' ') if !editor.on_text() => editor.toggle(),
+                KeyCode::Char(c) => editor.type_char(c),
+                KeyCode::Backspace => editor.backspace(),
+                KeyCode::Enter if editor.problems().is_empty() => {
+                    let action = AppAction::CommitEdit(editor.index, editor.value());
+                    self.permissions = None;
+                    self.save_message = None;
+                    return action;
+                }
+                KeyCode::Esc => self.permissions = None,
+                _ => {}
+            }
+            return AppAction::None;
+        }
+        if let Some(backup) = &mut self.onion_backup {
+            match key.code {
+                KeyCode::Char(c) => backup.passphrase.push(c),
+                KeyCode::Backspace => {
+                    backup.passphrase.pop();
+                }
+                KeyCode::Enter => return AppAction::BackUpOnionKey,
+                KeyCode::Esc => self.onion_backup = None,
+                _ => {}
+            }
+            return AppAction::None;
+        }
+        if let Some(editor) = &mut self.profiles {
+            match key.code {
+                KeyCode::Left => editor.switch(false),
+                KeyCode::Right | KeyCode::Tab => editor.switch(true),
+                KeyCode::Up => editor.cursor = editor.cursor.saturating_sub(1),
+                KeyCode::Down => {
+                    let rows = editor.rows().map_or(0, |r| r.len());
+                    editor.cursor = (editor.cursor + 1).min(rows.saturating_sub(1));
+                }
+                KeyCode::Char('d') | KeyCode::Delete => {
+                    if let (Some(profile), Some(row)) = (editor.profile(), editor.own_row()) {
+                        return AppAction::ClearProfileOverride(
+                            profile.name.clone(),
+                            row.table,
+                            row.key,
+                        );
+                    }
+                }
+                KeyCode::Char('g') => return AppAction::GenerateProfiles,
+                KeyCode::Esc => self.profiles = None,
+                _ => {}
+            }
+            return AppAction::None;
+        }
+        if let Some(picker) = &mut self.relay_policy {
+            match key.code {
+                KeyCode::Up => picker.cursor = picker.cursor.saturating_sub(1),
+                KeyCode::Down => {
+                    picker.cursor = (picker.cursor + 1).min(PolicyPreset::ALL.len() - 1);
+                }
+                KeyCode::Enter => {
+                    let action = AppAction::ApplyPolicyPreset(picker.preset());
+                    self.relay_policy = None;
+                    return action;
+                }
+                KeyCode::Esc => self.relay_policy = None,
+                _ => {}
+            }
+            return AppAction::None;
+        }
+        if let Some(picker) = &mut self.categories {
+            match key.code {
+                KeyCode::Up => picker.cursor = picker.cursor.saturating_sub(1),
+                KeyCode::Down => {
+                    picker.cursor = (picker.cursor + 1).min(DEBUG_CATEGORIES.len() - 1);
+                }
+                KeyCode::Char(' ') => picker.toggle(),
+                KeyCode::Enter => {
+                    let action = AppAction::CommitEdit(picker.index, picker.value());
+                    self.categories = None;
+                    self.save_message = None;
+                    return action;
+                }
+                KeyCode::Esc => self.categories = None,
+                _ => {}
+            }
+            return AppAction::None;
+        }
+        if let Some(slider) = &mut self.slider {
+            match key.code {
+                KeyCode::Left | KeyCode::Down => slider.nudge(-1),
+                KeyCode::Right | KeyCode::Up => slider.nudge(1),
+                KeyCode::PageDown => slider.nudge(-10),
+                KeyCode::PageUp => slider.nudge(10),
+                KeyCode::Home => slider.jump(false),
+                KeyCode::End => slider.jump(true),
+                KeyCode::Enter => {
+                    let action = AppAction::CommitEdit(slider.index, slider.value());
+                    self.slider = None;
+                    self.save_message = None;
+                    return action;
+                }
+                KeyCode::Esc => self.slider = None,
+                _ => {}
+            }
+            return AppAction::None;
+        }
+        if let Some(picker) = &mut self.notify_script {
+            match key.code {
+                KeyCode::Up => picker.cursor = picker.cursor.saturating_sub(1),
+                KeyCode::Down => picker.cursor = (picker.cursor + 1).min(ScriptKind::ALL.len() - 1),
+                KeyCode::Enter => {
+                    let action = AppAction::WriteNotifyScript(picker.index, picker.kind());
+                    self.notify_script = None;
+                    return action;
+                }
+                KeyCode::Esc => self.notify_script = None,
+                _ => {}
+            }
+            return AppAction::None;
+        }
+        if let Some(builder) = &mut self.rpc_whitelist {
+            let last = catalog_rows().len() - 1;
+            match key.code {
+                KeyCode::Up => builder.cursor = builder.cursor.saturating_sub(1),
+                KeyCode::Down => builder.cursor = (builder.cursor + 1).min(last),
+                KeyCode::Left => builder.switch_user(false),
+                KeyCode::Right | KeyCode::Tab => builder.switch_user(true),
+                KeyCode::Char(' ') | KeyCode::Enter => builder.toggle(),
+                KeyCode::Char('s') => return AppAction::WriteRpcWhitelist,
+                KeyCode::Esc => self.rpc_whitelist = None,
+                _ => {}
+            }
+            self.save_message = None;
+            return AppAction::None;
+        }
+        if let Some(raw) = &mut self.raw {
+            return match key.code {
+                // The find/replace prompt takes every key until closed
+                _ if raw.search.is_some() => {
+                    if raw.handle_input(key) {
+                        self.save_message = None;
+                    }
+                    AppAction::None
+                }
+                KeyCode::Tab => AppAction::ToggleRawView,
+                KeyCode::Esc => {
+                    self.sidebar_focused = true;
+                    AppAction::None
+                }
+                KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    AppAction::SaveBitcoinConfig
+                }
+                _ => {
+                    if raw.handle_input(key) {
+                        self.save_message = None;
+                    }
+                    AppAction::None
+                }
+            };
+        }
         if self.editing {
             match key.code {
                 KeyCode::Enter => {
@@ -122,21 +441,50 @@ impl BitcoinConfigView {
         } else {
             match key.code {
                 KeyCode::Up => {
-                    if self.selected_index > 0 {
-                        self.selected_index -= 1;
-                    }
+                    self.step(entries, false);
                     self.save_message = None;
+                    self.warning_message = None;
                     AppAction::None
                 }
                 KeyCode::Down => {
-                    if self.selected_index + 1 < entries.len() {
-                        self.selected_index += 1;
-                    }
+                    self.step(entries, true);
                     self.save_message = None;
+                    self.warning_message = None;
+                    AppAction::None
+                }
+                KeyCode::Char('v') => {
+                    self.show_all = !self.show_all;
+                    self.ensure_visible(entries);
+                    AppAction::None
+                }
+                KeyCode::Char('n') => AppAction::ToggleNetworkValues,
+                KeyCode::Char('L') => {
+                    self.show_relations = !self.show_relations;
+                    AppAction::None
+                }
+                KeyCode::Char('N') => AppAction::SwitchNetwork,
+                KeyCode::Char('c') => {
+                    self.customized_only = !self.customized_only;
+                    self.ensure_visible(entries);
                     AppAction::None
                 }
                 KeyCode::Enter => {
-                    if !entries.is_empty() {
+                    let entry = entries.get(self.selected_index);
+                    if let Some(e) = entry.filter(|e| CATEGORY_OPTIONS.contains(&e.key.as_str())) {
+                        self.categories =
+                            Some(CategoryPicker::new(self.selected_index, &e.key, &e.value));
+                        self.save_message = None;
+                    } else if let Some(e) = entry.filter(|e| BIND_OPTIONS.contains(&e.key.as_str()))
+                    {
+                        self.permissions =
+                            Some(PermissionEditor::new(self.selected_index, &e.key, &e.value));
+                        self.save_message = None;
+                    } else if let Some((e, range)) =
+                        entry.and_then(|e| range_for(&e.key).map(|r| (e, r)))
+                    {
+                        self.slider = Some(Slider::new(self.selected_index, &e.value, range));
+                        self.save_message = None;
+                    } else if !entries.is_empty() {
                         self.edit_input
                             .clone_from(&entries[self.selected_index].value);
                         self.editing = true;
@@ -145,6 +493,66 @@ impl BitcoinConfigView {
                     AppAction::None
                 }
                 KeyCode::Char('s') => AppAction::SaveBitcoinConfig,
+                KeyCode::Char('d') => AppAction::ExportDockerCompose,
+                KeyCode::Char('p') => AppAction::ExportReverseProxy,
+                KeyCode::Char('i') => AppAction::ExportConfigManagement,
+                KeyCode::Char('m') => AppAction::ExportMarkdownReport,
+                KeyCode::Char('E') => AppAction::ExportSupportCopy,
+                KeyCode::Char('D') => AppAction::ExportDiagnosticBundle,
+                KeyCode::Char('A') => AppAction::UpdateAsmap,
+                KeyCode::Char('U') => AppAction::UpdateSchemaBundle,
+                KeyCode::Char('Q') => AppAction::ToggleMacroRecording,
+                KeyCode::Char('@') => AppAction::ReplayMacro,
+                KeyCode::Char('k') => AppAction::ToggleNodeFlavor,
+                KeyCode::Char('t') => AppAction::TestBitcoinConfig,
+                KeyCode::Char('T') => AppAction::TestOption(self.selected_index),
+                KeyCode::Char('a') => AppAction::AcceptKeySuggestion(self.selected_index),
+                KeyCode::Char('r') => AppAction::ResetEntry(self.selected_index),
+                KeyCode::Char('f') => AppAction::ToggleFavorite(self.selected_index),
+                KeyCode::Char('x') => AppAction::StoreSecret(self.selected_index),
+                KeyCode::Char('g') => AppAction::GenerateProfiles,
+                KeyCode::Char('G') => AppAction::OpenProfiles,
+                KeyCode::Char('H') => AppAction::ApplyNow(self.selected_index),
+                KeyCode::Char('B') => AppAction::SizePrune,
+                KeyCode::Char('u') => AppAction::RemoveDuplicates,
+                KeyCode::Char('w') => AppAction::OpenRpcWhitelist,
+                KeyCode::Char('O') => AppAction::OpenOnionBackup,
+                KeyCode::Char('M') => {
+                    self.relay_policy = Some(PresetPicker::default());
+                    self.save_message = None;
+                    self.warning_message = None;
+                    AppAction::None
+                }
+                KeyCode::Char('P') => {
+                    self.permissions = entries
+                        .get(self.selected_index)
+                        .filter(|e| {
+                            PERMISSION_OPTIONS.contains(&e.key.as_str())
+                                || BIND_OPTIONS.contains(&e.key.as_str())
+                        })
+                        .map(|e| PermissionEditor::new(self.selected_index, &e.key, &e.value));
+                    AppAction::None
+                }
+                KeyCode::Char('e') => {
+                    self.notify_script = entries
+                        .get(self.selected_index)
+                        .filter(|e| NOTIFY_OPTIONS.contains(&e.key.as_str()))
+                        .map(|e| ScriptPicker {
+                            index: self.selected_index,
+                            key: e.key.clone(),
+                            cursor: 0,
+                        });
+                    AppAction::None
+                }
+                KeyCode::Tab => AppAction::ToggleRawView,
+                KeyCode::Char('R') => {
+                    self.confirm_reset = entries
+                        .get(self.selected_index)
+                        .and_then(|e| e.schema.as_ref())
+                        .map(|s| s.category);
+                    self.save_message = None;
+                    AppAction::None
+                }
                 KeyCode::Esc => {
                     self.sidebar_focused = true;
                     self.save_message = None;
@@ -155,195 +563,1317 @@ impl BitcoinConfigView {
         }
     }
 
-    #[allow(clippy::too_many_lines)] // Renders two panels with multiple layout passes
     pub fn render(f: &mut Frame, app: &mut App, area: Rect) {
-        const FIXED: usize = 33;
-        if app.bitcoin_conf_path.is_none() {
-            let p = Paragraph::new("Press [Enter] to select a bitcoin.conf file").block(
+        let Some(path) = app.bitcoin_conf_path.clone() else {
+            if let Some((broken, errors)) = &app.bitcoin_parse_errors {
+                render_parse_errors(f, area, broken, errors, &app.home_dir);
+                return;
+            }
+            let text = "Press [Enter] to select a bitcoin.conf file, or [i] to import the one \
+                        of an Umbrel, RaspiBlitz or Start9 on this machine";
+            let p = Paragraph::new(text).wrap(Wrap { trim: true }).block(
                 Block::default()
                     .borders(Borders::ALL)
                     .title(" Bitcoin Config "),
             );
             f.render_widget(p, area);
             return;
+        };
+
+        // Editing a file the running node does not use is easy to miss
+        let mismatch = app
+            .bitcoin_diagnostics
+            .iter()
+            .find(|d| d.key == "chain" && d.severity == Severity::Error);
+        let area = if let Some(d) = mismatch {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(1), Constraint::Min(0)])
+                .split(area);
+            f.render_widget(
+                Paragraph::new(format!(" ⚠ {} ", d.message)).style(
+                    Style::default()
+                        .fg(Color::White)
+                        .bg(Color::Red)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                chunks[0],
+            );
+            chunks[1]
+        } else {
+            area
+        };
+        let area = if app.bitcoin_diagnostics.is_empty() {
+            area
+        } else {
+            let height = u16::try_from(app.bitcoin_diagnostics.len().min(5)).unwrap_or(5) + 2;
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(0), Constraint::Length(height)])
+                .split(area);
+            render_diagnostics(f, chunks[1], &app.bitcoin_diagnostics);
+            chunks[0]
+        };
+        let name = format!("{} Configuration", app.settings.node_flavor.label());
+        let view = &mut app.bitcoin_config_view;
+        if let Some(raw) = &mut view.raw {
+            let title = format!(
+                " {}{name} (raw) --- {} ",
+                if view.dirty || raw.modified {
+                    "● "
+                } else {
+                    ""
+                },
+                shorten_path(&path, usize::from(area.width / 2), &app.home_dir)
+            );
+            raw.render(f, area, &title, !view.sidebar_focused);
+            return;
+        }
+
+        if let Some(builder) = &view.rpc_whitelist {
+            render_rpc_whitelist(f, area, builder, view.sidebar_focused);
+            return;
+        }
+        if let Some(editor) = &view.permissions {
+            render_permissions(f, area, editor, view.sidebar_focused);
+            return;
+        }
+        if let Some(backup) = &view.onion_backup {
+            let dir = path.parent().unwrap_or(Path::new("."));
+            render_onion_backup(f, area, backup, dir, view.sidebar_focused);
+            return;
+        }
+        if let Some(editor) = &view.profiles {
+            render_profiles(f, area, editor, &app.bitcoin_data, view.sidebar_focused);
+            return;
+        }
+        if let Some(picker) = &view.relay_policy {
+            render_relay_policy(f, area, picker, &app.bitcoin_data, view.sidebar_focused);
+            return;
+        }
+        if let Some(picker) = &view.categories {
+            render_categories(f, area, picker, view.sidebar_focused);
+            return;
+        }
+        if let Some(picker) = &view.notify_script {
+            let dir = path.parent().unwrap_or(Path::new("."));
+            render_notify_script(f, area, picker, dir, view.sidebar_focused);
+            return;
         }
 
-        let panels = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([Constraint::Percentage(45), Constraint::Percentage(55)])
-            .split(area);
+        let view = &app.bitcoin_config_view;
+        let rows = view.rows(&app.bitcoin_data);
+        let name = if view.customized_only {
+            format!("{name}, {} customized", rows.len())
+        } else {
+            name
+        };
+        let selected = app.bitcoin_data.get(view.selected_index);
+        let suggestion = selected
+            .filter(|e| e.schema.is_none())
+            .and_then(|e| suggest_key(&e.key, app.settings.node_flavor))
+            .map(|s| format!("Unknown option. Did you mean {s}? Press [a] to rename it."));
+        let help = suggestion
+            .as_deref()
+            .or_else(|| selected.and_then(|e| app.option_help.get(&e.key)));
+        render_entry_panels(
+            f,
+            area,
+            &EntryPanel {
+                name: &name,
+                path: &path,
+                home: &app.home_dir,
+                entries: &app.bitcoin_data,
+                selected_index: view.selected_index,
+                editing: view.editing,
+                edit_input: &view.edit_input,
+                help,
+                preview: selected.and_then(|e| {
+                    let value = if view.editing {
+                        view.edit_input.as_str()
+                    } else {
+                        e.value.as_str()
+                    };
+                    expansion_preview(e)
+                        .or_else(|| unit_preview(e, value))
+                        .or_else(|| fee_preview(e, value))
+                        .or_else(|| negation_preview(e))
+                        .or_else(|| index_preview(e, value, active_network(&app.bitcoin_data)))
+                }),
+                rows: Some(&rows),
+                pinned: &view.favorites,
+                details: match (selected, &view.network_lines, &view.peer_checks) {
+                    (Some(e), _, _) if view.show_relations => {
+                        Some(("Relations", relation_lines(&app.bitcoin_data, &e.key)))
+                    }
+                    (Some(e), Some(lines), _) => {
+                        Some(("Per network", network_values(e, lines, view.dirty)))
+                    }
+                    (Some(e), None, Some((key, checks))) if *key == e.key => {
+                        Some(("Peer test", peer_check_lines(checks)))
+                    }
+                    (Some(e), None, _) => view
+                        .cookie_notes
+                        .as_ref()
+                        .filter(|(key, _)| *key == e.key)
+                        .map(|(_, notes)| ("Cookie", cookie_note_lines(notes))),
+                    _ => None,
+                },
+                value_line: view.slider.as_ref().map(|slider| {
+                    let unit = selected
+                        .and_then(|e| e.schema.as_ref())
+                        .and_then(|s| s.unit);
+                    slider_line(slider, unit)
+                }),
+                raw_line: selected.map(|e| match (e.enabled, &e.section) {
+                    (false, _) => "(not in the file)".to_string(),
+                    (true, Some(section)) => format!("[{section}] {}", config_line(e)),
+                    (true, None) => config_line(e),
+                }),
+                diagnostics: Some(&app.bitcoin_diagnostics),
+                sidebar_focused: view.sidebar_focused,
+                dirty: view.dirty,
+            },
+        );
+    }
+}
 
-        // Left panel: scrollable entry list
-        let items: Vec<ListItem> = app
-            .bitcoin_data
-            .iter()
-            .map(|entry| {
-                let label = entry.schema.as_ref().map_or("", |s| s.description.as_str());
+/// The catalog of RPC methods with those picked for the selected user
+/// (left), and the users and the lines that will be written (right).
+fn render_rpc_whitelist(f: &mut Frame, area: Rect, builder: &WhitelistBuilder, dimmed: bool) {
+    let panels = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(45), Constraint::Percentage(55)])
+        .split(area);
+    let panel_style = if dimmed {
+        Style::default().fg(Color::DarkGray)
+    } else {
+        Style::default()
+    };
 
-                let (value_display, value_style) = if entry.enabled {
-                    (
-                        entry.value.clone(),
+    let methods = builder.methods();
+    let items: Vec<ListItem> = catalog_rows()
+        .into_iter()
+        .map(|row| match row {
+            CatalogRow::Category(name, all) => {
+                let picked = all.iter().filter(|m| methods.contains(**m)).count();
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        name,
                         Style::default()
-                            .fg(Color::White)
+                            .fg(Color::Cyan)
                             .add_modifier(Modifier::BOLD),
-                    )
+                    ),
+                    Span::styled(
+                        format!("  {picked}/{}", all.len()),
+                        Style::default().fg(Color::Gray),
+                    ),
+                ]))
+            }
+            CatalogRow::Method(method) => {
+                let (mark, style) = if methods.contains(method) {
+                    ("[x] ", Style::default().fg(Color::Green))
                 } else {
-                    let placeholder = entry
-                        .schema
-                        .as_ref()
-                        .filter(|s| !s.default.is_empty())
-                        .map_or_else(
-                            || "not set".to_string(),
-                            |s| format!("default: {}", s.default),
-                        );
-                    (
-                        format!("({placeholder})"),
-                        Style::default().fg(Color::DarkGray),
-                    )
+                    ("[ ] ", Style::default().fg(Color::White))
                 };
+                ListItem::new(Line::from(Span::styled(format!("  {mark}{method}"), style)))
+            }
+        })
+        .collect();
+    let mut state = ListState::default();
+    state.select(Some(builder.cursor));
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" RPC methods ")
+                .border_style(panel_style),
+        )
+        .highlight_style(Style::default().bg(Color::DarkGray));
+    f.render_stateful_widget(list, panels[0], &mut state);
+
+    let mut users: Vec<Span> = vec![Span::styled("Users: ", Style::default().fg(Color::Gray))];
+    for (i, (user, _)) in builder.users.iter().enumerate() {
+        let style = if i == builder.user {
+            Style::default()
+                .fg(Color::Black)
+                .bg(Color::Cyan)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::Cyan)
+        };
+        users.push(Span::styled(format!(" {user} "), style));
+        users.push(Span::raw(" "));
+    }
+    let mut lines = vec![Line::from(users), Line::from("")];
+    let generated = builder.lines();
+    if generated.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "No methods picked; every user may call any method",
+            Style::default().fg(Color::DarkGray),
+        )));
+    } else {
+        lines.extend(
+            generated
+                .into_iter()
+                .map(|l| Line::from(Span::styled(l, Style::default().fg(Color::White)))),
+        );
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "Users left without a whitelist may call nothing while rpcwhitelistdefault is 1, \
+             its default once any whitelist is set.",
+            Style::default().fg(Color::Yellow),
+        )));
+    }
+    let p = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(" rpcwhitelist ")
+            .border_style(panel_style),
+    );
+    f.render_widget(p, panels[1]);
+}
 
-                ListItem::new(vec![
-                    Line::from(Span::styled(label, Style::default().fg(Color::Gray))),
-                    Line::from(vec![
-                        Span::styled(
-                            format!("{} = ", entry.key),
-                            Style::default().fg(Color::Cyan),
-                        ),
-                        Span::styled(value_display, value_style),
-                    ]),
-                ])
-            })
-            .collect();
-
-        let mut list_state = ListState::default();
-        list_state.select(Some(app.bitcoin_config_view.selected_index));
+/// The permission checklist, address, port and onion tag of a `bind`,
+/// `whitebind` or `whitelist` entry (left), and the value it makes with
+/// what is wrong with it (right).
+fn render_permissions(f: &mut Frame, area: Rect, editor: &PermissionEditor, dimmed: bool) {
+    let panels = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(45), Constraint::Percentage(55)])
+        .split(area);
+    let panel_style = if dimmed {
+        Style::default().fg(Color::DarkGray)
+    } else {
+        Style::default()
+    };
 
-        // Border style: dim both panels when the user is navigating the main sidebar
-        let panel_style = if app.bitcoin_config_view.sidebar_focused {
-            Style::default().fg(Color::DarkGray)
+    let checkbox = |label: &str, description: &str, on: bool| {
+        let (mark, style) = if on {
+            ("[x] ", Style::default().fg(Color::Green))
         } else {
-            Style::default()
+            ("[ ] ", Style::default().fg(Color::White))
         };
+        ListItem::new(vec![
+            Line::from(Span::styled(format!("{mark}{label}"), style)),
+            Line::from(Span::styled(
+                format!("    {description}"),
+                Style::default().fg(Color::Gray),
+            )),
+        ])
+    };
+    let field = |label: &str, text: &str| {
+        ListItem::new(vec![
+            Line::from(Span::styled(
+                label.to_string(),
+                Style::default().fg(Color::Cyan),
+            )),
+            Line::from(Span::styled(
+                format!("    {text}"),
+                Style::default()
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD),
+            )),
+        ])
+    };
+    let items: Vec<ListItem> = editor
+        .rows()
+        .into_iter()
+        .map(|row| match row {
+            EditorRow::Flag(i) => {
+                let (flag, description) = PERMISSION_FLAGS[i];
+                checkbox(flag, description, editor.flags.iter().any(|f| f == flag))
+            }
+            EditorRow::Address if editor.is_bind() => field("Host", &editor.address),
+            EditorRow::Address => field("Address", &editor.address),
+            EditorRow::Port if editor.port.is_empty() && editor.key == "bind" => {
+                field("Port", "(network default)")
+            }
+            EditorRow::Port => field("Port", &editor.port),
+            EditorRow::Onion => checkbox(
+                "onion",
+                "Tag connections here as incoming Tor",
+                editor.onion,
+            ),
+        })
+        .collect();
+    let mut state = ListState::default();
+    state.select(Some(editor.cursor));
+    let title = if editor.key == "bind" {
+        " bind address ".to_string()
+    } else {
+        format!(" {} permissions ", editor.key)
+    };
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .border_style(panel_style),
+        )
+        .highlight_style(Style::default().bg(Color::DarkGray));
+    f.render_stateful_widget(list, panels[0], &mut state);
+
+    let address_help = match editor.key.as_str() {
+        "bind" => "Local IP to listen on, e.g. 0.0.0.0 or ::, with [host]:port written for IPv6",
+        "whitebind" => "Local IP and port to listen on, e.g. 0.0.0.0 and 8335",
+        _ => "Peer address or network, e.g. 192.168.1.0/24",
+    };
+    let mut lines = vec![
+        Line::from(Span::styled(
+            format!("{}={}", editor.key, editor.value()),
+            Style::default().fg(Color::White),
+        )),
+        Line::from(""),
+        Line::from(Span::styled(address_help, Style::default().fg(Color::Gray))),
+    ];
+    for problem in editor.problems() {
+        lines.push(Line::from(Span::styled(
+            format!("✗ {problem}"),
+            Style::default().fg(Color::Red),
+        )));
+    }
+    if editor.flags.is_empty() && editor.key != "bind" {
+        lines.push(Line::from(Span::styled(
+            "No flags picked: bitcoind grants noban and mempool, relay unless \
+             whitelistrelay=0, and forcerelay with whitelistforcerelay=1.",
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    let p = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(" Value ")
+            .border_style(panel_style),
+    );
+    f.render_widget(p, panels[1]);
+}
+
+/// What the onion key is, where it is copied to, and the passphrase
+/// typed so far, masked.
+fn render_onion_backup(f: &mut Frame, area: Rect, backup: &OnionBackup, dir: &Path, dimmed: bool) {
+    let panel_style = if dimmed {
+        Style::default().fg(Color::DarkGray)
+    } else {
+        Style::default()
+    };
+    let label = Style::default().fg(Color::Cyan);
+    let passphrase = if backup.passphrase.is_empty() {
+        Span::styled("(empty: plain copy)", Style::default().fg(Color::DarkGray))
+    } else {
+        Span::styled(
+            "*".repeat(backup.passphrase.chars().count()),
+            Style::default().fg(Color::White),
+        )
+    };
+    let lines = vec![
+        Line::from(vec![
+            Span::styled("Key:         ", label),
+            Span::raw(backup.key.display().to_string()),
+        ]),
+        Line::from(vec![
+            Span::styled("Backup into: ", label),
+            Span::raw(dir.display().to_string()),
+        ]),
+        Line::from(""),
+        Line::from(Span::styled(
+            ONION_KEY_NOTE,
+            Style::default().fg(Color::Gray),
+        )),
+        Line::from(""),
+        Line::from(vec![Span::styled("Passphrase:  ", label), passphrase]),
+        Line::from(Span::styled(
+            "Type one to encrypt the copy with gpg; restore it with gpg --decrypt.",
+            Style::default().fg(Color::DarkGray),
+        )),
+    ];
+    let p = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(" Back up onion service key ")
+            .border_style(panel_style),
+    );
+    f.render_widget(p, area);
+}
+
+/// The category checklist of a `debug` or `debugexclude` entry (left),
+/// and the value it makes (right).
+fn render_categories(f: &mut Frame, area: Rect, picker: &CategoryPicker, dimmed: bool) {
+    let panels = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(45), Constraint::Percentage(55)])
+        .split(area);
+    let panel_style = if dimmed {
+        Style::default().fg(Color::DarkGray)
+    } else {
+        Style::default()
+    };
 
-        let dirty = app.bitcoin_config_view.dirty;
-        let path_max = (panels[0].width as usize).saturating_sub(FIXED);
-        let title = match &app.bitcoin_conf_path {
-            Some(path) => format!(
-                " {}Bitcoin Configuration --- {} ",
-                if dirty { "● " } else { "" },
-                shorten_path(path, path_max, &app.home_dir)
+    let items: Vec<ListItem> = DEBUG_CATEGORIES
+        .iter()
+        .map(|(category, description)| {
+            let (mark, style) = if picker.is_picked(category) {
+                ("[x] ", Style::default().fg(Color::Green))
+            } else {
+                ("[ ] ", Style::default().fg(Color::White))
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{mark}{category:<17}"), style),
+                Span::styled(*description, Style::default().fg(Color::Gray)),
+            ]))
+        })
+        .collect();
+    let mut state = ListState::default();
+    state.select(Some(picker.cursor));
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(" {} categories ", picker.key))
+                .border_style(panel_style),
+        )
+        .highlight_style(Style::default().bg(Color::DarkGray));
+    f.render_stateful_widget(list, panels[0], &mut state);
+
+    let help = if picker.key == "debug" {
+        "Categories logged to debug.log beyond the defaults."
+    } else {
+        "Categories left out, when debug picks all."
+    };
+    let p = Paragraph::new(vec![
+        Line::from(Span::styled(
+            format!("{}={}", picker.key, picker.value()),
+            Style::default().fg(Color::White),
+        )),
+        Line::from(""),
+        Line::from(Span::styled(help, Style::default().fg(Color::Gray))),
+    ])
+    .wrap(Wrap { trim: false })
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(" Value ")
+            .border_style(panel_style),
+    );
+    f.render_widget(p, panels[1]);
+}
+
+/// The profiles (left) and the options and vars of the one shown, marked
+/// as set there, overriding an ancestor or inherited (right).
+fn render_profiles(
+    f: &mut Frame,
+    area: Rect,
+    editor: &ProfileEditor,
+    entries: &[ConfigEntry],
+    dimmed: bool,
+) {
+    let panels = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(30), Constraint::Percentage(70)])
+        .split(area);
+    let panel_style = if dimmed {
+        Style::default().fg(Color::DarkGray)
+    } else {
+        Style::default()
+    };
+
+    let items: Vec<ListItem> = editor
+        .profiles
+        .iter()
+        .map(|profile| {
+            let mut spans = vec![Span::styled(
+                profile.name.clone(),
+                Style::default().fg(Color::White),
+            )];
+            if let Some(parent) = &profile.inherits {
+                spans.push(Span::styled(
+                    format!(" ← {parent}"),
+                    Style::default().fg(Color::Gray),
+                ));
+            }
+            if profile.base {
+                spans.push(Span::styled(
+                    " (base)",
+                    Style::default().fg(Color::DarkGray),
+                ));
+            }
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+    let mut state = ListState::default();
+    state.select(Some(editor.selected));
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Profiles ")
+                .border_style(panel_style),
+        )
+        .highlight_style(Style::default().bg(Color::DarkGray));
+    f.render_stateful_widget(list, panels[0], &mut state);
+
+    let title = format!(" {} ", editor.profile().map_or("", |p| p.name.as_str()));
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .border_style(panel_style);
+    let rows = match editor.rows() {
+        Ok(rows) => rows,
+        Err(e) => {
+            let p = Paragraph::new(Span::styled(e.to_string(), Style::default().fg(Color::Red)))
+                .wrap(Wrap { trim: false })
+                .block(block);
+            f.render_widget(p, panels[1]);
+            return;
+        }
+    };
+    let name = |row: &ProfileRow| match row.table {
+        ProfileTable::Options => row.key.clone(),
+        ProfileTable::Vars => format!("${{{}}}", row.key),
+    };
+    let name_width = rows.iter().map(|r| name(r).width()).max().unwrap_or(0);
+    let value_width = rows
+        .iter()
+        .map(|r| r.value.value.width())
+        .max()
+        .unwrap_or(0);
+    let items: Vec<ListItem> = rows
+        .iter()
+        .map(|row| {
+            // An option nothing above the profile sets replaces bitcoin.conf's value
+            let replaced = row.value.overrides.clone().or_else(|| {
+                (row.table == ProfileTable::Options)
+                    .then(|| enabled_value(entries, &row.key).map(str::to_string))
+                    .flatten()
+            });
+            let (origin, color) = match (&row.value.origin, replaced) {
+                (Origin::Inherited(from), _) => (format!("from {from}"), Color::Gray),
+                (Origin::Own, Some(old)) => (format!("overrides {old}"), Color::Yellow),
+                (Origin::Own, None) => ("set here".to_string(), Color::Green),
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(
+                    format!("{:<name_width$}  ", name(row)),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(
+                    format!("{:<value_width$}  ", row.value.value),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(origin, Style::default().fg(color)),
+            ]))
+        })
+        .collect();
+    let mut state = ListState::default();
+    state.select((!rows.is_empty()).then_some(editor.cursor));
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(Style::default().bg(Color::DarkGray));
+    f.render_stateful_widget(list, panels[1], &mut state);
+}
+
+/// The relay-policy presets (left) and what the one under the cursor
+/// changes, option by option, with the trade-off of each (right).
+fn render_relay_policy(
+    f: &mut Frame,
+    area: Rect,
+    picker: &PresetPicker,
+    entries: &[ConfigEntry],
+    dimmed: bool,
+) {
+    let panels = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(35), Constraint::Percentage(65)])
+        .split(area);
+    let panel_style = if dimmed {
+        Style::default().fg(Color::DarkGray)
+    } else {
+        Style::default()
+    };
+
+    let items: Vec<ListItem> = PolicyPreset::ALL
+        .iter()
+        .map(|preset| {
+            ListItem::new(vec![
+                Line::from(Span::styled(
+                    preset.label(),
+                    Style::default().fg(Color::White),
+                )),
+                Line::from(Span::styled(
+                    format!("  {}", preset.description()),
+                    Style::default().fg(Color::Gray),
+                )),
+            ])
+        })
+        .collect();
+    let mut state = ListState::default();
+    state.select(Some(picker.cursor));
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Relay policy presets ")
+                .border_style(panel_style),
+        )
+        .highlight_style(Style::default().bg(Color::DarkGray));
+    f.render_stateful_widget(list, panels[0], &mut state);
+
+    let preset = picker.preset();
+    let mut lines = Vec::new();
+    for option in POLICY_OPTIONS {
+        let current = enabled_value(entries, option.key).unwrap_or("default");
+        let new = preset.value(option).unwrap_or("default");
+        let color = if current == new {
+            Color::Gray
+        } else {
+            Color::Yellow
+        };
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("{:<19}", option.key),
+                Style::default().fg(Color::White),
             ),
-            None => " Bitcoin Configuration ".to_string(),
+            Span::styled(format!("{current} → {new}"), Style::default().fg(color)),
+        ]));
+        lines.push(Line::from(Span::styled(
+            option.tradeoff,
+            Style::default().fg(Color::Gray),
+        )));
+        lines.push(Line::from(""));
+    }
+    let p = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!(" {} ", preset.label()))
+            .border_style(panel_style),
+    );
+    f.render_widget(p, panels[1]);
+}
+
+/// The notify script kinds (left) and the script the one under the
+/// cursor would write into `dir` (right).
+fn render_notify_script(
+    f: &mut Frame,
+    area: Rect,
+    picker: &ScriptPicker,
+    dir: &Path,
+    dimmed: bool,
+) {
+    let panels = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+        .split(area);
+    let panel_style = if dimmed {
+        Style::default().fg(Color::DarkGray)
+    } else {
+        Style::default()
+    };
+
+    let items: Vec<ListItem> = ScriptKind::ALL
+        .iter()
+        .map(|kind| {
+            ListItem::new(vec![
+                Line::from(Span::styled(
+                    kind.label(),
+                    Style::default().fg(Color::White),
+                )),
+                Line::from(Span::styled(
+                    format!("  {}", kind.description()),
+                    Style::default().fg(Color::Gray),
+                )),
+            ])
+        })
+        .collect();
+    let mut state = ListState::default();
+    state.select(Some(picker.cursor));
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(" {} script ", picker.key))
+                .border_style(panel_style),
+        )
+        .highlight_style(Style::default().bg(Color::DarkGray));
+    f.render_stateful_widget(list, panels[0], &mut state);
+
+    let kind = picker.kind();
+    let path = dir.join(script_file(&picker.key, kind));
+    let p = Paragraph::new(template(&picker.key, kind, &path))
+        .style(Style::default().fg(Color::White))
+        .wrap(Wrap { trim: false })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(" {} ", script_file(&picker.key, kind)))
+                .border_style(panel_style),
+        );
+    f.render_widget(p, panels[1]);
+}
+
+/// One line per cookie finding, coloured by how serious it is.
+fn cookie_note_lines(notes: &[CookieNote]) -> Vec<Line<'static>> {
+    notes
+        .iter()
+        .map(|note| {
+            let color = match note.severity {
+                None => Color::Gray,
+                Some(Severity::Warning) => Color::Yellow,
+                Some(Severity::Error) => Color::Red,
+            };
+            Line::from(Span::styled(note.text.clone(), Style::default().fg(color)))
+        })
+        .collect()
+}
+
+/// What the option `key` depends on and which options bear on it, each
+/// colored by whether the relation holds in `entries`.
+fn relation_lines(entries: &[ConfigEntry], key: &str) -> Vec<Line<'static>> {
+    let own = entries
+        .iter()
+        .find(|e| e.key == key)
+        .and_then(|e| e.schema.as_ref())
+        .map(|s| s.relations.clone())
+        .unwrap_or_default();
+    let line = |from: &str, text: String, holds: Option<bool>| {
+        let (mark, color) = match holds {
+            None => ("·", Color::DarkGray),
+            Some(true) => ("✓", Color::Green),
+            Some(false) => ("✗", Color::Red),
         };
-        let title_style = if dirty {
-            Style::default().fg(Color::Yellow)
+        Line::from(vec![
+            Span::styled(format!("{mark} "), Style::default().fg(color)),
+            Span::styled(format!("{from} "), Style::default().fg(Color::Cyan)),
+            Span::styled(text, Style::default().fg(color)),
+        ])
+    };
+    let mut out: Vec<Line<'static>> = own
+        .iter()
+        .map(|r| line(key, r.to_string(), relation_holds(entries, key, r)))
+        .collect();
+    out.extend(
+        relations_to(key)
+            .iter()
+            .map(|(from, r)| line(from, r.to_string(), relation_holds(entries, from, r))),
+    );
+    if out.is_empty() {
+        out.push(Line::from(Span::styled(
+            format!("{key} does not depend on or affect other options"),
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    out
+}
+
+/// One line per tested peer: its latency, or why it cannot be reached.
+fn peer_check_lines(checks: &[PeerCheck]) -> Vec<Line<'static>> {
+    checks
+        .iter()
+        .map(|check| {
+            let (text, color) = match &check.status {
+                PeerStatus::Reachable(latency) => (
+                    format!("reachable, {} ms", latency.as_millis()),
+                    Color::Green,
+                ),
+                PeerStatus::Skipped(reason) => (reason.clone(), Color::DarkGray),
+                PeerStatus::Failed(reason) => (reason.clone(), Color::Red),
+            };
+            Line::from(vec![
+                Span::styled(
+                    format!("{}  ", check.value),
+                    Style::default().fg(Color::Cyan),
+                ),
+                Span::styled(text, Style::default().fg(color)),
+            ])
+        })
+        .collect()
+}
+
+/// What bitcoind uses for `entry` on each chain, given the saved `lines`,
+/// followed by the lines it ignores and why.
+fn network_values(entry: &ConfigEntry, lines: &[Assignment], dirty: bool) -> Vec<Line<'static>> {
+    let default = entry.schema.as_ref().map_or("", |s| s.default.as_str());
+    let mut out = Vec::new();
+    let mut notes: Vec<(usize, String)> = Vec::new();
+    for chain in CHAINS {
+        let resolution = resolve_for_chain(lines, &entry.key, chain);
+        let value = if resolution.values.is_empty() {
+            let default = chain_default(&entry.key, chain, default);
+            Span::styled(
+                if default.is_empty() {
+                    "not set".to_string()
+                } else {
+                    format!("{default} (default)")
+                },
+                Style::default().fg(Color::DarkGray),
+            )
         } else {
-            Style::default()
+            let values: Vec<String> = resolution
+                .values
+                .iter()
+                .map(|(value, line)| {
+                    if is_secret(&entry.key) {
+                        format!("(hidden), line {line}")
+                    } else {
+                        format!("{value}, line {line}")
+                    }
+                })
+                .collect();
+            Span::styled(values.join("; "), Style::default().fg(Color::White))
         };
+        out.push(Line::from(vec![
+            Span::styled(format!("{chain:<9}"), Style::default().fg(Color::Cyan)),
+            value,
+        ]));
+        for ignored in resolution.ignored {
+            let note = (ignored.line, ignored.reason);
+            if !notes.contains(&note) {
+                notes.push(note);
+            }
+        }
+    }
+    notes.sort();
+    for (line, reason) in notes {
+        out.push(Line::from(Span::styled(
+            format!("line {line} ignored: {reason}"),
+            Style::default().fg(Color::Yellow),
+        )));
+    }
+    if dirty {
+        out.push(Line::from(Span::styled(
+            "As saved; unsaved edits are not included",
+            Style::default().fg(Color::Gray),
+        )));
+    }
+    out
+}
 
-        let list = List::new(items)
-            .block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .title(title)
-                    .title_style(title_style)
-                    .border_style(panel_style),
-            )
-            .highlight_style(Style::default().bg(Color::DarkGray));
+/// The slider's bar between its bounds, with the value picked.
+fn slider_line(slider: &Slider, unit: Option<Unit>) -> Line<'static> {
+    let IntRange { min, max, .. } = slider.range;
+    Line::from(vec![
+        Span::styled(format!("{min} ◀ "), Style::default().fg(Color::DarkGray)),
+        Span::styled(slider.bar(), Style::default().fg(Color::Yellow)),
+        Span::styled(format!(" ▶ {max}  "), Style::default().fg(Color::DarkGray)),
+        Span::styled(
+            with_unit(&slider.value(), unit),
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        ),
+    ])
+}
+
+/// What human input such as `2g` or `1 week` is saved as.
+fn unit_preview(entry: &ConfigEntry, value: &str) -> Option<Span<'static>> {
+    let unit = entry.schema.as_ref()?.unit?;
+    convert(value, unit).map(|n| {
+        Span::styled(
+            format!("Saves as {}", with_unit(&n, Some(unit))),
+            Style::default().fg(Color::Green),
+        )
+    })
+}
 
-        f.render_stateful_widget(list, panels[0], &mut list_state);
+/// `value` of a fee option in satoshi units, e.g. `= 20 sat/vB`.
+fn fee_preview(entry: &ConfigEntry, value: &str) -> Option<Span<'static>> {
+    if !entry.enabled && value == entry.value {
+        return None;
+    }
+    fee_equivalent(&entry.key, value)
+        .map(|sats| Span::styled(format!("= {sats}"), Style::default().fg(Color::Green)))
+}
 
-        // Right panel: detail and edit field
-        let right_block = Block::default()
+/// Lists the syntax errors that kept `path` from loading.
+fn render_parse_errors(f: &mut Frame, area: Rect, path: &Path, errors: &[ParseError], home: &str) {
+    let mut lines = vec![
+        Line::from(Span::styled(
+            shorten_path(path, usize::from(area.width.saturating_sub(4)), home),
+            Style::default().fg(Color::Cyan),
+        )),
+        Line::from("was not loaded, so PDM cannot save over lines it does not understand."),
+        Line::from(""),
+    ];
+    lines.extend(errors.iter().map(|e| {
+        Line::from(vec![
+            Span::styled(
+                format!("line {}: ", e.line),
+                Style::default().fg(Color::Red),
+            ),
+            Span::raw(e.message.as_str()),
+        ])
+    }));
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "Fix the file in a text editor, then press [Enter] to select it again.",
+        Style::default().fg(Color::Gray),
+    )));
+    let p = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::default()
             .borders(Borders::ALL)
-            .title(" Detail ")
-            .border_style(panel_style);
-        let inner = right_block.inner(panels[1]);
-        f.render_widget(right_block, panels[1]);
-
-        let selected_entry = app.bitcoin_data.get(app.bitcoin_config_view.selected_index);
-        let editing = app.bitcoin_config_view.editing;
-        let edit_input = app.bitcoin_config_view.edit_input.clone();
-
-        if let Some(entry) = selected_entry {
-            let description = entry
-                .schema
-                .as_ref()
-                .map_or("Unknown option", |s| s.description.as_str());
-            let type_label = entry
-                .schema
-                .as_ref()
-                .map(|s| format!("{}", s.config_type))
-                .unwrap_or_default();
-
-            let rows = Layout::default()
-                .direction(Direction::Vertical)
-                .constraints([
-                    Constraint::Length(2), // description
-                    Constraint::Length(1), // type
-                    Constraint::Length(1), // spacer
-                    Constraint::Length(1), // "Value:" label
-                    Constraint::Length(3), // value / input box
-                    Constraint::Min(0),
-                ])
-                .split(inner);
+            .border_style(Style::default().fg(Color::Red))
+            .title(format!(" Syntax errors ({}) ", errors.len())),
+    );
+    f.render_widget(p, area);
+}
 
-            f.render_widget(
-                Paragraph::new(description).style(Style::default().fg(Color::White)),
-                rows[0],
-            );
-            f.render_widget(
-                Paragraph::new(format!("Type: {type_label}"))
-                    .style(Style::default().fg(Color::Gray)),
-                rows[1],
-            );
-            f.render_widget(
-                Paragraph::new("Value:").style(Style::default().fg(Color::Gray)),
-                rows[3],
+/// How a `-nooption` entry is written back, e.g. `Written as nolisten=1`.
+fn negation_preview(entry: &ConfigEntry) -> Option<Span<'static>> {
+    (entry.enabled && entry.negated).then(|| {
+        Span::styled(
+            format!("Written as {}", config_line(entry)),
+            Style::default().fg(Color::Gray),
+        )
+    })
+}
+
+/// Disk and time an index needs, while `value` turns it on.
+fn index_preview(entry: &ConfigEntry, value: &str, chain: &str) -> Option<Span<'static>> {
+    let index = index_for(&entry.key)?;
+    enables(value).then(|| Span::styled(estimate(index, chain), Style::default().fg(Color::Gray)))
+}
+
+/// Lists the problems found in the loaded config, errors first.
+fn render_diagnostics(f: &mut Frame, area: Rect, diagnostics: &[Diagnostic]) {
+    let lines: Vec<Line> = diagnostics
+        .iter()
+        .map(|d| {
+            let (label, color) = match d.severity {
+                Severity::Error => ("error", Color::Red),
+                Severity::Warning => ("warning", Color::Yellow),
+            };
+            Line::from(vec![
+                Span::styled(format!("{label}: "), Style::default().fg(color)),
+                Span::styled(format!("{}: ", d.key), Style::default().fg(Color::Cyan)),
+                Span::raw(d.message.as_str()),
+            ])
+        })
+        .collect();
+    let p = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!(" Diagnostics ({}) ", diagnostics.len())),
+    );
+    f.render_widget(p, area);
+}
+
+/// What a `${VAR}` placeholder in `entry` expands to when saved, if it has one.
+fn expansion_preview(entry: &ConfigEntry) -> Option<Span<'static>> {
+    if !entry.enabled || !has_placeholders(&entry.value) {
+        return None;
+    }
+    Some(match expand_env(&entry.value) {
+        Ok(_) if is_secret(&entry.key) => {
+            Span::styled("Expands when saved", Style::default().fg(Color::Green))
+        }
+        Ok(value) => Span::styled(
+            format!("Expands to: {value}"),
+            Style::default().fg(Color::Green),
+        ),
+        Err(e) => Span::styled(
+            format!("Cannot expand: {e}"),
+            Style::default().fg(Color::Yellow),
+        ),
+    })
+}
+
+/// Everything needed to draw a `ConfigEntry` list with its detail panel.
+/// Shared by every screen that edits a schema-backed config file.
+pub struct EntryPanel<'a> {
+    /// Title shown on the list panel, e.g. "Bitcoin Core Configuration".
+    pub name: &'a str,
+    pub path: &'a Path,
+    pub home: &'a str,
+    pub entries: &'a [ConfigEntry],
+    pub selected_index: usize,
+    pub editing: bool,
+    pub edit_input: &'a str,
+    /// Long-form help for the selected entry, shown under its value
+    pub help: Option<&'a str>,
+    /// Note on the selected entry's value, such as its placeholder expansion
+    /// or fee in sat/vB, shown above it
+    pub preview: Option<Span<'a>>,
+    /// Indices of `entries` to list, in order; all entries when `None`
+    pub rows: Option<&'a [usize]>,
+    /// Keys marked with a star in the list
+    pub pinned: &'a [String],
+    /// Titled lines about the selected entry, such as its per-network
+    /// values, shown instead of the help
+    pub details: Option<(&'a str, Vec<Line<'a>>)>,
+    /// Drawn in the value box in place of the value, such as a slider
+    pub value_line: Option<Line<'a>>,
+    /// The selected entry as written to the file, when the screen knows
+    pub raw_line: Option<String>,
+    /// Problems found in the config, of which those about the selected
+    /// entry are shown; `None` on screens that do not validate
+    pub diagnostics: Option<&'a [Diagnostic]>,
+    pub sidebar_focused: bool,
+    pub dirty: bool,
+}
+
+/// Returns `(display_string, style)` for an entry value, falling back to the
+/// schema default (or "not set") for disabled entries.
+fn entry_display(entry: &ConfigEntry) -> (String, Style) {
+    let unit = entry.schema.as_ref().and_then(|s| s.unit);
+    if entry.enabled {
+        (
+            with_unit(&entry.value, unit),
+            Style::default()
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD),
+        )
+    } else {
+        let placeholder = entry
+            .schema
+            .as_ref()
+            .filter(|s| !s.default.is_empty())
+            .map_or_else(
+                || "not set".to_string(),
+                |s| format!("default: {}", with_unit(&s.default, unit)),
             );
+        (
+            format!("({placeholder})"),
+            Style::default().fg(Color::DarkGray),
+        )
+    }
+}
+
+/// Type, default, file line and validation status of `entry`, one line
+/// each, for the detail panel.
+fn entry_facts<'a>(entry: &ConfigEntry, panel: &EntryPanel<'a>) -> Vec<Line<'a>> {
+    let gray = Style::default().fg(Color::Gray);
+    let mut facts = Vec::new();
+    if let Some(schema) = &entry.schema {
+        let default = if schema.default.is_empty() {
+            "none"
+        } else {
+            schema.default.as_str()
+        };
+        let unit = schema
+            .unit
+            .map(|u| format!(" ({})", u.label()))
+            .unwrap_or_default();
+        facts.push(Line::styled(
+            format!(
+                "Type: {}{unit} · Default: {}",
+                schema.config_type,
+                with_unit(default, schema.unit)
+            ),
+            gray,
+        ));
+        if let Some(example) = &schema.example {
+            facts.push(Line::from(vec![
+                Span::styled("Example: ", gray),
+                Span::raw(format!("{}={example}", entry.key)),
+            ]));
+        }
+    } else {
+        facts.push(Line::styled("Type: unknown", gray));
+    }
+    if let Some(line) = &panel.raw_line {
+        facts.push(Line::from(vec![
+            Span::styled("Line: ", gray),
+            Span::styled(line.clone(), Style::default().fg(Color::Cyan)),
+        ]));
+    }
+    if let Some(diagnostics) = panel.diagnostics {
+        let mut found = diagnostics.iter().filter(|d| d.key == entry.key).peekable();
+        if found.peek().is_none() {
+            let (text, color) = if entry.schema.is_some() {
+                ("✓ No problems found", Color::Green)
+            } else {
+                ("? Not checked: unknown option", Color::DarkGray)
+            };
+            facts.push(Line::styled(text, Style::default().fg(color)));
+        }
+        for d in found {
+            let (mark, color) = match d.severity {
+                Severity::Error => ("✗", Color::Red),
+                Severity::Warning => ("⚠", Color::Yellow),
+            };
+            facts.push(Line::styled(
+                format!("{mark} {}", d.message),
+                Style::default().fg(color),
+            ));
+        }
+    }
+    facts
+}
+
+/// Renders the entry list (left) and the detail/edit panel (right).
+pub fn render_entry_panels(f: &mut Frame, area: Rect, panel: &EntryPanel) {
+    // Columns taken by the fixed part of the title: name, markers and borders
+    let fixed = panel.name.width() + 12;
+
+    let panels = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(45), Constraint::Percentage(55)])
+        .split(area);
+
+    // Left panel: scrollable entry list
+    let rows: Vec<usize> = panel
+        .rows
+        .map_or_else(|| (0..panel.entries.len()).collect(), <[usize]>::to_vec);
+    let items: Vec<ListItem> = rows
+        .iter()
+        .filter_map(|&i| panel.entries.get(i))
+        .map(|entry| {
+            let label = entry.schema.as_ref().map_or("", |s| s.description.as_str());
+            let (value_display, value_style) = entry_display(entry);
+
+            let star = if panel.pinned.contains(&entry.key) {
+                "★ "
+            } else {
+                ""
+            };
+
+            ListItem::new(vec![
+                Line::from(Span::styled(label, Style::default().fg(Color::Gray))),
+                Line::from(vec![
+                    Span::styled(star, Style::default().fg(Color::Yellow)),
+                    Span::styled(
+                        format!("{} = ", entry.key),
+                        Style::default().fg(Color::Cyan),
+                    ),
+                    Span::styled(value_display, value_style),
+                ]),
+            ])
+        })
+        .collect();
+
+    let mut list_state = ListState::default();
+    list_state.select(rows.iter().position(|&i| i == panel.selected_index));
+
+    // Border style: dim both panels when the user is navigating the main sidebar
+    let panel_style = if panel.sidebar_focused {
+        Style::default().fg(Color::DarkGray)
+    } else {
+        Style::default()
+    };
+
+    let path_max = (panels[0].width as usize).saturating_sub(fixed);
+    let title = format!(
+        " {}{} --- {} ",
+        if panel.dirty { "● " } else { "" },
+        panel.name,
+        shorten_path(panel.path, path_max, panel.home)
+    );
+    let title_style = if panel.dirty {
+        Style::default().fg(Color::Yellow)
+    } else {
+        Style::default()
+    };
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .title_style(title_style)
+                .border_style(panel_style),
+        )
+        .highlight_style(Style::default().bg(Color::DarkGray));
+
+    f.render_stateful_widget(list, panels[0], &mut list_state);
+
+    // Right panel: detail and edit field
+    let right_block = Block::default()
+        .borders(Borders::ALL)
+        .title(" Detail ")
+        .border_style(panel_style);
+    let inner = right_block.inner(panels[1]);
+    f.render_widget(right_block, panels[1]);
+
+    let Some(entry) = panel.entries.get(panel.selected_index) else {
+        return;
+    };
+
+    let description = entry
+        .schema
+        .as_ref()
+        .map_or("Unknown option", |s| s.description.as_str());
+    let facts = entry_facts(entry, panel);
+    // The description wraps in full, up to a few lines
+    let description_height = description
+        .width()
+        .div_ceil(usize::from(inner.width.max(1)))
+        .clamp(1, 4);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(u16::try_from(description_height).unwrap_or(4)),
+            Constraint::Length(u16::try_from(facts.len()).unwrap_or(0)), // type, line, status
+            Constraint::Length(1), // spacer, or the value preview
+            Constraint::Length(1), // "Value:" label
+            Constraint::Length(3), // value / input box
+            Constraint::Min(0),
+        ])
+        .split(inner);
+
+    f.render_widget(
+        Paragraph::new(description)
+            .wrap(Wrap { trim: true })
+            .style(Style::default().fg(Color::White)),
+        rows[0],
+    );
+    f.render_widget(Paragraph::new(facts), rows[1]);
+    f.render_widget(
+        Paragraph::new("Value:").style(Style::default().fg(Color::Gray)),
+        rows[3],
+    );
+
+    if let Some(preview) = &panel.preview {
+        f.render_widget(Paragraph::new(Line::from(preview.clone())), rows[2]);
+    }
 
-            if editing {
-                f.render_widget(
-                    Paragraph::new(edit_input.as_str())
-                        .block(Block::default().borders(Borders::ALL))
-                        .style(Style::default().fg(Color::Yellow)),
-                    rows[4],
-                );
-                let cursor_x =
-                    (rows[4].x + 1 + u16::try_from(edit_input.chars().count()).unwrap_or(u16::MAX))
-                        .min(rows[4].x + rows[4].width.saturating_sub(2));
-                let cursor_y = rows[4].y + 1;
-                f.set_cursor_position((cursor_x, cursor_y));
-            } else {
-                let (display, style) = if entry.enabled {
-                    (
-                        entry.value.clone(),
-                        Style::default()
-                            .fg(Color::White)
-                            .add_modifier(Modifier::BOLD),
-                    )
-                } else {
-                    let placeholder = entry
-                        .schema
-                        .as_ref()
-                        .filter(|s| !s.default.is_empty())
-                        .map_or_else(
-                            || "not set".to_string(),
-                            |s| format!("default: {}", s.default),
-                        );
-                    (
-                        format!("({placeholder})"),
-                        Style::default().fg(Color::DarkGray),
-                    )
-                };
-                f.render_widget(
-                    Paragraph::new(display)
-                        .block(Block::default().borders(Borders::ALL))
-                        .style(style),
-                    rows[4],
-                );
+    if panel.editing {
+        let example = entry.schema.as_ref().and_then(|s| s.example.as_deref());
+        let input = match example {
+            Some(example) if panel.edit_input.is_empty() => {
+                Paragraph::new(example).style(Style::default().fg(Color::DarkGray))
             }
-        }
+            _ => Paragraph::new(panel.edit_input).style(Style::default().fg(Color::Yellow)),
+        };
+        f.render_widget(input.block(Block::default().borders(Borders::ALL)), rows[4]);
+        let cursor_x =
+            (rows[4].x + 1 + u16::try_from(panel.edit_input.chars().count()).unwrap_or(u16::MAX))
+                .min(rows[4].x + rows[4].width.saturating_sub(2));
+        let cursor_y = rows[4].y + 1;
+        f.set_cursor_position((cursor_x, cursor_y));
+    } else if let Some(line) = &panel.value_line {
+        f.render_widget(
+            Paragraph::new(line.clone()).block(Block::default().borders(Borders::ALL)),
+            rows[4],
+        );
+    } else {
+        let (display, style) = entry_display(entry);
+        f.render_widget(
+            Paragraph::new(display)
+                .block(Block::default().borders(Borders::ALL))
+                .style(style),
+            rows[4],
+        );
+    }
+
+    if let Some((title, lines)) = &panel.details {
+        f.render_widget(
+            Paragraph::new(lines.clone())
+                .wrap(Wrap { trim: false })
+                .block(
+                    Block::default()
+                        .borders(Borders::TOP)
+                        .title(format!(" {title} ")),
+                ),
+            rows[5],
+        );
+    } else if let Some(help) = panel.help {
+        f.render_widget(
+            Paragraph::new(help)
+                .wrap(Wrap { trim: true })
+                .style(Style::default().fg(Color::Gray))
+                .block(Block::default().borders(Borders::TOP).title(" Help ")),
+            rows[5],
+        );
     }
 }
 
@@ -367,6 +1897,7 @@ mod tests {
             enabled,
             schema: None,
             section: None,
+            negated: false,
         }
     }
 
@@ -374,6 +1905,135 @@ mod tests {
         KeyEvent::new(code, KeyModifiers::empty())
     }
 
+    #[test]
+    fn permission_editor_commits_flags_and_address() {
+        let entries = vec![
+            entry("dbcache", "450", true),
+            entry("whitebind", "0.0.0.0:8335", true),
+        ];
+        let mut view = BitcoinConfigView::new();
+        view.handle_input(key(KeyCode::Char('P')), &entries);
+        assert!(view.permissions.is_none());
+
+        view.selected_index = 1;
+        view.handle_input(key(KeyCode::Char('P')), &entries);
+        view.handle_input(key(KeyCode::Down), &entries);
+        view.handle_input(key(KeyCode::Char(' ')), &entries);
+        for _ in 0..PERMISSION_FLAGS.len() {
+            view.handle_input(key(KeyCode::Down), &entries);
+        }
+        view.handle_input(key(KeyCode::Backspace), &entries);
+        view.handle_input(key(KeyCode::Char('6')), &entries);
+        let action = view.handle_input(key(KeyCode::Enter), &entries);
+        assert!(matches!(
+            action,
+            AppAction::CommitEdit(1, ref v) if v == "noban@0.0.0.0:8336"
+        ));
+        assert!(view.permissions.is_none());
+    }
+
+    #[test]
+    fn bind_editor_refuses_an_invalid_component() {
+        let entries = vec![entry("bind", "127.0.0.1:8334=onion", true)];
+        let mut view = BitcoinConfigView::new();
+        view.handle_input(key(KeyCode::Enter), &entries);
+        assert!(!view.editing);
+        view.handle_input(key(KeyCode::Down), &entries);
+        view.handle_input(key(KeyCode::Char('9')), &entries);
+        let action = view.handle_input(key(KeyCode::Enter), &entries);
+        assert!(matches!(action, AppAction::None));
+        assert!(view.permissions.is_some());
+
+        view.handle_input(key(KeyCode::Backspace), &entries);
+        view.handle_input(key(KeyCode::Down), &entries);
+        view.handle_input(key(KeyCode::Char(' ')), &entries);
+        let action = view.handle_input(key(KeyCode::Enter), &entries);
+        assert!(matches!(
+            action,
+            AppAction::CommitEdit(0, ref v) if v == "127.0.0.1:8334"
+        ));
+    }
+
+    #[test]
+    fn enter_on_a_bounded_int_opens_the_slider() {
+        let mut par = entry("par", "0", false);
+        par.schema = crate::bitcoin_config::default_schema_for("par").cloned();
+        let entries = vec![entry("dbcache", "450", true), par];
+        let mut view = BitcoinConfigView::new();
+        view.selected_index = 1;
+        view.handle_input(key(KeyCode::Enter), &entries);
+        assert!(!view.editing);
+        for _ in 0..20 {
+            view.handle_input(key(KeyCode::Right), &entries);
+        }
+        view.handle_input(key(KeyCode::Left), &entries);
+        let action = view.handle_input(key(KeyCode::Enter), &entries);
+        assert!(matches!(action, AppAction::CommitEdit(1, ref v) if v == "14"));
+        assert!(view.slider.is_none());
+    }
+
+    #[test]
+    fn enter_on_debug_opens_the_category_checklist() {
+        let entries = vec![entry("dbcache", "450", true), entry("debug", "net", true)];
+        let mut view = BitcoinConfigView::new();
+        view.handle_input(key(KeyCode::Enter), &entries);
+        assert!(view.editing);
+        assert!(view.categories.is_none());
+        view.handle_input(key(KeyCode::Esc), &entries);
+
+        view.selected_index = 1;
+        view.handle_input(key(KeyCode::Enter), &entries);
+        assert!(!view.editing);
+        for _ in 0..2 {
+            view.handle_input(key(KeyCode::Down), &entries);
+        }
+        view.handle_input(key(KeyCode::Char(' ')), &entries);
+        let action = view.handle_input(key(KeyCode::Enter), &entries);
+        assert!(matches!(
+            action,
+            AppAction::CommitEdit(1, ref v) if v == "net,bench"
+        ));
+        assert!(view.categories.is_none());
+    }
+
+    #[test]
+    fn shift_m_picks_a_relay_policy_preset() {
+        let entries = vec![entry("datacarrier", "1", true)];
+        let mut view = BitcoinConfigView::new();
+        view.handle_input(key(KeyCode::Char('M')), &entries);
+        assert_eq!(view.relay_policy, Some(PresetPicker::default()));
+        view.handle_input(key(KeyCode::Down), &entries);
+        let action = view.handle_input(key(KeyCode::Enter), &entries);
+        assert!(matches!(
+            action,
+            AppAction::ApplyPolicyPreset(PolicyPreset::Restrictive)
+        ));
+        assert!(view.relay_policy.is_none());
+    }
+
+    #[test]
+    fn notify_script_picker_writes_the_kind_chosen() {
+        let entries = vec![
+            entry("dbcache", "450", true),
+            entry("blocknotify", "", false),
+        ];
+        let mut view = BitcoinConfigView::new();
+        view.handle_input(key(KeyCode::Char('e')), &entries);
+        assert!(view.notify_script.is_none());
+
+        view.selected_index = 1;
+        view.handle_input(key(KeyCode::Char('e')), &entries);
+        for _ in 0..5 {
+            view.handle_input(key(KeyCode::Down), &entries);
+        }
+        let action = view.handle_input(key(KeyCode::Enter), &entries);
+        assert!(matches!(
+            action,
+            AppAction::WriteNotifyScript(1, ScriptKind::Webhook)
+        ));
+        assert!(view.notify_script.is_none());
+    }
+
     // --- shorten path ---
 
     #[test]
@@ -478,6 +2138,32 @@ mod tests {
         assert!(view.edit_input.is_empty());
     }
 
+    #[test]
+    fn raw_search_prompt_takes_tab_and_esc() {
+        let mut view = BitcoinConfigView::new();
+        view.raw = Some(RawEditor::new("prune=550\n"));
+        view.sidebar_focused = false;
+        let entries = vec![];
+
+        let ctrl_r = KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL);
+        assert!(matches!(
+            view.handle_input(ctrl_r, &entries),
+            AppAction::None
+        ));
+        // Tab switches to the replacement field instead of leaving raw view
+        assert!(matches!(
+            view.handle_input(key(KeyCode::Tab), &entries),
+            AppAction::None
+        ));
+        let search = view.raw.as_ref().unwrap().search.as_ref().unwrap();
+        assert!(search.in_replacement);
+
+        // Esc closes the prompt, keeping focus on the text
+        view.handle_input(key(KeyCode::Esc), &entries);
+        assert!(view.raw.as_ref().unwrap().search.is_none());
+        assert!(!view.sidebar_focused);
+    }
+
     #[test]
     fn editing_esc_cancels_without_committing() {
         let mut view = BitcoinConfigView::new();
@@ -661,23 +2347,29 @@ mod tests {
 
         // One enabled entry
         let mut e1 = entry("rpcuser", "alice", true);
-        e1.schema = Some(ConfigSchema::new(
-            "rpcuser",
-            "",
-            ConfigType::String,
-            ConfigCategory::RPC,
-            "RPC username",
-        ));
+        e1.schema = Some(
+            ConfigSchema::new(
+                "rpcuser",
+                "",
+                ConfigType::String,
+                ConfigCategory::RPC,
+                "RPC username",
+            )
+            .into(),
+        );
 
         // One disabled entry with schema
         let mut e2 = entry("dbcache", "450", false);
-        e2.schema = Some(ConfigSchema::new(
-            "dbcache",
-            "450",
-            ConfigType::Int,
-            ConfigCategory::Core,
-            "DB cache size",
-        ));
+        e2.schema = Some(
+            ConfigSchema::new(
+                "dbcache",
+                "450",
+                ConfigType::Int,
+                ConfigCategory::Core,
+                "DB cache size",
+            )
+            .into(),
+        );
 
         // One disabled entry with no schema
         let e3 = entry("unknownkey", "", false);
@@ -700,6 +2392,353 @@ mod tests {
             .map(|c| c.symbol().to_string())
             .collect();
 
-        assert!(output.contains("Bitcoin Configuration"));
+        assert!(output.contains("Bitcoin Core Configuration"));
+    }
+
+    #[test]
+    fn render_shows_help_for_selected_option() {
+        use crate::app::App;
+        use ratatui::Terminal;
+        use ratatui::backend::TestBackend;
+
+        let mut app = App::new();
+        app.bitcoin_conf_path = Some(std::path::PathBuf::from("/tmp/bitcoin.conf"));
+        app.bitcoin_data = vec![
+            entry("rpcuser", "alice", true),
+            entry("dbcache", "450", true),
+        ];
+        app.bitcoin_config_view.selected_index = 1;
+
+        let mut terminal = Terminal::new(TestBackend::new(120, 30)).unwrap();
+        terminal
+            .draw(|f| {
+                let area = f.area();
+                BitcoinConfigView::render(f, &mut app, area);
+            })
+            .unwrap();
+
+        let output: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|c| c.symbol().to_string())
+            .collect();
+
+        assert!(output.contains(" Help "));
+        assert!(output.contains("Maximum database cache"));
+    }
+
+    #[test]
+    fn render_estimates_the_cost_of_an_index_being_enabled() {
+        use crate::app::App;
+        use ratatui::Terminal;
+        use ratatui::backend::TestBackend;
+
+        let mut app = App::new();
+        app.bitcoin_conf_path = Some(std::path::PathBuf::from("/tmp/bitcoin.conf"));
+        app.bitcoin_data = vec![entry("blockfilterindex", "", false)];
+        app.bitcoin_config_view.editing = true;
+        app.bitcoin_config_view.edit_input = "basic".to_string();
+
+        let mut terminal = Terminal::new(TestBackend::new(120, 30)).unwrap();
+        terminal
+            .draw(|f| {
+                let area = f.area();
+                BitcoinConfigView::render(f, &mut app, area);
+            })
+            .unwrap();
+        let output: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|c| c.symbol().to_string())
+            .collect();
+        assert!(output.contains("Needs about 13 GB more disk"));
+    }
+
+    #[test]
+    fn render_shows_diagnostics_pane() {
+        use crate::app::App;
+        use ratatui::Terminal;
+        use ratatui::backend::TestBackend;
+
+        let mut app = App::new();
+        app.bitcoin_conf_path = Some(std::path::PathBuf::from("/tmp/bitcoin.conf"));
+        app.bitcoin_data = vec![entry("rpcport", "8333", true)];
+        app.bitcoin_diagnostics = vec![Diagnostic::error(
+            "rpcport",
+            "port 8333 is also used by port",
+        )];
+
+        let mut terminal = Terminal::new(TestBackend::new(120, 30)).unwrap();
+        terminal
+            .draw(|f| {
+                let area = f.area();
+                BitcoinConfigView::render(f, &mut app, area);
+            })
+            .unwrap();
+
+        let output: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|c| c.symbol().to_string())
+            .collect();
+
+        assert!(output.contains(" Diagnostics (1) "));
+        assert!(output.contains("error: rpcport: port 8333 is also used by port"));
+    }
+
+    #[test]
+    fn detail_pane_shows_line_default_and_status() {
+        use crate::app::App;
+        use crate::bitcoin_config::{NodeFlavor, parse_config_str};
+        use ratatui::Terminal;
+        use ratatui::backend::TestBackend;
+
+        let mut app = App::new();
+        app.bitcoin_conf_path = Some(std::path::PathBuf::from("/tmp/bitcoin.conf"));
+        app.bitcoin_data =
+            parse_config_str("dbcache=1000\n[test]\nrpcport=8333\n", NodeFlavor::Core).unwrap();
+        app.bitcoin_diagnostics = vec![Diagnostic::warning("rpcport", "clashes with port")];
+        let render = |app: &mut App| {
+            let mut terminal = Terminal::new(TestBackend::new(140, 40)).unwrap();
+            terminal
+                .draw(|f| {
+                    let area = f.area();
+                    BitcoinConfigView::render(f, app, area);
+                })
+                .unwrap();
+            terminal
+                .backend()
+                .buffer()
+                .content()
+                .iter()
+                .map(|c| c.symbol().to_string())
+                .collect::<String>()
+        };
+
+        let index = |app: &App, key: &str| app.bitcoin_data.iter().position(|e| e.key == key);
+        app.bitcoin_config_view.selected_index = index(&app, "dbcache").unwrap();
+        let output = render(&mut app);
+        assert!(output.contains("Default: 450"));
+        assert!(output.contains("Line: dbcache=1000"));
+        assert!(output.contains("✓ No problems found"));
+
+        app.bitcoin_config_view.selected_index = index(&app, "rpcport").unwrap();
+        let output = render(&mut app);
+        assert!(output.contains("Line: [test] rpcport=8333"));
+        assert!(output.contains("⚠ clashes with port"));
+
+        app.bitcoin_config_view.selected_index = index(&app, "txindex").unwrap();
+        assert!(render(&mut app).contains("Line: (not in the file)"));
+
+        // The example shows in the panel and, while the input is empty, in the editor
+        app.bitcoin_config_view.selected_index = index(&app, "zmqpubrawblock").unwrap();
+        let output = render(&mut app);
+        assert!(output.contains("Example: zmqpubrawblock=tcp://127.0.0.1:28332"));
+        app.bitcoin_config_view.editing = true;
+        let output = render(&mut app);
+        assert_eq!(output.matches("tcp://127.0.0.1:28332").count(), 2);
+    }
+
+    #[test]
+    fn render_puts_chain_mismatch_in_a_banner() {
+        use crate::app::App;
+        use ratatui::Terminal;
+        use ratatui::backend::TestBackend;
+
+        let mut app = App::new();
+        app.bitcoin_conf_path = Some(std::path::PathBuf::from("/tmp/bitcoin.conf"));
+        app.bitcoin_data = vec![entry("chain", "signet", true)];
+        app.bitcoin_diagnostics = vec![Diagnostic::error("chain", "bitcoind at x runs main")];
+
+        let mut terminal = Terminal::new(TestBackend::new(120, 30)).unwrap();
+        terminal
+            .draw(|f| {
+                let area = f.area();
+                BitcoinConfigView::render(f, &mut app, area);
+            })
+            .unwrap();
+
+        let buffer = terminal.backend().buffer();
+        let top: String = (0..40)
+            .map(|x| buffer[(x, 0)].symbol().to_string())
+            .collect();
+        assert!(top.starts_with(" ⚠ bitcoind at x runs main"));
+        assert_eq!(buffer[(1, 0)].bg, Color::Red);
+    }
+
+    #[test]
+    fn common_view_hides_unset_advanced_options() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bitcoin.conf");
+        std::fs::write(&path, "par=2\n").unwrap();
+        let entries = crate::bitcoin_config::parse_config(&path).unwrap();
+        let key_at = |i: usize| entries[i].key.as_str();
+
+        let common: Vec<&str> = visible_rows(&entries, false, &[])
+            .into_iter()
+            .map(key_at)
+            .collect();
+        assert!(common.contains(&"dbcache"));
+        // Set options stay visible even when advanced
+        assert!(common.contains(&"par"));
+        assert!(!common.contains(&"blockreconstructionextratxn"));
+        assert!(!common.contains(&"debug"));
+
+        assert_eq!(visible_rows(&entries, true, &[]).len(), entries.len());
+    }
+
+    #[test]
+    fn c_lists_only_values_that_differ_from_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bitcoin.conf");
+        std::fs::write(&path, "server=1\ntxindex=0\ndbcache=1000\nmystery=1\n").unwrap();
+        let entries = crate::bitcoin_config::parse_config(&path).unwrap();
+        let mut view = BitcoinConfigView::new();
+        view.sidebar_focused = false;
+        view.favorites = vec!["prune".to_string()];
+
+        view.handle_input(key(KeyCode::Char('c')), &entries);
+        assert!(view.customized_only);
+        let keys: Vec<&str> = view
+            .rows(&entries)
+            .into_iter()
+            .map(|i| entries[i].key.as_str())
+            .collect();
+        // txindex=0 is the default and the pinned prune is unset
+        assert_eq!(keys.len(), 3);
+        assert!(keys.contains(&"server"));
+        assert!(keys.contains(&"dbcache"));
+        assert!(keys.contains(&"mystery"));
+        assert!(keys.contains(&entries[view.selected_index].key.as_str()));
+
+        view.handle_input(key(KeyCode::Char('c')), &entries);
+        assert!(view.rows(&entries).len() > 3);
+    }
+
+    #[test]
+    fn relations_show_both_directions_and_whether_they_hold() {
+        let schema = crate::bitcoin_config::get_default_schema();
+        let with_schema = |key: &str, value: &str| ConfigEntry {
+            schema: schema.iter().find(|s| s.key == key).cloned(),
+            ..entry(key, value, true)
+        };
+        let entries = vec![with_schema("listenonion", "1"), with_schema("listen", "0")];
+        let text = |key: &str| -> Vec<String> {
+            relation_lines(&entries, key)
+                .iter()
+                .map(ToString::to_string)
+                .collect()
+        };
+        assert_eq!(text("listenonion"), ["✗ listenonion requires listen=1"]);
+        let listen = text("listen");
+        assert!(listen.contains(&"· bind implies listen=1".to_string()));
+        assert!(listen.contains(&"✗ listenonion requires listen=1".to_string()));
+        assert_eq!(
+            text("dbcache"),
+            ["dbcache does not depend on or affect other options"]
+        );
+
+        let mut view = BitcoinConfigView::new();
+        view.sidebar_focused = false;
+        view.handle_input(key(KeyCode::Char('L')), &entries);
+        assert!(view.show_relations);
+    }
+
+    #[test]
+    fn network_values_list_each_chain_and_ignored_lines() {
+        let lines =
+            crate::bitcoin_config::read_assignments("rpcport=9000\n[test]\nrpcport=19000\n");
+        let text: Vec<String> = network_values(&entry("rpcport", "9000", true), &lines, true)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(text[0], "main     9000, line 1");
+        assert_eq!(text[1], "test     19000, line 3");
+        assert_eq!(text[3], "signet   38332 (default)");
+        // The top-level line is reported once, not per chain
+        assert_eq!(
+            text.iter()
+                .filter(|l| l.starts_with("line 1 ignored"))
+                .count(),
+            1
+        );
+        assert!(text.last().unwrap().contains("unsaved edits"));
+    }
+
+    #[test]
+    fn navigation_skips_hidden_rows_and_v_toggles() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bitcoin.conf");
+        std::fs::write(&path, "").unwrap();
+        let entries = crate::bitcoin_config::parse_config(&path).unwrap();
+        let mut view = BitcoinConfigView::new();
+        view.sidebar_focused = false;
+
+        view.handle_input(key(KeyCode::Down), &entries);
+        let rows = visible_rows(&entries, false, &[]);
+        assert_eq!(view.selected_index, rows[1]);
+
+        view.handle_input(key(KeyCode::Char('v')), &entries);
+        assert!(view.show_all);
+        view.handle_input(key(KeyCode::Down), &entries);
+        assert_eq!(view.selected_index, rows[1] + 1);
+
+        // Hiding the selected advanced row moves the selection to a visible one
+        view.handle_input(key(KeyCode::Char('v')), &entries);
+        assert!(rows.contains(&view.selected_index));
+    }
+
+    #[test]
+    fn shift_r_asks_before_resetting_category() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bitcoin.conf");
+        std::fs::write(&path, "").unwrap();
+        let entries = crate::bitcoin_config::parse_config(&path).unwrap();
+        let mut view = BitcoinConfigView::new();
+
+        let action = view.handle_input(key(KeyCode::Char('R')), &entries);
+        assert!(matches!(action, AppAction::None));
+        assert_eq!(view.confirm_reset, Some(ConfigCategory::Core));
+
+        let action = view.handle_input(key(KeyCode::Char('y')), &entries);
+        assert!(matches!(
+            action,
+            AppAction::ResetCategory(ConfigCategory::Core)
+        ));
+        assert!(view.confirm_reset.is_none());
+
+        view.handle_input(key(KeyCode::Char('R')), &entries);
+        let action = view.handle_input(key(KeyCode::Char('n')), &entries);
+        assert!(matches!(action, AppAction::None));
+        assert!(view.confirm_reset.is_none());
+    }
+
+    #[test]
+    fn favorites_are_pinned_first_even_when_advanced() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bitcoin.conf");
+        std::fs::write(&path, "").unwrap();
+        let entries = crate::bitcoin_config::parse_config(&path).unwrap();
+        let favorites = vec!["debug".to_string(), "dbcache".to_string()];
+
+        let rows = visible_rows(&entries, false, &favorites);
+        let keys: Vec<&str> = rows.iter().map(|&i| entries[i].key.as_str()).collect();
+        assert_eq!(keys[..2], ["debug", "dbcache"]);
+        // Pinned rows are not listed a second time
+        assert_eq!(keys.iter().filter(|k| **k == "dbcache").count(), 1);
+
+        let mut view = BitcoinConfigView::new();
+        view.favorites = favorites;
+        view.selected_index = rows[0];
+        view.handle_input(key(KeyCode::Down), &entries);
+        assert_eq!(entries[view.selected_index].key, "dbcache");
+        let action = view.handle_input(key(KeyCode::Char('f')), &entries);
+        assert!(matches!(action, AppAction::ToggleFavorite(i) if i == view.selected_index));
     }
 }
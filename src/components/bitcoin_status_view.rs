@@ -3,15 +3,37 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
 use crate::app::{App, BITCOIN_STATUS_TABS};
+use crate::bitcoin_config::{ConfigEntry, enabled_value};
+use crate::block_filters::{FilterTester, filters_enabled, served_to_peers};
+use crate::blocks::{BIG_REORG, BlockMonitor, Reorg, SLOW_BLOCK};
+use crate::descriptors::{Checksum, WalletMonitor};
+use crate::history::format_timestamp;
+use crate::indexes::{INDEXES, IndexMonitor, enables};
+use crate::mempool::{BLOCK_VSIZE, MempoolMonitor, ProjectedBlock};
+use crate::mempool_accept::{TxTester, policy_option};
+use crate::mining::{MiningMonitor, block_max_weight, block_min_feerate, format_hashrate};
+use crate::peers::{
+    PeerMonitor, PeerStats, by_asn, by_network, format_age, format_bytes, outbound_concentration,
+    sparkline,
+};
+use crate::traffic::{
+    TRAFFIC_HISTORY, TRAFFIC_POLL_INTERVAL, TrafficMonitor, TrafficRate, format_rate,
+};
+use crate::upload_budget::{GB, Usage, recommend_target};
+use crate::utxo::{COINSTATSINDEX_NOTE, UtxoMonitor, coinstatsindex_enabled};
 
 use ratatui::{
     prelude::*,
-    widgets::{Block, Borders, Paragraph, Tabs, Wrap},
+    widgets::{
+        Axis, Bar, BarChart, Block, Borders, Cell, Chart, Dataset, GraphType, Paragraph, Row,
+        Table, Tabs, Wrap,
+    },
 };
+use std::time::{SystemTime, UNIX_EPOCH};
 
 // Bitcoin Status tabs count
 const _: () = assert!(
-    BITCOIN_STATUS_TABS.len() == 4,
+    BITCOIN_STATUS_TABS.len() == 13,
     "update tab dispatch match in bitcoin_status_view.rs"
 );
 
@@ -33,9 +55,10 @@ impl BitcoinStatusView {
             ])
             .split(area);
 
-        let tabs = Tabs::new(BITCOIN_STATUS_TABS.to_vec())
+        let first = first_visible_tab(app.bitcoin_status_tab, outer[0].width.saturating_sub(2));
+        let tabs = Tabs::new(BITCOIN_STATUS_TABS[first..].to_vec())
             .block(Block::default().borders(Borders::ALL).title(" Info "))
-            .select(app.bitcoin_status_tab)
+            .select(app.bitcoin_status_tab - first)
             .highlight_style(Style::default().bg(Color::Gray).fg(Color::Black));
 
         f.render_widget(tabs, outer[0]);
@@ -67,18 +90,1226 @@ impl BitcoinStatusView {
                 f.render_widget(p, content_area);
             }
             // Peers
-            3 => {
-                let text = "Peers";
-                let p = Paragraph::new(text)
-                    .block(Block::default().borders(Borders::ALL))
-                    .wrap(Wrap { trim: true });
-                f.render_widget(p, content_area);
-            }
+            3 => render_peers(f, &app.peer_monitor, content_area),
+            // Indexes
+            4 => render_indexes(
+                f,
+                &app.index_monitor,
+                &app.filter_tester,
+                &app.bitcoin_data,
+                content_area,
+            ),
+            // UTXO
+            5 => render_utxo_set(f, &app.utxo_monitor, &app.bitcoin_data, content_area),
+            // Traffic
+            6 => render_traffic(
+                f,
+                &app.traffic_monitor,
+                app.settings.data_cap_gb,
+                content_area,
+            ),
+            // Mempool
+            7 => render_mempool(f, &app.mempool_monitor, content_area),
+            // Blocks
+            8 => render_blocks(
+                f,
+                &app.block_monitor,
+                enabled_value(&app.bitcoin_data, "zmqpubsequence").is_some(),
+                content_area,
+            ),
+            // Wallets
+            9 => render_wallets(f, &app.wallet_monitor, content_area),
+            // Mining
+            10 => render_mining(f, &app.mining_monitor, &app.bitcoin_data, content_area),
+            // Test tx
+            11 => render_tx_tester(f, &app.tx_tester, content_area),
+            // Reorgs
+            12 => render_reorgs(f, &app.reorg_history, content_area),
             _ => {}
         }
     }
 }
 
+/// Table of connected peers with their traffic, age and ping history.
+fn render_peers(f: &mut Frame, monitor: &PeerMonitor, area: Rect) {
+    let block = Block::default().borders(Borders::ALL);
+    if monitor.peers.is_empty() {
+        let text = match (&monitor.error, monitor.last_poll) {
+            (Some(e), _) => format!("Cannot reach bitcoind: {e}"),
+            (None, None) => "Asking bitcoind for its peers…".to_string(),
+            (None, Some(_)) => "No peers connected".to_string(),
+        };
+        f.render_widget(
+            Paragraph::new(text).block(block).wrap(Wrap { trim: true }),
+            area,
+        );
+        return;
+    }
+
+    let by_network = by_network(&monitor.peers);
+    let by_asn = by_asn(&monitor.peers);
+    let chart_rows = by_network
+        .len()
+        .max(by_asn.as_ref().map_or(1, Vec::len))
+        .min(MAX_BARS);
+    let [table_area, chart_area] = Layout::vertical([
+        Constraint::Min(4),
+        Constraint::Length(u16::try_from(chart_rows).unwrap_or(0) + 2),
+    ])
+    .areas(area);
+    render_distribution(
+        f,
+        &monitor.peers,
+        &by_network,
+        by_asn.as_deref(),
+        chart_area,
+    );
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let header = Row::new([
+        "Peer", "Net", "Dir", "Age", "Sent", "Received", "Ping", "History",
+    ])
+    .style(
+        Style::default()
+            .fg(Color::Cyan)
+            .add_modifier(Modifier::BOLD),
+    );
+    let rows = monitor.peers.iter().map(|p| {
+        Row::new(vec![
+            Cell::from(p.addr.clone()),
+            Cell::from(p.network.clone()),
+            Cell::from(if p.inbound { "in" } else { "out" }),
+            Cell::from(format_age(p.conntime, now)),
+            Cell::from(format_bytes(p.bytes_sent)),
+            Cell::from(format_bytes(p.bytes_recv)),
+            Cell::from(
+                p.ping
+                    .map_or("-".to_string(), |s| format!("{:.0} ms", s * 1000.0)),
+            ),
+            Cell::from(sparkline(&p.ping_history)).style(Style::default().fg(Color::Green)),
+        ])
+    });
+    let count = match monitor.peers.len() {
+        1 => "1 peer".to_string(),
+        n => format!("{n} peers"),
+    };
+    let title = match &monitor.error {
+        Some(e) => format!(" {count}, not updated: {e} "),
+        None => format!(" {count} "),
+    };
+    let widths = [
+        Constraint::Min(15),
+        Constraint::Length(5),
+        Constraint::Length(3),
+        Constraint::Length(7),
+        Constraint::Length(9),
+        Constraint::Length(9),
+        Constraint::Length(7),
+        Constraint::Fill(1),
+    ];
+    f.render_widget(
+        Table::new(rows, widths)
+            .header(header)
+            .block(block.title(title)),
+        table_area,
+    );
+}
+
+/// Sync state of each index, whether bitcoin.conf enables it, and a
+/// warning for indexes enabled but not running.
+fn render_indexes(
+    f: &mut Frame,
+    monitor: &IndexMonitor,
+    tester: &FilterTester,
+    entries: &[ConfigEntry],
+    area: Rect,
+) {
+    let block = Block::default().borders(Borders::ALL);
+    if monitor.last_poll.is_none() || (monitor.error.is_some() && monitor.indexes.is_empty()) {
+        let text = match &monitor.error {
+            Some(e) => format!("Cannot reach bitcoind: {e}"),
+            None => "Asking bitcoind for its indexes…".to_string(),
+        };
+        let mut lines = vec![Line::from(text), Line::default()];
+        lines.extend(filter_tester_lines(tester, entries));
+        f.render_widget(
+            Paragraph::new(lines).block(block).wrap(Wrap { trim: true }),
+            area,
+        );
+        return;
+    }
+
+    let title = match &monitor.error {
+        Some(e) => format!(" Indexes at block {}, not updated: {e} ", monitor.height),
+        None => format!(" Indexes at block {} ", monitor.height),
+    };
+    let block = block.title(title);
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let not_running = monitor.not_running(entries);
+    let [table_area, warning_area] = Layout::vertical([
+        Constraint::Length(u16::try_from(INDEXES.len()).unwrap_or(0) + 2),
+        Constraint::Fill(1),
+    ])
+    .areas(inner);
+
+    let header = Row::new(["Index", "Conf", "Status", "Best block"]).style(
+        Style::default()
+            .fg(Color::Cyan)
+            .add_modifier(Modifier::BOLD),
+    );
+    let rows = INDEXES.iter().map(|index| {
+        let configured = enabled_value(entries, index.key).is_some_and(enables);
+        let (status, color, best) = match monitor.status(index) {
+            Some(s) if s.synced => ("synced".to_string(), Color::Green, s.best_block_height),
+            Some(s) => (
+                format!("{:.1}% built", s.percent(monitor.height)),
+                Color::Yellow,
+                s.best_block_height,
+            ),
+            None if configured => ("not running".to_string(), Color::Red, 0),
+            None => ("off".to_string(), Color::DarkGray, 0),
+        };
+        Row::new(vec![
+            Cell::from(index.name),
+            Cell::from(if configured { "on" } else { "-" }),
+            Cell::from(status).style(Style::default().fg(color)),
+            Cell::from(if best > 0 {
+                best.to_string()
+            } else {
+                "-".to_string()
+            }),
+        ])
+    });
+    f.render_widget(
+        Table::new(
+            rows,
+            [
+                Constraint::Length(24),
+                Constraint::Length(4),
+                Constraint::Length(11),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(header),
+        table_area,
+    );
+
+    let mut warnings: Vec<Line> = not_running
+        .iter()
+        .map(|i| {
+            Line::from(Span::styled(
+                format!(
+                    "⚠ {} is enabled but not running; restart bitcoind to build it",
+                    i.key
+                ),
+                Style::default().fg(Color::Yellow),
+            ))
+        })
+        .collect();
+    warnings.extend(filter_tester_lines(tester, entries));
+    f.render_widget(
+        Paragraph::new(warnings).wrap(Wrap { trim: true }),
+        warning_area,
+    );
+}
+
+/// The compact filter query being typed and the last answer, when
+/// `blockfilterindex` is on.
+fn filter_tester_lines(tester: &FilterTester, entries: &[ConfigEntry]) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    if !filters_enabled(entries) {
+        return lines;
+    }
+    if let Some(input) = &tester.input {
+        lines.push(Line::from(vec![
+            Span::raw("Block and address: "),
+            Span::styled(format!("{input}▏"), Style::default().fg(Color::Yellow)),
+        ]));
+    }
+    if let Some(e) = &tester.error {
+        lines.push(Line::from(Span::styled(
+            e.clone(),
+            Style::default().fg(Color::Red),
+        )));
+    }
+    if let Some(found) = &tester.result {
+        lines.push(if found.matched {
+            Line::from(Span::styled(
+                format!(
+                    "✓ Filter of block {} matches {}: the block probably touches it",
+                    found.block_hash, found.target
+                ),
+                Style::default().fg(Color::Green),
+            ))
+        } else {
+            Line::from(Span::styled(
+                format!(
+                    "✗ Filter of block {} does not match {}: the block does not touch it",
+                    found.block_hash, found.target
+                ),
+                Style::default().fg(Color::Yellow),
+            ))
+        });
+        lines.push(Line::from(Span::styled(
+            format!(
+                "Filter {} bytes, header {}",
+                found.filter_size, found.header
+            ),
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    let peers = if served_to_peers(entries) {
+        "peerblockfilters is on: light clients can fetch these filters over P2P."
+    } else {
+        "peerblockfilters is off: only RPC serves these filters."
+    };
+    lines.push(Line::from(Span::styled(
+        peers,
+        Style::default().fg(Color::DarkGray),
+    )));
+    lines
+}
+
+/// `gettxoutsetinfo` figures, and how `coinstatsindex` changes the wait
+/// for them.
+fn render_utxo_set(f: &mut Frame, monitor: &UtxoMonitor, entries: &[ConfigEntry], area: Rect) {
+    let indexed = coinstatsindex_enabled(entries);
+    let field = |name: &str, value: String| {
+        Line::from(vec![
+            Span::styled(format!("{name:<20}"), Style::default().fg(Color::Cyan)),
+            Span::raw(value),
+        ])
+    };
+    let mut lines = Vec::new();
+    let title = match &monitor.stats {
+        Some(stats) => {
+            lines.push(field("Unspent outputs", stats.txouts.to_string()));
+            lines.push(field(
+                "Total amount",
+                format!("{:.8} BTC", stats.total_amount),
+            ));
+            lines.push(field("Serialized size", format_bytes(stats.bogosize)));
+            if let Some(disk) = stats.disk_size {
+                lines.push(field("Chainstate on disk", format_bytes(disk)));
+            }
+            if let Some(muhash) = &stats.muhash {
+                lines.push(field("MuHash", muhash.clone()));
+            }
+            lines.push(field(
+                "Answered in",
+                format!("{:.1} s", monitor.took.as_secs_f64()),
+            ));
+            format!(" UTXO set at block {} ", stats.height)
+        }
+        None => " UTXO set ".to_string(),
+    };
+    if let Some(e) = &monitor.error {
+        lines.push(Line::from(Span::styled(
+            format!("Cannot get UTXO set statistics: {e}"),
+            Style::default().fg(Color::Red),
+        )));
+    } else if monitor.stats.is_none() {
+        lines.push(Line::from(if indexed {
+            "Asking bitcoind for UTXO set statistics…"
+        } else {
+            "Press r to ask bitcoind for UTXO set statistics"
+        }));
+    }
+    lines.push(Line::default());
+    if indexed {
+        lines.push(Line::from(Span::styled(
+            "coinstatsindex is enabled, so this refreshes every minute.",
+            Style::default().fg(Color::Green),
+        )));
+    } else {
+        lines.push(Line::from(Span::styled(
+            "coinstatsindex is off: a scan holds up this screen until bitcoind answers.",
+            Style::default().fg(Color::Yellow),
+        )));
+    }
+    lines.push(Line::from(Span::styled(
+        COINSTATSINDEX_NOTE,
+        Style::default().fg(Color::DarkGray),
+    )));
+    f.render_widget(
+        Paragraph::new(lines)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .wrap(Wrap { trim: true }),
+        area,
+    );
+}
+
+/// First tab to draw so that the selected one fits in `width` columns;
+/// each tab takes its label, a space either side and a divider.
+fn first_visible_tab(selected: usize, width: u16) -> usize {
+    let span = |first: usize| -> usize {
+        BITCOIN_STATUS_TABS[first..=selected]
+            .iter()
+            .map(|label| label.chars().count() + 3)
+            .sum()
+    };
+    let mut first = 0;
+    while first < selected && span(first) > usize::from(width) {
+        first += 1;
+    }
+    first
+}
+
+/// The data cap prompt, or how this month is going against the cap.
+#[allow(clippy::cast_precision_loss)] // Only shown
+fn budget_lines(monitor: &TrafficMonitor, data_cap: Option<u64>) -> Vec<Line<'static>> {
+    let usage = monitor.usage();
+    let mut lines = Vec::new();
+    if let Some(input) = &monitor.cap_input {
+        let target = input
+            .parse()
+            .ok()
+            .filter(|gb| *gb > 0)
+            .map_or_else(String::new, |gb| {
+                format!(
+                    " → maxuploadtarget={}",
+                    recommend_target(gb, usage.as_ref())
+                )
+            });
+        lines.push(Line::from(vec![
+            Span::raw("Monthly data cap: "),
+            Span::styled(format!("{input}▏"), Style::default().fg(Color::Yellow)),
+            Span::raw(" GB"),
+            Span::styled(target, Style::default().fg(Color::DarkGray)),
+        ]));
+    } else if let Some(cap) = data_cap {
+        lines.push(match usage.as_ref().and_then(Usage::projected_month) {
+            Some(month) => {
+                let percent = month.saturating_mul(100) / cap.saturating_mul(GB).max(1);
+                let color = if percent > 100 {
+                    Color::Red
+                } else if percent >= 80 {
+                    Color::Yellow
+                } else {
+                    Color::Green
+                };
+                Line::from(Span::styled(
+                    format!(
+                        "At this rate {:.1} GB a month, {percent}% of the {cap} GB cap",
+                        month as f64 / GB as f64
+                    ),
+                    Style::default().fg(color),
+                ))
+            }
+            None => Line::from(Span::styled(
+                format!("{cap} GB cap; a monthly projection needs an hour of uptime"),
+                Style::default().fg(Color::DarkGray),
+            )),
+        });
+    }
+    if let Some(message) = &monitor.message {
+        lines.push(Line::from(message.clone()));
+    }
+    lines
+}
+
+/// Received and sent bandwidth over the last polls, against the upload
+/// rate `maxuploadtarget` allows.
+#[allow(clippy::cast_precision_loss)] // Sample positions on the x axis
+fn render_traffic(f: &mut Frame, monitor: &TrafficMonitor, data_cap: Option<u64>, area: Rect) {
+    let block = Block::default().borders(Borders::ALL);
+    if monitor.time_millis == 0 {
+        let text = match &monitor.error {
+            Some(e) => format!("Cannot reach bitcoind: {e}"),
+            None => "Asking bitcoind for its traffic…".to_string(),
+        };
+        let mut lines = vec![Line::from(text)];
+        lines.extend(budget_lines(monitor, data_cap));
+        f.render_widget(
+            Paragraph::new(lines).block(block).wrap(Wrap { trim: true }),
+            area,
+        );
+        return;
+    }
+
+    let cap_lines = budget_lines(monitor, data_cap);
+    let summary_height = 6 + u16::try_from(cap_lines.len()).unwrap_or(0);
+    let [chart_area, summary_area] =
+        Layout::vertical([Constraint::Min(6), Constraint::Length(summary_height)]).areas(area);
+
+    let offset = TRAFFIC_HISTORY - monitor.rates.len();
+    let points = |rate: fn(&TrafficRate) -> f64| -> Vec<(f64, f64)> {
+        monitor
+            .rates
+            .iter()
+            .enumerate()
+            .map(|(i, r)| ((offset + i) as f64, rate(r)))
+            .collect()
+    };
+    let recv = points(|r| r.recv);
+    let sent = points(|r| r.sent);
+    let last_x = (TRAFFIC_HISTORY - 1) as f64;
+    let budget = monitor
+        .upload_target
+        .as_ref()
+        .map(|t| vec![(0.0, t.budget_rate()), (last_x, t.budget_rate())]);
+    let peak = recv
+        .iter()
+        .chain(&sent)
+        .chain(budget.iter().flatten())
+        .map(|(_, y)| *y)
+        .fold(1024.0, f64::max)
+        * 1.2;
+
+    let mut datasets = vec![
+        Dataset::default()
+            .name("received")
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Color::Cyan))
+            .data(&recv),
+        Dataset::default()
+            .name("sent")
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Color::Yellow))
+            .data(&sent),
+    ];
+    if let Some(budget) = &budget {
+        datasets.push(
+            Dataset::default()
+                .name("upload budget")
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(Color::Red))
+                .data(budget),
+        );
+    }
+    // The chart's own legend hides once it covers too much of a small chart
+    let mut legend = vec![
+        Span::raw(" "),
+        Span::styled("received", Style::default().fg(Color::Cyan)),
+        Span::raw(" "),
+        Span::styled("sent", Style::default().fg(Color::Yellow)),
+    ];
+    if budget.is_some() {
+        legend.push(Span::raw(" "));
+        legend.push(Span::styled(
+            "upload budget",
+            Style::default().fg(Color::Red),
+        ));
+    }
+    legend.push(Span::raw(" "));
+    let legend = Line::from(legend);
+    let minutes = TRAFFIC_HISTORY as u64 * TRAFFIC_POLL_INTERVAL.as_secs() / 60;
+    let chart = Chart::new(datasets)
+        .block(block.title(legend))
+        .x_axis(
+            Axis::default()
+                .bounds([0.0, last_x])
+                .labels([format!("-{minutes}m"), "now".to_string()])
+                .style(Style::default().fg(Color::DarkGray)),
+        )
+        .y_axis(
+            Axis::default()
+                .bounds([0.0, peak])
+                .labels(["0".to_string(), format_rate(peak)])
+                .style(Style::default().fg(Color::DarkGray)),
+        );
+    f.render_widget(chart, chart_area);
+
+    let latest = monitor.rates.last();
+    let now =
+        |rate: Option<f64>| rate.map_or_else(String::new, |r| format!(", {}", format_rate(r)));
+    let mut lines = vec![
+        Line::from(format!(
+            "Received {}{}",
+            format_bytes(monitor.total_recv),
+            now(latest.map(|r| r.recv))
+        )),
+        Line::from(format!(
+            "Sent     {}{}",
+            format_bytes(monitor.total_sent),
+            now(latest.map(|r| r.sent))
+        )),
+    ];
+    match &monitor.upload_target {
+        Some(target) => {
+            let percent = target.used().saturating_mul(100) / target.target;
+            let color = if target.target_reached {
+                Color::Red
+            } else if percent >= 80 {
+                Color::Yellow
+            } else {
+                Color::Green
+            };
+            lines.push(Line::from(Span::styled(
+                format!(
+                    "Upload target {} of {} used ({percent}%), resets in {}",
+                    format_bytes(target.used()),
+                    format_bytes(target.target),
+                    format_age(0, target.time_left_in_cycle)
+                ),
+                Style::default().fg(color),
+            )));
+            if !target.serve_historical_blocks {
+                lines.push(Line::from(Span::styled(
+                    "Budget nearly spent: only serving recent blocks until the cycle resets",
+                    Style::default().fg(Color::Red),
+                )));
+            }
+        }
+        None => lines.push(Line::from(Span::styled(
+            "No maxuploadtarget set; uploads are not capped",
+            Style::default().fg(Color::DarkGray),
+        ))),
+    }
+    lines.extend(cap_lines);
+    if let Some(e) = &monitor.error {
+        lines.push(Line::from(Span::styled(
+            format!("Not updated: {e}"),
+            Style::default().fg(Color::Red),
+        )));
+    }
+    f.render_widget(
+        Paragraph::new(lines)
+            .block(Block::default().borders(Borders::LEFT | Borders::RIGHT | Borders::BOTTOM))
+            .wrap(Wrap { trim: true }),
+        summary_area,
+    );
+}
+
+/// The next blocks a miner would build from the mempool, above a
+/// histogram of the mempool by feerate, highest first.
+fn render_mempool(f: &mut Frame, monitor: &MempoolMonitor, area: Rect) {
+    let block = Block::default().borders(Borders::ALL);
+    if monitor.txs == 0 {
+        let text = match (&monitor.error, monitor.last_poll) {
+            (Some(e), _) => format!("Cannot read the mempool: {e}"),
+            (None, None) => "Asking bitcoind for its mempool…".to_string(),
+            (None, Some(_)) => "The mempool is empty".to_string(),
+        };
+        f.render_widget(
+            Paragraph::new(text).block(block).wrap(Wrap { trim: true }),
+            area,
+        );
+        return;
+    }
+
+    let [blocks_area, histogram_area] =
+        Layout::vertical([Constraint::Length(6), Constraint::Min(4)]).areas(area);
+    let columns =
+        Layout::horizontal(vec![Constraint::Fill(1); monitor.blocks.len()]).split(blocks_area);
+    for (i, (projected, column)) in monitor.blocks.iter().zip(columns.iter()).enumerate() {
+        f.render_widget(projected_block(i, projected), *column);
+    }
+
+    let bars: Vec<Bar> = monitor
+        .histogram
+        .iter()
+        .rev()
+        .map(|b| {
+            Bar::with_label(format!("{:>4}", b.min_feerate), b.vsize)
+                .text_value(format!("{} kvB", b.vsize / 1000))
+        })
+        .collect();
+    let title = match &monitor.error {
+        Some(e) => format!(" sat/vB, not updated: {e} "),
+        None => format!(
+            " sat/vB: {} txs, {} kvB ",
+            monitor.txs,
+            monitor.vsize / 1000
+        ),
+    };
+    f.render_widget(
+        BarChart::horizontal(bars)
+            .bar_width(1)
+            .bar_gap(0)
+            .bar_style(Style::default().fg(Color::Cyan))
+            .block(block.title(title)),
+        histogram_area,
+    );
+}
+
+/// One projected block, the `index`-th from the tip; the last one also
+/// stands for whatever of the mempool is left after it.
+#[allow(clippy::cast_precision_loss)] // vsize shown in MvB
+fn projected_block(index: usize, projected: &ProjectedBlock) -> Paragraph<'static> {
+    let title = match (index, projected.vsize.div_ceil(BLOCK_VSIZE)) {
+        (0, _) => " Next ".to_string(),
+        (i, n) if n > 1 => format!(" +{i}…{} ", i as u64 + n - 1),
+        (i, _) => format!(" +{i} "),
+    };
+    Paragraph::new(vec![
+        Line::from(Span::styled(
+            format!("~{:.0} sat/vB", projected.median_feerate),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Line::from(format!(
+            "{:.0}-{:.0}",
+            projected.min_feerate, projected.max_feerate
+        )),
+        Line::from(format!("{} txs", projected.txs)),
+        Line::from(format!("{:.2} MvB", projected.vsize as f64 / 1e6)),
+    ])
+    .style(Style::default().fg(Color::Yellow))
+    .block(Block::default().borders(Borders::ALL).title(title))
+}
+
+/// The last blocks with the gap before each, slow ones in red, and the
+/// reorgs `zmqpubsequence` reported.
+#[allow(clippy::cast_precision_loss)] // Weight shown in MWU
+fn render_blocks(f: &mut Frame, monitor: &BlockMonitor, zmq_configured: bool, area: Rect) {
+    let block = Block::default().borders(Borders::ALL);
+    if monitor.blocks.is_empty() {
+        let text = match &monitor.error {
+            Some(e) => format!("Cannot reach bitcoind: {e}"),
+            None => "Asking bitcoind for recent blocks…".to_string(),
+        };
+        f.render_widget(
+            Paragraph::new(text).block(block).wrap(Wrap { trim: true }),
+            area,
+        );
+        return;
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let mut notes: Vec<Line> = monitor
+        .reorgs
+        .iter()
+        .rev()
+        .take(3)
+        .map(|reorg| {
+            let color = if reorg.depth >= BIG_REORG {
+                Color::Red
+            } else {
+                Color::Yellow
+            };
+            Line::from(Span::styled(
+                format!(
+                    "⚠ Reorg of {} block{}, {} ago",
+                    reorg.depth,
+                    if reorg.depth == 1 { "" } else { "s" },
+                    format_age(reorg.seen, now)
+                ),
+                Style::default().fg(color),
+            ))
+        })
+        .collect();
+    let (zmq_note, zmq_color) = match (&monitor.zmq_error, &monitor.subscriber) {
+        (Some(e), None) => (format!("Cannot follow zmqpubsequence: {e}"), Color::Red),
+        (_, Some(_)) => (
+            "Watching zmqpubsequence for reorgs".to_string(),
+            Color::DarkGray,
+        ),
+        (None, None) if zmq_configured => {
+            ("Connecting to zmqpubsequence…".to_string(), Color::DarkGray)
+        }
+        (None, None) => (
+            "Set zmqpubsequence to spot reorgs".to_string(),
+            Color::DarkGray,
+        ),
+    };
+    notes.push(Line::from(Span::styled(
+        zmq_note,
+        Style::default().fg(zmq_color),
+    )));
+
+    let title = match &monitor.error {
+        Some(e) => format!(" Recent blocks, not updated: {e} "),
+        None => " Recent blocks ".to_string(),
+    };
+    let block = block.title(title);
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    let [table_area, notes_area] = Layout::vertical([
+        Constraint::Min(2),
+        Constraint::Length(u16::try_from(notes.len()).unwrap_or(0)),
+    ])
+    .areas(inner);
+
+    let header = Row::new(["Height", "Gap", "Size", "Weight", "Txs", "UTC"]).style(
+        Style::default()
+            .fg(Color::Cyan)
+            .add_modifier(Modifier::BOLD),
+    );
+    let rows = monitor.blocks.iter().map(|b| {
+        let gap = monitor.interval(b);
+        let gap_style = if gap.is_some_and(|g| g > SLOW_BLOCK) {
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        Row::new(vec![
+            Cell::from(b.height.to_string()),
+            Cell::from(gap.map_or_else(|| "-".to_string(), |g| format_age(0, g))).style(gap_style),
+            Cell::from(format_bytes(b.size)),
+            Cell::from(format!("{:.2} MWU", b.weight as f64 / 1e6)),
+            Cell::from(b.txs.to_string()),
+            Cell::from(format!(
+                "{:02}:{:02}",
+                b.time % 86_400 / 3600,
+                b.time % 3600 / 60
+            )),
+        ])
+    });
+    f.render_widget(
+        Table::new(
+            rows,
+            [
+                Constraint::Length(7),
+                Constraint::Length(7),
+                Constraint::Length(9),
+                Constraint::Length(8),
+                Constraint::Length(5),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(header),
+        table_area,
+    );
+    f.render_widget(Paragraph::new(notes), notes_area);
+}
+
+/// Descriptors of the wallet shown, each with whether its checksum holds.
+fn render_wallets(f: &mut Frame, monitor: &WalletMonitor, area: Rect) {
+    let block = Block::default().borders(Borders::ALL);
+    let Some(wallet) = monitor.wallet() else {
+        let text = match (&monitor.error, monitor.last_poll) {
+            (Some(e), _) => format!("Cannot list wallets: {e}"),
+            (None, None) => "Asking bitcoind for its wallets…".to_string(),
+            (None, Some(_)) => "No wallet is loaded".to_string(),
+        };
+        f.render_widget(
+            Paragraph::new(text).block(block).wrap(Wrap { trim: true }),
+            area,
+        );
+        return;
+    };
+
+    let name = if wallet.is_empty() {
+        "(default)"
+    } else {
+        wallet
+    };
+    let title = match &monitor.error {
+        Some(e) => format!(" Wallet {name}, not updated: {e} "),
+        None => format!(
+            " Wallet {name} ({} of {}) ",
+            monitor.selected + 1,
+            monitor.wallets.len()
+        ),
+    };
+    let block = block.title(title);
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let mut notes = Vec::new();
+    if monitor.private {
+        notes.push(Line::from(Span::styled(
+            "⚠ Showing private keys: anyone who sees them can spend the funds",
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        )));
+    }
+    if let Some(message) = &monitor.message {
+        notes.push(Line::from(Span::styled(
+            message.as_str(),
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    let [table_area, notes_area] = Layout::vertical([
+        Constraint::Min(2),
+        Constraint::Length(u16::try_from(notes.len()).unwrap_or(0)),
+    ])
+    .areas(inner);
+
+    let header = Row::new(["Role", "Checksum", "Range", "Descriptor"]).style(
+        Style::default()
+            .fg(Color::Cyan)
+            .add_modifier(Modifier::BOLD),
+    );
+    let rows = monitor.descriptors.iter().map(|d| {
+        let checksum = match &d.checksum {
+            Checksum::Valid => Cell::from("✓ valid").style(Style::default().fg(Color::Green)),
+            Checksum::Invalid(expected) if expected.is_empty() => {
+                Cell::from("✗ bad characters").style(Style::default().fg(Color::Red))
+            }
+            Checksum::Invalid(expected) => {
+                Cell::from(format!("✗ want {expected}")).style(Style::default().fg(Color::Red))
+            }
+            Checksum::Missing => Cell::from("missing").style(Style::default().fg(Color::Yellow)),
+        };
+        Row::new(vec![
+            Cell::from(d.role()),
+            checksum,
+            Cell::from(
+                d.range
+                    .map_or_else(|| "-".to_string(), |(start, end)| format!("{start}-{end}")),
+            ),
+            Cell::from(d.desc.as_str()),
+        ])
+    });
+    f.render_widget(
+        Table::new(
+            rows,
+            [
+                Constraint::Length(8),
+                Constraint::Length(16),
+                Constraint::Length(9),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(header),
+        table_area,
+    );
+    f.render_widget(Paragraph::new(notes), notes_area);
+}
+
+/// Every reorg logged, newest first, with the heights and blocks it
+/// replaced.
+fn render_reorgs(f: &mut Frame, history: &[Reorg], area: Rect) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" Reorgs seen: {} ", history.len()));
+    if history.is_empty() {
+        f.render_widget(
+            Paragraph::new(
+                "No reorgs seen yet. With zmqpubsequence set, PDM logs every block \
+                 disconnect here while it runs.",
+            )
+            .block(block)
+            .wrap(Wrap { trim: true }),
+            area,
+        );
+        return;
+    }
+    let header = Row::new(["Seen", "Depth", "Heights", "Old tip", "New branch"]).style(
+        Style::default()
+            .fg(Color::Cyan)
+            .add_modifier(Modifier::BOLD),
+    );
+    let rows = history.iter().rev().map(|reorg| {
+        let color = if reorg.depth >= BIG_REORG {
+            Color::Red
+        } else {
+            Color::Yellow
+        };
+        Row::new(vec![
+            Cell::from(format_timestamp(reorg.seen)),
+            Cell::from(reorg.depth.to_string()).style(Style::default().fg(color)),
+            Cell::from(reorg.heights().map_or_else(
+                || "?".to_string(),
+                |h| {
+                    if h.start() == h.end() {
+                        h.start().to_string()
+                    } else {
+                        format!("{}–{}", h.start(), h.end())
+                    }
+                },
+            )),
+            Cell::from(reorg.disconnected.first().cloned().unwrap_or_default()),
+            Cell::from(reorg.connected.as_str()),
+        ])
+    });
+    f.render_widget(
+        Table::new(
+            rows,
+            [
+                Constraint::Length(20),
+                Constraint::Length(5),
+                Constraint::Length(13),
+                Constraint::Fill(1),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(header)
+        .block(block),
+        area,
+    );
+}
+
+/// Cells of the usage bars on the Mining tab.
+const USAGE_BAR: usize = 12;
+
+/// `used` of `limit` as a bar with the percentage, red past the limit.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)] // Bar cells only
+fn usage_bar(used: u64, limit: u64) -> Span<'static> {
+    let ratio = if limit == 0 {
+        1.0
+    } else {
+        used as f64 / limit as f64
+    };
+    let filled = ((ratio.min(1.0) * USAGE_BAR as f64).round() as usize).min(USAGE_BAR);
+    let color = if ratio > 1.0 {
+        Color::Red
+    } else if ratio >= 0.9 {
+        Color::Green
+    } else {
+        Color::Yellow
+    };
+    Span::styled(
+        format!(
+            "{}{} {:.1}%",
+            "█".repeat(filled),
+            "░".repeat(USAGE_BAR - filled),
+            ratio * 100.0
+        ),
+        Style::default().fg(color),
+    )
+}
+
+/// `getmininginfo` and the next block template checked against the
+/// Mining options.
+#[allow(clippy::cast_precision_loss)] // Only shown
+fn render_mining(f: &mut Frame, monitor: &MiningMonitor, entries: &[ConfigEntry], area: Rect) {
+    let block = Block::default().borders(Borders::ALL);
+    let Some(info) = &monitor.info else {
+        let text = match &monitor.error {
+            Some(e) => format!("Cannot get mining info: {e}"),
+            None => "Asking bitcoind for mining info…".to_string(),
+        };
+        f.render_widget(
+            Paragraph::new(text).block(block).wrap(Wrap { trim: true }),
+            area,
+        );
+        return;
+    };
+    let field = |name: &str, value: String| {
+        Line::from(vec![
+            Span::styled(format!("{name:<20}"), Style::default().fg(Color::Cyan)),
+            Span::raw(value),
+        ])
+    };
+    let mut lines = vec![
+        field("Height", info.blocks.to_string()),
+        field("Difficulty", format!("{:.3e}", info.difficulty)),
+        field("Network hashrate", format_hashrate(info.networkhashps)),
+        field("Mempool", format!("{} txs", info.pooledtx)),
+        Line::default(),
+    ];
+    let title = match &monitor.template {
+        Some(template) => {
+            let max_weight = block_max_weight(entries);
+            lines.push(field("Transactions", template.txs.to_string()));
+            lines.push(field(
+                "Fees",
+                format!("{:.8} BTC", template.fees as f64 / 1e8),
+            ));
+            lines.push(field(
+                "Subsidy",
+                format!("{:.8} BTC", template.subsidy as f64 / 1e8),
+            ));
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!("{:<20}", "Weight"),
+                    Style::default().fg(Color::Cyan),
+                ),
+                usage_bar(template.weight, max_weight),
+                Span::raw(format!(" of {max_weight}")),
+            ]));
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!("{:<20}", "Sigops"),
+                    Style::default().fg(Color::Cyan),
+                ),
+                usage_bar(template.sigops, template.sigop_limit),
+                Span::raw(format!(" of {}", template.sigop_limit)),
+            ]));
+            if let Some(rate) = template.min_feerate {
+                lines.push(field(
+                    "Lowest feerate",
+                    format!(
+                        "{rate:.2} sat/vB (blockmintxfee {:.2})",
+                        block_min_feerate(entries)
+                    ),
+                ));
+            }
+            if max_weight > template.weight_limit && template.weight_limit > 0 {
+                lines.push(Line::from(Span::styled(
+                    format!(
+                        "blockmaxweight is above the {} consensus limit; bitcoind caps it",
+                        template.weight_limit
+                    ),
+                    Style::default().fg(Color::Yellow),
+                )));
+            }
+            format!(" Next block template at height {} ", template.height)
+        }
+        None => {
+            lines.push(Line::from(Span::styled(
+                format!(
+                    "No block template: {}",
+                    monitor.template_error.as_deref().unwrap_or("not asked yet")
+                ),
+                Style::default().fg(Color::Yellow),
+            )));
+            " Mining ".to_string()
+        }
+    };
+    if let Some(e) = &monitor.error {
+        lines.push(Line::from(Span::styled(
+            format!("Not updated: {e}"),
+            Style::default().fg(Color::Red),
+        )));
+    }
+    f.render_widget(
+        Paragraph::new(lines)
+            .block(block.title(title))
+            .wrap(Wrap { trim: true }),
+        area,
+    );
+}
+
+/// Hex characters of the pasted input shown before the cursor.
+const INPUT_TAIL: usize = 64;
+
+/// The raw transaction input and what `testmempoolaccept` said about the
+/// transactions last tested.
+fn render_tx_tester(f: &mut Frame, tester: &TxTester, area: Rect) {
+    let field = |name: &str, value: String| {
+        Line::from(vec![
+            Span::styled(format!("{name:<20}"), Style::default().fg(Color::Cyan)),
+            Span::raw(value),
+        ])
+    };
+    let mut lines = Vec::new();
+    if let Some(input) = &tester.input {
+        let chars = input.chars().count();
+        let tail: String = input
+            .chars()
+            .skip(chars.saturating_sub(INPUT_TAIL))
+            .collect();
+        let ellipsis = if chars > INPUT_TAIL { "…" } else { "" };
+        lines.push(Line::from(vec![
+            Span::raw("Raw transaction: "),
+            Span::styled(
+                format!("{ellipsis}{tail}▏"),
+                Style::default().fg(Color::Yellow),
+            ),
+        ]));
+        lines.push(Line::from(Span::styled(
+            format!(
+                "{chars} characters; separate several transactions with spaces to test them as a package"
+            ),
+            Style::default().fg(Color::DarkGray),
+        )));
+        lines.push(Line::default());
+    } else if tester.txs.is_empty() {
+        lines.push(Line::from(
+            "Press t and paste a raw transaction in hex to see whether this node's mempool \
+             would accept it",
+        ));
+    }
+    if let Some(e) = &tester.error {
+        lines.push(Line::from(Span::styled(
+            e.as_str(),
+            Style::default().fg(Color::Red),
+        )));
+    }
+    if let Some(e) = &tester.package_error {
+        lines.push(Line::from(Span::styled(
+            format!("Package rejected: {e}"),
+            Style::default().fg(Color::Red),
+        )));
+    }
+    for result in &tester.results {
+        lines.push(if result.allowed {
+            Line::from(Span::styled(
+                "✓ Accepted",
+                Style::default()
+                    .fg(Color::Green)
+                    .add_modifier(Modifier::BOLD),
+            ))
+        } else {
+            Line::from(Span::styled(
+                format!(
+                    "✗ Rejected: {}",
+                    result.reject_reason.as_deref().unwrap_or("no reason given")
+                ),
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            ))
+        });
+        lines.push(field("Txid", result.txid.clone()));
+        if let Some(vsize) = result.vsize {
+            lines.push(field("Size", format!("{vsize} vB")));
+        }
+        if let Some(fee) = result.fee_sat() {
+            lines.push(field("Fee", format!("{fee} sat")));
+        }
+        if let Some(rate) = result.feerate() {
+            lines.push(field("Feerate", format!("{rate:.2} sat/vB")));
+        }
+        if let Some(key) = result.reject_reason.as_deref().and_then(policy_option) {
+            lines.push(Line::from(Span::styled(
+                format!("Your node's policy decides this: see {key}"),
+                Style::default().fg(Color::DarkGray),
+            )));
+        }
+        lines.push(Line::default());
+    }
+    f.render_widget(
+        Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(" testmempoolaccept "),
+            )
+            .wrap(Wrap { trim: true }),
+        area,
+    );
+}
+
+/// Bars per distribution chart; the rest of the groups are left out.
+const MAX_BARS: usize = 6;
+
+/// Horizontal bar chart of `(label, peers)` counts.
+fn bar_chart<'a>(counts: &'a [(String, u64)], title: Line<'a>) -> BarChart<'a> {
+    let bars: Vec<Bar> = counts
+        .iter()
+        .take(MAX_BARS)
+        .map(|(label, n)| Bar::with_label(label.as_str(), *n).text_value(n.to_string()))
+        .collect();
+    BarChart::horizontal(bars)
+        .bar_width(1)
+        .bar_gap(0)
+        .bar_style(Style::default().fg(Color::Cyan))
+        .block(Block::default().borders(Borders::ALL).title(title))
+}
+
+/// Peers per network and per AS side by side, so a node whose peers sit
+/// in few networks, and so are easier to eclipse, stands out.
+fn render_distribution(
+    f: &mut Frame,
+    peers: &[PeerStats],
+    by_network: &[(String, u64)],
+    by_asn: Option<&[(String, u64)]>,
+    area: Rect,
+) {
+    let [network_area, asn_area] =
+        Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(area);
+    f.render_widget(
+        bar_chart(by_network, Line::from(" By network ")),
+        network_area,
+    );
+
+    let Some(by_asn) = by_asn else {
+        f.render_widget(
+            Paragraph::new("Start bitcoind with asmap=<file> to group peers by AS")
+                .style(Style::default().fg(Color::DarkGray))
+                .wrap(Wrap { trim: true })
+                .block(Block::default().borders(Borders::ALL).title(" By AS ")),
+            asn_area,
+        );
+        return;
+    };
+    let title = match outbound_concentration(peers) {
+        Some((asn, n, total)) => Line::from(Span::styled(
+            format!(" By AS: {n} of {total} outbound peers in {asn} "),
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        )),
+        None => Line::from(" By AS "),
+    };
+    f.render_widget(bar_chart(by_asn, title), asn_area);
+}
+
 impl Default for BitcoinStatusView {
     fn default() -> Self {
         Self::new()
@@ -2,6 +2,7 @@
 //
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
+use crate::accessibility::{dir_marker, file_marker};
 use crate::app::{App, AppAction};
 use crossterm::event::{KeyCode, KeyEvent};
 use ratatui::{
@@ -61,6 +62,11 @@ impl FileExplorer {
 
         if self.current_dir.parent().is_some() {
             self.files.push(self.current_dir.join(".."));
+        } else {
+            // At the root of a Windows drive, the other drives are the way out
+            let current = &self.current_dir;
+            self.files
+                .extend(drive_roots().into_iter().filter(|d| d != current));
         }
 
         if let Ok(entries) = fs::read_dir(&self.current_dir) {
@@ -163,6 +169,7 @@ impl FileExplorer {
 
     pub fn render(f: &mut Frame, app: &mut App, area: Rect) {
         let allow_dir_select = app.explorer.allow_dir_select;
+        let accessible = app.settings.accessibility;
         let sentinel = app.explorer.current_dir.clone();
 
         let files: Vec<ListItem> = app
@@ -173,13 +180,17 @@ impl FileExplorer {
                 let display_name = if allow_dir_select && path == &sentinel {
                     "[✓ Use this directory]".to_string()
                 } else if path.ends_with("..") {
-                    "📁 ..".to_string()
+                    format!("{} ..", dir_marker(accessible))
                 } else {
-                    let name = path.file_name().unwrap_or_default().to_string_lossy();
+                    // Drive roots such as `D:\` have no file name
+                    let name = path.file_name().map_or_else(
+                        || path.display().to_string(),
+                        |n| n.to_string_lossy().into_owned(),
+                    );
                     if path.is_dir() {
-                        format!("📁 {name}")
+                        format!("{} {name}", dir_marker(accessible))
                     } else {
-                        format!("📄 {name}")
+                        format!("{} {name}", file_marker(accessible))
                     }
                 };
                 ListItem::new(display_name)
@@ -210,6 +221,18 @@ impl FileExplorer {
     }
 }
 
+/// Roots of the mounted Windows drives; none on other platforms, where
+/// everything is under `/`.
+fn drive_roots() -> Vec<PathBuf> {
+    if !cfg!(windows) {
+        return Vec::new();
+    }
+    (b'A'..=b'Z')
+        .map(|letter| PathBuf::from(format!("{}:\\", letter as char)))
+        .filter(|root| root.is_dir())
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,233 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::app::{App, AppAction};
+use crate::history::{Change, format_timestamp};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+};
+use std::path::PathBuf;
+
+#[derive(Debug, Clone)]
+pub struct HistoryView {
+    pub selected_index: usize,
+    pub sidebar_focused: bool,
+    /// Only changes whose key contains this text are listed
+    pub filter: String,
+    /// True while the filter is being typed
+    pub filtering: bool,
+    /// `git log`/`git diff` output shown instead of the journal, when loaded
+    pub git_log: Option<Vec<String>>,
+}
+
+impl HistoryView {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            selected_index: 0,
+            sidebar_focused: true,
+            filter: String::new(),
+            filtering: false,
+            git_log: None,
+        }
+    }
+
+    /// Changes matching the filter, in display order.
+    #[must_use]
+    pub fn visible<'a>(&self, history: &'a [(PathBuf, Change)]) -> Vec<&'a (PathBuf, Change)> {
+        history
+            .iter()
+            .filter(|(_, c)| c.key.contains(self.filter.as_str()))
+            .collect()
+    }
+
+    /// Called only when the history panel is focused (`sidebar_focused` = false).
+    pub fn handle_input(&mut self, key: KeyEvent, history: &[(PathBuf, Change)]) -> AppAction {
+        if self.filtering {
+            match key.code {
+                KeyCode::Enter | KeyCode::Esc => self.filtering = false,
+                KeyCode::Backspace => {
+                    self.filter.pop();
+                    self.selected_index = 0;
+                }
+                KeyCode::Char(c) => {
+                    self.filter.push(c);
+                    self.selected_index = 0;
+                }
+                _ => {}
+            }
+            return AppAction::None;
+        }
+        match key.code {
+            KeyCode::Up => {
+                self.selected_index = self.selected_index.saturating_sub(1);
+            }
+            KeyCode::Down if self.selected_index + 1 < self.visible(history).len() => {
+                self.selected_index += 1;
+            }
+            KeyCode::Char('/') => self.filtering = true,
+            KeyCode::Char('g') if self.git_log.is_some() => self.git_log = None,
+            KeyCode::Char('g') => {
+                let file = self
+                    .visible(history)
+                    .get(self.selected_index)
+                    .map(|(file, _)| file.clone());
+                return AppAction::ShowGitLog(file);
+            }
+            KeyCode::Esc if self.git_log.is_some() => self.git_log = None,
+            KeyCode::Esc => self.sidebar_focused = true,
+            _ => {}
+        }
+        AppAction::None
+    }
+
+    pub fn render(f: &mut Frame, app: &mut App, area: Rect) {
+        let view = &app.history_view;
+        let panel_style = if view.sidebar_focused {
+            Style::default().fg(Color::DarkGray)
+        } else {
+            Style::default()
+        };
+        let title = if view.filter.is_empty() && !view.filtering {
+            " Change History ".to_string()
+        } else {
+            format!(" Change History --- filter: {} ", view.filter)
+        };
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .border_style(panel_style);
+
+        if let Some(lines) = &view.git_log {
+            let lines: Vec<Line> = lines
+                .iter()
+                .map(|l| {
+                    let color = if l.starts_with('+') && !l.starts_with("+++") {
+                        Color::Green
+                    } else if l.starts_with('-') && !l.starts_with("---") {
+                        Color::Red
+                    } else {
+                        Color::Gray
+                    };
+                    Line::from(Span::styled(l.as_str(), Style::default().fg(color)))
+                })
+                .collect();
+            let block = block.title(" git log ");
+            f.render_widget(Paragraph::new(lines).block(block), area);
+            return;
+        }
+
+        let rows = view.visible(&app.history);
+        if rows.is_empty() {
+            let text = if app.history.is_empty() {
+                "No recorded changes yet. Saving a config from PDM records what changed."
+            } else {
+                "No changes match the filter."
+            };
+            f.render_widget(Paragraph::new(text).block(block), area);
+            return;
+        }
+
+        let items: Vec<ListItem> = rows
+            .iter()
+            .map(|(file, change)| {
+                let name = file
+                    .file_name()
+                    .map_or_else(String::new, |n| n.to_string_lossy().into_owned());
+                let old = change.old.as_deref().unwrap_or("(not set)");
+                let new = change.new.as_deref().unwrap_or("(not set)");
+                ListItem::new(vec![
+                    Line::from(vec![
+                        Span::styled(
+                            format_timestamp(change.timestamp),
+                            Style::default().fg(Color::Gray),
+                        ),
+                        Span::styled(format!("  {name}"), Style::default().fg(Color::DarkGray)),
+                    ]),
+                    Line::from(vec![
+                        Span::styled(format!("{} ", change.key), Style::default().fg(Color::Cyan)),
+                        Span::styled(old.to_string(), Style::default().fg(Color::DarkGray)),
+                        Span::raw(" → "),
+                        Span::styled(
+                            new.to_string(),
+                            Style::default()
+                                .fg(Color::White)
+                                .add_modifier(Modifier::BOLD),
+                        ),
+                    ]),
+                ])
+            })
+            .collect();
+
+        let mut list_state = ListState::default();
+        list_state.select(Some(view.selected_index.min(items.len() - 1)));
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(Style::default().bg(Color::DarkGray));
+        f.render_stateful_widget(list, area, &mut list_state);
+    }
+}
+
+impl Default for HistoryView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::empty())
+    }
+
+    fn change(key: &str) -> (PathBuf, Change) {
+        (
+            PathBuf::from("/tmp/bitcoin.conf"),
+            Change {
+                timestamp: 0,
+                key: key.to_string(),
+                old: None,
+                new: Some("1".to_string()),
+            },
+        )
+    }
+
+    #[test]
+    fn slash_filters_by_key() {
+        let history = vec![change("prune"), change("dbcache"), change("test.prune")];
+        let mut view = HistoryView::new();
+        view.sidebar_focused = false;
+
+        view.handle_input(key(KeyCode::Down), &history);
+        assert_eq!(view.selected_index, 1);
+
+        view.handle_input(key(KeyCode::Char('/')), &history);
+        for c in "prune".chars() {
+            view.handle_input(key(KeyCode::Char(c)), &history);
+        }
+        assert_eq!(view.selected_index, 0);
+        let keys: Vec<&str> = view
+            .visible(&history)
+            .iter()
+            .map(|(_, c)| c.key.as_str())
+            .collect();
+        assert_eq!(keys, vec!["prune", "test.prune"]);
+
+        view.handle_input(key(KeyCode::Enter), &history);
+        assert!(!view.filtering);
+        let action = view.handle_input(key(KeyCode::Char('g')), &history);
+        assert!(matches!(action, AppAction::ShowGitLog(Some(_))));
+        view.git_log = Some(vec!["abc1234 2024-02-29 Update bitcoin.conf: prune".into()]);
+        view.handle_input(key(KeyCode::Esc), &history);
+        assert!(view.git_log.is_none());
+        assert!(!view.sidebar_focused);
+        view.handle_input(key(KeyCode::Esc), &history);
+        assert!(view.sidebar_focused);
+    }
+}
@@ -3,6 +3,8 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
 use crate::app::App;
+use crate::process_args::same_file;
+use crate::update_check::CURRENT_VERSION;
 use ratatui::{
     prelude::*,
     widgets::{Block, Borders, Paragraph, Wrap},
@@ -17,8 +19,34 @@ impl HomeView {
         Self
     }
 
-    pub fn render(f: &mut Frame, _app: &mut App, area: Rect) {
-        let p = Paragraph::new("Welcome to PDM.\n\nSelect a config from the sidebar to edit.")
+    pub fn render(f: &mut Frame, app: &mut App, area: Rect) {
+        let mut text = "Welcome to PDM.\n\nSelect a config from the sidebar to edit.".to_string();
+        if let Some(node) = &app.running_node {
+            let editing = app
+                .bitcoin_conf_path
+                .as_deref()
+                .is_some_and(|conf| same_file(conf, &node.conf));
+            if editing {
+                text.push_str(&format!(
+                    "\n\nEditing the config of the bitcoind running on this machine (pid {}).",
+                    node.pid
+                ));
+            } else {
+                text.push_str(&format!(
+                    "\n\nbitcoind is running on this machine (pid {}) with {}.\n\
+                     Press o to open the config of the node currently running on this machine.",
+                    node.pid,
+                    node.conf.display()
+                ));
+            }
+        }
+        if let Some(release) = &app.update_notice {
+            text.push_str(&format!(
+                "\n\nPDM {} is available (running {CURRENT_VERSION}). Changes: {}",
+                release.version, release.url
+            ));
+        }
+        let p = Paragraph::new(text)
             .block(Block::default().borders(Borders::ALL).title(" Home "))
             .wrap(Wrap { trim: true });
         f.render_widget(p, area);
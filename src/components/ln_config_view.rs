@@ -2,26 +2,138 @@
 //
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
-use crate::app::App;
+use crate::app::{App, AppAction};
+use crate::bitcoin_config::ConfigEntry;
+use crate::components::bitcoin_config_view::{EntryPanel, render_entry_panels};
+use crossterm::event::{KeyCode, KeyEvent};
 use ratatui::{
     prelude::*,
     widgets::{Block, Borders, Paragraph},
 };
 
 #[derive(Debug, Clone)]
-pub struct LNConfigView;
+pub struct LNConfigView {
+    pub selected_index: usize,
+    pub editing: bool,
+    pub edit_input: String,
+    pub save_message: Option<String>,
+    pub warning_message: Option<String>,
+    pub sidebar_focused: bool,
+    /// True when entries have been committed (via `CommitLnEdit`) but not yet saved to disk.
+    pub dirty: bool,
+}
 
 impl LNConfigView {
     #[must_use]
     pub fn new() -> Self {
-        Self
+        Self {
+            selected_index: 0,
+            editing: false,
+            edit_input: String::new(),
+            save_message: None,
+            warning_message: None,
+            sidebar_focused: true,
+            dirty: false,
+        }
+    }
+
+    pub fn handle_input(&mut self, key: KeyEvent, entries: &[ConfigEntry]) -> AppAction {
+        if self.editing {
+            match key.code {
+                KeyCode::Enter => {
+                    let action =
+                        AppAction::CommitLnEdit(self.selected_index, self.edit_input.clone());
+                    self.editing = false;
+                    self.edit_input.clear();
+                    self.save_message = None;
+                    action
+                }
+                KeyCode::Esc => {
+                    self.editing = false;
+                    self.edit_input.clear();
+                    AppAction::None
+                }
+                KeyCode::Backspace => {
+                    self.edit_input.pop();
+                    AppAction::None
+                }
+                KeyCode::Char(c) => {
+                    self.edit_input.push(c);
+                    AppAction::None
+                }
+                _ => AppAction::None,
+            }
+        } else {
+            match key.code {
+                KeyCode::Up => {
+                    if self.selected_index > 0 {
+                        self.selected_index -= 1;
+                    }
+                    self.save_message = None;
+                    AppAction::None
+                }
+                KeyCode::Down => {
+                    if self.selected_index + 1 < entries.len() {
+                        self.selected_index += 1;
+                    }
+                    self.save_message = None;
+                    AppAction::None
+                }
+                KeyCode::Enter => {
+                    if !entries.is_empty() {
+                        self.edit_input
+                            .clone_from(&entries[self.selected_index].value);
+                        self.editing = true;
+                        self.save_message = None;
+                    }
+                    AppAction::None
+                }
+                KeyCode::Char('s') => AppAction::SaveLnConfig,
+                KeyCode::Char('b') => AppAction::FillLnBitcoindRpc,
+                KeyCode::Esc => {
+                    self.sidebar_focused = true;
+                    self.save_message = None;
+                    AppAction::None
+                }
+                _ => AppAction::None,
+            }
+        }
     }
 
     // LN Config
-    pub fn render(f: &mut Frame, _app: &mut App, area: Rect) {
-        let p = Paragraph::new("LN Config")
-            .block(Block::default().borders(Borders::ALL).title(" LN Config "));
-        f.render_widget(p, area);
+    pub fn render(f: &mut Frame, app: &mut App, area: Rect) {
+        let Some(path) = app.ln_conf_path.clone() else {
+            let p = Paragraph::new("Press [Enter] to select a Lightning config file")
+                .block(Block::default().borders(Borders::ALL).title(" LN Config "));
+            f.render_widget(p, area);
+            return;
+        };
+
+        let view = &app.ln_config_view;
+        let name = format!("{} Configuration", app.ln_flavor.label());
+        render_entry_panels(
+            f,
+            area,
+            &EntryPanel {
+                name: &name,
+                path: &path,
+                home: &app.home_dir,
+                entries: &app.ln_data,
+                selected_index: view.selected_index,
+                editing: view.editing,
+                edit_input: &view.edit_input,
+                help: None,
+                preview: None,
+                rows: None,
+                pinned: &[],
+                details: None,
+                value_line: None,
+                raw_line: None,
+                diagnostics: None,
+                sidebar_focused: view.sidebar_focused,
+                dirty: view.dirty,
+            },
+        );
     }
 }
 
@@ -30,3 +142,44 @@ impl Default for LNConfigView {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn entries() -> Vec<ConfigEntry> {
+        crate::lnd_config::parse_lnd_str("alias=node\n")
+    }
+
+    #[test]
+    fn enter_starts_editing_with_current_value() {
+        let mut view = LNConfigView::new();
+        let action = view.handle_input(key(KeyCode::Enter), &entries());
+        assert!(matches!(action, AppAction::None));
+        assert!(view.editing);
+        assert_eq!(view.edit_input, "node");
+    }
+
+    #[test]
+    fn enter_while_editing_commits_value() {
+        let mut view = LNConfigView::new();
+        let entries = entries();
+        view.handle_input(key(KeyCode::Enter), &entries);
+        view.handle_input(key(KeyCode::Char('2')), &entries);
+        let action = view.handle_input(key(KeyCode::Enter), &entries);
+        assert!(matches!(action, AppAction::CommitLnEdit(0, ref v) if v == "node2"));
+        assert!(!view.editing);
+    }
+
+    #[test]
+    fn s_requests_save() {
+        let mut view = LNConfigView::new();
+        let action = view.handle_input(key(KeyCode::Char('s')), &entries());
+        assert!(matches!(action, AppAction::SaveLnConfig));
+    }
+}
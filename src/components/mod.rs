@@ -5,11 +5,16 @@
 pub mod bitcoin_config_view;
 pub mod bitcoin_status_view;
 pub mod file_explorer;
+pub mod history_view;
 pub mod home_view;
 pub mod ln_config_view;
 pub mod ln_status_view;
 pub mod p2pool_config_view;
 pub mod p2pool_status_view;
+pub mod plugins_view;
+pub mod preflight_view;
+pub mod raw_editor;
+pub mod services_view;
 pub mod settings_view;
 pub mod shares_market_view;
 pub mod status_bar;
@@ -0,0 +1,131 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::app::{App, AppAction};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Paragraph, Tabs, Wrap},
+};
+
+#[derive(Debug, Clone)]
+pub struct PluginsView {
+    /// Plugin whose screen is shown
+    pub selected: usize,
+    pub sidebar_focused: bool,
+}
+
+/// Name a plugin gets for `key`, pressed on its screen.
+fn key_name(code: KeyCode) -> Option<String> {
+    let name = match code {
+        KeyCode::Char(c) => return Some(c.to_string()),
+        KeyCode::Enter => "Enter",
+        KeyCode::Up => "Up",
+        KeyCode::Down => "Down",
+        KeyCode::PageUp => "PageUp",
+        KeyCode::PageDown => "PageDown",
+        KeyCode::Tab => "Tab",
+        KeyCode::Backspace => "Backspace",
+        _ => return None,
+    };
+    Some(name.to_string())
+}
+
+impl PluginsView {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            selected: 0,
+            sidebar_focused: true,
+        }
+    }
+
+    /// Called only when the plugin screen is focused (`sidebar_focused` =
+    /// false). Left and Right switch plugins; other keys go to the plugin
+    /// shown.
+    pub fn handle_input(&mut self, key: KeyEvent, plugins: usize) -> AppAction {
+        match key.code {
+            KeyCode::Left => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Right if self.selected + 1 < plugins => self.selected += 1,
+            KeyCode::Esc => self.sidebar_focused = true,
+            code => {
+                if let Some(name) = key_name(code) {
+                    return AppAction::PluginKey(name);
+                }
+            }
+        }
+        AppAction::None
+    }
+
+    pub fn render(f: &mut Frame, app: &mut App, area: Rect) {
+        let view = &app.plugins_view;
+        let panel_style = if view.sidebar_focused {
+            Style::default().fg(Color::DarkGray)
+        } else {
+            Style::default()
+        };
+        if app.plugins.is_empty() {
+            let text = "No plugins. Add a [[plugins]] table with a name, a command and the \
+                        bitcoind rpc_methods it may call to settings.toml; the command gets \
+                        PDM's events as JSON lines and writes the lines of its screen back.";
+            let block = Block::default()
+                .borders(Borders::ALL)
+                .title(" Plugins ")
+                .border_style(panel_style);
+            f.render_widget(
+                Paragraph::new(text).wrap(Wrap { trim: true }).block(block),
+                area,
+            );
+            return;
+        }
+
+        let outer = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(0)])
+            .split(area);
+        let selected = view.selected.min(app.plugins.len() - 1);
+        let names: Vec<&str> = app.plugins.iter().map(|p| p.config.name.as_str()).collect();
+        let tabs = Tabs::new(names)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(" Plugins ")
+                    .border_style(panel_style),
+            )
+            .select(selected)
+            .highlight_style(Style::default().bg(Color::Gray).fg(Color::Black));
+        f.render_widget(tabs, outer[0]);
+
+        let plugin = &app.plugins[selected];
+        let mut lines: Vec<Line> = plugin
+            .lines
+            .iter()
+            .map(|l| Line::from(l.as_str()))
+            .collect();
+        if let Some(error) = &plugin.error {
+            lines.push(Line::from(Span::styled(
+                format!("{}: {error}", plugin.config.name),
+                Style::default().fg(Color::Red),
+            )));
+        }
+        let title = if plugin.running() {
+            format!(" {} ", plugin.config.command)
+        } else {
+            format!(" {} (not running) ", plugin.config.command)
+        };
+        let body = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .border_style(panel_style),
+        );
+        f.render_widget(body, outer[1]);
+    }
+}
+
+impl Default for PluginsView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
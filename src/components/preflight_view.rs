@@ -0,0 +1,136 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::app::{App, AppAction};
+use crate::preflight::{CheckStatus, PreflightCheck};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+};
+
+#[derive(Debug, Clone)]
+pub struct PreflightView {
+    pub selected_index: usize,
+    pub sidebar_focused: bool,
+    /// Results of the last run, in the order the checks ran
+    pub checks: Vec<PreflightCheck>,
+}
+
+impl PreflightView {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            selected_index: 0,
+            sidebar_focused: true,
+            checks: Vec::new(),
+        }
+    }
+
+    /// The worst result of the last run, if it ran.
+    #[must_use]
+    pub fn worst(&self) -> Option<CheckStatus> {
+        self.checks.iter().map(|c| c.status).max()
+    }
+
+    /// Called only when the checks panel is focused (`sidebar_focused` = false).
+    pub fn handle_input(&mut self, key: KeyEvent) -> AppAction {
+        match key.code {
+            KeyCode::Up => self.selected_index = self.selected_index.saturating_sub(1),
+            KeyCode::Down if self.selected_index + 1 < self.checks.len() => {
+                self.selected_index += 1;
+            }
+            KeyCode::Char('r') => return AppAction::RunPreflight,
+            KeyCode::Esc => self.sidebar_focused = true,
+            _ => {}
+        }
+        AppAction::None
+    }
+
+    pub fn render(f: &mut Frame, app: &mut App, area: Rect) {
+        let view = &app.preflight_view;
+        let panel_style = if view.sidebar_focused {
+            Style::default().fg(Color::DarkGray)
+        } else {
+            Style::default()
+        };
+        let count = |status| view.checks.iter().filter(|c| c.status == status).count();
+        let title = if view.checks.is_empty() {
+            " Preflight ".to_string()
+        } else {
+            format!(
+                " Preflight --- {} pass, {} warn, {} fail ",
+                count(CheckStatus::Pass),
+                count(CheckStatus::Warn),
+                count(CheckStatus::Fail)
+            )
+        };
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .border_style(panel_style);
+        if view.checks.is_empty() {
+            let text = "Checks this machine before starting bitcoind: disk, memory, clock, \
+                        ports, open file limit and data directory.";
+            f.render_widget(
+                Paragraph::new(text).wrap(Wrap { trim: true }).block(block),
+                area,
+            );
+            return;
+        }
+
+        let items: Vec<ListItem> = view
+            .checks
+            .iter()
+            .map(|check| {
+                let color = match check.status {
+                    CheckStatus::Pass => Color::Green,
+                    CheckStatus::Warn => Color::Yellow,
+                    CheckStatus::Fail => Color::Red,
+                };
+                ListItem::new(vec![
+                    Line::from(vec![
+                        Span::styled(
+                            format!("[{}] ", check.status.label()),
+                            Style::default().fg(color).add_modifier(Modifier::BOLD),
+                        ),
+                        Span::styled(check.name, Style::default().fg(Color::Cyan)),
+                    ]),
+                    Line::from(Span::styled(
+                        format!("       {}", check.detail),
+                        Style::default().fg(Color::Gray),
+                    )),
+                ])
+            })
+            .collect();
+        let selected = view.selected_index.min(items.len() - 1);
+        let mut list_state = ListState::default();
+        list_state.select(Some(selected));
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(Style::default().bg(Color::DarkGray));
+        // Details rarely fit on one line; the selected one is shown whole
+        let panels = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(5)])
+            .split(area);
+        f.render_stateful_widget(list, panels[0], &mut list_state);
+        let check = &view.checks[selected];
+        let detail = Paragraph::new(check.detail.as_str())
+            .wrap(Wrap { trim: true })
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!(" {} ", check.name))
+                    .border_style(panel_style),
+            );
+        f.render_widget(detail, panels[1]);
+    }
+}
+
+impl Default for PreflightView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
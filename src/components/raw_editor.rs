@@ -0,0 +1,741 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Paragraph},
+};
+use regex::Regex;
+
+/// Plain-text editor over the lines of a config file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawEditor {
+    pub lines: Vec<String>,
+    /// Cursor line
+    pub row: usize,
+    /// Cursor position within the line, in characters
+    pub col: usize,
+    /// First line shown
+    pub scroll: usize,
+    /// True once the text has been edited
+    pub modified: bool,
+    /// Find/replace prompt, open while searching
+    pub search: Option<Search>,
+}
+
+/// State of the find (Ctrl-F) or find-and-replace (Ctrl-R) prompt.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Search {
+    pub query: String,
+    /// Replacement text, present when the prompt was opened to replace
+    pub replacement: Option<String>,
+    /// Typing goes to the replacement instead of the query
+    pub in_replacement: bool,
+    /// Treat the query as a regular expression; `$1` etc. then work in the replacement
+    pub regex: bool,
+    /// Stepping through matches, asking before each replacement
+    pub confirming: bool,
+    /// Replacements made in the current confirm-each pass
+    pub replaced: usize,
+    /// Outcome of the last replace, shown in the prompt
+    pub message: Option<String>,
+    /// Cursor position when the prompt opened; incremental search starts here
+    origin: (usize, usize),
+}
+
+/// A match of the search query, in characters within line `row`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Match {
+    pub row: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Byte offset of character `col` in `line`.
+fn byte_at(line: &str, col: usize) -> usize {
+    line.char_indices().nth(col).map_or(line.len(), |(i, _)| i)
+}
+
+/// Character position of byte offset `byte` in `line`.
+fn col_at(line: &str, byte: usize) -> usize {
+    line[..byte].chars().count()
+}
+
+impl Search {
+    /// Compiles the query. `Ok(None)` while it is empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns the reason when the query is not a valid regular expression
+    /// or would match empty text.
+    pub fn pattern(&self) -> Result<Option<Regex>, String> {
+        if self.query.is_empty() {
+            return Ok(None);
+        }
+        let source = if self.regex {
+            self.query.clone()
+        } else {
+            regex::escape(&self.query)
+        };
+        let re = Regex::new(&source).map_err(|e| match e {
+            regex::Error::Syntax(msg) => msg
+                .lines()
+                .last()
+                .unwrap_or_default()
+                .trim_start_matches("error: ")
+                .to_string(),
+            e => e.to_string(),
+        })?;
+        if re.is_match("") {
+            return Err("pattern matches empty text".to_string());
+        }
+        Ok(Some(re))
+    }
+}
+
+/// Compiled query and replacement text of a replace operation.
+struct Replacer {
+    re: Regex,
+    with: String,
+    /// Expand `$1`-style group references in `with`
+    expand: bool,
+}
+
+impl Replacer {
+    /// Replaces the matches in `line` that start at or after byte `from`,
+    /// only the first of them when `once`. Returns the new line, the number
+    /// of replacements and the byte offset just past the last one.
+    fn apply(&self, line: &str, from: usize, once: bool) -> (String, usize, usize) {
+        let mut out = String::with_capacity(line.len());
+        let mut last = 0;
+        let mut count = 0;
+        let mut end = from;
+        for caps in self.re.captures_iter(line) {
+            let Some(m) = caps.get(0) else { continue };
+            if m.start() < from {
+                continue;
+            }
+            out.push_str(&line[last..m.start()]);
+            if self.expand {
+                caps.expand(&self.with, &mut out);
+            } else {
+                out.push_str(&self.with);
+            }
+            last = m.end();
+            end = out.len();
+            count += 1;
+            if once {
+                break;
+            }
+        }
+        out.push_str(&line[last..]);
+        (out, count, end)
+    }
+}
+
+fn replaced_message(count: usize) -> String {
+    if count == 1 {
+        "Replaced 1 match".to_string()
+    } else {
+        format!("Replaced {count} matches")
+    }
+}
+
+impl RawEditor {
+    #[must_use]
+    pub fn new(text: &str) -> Self {
+        let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+        if lines.is_empty() {
+            lines.push(String::new());
+        }
+        Self {
+            lines,
+            row: 0,
+            col: 0,
+            scroll: 0,
+            modified: false,
+            search: None,
+        }
+    }
+
+    /// The buffer as file content, with a trailing newline.
+    #[must_use]
+    pub fn text(&self) -> String {
+        let mut text = self.lines.join("\n");
+        text.push('\n');
+        text
+    }
+
+    fn line_len(&self) -> usize {
+        self.lines[self.row].chars().count()
+    }
+
+    /// Every match of the open search, in buffer order.
+    #[must_use]
+    pub fn matches(&self) -> Vec<Match> {
+        let Some(Ok(Some(re))) = self.search.as_ref().map(Search::pattern) else {
+            return Vec::new();
+        };
+        self.lines
+            .iter()
+            .enumerate()
+            .flat_map(|(row, line)| {
+                re.find_iter(line).map(move |m| Match {
+                    row,
+                    start: col_at(line, m.start()),
+                    end: col_at(line, m.end()),
+                })
+            })
+            .collect()
+    }
+
+    /// Number of the match under the cursor, if any, and the total.
+    #[must_use]
+    pub fn match_count(&self) -> (Option<usize>, usize) {
+        let matches = self.matches();
+        let current = matches
+            .iter()
+            .position(|m| (m.row, m.start) == (self.row, self.col));
+        (current.map(|i| i + 1), matches.len())
+    }
+
+    fn search_mut(&mut self) -> &mut Search {
+        self.search.get_or_insert_with(Search::default)
+    }
+
+    /// Moves the cursor to the first match at or after `from`, or the last
+    /// one before it when not `forward`, wrapping around the buffer if
+    /// `wrap`. Returns false when there is no such match.
+    fn seek(&mut self, from: (usize, usize), forward: bool, wrap: bool) -> bool {
+        let matches = self.matches();
+        let found = if forward {
+            matches
+                .iter()
+                .find(|m| (m.row, m.start) >= from)
+                .or(matches.first().filter(|_| wrap))
+        } else {
+            matches
+                .iter()
+                .rev()
+                .find(|m| (m.row, m.start) < from)
+                .or(matches.last().filter(|_| wrap))
+        };
+        let Some(&Match { row, start, .. }) = found else {
+            return false;
+        };
+        self.row = row;
+        self.col = start;
+        true
+    }
+
+    fn replacer(&self) -> Option<Replacer> {
+        let search = self.search.as_ref()?;
+        Some(Replacer {
+            re: search.pattern().ok()??,
+            with: search.replacement.clone()?,
+            expand: search.regex,
+        })
+    }
+
+    /// Replaces the match under the cursor and moves past it. Returns false
+    /// when the cursor is not on a match.
+    fn replace_current(&mut self) -> bool {
+        if self.match_count().0.is_none() {
+            return false;
+        }
+        let Some(replacer) = self.replacer() else {
+            return false;
+        };
+        let line = &self.lines[self.row];
+        let (out, count, end) = replacer.apply(line, byte_at(line, self.col), true);
+        self.col = col_at(&out, end);
+        self.lines[self.row] = out;
+        self.modified |= count > 0;
+        count > 0
+    }
+
+    /// Replaces every match from `row`, `col` to the end of the buffer.
+    /// Returns the number replaced.
+    fn replace_from(&mut self, row: usize, col: usize) -> usize {
+        let Some(replacer) = self.replacer() else {
+            return 0;
+        };
+        let mut total = 0;
+        for (i, line) in self.lines.iter_mut().enumerate().skip(row) {
+            let from = if i == row { byte_at(line, col) } else { 0 };
+            let (out, count, _) = replacer.apply(line, from, false);
+            if count > 0 {
+                *line = out;
+                total += count;
+            }
+        }
+        self.modified |= total > 0;
+        self.col = self.col.min(self.line_len());
+        total
+    }
+
+    fn finish_confirm(&mut self) {
+        let search = self.search_mut();
+        search.confirming = false;
+        search.message = Some(replaced_message(search.replaced));
+    }
+
+    /// y replaces the current match, n skips it, a replaces it and all that
+    /// follow, q or Esc stops.
+    fn handle_confirm_input(&mut self, key: KeyEvent) -> bool {
+        let changed = match key.code {
+            KeyCode::Char('y') => self.replace_current(),
+            KeyCode::Char('n') => false,
+            KeyCode::Char('a') => {
+                let count = self.replace_from(self.row, self.col);
+                self.search_mut().replaced += count;
+                self.finish_confirm();
+                return count > 0;
+            }
+            KeyCode::Char('q') | KeyCode::Esc => {
+                self.finish_confirm();
+                return false;
+            }
+            _ => return false,
+        };
+        let from = if changed {
+            self.search_mut().replaced += 1;
+            (self.row, self.col)
+        } else {
+            (self.row, self.col + 1)
+        };
+        if !self.seek(from, true, false) {
+            self.finish_confirm();
+        }
+        changed
+    }
+
+    /// Keys while the find/replace prompt is open. Returns true when the
+    /// text changed.
+    fn handle_search_input(&mut self, key: KeyEvent) -> bool {
+        let Some(search) = &self.search else {
+            return false;
+        };
+        if search.confirming {
+            return self.handle_confirm_input(key);
+        }
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let replacing = search.replacement.is_some();
+        let in_replacement = search.in_replacement;
+        let origin = search.origin;
+        let mut changed = false;
+        match key.code {
+            KeyCode::Esc => self.search = None,
+            KeyCode::Tab if replacing => self.search_mut().in_replacement = !in_replacement,
+            KeyCode::Char('e') if ctrl => {
+                let search = self.search_mut();
+                search.regex = !search.regex;
+                self.seek(origin, true, true);
+            }
+            KeyCode::Char('a') if ctrl && replacing => {
+                let count = self.replace_from(0, 0);
+                self.search_mut().message = Some(replaced_message(count));
+                changed = count > 0;
+            }
+            KeyCode::Enter if in_replacement => {
+                let found = self.seek((0, 0), true, false);
+                let search = self.search_mut();
+                search.confirming = found;
+                search.replaced = 0;
+                search.message = (!found).then(|| "No matches".to_string());
+            }
+            KeyCode::Enter | KeyCode::Down => {
+                self.seek((self.row, self.col + 1), true, true);
+            }
+            KeyCode::Up => {
+                self.seek((self.row, self.col), false, true);
+            }
+            KeyCode::Backspace | KeyCode::Char(_) if !ctrl => {
+                let search = self.search_mut();
+                search.message = None;
+                let field = match &mut search.replacement {
+                    Some(replacement) if in_replacement => replacement,
+                    _ => &mut search.query,
+                };
+                if let KeyCode::Char(c) = key.code {
+                    field.push(c);
+                } else {
+                    field.pop();
+                }
+                if !in_replacement {
+                    // Incremental: look again from where the search started
+                    self.row = origin.0.min(self.lines.len() - 1);
+                    self.col = origin.1.min(self.line_len());
+                    self.seek(origin, true, true);
+                }
+            }
+            _ => {}
+        }
+        changed
+    }
+
+    /// Applies an editing or movement key. Returns true when the text changed.
+    pub fn handle_input(&mut self, key: KeyEvent) -> bool {
+        if self.search.is_some() {
+            return self.handle_search_input(key);
+        }
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let changed = match key.code {
+            KeyCode::Char(c @ ('f' | 'r')) if ctrl => {
+                self.search = Some(Search {
+                    replacement: (c == 'r').then(String::new),
+                    origin: (self.row, self.col),
+                    ..Search::default()
+                });
+                false
+            }
+            KeyCode::Char(c) if !ctrl => {
+                let at = byte_at(&self.lines[self.row], self.col);
+                self.lines[self.row].insert(at, c);
+                self.col += 1;
+                true
+            }
+            KeyCode::Enter => {
+                let at = byte_at(&self.lines[self.row], self.col);
+                let rest = self.lines[self.row].split_off(at);
+                self.row += 1;
+                self.lines.insert(self.row, rest);
+                self.col = 0;
+                true
+            }
+            KeyCode::Backspace if self.col > 0 => {
+                self.col -= 1;
+                let at = byte_at(&self.lines[self.row], self.col);
+                self.lines[self.row].remove(at);
+                true
+            }
+            KeyCode::Backspace if self.row > 0 => {
+                let line = self.lines.remove(self.row);
+                self.row -= 1;
+                self.col = self.line_len();
+                self.lines[self.row].push_str(&line);
+                true
+            }
+            KeyCode::Delete if self.col < self.line_len() => {
+                let at = byte_at(&self.lines[self.row], self.col);
+                self.lines[self.row].remove(at);
+                true
+            }
+            KeyCode::Delete if self.row + 1 < self.lines.len() => {
+                let next = self.lines.remove(self.row + 1);
+                self.lines[self.row].push_str(&next);
+                true
+            }
+            KeyCode::Left if self.col > 0 => {
+                self.col -= 1;
+                false
+            }
+            KeyCode::Right if self.col < self.line_len() => {
+                self.col += 1;
+                false
+            }
+            KeyCode::Up => {
+                self.row = self.row.saturating_sub(1);
+                false
+            }
+            KeyCode::Down => {
+                self.row = (self.row + 1).min(self.lines.len() - 1);
+                false
+            }
+            KeyCode::PageUp => {
+                self.row = self.row.saturating_sub(10);
+                false
+            }
+            KeyCode::PageDown => {
+                self.row = (self.row + 10).min(self.lines.len() - 1);
+                false
+            }
+            KeyCode::Home => {
+                self.col = 0;
+                false
+            }
+            KeyCode::End => {
+                self.col = self.line_len();
+                false
+            }
+            _ => false,
+        };
+        self.col = self.col.min(self.line_len());
+        self.modified |= changed;
+        changed
+    }
+
+    /// Draws the buffer with line numbers, scrolled to keep the cursor in
+    /// view, and places the terminal cursor when `focused`.
+    pub fn render(&mut self, f: &mut Frame, area: Rect, title: &str, focused: bool) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(title.to_string())
+            .border_style(if focused {
+                Style::default()
+            } else {
+                Style::default().fg(Color::DarkGray)
+            });
+        let mut inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let prompt = self.search.is_some().then(|| {
+            let rows = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(0), Constraint::Length(1)])
+                .split(inner);
+            inner = rows[0];
+            rows[1]
+        });
+
+        let height = usize::from(inner.height.max(1));
+        if self.row < self.scroll {
+            self.scroll = self.row;
+        } else if self.row >= self.scroll + height {
+            self.scroll = self.row + 1 - height;
+        }
+
+        let matches = self.matches();
+        let current = Style::default().fg(Color::Black).bg(Color::Yellow);
+        let other = Style::default().bg(Color::DarkGray);
+        let gutter = self.lines.len().to_string().len();
+        let lines: Vec<Line> = self
+            .lines
+            .iter()
+            .enumerate()
+            .skip(self.scroll)
+            .take(height)
+            .map(|(i, line)| {
+                let mut spans = vec![Span::styled(
+                    format!("{:>gutter$} ", i + 1),
+                    Style::default().fg(Color::DarkGray),
+                )];
+                let mut last = 0;
+                for m in matches.iter().filter(|m| m.row == i) {
+                    let (start, end) = (byte_at(line, m.start), byte_at(line, m.end));
+                    spans.push(Span::raw(&line[last..start]));
+                    spans.push(Span::styled(
+                        &line[start..end],
+                        if (m.row, m.start) == (self.row, self.col) {
+                            current
+                        } else {
+                            other
+                        },
+                    ));
+                    last = end;
+                }
+                spans.push(Span::raw(&line[last..]));
+                Line::from(spans)
+            })
+            .collect();
+        f.render_widget(Paragraph::new(lines), inner);
+
+        let mut cursor = None;
+        if let (Some(search), Some(area)) = (&self.search, prompt) {
+            let (line, prompt_cursor) = self.prompt_line(search);
+            f.render_widget(Paragraph::new(line), area);
+            cursor = prompt_cursor.map(|x| {
+                (
+                    area.x
+                        + u16::try_from(x)
+                            .unwrap_or(u16::MAX)
+                            .min(area.width.saturating_sub(1)),
+                    area.y,
+                )
+            });
+        }
+
+        if focused {
+            let x = inner.x
+                + u16::try_from(gutter + 1 + self.col)
+                    .unwrap_or(u16::MAX)
+                    .min(inner.width.saturating_sub(1));
+            let y = inner.y + u16::try_from(self.row - self.scroll).unwrap_or(0);
+            f.set_cursor_position(cursor.unwrap_or((x, y)));
+        }
+    }
+
+    /// The find/replace prompt, with the column of the text cursor when the
+    /// prompt is taking input.
+    fn prompt_line<'a>(&self, search: &'a Search) -> (Line<'a>, Option<usize>) {
+        let label = Style::default().fg(Color::Cyan);
+        let dim = Style::default().fg(Color::DarkGray);
+        let (current, total) = self.match_count();
+        let count = match (current, total) {
+            (_, 0) => "no matches".to_string(),
+            (Some(current), total) => format!("{current}/{total}"),
+            (None, total) => format!("{total} matches"),
+        };
+        if search.confirming {
+            let line = Line::from(vec![
+                Span::styled("Replace this match? ", Style::default().fg(Color::Yellow)),
+                Span::styled(count, dim),
+            ]);
+            return (line, None);
+        }
+
+        let mut spans = vec![
+            Span::styled("Find: ", label),
+            Span::raw(search.query.as_str()),
+        ];
+        let mut cursor = Line::from(spans.clone()).width();
+        if let Some(replacement) = &search.replacement {
+            spans.push(Span::styled("  Replace: ", label));
+            spans.push(Span::raw(replacement.as_str()));
+            if search.in_replacement {
+                cursor = Line::from(spans.clone()).width();
+            }
+        }
+        if search.regex {
+            spans.push(Span::styled("  [regex]", dim));
+        }
+        match search.pattern() {
+            Err(e) => spans.push(Span::styled(
+                format!("  {e}"),
+                Style::default().fg(Color::Red),
+            )),
+            Ok(Some(_)) => spans.push(Span::styled(format!("  {count}"), dim)),
+            Ok(None) => {}
+        }
+        if let Some(message) = &search.message {
+            spans.push(Span::styled(
+                format!("  {message}"),
+                Style::default().fg(Color::Green),
+            ));
+        }
+        (Line::from(spans), Some(cursor))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn ctrl(c: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL)
+    }
+
+    fn type_str(editor: &mut RawEditor, text: &str) {
+        for c in text.chars() {
+            editor.handle_input(key(KeyCode::Char(c)));
+        }
+    }
+
+    #[test]
+    fn edits_split_and_join_lines() {
+        let mut editor = RawEditor::new("prune=550\n");
+        assert!(!editor.modified);
+        editor.handle_input(key(KeyCode::End));
+        editor.handle_input(key(KeyCode::Enter));
+        type_str(&mut editor, "txindex=1");
+        assert_eq!(editor.text(), "prune=550\ntxindex=1\n");
+        assert!(editor.modified);
+
+        editor.handle_input(key(KeyCode::Home));
+        editor.handle_input(key(KeyCode::Backspace));
+        assert_eq!(editor.text(), "prune=550txindex=1\n");
+        assert_eq!((editor.row, editor.col), (0, 9));
+        editor.handle_input(key(KeyCode::Delete));
+        assert_eq!(editor.text(), "prune=550xindex=1\n");
+    }
+
+    #[test]
+    fn cursor_stays_within_lines() {
+        let mut editor = RawEditor::new("datadir=/ü\nx=1\n");
+        editor.handle_input(key(KeyCode::End));
+        assert_eq!(editor.col, 10);
+        editor.handle_input(key(KeyCode::Down));
+        assert_eq!((editor.row, editor.col), (1, 3));
+        editor.handle_input(key(KeyCode::Down));
+        assert_eq!(editor.row, 1);
+        editor.handle_input(key(KeyCode::Up));
+        editor.handle_input(key(KeyCode::End));
+        editor.handle_input(key(KeyCode::Backspace));
+        assert_eq!(editor.lines[0], "datadir=/");
+    }
+
+    #[test]
+    fn incremental_search_counts_and_cycles_matches() {
+        let mut editor = RawEditor::new("rpcport=8332\nport=8333\n[test]\nport=18333\n");
+        editor.handle_input(key(KeyCode::Down));
+        editor.handle_input(ctrl('f'));
+        type_str(&mut editor, "p");
+        // Starts from the cursor rather than the top
+        assert_eq!((editor.row, editor.col), (1, 0));
+        type_str(&mut editor, "ort=");
+        assert_eq!(editor.match_count(), (Some(2), 3));
+
+        editor.handle_input(key(KeyCode::Enter));
+        assert_eq!((editor.row, editor.col), (3, 0));
+        editor.handle_input(key(KeyCode::Enter));
+        assert_eq!((editor.row, editor.col), (0, 3));
+        editor.handle_input(key(KeyCode::Up));
+        assert_eq!((editor.row, editor.col), (3, 0));
+
+        // Typing goes to the query, not the buffer
+        assert!(!editor.modified);
+        editor.handle_input(key(KeyCode::Esc));
+        assert!(editor.search.is_none());
+        assert_eq!((editor.row, editor.col), (3, 0));
+    }
+
+    #[test]
+    fn replace_all_expands_regex_groups() {
+        let mut editor = RawEditor::new("rpcbind=127.0.0.1\nbind=127.0.0.1:8333\n");
+        editor.handle_input(ctrl('r'));
+        type_str(&mut editor, r"127\.0\.0\.1(:\d+)?");
+        assert_eq!(editor.match_count().1, 0, "literal until regex is on");
+        editor.handle_input(ctrl('e'));
+        assert_eq!(editor.match_count().1, 2);
+        editor.handle_input(key(KeyCode::Tab));
+        type_str(&mut editor, "0.0.0.0${1}");
+        assert!(editor.handle_input(ctrl('a')));
+        assert_eq!(editor.text(), "rpcbind=0.0.0.0\nbind=0.0.0.0:8333\n");
+        assert!(editor.modified);
+        let search = editor.search.as_ref().unwrap();
+        assert_eq!(search.message.as_deref(), Some("Replaced 2 matches"));
+    }
+
+    #[test]
+    fn confirm_each_asks_per_match() {
+        let mut editor = RawEditor::new("addnode=a\naddnode=b\naddnode=c\n");
+        editor.handle_input(ctrl('r'));
+        type_str(&mut editor, "addnode");
+        editor.handle_input(key(KeyCode::Tab));
+        type_str(&mut editor, "connect");
+        editor.handle_input(key(KeyCode::Enter));
+        assert!(editor.search.as_ref().unwrap().confirming);
+        assert_eq!((editor.row, editor.col), (0, 0));
+
+        editor.handle_input(key(KeyCode::Char('n')));
+        assert_eq!((editor.row, editor.col), (1, 0));
+        editor.handle_input(key(KeyCode::Char('y')));
+        assert_eq!((editor.row, editor.col), (2, 0));
+        editor.handle_input(key(KeyCode::Char('y')));
+        assert_eq!(editor.text(), "addnode=a\nconnect=b\nconnect=c\n");
+        let search = editor.search.as_ref().unwrap();
+        assert!(!search.confirming);
+        assert_eq!(search.message.as_deref(), Some("Replaced 2 matches"));
+    }
+
+    #[test]
+    fn bad_patterns_match_nothing() {
+        let mut search = Search {
+            query: "(".to_string(),
+            regex: true,
+            ..Search::default()
+        };
+        assert_eq!(search.pattern().unwrap_err(), "unclosed group");
+        search.query = "x*".to_string();
+        assert_eq!(search.pattern().unwrap_err(), "pattern matches empty text");
+        search.regex = false;
+        assert!(search.pattern().unwrap().is_some());
+    }
+}
@@ -0,0 +1,248 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::app::{App, AppAction};
+use crate::bitcoin_config::ConfigEntry;
+use crate::components::bitcoin_config_view::{EntryPanel, render_entry_panels};
+use crate::services::ServiceKind;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, List, ListItem, Paragraph, Tabs},
+};
+
+/// Editor state for one service config file.
+#[derive(Debug, Clone)]
+pub struct ServiceConfigView {
+    pub selected_index: usize,
+    pub editing: bool,
+    pub edit_input: String,
+    pub save_message: Option<String>,
+    pub warning_message: Option<String>,
+    pub sidebar_focused: bool,
+    /// True when entries have been committed but not yet saved to disk.
+    pub dirty: bool,
+}
+
+impl ServiceConfigView {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            selected_index: 0,
+            editing: false,
+            edit_input: String::new(),
+            save_message: None,
+            warning_message: None,
+            sidebar_focused: true,
+            dirty: false,
+        }
+    }
+
+    /// Handles a key while the editor (not the sidebar) has focus.
+    pub fn handle_input(
+        &mut self,
+        key: KeyEvent,
+        kind: ServiceKind,
+        entries: &[ConfigEntry],
+    ) -> AppAction {
+        if self.editing {
+            match key.code {
+                KeyCode::Enter => {
+                    let action = AppAction::CommitServiceEdit(
+                        kind,
+                        self.selected_index,
+                        self.edit_input.clone(),
+                    );
+                    self.editing = false;
+                    self.edit_input.clear();
+                    self.save_message = None;
+                    action
+                }
+                KeyCode::Esc => {
+                    self.editing = false;
+                    self.edit_input.clear();
+                    AppAction::None
+                }
+                KeyCode::Backspace => {
+                    self.edit_input.pop();
+                    AppAction::None
+                }
+                KeyCode::Char(c) => {
+                    self.edit_input.push(c);
+                    AppAction::None
+                }
+                _ => AppAction::None,
+            }
+        } else {
+            match key.code {
+                KeyCode::Up => {
+                    if self.selected_index > 0 {
+                        self.selected_index -= 1;
+                    }
+                    self.save_message = None;
+                    AppAction::None
+                }
+                KeyCode::Down => {
+                    if self.selected_index + 1 < entries.len() {
+                        self.selected_index += 1;
+                    }
+                    self.save_message = None;
+                    AppAction::None
+                }
+                KeyCode::Enter => {
+                    if !entries.is_empty() {
+                        self.edit_input
+                            .clone_from(&entries[self.selected_index].value);
+                        self.editing = true;
+                        self.save_message = None;
+                    }
+                    AppAction::None
+                }
+                KeyCode::Char('s') => AppAction::SaveServiceConfig(kind),
+                KeyCode::Esc => {
+                    self.sidebar_focused = true;
+                    self.save_message = None;
+                    AppAction::None
+                }
+                _ => AppAction::None,
+            }
+        }
+    }
+}
+
+impl Default for ServiceConfigView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ServicesView;
+
+impl ServicesView {
+    #[must_use]
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn render(f: &mut Frame, app: &mut App, area: Rect) {
+        let kind = app.current_service();
+        let bitcoin_loaded = app.bitcoin_conf_path.is_some();
+        let service = app.service(kind);
+        let issues = if bitcoin_loaded && service.path.is_some() {
+            kind.cross_check(&service.data, &app.bitcoin_data)
+        } else {
+            Vec::new()
+        };
+        let issues_height = if issues.is_empty() {
+            0
+        } else {
+            u16::try_from(issues.len() + 2).unwrap_or(u16::MAX)
+        };
+
+        let outer = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3),             // Tabs bar
+                Constraint::Min(0),                // Editor
+                Constraint::Length(issues_height), // bitcoin.conf cross-check
+            ])
+            .split(area);
+
+        let labels: Vec<&str> = ServiceKind::ALL.iter().map(|k| k.label()).collect();
+        let tabs = Tabs::new(labels)
+            .block(Block::default().borders(Borders::ALL).title(" Services "))
+            .select(app.service_tab)
+            .highlight_style(Style::default().bg(Color::Gray).fg(Color::Black));
+        f.render_widget(tabs, outer[0]);
+
+        let Some(path) = service.path.clone() else {
+            let p = Paragraph::new(format!(
+                "Press [Enter] to select the {} file",
+                kind.file_hint()
+            ))
+            .block(Block::default().borders(Borders::ALL));
+            f.render_widget(p, outer[1]);
+            return;
+        };
+
+        let name = format!("{} Configuration", kind.label());
+        render_entry_panels(
+            f,
+            outer[1],
+            &EntryPanel {
+                name: &name,
+                path: &path,
+                home: &app.home_dir,
+                entries: &service.data,
+                selected_index: service.view.selected_index,
+                editing: service.view.editing,
+                edit_input: &service.view.edit_input,
+                help: None,
+                preview: None,
+                rows: None,
+                pinned: &[],
+                details: None,
+                value_line: None,
+                raw_line: None,
+                diagnostics: None,
+                sidebar_focused: service.view.sidebar_focused,
+                dirty: service.view.dirty,
+            },
+        );
+
+        if !issues.is_empty() {
+            let items: Vec<ListItem> = issues
+                .iter()
+                .map(|i| ListItem::new(format!("⚠ {i}")).style(Style::default().fg(Color::Yellow)))
+                .collect();
+            let list = List::new(items).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(" Mismatches with bitcoin.conf "),
+            );
+            f.render_widget(list, outer[2]);
+        }
+    }
+}
+
+impl Default for ServicesView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn edit_and_commit_emits_service_action() {
+        let entries = crate::electrs_config::parse_electrs_str("db_dir = \"/db\"").unwrap();
+        let mut view = ServiceConfigView::new();
+        view.handle_input(key(KeyCode::Enter), ServiceKind::Electrs, &entries);
+        assert!(view.editing);
+        view.handle_input(key(KeyCode::Char('x')), ServiceKind::Electrs, &entries);
+        let action = view.handle_input(key(KeyCode::Enter), ServiceKind::Electrs, &entries);
+        assert!(matches!(
+            action,
+            AppAction::CommitServiceEdit(ServiceKind::Electrs, 0, ref v) if v == "bitcoinx"
+        ));
+    }
+
+    #[test]
+    fn s_requests_save_for_kind() {
+        let mut view = ServiceConfigView::new();
+        let action = view.handle_input(key(KeyCode::Char('s')), ServiceKind::Electrs, &[]);
+        assert!(matches!(
+            action,
+            AppAction::SaveServiceConfig(ServiceKind::Electrs)
+        ));
+    }
+}
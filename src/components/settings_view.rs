@@ -10,7 +10,7 @@ use ratatui::{
 };
 
 /// Number of settings fields.
-pub const FIELD_COUNT: usize = 5;
+pub const FIELD_COUNT: usize = 7;
 
 /// Describes how a settings field behaves when Enter is pressed.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -19,6 +19,8 @@ pub enum FieldKind {
     FilePicker,
     /// Opens a file-explorer dialog in directory-selection mode.
     DirectoryPicker,
+    /// Switches an option on or off.
+    Toggle,
 }
 
 /// All settings fields in display order.  Each entry is `(label, kind)`.
@@ -28,6 +30,8 @@ pub const FIELDS: [(&str, FieldKind); FIELD_COUNT] = [
     ("LN config path", FieldKind::FilePicker),
     ("Shares Market config path", FieldKind::FilePicker),
     ("Settings directory", FieldKind::DirectoryPicker),
+    ("bitcoind binary", FieldKind::FilePicker),
+    ("Accessibility mode", FieldKind::Toggle),
 ];
 
 #[derive(Debug, Clone)]
@@ -68,6 +72,9 @@ impl SettingsView {
                 }
                 AppAction::None
             }
+            KeyCode::Enter if FIELDS[self.selected_index].1 == FieldKind::Toggle => {
+                AppAction::ToggleAccessibility
+            }
             KeyCode::Enter => AppAction::OpenExplorerForSettings(self.selected_index),
             KeyCode::Backspace => AppAction::ClearSettingsField(self.selected_index),
             KeyCode::Esc => {
@@ -100,6 +107,13 @@ impl SettingsView {
                 .settings_dir_override
                 .as_ref()
                 .map(|p| p.to_string_lossy().into_owned()),
+            app.settings
+                .bitcoind_path
+                .as_ref()
+                .map(|p| p.to_string_lossy().into_owned()),
+            app.settings
+                .accessibility
+                .then(|| "On: text markers, styled selection, high contrast".to_string()),
         ];
 
         let items: Vec<ListItem> = (0..FIELD_COUNT)
@@ -107,6 +121,15 @@ impl SettingsView {
                 let (label, _kind) = FIELDS[idx];
                 let val = &values[idx];
                 let (display, style) = match val {
+                    Some(v) if idx == 5 => (
+                        match &app.node_version {
+                            Some(version) => format!("{v} ({})", version.label()),
+                            None => format!("{v} (version unknown)"),
+                        },
+                        Style::default()
+                            .fg(Color::White)
+                            .add_modifier(Modifier::BOLD),
+                    ),
                     Some(v) => (
                         v.clone(),
                         Style::default()
@@ -121,6 +144,15 @@ impl SettingsView {
                                 app.config_dir.to_string_lossy().into_owned()
                             };
                             (path, Style::default().fg(Color::DarkGray))
+                        } else if idx == 5 {
+                            let found = match (&app.bitcoind_binary, &app.node_version) {
+                                (Some(p), Some(v)) => format!("{} ({})", p.display(), v.label()),
+                                (Some(p), None) => p.display().to_string(),
+                                (None, _) => "(not found on PATH)".to_string(),
+                            };
+                            (found, Style::default().fg(Color::DarkGray))
+                        } else if idx == 6 {
+                            ("Off".to_string(), Style::default().fg(Color::DarkGray))
                         } else {
                             (
                                 "(not set)".to_string(),
@@ -221,9 +253,9 @@ mod tests {
     }
 
     #[test]
-    fn browsing_enter_opens_explorer_for_all_fields() {
+    fn browsing_enter_opens_explorer_for_all_picker_fields() {
         let mut view = content_focused_view();
-        for idx in 0..FIELD_COUNT {
+        for idx in (0..FIELD_COUNT).filter(|&i| FIELDS[i].1 != FieldKind::Toggle) {
             view.selected_index = idx;
             let action = view.handle_input(key(KeyCode::Enter));
             assert!(
@@ -233,6 +265,14 @@ mod tests {
         }
     }
 
+    #[test]
+    fn enter_on_accessibility_toggles_it() {
+        let mut view = content_focused_view();
+        view.selected_index = 6;
+        let action = view.handle_input(key(KeyCode::Enter));
+        assert!(matches!(action, AppAction::ToggleAccessibility));
+    }
+
     #[test]
     fn browsing_esc_sets_sidebar_focused_flag() {
         let mut view = content_focused_view();
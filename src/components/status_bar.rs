@@ -2,8 +2,19 @@
 //
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
-use crate::app::{App, CurrentScreen};
+use crate::app::{
+    App, CurrentScreen, INDEXES_TAB, TEST_TX_TAB, TRAFFIC_TAB, UTXO_TAB, WALLETS_TAB,
+};
+use crate::bitcoin_config::suggest_key;
+use crate::block_filters::filters_enabled;
 use crate::components::settings_view::{FIELDS, FieldKind};
+use crate::hot_apply::HOT_OPTIONS;
+use crate::kiosk::ROTATE_INTERVAL;
+use crate::notify_scripts::NOTIFY_OPTIONS;
+use crate::permissions::{BIND_OPTIONS, EditorRow, PERMISSION_OPTIONS};
+use crate::probe::live_test;
+use crate::relay_policy::POLICY_OPTIONS;
+use crate::secrets::{SECRET_OPTIONS, referenced_account};
 use ratatui::{prelude::*, widgets::Paragraph};
 
 #[derive(Clone, Debug)]
@@ -30,6 +41,17 @@ impl StatusBar {
         let mut spans: Vec<Span> = Vec::new();
 
         match app.current_screen {
+            _ if app.kiosk.is_some() => {
+                spans.push(Span::styled(
+                    format!(
+                        " Kiosk: read only, next tab every {}s  ",
+                        ROTATE_INTERVAL.as_secs()
+                    ),
+                    Style::default().fg(Color::DarkGray),
+                ));
+                spans.extend(hint("←→", "Switch tab"));
+                spans.extend(hint("Ctrl-C", "Quit"));
+            }
             CurrentScreen::FileExplorer => {
                 spans.extend(hint("↑↓", "Navigate"));
                 spans.extend(hint("Enter", "Select"));
@@ -42,6 +64,141 @@ impl StatusBar {
                         format!(" ✓ {msg}  "),
                         Style::default().fg(Color::Green),
                     ));
+                } else if let Some(plan) = &app.bitcoin_config_view.datadir_move {
+                    spans.push(Span::styled(
+                        format!(
+                            " Data is still in {}. Move it to {}? ",
+                            plan.from.display(),
+                            plan.to.display()
+                        ),
+                        Style::default().fg(Color::Yellow),
+                    ));
+                    spans.extend(hint("m", "Move"));
+                    spans.extend(hint("l", "Symlink"));
+                    spans.extend(hint("any key", "Leave it"));
+                } else if let Some(category) = app.bitcoin_config_view.confirm_reset {
+                    spans.push(Span::styled(
+                        format!(" Reset all {category:?} options to defaults? "),
+                        Style::default().fg(Color::Yellow),
+                    ));
+                    spans.extend(hint("y", "Yes"));
+                    spans.extend(hint("any key", "Cancel"));
+                } else if let Some(msg) = &app.bitcoin_config_view.warning_message {
+                    spans.push(Span::styled(
+                        format!(" ⚠ {msg}  "),
+                        Style::default().fg(Color::Yellow),
+                    ));
+                } else if let Some(holder) = &app.bitcoin_locked_by {
+                    spans.push(Span::styled(
+                        format!(" ⚠ Read only: {holder} has this file open  "),
+                        Style::default().fg(Color::Yellow),
+                    ));
+                } else if let Some(search) = app
+                    .bitcoin_config_view
+                    .raw
+                    .as_ref()
+                    .and_then(|raw| raw.search.as_ref())
+                    .filter(|_| !app.bitcoin_config_view.sidebar_focused)
+                {
+                    if search.confirming {
+                        spans.extend(hint("y", "Replace"));
+                        spans.extend(hint("n", "Skip"));
+                        spans.extend(hint("a", "All"));
+                        spans.extend(hint("q", "Stop"));
+                    } else {
+                        if search.in_replacement {
+                            spans.extend(hint("Enter", "Confirm each"));
+                        } else {
+                            spans.extend(hint("Enter", "Next"));
+                        }
+                        spans.extend(hint("↑↓", "Prev/Next"));
+                        if search.replacement.is_some() {
+                            spans.extend(hint("Ctrl-A", "Replace all"));
+                            spans.extend(hint("Tab", "Find/Replace"));
+                        }
+                        spans.extend(hint("Ctrl-E", "Regex"));
+                        spans.extend(hint("Esc", "Close"));
+                    }
+                } else if let Some(editor) = app
+                    .bitcoin_config_view
+                    .permissions
+                    .as_ref()
+                    .filter(|_| !app.bitcoin_config_view.sidebar_focused)
+                {
+                    spans.extend(hint("↑↓", "Navigate"));
+                    match editor.row() {
+                        EditorRow::Address => spans.extend(hint("type", "Address")),
+                        EditorRow::Port => spans.extend(hint("type", "Port")),
+                        EditorRow::Flag(_) | EditorRow::Onion => {
+                            spans.extend(hint("Space", "Toggle"));
+                        }
+                    }
+                    if editor.problems().is_empty() {
+                        spans.extend(hint("Enter", "Apply"));
+                    }
+                    spans.extend(hint("Esc", "Cancel"));
+                } else if app.bitcoin_config_view.onion_backup.is_some()
+                    && !app.bitcoin_config_view.sidebar_focused
+                {
+                    spans.extend(hint("type", "Passphrase"));
+                    spans.extend(hint("Enter", "Back up"));
+                    spans.extend(hint("Esc", "Cancel"));
+                } else if let Some(editor) = app
+                    .bitcoin_config_view
+                    .profiles
+                    .as_ref()
+                    .filter(|_| !app.bitcoin_config_view.sidebar_focused)
+                {
+                    spans.extend(hint("←→", "Profile"));
+                    spans.extend(hint("↑↓", "Navigate"));
+                    if editor.own_row().is_some() {
+                        spans.extend(hint("d", "Clear override"));
+                    }
+                    spans.extend(hint("g", "Generate"));
+                    spans.extend(hint("Esc", "Close"));
+                } else if app.bitcoin_config_view.relay_policy.is_some()
+                    && !app.bitcoin_config_view.sidebar_focused
+                {
+                    spans.extend(hint("↑↓", "Navigate"));
+                    spans.extend(hint("Enter", "Apply preset"));
+                    spans.extend(hint("Esc", "Cancel"));
+                } else if app.bitcoin_config_view.categories.is_some()
+                    && !app.bitcoin_config_view.sidebar_focused
+                {
+                    spans.extend(hint("↑↓", "Navigate"));
+                    spans.extend(hint("Space", "Toggle"));
+                    spans.extend(hint("Enter", "Apply"));
+                    spans.extend(hint("Esc", "Cancel"));
+                } else if app.bitcoin_config_view.slider.is_some()
+                    && !app.bitcoin_config_view.sidebar_focused
+                {
+                    spans.extend(hint("←→", "Adjust"));
+                    spans.extend(hint("PgUp/PgDn", "Faster"));
+                    spans.extend(hint("Home/End", "Min/Max"));
+                    spans.extend(hint("Enter", "Set"));
+                    spans.extend(hint("Esc", "Cancel"));
+                } else if app.bitcoin_config_view.notify_script.is_some()
+                    && !app.bitcoin_config_view.sidebar_focused
+                {
+                    spans.extend(hint("↑↓", "Navigate"));
+                    spans.extend(hint("Enter", "Write script"));
+                    spans.extend(hint("Esc", "Cancel"));
+                } else if app.bitcoin_config_view.rpc_whitelist.is_some()
+                    && !app.bitcoin_config_view.sidebar_focused
+                {
+                    spans.extend(hint("↑↓", "Navigate"));
+                    spans.extend(hint("Space", "Toggle"));
+                    spans.extend(hint("←→", "User"));
+                    spans.extend(hint("s", "Write"));
+                    spans.extend(hint("Esc", "Close"));
+                } else if app.bitcoin_config_view.raw.is_some()
+                    && !app.bitcoin_config_view.sidebar_focused
+                {
+                    spans.extend(hint("Tab", "Form view"));
+                    spans.extend(hint("Ctrl-S", "Save"));
+                    spans.extend(hint("Esc", "Back"));
+                    spans.extend(hint("Ctrl-F", "Find"));
+                    spans.extend(hint("Ctrl-R", "Replace"));
                 } else if app.bitcoin_config_view.editing {
                     spans.extend(hint("Enter", "Confirm"));
                     spans.extend(hint("Esc", "Cancel"));
@@ -52,7 +209,116 @@ impl StatusBar {
                     spans.extend(hint("↑↓", "Navigate"));
                     spans.extend(hint("Enter", "Edit"));
                     spans.extend(hint("s", "Save"));
+                    if let Some(steps) = &app.macro_recording {
+                        let label = format!("Stop recording ({})", steps.len());
+                        spans.extend(hint("Q", &label));
+                    } else if !app.recorded_macro.is_empty() {
+                        spans.extend(hint("@", "Replay macro"));
+                    }
+                    let has_suggestion = app
+                        .bitcoin_data
+                        .get(app.bitcoin_config_view.selected_index)
+                        .filter(|e| e.schema.is_none())
+                        .and_then(|e| suggest_key(&e.key, app.settings.node_flavor))
+                        .is_some();
+                    if has_suggestion {
+                        spans.extend(hint("a", "Accept suggestion"));
+                    }
+                    let plaintext_secret = app
+                        .bitcoin_data
+                        .get(app.bitcoin_config_view.selected_index)
+                        .filter(|e| e.enabled && SECRET_OPTIONS.contains(&e.key.as_str()))
+                        .is_some_and(|e| referenced_account(&e.value).is_none());
+                    if plaintext_secret {
+                        spans.extend(hint("x", "Move to keyring"));
+                    }
+                    let on_asmap = app
+                        .bitcoin_data
+                        .get(app.bitcoin_config_view.selected_index)
+                        .is_some_and(|e| e.key == "asmap");
+                    if on_asmap {
+                        spans.extend(hint("A", "Update asmap"));
+                    }
+                    let on_prune = app
+                        .bitcoin_data
+                        .get(app.bitcoin_config_view.selected_index)
+                        .is_some_and(|e| e.key == "prune");
+                    if on_prune {
+                        spans.extend(hint("B", "Size to disk"));
+                    }
+                    let on_permissions = app
+                        .bitcoin_data
+                        .get(app.bitcoin_config_view.selected_index)
+                        .is_some_and(|e| PERMISSION_OPTIONS.contains(&e.key.as_str()));
+                    let on_bind = app
+                        .bitcoin_data
+                        .get(app.bitcoin_config_view.selected_index)
+                        .is_some_and(|e| BIND_OPTIONS.contains(&e.key.as_str()));
+                    if on_permissions {
+                        spans.extend(hint("P", "Permissions"));
+                    } else if on_bind {
+                        spans.extend(hint("P", "Edit address"));
+                    }
+                    let on_policy = app
+                        .bitcoin_data
+                        .get(app.bitcoin_config_view.selected_index)
+                        .is_some_and(|e| POLICY_OPTIONS.iter().any(|o| o.key == e.key));
+                    if on_policy {
+                        spans.extend(hint("M", "Policy presets"));
+                    }
+                    let on_hot = app
+                        .bitcoin_data
+                        .get(app.bitcoin_config_view.selected_index)
+                        .is_some_and(|e| HOT_OPTIONS.contains(&e.key.as_str()));
+                    if on_hot {
+                        spans.extend(hint("H", "Apply now"));
+                    }
+                    let on_notify = app
+                        .bitcoin_data
+                        .get(app.bitcoin_config_view.selected_index)
+                        .is_some_and(|e| NOTIFY_OPTIONS.contains(&e.key.as_str()));
+                    if on_notify {
+                        spans.extend(hint("e", "Script template"));
+                    }
+                    if let Some(test) = app
+                        .bitcoin_data
+                        .get(app.bitcoin_config_view.selected_index)
+                        .and_then(live_test)
+                    {
+                        spans.extend(hint("T", test.label()));
+                    }
                     spans.extend(hint("Esc", "Back"));
+                    // Less frequent actions last, so narrow terminals keep the essentials
+                    if app.bitcoin_config_view.show_all {
+                        spans.extend(hint("v", "Common only"));
+                    } else {
+                        spans.extend(hint("v", "Show all"));
+                    }
+                    if app.bitcoin_config_view.customized_only {
+                        spans.extend(hint("c", "All values"));
+                    } else {
+                        spans.extend(hint("c", "Customized"));
+                    }
+                    spans.extend(hint("n", "Networks"));
+                    spans.extend(hint("L", "Relations"));
+                    spans.extend(hint("N", "Switch chain"));
+                    spans.extend(hint("Tab", "Raw"));
+                    spans.extend(hint("f", "Pin"));
+                    spans.extend(hint("r/R", "Reset"));
+                    spans.extend(hint("t", "Test"));
+                    spans.extend(hint("k", "Core/Knots"));
+                    spans.extend(hint("d", "Compose"));
+                    spans.extend(hint("p", "Proxy"));
+                    spans.extend(hint("i", "Ansible/Nix"));
+                    spans.extend(hint("m", "Report"));
+                    spans.extend(hint("E", "Export for support"));
+                    spans.extend(hint("D", "Diagnostic bundle"));
+                    spans.extend(hint("g", "Profiles"));
+                    spans.extend(hint("G", "Edit profiles"));
+                    spans.extend(hint("w", "RPC whitelist"));
+                    spans.extend(hint("O", "Onion key backup"));
+                    spans.extend(hint("U", "Update options"));
+                    spans.extend(hint("Q", "Record macro"));
                 }
             }
             CurrentScreen::P2PoolConfig if app.p2pool_conf_path.is_some() => {
@@ -70,6 +336,112 @@ impl StatusBar {
                 } else {
                     spans.extend(hint("↑↓", "Navigate sidebar"));
                     spans.extend(hint("Enter", "Open file"));
+                    spans.extend(hint("i", "Import from appliance"));
+                    spans.extend(hint("Esc", "Back"));
+                }
+            }
+            CurrentScreen::LNConfig if app.ln_conf_path.is_some() => {
+                if let Some(msg) = &app.ln_config_view.save_message {
+                    spans.push(Span::styled(
+                        format!(" ✓ {msg}  "),
+                        Style::default().fg(Color::Green),
+                    ));
+                } else if let Some(msg) = &app.ln_config_view.warning_message {
+                    spans.push(Span::styled(
+                        format!(" ⚠ {msg}  "),
+                        Style::default().fg(Color::Yellow),
+                    ));
+                } else if app.ln_config_view.editing {
+                    spans.extend(hint("Enter", "Confirm"));
+                    spans.extend(hint("Esc", "Cancel"));
+                } else if app.ln_config_view.sidebar_focused {
+                    spans.extend(hint("↑↓", "Navigate sidebar"));
+                    spans.extend(hint("Enter", "Focus config"));
+                } else {
+                    spans.extend(hint("↑↓", "Navigate"));
+                    spans.extend(hint("Enter", "Edit"));
+                    spans.extend(hint("s", "Save"));
+                    spans.extend(hint("b", "Use bitcoind RPC"));
+                    spans.extend(hint("Esc", "Back"));
+                }
+            }
+            CurrentScreen::LNConfig => {
+                if let Some(msg) = &app.ln_config_view.warning_message {
+                    spans.push(Span::styled(
+                        format!(" ⚠ {msg}  "),
+                        Style::default().fg(Color::Yellow),
+                    ));
+                    spans.extend(hint("Enter", "Try again"));
+                } else {
+                    spans.extend(hint("↑↓", "Navigate sidebar"));
+                    spans.extend(hint("Enter", "Open file"));
+                    spans.extend(hint("Esc", "Back"));
+                }
+            }
+            CurrentScreen::Services => {
+                let service = app.service(app.current_service());
+                if let Some(msg) = &service.view.save_message {
+                    spans.push(Span::styled(
+                        format!(" ✓ {msg}  "),
+                        Style::default().fg(Color::Green),
+                    ));
+                } else if let Some(msg) = &service.view.warning_message {
+                    spans.push(Span::styled(
+                        format!(" ⚠ {msg}  "),
+                        Style::default().fg(Color::Yellow),
+                    ));
+                } else if service.view.editing {
+                    spans.extend(hint("Enter", "Confirm"));
+                    spans.extend(hint("Esc", "Cancel"));
+                } else if service.view.sidebar_focused {
+                    spans.extend(hint("↑↓", "Navigate sidebar"));
+                    spans.extend(hint("←→", "Switch service"));
+                    if service.path.is_some() {
+                        spans.extend(hint("Enter", "Focus config"));
+                    } else {
+                        spans.extend(hint("Enter", "Open file"));
+                    }
+                } else {
+                    spans.extend(hint("↑↓", "Navigate"));
+                    spans.extend(hint("Enter", "Edit"));
+                    spans.extend(hint("s", "Save"));
+                    spans.extend(hint("Esc", "Back"));
+                }
+            }
+            CurrentScreen::Preflight => {
+                if app.preflight_view.sidebar_focused {
+                    spans.extend(hint("↑↓", "Navigate sidebar"));
+                    spans.extend(hint("Enter", "Focus checks"));
+                } else {
+                    spans.extend(hint("↑↓", "Navigate"));
+                    spans.extend(hint("r", "Run again"));
+                    spans.extend(hint("Esc", "Back"));
+                }
+            }
+            CurrentScreen::Plugins => {
+                if app.plugins_view.sidebar_focused {
+                    spans.extend(hint("↑↓", "Navigate sidebar"));
+                    spans.extend(hint("Enter", "Focus plugin"));
+                } else {
+                    spans.extend(hint("←→", "Switch plugin"));
+                    spans.extend(hint("Esc", "Back"));
+                }
+            }
+            CurrentScreen::History => {
+                if app.history_view.filtering {
+                    spans.extend(hint("Enter", "Apply filter"));
+                    spans.extend(hint("⌫", "Delete"));
+                } else if app.history_view.sidebar_focused {
+                    spans.extend(hint("↑↓", "Navigate sidebar"));
+                    spans.extend(hint("Enter", "Focus history"));
+                } else {
+                    spans.extend(hint("↑↓", "Navigate"));
+                    spans.extend(hint("/", "Filter by key"));
+                    if app.history_view.git_log.is_some() {
+                        spans.extend(hint("g", "Journal"));
+                    } else {
+                        spans.extend(hint("g", "git log"));
+                    }
                     spans.extend(hint("Esc", "Back"));
                 }
             }
@@ -91,14 +463,15 @@ impl StatusBar {
                         2 => s.ln_conf_path.is_some(),
                         3 => s.shares_market_conf_path.is_some(),
                         4 => s.settings_dir_override.is_some(),
+                        6 => s.accessibility,
                         _ => false,
                     };
                     spans.extend(hint("↑↓", "Navigate"));
                     if let Some(&(_, kind)) = FIELDS.get(idx) {
-                        let label = if matches!(kind, FieldKind::DirectoryPicker) {
-                            "Browse dir"
-                        } else {
-                            "Browse file"
+                        let label = match kind {
+                            FieldKind::DirectoryPicker => "Browse dir",
+                            FieldKind::FilePicker => "Browse file",
+                            FieldKind::Toggle => "Toggle",
                         };
                         spans.extend(hint("Enter", label));
                     }
@@ -108,14 +481,59 @@ impl StatusBar {
                     spans.extend(hint("Esc", "Back"));
                 }
             }
+            CurrentScreen::BitcoinStatus if app.traffic_monitor.cap_input.is_some() => {
+                spans.extend(hint("0-9", "Data cap in GB"));
+                spans.extend(hint("Enter", "Set maxuploadtarget"));
+                spans.extend(hint("Esc", "Cancel"));
+            }
+            CurrentScreen::BitcoinStatus if app.filter_tester.input.is_some() => {
+                spans.extend(hint("Type", "Height or hash, space, address or script"));
+                spans.extend(hint("Enter", "Test filter"));
+                spans.extend(hint("Esc", "Cancel"));
+            }
+            CurrentScreen::BitcoinStatus if app.tx_tester.input.is_some() => {
+                spans.extend(hint("Paste", "Raw transaction hex"));
+                spans.extend(hint("Enter", "Test"));
+                spans.extend(hint("Esc", "Cancel"));
+            }
             CurrentScreen::BitcoinStatus => {
                 spans.extend(hint("↑↓", "Navigate sidebar"));
                 spans.extend(hint("←→", "Switch tab"));
+                if app.bitcoin_status_tab == UTXO_TAB {
+                    spans.extend(hint("r", "Scan UTXO set"));
+                }
+                if app.bitcoin_status_tab == INDEXES_TAB && filters_enabled(&app.bitcoin_data) {
+                    spans.extend(hint("f", "Test block filter"));
+                }
+                if app.bitcoin_status_tab == TRAFFIC_TAB {
+                    spans.extend(hint("b", "Upload budget"));
+                }
+                if app.bitcoin_status_tab == WALLETS_TAB {
+                    spans.extend(hint("w", "Next wallet"));
+                    spans.extend(hint(
+                        "p",
+                        if app.wallet_monitor.private {
+                            "Hide private keys"
+                        } else {
+                            "Show private keys"
+                        },
+                    ));
+                    spans.extend(hint("x", "Export"));
+                }
+                if app.bitcoin_status_tab == TEST_TX_TAB {
+                    spans.extend(hint("t", "Paste transaction"));
+                    if !app.tx_tester.txs.is_empty() {
+                        spans.extend(hint("r", "Test again"));
+                    }
+                }
                 spans.extend(hint("q", "Quit"));
             }
             _ => {
                 spans.extend(hint("↑↓", "Navigate sidebar"));
                 spans.extend(hint("Enter", "Select"));
+                if app.current_screen == CurrentScreen::Home && app.running_node.is_some() {
+                    spans.extend(hint("o", "Open running node's config"));
+                }
                 spans.extend(hint("q", "Quit"));
             }
         }
@@ -219,6 +637,44 @@ mod tests {
         assert!(output.contains("Back"));
     }
 
+    #[test]
+    fn bitcoin_config_offers_live_test_for_rpc_options() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bitcoin.conf");
+        std::fs::write(&path, "rpcport=8332\n").unwrap();
+        let mut app = App::new();
+        app.current_screen = CurrentScreen::BitcoinConfig;
+        app.bitcoin_data = crate::bitcoin_config::parse_config(&path).unwrap();
+        app.bitcoin_conf_path = Some(path);
+        app.bitcoin_config_view.sidebar_focused = false;
+        app.bitcoin_config_view.selected_index = app
+            .bitcoin_data
+            .iter()
+            .position(|e| e.key == "rpcport")
+            .unwrap();
+        let output = render_status_bar(&app);
+        assert!(output.contains("T  Test connection"), "{output}");
+
+        app.bitcoin_config_view.selected_index = app
+            .bitcoin_data
+            .iter()
+            .position(|e| e.key == "txindex")
+            .unwrap();
+        assert!(!render_status_bar(&app).contains("Test connection"));
+    }
+
+    #[test]
+    fn bitcoin_config_reset_confirmation_asks_yes() {
+        let mut app = App::new();
+        app.current_screen = CurrentScreen::BitcoinConfig;
+        app.bitcoin_conf_path = Some(std::path::PathBuf::from("/tmp/bitcoin.conf"));
+        app.bitcoin_config_view.sidebar_focused = false;
+        app.bitcoin_config_view.confirm_reset = Some(crate::bitcoin_config::ConfigCategory::RPC);
+        let output = render_status_bar(&app);
+        assert!(output.contains("Reset all RPC options"));
+        assert!(output.contains("Yes"));
+    }
+
     #[test]
     fn bitcoin_config_with_file_save_message_shows_saved() {
         let mut app = App::new();
@@ -237,6 +693,50 @@ mod tests {
         assert!(output.contains("Switch tab"));
     }
 
+    #[test]
+    fn kiosk_only_offers_tabs_and_quit() {
+        let mut app = App::new();
+        app.current_screen = CurrentScreen::BitcoinStatus;
+        app.kiosk = Some(crate::kiosk::Kiosk::default());
+        let output = render_status_bar(&app);
+        assert!(output.contains("read only"));
+        assert!(output.contains("Ctrl-C"));
+        assert!(!output.contains("Navigate sidebar"));
+    }
+
+    #[test]
+    fn utxo_tab_offers_a_scan() {
+        let mut app = App::new();
+        app.current_screen = CurrentScreen::BitcoinStatus;
+        assert!(!render_status_bar(&app).contains("Scan UTXO set"));
+        app.bitcoin_status_tab = UTXO_TAB;
+        assert!(render_status_bar(&app).contains("Scan UTXO set"));
+    }
+
+    #[test]
+    fn wallets_tab_offers_private_keys_and_export() {
+        let mut app = App::new();
+        app.current_screen = CurrentScreen::BitcoinStatus;
+        app.bitcoin_status_tab = WALLETS_TAB;
+        let output = render_status_bar(&app);
+        assert!(output.contains("Show private keys"));
+        assert!(output.contains("Export"));
+        app.wallet_monitor.private = true;
+        assert!(render_status_bar(&app).contains("Hide private keys"));
+    }
+
+    #[test]
+    fn traffic_tab_prompts_for_the_data_cap() {
+        let mut app = App::new();
+        app.current_screen = CurrentScreen::BitcoinStatus;
+        app.bitcoin_status_tab = TRAFFIC_TAB;
+        assert!(render_status_bar(&app).contains("Upload budget"));
+        app.traffic_monitor.cap_input = Some(String::new());
+        let output = render_status_bar(&app);
+        assert!(output.contains("Set maxuploadtarget"));
+        assert!(!output.contains("Quit"));
+    }
+
     #[test]
     fn default_screen_shows_select() {
         let mut app = App::new();
@@ -363,4 +863,33 @@ mod tests {
         let output = render_status_bar(&app);
         assert!(output.contains("Clear"));
     }
+
+    #[test]
+    fn ln_config_no_file_shows_open_file() {
+        let mut app = App::new();
+        app.current_screen = CurrentScreen::LNConfig;
+        let output = render_status_bar(&app);
+        assert!(output.contains("Open file"));
+    }
+
+    #[test]
+    fn ln_config_with_file_browsing_shows_edit_save_back() {
+        let mut app = App::new();
+        app.current_screen = CurrentScreen::LNConfig;
+        app.ln_conf_path = Some(std::path::PathBuf::from("/tmp/lnd.conf"));
+        app.ln_config_view.sidebar_focused = false;
+        let output = render_status_bar(&app);
+        assert!(output.contains("Edit"));
+        assert!(output.contains("Save"));
+        assert!(output.contains("Back"));
+    }
+
+    #[test]
+    fn services_without_file_shows_switch_and_open_file() {
+        let mut app = App::new();
+        app.current_screen = CurrentScreen::Services;
+        let output = render_status_bar(&app);
+        assert!(output.contains("Switch service"));
+        assert!(output.contains("Open file"));
+    }
 }
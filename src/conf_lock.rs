@@ -0,0 +1,205 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::process_args::PROC_DIR;
+use std::fmt;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+/// The lock file kept next to a config while it is open for editing.
+#[must_use]
+pub fn lock_path(conf: &Path) -> PathBuf {
+    let mut name = conf.as_os_str().to_owned();
+    name.push(".lock");
+    PathBuf::from(name)
+}
+
+/// Name of this machine, as the lock files record it.
+#[must_use]
+pub fn hostname() -> String {
+    ["/proc/sys/kernel/hostname", "/etc/hostname"]
+        .iter()
+        .find_map(|path| {
+            let name = std::fs::read_to_string(path).ok()?;
+            let name = name.trim();
+            (!name.is_empty()).then(|| name.to_string())
+        })
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .unwrap_or_else(|| "unknown host".to_string())
+}
+
+/// The PDM holding a lock.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockHolder {
+    pub pid: u32,
+    pub host: String,
+}
+
+impl LockHolder {
+    /// This PDM.
+    #[must_use]
+    pub fn current() -> Self {
+        Self {
+            pid: std::process::id(),
+            host: hostname(),
+        }
+    }
+
+    /// Reads a lock file's `pid=` and `host=` lines.
+    #[must_use]
+    pub fn parse(text: &str) -> Option<Self> {
+        let field = |key: &str| {
+            text.lines()
+                .find_map(|l| l.strip_prefix(key)?.strip_prefix('='))
+                .map(str::trim)
+        };
+        Some(Self {
+            pid: field("pid")?.parse().ok()?,
+            host: field("host")?.to_string(),
+        })
+    }
+
+    /// The lock file's content.
+    #[must_use]
+    pub fn to_text(&self) -> String {
+        format!("pid={}\nhost={}\n", self.pid, self.host)
+    }
+
+    /// Whether the holder is a process of this machine that has exited;
+    /// one on another machine is taken as alive.
+    fn gone(&self, proc_dir: &Path) -> bool {
+        self.host == hostname() && !proc_dir.join(self.pid.to_string()).exists()
+    }
+}
+
+impl fmt::Display for LockHolder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PDM (pid {}) on {}", self.pid, self.host)
+    }
+}
+
+/// An advisory lock on a config, so two PDMs do not overwrite each
+/// other's saves. Only other PDMs honor it. Dropping it removes the lock
+/// file.
+#[derive(Debug)]
+pub struct ConfLock {
+    path: PathBuf,
+}
+
+impl ConfLock {
+    /// Takes the lock on `conf`, replacing one left by a PDM of this
+    /// machine that exited. Gives `Ok(None)` when no lock file can be
+    /// written next to `conf`, as in a read-only directory.
+    ///
+    /// # Errors
+    /// Returns the holder when another PDM has `conf` open.
+    pub fn acquire(conf: &Path) -> Result<Option<Self>, LockHolder> {
+        Self::acquire_in(conf, Path::new(PROC_DIR))
+    }
+
+    fn acquire_in(conf: &Path, proc_dir: &Path) -> Result<Option<Self>, LockHolder> {
+        let path = lock_path(conf);
+        let me = LockHolder::current();
+        for _ in 0..2 {
+            match std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+            {
+                Ok(mut file) => {
+                    if file.write_all(me.to_text().as_bytes()).is_err() {
+                        let _ = std::fs::remove_file(&path);
+                        return Ok(None);
+                    }
+                    return Ok(Some(Self { path }));
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    let text = std::fs::read_to_string(&path).unwrap_or_default();
+                    match LockHolder::parse(&text) {
+                        Some(holder) if holder == me => return Ok(Some(Self { path })),
+                        Some(holder) if !holder.gone(proc_dir) => return Err(holder),
+                        // Stale or unreadable: take it over
+                        _ => {
+                            if std::fs::remove_file(&path).is_err() {
+                                return Ok(None);
+                            }
+                        }
+                    }
+                }
+                Err(_) => return Ok(None),
+            }
+        }
+        Ok(None)
+    }
+
+    /// Path of the lock file.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for ConfLock {
+    fn drop(&mut self) {
+        // Leave a lock another PDM took over meanwhile
+        let ours = std::fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|text| LockHolder::parse(&text))
+            .is_some_and(|holder| holder == LockHolder::current());
+        if ours {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lock_keeps_other_pdms_out_until_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let conf = dir.path().join("bitcoin.conf");
+        let lock = ConfLock::acquire(&conf).unwrap().unwrap();
+        assert_eq!(lock.path(), dir.path().join("bitcoin.conf.lock"));
+        assert_eq!(
+            LockHolder::parse(&std::fs::read_to_string(lock.path()).unwrap()),
+            Some(LockHolder::current())
+        );
+
+        let other = LockHolder {
+            pid: 1,
+            host: "node-b".to_string(),
+        };
+        std::fs::write(lock.path(), other.to_text()).unwrap();
+        assert_eq!(ConfLock::acquire(&conf).unwrap_err(), other);
+        assert_eq!(other.to_string(), "PDM (pid 1) on node-b");
+        drop(lock);
+        assert!(dir.path().join("bitcoin.conf.lock").exists());
+    }
+
+    #[test]
+    fn lock_of_an_exited_pdm_is_taken_over() {
+        let dir = tempfile::tempdir().unwrap();
+        let conf = dir.path().join("bitcoin.conf");
+        let exited = LockHolder {
+            pid: 4242,
+            host: hostname(),
+        };
+        std::fs::write(lock_path(&conf), exited.to_text()).unwrap();
+        let proc_dir = dir.path().join("proc");
+        std::fs::create_dir_all(proc_dir.join("4242")).unwrap();
+        assert_eq!(ConfLock::acquire_in(&conf, &proc_dir).unwrap_err(), exited);
+
+        std::fs::remove_dir(proc_dir.join("4242")).unwrap();
+        let lock = ConfLock::acquire_in(&conf, &proc_dir).unwrap().unwrap();
+        drop(lock);
+        assert!(!lock_path(&conf).exists());
+        assert!(
+            ConfLock::acquire(&dir.path().join("missing/bitcoin.conf"))
+                .unwrap()
+                .is_none()
+        );
+    }
+}
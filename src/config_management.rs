@@ -0,0 +1,292 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_config::{ConfigEntry, config_line};
+use crate::docker_compose::{HEADER, write_generated};
+use crate::secrets::is_secret;
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+/// Jinja template of bitcoin.conf the Ansible tasks render.
+pub const ANSIBLE_TEMPLATE: &str = "bitcoin.conf.j2";
+
+/// Ansible task file, written next to bitcoin.conf.
+pub const ANSIBLE_TASKS: &str = "bitcoind.ansible.yml";
+
+/// NixOS module fragment, written next to bitcoin.conf.
+pub const NIX_MODULE: &str = "bitcoind.nix";
+
+/// bitcoind options with a typed `services.bitcoind` attribute: the
+/// option, the attribute and whether it holds a number rather than a
+/// string.
+const NIX_OPTIONS: &[(&str, &str, bool)] = &[
+    ("port", "port", true),
+    ("rpcport", "rpc.port", true),
+    ("dbcache", "dbCache", true),
+    ("prune", "prune", true),
+    ("datadir", "dataDir", false),
+];
+
+/// Enabled entries in the order bitcoin.conf lists them: top level
+/// first, then each network section.
+fn by_section(entries: &[ConfigEntry]) -> Vec<(Option<&str>, Vec<&ConfigEntry>)> {
+    let mut top = Vec::new();
+    let mut sections: BTreeMap<&str, Vec<&ConfigEntry>> = BTreeMap::new();
+    for entry in entries.iter().filter(|e| e.enabled) {
+        match &entry.section {
+            None => top.push(entry),
+            Some(section) => sections.entry(section).or_default().push(entry),
+        }
+    }
+    let mut out = vec![(None, top)];
+    out.extend(sections.into_iter().map(|(s, e)| (Some(s), e)));
+    out
+}
+
+/// Ansible variable a secret is read from, such as
+/// `bitcoind_test_rpcpassword`.
+fn secret_variable(section: Option<&str>, key: &str) -> String {
+    match section {
+        Some(section) => format!("bitcoind_{section}_{key}"),
+        None => format!("bitcoind_{key}"),
+    }
+}
+
+/// Renders the enabled entries as a Jinja template of bitcoin.conf.
+/// Secrets become variables to keep in Ansible Vault. Returns the
+/// template and the variables it needs.
+#[must_use]
+pub fn generate_ansible_template(entries: &[ConfigEntry]) -> (String, Vec<String>) {
+    let mut out = String::new();
+    let mut variables = Vec::new();
+    let _ = writeln!(out, "{HEADER}; render with the tasks in {ANSIBLE_TASKS}");
+    for (section, entries) in by_section(entries) {
+        if let Some(section) = section {
+            let _ = writeln!(out, "\n[{section}]");
+        }
+        for entry in entries {
+            if is_secret(&entry.key) {
+                let variable = secret_variable(section, &entry.key);
+                let _ = writeln!(out, "{}={{{{ {variable} }}}}", entry.key);
+                variables.push(variable);
+                continue;
+            }
+            let line = config_line(entry);
+            // Keep Jinja from reading braces of the value
+            if ["{{", "{%", "{#"].iter().any(|s| line.contains(s)) {
+                let _ = writeln!(out, "{{% raw %}}{line}{{% endraw %}}");
+            } else {
+                let _ = writeln!(out, "{line}");
+            }
+        }
+    }
+    (out, variables)
+}
+
+/// Renders the tasks installing the template as `bitcoin_conf` and
+/// restarting bitcoind when it changed. `variables` are listed so they
+/// can be added to the vault.
+#[must_use]
+pub fn generate_ansible_tasks(bitcoin_conf: &Path, variables: &[String]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "{HEADER} from {}", bitcoin_conf.display());
+    if !variables.is_empty() {
+        out.push_str("# Define in Ansible Vault:\n");
+        for variable in variables {
+            let _ = writeln!(out, "#   {variable}");
+        }
+    }
+    out.push_str("- name: Render bitcoin.conf\n");
+    out.push_str("  ansible.builtin.template:\n");
+    let _ = writeln!(out, "    src: {ANSIBLE_TEMPLATE}");
+    let _ = writeln!(out, "    dest: {}", bitcoin_conf.display());
+    out.push_str("    owner: \"{{ bitcoind_user | default('bitcoin') }}\"\n");
+    out.push_str("    group: \"{{ bitcoind_group | default('bitcoin') }}\"\n");
+    out.push_str("    mode: \"0600\"\n");
+    out.push_str("  register: bitcoin_conf\n");
+    out.push('\n');
+    out.push_str("- name: Restart bitcoind for the new bitcoin.conf\n");
+    out.push_str("  ansible.builtin.systemd:\n");
+    out.push_str("    name: \"{{ bitcoind_service | default('bitcoind') }}\"\n");
+    out.push_str("    state: restarted\n");
+    out.push_str("  when: bitcoin_conf.changed\n");
+    out
+}
+
+/// `text` inside a Nix `''` string, where `''` and `${` are special.
+fn nix_indented(text: &str) -> String {
+    text.replace("''", "'''").replace("${", "''${")
+}
+
+/// `text` as a Nix `"` string.
+fn nix_string(text: &str) -> String {
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace("${", "\\${");
+    format!("\"{escaped}\"")
+}
+
+/// Renders the enabled entries as a NixOS `services.bitcoind` fragment:
+/// options the module has an attribute for are set through it, the rest
+/// go to `extraConfig`. Secrets are left out, the module has its own
+/// ways to keep them out of the Nix store.
+#[must_use]
+pub fn generate_nix(bitcoin_conf: &Path, entries: &[ConfigEntry]) -> String {
+    let mut attributes = Vec::new();
+    let mut extra = String::new();
+    let mut secrets = Vec::new();
+    for (section, entries) in by_section(entries) {
+        if let Some(section) = section {
+            let _ = writeln!(extra, "[{section}]");
+        }
+        for entry in entries {
+            if is_secret(&entry.key) {
+                secrets.push(entry.key.as_str());
+                continue;
+            }
+            let typed = NIX_OPTIONS
+                .iter()
+                .find(|(key, _, _)| section.is_none() && !entry.negated && *key == entry.key);
+            match typed {
+                Some((_, attribute, true)) if entry.value.parse::<u64>().is_ok() => {
+                    attributes.push(format!("{attribute} = {};", entry.value));
+                }
+                Some((_, attribute, false)) => {
+                    attributes.push(format!("{attribute} = {};", nix_string(&entry.value)));
+                }
+                _ if section.is_none() && entry.key == "testnet" && entry.value == "1" => {
+                    attributes.push("testnet = true;".to_string());
+                }
+                _ => {
+                    let _ = writeln!(extra, "{}", config_line(entry));
+                }
+            }
+        }
+    }
+
+    let mut out = String::new();
+    let _ = writeln!(out, "{HEADER} from {}", bitcoin_conf.display());
+    if !secrets.is_empty() {
+        secrets.sort_unstable();
+        secrets.dedup();
+        let _ = writeln!(
+            out,
+            "# Left out: {}. Use rpc.users.<name>.passwordHMAC or a file outside the store.",
+            secrets.join(", ")
+        );
+    }
+    out.push_str("{\n");
+    out.push_str("  services.bitcoind.\"main\" = {\n");
+    out.push_str("    enable = true;\n");
+    for attribute in attributes {
+        let _ = writeln!(out, "    {attribute}");
+    }
+    if !extra.is_empty() {
+        out.push_str("    extraConfig = ''\n");
+        for line in nix_indented(&extra).lines() {
+            if line.is_empty() {
+                out.push('\n');
+            } else {
+                let _ = writeln!(out, "      {line}");
+            }
+        }
+        out.push_str("    '';\n");
+    }
+    out.push_str("  };\n");
+    out.push_str("}\n");
+    out
+}
+
+/// Writes the Ansible template and tasks and the NixOS fragment next to
+/// `bitcoin_conf` and returns their paths.
+///
+/// # Errors
+/// Returns an error if a file cannot be written or holds one PDM did not
+/// generate.
+pub fn export_snippets(bitcoin_conf: &Path, entries: &[ConfigEntry]) -> Result<Vec<PathBuf>> {
+    let dir = bitcoin_conf.parent().unwrap_or(Path::new("."));
+    let (template, variables) = generate_ansible_template(entries);
+    let files = [
+        (ANSIBLE_TEMPLATE, template),
+        (
+            ANSIBLE_TASKS,
+            generate_ansible_tasks(bitcoin_conf, &variables),
+        ),
+        (NIX_MODULE, generate_nix(bitcoin_conf, entries)),
+    ];
+    let mut written = Vec::new();
+    for (name, content) in files {
+        let path = dir.join(name);
+        write_generated(&path, &content)?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(content: &str) -> Vec<ConfigEntry> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bitcoin.conf");
+        std::fs::write(&path, content).unwrap();
+        crate::bitcoin_config::parse_config(&path).unwrap()
+    }
+
+    #[test]
+    fn ansible_template_reads_secrets_from_the_vault() {
+        let (template, variables) = generate_ansible_template(&entries(
+            "server=1\nrpcauth=alice:00$11\nuacomment={{x}}\n\
+             [test]\nrpcpassword=hunter2\n",
+        ));
+        assert!(template.starts_with(HEADER));
+        assert!(template.contains("server=1\n"));
+        assert!(template.contains("rpcauth={{ bitcoind_rpcauth }}\n"));
+        assert!(template.contains("{% raw %}uacomment={{x}}{% endraw %}\n"));
+        assert!(template.contains("\n[test]\nrpcpassword={{ bitcoind_test_rpcpassword }}\n"));
+        assert!(!template.contains("hunter2"));
+        assert_eq!(variables, ["bitcoind_rpcauth", "bitcoind_test_rpcpassword"]);
+
+        let tasks = generate_ansible_tasks(Path::new("/etc/bitcoin/bitcoin.conf"), &variables);
+        assert!(tasks.contains("#   bitcoind_test_rpcpassword\n"));
+        assert!(tasks.contains("    dest: /etc/bitcoin/bitcoin.conf\n"));
+        assert!(tasks.contains("  when: bitcoin_conf.changed\n"));
+    }
+
+    #[test]
+    fn nix_fragment_uses_module_options_where_it_has_them() {
+        let nix = generate_nix(
+            Path::new("/etc/bitcoin/bitcoin.conf"),
+            &entries(
+                "dbcache=4000\ndatadir=/srv/bitcoin\nprune=auto\ntxindex=1\nrpcpassword=x\n\
+                 uacomment=${NODE}\n[test]\nport=18444\n",
+            ),
+        );
+        assert!(nix.contains("# Left out: rpcpassword."));
+        assert!(nix.contains("    dbCache = 4000;\n"));
+        assert!(nix.contains("    dataDir = \"/srv/bitcoin\";\n"));
+        assert!(nix.contains("      prune=auto\n"));
+        assert!(nix.contains("      txindex=1\n"));
+        assert!(nix.contains("      uacomment=''${NODE}\n"));
+        assert!(nix.contains("      [test]\n      port=18444\n"));
+        assert!(!nix.contains("    port = "));
+        assert!(nix.ends_with("    '';\n  };\n}\n"));
+    }
+
+    #[test]
+    fn export_writes_template_tasks_and_module() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bitcoin.conf");
+        let written = export_snippets(&path, &entries("server=1\n")).unwrap();
+        assert_eq!(written.len(), 3);
+        assert!(written.iter().all(|p| p.exists()));
+
+        std::fs::write(dir.path().join(NIX_MODULE), "{ }\n").unwrap();
+        assert!(export_snippets(&path, &entries("server=1\n")).is_err());
+    }
+}
@@ -0,0 +1,357 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use serde_json::{Value, json};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+#[cfg(unix)]
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+
+/// How long a client may take to send its request or read the reply;
+/// the event loop waits meanwhile.
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Longest request read; a config change fits well within it.
+const MAX_REQUEST: u64 = 64 * 1024;
+
+/// What a script asks of the open PDM session, one JSON object per line
+/// such as `{"command": "set", "key": "dbcache", "value": "1000"}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ControlRequest {
+    /// The value bitcoind gets for an option, `null` when unset
+    Get(String),
+    /// Sets an option in the loaded bitcoin.conf, as the editor does
+    Set(String, String),
+    /// Puts an option back to its default
+    Unset(String),
+    /// Every option set, by name
+    List,
+    /// Writes the loaded bitcoin.conf
+    Save,
+    /// Polls bitcoind again for the screen shown
+    Refresh,
+}
+
+impl ControlRequest {
+    /// Reads the words of `pdm ctl`, such as `set dbcache 1000`.
+    ///
+    /// # Errors
+    /// Returns the usage when the words are not a request.
+    pub fn from_args(args: &[String]) -> Result<Self, String> {
+        let words: Vec<&str> = args.iter().map(String::as_str).collect();
+        match words.as_slice() {
+            ["get", key] => Ok(Self::Get((*key).to_string())),
+            ["set", key, value @ ..] if !value.is_empty() => {
+                Ok(Self::Set((*key).to_string(), value.join(" ")))
+            }
+            ["unset", key] => Ok(Self::Unset((*key).to_string())),
+            ["list"] => Ok(Self::List),
+            ["save"] => Ok(Self::Save),
+            ["refresh"] => Ok(Self::Refresh),
+            _ => Err(
+                "usage: pdm ctl get <option> | set <option> <value> | unset <option> | list \
+                 | save | refresh"
+                    .to_string(),
+            ),
+        }
+    }
+
+    /// Reads one request line.
+    ///
+    /// # Errors
+    /// Returns what is wrong with the line.
+    pub fn from_json(line: &str) -> Result<Self, String> {
+        let request: Value = serde_json::from_str(line).map_err(|e| format!("not JSON: {e}"))?;
+        let text = |key: &str| {
+            request
+                .get(key)
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .ok_or_else(|| format!("missing {key}"))
+        };
+        match text("command")?.as_str() {
+            "get" => Ok(Self::Get(text("key")?)),
+            "set" => Ok(Self::Set(text("key")?, text("value")?)),
+            "unset" => Ok(Self::Unset(text("key")?)),
+            "list" => Ok(Self::List),
+            "save" => Ok(Self::Save),
+            "refresh" => Ok(Self::Refresh),
+            other => Err(format!("unknown command {other}")),
+        }
+    }
+
+    /// The request as one line.
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        match self {
+            Self::Get(key) => json!({"command": "get", "key": key}),
+            Self::Set(key, value) => json!({"command": "set", "key": key, "value": value}),
+            Self::Unset(key) => json!({"command": "unset", "key": key}),
+            Self::List => json!({"command": "list"}),
+            Self::Save => json!({"command": "save"}),
+            Self::Refresh => json!({"command": "refresh"}),
+        }
+        .to_string()
+    }
+}
+
+/// The line answering a request.
+#[must_use]
+pub fn reply_line(result: &Result<Value, String>) -> String {
+    match result {
+        Ok(result) => json!({"ok": true, "result": result}),
+        Err(error) => json!({"ok": false, "error": error}),
+    }
+    .to_string()
+}
+
+/// Reads a reply line.
+///
+/// # Errors
+/// Returns the error the reply carries, or why it cannot be read.
+pub fn parse_reply(line: &str) -> Result<Value, String> {
+    let reply: Value = serde_json::from_str(line).map_err(|e| format!("unreadable reply: {e}"))?;
+    if reply.get("ok").and_then(Value::as_bool) == Some(true) {
+        Ok(reply.get("result").cloned().unwrap_or(Value::Null))
+    } else {
+        Err(reply
+            .get("error")
+            .and_then(Value::as_str)
+            .unwrap_or("request failed")
+            .to_string())
+    }
+}
+
+/// A Unix socket taking [`ControlRequest`]s while the TUI is open.
+/// Accepting never blocks, so the event loop can answer every tick. Only
+/// the user running PDM may connect, since the config holds secrets.
+#[derive(Debug)]
+pub struct ControlSocket {
+    #[cfg(unix)]
+    listener: UnixListener,
+    path: PathBuf,
+}
+
+impl ControlSocket {
+    /// Listens on `path`, replacing a socket left by a PDM that exited.
+    ///
+    /// # Errors
+    /// Returns why `path` cannot be listened on, such as another PDM
+    /// answering there or a file that is not a socket.
+    #[cfg(unix)]
+    pub fn bind(path: &Path) -> Result<Self, String> {
+        use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+        let fail = |e: std::io::Error| format!("control socket {}: {e}", path.display());
+        if let Ok(metadata) = std::fs::symlink_metadata(path) {
+            if !metadata.file_type().is_socket() {
+                return Err(format!(
+                    "control socket {}: exists and is not a socket",
+                    path.display()
+                ));
+            }
+            if UnixStream::connect(path).is_ok() {
+                return Err(format!(
+                    "control socket {}: another PDM is listening",
+                    path.display()
+                ));
+            }
+            std::fs::remove_file(path).map_err(fail)?;
+        }
+        // Bound in a directory only the user can enter and moved into place
+        // once its mode is set, so no one else can ever connect
+        let parent = path
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        let private = tempfile::Builder::new()
+            .prefix(".pdm-control-")
+            .tempdir_in(parent)
+            .map_err(fail)?;
+        let staged = private.path().join("socket");
+        let listener = UnixListener::bind(&staged).map_err(fail)?;
+        listener.set_nonblocking(true).map_err(fail)?;
+        std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o600)).map_err(fail)?;
+        std::fs::rename(&staged, path).map_err(fail)?;
+        Ok(Self {
+            listener,
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// Unix sockets are not available here.
+    ///
+    /// # Errors
+    /// Always.
+    #[cfg(not(unix))]
+    pub fn bind(path: &Path) -> Result<Self, String> {
+        Err(format!(
+            "control socket {}: needs a Unix system",
+            path.display()
+        ))
+    }
+
+    /// Path listened on.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Answers every request waiting with what `handle` returns.
+    #[cfg(unix)]
+    pub fn serve(&self, mut handle: impl FnMut(ControlRequest) -> Result<Value, String>) {
+        loop {
+            match self.listener.accept() {
+                Ok((stream, _)) => {
+                    // A client that hangs up early gets no answer
+                    let _ = respond(stream, &mut handle);
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(_) => break,
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    pub fn serve(&self, _handle: impl FnMut(ControlRequest) -> Result<Value, String>) {}
+}
+
+impl Drop for ControlSocket {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Reads one request from `stream` and replies to it.
+#[cfg(unix)]
+fn respond(
+    mut stream: UnixStream,
+    handle: &mut impl FnMut(ControlRequest) -> Result<Value, String>,
+) -> std::io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut line = String::new();
+    BufReader::new((&stream).take(MAX_REQUEST)).read_line(&mut line)?;
+    let result = ControlRequest::from_json(&line).and_then(handle);
+    writeln!(stream, "{}", reply_line(&result))?;
+    stream.flush()
+}
+
+/// Sends `request` to the PDM listening on `path` and returns its answer.
+///
+/// # Errors
+/// Returns why PDM cannot be reached or the error it answered with.
+#[cfg(unix)]
+pub fn send(path: &Path, request: &ControlRequest) -> Result<Value, String> {
+    let fail = |e: std::io::Error| format!("{}: {e}; is PDM open?", path.display());
+    let mut stream = UnixStream::connect(path).map_err(fail)?;
+    stream
+        .set_read_timeout(Some(REQUEST_TIMEOUT * 5))
+        .map_err(fail)?;
+    writeln!(stream, "{}", request.to_json()).map_err(fail)?;
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line).map_err(fail)?;
+    parse_reply(&line)
+}
+
+/// Unix sockets are not available here.
+///
+/// # Errors
+/// Always.
+#[cfg(not(unix))]
+pub fn send(path: &Path, _request: &ControlRequest) -> Result<Value, String> {
+    Err(format!("{}: needs a Unix system", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(words: &[&str]) -> Vec<String> {
+        words.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn requests_read_from_words_and_json_alike() {
+        let set = ControlRequest::from_args(&args(&["set", "uacomment", "my", "node"])).unwrap();
+        assert_eq!(
+            set,
+            ControlRequest::Set("uacomment".to_string(), "my node".to_string())
+        );
+        assert_eq!(ControlRequest::from_json(&set.to_json()), Ok(set));
+        assert_eq!(
+            ControlRequest::from_args(&args(&["refresh"])),
+            Ok(ControlRequest::Refresh)
+        );
+        assert!(ControlRequest::from_args(&args(&["set", "dbcache"])).is_err());
+        assert!(ControlRequest::from_json(r#"{"command": "reboot"}"#).is_err());
+        assert!(ControlRequest::from_json(r#"{"command": "get"}"#).is_err());
+
+        assert_eq!(parse_reply(&reply_line(&Ok(json!(1000)))), Ok(json!(1000)));
+        assert_eq!(
+            parse_reply(&reply_line(&Err("no".to_string()))),
+            Err("no".to_string())
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn socket_answers_requests_while_served() {
+        use std::time::Instant;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pdm.sock");
+        let socket = ControlSocket::bind(&path).unwrap();
+        assert!(
+            ControlSocket::bind(&path)
+                .unwrap_err()
+                .contains("another PDM")
+        );
+
+        let client = std::thread::spawn({
+            let path = path.clone();
+            move || send(&path, &ControlRequest::Get("dbcache".to_string()))
+        });
+        let mut seen = Vec::new();
+        let start = Instant::now();
+        while !client.is_finished() && start.elapsed() < Duration::from_secs(5) {
+            socket.serve(|request| {
+                seen.push(request);
+                Ok(json!("1000"))
+            });
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(client.join().unwrap(), Ok(json!("1000")));
+        assert_eq!(seen, [ControlRequest::Get("dbcache".to_string())]);
+
+        drop(socket);
+        assert!(!path.exists());
+        assert!(send(&path, &ControlRequest::List).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn socket_is_private_and_never_replaces_other_files() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pdm.sock");
+        std::fs::write(&path, "notes").unwrap();
+        assert!(
+            ControlSocket::bind(&path)
+                .unwrap_err()
+                .contains("not a socket")
+        );
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "notes");
+
+        std::fs::remove_file(&path).unwrap();
+        let socket = ControlSocket::bind(&path).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        // Only the socket is left next to it
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+        drop(socket);
+    }
+}
@@ -0,0 +1,262 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_config::{ConfigEntry, enabled_value};
+use crate::diagnostics::Severity;
+use crate::peers::format_age;
+use crate::rpc::{RpcError, cookie_path, net_datadir};
+use std::io::ErrorKind;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Options about the RPC cookie.
+pub const COOKIE_OPTIONS: &[&str] = &["rpccookiefile", "rpccookieperms"];
+
+/// One finding about the cookie file; `severity` is `None` for plain
+/// information.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CookieNote {
+    pub severity: Option<Severity>,
+    pub text: String,
+}
+
+impl CookieNote {
+    fn info(text: impl Into<String>) -> Self {
+        Self {
+            severity: None,
+            text: text.into(),
+        }
+    }
+
+    fn warning(text: impl Into<String>) -> Self {
+        Self {
+            severity: Some(Severity::Warning),
+            text: text.into(),
+        }
+    }
+
+    fn error(text: impl Into<String>) -> Self {
+        Self {
+            severity: Some(Severity::Error),
+            text: text.into(),
+        }
+    }
+}
+
+/// Mode bitcoind gives the cookie for an `rpccookieperms` value.
+fn expected_mode(perms: &str) -> Option<u32> {
+    match perms {
+        "owner" => Some(0o600),
+        "group" => Some(0o640),
+        "all" => Some(0o644),
+        _ => None,
+    }
+}
+
+/// Where the cookie for `chain` is, why there, and whether its mode,
+/// content and age make sense.
+#[must_use]
+pub fn inspect_cookie(entries: &[ConfigEntry], chain: &str, now: SystemTime) -> Vec<CookieNote> {
+    let path = cookie_path(entries, chain);
+    let mut notes = vec![CookieNote::info(format!("Cookie: {}", path.display()))];
+    if let (Some(user), Some(_)) = (
+        enabled_value(entries, "rpcuser"),
+        enabled_value(entries, "rpcpassword"),
+    ) {
+        notes.push(CookieNote::info(format!(
+            "rpcpassword is set, so bitcoind writes no cookie and PDM logs in as {user}"
+        )));
+        return notes;
+    }
+    notes.push(CookieNote::info(
+        match enabled_value(entries, "rpccookiefile") {
+            None => format!(
+                "No rpccookiefile, so bitcoind writes .cookie in the {chain} data directory"
+            ),
+            Some(file) if Path::new(file).is_absolute() => format!(
+                "rpccookiefile={file} is absolute: every chain writes this one file, so two nodes \
+             on different chains overwrite each other's cookie"
+            ),
+            Some(file) => format!(
+                "rpccookiefile={file} is relative to the {chain} data directory, {}",
+                net_datadir(entries, chain).display()
+            ),
+        },
+    ));
+
+    let metadata = match std::fs::metadata(&path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            notes.push(CookieNote::error(
+                "Not found: bitcoind is not running, runs with another datadir or chain, \
+                 or was started with rpcpassword",
+            ));
+            return notes;
+        }
+        Err(e) => {
+            notes.push(CookieNote::error(format!("Cannot inspect it: {e}")));
+            return notes;
+        }
+    };
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = metadata.permissions().mode() & 0o777;
+        let perms = enabled_value(entries, "rpccookieperms").unwrap_or("owner");
+        notes.push(CookieNote::info(format!("Mode {mode:04o}")));
+        if mode & 0o004 != 0 && perms != "all" {
+            notes.push(CookieNote::warning(
+                "Every user on this machine can read it, and so control bitcoind; \
+                 set rpccookieperms=owner or group",
+            ));
+        }
+        match expected_mode(perms) {
+            Some(expected) if expected != mode => notes.push(CookieNote::warning(format!(
+                "rpccookieperms={perms} gives {expected:04o}; bitcoind 28 and later apply it \
+                 the next time they write the cookie"
+            ))),
+            Some(_) => {}
+            None => notes.push(CookieNote::error(format!(
+                "rpccookieperms={perms} is not owner, group or all"
+            ))),
+        }
+    }
+
+    #[cfg(not(unix))]
+    notes.push(CookieNote::info(
+        "Windows ACLs decide who can read it, which PDM does not check; \
+         rpccookieperms has no effect here",
+    ));
+
+    match std::fs::read_to_string(&path) {
+        Ok(content) if content.trim_end().starts_with("__cookie__:") => {}
+        Ok(_) => notes.push(CookieNote::warning(
+            "It does not hold __cookie__:<password>, so bitcoind did not write it",
+        )),
+        Err(e) if e.kind() == ErrorKind::PermissionDenied => notes.push(CookieNote::error(
+            "PDM cannot read it: run PDM as the user bitcoind runs as, or set \
+             rpccookieperms=group and add your user to that user's group",
+        )),
+        Err(e) => notes.push(CookieNote::error(format!("Cannot read it: {e}"))),
+    }
+
+    if let Ok(modified) = metadata.modified() {
+        let secs = |t: SystemTime| t.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        notes.push(CookieNote::info(format!(
+            "Written {} ago, when bitcoind last started",
+            format_age(secs(modified), secs(now))
+        )));
+    }
+    notes
+}
+
+/// What bitcoind answering `uptime` with the cookie says about it.
+#[must_use]
+pub fn node_note(result: &Result<u64, RpcError>, endpoint: &str) -> CookieNote {
+    match result {
+        Ok(uptime) => CookieNote::info(format!(
+            "bitcoind at {endpoint} accepts it, up {}",
+            format_age(0, *uptime)
+        )),
+        Err(RpcError::Unauthorized) => CookieNote::error(format!(
+            "bitcoind at {endpoint} rejects it: it is stale, left by an earlier run, or was \
+             written by another bitcoind than the one answering"
+        )),
+        Err(RpcError::Connect(..)) => CookieNote::warning(format!(
+            "Nothing answers at {endpoint}; a cookie left by a crashed bitcoind is replaced \
+             when it next starts"
+        )),
+        Err(e) => CookieNote::warning(format!("bitcoind at {endpoint}: {e}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(dir: &Path, extra: &str) -> Vec<ConfigEntry> {
+        crate::bitcoin_config::parse_config_str(
+            &format!("datadir={}\n{extra}", dir.display()),
+            crate::bitcoin_config::NodeFlavor::Core,
+        )
+        .unwrap()
+    }
+
+    fn texts(notes: &[CookieNote]) -> Vec<String> {
+        notes.iter().map(|n| n.text.clone()).collect()
+    }
+
+    #[test]
+    fn missing_cookie_and_password_auth_are_explained() {
+        let dir = tempfile::tempdir().unwrap();
+        let notes = inspect_cookie(&entries(dir.path(), ""), "main", SystemTime::now());
+        assert_eq!(
+            notes[0].text,
+            format!("Cookie: {}", dir.path().join(".cookie").display())
+        );
+        assert_eq!(notes.last().unwrap().severity, Some(Severity::Error));
+        assert!(notes.last().unwrap().text.starts_with("Not found"));
+
+        let notes = inspect_cookie(
+            &entries(dir.path(), "rpcuser=u\nrpcpassword=p\n"),
+            "main",
+            SystemTime::now(),
+        );
+        assert_eq!(notes.len(), 2);
+        assert!(notes[1].text.contains("logs in as u"));
+
+        let notes = inspect_cookie(
+            &entries(dir.path(), "rpccookiefile=auth/cookie\n"),
+            "signet",
+            SystemTime::now(),
+        );
+        assert_eq!(
+            notes[0].text,
+            format!(
+                "Cookie: {}",
+                dir.path().join("signet/auth/cookie").display()
+            )
+        );
+        assert!(
+            notes[1]
+                .text
+                .starts_with("rpccookiefile=auth/cookie is relative")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn world_readable_cookie_is_flagged() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let cookie = dir.path().join(".cookie");
+        std::fs::write(&cookie, "__cookie__:abc").unwrap();
+        std::fs::set_permissions(&cookie, std::fs::Permissions::from_mode(0o600)).unwrap();
+        let notes = inspect_cookie(&entries(dir.path(), ""), "main", SystemTime::now());
+        assert!(notes.iter().all(|n| n.severity.is_none()), "{notes:?}");
+        assert!(texts(&notes).contains(&"Mode 0600".to_string()));
+
+        std::fs::set_permissions(&cookie, std::fs::Permissions::from_mode(0o644)).unwrap();
+        let notes = inspect_cookie(&entries(dir.path(), ""), "main", SystemTime::now());
+        let warnings: Vec<_> = notes.iter().filter(|n| n.severity.is_some()).collect();
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].text.starts_with("Every user on this machine"));
+
+        let notes = inspect_cookie(
+            &entries(dir.path(), "rpccookieperms=all\n"),
+            "main",
+            SystemTime::now(),
+        );
+        assert!(notes.iter().all(|n| n.severity.is_none()), "{notes:?}");
+    }
+
+    #[test]
+    fn node_answer_tells_whether_the_cookie_is_stale() {
+        assert_eq!(node_note(&Ok(7200), "127.0.0.1:8332").severity, None);
+        let stale = node_note(&Err(RpcError::Unauthorized), "127.0.0.1:8332");
+        assert_eq!(stale.severity, Some(Severity::Error));
+        assert!(stale.text.contains("stale"));
+    }
+}
@@ -0,0 +1,232 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_config::{CHAINS, ConfigEntry, ConfigType};
+use crate::process_args::PROC_DIR;
+use crate::rpc::chain_subdir;
+use anyhow::{Context, Result, bail};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// A `datadir` change whose old directory holds data the new one lacks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DatadirMove {
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+/// How the data follows the new `datadir`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveMethod {
+    /// Move the directory to the new path
+    Move,
+    /// Leave the data in place and link the new path to it
+    Symlink,
+}
+
+/// The move to offer when `datadir` changes from `old` to `new`: only
+/// when the old directory exists and nothing is at the new path yet.
+#[must_use]
+pub fn plan_move(old: &Path, new: &str) -> Option<DatadirMove> {
+    let new = new.trim();
+    if new.is_empty() || Path::new(new) == old || !old.is_dir() {
+        return None;
+    }
+    let to = PathBuf::from(new);
+    if to.exists() || to.is_symlink() {
+        return None;
+    }
+    Some(DatadirMove {
+        from: old.to_path_buf(),
+        to,
+    })
+}
+
+/// Carries out `plan`, creating the new path's parent if needed.
+///
+/// # Errors
+/// Returns an error if the data cannot be moved or linked; a move that
+/// fails leaves the old directory in place.
+pub fn move_datadir(plan: &DatadirMove, method: MoveMethod) -> Result<()> {
+    if let Some(parent) = plan.to.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("cannot create {}", parent.display()))?;
+    }
+    match method {
+        MoveMethod::Move => {
+            if std::fs::rename(&plan.from, &plan.to).is_ok() {
+                return Ok(());
+            }
+            // rename cannot cross filesystems; mv copies then removes
+            let output = Command::new("mv")
+                .arg(&plan.from)
+                .arg(&plan.to)
+                .output()
+                .context("cannot run mv")?;
+            if !output.status.success() {
+                bail!("mv: {}", String::from_utf8_lossy(&output.stderr).trim());
+            }
+            Ok(())
+        }
+        #[cfg(unix)]
+        MoveMethod::Symlink => std::os::unix::fs::symlink(&plan.from, &plan.to)
+            .with_context(|| format!("cannot link {}", plan.to.display())),
+        #[cfg(not(unix))]
+        MoveMethod::Symlink => {
+            bail!("symlinks need administrator rights here; move the data instead")
+        }
+    }
+}
+
+/// The pid of the process holding the `.lock` bitcoind keeps in `datadir`,
+/// or in the directory of any of its chains, as `/proc/locks` lists it;
+/// `None` when nothing holds one or the locks cannot be read.
+#[must_use]
+pub fn lock_holder(datadir: &Path) -> Option<u32> {
+    lock_holder_in(datadir, Path::new(PROC_DIR))
+}
+
+/// How `/proc/locks` names a file: `major:minor:inode`, the device
+/// numbers in hex.
+#[cfg(unix)]
+fn lock_id(file: &Path) -> Option<String> {
+    use std::os::unix::fs::MetadataExt;
+    let meta = std::fs::metadata(file).ok()?;
+    let dev = meta.dev();
+    let major = ((dev >> 8) & 0xfff) | ((dev >> 32) & !0xfff);
+    let minor = (dev & 0xff) | ((dev >> 12) & !0xff);
+    Some(format!("{major:02x}:{minor:02x}:{}", meta.ino()))
+}
+
+#[cfg(not(unix))]
+fn lock_id(_file: &Path) -> Option<String> {
+    None
+}
+
+fn lock_holder_in(datadir: &Path, proc_dir: &Path) -> Option<u32> {
+    let ids: Vec<String> = CHAINS
+        .iter()
+        .filter_map(|chain| lock_id(&datadir.join(chain_subdir(chain)).join(".lock")))
+        .collect();
+    if ids.is_empty() {
+        return None;
+    }
+    let locks = std::fs::read_to_string(proc_dir.join("locks")).ok()?;
+    // 1: POSIX  ADVISORY  WRITE 1234 08:01:5678 0 EOF
+    locks.lines().find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let at = fields.iter().position(|f| ids.iter().any(|id| id == f))?;
+        fields.get(at.checked_sub(1)?)?.parse().ok()
+    })
+}
+
+/// Points every enabled path option under `from`, such as `walletdir`
+/// and `blocksdir`, at the same place under `to`. Returns the keys
+/// changed.
+pub fn rewrite_dependents(entries: &mut [ConfigEntry], from: &Path, to: &Path) -> Vec<String> {
+    let mut changed = Vec::new();
+    for entry in entries.iter_mut().filter(|e| {
+        e.enabled
+            && e.key != "datadir"
+            && e.schema
+                .as_ref()
+                .is_some_and(|s| s.config_type == ConfigType::Path)
+    }) {
+        let Ok(rest) = Path::new(&entry.value).strip_prefix(from) else {
+            continue;
+        };
+        entry.value = to.join(rest).display().to_string();
+        changed.push(entry.key.clone());
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitcoin_config::{NodeFlavor, enabled_value, parse_config_str};
+
+    #[test]
+    fn move_is_offered_only_into_a_free_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = dir.path().join("old");
+        std::fs::create_dir(&old).unwrap();
+        let new = dir.path().join("new").display().to_string();
+        assert_eq!(
+            plan_move(&old, &new),
+            Some(DatadirMove {
+                from: old.clone(),
+                to: PathBuf::from(&new),
+            })
+        );
+        assert_eq!(plan_move(&old, ""), None);
+        assert_eq!(plan_move(&old, &old.display().to_string()), None);
+        assert_eq!(plan_move(&dir.path().join("missing"), &new), None);
+        std::fs::create_dir(&new).unwrap();
+        assert_eq!(plan_move(&old, &new), None);
+    }
+
+    #[test]
+    fn data_moves_or_is_linked() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = dir.path().join("old");
+        std::fs::create_dir(&old).unwrap();
+        std::fs::write(old.join("peers.dat"), "x").unwrap();
+
+        let plan = plan_move(&old, &dir.path().join("a/b").display().to_string()).unwrap();
+        move_datadir(&plan, MoveMethod::Move).unwrap();
+        assert!(!old.exists());
+        assert!(plan.to.join("peers.dat").exists());
+
+        #[cfg(unix)]
+        {
+            let plan = plan_move(&plan.to, &dir.path().join("c").display().to_string()).unwrap();
+            move_datadir(&plan, MoveMethod::Symlink).unwrap();
+            assert!(plan.from.join("peers.dat").exists());
+            assert!(plan.to.is_symlink());
+            assert!(plan.to.join("peers.dat").exists());
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn locked_datadir_names_its_holder() {
+        let dir = tempfile::tempdir().unwrap();
+        let datadir = dir.path().join("data");
+        std::fs::create_dir_all(datadir.join("signet")).unwrap();
+        let proc_dir = dir.path().join("proc");
+        std::fs::create_dir(&proc_dir).unwrap();
+        std::fs::write(proc_dir.join("locks"), "").unwrap();
+        assert_eq!(lock_holder_in(&datadir, &proc_dir), None);
+
+        let lock = datadir.join("signet/.lock");
+        std::fs::write(&lock, "").unwrap();
+        assert_eq!(lock_holder_in(&datadir, &proc_dir), None);
+        std::fs::write(
+            proc_dir.join("locks"),
+            format!(
+                "1: FLOCK  ADVISORY  WRITE 99 00:00:1 0 EOF\n2: POSIX  ADVISORY  WRITE 4242 {} 0 EOF\n",
+                lock_id(&lock).unwrap()
+            ),
+        )
+        .unwrap();
+        assert_eq!(lock_holder_in(&datadir, &proc_dir), Some(4242));
+    }
+
+    #[test]
+    fn paths_under_the_old_datadir_follow_it() {
+        let mut entries = parse_config_str(
+            "datadir=/old\nwalletdir=/old/wallets\nblocksdir=/hdd/blocks\n\
+             debuglogfile=/old/logs/debug.log\nuacomment=/old\n",
+            NodeFlavor::Core,
+        )
+        .unwrap();
+        let changed = rewrite_dependents(&mut entries, Path::new("/old"), Path::new("/new"));
+        assert_eq!(changed, ["debuglogfile", "walletdir"]);
+        assert_eq!(enabled_value(&entries, "walletdir"), Some("/new/wallets"));
+        assert_eq!(enabled_value(&entries, "blocksdir"), Some("/hdd/blocks"));
+        assert_eq!(enabled_value(&entries, "uacomment"), Some("/old"));
+        assert_eq!(enabled_value(&entries, "datadir"), Some("/old"));
+    }
+}
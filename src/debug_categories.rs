@@ -0,0 +1,119 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+/// Options whose value is a comma-separated list of log categories.
+pub const CATEGORY_OPTIONS: &[&str] = &["debug", "debugexclude"];
+
+/// Log categories bitcoind knows, with what each logs. `all` stands for
+/// every one of them.
+pub const DEBUG_CATEGORIES: &[(&str, &str)] = &[
+    ("all", "Every category"),
+    ("addrman", "Address manager"),
+    ("bench", "Timing of block validation steps"),
+    ("blockstorage", "Block and undo files"),
+    ("cmpctblock", "Compact block relay"),
+    ("coindb", "Chainstate database"),
+    ("estimatefee", "Fee estimation"),
+    ("http", "HTTP server"),
+    ("i2p", "I2P connections"),
+    ("ipc", "Inter-process communication"),
+    ("leveldb", "LevelDB internals"),
+    ("libevent", "libevent internals"),
+    ("mempool", "Mempool additions and removals"),
+    ("mempoolrej", "Transactions the mempool rejects"),
+    ("net", "P2P messages and connections"),
+    ("proxy", "Proxy connections"),
+    ("prune", "Block file pruning"),
+    ("qt", "GUI"),
+    ("rand", "Random number generation"),
+    ("reindex", "Reindexing"),
+    ("rpc", "RPC calls"),
+    ("scan", "Wallet rescans"),
+    ("selectcoins", "Wallet coin selection"),
+    ("tor", "Tor control port"),
+    ("txpackages", "Transaction package relay"),
+    ("txreconciliation", "Transaction reconciliation"),
+    ("validation", "Block and transaction validation"),
+    ("walletdb", "Wallet database"),
+    ("zmq", "ZMQ notifications"),
+];
+
+/// Checklist of [`DEBUG_CATEGORIES`] for a `debug` or `debugexclude`
+/// entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CategoryPicker {
+    /// Entry being edited
+    pub index: usize,
+    pub key: String,
+    /// Categories picked, in the order written. Ones PDM does not list,
+    /// such as `1` for all, are kept as written.
+    pub picked: Vec<String>,
+    /// Row under the cursor
+    pub cursor: usize,
+}
+
+impl CategoryPicker {
+    /// Picker for the entry at `index`, starting from its `value`.
+    #[must_use]
+    pub fn new(index: usize, key: &str, value: &str) -> Self {
+        Self {
+            index,
+            key: key.to_string(),
+            picked: value
+                .split(',')
+                .map(str::trim)
+                .filter(|c| !c.is_empty())
+                .map(str::to_string)
+                .collect(),
+            cursor: 0,
+        }
+    }
+
+    /// Whether `category` is picked.
+    #[must_use]
+    pub fn is_picked(&self, category: &str) -> bool {
+        self.picked.iter().any(|c| c == category)
+    }
+
+    /// Picks or drops the category under the cursor.
+    pub fn toggle(&mut self) {
+        let Some((category, _)) = DEBUG_CATEGORIES.get(self.cursor) else {
+            return;
+        };
+        if let Some(i) = self.picked.iter().position(|c| c == category) {
+            self.picked.remove(i);
+        } else {
+            self.picked.push((*category).to_string());
+        }
+    }
+
+    /// The value to write.
+    #[must_use]
+    pub fn value(&self) -> String {
+        self.picked.join(",")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picker_toggles_categories_and_keeps_unknown_ones() {
+        let mut picker = CategoryPicker::new(2, "debug", "net, 1,mempool");
+        assert!(picker.is_picked("net"));
+        picker.cursor = DEBUG_CATEGORIES
+            .iter()
+            .position(|(c, _)| *c == "net")
+            .unwrap();
+        picker.toggle();
+        picker.cursor = DEBUG_CATEGORIES
+            .iter()
+            .position(|(c, _)| *c == "rpc")
+            .unwrap();
+        picker.toggle();
+        assert_eq!(picker.value(), "1,mempool,rpc");
+        assert_eq!(CategoryPicker::new(0, "debug", "").value(), "");
+    }
+}
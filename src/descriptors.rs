@@ -0,0 +1,346 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::docker_compose::{HEADER, write_generated};
+use anyhow::Result;
+use serde_json::Value;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// How often the Wallets tab asks bitcoind for its wallets and their
+/// descriptors.
+pub const WALLET_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Characters a descriptor may use, in the order BIP-380 numbers them.
+const INPUT_CHARSET: &str = "0123456789()[],'/*abcdefgh@:$%{}IJKLMNOPQRSTUVWXYZ&+-.;<=>?!^_|~ijklmnopqrstuvwxyzABCDEFGH`#\"\\ ";
+
+/// Characters of the checksum itself.
+const CHECKSUM_CHARSET: &[u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+/// One step of the BIP-380 checksum's BCH code.
+fn polymod(c: u64, value: u64) -> u64 {
+    const GENERATORS: [u64; 5] = [
+        0xf5_dee5_1989,
+        0xa9_fdca_3312,
+        0x1b_ab10_e32d,
+        0x37_06b1_677a,
+        0x64_4d62_6ffd,
+    ];
+    let top = c >> 35;
+    let mut c = ((c & 0x7_ffff_ffff) << 5) ^ value;
+    for (i, g) in GENERATORS.iter().enumerate() {
+        if top >> i & 1 == 1 {
+            c ^= g;
+        }
+    }
+    c
+}
+
+/// The eight-character checksum of `descriptor`, given without its `#`
+/// suffix; `None` if it uses a character descriptors cannot contain.
+#[must_use]
+pub fn descriptor_checksum(descriptor: &str) -> Option<String> {
+    let mut c = 1;
+    let mut classes = 0;
+    let mut count = 0;
+    for ch in descriptor.chars() {
+        let pos = INPUT_CHARSET.find(ch)? as u64;
+        c = polymod(c, pos & 31);
+        classes = classes * 3 + (pos >> 5);
+        count += 1;
+        if count == 3 {
+            c = polymod(c, classes);
+            classes = 0;
+            count = 0;
+        }
+    }
+    if count > 0 {
+        c = polymod(c, classes);
+    }
+    for _ in 0..8 {
+        c = polymod(c, 0);
+    }
+    c ^= 1;
+    Some(
+        (0..8)
+            .map(|j| char::from(CHECKSUM_CHARSET[(c >> (5 * (7 - j)) & 31) as usize]))
+            .collect(),
+    )
+}
+
+/// What checking a descriptor's `#checksum` found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Checksum {
+    Valid,
+    /// The checksum does not match; holds the one that would
+    Invalid(String),
+    Missing,
+}
+
+/// Checks the checksum `descriptor` ends with.
+#[must_use]
+pub fn verify_checksum(descriptor: &str) -> Checksum {
+    let Some((body, given)) = descriptor.rsplit_once('#') else {
+        return Checksum::Missing;
+    };
+    match descriptor_checksum(body) {
+        Some(expected) if expected == given => Checksum::Valid,
+        Some(expected) => Checksum::Invalid(expected),
+        None => Checksum::Invalid(String::new()),
+    }
+}
+
+/// One entry of `listdescriptors`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DescriptorInfo {
+    pub desc: String,
+    /// Whether the wallet derives new addresses from it
+    pub active: bool,
+    /// Change (`true`) or receiving (`false`), for active descriptors
+    pub internal: Option<bool>,
+    /// Unix time from which the wallet scans for it
+    pub timestamp: u64,
+    /// Derivation indexes covered, for ranged descriptors
+    pub range: Option<(u64, u64)>,
+    pub next_index: Option<u64>,
+    pub checksum: Checksum,
+}
+
+impl DescriptorInfo {
+    /// Reads one element of the `descriptors` array.
+    #[must_use]
+    pub fn from_json(item: &Value) -> Option<Self> {
+        let desc = item.get("desc")?.as_str()?.to_string();
+        let range = item
+            .get("range")
+            .and_then(Value::as_array)
+            .and_then(|r| Some((r.first()?.as_u64()?, r.get(1)?.as_u64()?)));
+        Some(Self {
+            checksum: verify_checksum(&desc),
+            active: item.get("active").and_then(Value::as_bool) == Some(true),
+            internal: item.get("internal").and_then(Value::as_bool),
+            timestamp: item.get("timestamp").and_then(Value::as_u64).unwrap_or(0),
+            range,
+            next_index: item
+                .get("next_index")
+                .or_else(|| item.get("next"))
+                .and_then(Value::as_u64),
+            desc,
+        })
+    }
+
+    /// What the wallet uses it for: `receive`, `change` or `imported`.
+    #[must_use]
+    pub fn role(&self) -> &'static str {
+        match (self.active, self.internal) {
+            (true, Some(true)) => "change",
+            (true, _) => "receive",
+            (false, _) => "imported",
+        }
+    }
+}
+
+/// Descriptors of the wallets loaded in the running bitcoind.
+#[derive(Debug, Clone, Default)]
+pub struct WalletMonitor {
+    /// Loaded wallets, as `listwallets` names them
+    pub wallets: Vec<String>,
+    /// Index into `wallets` shown
+    pub selected: usize,
+    pub descriptors: Vec<DescriptorInfo>,
+    /// Whether descriptors are listed with their private keys
+    pub private: bool,
+    /// Outcome of the last export
+    pub message: Option<String>,
+    /// Why the last poll failed
+    pub error: Option<String>,
+    pub last_poll: Option<Instant>,
+}
+
+impl WalletMonitor {
+    /// Whether the next poll is due at `now`.
+    #[must_use]
+    pub fn due(&self, now: Instant) -> bool {
+        self.last_poll
+            .is_none_or(|last| now.duration_since(last) >= WALLET_POLL_INTERVAL)
+    }
+
+    /// The wallet shown, if any is loaded.
+    #[must_use]
+    pub fn wallet(&self) -> Option<&str> {
+        self.wallets.get(self.selected).map(String::as_str)
+    }
+
+    /// Takes a `listwallets` result, keeping the wallet shown selected.
+    pub fn set_wallets(&mut self, wallets: &Value) {
+        let shown = self.wallet().map(str::to_string);
+        self.wallets = wallets
+            .as_array()
+            .map(|w| {
+                w.iter()
+                    .filter_map(Value::as_str)
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+        self.selected = shown
+            .and_then(|name| self.wallets.iter().position(|w| *w == name))
+            .unwrap_or(0);
+        if self.wallets.is_empty() {
+            self.descriptors.clear();
+        }
+    }
+
+    /// Takes a `listdescriptors` result.
+    pub fn update(&mut self, listing: &Value) {
+        self.descriptors = listing
+            .get("descriptors")
+            .and_then(Value::as_array)
+            .map(|d| d.iter().filter_map(DescriptorInfo::from_json).collect())
+            .unwrap_or_default();
+        self.error = None;
+    }
+
+    /// Shows the next wallet, wrapping, and makes the next poll due.
+    pub fn next_wallet(&mut self) {
+        if !self.wallets.is_empty() {
+            self.selected = (self.selected + 1) % self.wallets.len();
+            self.descriptors.clear();
+            self.last_poll = None;
+        }
+    }
+}
+
+/// File the descriptors of `wallet` are exported to; the default wallet
+/// has an empty name.
+#[must_use]
+pub fn export_file(wallet: &str) -> String {
+    let name: String = wallet
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || "-_.".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if name.is_empty() {
+        "default.descriptors.txt".to_string()
+    } else {
+        format!("{name}.descriptors.txt")
+    }
+}
+
+/// The descriptors of `wallet` as text, one per line with its role in a
+/// comment, ready for `importdescriptors` into a watch-only wallet.
+#[must_use]
+pub fn descriptors_text(wallet: &str, descriptors: &[DescriptorInfo], private: bool) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "{HEADER}: descriptors of wallet \"{wallet}\"");
+    if private {
+        let _ = writeln!(
+            out,
+            "# PRIVATE KEYS: anyone with this file can spend the funds"
+        );
+    }
+    for d in descriptors {
+        let mut note = d.role().to_string();
+        if let Some((start, end)) = d.range {
+            let _ = write!(note, ", range {start}-{end}");
+        }
+        let _ = write!(note, ", timestamp {}", d.timestamp);
+        let _ = writeln!(out, "\n# {note}\n{}", d.desc);
+    }
+    out
+}
+
+/// Writes the descriptors of `wallet` into `dir` and returns the file.
+///
+/// # Errors
+/// Returns an error if a hand-written file of the same name exists or the
+/// file cannot be written.
+pub fn export_descriptors(
+    dir: &Path,
+    wallet: &str,
+    descriptors: &[DescriptorInfo],
+    private: bool,
+) -> Result<PathBuf> {
+    let path = dir.join(export_file(wallet));
+    write_generated(&path, &descriptors_text(wallet, descriptors, private))?;
+    #[cfg(unix)]
+    if private {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XPUB_DESC: &str = "wpkh([d34db33f/84h/0h/0h]xpub6DJ2dNUysrn5Vt36jH2KLBT2i1auw1tTSSomg8PhqNiUtx8QX2SvC9nrHu81fT41fvDUnhMjEzQgXnQjKEu3oaqMSzhSrHMxyyoEAmUHQbY/0/*)";
+
+    #[test]
+    fn checksums_match_bip380() {
+        assert_eq!(descriptor_checksum("raw(deadbeef)").unwrap(), "89f8spxm");
+        assert_eq!(descriptor_checksum(XPUB_DESC).unwrap(), "cjjspncu");
+        assert_eq!(descriptor_checksum("raw(dé)"), None);
+
+        assert_eq!(verify_checksum("raw(deadbeef)#89f8spxm"), Checksum::Valid);
+        assert_eq!(
+            verify_checksum("raw(deadbeef)#89f8spxn"),
+            Checksum::Invalid("89f8spxm".to_string())
+        );
+        assert_eq!(verify_checksum("raw(deadbeef)"), Checksum::Missing);
+    }
+
+    #[test]
+    fn monitor_reads_listdescriptors() {
+        let mut monitor = WalletMonitor::default();
+        monitor.set_wallets(&serde_json::json!(["hot", "cold"]));
+        monitor.next_wallet();
+        assert_eq!(monitor.wallet(), Some("cold"));
+        monitor.set_wallets(&serde_json::json!(["cold"]));
+        assert_eq!(monitor.wallet(), Some("cold"));
+
+        monitor.update(&serde_json::json!({
+            "wallet_name": "cold",
+            "descriptors": [
+                {"desc": format!("{XPUB_DESC}#cjjspncu"), "timestamp": 1_700_000_000,
+                 "active": true, "internal": false, "range": [0, 999], "next_index": 12},
+                {"desc": "raw(deadbeef)#00000000", "timestamp": 0, "active": false},
+            ],
+        }));
+        let [receive, imported] = &monitor.descriptors[..] else {
+            panic!("expected two descriptors");
+        };
+        assert_eq!(receive.role(), "receive");
+        assert_eq!(receive.checksum, Checksum::Valid);
+        assert_eq!(receive.range, Some((0, 999)));
+        assert_eq!(receive.next_index, Some(12));
+        assert_eq!(imported.role(), "imported");
+        assert_eq!(imported.checksum, Checksum::Invalid("89f8spxm".to_string()));
+    }
+
+    #[test]
+    fn export_writes_one_descriptor_per_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let descriptors = [DescriptorInfo::from_json(&serde_json::json!({
+            "desc": "raw(deadbeef)#89f8spxm", "timestamp": 5, "active": true, "internal": true,
+        }))
+        .unwrap()];
+        let path = export_descriptors(dir.path(), "", &descriptors, false).unwrap();
+        assert_eq!(path, dir.path().join("default.descriptors.txt"));
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            format!(
+                "{HEADER}: descriptors of wallet \"\"\n\n# change, timestamp 5\nraw(deadbeef)#89f8spxm\n"
+            )
+        );
+        assert_eq!(export_file("my wallet/1"), "my_wallet_1.descriptors.txt");
+    }
+}
@@ -0,0 +1,235 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_config::{ConfigEntry, enabled_value};
+use crate::history::{Change, format_timestamp};
+use crate::rpc::net_datadir;
+use crate::support_export::PathContext;
+use anyhow::{Context, Result, bail};
+use regex::Regex;
+use std::fmt::Write as _;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::LazyLock;
+
+/// Lines of debug.log the bundle keeps, from the end.
+pub const DEBUG_LOG_LINES: usize = 200;
+
+/// How much of the end of debug.log is read to find those lines; the file
+/// itself can be hundreds of MB.
+const DEBUG_LOG_TAIL_BYTES: u64 = 256 * 1024;
+
+/// Names the archive and the directory inside it.
+pub const BUNDLE_PREFIX: &str = "pdm-diagnostics";
+
+/// Peer addresses in debug.log: IPv4, bracketed IPv6, onion and I2P.
+static ADDRESSES: LazyLock<[(Regex, &str); 4]> = LazyLock::new(|| {
+    [
+        (Regex::new(r"\b(?:\d{1,3}\.){3}\d{1,3}\b").unwrap(), "<ip>"),
+        (
+            Regex::new(r"\[[0-9A-Fa-f:]*:[0-9A-Fa-f:]*\]").unwrap(),
+            "[<ip>]",
+        ),
+        (Regex::new(r"\b[a-z2-7]{56}\.onion\b").unwrap(), "<onion>"),
+        (Regex::new(r"\b[a-z2-7]{52}\.b32\.i2p\b").unwrap(), "<i2p>"),
+    ]
+});
+
+/// The debug.log bitcoind writes on `chain`: `debuglogfile`, relative to
+/// the chain's data directory, or `debug.log` there; `None` with
+/// `nodebuglogfile`.
+#[must_use]
+pub fn debug_log_path(entries: &[ConfigEntry], chain: &str) -> Option<PathBuf> {
+    match enabled_value(entries, "debuglogfile") {
+        Some("0") => None,
+        file => Some(net_datadir(entries, chain).join(file.unwrap_or("debug.log"))),
+    }
+}
+
+/// The last `lines` lines of `text`.
+#[must_use]
+pub fn tail_lines(text: &str, lines: usize) -> &str {
+    let Some(n) = text.lines().count().checked_sub(lines) else {
+        return text;
+    };
+    let start: usize = text.split_inclusive('\n').take(n).map(str::len).sum();
+    &text[start..]
+}
+
+/// The last `lines` lines of the file at `path`, reading only its end.
+///
+/// # Errors
+/// Returns an error if the file cannot be read.
+pub fn read_tail(path: &Path, lines: usize) -> Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let len = file.metadata()?.len();
+    let skip = len.saturating_sub(DEBUG_LOG_TAIL_BYTES);
+    file.seek(SeekFrom::Start(skip))?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)?;
+    let text = String::from_utf8_lossy(&bytes);
+    // The first line is cut short unless the whole file was read
+    let text = if skip > 0 {
+        text.split_once('\n').map_or("", |(_, rest)| rest)
+    } else {
+        &text
+    };
+    Ok(tail_lines(text, lines).to_string())
+}
+
+/// `log` with peer addresses and the datadir and home paths replaced.
+#[must_use]
+pub fn redact_log(log: &str, paths: &PathContext) -> String {
+    let mut out = paths.generalize(log);
+    for (pattern, placeholder) in ADDRESSES.iter() {
+        out = pattern.replace_all(&out, *placeholder).into_owned();
+    }
+    out
+}
+
+/// PDM's change journal as text, one change per line, with paths
+/// generalized. Secret values are already left out of the journal.
+#[must_use]
+pub fn format_history(changes: &[Change], paths: &PathContext) -> String {
+    let mut out = String::new();
+    for change in changes {
+        let value = |v: &Option<String>| v.as_deref().unwrap_or("(unset)").to_string();
+        let _ = writeln!(
+            out,
+            "{} {}: {} -> {}",
+            format_timestamp(change.timestamp),
+            change.key,
+            value(&change.old),
+            value(&change.new)
+        );
+    }
+    if out.is_empty() {
+        out.push_str("No saves recorded\n");
+    }
+    paths.generalize(&out)
+}
+
+/// Writes `files`, as name and content, into `<dir>/<BUNDLE_PREFIX>-<stamp>.tar.gz`
+/// with `tar` and returns the archive's path.
+///
+/// # Errors
+/// Returns an error if the files cannot be staged or `tar` fails.
+pub fn write_bundle(dir: &Path, stamp: u64, files: &[(&str, String)]) -> Result<PathBuf> {
+    let name = format!("{BUNDLE_PREFIX}-{stamp}");
+    let staging = std::env::temp_dir().join(format!("{name}-{}", std::process::id()));
+    let inner = staging.join(&name);
+    std::fs::create_dir_all(&inner)?;
+    let archive = dir.join(format!("{name}.tar.gz"));
+    let result = (|| {
+        for (file, content) in files {
+            std::fs::write(inner.join(file), content)?;
+        }
+        let output = Command::new("tar")
+            .arg("-czf")
+            .arg(&archive)
+            .arg("-C")
+            .arg(&staging)
+            .arg(&name)
+            .output()
+            .context("cannot run tar")?;
+        if !output.status.success() {
+            bail!("tar: {}", String::from_utf8_lossy(&output.stderr).trim());
+        }
+        Ok(archive)
+    })();
+    let _ = std::fs::remove_dir_all(&staging);
+    result
+}
+
+/// Placeholder text for a part of the bundle that could not be collected.
+#[must_use]
+pub fn unavailable(what: &str, reason: &str) -> String {
+    format!("{what} not available: {reason}\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitcoin_config::{NodeFlavor, parse_config_str};
+
+    fn paths() -> PathContext {
+        PathContext {
+            datadir: Some(PathBuf::from("/srv/btc")),
+            home: Some(PathBuf::from("/home/alice")),
+        }
+    }
+
+    #[test]
+    fn log_tail_is_redacted() {
+        let log = "one\ntwo\nthree\n";
+        assert_eq!(tail_lines(log, 2), "two\nthree\n");
+        assert_eq!(tail_lines(log, 5), log);
+
+        let line = "2024-05-01T10:00:00Z New outbound peer connected: peer=3 \
+                    addr=203.0.113.7:8333 [2001:db8::1]:8333 \
+                    abcdefghijklmnopqrstuvwxyz234567abcdefghijklmnopqrstuvwx.onion \
+                    datadir=/srv/btc/blocks home=/home/alice/x\n";
+        let redacted = redact_log(line, &paths());
+        assert!(redacted.contains("addr=<ip>:8333 [<ip>]:8333 <onion>"));
+        assert!(redacted.contains("datadir=<datadir>/blocks home=~/x"));
+        assert!(redacted.starts_with("2024-05-01T10:00:00Z"));
+    }
+
+    #[test]
+    fn tail_of_a_large_file_drops_the_cut_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("debug.log");
+        let line = "x".repeat(99) + "\n";
+        std::fs::write(&path, line.repeat(5000) + "last\n").unwrap();
+        let tail = read_tail(&path, 3).unwrap();
+        assert_eq!(tail, format!("{line}{line}last\n"));
+        let tail = read_tail(&path, 100_000).unwrap();
+        assert!(tail.lines().all(|l| l == "last" || l.len() == 99));
+    }
+
+    #[test]
+    fn debug_log_follows_debuglogfile() {
+        let entries = parse_config_str("datadir=/srv/btc\n", NodeFlavor::Core).unwrap();
+        assert_eq!(
+            debug_log_path(&entries, "signet"),
+            Some(PathBuf::from("/srv/btc/signet/debug.log"))
+        );
+        let entries = parse_config_str(
+            "datadir=/srv/btc\ndebuglogfile=/var/log/bitcoind.log\n",
+            NodeFlavor::Core,
+        )
+        .unwrap();
+        assert_eq!(
+            debug_log_path(&entries, "main"),
+            Some(PathBuf::from("/var/log/bitcoind.log"))
+        );
+        let entries = parse_config_str("nodebuglogfile=1\n", NodeFlavor::Core).unwrap();
+        assert_eq!(debug_log_path(&entries, "main"), None);
+    }
+
+    #[test]
+    fn bundle_is_one_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = write_bundle(
+            dir.path(),
+            1_700_000_000,
+            &[("bitcoin.conf", "server=1\n".to_string())],
+        )
+        .unwrap();
+        assert_eq!(
+            archive,
+            dir.path().join("pdm-diagnostics-1700000000.tar.gz")
+        );
+        let listing = Command::new("tar")
+            .arg("-tzf")
+            .arg(&archive)
+            .output()
+            .unwrap();
+        assert!(
+            String::from_utf8_lossy(&listing.stdout)
+                .contains("pdm-diagnostics-1700000000/bitcoin.conf")
+        );
+    }
+}
@@ -0,0 +1,952 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::asmap::asmap_problems;
+use crate::bitcoin_config::{
+    ConfigEntry, ConfigType, active_network, conflicting_duplicates, effective_p2p_port,
+    effective_rpc_port, enabled_value, read_assignments,
+};
+use crate::indexes::prune_conflicts;
+use crate::preflight::is_writable;
+use crate::rpc::{NodeChain, chain_subdir, default_datadir};
+use crate::template::{has_placeholders, load_profiles, render_profile, resolve_profile};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::net::{Ipv4Addr, Ipv6Addr, TcpListener};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// A problem found in a loaded bitcoin.conf.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Option the problem is about
+    pub key: String,
+    pub message: String,
+}
+
+impl Diagnostic {
+    #[must_use]
+    pub fn error(key: &str, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            key: key.to_string(),
+            message: message.into(),
+        }
+    }
+
+    #[must_use]
+    pub fn warning(key: &str, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            key: key.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Splits `host[:port]`, `[ipv6][:port]` or a bare IPv6 address.
+///
+/// # Errors
+/// Returns an error if the brackets or the port are malformed.
+pub fn split_host_port(endpoint: &str) -> Result<(&str, Option<u16>), String> {
+    let (host, port) = if let Some(rest) = endpoint.strip_prefix('[') {
+        let Some((host, rest)) = rest.split_once(']') else {
+            return Err("missing ] after the IPv6 address".to_string());
+        };
+        match rest {
+            "" => (host, None),
+            _ => match rest.strip_prefix(':') {
+                Some(port) => (host, Some(port)),
+                None => return Err(format!("unexpected {rest:?} after ]")),
+            },
+        }
+    } else {
+        match endpoint.rsplit_once(':') {
+            // A bare IPv6 address has colons but no port
+            Some((host, _)) if host.contains(':') => (endpoint, None),
+            Some((host, port)) => (host, Some(port)),
+            None => (endpoint, None),
+        }
+    };
+    let port = match port {
+        None => None,
+        Some(p) => match p.parse::<u16>() {
+            Ok(p) if p > 0 => Some(p),
+            _ => return Err(format!("{p:?} is not a port number (1-65535)")),
+        },
+    };
+    Ok((host, port))
+}
+
+/// Address part of a `bind`, `whitebind` or ZMQ value, without the
+/// `perm@` prefix, `=onion` suffix or `tcp://` scheme.
+fn bare_endpoint(value: &str) -> &str {
+    let value = value.rsplit_once('@').map_or(value, |(_, addr)| addr);
+    let value = value.split_once('=').map_or(value, |(addr, _)| addr);
+    value.strip_prefix("tcp://").unwrap_or(value)
+}
+
+/// Port of an endpoint such as `0.0.0.0:8333`, `[::1]:8332`,
+/// `tcp://127.0.0.1:28332` or `127.0.0.1:8334=onion`. `None` when the
+/// endpoint names no port.
+#[must_use]
+pub fn endpoint_port(endpoint: &str) -> Option<u16> {
+    split_host_port(bare_endpoint(endpoint)).ok()?.1
+}
+
+/// A TCP port bitcoind will listen on, and the option that opens it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Listener {
+    pub port: u16,
+    pub key: String,
+    /// Listeners with the same service may share a port: P2P binds,
+    /// RPC binds, or ZMQ topics published on one endpoint.
+    pub service: String,
+}
+
+/// Ports bitcoind listens on for the active network.
+#[must_use]
+pub fn listeners(entries: &[ConfigEntry]) -> Vec<Listener> {
+    let network = active_network(entries);
+    let p2p = effective_p2p_port(entries);
+    let rpc = effective_rpc_port(entries);
+    let active = entries
+        .iter()
+        .filter(|e| e.enabled && e.section.as_deref().is_none_or(|s| s == network));
+
+    let mut out = vec![
+        Listener {
+            port: p2p,
+            key: "port".to_string(),
+            service: "p2p".to_string(),
+        },
+        Listener {
+            port: rpc,
+            key: "rpcport".to_string(),
+            service: "rpc".to_string(),
+        },
+    ];
+    for entry in active {
+        let key = entry.key.as_str();
+        let listener = match key {
+            "bind" | "whitebind" => Listener {
+                port: endpoint_port(&entry.value).unwrap_or(p2p),
+                key: key.to_string(),
+                service: "p2p".to_string(),
+            },
+            "rpcbind" => Listener {
+                port: endpoint_port(&entry.value).unwrap_or(rpc),
+                key: key.to_string(),
+                service: "rpc".to_string(),
+            },
+            _ if key.starts_with("zmqpub") => {
+                let Some(port) = endpoint_port(&entry.value) else {
+                    continue;
+                };
+                Listener {
+                    port,
+                    key: key.to_string(),
+                    service: format!("zmq {}", entry.value),
+                }
+            }
+            _ => continue,
+        };
+        out.push(listener);
+    }
+    out
+}
+
+/// Ports claimed by two different services within one config.
+#[must_use]
+pub fn port_conflicts(entries: &[ConfigEntry]) -> Vec<Diagnostic> {
+    let mut by_port: BTreeMap<u16, Vec<Listener>> = BTreeMap::new();
+    for listener in listeners(entries) {
+        by_port.entry(listener.port).or_default().push(listener);
+    }
+    let mut out = Vec::new();
+    for (port, users) in by_port {
+        let first = &users[0];
+        if let Some(other) = users.iter().find(|l| l.service != first.service) {
+            out.push(Diagnostic::error(
+                &other.key,
+                format!("port {port} is also used by {}", first.key),
+            ));
+        }
+    }
+    out
+}
+
+/// Ports this config shares with a node generated from one of its profiles.
+#[must_use]
+pub fn profile_conflicts(conf: &Path, entries: &[ConfigEntry]) -> Vec<Diagnostic> {
+    let Ok(profiles) = load_profiles(conf) else {
+        return Vec::new();
+    };
+    let mut seen: BTreeMap<u16, String> = BTreeMap::new();
+    let mut out = Vec::new();
+    for profile in profiles.iter().filter(|p| !p.base) {
+        let Ok(rendered) =
+            resolve_profile(&profiles, &profile.name).and_then(|r| render_profile(entries, &r))
+        else {
+            continue;
+        };
+        let mut ports: Vec<Listener> = listeners(&rendered);
+        ports.dedup_by_key(|l| l.port);
+        for listener in ports {
+            match seen.get(&listener.port) {
+                Some(owner) if owner != &profile.name => out.push(Diagnostic::error(
+                    &listener.key,
+                    format!(
+                        "profiles {owner} and {} both use port {}",
+                        profile.name, listener.port
+                    ),
+                )),
+                Some(_) => {}
+                None => {
+                    seen.insert(listener.port, profile.name.clone());
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Ports another process on this machine is already listening on.
+#[must_use]
+pub fn ports_in_use(entries: &[ConfigEntry]) -> Vec<Diagnostic> {
+    let mut ports: Vec<Listener> = listeners(entries);
+    ports.sort_by_key(|l| l.port);
+    ports.dedup_by_key(|l| l.port);
+    ports
+        .into_iter()
+        .filter(|l| {
+            TcpListener::bind(("127.0.0.1", l.port))
+                .is_err_and(|e| e.kind() == std::io::ErrorKind::AddrInUse)
+        })
+        .map(|l| {
+            Diagnostic::warning(
+                &l.key,
+                format!(
+                    "port {} is already in use on this machine (a running bitcoind?)",
+                    l.port
+                ),
+            )
+        })
+        .collect()
+}
+
+/// What kind of host an address names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Host {
+    Ip,
+    Name,
+    Onion,
+    I2p,
+}
+
+fn is_base32(s: &str) -> bool {
+    s.chars().all(|c| matches!(c, 'a'..='z' | '2'..='7'))
+}
+
+/// Classifies `host` as an IP address, DNS name, onion or I2P address.
+///
+/// # Errors
+/// Returns why `host` is none of those.
+pub fn classify_host(host: &str) -> Result<Host, String> {
+    if host.is_empty() {
+        return Err("missing host".to_string());
+    }
+    if host.contains(':') {
+        return match host.parse::<Ipv6Addr>() {
+            Ok(_) => Ok(Host::Ip),
+            Err(_) => Err(format!("{host} is not a valid IPv6 address")),
+        };
+    }
+    if host.chars().all(|c| c.is_ascii_digit() || c == '.') {
+        return match host.parse::<Ipv4Addr>() {
+            Ok(_) => Ok(Host::Ip),
+            Err(_) => Err(format!("{host} is not a valid IPv4 address")),
+        };
+    }
+    let lower = host.to_ascii_lowercase();
+    if let Some(name) = lower.strip_suffix(".onion") {
+        return if name.len() == 56 && is_base32(name) {
+            Ok(Host::Onion)
+        } else {
+            Err(format!(
+                "{host} is not a v3 onion address (56 base32 characters)"
+            ))
+        };
+    }
+    if let Some(name) = lower.strip_suffix(".b32.i2p") {
+        return if name.len() == 52 && is_base32(name) {
+            Ok(Host::I2p)
+        } else {
+            Err(format!(
+                "{host} is not a valid I2P address (52 base32 characters)"
+            ))
+        };
+    }
+    let valid_label =
+        |l: &str| !l.is_empty() && l.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+    if !lower.split('.').all(valid_label) {
+        return Err(format!("{host} is not a valid host name"));
+    }
+    Ok(if lower.ends_with(".i2p") {
+        Host::I2p
+    } else {
+        Host::Name
+    })
+}
+
+/// Why the value of the address option `key` is malformed, if it is.
+#[must_use]
+pub fn address_error(key: &str, value: &str) -> Option<String> {
+    if key.starts_with("zmqpub") {
+        return zmq_error(value);
+    }
+    let endpoint = match key {
+        "bind" => value.strip_suffix("=onion").unwrap_or(value),
+        "whitebind" => value.rsplit_once('@').map_or(value, |(_, addr)| addr),
+        "connect" if value == "0" => return None,
+        _ => value,
+    };
+    let (host, _) = match split_host_port(endpoint) {
+        Ok(parts) => parts,
+        Err(e) => return Some(e),
+    };
+    let host = match classify_host(host) {
+        Ok(kind) => kind,
+        Err(e) => return Some(e),
+    };
+    match (key, host) {
+        ("bind" | "whitebind", Host::Ip) | ("rpcbind", Host::Ip | Host::Name) => None,
+        ("bind" | "whitebind", _) => Some("expected a local IP address, e.g. 0.0.0.0:8333".into()),
+        ("rpcbind", _) => Some("expected a local address, e.g. 127.0.0.1:8332".into()),
+        ("proxy" | "onion" | "torcontrol" | "i2psam", Host::Onion | Host::I2p) => {
+            Some("expected the host:port of a local proxy, not an overlay address".into())
+        }
+        _ => None,
+    }
+}
+
+/// Why a `zmqpub*` value is not a usable ZMQ endpoint, if it is not.
+#[must_use]
+pub fn zmq_error(value: &str) -> Option<String> {
+    if let Some(path) = value.strip_prefix("ipc://") {
+        return path
+            .is_empty()
+            .then(|| "ipc:// needs a socket path, e.g. ipc:///tmp/bitcoind.zmq".into());
+    }
+    let Some(endpoint) = value.strip_prefix("tcp://") else {
+        return Some("expected tcp://host:port or ipc://path".into());
+    };
+    match split_host_port(endpoint) {
+        Err(e) => Some(e),
+        Ok((_, None)) => Some("tcp:// needs a port, e.g. tcp://127.0.0.1:28332".into()),
+        Ok(("*", Some(_))) => None,
+        Ok((host, Some(_))) => classify_host(host).err(),
+    }
+}
+
+/// Address options whose values are malformed.
+#[must_use]
+pub fn invalid_addresses(entries: &[ConfigEntry]) -> Vec<Diagnostic> {
+    entries
+        .iter()
+        .filter(|e| {
+            e.enabled
+                && !e.value.is_empty()
+                && !has_placeholders(&e.value)
+                && e.schema
+                    .as_ref()
+                    .is_some_and(|s| s.config_type == ConfigType::Address)
+        })
+        .filter_map(|e| {
+            address_error(&e.key, &e.value)
+                .map(|message| Diagnostic::error(&e.key, format!("{}: {message}", e.value)))
+        })
+        .collect()
+}
+
+/// Fee-rate options, in BTC per 1000 virtual bytes.
+pub const FEE_RATE_OPTIONS: &[&str] = &[
+    "blockmintxfee",
+    "consolidatefeerate",
+    "discardfee",
+    "dustrelayfee",
+    "fallbackfee",
+    "incrementalrelayfee",
+    "minrelaytxfee",
+    "mintxfee",
+    "paytxfee",
+];
+
+/// Absolute fee options, in BTC.
+pub const FEE_AMOUNT_OPTIONS: &[&str] = &["maxapsfee", "maxtxfee"];
+
+const SATS_PER_BTC: u64 = 100_000_000;
+const MAX_MONEY: u64 = 21_000_000 * SATS_PER_BTC;
+/// bitcoind warns about fee rates above 0.01 BTC/kvB
+const HIGH_FEE_RATE: u64 = SATS_PER_BTC / 100;
+/// bitcoind warns about a `maxtxfee` above 1 BTC
+const HIGH_MAX_TX_FEE: u64 = SATS_PER_BTC;
+/// Default `minrelaytxfee`, in sat/kvB
+const DEFAULT_MIN_RELAY_FEE: u64 = 1000;
+
+/// Parses a BTC amount the way bitcoind does, into satoshis.
+///
+/// # Errors
+/// Returns an error if `value` is not a non-negative decimal with at most
+/// eight decimal places, or exceeds 21 million BTC.
+pub fn parse_btc(value: &str) -> Result<u64, String> {
+    let (whole, frac) = value.split_once('.').unwrap_or((value, ""));
+    let digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+    if (whole.is_empty() && frac.is_empty()) || !digits(whole) || !digits(frac) {
+        return Err("expected a BTC amount such as 0.0001".to_string());
+    }
+    if frac.len() > 8 {
+        return Err("more than 8 decimal places (1 sat is 0.00000001)".to_string());
+    }
+    let sats = format!("{whole}{frac:0<8}")
+        .parse::<u64>()
+        .ok()
+        .filter(|s| *s <= MAX_MONEY);
+    sats.ok_or_else(|| "more than 21 million BTC".to_string())
+}
+
+/// `sats` per 1000 vbytes as sat/vB, e.g. `1000` as `1 sat/vB`.
+#[must_use]
+pub fn format_sat_per_vb(sats_per_kvb: u64) -> String {
+    let whole = sats_per_kvb / 1000;
+    let frac = sats_per_kvb % 1000;
+    if frac == 0 {
+        format!("{whole} sat/vB")
+    } else {
+        let frac = format!("{frac:03}");
+        format!("{whole}.{} sat/vB", frac.trim_end_matches('0'))
+    }
+}
+
+/// The value of the fee option `key` in satoshi units, for display next to
+/// its BTC value.
+#[must_use]
+pub fn fee_equivalent(key: &str, value: &str) -> Option<String> {
+    let sats = parse_btc(value.trim()).ok()?;
+    if FEE_RATE_OPTIONS.contains(&key) {
+        Some(format_sat_per_vb(sats))
+    } else if FEE_AMOUNT_OPTIONS.contains(&key) {
+        Some(format!("{sats} sat"))
+    } else {
+        None
+    }
+}
+
+/// Fee options outside the range bitcoind accepts or considers sane.
+#[must_use]
+pub fn invalid_fees(entries: &[ConfigEntry]) -> Vec<Diagnostic> {
+    let min_relay = enabled_value(entries, "minrelaytxfee")
+        .and_then(|v| parse_btc(v).ok())
+        .unwrap_or(DEFAULT_MIN_RELAY_FEE);
+    let mut out = Vec::new();
+    for entry in entries.iter().filter(|e| {
+        e.enabled
+            && !has_placeholders(&e.value)
+            && (FEE_RATE_OPTIONS.contains(&e.key.as_str())
+                || FEE_AMOUNT_OPTIONS.contains(&e.key.as_str()))
+    }) {
+        let key = entry.key.as_str();
+        let value = entry.value.trim();
+        // -1 turns partial spend avoidance off
+        if key == "maxapsfee" && value == "-1" {
+            continue;
+        }
+        let sats = match parse_btc(value) {
+            Ok(sats) => sats,
+            Err(e) => {
+                out.push(Diagnostic::error(key, format!("{value}: {e}")));
+                continue;
+            }
+        };
+        let is_rate = FEE_RATE_OPTIONS.contains(&key);
+        match key {
+            "paytxfee" if sats > 0 && sats < min_relay => out.push(Diagnostic::error(
+                key,
+                format!(
+                    "{} is below minrelaytxfee ({}); transactions would not relay",
+                    format_sat_per_vb(sats),
+                    format_sat_per_vb(min_relay)
+                ),
+            )),
+            "maxtxfee" if sats < min_relay => out.push(Diagnostic::error(
+                key,
+                format!(
+                    "{sats} sat is below minrelaytxfee for 1000 vB ({min_relay} sat); transactions could get stuck"
+                ),
+            )),
+            "maxtxfee" if sats > HIGH_MAX_TX_FEE => out.push(Diagnostic::warning(
+                key,
+                format!("{value} BTC is a very high fee cap"),
+            )),
+            _ if is_rate && sats > HIGH_FEE_RATE => out.push(Diagnostic::warning(
+                key,
+                format!(
+                    "{value} BTC/kvB ({}) is very high",
+                    format_sat_per_vb(sats)
+                ),
+            )),
+            _ => {}
+        }
+    }
+    out
+}
+
+/// Lines of the file at `conf` that give a single-value option a second,
+/// different value, which bitcoind ignores.
+#[must_use]
+pub fn duplicate_keys(conf: &Path) -> Vec<Diagnostic> {
+    let Ok(content) = std::fs::read_to_string(conf) else {
+        return Vec::new();
+    };
+    let assignments = read_assignments(&content);
+    conflicting_duplicates(&assignments)
+        .into_iter()
+        .map(|(ignored, used)| {
+            Diagnostic::warning(
+                &ignored.key,
+                format!(
+                    "line {}: {} is ignored, bitcoind uses {} from line {} (press u to clean up)",
+                    ignored.line, ignored.value, used.value, used.line
+                ),
+            )
+        })
+        .collect()
+}
+
+/// Path options bitcoind only reads, so the file must already be there.
+const READ_PATHS: &[&str] = &["includeconf", "loadblock"];
+
+/// Path options that are not a plain path: `asmap` has its own check and
+/// `wallet` names a wallet inside `walletdir`.
+const NOT_PLAIN_PATHS: &[&str] = &["asmap", "wallet"];
+
+/// Path options naming a directory rather than a file.
+const DIRECTORY_PATHS: &[&str] = &["datadir", "blocksdir", "walletdir"];
+
+/// `value` with a leading `~` replaced by the home directory.
+#[must_use]
+pub fn expand_home(value: &str) -> PathBuf {
+    let home = || directories::BaseDirs::new().map(|d| d.home_dir().to_path_buf());
+    match value.strip_prefix('~') {
+        Some("") => home().unwrap_or_else(|| PathBuf::from(value)),
+        Some(rest) if rest.starts_with('/') => home()
+            .map(|h| h.join(&rest[1..]))
+            .unwrap_or_else(|| PathBuf::from(value)),
+        _ => PathBuf::from(value),
+    }
+}
+
+/// What is wrong with `path` as the value of `key`, if anything: a file
+/// bitcoind reads must exist, anything else must exist or be creatable in
+/// its parent.
+fn path_problem(key: &str, path: &Path) -> Option<String> {
+    let shown = path.display();
+    if path.exists() {
+        let directory = DIRECTORY_PATHS.contains(&key);
+        return (directory && !path.is_dir())
+            .then(|| format!("{shown} is not a directory"))
+            .or_else(|| (!directory && path.is_dir()).then(|| format!("{shown} is a directory")));
+    }
+    if READ_PATHS.contains(&key) {
+        return Some(format!("{shown} does not exist"));
+    }
+    match path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        Some(parent) if !parent.is_dir() => Some(format!(
+            "{shown} does not exist, nor does {}",
+            parent.display()
+        )),
+        Some(parent) if !is_writable(parent) => Some(format!(
+            "{shown} does not exist and {} is not writable",
+            parent.display()
+        )),
+        _ => None,
+    }
+}
+
+/// Path options pointing where bitcoind will find nothing or cannot
+/// create what it needs. `~` is expanded for the check, but bitcoind
+/// takes it literally, so it is flagged too. Relative paths are taken
+/// from the chain's data directory, as bitcoind does.
+#[must_use]
+pub fn dangling_paths(entries: &[ConfigEntry]) -> Vec<Diagnostic> {
+    let chain = active_network(entries);
+    let datadir = enabled_value(entries, "datadir")
+        .map(expand_home)
+        .or_else(default_datadir)
+        .unwrap_or_default();
+    let net_datadir = datadir.join(chain_subdir(chain));
+    let mut out = Vec::new();
+    for e in entries.iter().filter(|e| {
+        e.enabled
+            && !e.value.is_empty()
+            && !has_placeholders(&e.value)
+            && !NOT_PLAIN_PATHS.contains(&e.key.as_str())
+            && e.schema
+                .as_ref()
+                .is_some_and(|s| s.config_type == ConfigType::Path)
+    }) {
+        let expanded = expand_home(&e.value);
+        if e.value.starts_with('~') {
+            out.push(Diagnostic::warning(
+                &e.key,
+                format!(
+                    "bitcoind does not expand ~; write {} instead",
+                    expanded.display()
+                ),
+            ));
+        }
+        let path = if expanded.is_relative() && e.key != "datadir" {
+            net_datadir.join(&expanded)
+        } else {
+            expanded
+        };
+        if let Some(problem) = path_problem(&e.key, &path) {
+            out.push(Diagnostic::warning(&e.key, problem));
+        }
+    }
+    out
+}
+
+/// Error when the bitcoind answering RPC runs another chain than `entries`
+/// select, which usually means the wrong file is open.
+#[must_use]
+pub fn chain_mismatch(node: &NodeChain, entries: &[ConfigEntry]) -> Option<Diagnostic> {
+    let chain = active_network(entries);
+    (node.chain != chain).then(|| {
+        Diagnostic::error(
+            "chain",
+            format!(
+                "bitcoind at {} runs {}, but this file selects {chain}; is this the file it uses?",
+                node.endpoint, node.chain
+            ),
+        )
+    })
+}
+
+/// Every check for the bitcoin.conf at `conf`, errors first.
+#[must_use]
+pub fn check(conf: &Path, entries: &[ConfigEntry]) -> Vec<Diagnostic> {
+    let mut out = invalid_addresses(entries);
+    out.extend(invalid_fees(entries));
+    out.extend(port_conflicts(entries));
+    out.extend(profile_conflicts(conf, entries));
+    out.extend(ports_in_use(entries));
+    out.extend(duplicate_keys(conf));
+    out.extend(prune_conflicts(entries));
+    out.extend(dangling_paths(entries));
+    out.extend(asmap_problems(
+        entries,
+        active_network(entries),
+        SystemTime::now(),
+    ));
+    out.sort_by_key(|d| Reverse(d.severity));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(dir: &Path, content: &str) -> Vec<ConfigEntry> {
+        let path = dir.join("bitcoin.conf");
+        std::fs::write(&path, content).unwrap();
+        crate::bitcoin_config::parse_config(&path).unwrap()
+    }
+
+    #[test]
+    fn parses_endpoint_ports() {
+        assert_eq!(endpoint_port("0.0.0.0:8333"), Some(8333));
+        assert_eq!(endpoint_port("[::1]:8332"), Some(8332));
+        assert_eq!(endpoint_port("tcp://127.0.0.1:28332"), Some(28332));
+        assert_eq!(endpoint_port("127.0.0.1:8334=onion"), Some(8334));
+        assert_eq!(endpoint_port("::1"), None);
+        assert_eq!(endpoint_port("127.0.0.1"), None);
+    }
+
+    #[test]
+    fn validates_addresses_per_option() {
+        let onion = format!("{}.onion", "a".repeat(56));
+        assert_eq!(address_error("bind", "0.0.0.0:8333"), None);
+        assert_eq!(address_error("bind", "[::]:8334=onion"), None);
+        assert_eq!(address_error("whitebind", "noban@127.0.0.1:8335"), None);
+        assert_eq!(address_error("rpcbind", "::1"), None);
+        assert_eq!(address_error("addnode", &format!("{onion}:8333")), None);
+        assert_eq!(address_error("connect", "0"), None);
+        assert_eq!(address_error("proxy", "localhost:9050"), None);
+
+        assert_eq!(
+            address_error("rpcbind", &onion),
+            Some("expected a local address, e.g. 127.0.0.1:8332".to_string())
+        );
+        assert_eq!(
+            address_error("bind", "node.example.com"),
+            Some("expected a local IP address, e.g. 0.0.0.0:8333".to_string())
+        );
+        assert_eq!(
+            address_error("bind", "300.0.0.1"),
+            Some("300.0.0.1 is not a valid IPv4 address".to_string())
+        );
+        assert_eq!(
+            address_error("addnode", "[::1:8333"),
+            Some("missing ] after the IPv6 address".to_string())
+        );
+        assert_eq!(
+            address_error("addnode", "abc.onion"),
+            Some("abc.onion is not a v3 onion address (56 base32 characters)".to_string())
+        );
+        assert_eq!(
+            address_error("seednode", "node.example.com:99999"),
+            Some("\"99999\" is not a port number (1-65535)".to_string())
+        );
+        assert!(address_error("proxy", &format!("{onion}:9050")).is_some());
+        assert!(address_error("addnode", "xyz.b32.i2p").is_some());
+    }
+
+    #[test]
+    fn validates_zmq_endpoints() {
+        assert_eq!(address_error("zmqpubrawtx", "tcp://127.0.0.1:28332"), None);
+        assert_eq!(address_error("zmqpubrawtx", "tcp://*:28332"), None);
+        assert_eq!(address_error("zmqpubrawtx", "ipc:///tmp/zmq.sock"), None);
+        assert_eq!(
+            address_error("zmqpubrawtx", "127.0.0.1:28332"),
+            Some("expected tcp://host:port or ipc://path".to_string())
+        );
+        assert_eq!(
+            address_error("zmqpubrawtx", "tcp://127.0.0.1"),
+            Some("tcp:// needs a port, e.g. tcp://127.0.0.1:28332".to_string())
+        );
+        assert!(address_error("zmqpubrawtx", "ipc://").is_some());
+    }
+
+    #[test]
+    fn reports_invalid_addresses_in_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let found = invalid_addresses(&entries(
+            dir.path(),
+            "rpcbind=localhost:0\nbind=0.0.0.0\nzmqpubrawblock=${ZMQ}\n",
+        ));
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].key, "rpcbind");
+        assert!(found[0].message.starts_with("localhost:0: "));
+    }
+
+    #[test]
+    fn parses_btc_amounts_like_bitcoind() {
+        assert_eq!(parse_btc("0.00001"), Ok(1000));
+        assert_eq!(parse_btc("1"), Ok(100_000_000));
+        assert_eq!(parse_btc(".5"), Ok(50_000_000));
+        assert!(parse_btc("0.000000001").is_err());
+        assert!(parse_btc("-1").is_err());
+        assert!(parse_btc("1e-5").is_err());
+        assert!(parse_btc("21000001").is_err());
+        assert!(parse_btc("").is_err());
+    }
+
+    #[test]
+    fn converts_fee_rates_to_sat_per_vb() {
+        assert_eq!(
+            fee_equivalent("minrelaytxfee", "0.00001").as_deref(),
+            Some("1 sat/vB")
+        );
+        assert_eq!(
+            fee_equivalent("fallbackfee", "0.0002").as_deref(),
+            Some("20 sat/vB")
+        );
+        assert_eq!(
+            fee_equivalent("blockmintxfee", "0.000001").as_deref(),
+            Some("0.1 sat/vB")
+        );
+        assert_eq!(
+            fee_equivalent("maxtxfee", "0.1").as_deref(),
+            Some("10000000 sat")
+        );
+        assert_eq!(fee_equivalent("dbcache", "450"), None);
+    }
+
+    #[test]
+    fn flags_fees_out_of_range() {
+        let dir = tempfile::tempdir().unwrap();
+        let found = invalid_fees(&entries(
+            dir.path(),
+            "minrelaytxfee=0.00002\npaytxfee=0.00001\nmaxtxfee=0.00001\nfallbackfee=0.05\nmintxfee=abc\nmaxapsfee=-1\n",
+        ));
+        let mut summary: Vec<(&str, Severity)> =
+            found.iter().map(|d| (d.key.as_str(), d.severity)).collect();
+        summary.sort_unstable();
+        assert_eq!(
+            summary,
+            vec![
+                ("fallbackfee", Severity::Warning),
+                ("maxtxfee", Severity::Error),
+                ("mintxfee", Severity::Error),
+                ("paytxfee", Severity::Error),
+            ]
+        );
+        let paytxfee = found.iter().find(|d| d.key == "paytxfee").unwrap();
+        assert_eq!(
+            paytxfee.message,
+            "1 sat/vB is below minrelaytxfee (2 sat/vB); transactions would not relay"
+        );
+    }
+
+    #[test]
+    fn reports_ignored_duplicate_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let conf = dir.path().join("bitcoin.conf");
+        std::fs::write(&conf, "dbcache=450\ndbcache=4000\n").unwrap();
+        assert_eq!(
+            duplicate_keys(&conf),
+            vec![Diagnostic::warning(
+                "dbcache",
+                "line 2: 4000 is ignored, bitcoind uses 450 from line 1 (press u to clean up)"
+            )]
+        );
+    }
+
+    #[test]
+    fn flags_dangling_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let datadir = dir.path().join("data");
+        std::fs::create_dir_all(datadir.join("signet")).unwrap();
+        std::fs::write(dir.path().join("not-a-dir"), "").unwrap();
+        let found = dangling_paths(&entries(
+            dir.path(),
+            &format!(
+                "signet=1\ndatadir={}\nblocksdir={}\nwalletdir=wallets\n\
+                 rpccookiefile=missing/.cookie\nloadblock=bootstrap.dat\n\
+                 debuglogfile=~/pdm-debug.log\nincludeconf=${{EXTRA}}\n",
+                datadir.display(),
+                dir.path().join("not-a-dir").display()
+            ),
+        ));
+        let mut summary: Vec<&str> = found.iter().map(|d| d.key.as_str()).collect();
+        summary.sort_unstable();
+        assert_eq!(
+            summary,
+            ["blocksdir", "debuglogfile", "loadblock", "rpccookiefile"]
+        );
+        let cookie = found.iter().find(|d| d.key == "rpccookiefile").unwrap();
+        assert_eq!(
+            cookie.message,
+            format!(
+                "{} does not exist, nor does {}",
+                datadir.join("signet/missing/.cookie").display(),
+                datadir.join("signet/missing").display()
+            )
+        );
+        let log = found.iter().find(|d| d.key == "debuglogfile").unwrap();
+        assert!(
+            log.message
+                .starts_with("bitcoind does not expand ~; write /")
+        );
+        assert_eq!(expand_home("~/x"), expand_home("~").join("x"));
+        assert_eq!(expand_home("~bob/x"), PathBuf::from("~bob/x"));
+    }
+
+    #[test]
+    fn flags_ports_shared_between_services() {
+        let dir = tempfile::tempdir().unwrap();
+        let conflicts = port_conflicts(&entries(
+            dir.path(),
+            "rpcport=8333\nzmqpubrawblock=tcp://127.0.0.1:28332\nzmqpubrawtx=tcp://127.0.0.1:28332\n",
+        ));
+        assert_eq!(
+            conflicts,
+            vec![Diagnostic::error(
+                "rpcport",
+                "port 8333 is also used by port"
+            )]
+        );
+
+        // ZMQ topics on one endpoint, and binds on the P2P port, are fine
+        let ok = entries(
+            dir.path(),
+            "bind=0.0.0.0:8333\nzmqpubrawblock=tcp://127.0.0.1:28332\nzmqpubrawtx=tcp://127.0.0.1:28332\n",
+        );
+        assert!(port_conflicts(&ok).is_empty());
+
+        let zmq = entries(
+            dir.path(),
+            "zmqpubrawblock=tcp://127.0.0.1:28332\nzmqpubrawtx=tcp://0.0.0.0:28332\n",
+        );
+        assert_eq!(port_conflicts(&zmq).len(), 1);
+    }
+
+    #[test]
+    fn flags_profiles_sharing_a_port() {
+        let dir = tempfile::tempdir().unwrap();
+        let conf = dir.path().join("bitcoin.conf");
+        let template = entries(dir.path(), "regtest=1\nport=${p2p}\nrpcport=${rpc}\n");
+        std::fs::write(
+            crate::template::profiles_path(&conf),
+            r#"
+[[profile]]
+name = "a"
+vars = { p2p = "18444", rpc = "18443" }
+
+[[profile]]
+name = "b"
+vars = { p2p = "18544", rpc = "18443" }
+"#,
+        )
+        .unwrap();
+        let conflicts = profile_conflicts(&conf, &template);
+        assert_eq!(
+            conflicts,
+            vec![Diagnostic::error(
+                "rpcport",
+                "profiles a and b both use port 18443"
+            )]
+        );
+    }
+
+    #[test]
+    fn detects_port_already_bound() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let port = socket.local_addr().unwrap().port();
+        let found = ports_in_use(&entries(dir.path(), &format!("rpcport={port}\n")));
+        assert!(
+            found
+                .iter()
+                .any(|d| d.key == "rpcport" && d.severity == Severity::Warning)
+        );
+    }
+
+    #[test]
+    fn chain_mismatch_compares_node_and_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let node = NodeChain {
+            endpoint: "127.0.0.1:8332".to_string(),
+            chain: "main".to_string(),
+        };
+        assert!(chain_mismatch(&node, &entries(dir.path(), "txindex=1\n")).is_none());
+
+        let d = chain_mismatch(&node, &entries(dir.path(), "chain=signet\n")).unwrap();
+        assert_eq!(d.severity, Severity::Error);
+        assert_eq!(d.key, "chain");
+        assert!(
+            d.message
+                .starts_with("bitcoind at 127.0.0.1:8332 runs main")
+        );
+        assert!(d.message.contains("selects signet"));
+    }
+}
@@ -0,0 +1,245 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_config::{ConfigEntry, effective_p2p_port, effective_rpc_port, enabled_value};
+use anyhow::{Result, bail};
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+/// First line of every generated file; only files starting with it are overwritten.
+pub const HEADER: &str = "# Generated by PDM";
+
+const BITCOIND_IMAGE: &str = "bitcoin/bitcoin:latest";
+const ELECTRS_IMAGE: &str = "getumbrel/electrs:latest";
+const TOR_IMAGE: &str = "dockurr/tor:latest";
+
+/// Where the bitcoind image keeps its data directory.
+const CONTAINER_DATADIR: &str = "/home/bitcoin/.bitcoin";
+
+/// Config files and parsed entries of the stack to export.
+pub struct ComposeStack<'a> {
+    pub bitcoin_conf: &'a Path,
+    pub bitcoin: &'a [ConfigEntry],
+    /// electrs `config.toml` and its entries, if loaded
+    pub electrs: Option<(&'a Path, &'a [ConfigEntry])>,
+    /// torrc, if loaded
+    pub torrc: Option<&'a Path>,
+}
+
+/// Extracts the TCP port from a ZMQ endpoint such as `tcp://127.0.0.1:28332`.
+fn zmq_port(endpoint: &str) -> Option<u16> {
+    endpoint
+        .strip_prefix("tcp://")
+        .and_then(|addr| addr.rsplit_once(':'))
+        .and_then(|(_, port)| port.parse().ok())
+}
+
+/// Returns the distinct ZMQ ports bitcoind publishes on.
+fn zmq_ports(bitcoin: &[ConfigEntry]) -> BTreeSet<u16> {
+    bitcoin
+        .iter()
+        .filter(|e| e.enabled && e.key.starts_with("zmqpub"))
+        .filter_map(|e| zmq_port(&e.value))
+        .collect()
+}
+
+/// Renders a docker-compose.yml for the configured stack.
+#[must_use]
+pub fn generate_compose(stack: &ComposeStack) -> String {
+    let conf = stack.bitcoin_conf.display();
+    let p2p = effective_p2p_port(stack.bitcoin);
+    let rpc = effective_rpc_port(stack.bitcoin);
+    let data_volume = enabled_value(stack.bitcoin, "datadir").unwrap_or("bitcoin-data");
+
+    let mut out = String::new();
+    let _ = writeln!(out, "{HEADER} from {conf}");
+    let _ = writeln!(
+        out,
+        "# bitcoind must bind RPC and ZMQ to 0.0.0.0 inside the container for these mappings to work."
+    );
+    let _ = writeln!(
+        out,
+        "# Ports are published on 127.0.0.1 only; drop the prefix to accept inbound connections."
+    );
+    out.push_str("services:\n");
+    out.push_str("  bitcoind:\n");
+    let _ = writeln!(out, "    image: {BITCOIND_IMAGE}");
+    out.push_str("    restart: unless-stopped\n");
+    // A datadir= in the mounted conf names a host path; inside the
+    // container the data lives at the mount point
+    let _ = writeln!(out, "    command:\n      - -datadir={CONTAINER_DATADIR}");
+    out.push_str("    volumes:\n");
+    let _ = writeln!(out, "      - {conf}:{CONTAINER_DATADIR}/bitcoin.conf:ro");
+    let _ = writeln!(out, "      - {data_volume}:{CONTAINER_DATADIR}");
+    out.push_str("    ports:\n");
+    let _ = writeln!(out, "      - \"127.0.0.1:{p2p}:{p2p}\"");
+    let _ = writeln!(out, "      - \"127.0.0.1:{rpc}:{rpc}\"");
+    for port in zmq_ports(stack.bitcoin) {
+        let _ = writeln!(out, "      - \"127.0.0.1:{port}:{port}\"");
+    }
+
+    if let Some((path, entries)) = stack.electrs {
+        let electrum_port = entries
+            .iter()
+            .find(|e| e.key == "electrum_rpc_addr")
+            .and_then(|e| e.value.rsplit_once(':'))
+            .map_or("50001", |(_, port)| port);
+        out.push_str("\n  electrs:\n");
+        let _ = writeln!(out, "    image: {ELECTRS_IMAGE}");
+        out.push_str("    restart: unless-stopped\n");
+        out.push_str("    depends_on:\n      - bitcoind\n");
+        // The config's addresses name the host; reach bitcoind by its
+        // service name instead (electrs reads ELECTRS_* over the file)
+        out.push_str("    environment:\n");
+        let _ = writeln!(out, "      ELECTRS_DAEMON_RPC_ADDR: \"bitcoind:{rpc}\"");
+        let _ = writeln!(out, "      ELECTRS_DAEMON_P2P_ADDR: \"bitcoind:{p2p}\"");
+        out.push_str("    volumes:\n");
+        let _ = writeln!(
+            out,
+            "      - {}:/etc/electrs/config.toml:ro",
+            path.display()
+        );
+        out.push_str("      - electrs-data:/data\n");
+        out.push_str("    ports:\n");
+        let _ = writeln!(out, "      - \"127.0.0.1:{electrum_port}:{electrum_port}\"");
+    }
+
+    if let Some(path) = stack.torrc {
+        out.push_str("\n  tor:\n");
+        let _ = writeln!(out, "    image: {TOR_IMAGE}");
+        out.push_str("    restart: unless-stopped\n");
+        out.push_str("    volumes:\n");
+        let _ = writeln!(out, "      - {}:/etc/tor/torrc:ro", path.display());
+        out.push_str("      - tor-data:/var/lib/tor\n");
+    }
+
+    let mut volumes: Vec<&str> = Vec::new();
+    if enabled_value(stack.bitcoin, "datadir").is_none() {
+        volumes.push("bitcoin-data");
+    }
+    if stack.electrs.is_some() {
+        volumes.push("electrs-data");
+    }
+    if stack.torrc.is_some() {
+        volumes.push("tor-data");
+    }
+    if !volumes.is_empty() {
+        out.push_str("\nvolumes:\n");
+        for volume in volumes {
+            let _ = writeln!(out, "  {volume}:");
+        }
+    }
+
+    out
+}
+
+/// Writes the compose file next to bitcoin.conf and returns its path.
+///
+/// # Errors
+/// Returns an error if a `docker-compose.yml` not generated by PDM already
+/// exists there, or if the file cannot be written.
+pub fn export_compose(stack: &ComposeStack) -> Result<PathBuf> {
+    let dir = stack.bitcoin_conf.parent().unwrap_or(Path::new("."));
+    let path = dir.join("docker-compose.yml");
+    write_generated(&path, &generate_compose(stack))?;
+    Ok(path)
+}
+
+/// Writes `content` (which must start with [`HEADER`]) to `path`, refusing to
+/// replace a file that PDM did not generate.
+///
+/// # Errors
+/// Returns an error if `path` holds a hand-written file or cannot be written.
+pub fn write_generated(path: &Path, content: &str) -> Result<()> {
+    if std::fs::read_to_string(path).is_ok_and(|existing| !existing.starts_with(HEADER)) {
+        bail!("{} exists and was not generated by PDM", path.display());
+    }
+    std::fs::write(path, content)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bitcoin(dir: &Path, content: &str) -> (PathBuf, Vec<ConfigEntry>) {
+        let path = dir.join("bitcoin.conf");
+        std::fs::write(&path, content).unwrap();
+        let entries = crate::bitcoin_config::parse_config(&path).unwrap();
+        (path, entries)
+    }
+
+    #[test]
+    fn compose_maps_p2p_rpc_and_zmq_ports() {
+        let dir = tempfile::tempdir().unwrap();
+        let (path, entries) = bitcoin(
+            dir.path(),
+            "port=8335\nrpcport=8336\nzmqpubrawblock=tcp://0.0.0.0:28332\nzmqpubrawtx=tcp://0.0.0.0:28333\n",
+        );
+        let yaml = generate_compose(&ComposeStack {
+            bitcoin_conf: &path,
+            bitcoin: &entries,
+            electrs: None,
+            torrc: None,
+        });
+
+        assert!(yaml.starts_with(HEADER));
+        assert!(yaml.contains("- \"127.0.0.1:8335:8335\""));
+        assert!(yaml.contains("- \"127.0.0.1:8336:8336\""));
+        assert!(yaml.contains("- \"127.0.0.1:28332:28332\""));
+        assert!(yaml.contains("- \"127.0.0.1:28333:28333\""));
+        assert!(yaml.contains(&format!(
+            "{}:/home/bitcoin/.bitcoin/bitcoin.conf:ro",
+            path.display()
+        )));
+        assert!(yaml.contains("volumes:\n  bitcoin-data:"));
+        assert!(!yaml.contains("electrs:"));
+    }
+
+    #[test]
+    fn compose_adds_optional_services() {
+        let dir = tempfile::tempdir().unwrap();
+        let (path, entries) = bitcoin(dir.path(), "datadir=/srv/bitcoin\n");
+        let electrs_path = dir.path().join("config.toml");
+        let electrs =
+            crate::electrs_config::parse_electrs_str("electrum_rpc_addr = \"0.0.0.0:50002\"")
+                .unwrap();
+        let torrc = dir.path().join("torrc");
+
+        let yaml = generate_compose(&ComposeStack {
+            bitcoin_conf: &path,
+            bitcoin: &entries,
+            electrs: Some((&electrs_path, &electrs)),
+            torrc: Some(&torrc),
+        });
+
+        assert!(yaml.contains("- /srv/bitcoin:/home/bitcoin/.bitcoin"));
+        assert!(yaml.contains("command:\n      - -datadir=/home/bitcoin/.bitcoin\n"));
+        assert!(yaml.contains("  electrs:\n"));
+        assert!(yaml.contains("ELECTRS_DAEMON_RPC_ADDR: \"bitcoind:8332\""));
+        assert!(yaml.contains("- \"127.0.0.1:50002:50002\""));
+        assert!(yaml.contains("  tor:\n"));
+        assert!(!yaml.contains("  bitcoin-data:"));
+    }
+
+    #[test]
+    fn export_refuses_to_overwrite_foreign_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let (path, entries) = bitcoin(dir.path(), "server=1\n");
+        let stack = ComposeStack {
+            bitcoin_conf: &path,
+            bitcoin: &entries,
+            electrs: None,
+            torrc: None,
+        };
+
+        let written = export_compose(&stack).unwrap();
+        // A file we generated ourselves may be regenerated
+        assert!(export_compose(&stack).is_ok());
+
+        std::fs::write(&written, "services: {}\n").unwrap();
+        assert!(export_compose(&stack).is_err());
+    }
+}
@@ -0,0 +1,43 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use anyhow::{Context, Result, bail};
+use std::process::Command;
+use std::time::Duration;
+
+/// Fetches `url` with curl, which also takes `file://` URLs, giving up
+/// after `timeout`.
+///
+/// # Errors
+/// Returns an error if curl cannot be run or the download fails.
+pub fn fetch(url: &str, timeout: Duration) -> Result<Vec<u8>> {
+    let output = Command::new("curl")
+        .args(["--fail", "--silent", "--show-error", "--location"])
+        .arg("--max-time")
+        .arg(timeout.as_secs().max(1).to_string())
+        .arg(url)
+        .output()
+        .context("cannot run curl")?;
+    if !output.status.success() {
+        bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(output.stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fetch_reads_file_urls_and_reports_failures() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("data");
+        std::fs::write(&file, b"bytes").unwrap();
+        let url = format!("file://{}", file.display());
+        assert_eq!(fetch(&url, Duration::from_secs(5)).unwrap(), b"bytes");
+
+        let missing = format!("file://{}", dir.path().join("missing").display());
+        assert!(fetch(&missing, Duration::from_secs(5)).is_err());
+    }
+}
@@ -0,0 +1,111 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_config::{ConfigEntry, reset_entry};
+
+/// One recorded edit, kept by option name so it replays on a config
+/// whose options are in another order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MacroStep {
+    /// Set the option to the value and enable it
+    Set { key: String, value: String },
+    /// Disable the option and put back its default
+    Reset { key: String },
+}
+
+impl MacroStep {
+    #[must_use]
+    pub fn key(&self) -> &str {
+        match self {
+            Self::Set { key, .. } | Self::Reset { key } => key,
+        }
+    }
+}
+
+/// Adds `step` to `steps`. An earlier step on the same option is
+/// dropped, since replaying it would only be undone.
+pub fn record(steps: &mut Vec<MacroStep>, step: MacroStep) {
+    steps.retain(|s| s.key() != step.key());
+    steps.push(step);
+}
+
+/// What replaying a macro did.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplayOutcome {
+    /// Steps that changed an entry
+    pub applied: usize,
+    /// Options the steps named that `entries` has no entry for
+    pub missing: Vec<String>,
+}
+
+/// Applies `steps` in order to the first entry of each option in
+/// `entries`.
+pub fn replay(steps: &[MacroStep], entries: &mut [ConfigEntry]) -> ReplayOutcome {
+    let mut outcome = ReplayOutcome::default();
+    for step in steps {
+        let Some(entry) = entries.iter_mut().find(|e| e.key == step.key()) else {
+            outcome.missing.push(step.key().to_string());
+            continue;
+        };
+        match step {
+            MacroStep::Set { value, .. } => {
+                if entry.enabled && entry.value == *value {
+                    continue;
+                }
+                entry.value.clone_from(value);
+                entry.enabled = true;
+            }
+            MacroStep::Reset { .. } => {
+                if !entry.enabled {
+                    continue;
+                }
+                reset_entry(entry);
+            }
+        }
+        outcome.applied += 1;
+    }
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitcoin_config::{NodeFlavor, enabled_value, parse_config_str};
+
+    fn set(key: &str, value: &str) -> MacroStep {
+        MacroStep::Set {
+            key: key.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn later_steps_replace_earlier_ones_on_the_same_option() {
+        let mut steps = Vec::new();
+        record(&mut steps, set("dbcache", "1000"));
+        record(&mut steps, set("prune", "550"));
+        record(&mut steps, set("dbcache", "2000"));
+        assert_eq!(steps, [set("prune", "550"), set("dbcache", "2000")]);
+    }
+
+    #[test]
+    fn replay_applies_steps_by_option_name() {
+        let steps = vec![
+            set("dbcache", "1000"),
+            set("server", "1"),
+            MacroStep::Reset {
+                key: "txindex".to_string(),
+            },
+            set("nosuchoption", "1"),
+        ];
+        let mut entries = parse_config_str("txindex=1\nserver=1\n", NodeFlavor::Core).unwrap();
+        let outcome = replay(&steps, &mut entries);
+        // dbcache comes from the schema, disabled; server is already set
+        assert_eq!(outcome.applied, 2);
+        assert_eq!(outcome.missing, ["nosuchoption"]);
+        assert_eq!(enabled_value(&entries, "dbcache"), Some("1000"));
+        assert_eq!(enabled_value(&entries, "txindex"), None);
+        assert_eq!(enabled_value(&entries, "server"), Some("1"));
+    }
+}
@@ -0,0 +1,388 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_config::{
+    ConfigCategory, ConfigEntry, ConfigSchema, ConfigType, active_network, effective_p2p_port,
+    effective_rpc_port, enabled_value,
+};
+use crate::secrets::referenced_account;
+use anyhow::Result;
+use std::path::Path;
+use toml_edit::DocumentMut;
+
+/// Returns the schema for the electrs `config.toml` options
+#[must_use]
+#[allow(clippy::too_many_lines)]
+pub fn get_electrs_schema() -> Vec<ConfigSchema> {
+    vec![
+        // General
+        ConfigSchema::new(
+            "network",
+            "bitcoin",
+            ConfigType::String,
+            ConfigCategory::ElectrsGeneral,
+            "Network to index (bitcoin, testnet, signet, regtest)",
+        ),
+        ConfigSchema::new(
+            "electrum_rpc_addr",
+            "127.0.0.1:50001",
+            ConfigType::Address,
+            ConfigCategory::ElectrsGeneral,
+            "Address the Electrum RPC server listens on",
+        ),
+        ConfigSchema::new(
+            "monitoring_addr",
+            "127.0.0.1:4224",
+            ConfigType::Address,
+            ConfigCategory::ElectrsGeneral,
+            "Address of the Prometheus monitoring endpoint",
+        ),
+        ConfigSchema::new(
+            "log_filters",
+            "INFO",
+            ConfigType::String,
+            ConfigCategory::ElectrsGeneral,
+            "Log filter directives",
+        ),
+        ConfigSchema::new(
+            "server_banner",
+            "",
+            ConfigType::String,
+            ConfigCategory::ElectrsGeneral,
+            "Banner shown to Electrum clients",
+        ),
+        // bitcoind connection
+        ConfigSchema::new(
+            "daemon_dir",
+            "",
+            ConfigType::Path,
+            ConfigCategory::ElectrsDaemon,
+            "bitcoind data directory",
+        ),
+        ConfigSchema::new(
+            "daemon_rpc_addr",
+            "127.0.0.1:8332",
+            ConfigType::Address,
+            ConfigCategory::ElectrsDaemon,
+            "bitcoind RPC address",
+        ),
+        ConfigSchema::new(
+            "daemon_p2p_addr",
+            "127.0.0.1:8333",
+            ConfigType::Address,
+            ConfigCategory::ElectrsDaemon,
+            "bitcoind P2P address",
+        ),
+        ConfigSchema::new(
+            "auth",
+            "",
+            ConfigType::String,
+            ConfigCategory::ElectrsDaemon,
+            "bitcoind RPC credentials as user:password",
+        ),
+        ConfigSchema::new(
+            "cookie_file",
+            "",
+            ConfigType::Path,
+            ConfigCategory::ElectrsDaemon,
+            "bitcoind RPC cookie file",
+        ),
+        ConfigSchema::new(
+            "skip_block_download_wait",
+            "false",
+            ConfigType::Bool,
+            ConfigCategory::ElectrsDaemon,
+            "Do not wait for bitcoind to finish initial block download",
+        ),
+        // Index
+        ConfigSchema::new(
+            "db_dir",
+            "./db",
+            ConfigType::Path,
+            ConfigCategory::ElectrsIndex,
+            "Directory for the index database",
+        ),
+        ConfigSchema::new(
+            "index_batch_size",
+            "10",
+            ConfigType::Int,
+            ConfigCategory::ElectrsIndex,
+            "Blocks fetched per indexing batch",
+        ),
+        ConfigSchema::new(
+            "index_lookup_limit",
+            "",
+            ConfigType::Int,
+            ConfigCategory::ElectrsIndex,
+            "Refuse address queries matching more transactions than this",
+        ),
+        ConfigSchema::new(
+            "reindex_last_blocks",
+            "0",
+            ConfigType::Int,
+            ConfigCategory::ElectrsIndex,
+            "Re-index this many recent blocks at startup",
+        ),
+    ]
+}
+
+/// Parses electrs TOML content into schema-backed entries.
+///
+/// # Errors
+/// Returns an error if the content is not valid TOML.
+pub fn parse_electrs_str(content: &str) -> Result<Vec<ConfigEntry>> {
+    let table: toml::Table = content.parse()?;
+
+    let mut entries: Vec<ConfigEntry> = get_electrs_schema()
+        .into_iter()
+        .map(|schema| ConfigEntry {
+            key: schema.key.clone(),
+            value: schema.default.clone(),
+            enabled: false,
+            section: None,
+            negated: false,
+            schema: Some(schema.into()),
+        })
+        .collect();
+
+    for (key, value) in &table {
+        let value = match value {
+            toml::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        if let Some(entry) = entries.iter_mut().find(|e| &e.key == key) {
+            entry.value = value;
+            entry.enabled = true;
+        } else {
+            entries.push(ConfigEntry {
+                key: key.clone(),
+                value,
+                schema: None,
+                enabled: true,
+                section: None,
+                negated: false,
+            });
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Parse an electrs `config.toml`
+///
+/// # Errors
+/// Returns an error if the file exists but cannot be read or is not valid TOML.
+/// A missing file yields schema defaults.
+pub fn parse_electrs_config(path: &Path) -> Result<Vec<ConfigEntry>> {
+    let content = if path.exists() {
+        std::fs::read_to_string(path)?
+    } else {
+        String::new()
+    };
+    parse_electrs_str(&content)
+}
+
+/// Converts an entry value into a TOML value of the type its schema declares.
+/// Keys without a schema keep integers and booleans unquoted.
+fn toml_value(entry: &ConfigEntry) -> toml_edit::Item {
+    let config_type = entry.schema.as_ref().map(|s| s.config_type);
+    let as_int = entry.value.parse::<i64>().ok();
+    let as_bool = entry.value.parse::<bool>().ok();
+    match (config_type, as_int, as_bool) {
+        (Some(ConfigType::Int) | None, Some(n), _) => toml_edit::value(n),
+        (Some(ConfigType::Bool) | None, _, Some(b)) => toml_edit::value(b),
+        _ => toml_edit::value(entry.value.clone()),
+    }
+}
+
+/// Writes enabled entries into the electrs config, keeping the comments and
+/// layout of the existing file.
+///
+/// # Errors
+/// Returns an error if the existing file is not valid TOML or cannot be written.
+pub fn save_electrs_config(path: &Path, entries: &[ConfigEntry]) -> Result<()> {
+    let original = if path.exists() {
+        std::fs::read_to_string(path)?
+    } else {
+        String::new()
+    };
+    let mut doc = original.parse::<DocumentMut>()?;
+
+    for entry in entries.iter().filter(|e| e.enabled) {
+        doc[entry.key.as_str()] = toml_value(entry);
+    }
+
+    std::fs::write(path, doc.to_string())?;
+    Ok(())
+}
+
+/// Maps a bitcoind chain name to the name electrs uses for it.
+fn electrs_network(bitcoin_network: &str) -> &str {
+    match bitcoin_network {
+        "main" => "bitcoin",
+        "test" => "testnet",
+        other => other,
+    }
+}
+
+fn port_of(addr: &str) -> Option<u16> {
+    addr.rsplit_once(':').and_then(|(_, p)| p.parse().ok())
+}
+
+/// Compares the electrs bitcoind settings with the loaded bitcoin.conf and
+/// returns a message for each mismatch.
+#[must_use]
+pub fn cross_check(electrs: &[ConfigEntry], bitcoin: &[ConfigEntry]) -> Vec<String> {
+    let mut issues = Vec::new();
+    let value = |key: &str| {
+        electrs
+            .iter()
+            .find(|e| e.key == key)
+            .map(|e| e.value.as_str())
+            .unwrap_or_default()
+    };
+
+    let expected_network = electrs_network(active_network(bitcoin));
+    if value("network") != expected_network {
+        issues.push(format!(
+            "network is {} but bitcoin.conf runs {expected_network}",
+            value("network")
+        ));
+    }
+
+    let rpc_port = effective_rpc_port(bitcoin);
+    if let Some(port) = port_of(value("daemon_rpc_addr")).filter(|p| *p != rpc_port) {
+        issues.push(format!(
+            "daemon_rpc_addr uses port {port} but bitcoind RPC listens on {rpc_port}"
+        ));
+    }
+
+    let p2p_port = effective_p2p_port(bitcoin);
+    if let Some(port) = port_of(value("daemon_p2p_addr")).filter(|p| *p != p2p_port) {
+        issues.push(format!(
+            "daemon_p2p_addr uses port {port} but bitcoind P2P listens on {p2p_port}"
+        ));
+    }
+
+    if let Some((user, password)) = value("auth").split_once(':') {
+        if enabled_value(bitcoin, "rpcuser").is_some_and(|u| u != user) {
+            issues.push("auth user does not match rpcuser".to_string());
+        }
+        // A password kept in the keyring cannot be compared here
+        if enabled_value(bitcoin, "rpcpassword")
+            .is_some_and(|p| referenced_account(p).is_none() && p != password)
+        {
+            issues.push("auth password does not match rpcpassword".to_string());
+        }
+    }
+
+    let daemon_dir = value("daemon_dir");
+    if let Some(datadir) =
+        enabled_value(bitcoin, "datadir").filter(|d| !daemon_dir.is_empty() && *d != daemon_dir)
+    {
+        issues.push(format!(
+            "daemon_dir is {daemon_dir} but bitcoin.conf datadir is {datadir}"
+        ));
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"
+# electrs config
+network = "regtest"
+db_dir = "/var/lib/electrs"
+daemon_rpc_addr = "127.0.0.1:18443"
+daemon_p2p_addr = "127.0.0.1:18444"
+electrum_rpc_addr = "0.0.0.0:60401"
+auth = "alice:secret"
+index_batch_size = 20
+"#;
+
+    fn bitcoin(content: &str) -> (tempfile::TempDir, Vec<ConfigEntry>) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bitcoin.conf");
+        std::fs::write(&path, content).unwrap();
+        let entries = crate::bitcoin_config::parse_config(&path).unwrap();
+        (dir, entries)
+    }
+
+    #[test]
+    fn schema_keys_are_unique() {
+        let schema = get_electrs_schema();
+        let mut keys: Vec<&str> = schema.iter().map(|s| s.key.as_str()).collect();
+        keys.sort_unstable();
+        keys.dedup();
+        assert_eq!(keys.len(), schema.len());
+    }
+
+    #[test]
+    fn parse_reads_strings_and_numbers() {
+        let entries = parse_electrs_str(SAMPLE).unwrap();
+        let db = entries.iter().find(|e| e.key == "db_dir").unwrap();
+        assert_eq!(db.value, "/var/lib/electrs");
+        assert!(db.enabled);
+        let batch = entries
+            .iter()
+            .find(|e| e.key == "index_batch_size")
+            .unwrap();
+        assert_eq!(batch.value, "20");
+    }
+
+    #[test]
+    fn parse_rejects_invalid_toml() {
+        assert!(parse_electrs_str("db_dir = ").is_err());
+    }
+
+    #[test]
+    fn save_keeps_comments_and_types() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, SAMPLE).unwrap();
+        let mut entries = parse_electrs_config(&path).unwrap();
+        let batch = entries
+            .iter_mut()
+            .find(|e| e.key == "index_batch_size")
+            .unwrap();
+        batch.value = "50".to_string();
+
+        save_electrs_config(&path, &entries).unwrap();
+        let saved = std::fs::read_to_string(&path).unwrap();
+
+        assert!(saved.contains("# electrs config"));
+        assert!(saved.contains("index_batch_size = 50"));
+        assert!(saved.contains("network = \"regtest\""));
+    }
+
+    #[test]
+    fn cross_check_accepts_matching_bitcoin_conf() {
+        let (_dir, btc) = bitcoin("regtest=1\nrpcuser=alice\nrpcpassword=secret\n");
+        let electrs = parse_electrs_str(SAMPLE).unwrap();
+        assert!(cross_check(&electrs, &btc).is_empty());
+    }
+
+    #[test]
+    fn cross_check_reports_network_port_and_auth_mismatches() {
+        let (_dir, btc) = bitcoin("rpcuser=bob\nrpcport=8332\n");
+        let electrs = parse_electrs_str(SAMPLE).unwrap();
+        let issues = cross_check(&electrs, &btc);
+        assert!(issues.iter().any(|i| i.starts_with("network is regtest")));
+        assert!(issues.iter().any(|i| i.starts_with("daemon_rpc_addr")));
+        assert!(issues.iter().any(|i| i.starts_with("daemon_p2p_addr")));
+        assert!(issues.iter().any(|i| i.contains("rpcuser")));
+    }
+
+    #[test]
+    fn cross_check_skips_a_password_kept_in_the_keyring() {
+        let (_dir, btc) = bitcoin(
+            "regtest=1\nrpcuser=alice\nrpcpassword=${secret:/etc/bitcoin.conf#rpcpassword}\n",
+        );
+        let electrs = parse_electrs_str(SAMPLE).unwrap();
+        assert!(cross_check(&electrs, &btc).is_empty());
+    }
+}
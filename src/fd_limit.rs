@@ -0,0 +1,199 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_config::{ConfigEntry, enabled_value};
+use crate::diagnostics::Diagnostic;
+use crate::indexes::{INDEXES, enables};
+use std::path::Path;
+
+/// Descriptors bitcoind keeps for itself: block files, the chainstate
+/// and block index databases, logs.
+pub const MIN_CORE_FDS: u64 = 150;
+
+/// Connections `addnode` may open beyond `maxconnections`.
+pub const MAX_ADDNODE_CONNECTIONS: u64 = 8;
+
+/// Files LevelDB keeps open per index database.
+pub const INDEX_FDS: u64 = 64;
+
+/// Files a loaded SQLite wallet holds: the database and its journal.
+pub const WALLET_FDS: u64 = 2;
+
+const DEFAULT_MAX_CONNECTIONS: u64 = 125;
+const DEFAULT_RPC_THREADS: u64 = 4;
+
+/// File descriptors a config makes bitcoind ask for, by what uses them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FdBudget {
+    /// `maxconnections` plus the `addnode` allowance
+    pub peers: u64,
+    pub core: u64,
+    /// One listening socket per `bind` and `whitebind`, at least one
+    pub listeners: u64,
+    /// RPC worker threads and listening sockets
+    pub rpc: u64,
+    pub wallets: u64,
+    pub indexes: u64,
+}
+
+impl FdBudget {
+    #[must_use]
+    pub fn total(&self) -> u64 {
+        self.peers + self.core + self.listeners + self.rpc + self.wallets + self.indexes
+    }
+
+    /// `maxconnections` bitcoind falls back to under `limit`, as it does
+    /// at startup with only a line in debug.log. It reserves descriptors
+    /// for itself, `addnode` and the listeners, not for RPC, wallets or
+    /// indexes.
+    #[must_use]
+    pub fn connections_within(&self, limit: u64) -> u64 {
+        limit
+            .saturating_sub(self.core + MAX_ADDNODE_CONNECTIONS + self.listeners)
+            .min(self.peers - MAX_ADDNODE_CONNECTIONS)
+    }
+}
+
+/// The descriptors `entries` need.
+#[must_use]
+pub fn fd_budget(entries: &[ConfigEntry]) -> FdBudget {
+    let number = |key: &str, default: u64| {
+        enabled_value(entries, key)
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(default)
+    };
+    let count = |keys: &[&str]| {
+        entries
+            .iter()
+            .filter(|e| e.enabled && keys.contains(&e.key.as_str()))
+            .count() as u64
+    };
+    let wallets = if enabled_value(entries, "disablewallet").is_some_and(enables) {
+        0
+    } else {
+        count(&["wallet"]) * WALLET_FDS
+    };
+    let indexes = INDEXES
+        .iter()
+        .filter(|i| enabled_value(entries, i.key).is_some_and(enables))
+        .count() as u64;
+    FdBudget {
+        peers: number("maxconnections", DEFAULT_MAX_CONNECTIONS) + MAX_ADDNODE_CONNECTIONS,
+        core: MIN_CORE_FDS,
+        listeners: count(&["bind", "whitebind"]).max(1),
+        rpc: number("rpcthreads", DEFAULT_RPC_THREADS) + count(&["rpcbind"]).max(1),
+        wallets,
+        indexes: indexes * INDEX_FDS,
+    }
+}
+
+/// The soft and hard `Max open files` of a `/proc/<pid>/limits` file;
+/// `unlimited` reads as `u64::MAX`.
+#[must_use]
+pub fn parse_limits(limits: &str) -> Option<(u64, u64)> {
+    let line = limits.lines().find(|l| l.starts_with("Max open files"))?;
+    let mut values = line["Max open files".len()..]
+        .split_whitespace()
+        .map(|v| match v {
+            "unlimited" => Some(u64::MAX),
+            v => v.parse().ok(),
+        });
+    Some((values.next()??, values.next()??))
+}
+
+/// The open file limits of process `pid` under `proc_dir`, or of PDM
+/// itself, whose limits a bitcoind it starts inherits.
+#[must_use]
+pub fn process_limits(proc_dir: &Path, pid: Option<u32>) -> Option<(u64, u64)> {
+    let dir = pid.map_or_else(|| "self".to_string(), |pid| pid.to_string());
+    parse_limits(&std::fs::read_to_string(proc_dir.join(dir).join("limits")).ok()?)
+}
+
+/// A warning when the hard limit is below what `entries` need. bitcoind
+/// raises its soft limit up to the hard one by itself, then cuts
+/// `maxconnections` to fit.
+#[must_use]
+pub fn fd_warning(entries: &[ConfigEntry], (_, hard): (u64, u64)) -> Option<Diagnostic> {
+    let budget = fd_budget(entries);
+    let needed = budget.total();
+    if needed <= hard {
+        return None;
+    }
+    let connections = budget.connections_within(hard);
+    let effect = if connections < budget.peers - MAX_ADDNODE_CONNECTIONS {
+        format!("bitcoind lowers maxconnections to {connections}")
+    } else {
+        "RPC, wallets and indexes may run out of descriptors under load".to_string()
+    };
+    Some(Diagnostic::warning(
+        "maxconnections",
+        format!(
+            "needs {needed} file descriptors ({} peers, {} core, {} listening, {} RPC, {} \
+             wallets, {} indexes) but the limit is {hard}, so {effect}; raise it with \
+             ulimit -n {needed} or LimitNOFILE={needed} in the service",
+            budget.peers, budget.core, budget.listeners, budget.rpc, budget.wallets, budget.indexes,
+        ),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitcoin_config::{NodeFlavor, parse_config_str};
+
+    #[test]
+    fn budget_adds_up_peers_services_and_indexes() {
+        let entries = parse_config_str(
+            "maxconnections=400\nrpcthreads=8\ntxindex=1\nbind=0.0.0.0\n\
+             whitebind=noban@127.0.0.1:8335\nwallet=hot\n",
+            NodeFlavor::Core,
+        )
+        .unwrap();
+        let budget = fd_budget(&entries);
+        assert_eq!(budget.peers, 408);
+        assert_eq!(budget.listeners, 2);
+        assert_eq!(budget.rpc, 9);
+        assert_eq!(budget.wallets, 2);
+        assert_eq!(budget.indexes, 64);
+        assert_eq!(budget.total(), 635);
+        assert_eq!(fd_budget(&[]).total(), 133 + 150 + 1 + 5);
+
+        assert_eq!(fd_warning(&entries, (1024, 4096)), None);
+        let warning = fd_warning(&entries, (1024, 512)).unwrap();
+        assert!(
+            warning.message.contains(
+                "limit is 512, so bitcoind lowers maxconnections to 352; raise it with ulimit -n 635"
+            ),
+            "{}",
+            warning.message
+        );
+        let warning = fd_warning(&entries, (600, 600)).unwrap();
+        assert!(
+            warning.message.contains("may run out"),
+            "{}",
+            warning.message
+        );
+    }
+
+    #[test]
+    fn limits_file_gives_the_open_file_limits() {
+        let limits = "Limit                     Soft Limit           Hard Limit           Units\n\
+                      Max processes             63471                63471                processes\n\
+                      Max open files            1024                 524288               files\n";
+        assert_eq!(parse_limits(limits), Some((1024, 524_288)));
+        assert_eq!(
+            parse_limits(
+                "Max open files            unlimited            unlimited            files"
+            ),
+            Some((u64::MAX, u64::MAX))
+        );
+        assert_eq!(parse_limits("Max processes 1 1 processes"), None);
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("42")).unwrap();
+        std::fs::write(dir.path().join("42/limits"), limits).unwrap();
+        assert_eq!(process_limits(dir.path(), Some(42)), Some((1024, 524_288)));
+        assert_eq!(process_limits(dir.path(), Some(7)), None);
+    }
+}
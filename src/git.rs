@@ -0,0 +1,193 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::history::Change;
+use crate::secrets::is_secret;
+use anyhow::{Result, bail};
+use std::path::Path;
+use std::process::{Command, Output};
+
+/// Runs `git` in the directory of `file`.
+fn git(file: &Path, args: &[&str]) -> Result<Output> {
+    let dir = file.parent().filter(|d| !d.as_os_str().is_empty());
+    let mut command = Command::new("git");
+    if let Some(dir) = dir {
+        command.arg("-C").arg(dir);
+    }
+    Ok(command.args(args).output()?)
+}
+
+fn file_arg(file: &Path) -> String {
+    file.file_name()
+        .map_or_else(String::new, |n| n.to_string_lossy().into_owned())
+}
+
+/// True when `file` is tracked in a git repository. Only tracked files are
+/// committed on save, so PDM never adds files to a repository on its own.
+#[must_use]
+pub fn is_tracked(file: &Path) -> bool {
+    git(
+        file,
+        &["ls-files", "--error-unmatch", "--", &file_arg(file)],
+    )
+    .is_ok_and(|o| o.status.success())
+}
+
+/// Commit message summarising `changes` to `file`: the changed keys in the
+/// subject, one `key: old -> new` line per change in the body.
+#[must_use]
+pub fn commit_message(file: &Path, changes: &[Change]) -> String {
+    let keys: Vec<&str> = changes.iter().map(|c| c.key.as_str()).collect();
+    let mut message = format!("Update {}: {}\n\n", file_arg(file), keys.join(", "));
+    for change in changes {
+        let show = |v: Option<&str>| match v {
+            None => "(not set)".to_string(),
+            Some(_) if is_secret(&change.key) => "(hidden)".to_string(),
+            Some(v) => v.to_string(),
+        };
+        message.push_str(&format!(
+            "{}: {} -> {}\n",
+            change.key,
+            show(change.old.as_deref()),
+            show(change.new.as_deref())
+        ));
+    }
+    message
+}
+
+/// Commits `file` alone with a message describing `changes`, if the file is
+/// tracked in a git repository. Returns whether a commit was made.
+///
+/// # Errors
+/// Returns an error if git refuses the commit.
+pub fn commit_changes(file: &Path, changes: &[Change]) -> Result<bool> {
+    if changes.is_empty() || !is_tracked(file) {
+        return Ok(false);
+    }
+    let message = commit_message(file, changes);
+    let output = git(
+        file,
+        &["commit", "--only", "-m", &message, "--", &file_arg(file)],
+    )?;
+    if !output.status.success() {
+        bail!(
+            "git commit failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(true)
+}
+
+/// Recent commits touching `file` (`<hash> <date> <subject>`), followed by
+/// any uncommitted diff.
+///
+/// # Errors
+/// Returns an error if `file` is not tracked in a git repository.
+pub fn log_and_diff(file: &Path, limit: usize) -> Result<Vec<String>> {
+    if !is_tracked(file) {
+        bail!("{} is not tracked in a git repository", file.display());
+    }
+    let name = file_arg(file);
+    let count = format!("-{limit}");
+    let log = git(
+        file,
+        &[
+            "log",
+            &count,
+            "--date=short",
+            "--format=%h %ad %s",
+            "--",
+            &name,
+        ],
+    )?;
+    let mut lines: Vec<String> = String::from_utf8_lossy(&log.stdout)
+        .lines()
+        .map(str::to_string)
+        .collect();
+    let diff = git(file, &["diff", "HEAD", "--", &name])?;
+    let diff = String::from_utf8_lossy(&diff.stdout);
+    if !diff.is_empty() {
+        lines.push(String::new());
+        lines.push("Uncommitted changes:".to_string());
+        lines.extend(diff.lines().map(str::to_string));
+    }
+    Ok(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(key: &str, old: Option<&str>, new: Option<&str>) -> Change {
+        Change {
+            timestamp: 0,
+            key: key.to_string(),
+            old: old.map(str::to_string),
+            new: new.map(str::to_string),
+        }
+    }
+
+    fn init_repo(dir: &Path) {
+        for args in [
+            &["init", "-q"][..],
+            &["config", "user.name", "PDM Test"],
+            &["config", "user.email", "pdm@example.com"],
+        ] {
+            let status = Command::new("git")
+                .arg("-C")
+                .arg(dir)
+                .args(args)
+                .status()
+                .unwrap();
+            assert!(status.success());
+        }
+    }
+
+    #[test]
+    fn message_lists_keys_and_hides_secrets() {
+        let message = commit_message(
+            Path::new("/srv/bitcoin.conf"),
+            &[
+                change("prune", None, Some("550")),
+                change("rpcpassword", Some("old"), Some("new")),
+            ],
+        );
+        assert!(message.starts_with("Update bitcoin.conf: prune, rpcpassword\n\n"));
+        assert!(message.contains("prune: (not set) -> 550\n"));
+        assert!(message.contains("rpcpassword: (hidden) -> (hidden)\n"));
+    }
+
+    #[test]
+    fn commits_only_tracked_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("bitcoin.conf");
+        std::fs::write(&file, "").unwrap();
+        let changes = [change("prune", None, Some("550"))];
+
+        // Not a repository
+        assert!(!commit_changes(&file, &changes).unwrap());
+        assert!(log_and_diff(&file, 10).is_err());
+
+        init_repo(dir.path());
+        assert!(!commit_changes(&file, &changes).unwrap());
+
+        let add = Command::new("git")
+            .arg("-C")
+            .arg(dir.path())
+            .args(["add", "bitcoin.conf"])
+            .status()
+            .unwrap();
+        assert!(add.success());
+        std::fs::write(&file, "prune=550\n").unwrap();
+        assert!(commit_changes(&file, &changes).unwrap());
+
+        let lines = log_and_diff(&file, 10).unwrap();
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].ends_with("Update bitcoin.conf: prune"));
+
+        std::fs::write(&file, "prune=1000\n").unwrap();
+        let lines = log_and_diff(&file, 10).unwrap();
+        assert!(lines.contains(&"+prune=1000".to_string()));
+    }
+}
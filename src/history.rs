@@ -0,0 +1,250 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_config::ConfigEntry;
+use crate::secrets::is_secret;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// One option changed by a save from PDM.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Change {
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    /// Option name, prefixed with its section (`test.rpcport`) when it has one
+    pub key: String,
+    /// Value before the save; `None` when the option was not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub old: Option<String>,
+    /// Value after the save; `None` when the option was removed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub new: Option<String>,
+}
+
+/// On-disk journal: a TOML file of `[[change]]` tables, appended to on every save.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Journal {
+    #[serde(default)]
+    change: Vec<Change>,
+}
+
+/// Journal of `file`, kept next to it as `<name>.history`.
+#[must_use]
+pub fn journal_path(file: &Path) -> PathBuf {
+    let name = file
+        .file_name()
+        .map_or_else(|| "config".into(), |n| n.to_string_lossy().into_owned());
+    file.with_file_name(format!("{name}.history"))
+}
+
+/// Enabled values by qualified key; repeated options are joined with ", ".
+fn values(entries: &[ConfigEntry]) -> BTreeMap<String, String> {
+    let mut map: BTreeMap<String, String> = BTreeMap::new();
+    for entry in entries.iter().filter(|e| e.enabled) {
+        let key = match &entry.section {
+            Some(section) => format!("{section}.{}", entry.key),
+            None => entry.key.clone(),
+        };
+        map.entry(key)
+            .and_modify(|v| {
+                v.push_str(", ");
+                v.push_str(&entry.value);
+            })
+            .or_insert_with(|| entry.value.clone());
+    }
+    map
+}
+
+/// What the journal keeps of `value`: secrets are only recorded as changed.
+fn shown(key: &str, value: String) -> String {
+    if is_secret(key) {
+        "(hidden)".to_string()
+    } else {
+        value
+    }
+}
+
+/// Options whose value differs between `before` and `after`, sorted by key.
+#[must_use]
+pub fn diff(before: &[ConfigEntry], after: &[ConfigEntry], timestamp: u64) -> Vec<Change> {
+    let mut old = values(before);
+    let new = values(after);
+    let mut changes: Vec<Change> = new
+        .into_iter()
+        .filter_map(|(key, value)| {
+            let previous = old.remove(&key);
+            let changed = previous.as_deref() != Some(value.as_str());
+            changed.then(|| Change {
+                timestamp,
+                old: previous.map(|v| shown(&key, v)),
+                new: Some(shown(&key, value)),
+                key,
+            })
+        })
+        .collect();
+    changes.extend(old.into_iter().map(|(key, value)| Change {
+        timestamp,
+        old: Some(shown(&key, value)),
+        new: None,
+        key,
+    }));
+    changes.sort_by(|a, b| a.key.cmp(&b.key));
+    changes
+}
+
+/// Appends `changes` to the journal of `file`.
+///
+/// # Errors
+/// Returns an error if the journal cannot be written.
+pub fn append(file: &Path, changes: &[Change]) -> Result<()> {
+    if changes.is_empty() {
+        return Ok(());
+    }
+    let content = toml::to_string(&Journal {
+        change: changes.to_vec(),
+    })?;
+    let mut journal = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(journal_path(file))?;
+    writeln!(journal, "{content}")?;
+    Ok(())
+}
+
+/// Records what saving `after` over `before` (the file as it was on disk)
+/// changed, and returns the recorded changes.
+///
+/// # Errors
+/// Returns an error if the journal cannot be written.
+pub fn record(file: &Path, before: &[ConfigEntry], after: &[ConfigEntry]) -> Result<Vec<Change>> {
+    let changes = diff(before, after, now());
+    append(file, &changes)?;
+    Ok(changes)
+}
+
+/// Reads the journal of `file`, oldest change first. A file PDM never saved has none.
+///
+/// # Errors
+/// Returns an error if the journal exists but cannot be read or parsed.
+pub fn load(file: &Path) -> Result<Vec<Change>> {
+    let path = journal_path(file);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let journal: Journal = toml::from_str(&std::fs::read_to_string(path)?)?;
+    Ok(journal.change)
+}
+
+#[must_use]
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Formats a Unix timestamp as `YYYY-MM-DD HH:MM UTC`.
+#[must_use]
+pub fn format_timestamp(timestamp: u64) -> String {
+    let days = i64::try_from(timestamp / 86_400).unwrap_or(0);
+    let secs = timestamp % 86_400;
+    // Civil-from-days, after Howard Hinnant's date algorithms
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02} UTC",
+        secs / 3600,
+        secs % 3600 / 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(dir: &Path, content: &str) -> Vec<ConfigEntry> {
+        let path = dir.join("bitcoin.conf");
+        std::fs::write(&path, content).unwrap();
+        crate::bitcoin_config::parse_config(&path).unwrap()
+    }
+
+    #[test]
+    fn diff_reports_set_changed_and_removed_options() {
+        let dir = tempfile::tempdir().unwrap();
+        let before = entries(dir.path(), "dbcache=450\ntxindex=1\n[test]\nrpcport=1\n");
+        let after = entries(dir.path(), "dbcache=4000\nprune=550\n[test]\nrpcport=1\n");
+
+        let changes = diff(&before, &after, 7);
+        let summary: Vec<(&str, Option<&str>, Option<&str>)> = changes
+            .iter()
+            .map(|c| (c.key.as_str(), c.old.as_deref(), c.new.as_deref()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("dbcache", Some("450"), Some("4000")),
+                ("prune", None, Some("550")),
+                ("txindex", Some("1"), None),
+            ]
+        );
+        assert!(changes.iter().all(|c| c.timestamp == 7));
+    }
+
+    #[test]
+    fn diff_hides_secret_values() {
+        let dir = tempfile::tempdir().unwrap();
+        let before = entries(
+            dir.path(),
+            "rpcpassword=old
+",
+        );
+        let after = entries(
+            dir.path(),
+            "rpcpassword=new
+",
+        );
+
+        let changes = diff(&before, &after, 0);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].old.as_deref(), Some("(hidden)"));
+        assert_eq!(changes[0].new.as_deref(), Some("(hidden)"));
+    }
+
+    #[test]
+    fn journal_appends_and_reads_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("bitcoin.conf");
+        assert!(load(&file).unwrap().is_empty());
+
+        let before = entries(dir.path(), "");
+        let after = entries(dir.path(), "prune=550\n");
+        assert_eq!(record(&file, &before, &after).unwrap().len(), 1);
+        assert_eq!(record(&file, &after, &before).unwrap().len(), 1);
+        // Saving without changes leaves the journal alone
+        assert!(record(&file, &after, &after).unwrap().is_empty());
+
+        let changes = load(&file).unwrap();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].new.as_deref(), Some("550"));
+        assert_eq!(changes[1].old.as_deref(), Some("550"));
+        assert!(changes[1].new.is_none());
+        assert!(dir.path().join("bitcoin.conf.history").exists());
+    }
+
+    #[test]
+    fn formats_timestamps_in_utc() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00 UTC");
+        assert_eq!(format_timestamp(1_709_210_096), "2024-02-29 12:34 UTC");
+    }
+}
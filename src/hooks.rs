@@ -0,0 +1,167 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::process::{Command, Stdio};
+
+/// Shell commands PDM runs on events it observes, set in the `[hooks]`
+/// table of settings.toml. Each runs with `sh -c` and gets the event in
+/// `PDM_EVENT` and what it concerns, such as a block hash or a file, in
+/// `PDM_DETAIL`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hooks {
+    /// bitcoind stopped answering RPC
+    pub node_down: Option<String>,
+    /// bitcoind answers RPC again
+    pub node_up: Option<String>,
+    /// Initial block download finished
+    pub sync_complete: Option<String>,
+    /// A block was connected, as `zmqpubsequence` reports it
+    pub new_block: Option<String>,
+    /// PDM saved a config file
+    pub config_saved: Option<String>,
+}
+
+/// Something PDM saw happen that a hook can react to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+    NodeDown,
+    NodeUp,
+    SyncComplete,
+    NewBlock,
+    ConfigSaved,
+}
+
+impl HookEvent {
+    /// Name of the event, as the hooks table and `PDM_EVENT` spell it.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            HookEvent::NodeDown => "node_down",
+            HookEvent::NodeUp => "node_up",
+            HookEvent::SyncComplete => "sync_complete",
+            HookEvent::NewBlock => "new_block",
+            HookEvent::ConfigSaved => "config_saved",
+        }
+    }
+}
+
+impl Hooks {
+    /// The command set for `event`, if any.
+    #[must_use]
+    pub fn command(&self, event: HookEvent) -> Option<&str> {
+        let command = match event {
+            HookEvent::NodeDown => &self.node_down,
+            HookEvent::NodeUp => &self.node_up,
+            HookEvent::SyncComplete => &self.sync_complete,
+            HookEvent::NewBlock => &self.new_block,
+            HookEvent::ConfigSaved => &self.config_saved,
+        };
+        command.as_deref().filter(|c| !c.trim().is_empty())
+    }
+
+    /// Starts the command for `event` without waiting for it; a thread
+    /// reaps it when it exits.
+    ///
+    /// # Errors
+    /// Returns why the command could not be started.
+    pub fn fire(&self, event: HookEvent, detail: &str) -> Result<(), String> {
+        let Some(command) = self.command(event) else {
+            return Ok(());
+        };
+        let mut child = Command::new("sh")
+            .args(["-c", command])
+            .env("PDM_EVENT", event.name())
+            .env("PDM_DETAIL", detail)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("{} hook did not start: {e}", event.name()))?;
+        std::thread::spawn(move || child.wait());
+        Ok(())
+    }
+}
+
+/// What the last `getblockchaininfo` poll said about bitcoind, to turn
+/// the next one into events.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NodeWatch {
+    reachable: Option<bool>,
+    syncing: Option<bool>,
+}
+
+impl NodeWatch {
+    /// Events between the previous poll and this one, whose
+    /// `getblockchaininfo` is `None` when bitcoind did not answer. The
+    /// first poll only sets the baseline.
+    pub fn observe(&mut self, chain_info: Option<&Value>) -> Vec<HookEvent> {
+        let mut events = Vec::new();
+        let reachable = chain_info.is_some();
+        match (self.reachable, reachable) {
+            (Some(true), false) => events.push(HookEvent::NodeDown),
+            (Some(false), true) => events.push(HookEvent::NodeUp),
+            _ => {}
+        }
+        self.reachable = Some(reachable);
+
+        let syncing = chain_info
+            .and_then(|info| info.get("initialblockdownload"))
+            .and_then(Value::as_bool);
+        if let Some(syncing) = syncing {
+            if self.syncing == Some(true) && !syncing {
+                events.push(HookEvent::SyncComplete);
+            }
+            self.syncing = Some(syncing);
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn node_watch_reports_transitions_only() {
+        let mut watch = NodeWatch::default();
+        let syncing = serde_json::json!({"initialblockdownload": true});
+        let synced = serde_json::json!({"initialblockdownload": false});
+        assert!(watch.observe(Some(&syncing)).is_empty());
+        assert!(watch.observe(Some(&syncing)).is_empty());
+        assert_eq!(watch.observe(None), [HookEvent::NodeDown]);
+        assert!(watch.observe(None).is_empty());
+        assert_eq!(
+            watch.observe(Some(&synced)),
+            [HookEvent::NodeUp, HookEvent::SyncComplete]
+        );
+        assert!(watch.observe(Some(&synced)).is_empty());
+    }
+
+    #[test]
+    fn fire_runs_the_command_with_the_event() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("out");
+        let hooks = Hooks {
+            new_block: Some(format!(
+                "echo \"$PDM_EVENT $PDM_DETAIL\" > {}",
+                out.display()
+            )),
+            node_down: Some("  ".to_string()),
+            ..Hooks::default()
+        };
+        assert_eq!(hooks.command(HookEvent::NodeDown), None);
+        hooks.fire(HookEvent::ConfigSaved, "unset").unwrap();
+        hooks.fire(HookEvent::NewBlock, "00ab").unwrap();
+
+        let start = Instant::now();
+        while !std::fs::read_to_string(&out).is_ok_and(|s| s.ends_with('\n')) {
+            assert!(start.elapsed() < Duration::from_secs(5), "hook did not run");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), "new_block 00ab\n");
+    }
+}
@@ -0,0 +1,138 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_config::{ConfigEntry, enabled_value};
+use serde_json::{Value, json};
+
+/// Options a running bitcoind can be switched to without a restart.
+/// Ban settings such as `bantime` have no RPC setter; `setban` takes its
+/// own duration per ban.
+pub const HOT_OPTIONS: &[&str] = &["networkactive", "debug", "debugexclude", "addnode"];
+
+/// An RPC that makes the running node match the config.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeCall {
+    pub method: &'static str,
+    pub params: Vec<Value>,
+}
+
+impl RuntimeCall {
+    fn new(method: &'static str, params: Vec<Value>) -> Self {
+        Self { method, params }
+    }
+
+    /// The call as `bitcoin-cli` would take it.
+    #[must_use]
+    pub fn describe(&self) -> String {
+        let mut out = self.method.to_string();
+        for param in &self.params {
+            out.push(' ');
+            match param {
+                Value::String(s) => out.push_str(s),
+                other => out.push_str(&other.to_string()),
+            }
+        }
+        out
+    }
+}
+
+/// Every category listed in the enabled entries for `key`.
+fn categories(entries: &[ConfigEntry], key: &str) -> Vec<String> {
+    entries
+        .iter()
+        .filter(|e| e.key == key && e.enabled)
+        .flat_map(|e| e.value.split(','))
+        .map(str::trim)
+        .filter(|c| !c.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// `logging` calls that leave the node logging exactly what `debug` and
+/// `debugexclude` ask for: every category off first, then the listed ones.
+fn logging_calls(entries: &[ConfigEntry]) -> Vec<RuntimeCall> {
+    let mut include = Vec::new();
+    for category in categories(entries, "debug") {
+        match category.as_str() {
+            "1" | "all" => include = vec!["all".to_string()],
+            "0" | "none" => include.clear(),
+            _ if include.first().is_some_and(|c| c == "all") => {}
+            _ => include.push(category),
+        }
+    }
+    let exclude = categories(entries, "debugexclude");
+    let mut calls = vec![RuntimeCall::new("logging", vec![json!([]), json!(["all"])])];
+    if !include.is_empty() || !exclude.is_empty() {
+        calls.push(RuntimeCall::new(
+            "logging",
+            vec![json!(include), json!(exclude)],
+        ));
+    }
+    calls
+}
+
+/// The calls that apply `key` as set in `entries` to a running node;
+/// `None` when bitcoind only reads it at startup.
+#[must_use]
+pub fn runtime_calls(entries: &[ConfigEntry], key: &str) -> Option<Vec<RuntimeCall>> {
+    match key {
+        "networkactive" => {
+            let active = !matches!(enabled_value(entries, key), Some("0" | "false"));
+            Some(vec![RuntimeCall::new(
+                "setnetworkactive",
+                vec![json!(active)],
+            )])
+        }
+        "debug" | "debugexclude" => Some(logging_calls(entries)),
+        // Nodes added earlier are kept; removing one needs `addnode <node> remove`
+        "addnode" => Some(
+            entries
+                .iter()
+                .filter(|e| e.key == key && e.enabled && !e.value.is_empty())
+                .map(|e| RuntimeCall::new("addnode", vec![json!(e.value), json!("add")]))
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitcoin_config::{NodeFlavor, parse_config_str};
+
+    fn described(conf: &str, key: &str) -> Option<Vec<String>> {
+        let entries = parse_config_str(conf, NodeFlavor::Core).unwrap();
+        runtime_calls(&entries, key).map(|calls| calls.iter().map(RuntimeCall::describe).collect())
+    }
+
+    #[test]
+    fn options_map_to_their_rpc() {
+        assert_eq!(
+            described("networkactive=0\n", "networkactive").unwrap(),
+            ["setnetworkactive false"]
+        );
+        assert_eq!(
+            described("", "networkactive").unwrap(),
+            ["setnetworkactive true"]
+        );
+        assert_eq!(
+            described("debug=net,mempool\ndebugexclude=libevent\n", "debug").unwrap(),
+            [
+                "logging [] [\"all\"]",
+                "logging [\"net\",\"mempool\"] [\"libevent\"]"
+            ]
+        );
+        assert_eq!(
+            described("debug=1\n", "debugexclude").unwrap()[1],
+            "logging [\"all\"] []"
+        );
+        assert_eq!(described("", "debug").unwrap(), ["logging [] [\"all\"]"]);
+        assert_eq!(
+            described("addnode=b.example:8333\n", "addnode").unwrap(),
+            ["addnode b.example:8333 add"]
+        );
+        assert_eq!(described("dbcache=100\n", "dbcache"), None);
+    }
+}
@@ -0,0 +1,335 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_config::{ConfigCategory, ConfigEntry, ConfigSchema, ConfigType, enabled_value};
+use anyhow::Result;
+use std::path::Path;
+
+/// Returns the schema for the i2pd.conf options relevant to bitcoind.
+///
+/// Options inside a `[section]` are keyed as `section.option`.
+#[must_use]
+#[allow(clippy::too_many_lines)]
+pub fn get_i2pd_schema() -> Vec<ConfigSchema> {
+    vec![
+        // General
+        ConfigSchema::new(
+            "log",
+            "file",
+            ConfigType::String,
+            ConfigCategory::I2pdGeneral,
+            "Log destination (stdout, file, syslog)",
+        ),
+        ConfigSchema::new(
+            "loglevel",
+            "warn",
+            ConfigType::String,
+            ConfigCategory::I2pdGeneral,
+            "Log verbosity (debug, info, warn, error, none)",
+        ),
+        ConfigSchema::new(
+            "datadir",
+            "",
+            ConfigType::Path,
+            ConfigCategory::I2pdGeneral,
+            "i2pd data directory",
+        ),
+        ConfigSchema::new(
+            "ipv4",
+            "true",
+            ConfigType::Bool,
+            ConfigCategory::I2pdGeneral,
+            "Use IPv4 for I2P transports",
+        ),
+        ConfigSchema::new(
+            "ipv6",
+            "false",
+            ConfigType::Bool,
+            ConfigCategory::I2pdGeneral,
+            "Use IPv6 for I2P transports",
+        ),
+        ConfigSchema::new(
+            "bandwidth",
+            "L",
+            ConfigType::String,
+            ConfigCategory::I2pdGeneral,
+            "Bandwidth class (L, O, P, X) or KB/s",
+        ),
+        ConfigSchema::new(
+            "notransit",
+            "false",
+            ConfigType::Bool,
+            ConfigCategory::I2pdGeneral,
+            "Do not route traffic for other I2P users",
+        ),
+        // SAM bridge
+        ConfigSchema::new(
+            "sam.enabled",
+            "true",
+            ConfigType::Bool,
+            ConfigCategory::I2pdSam,
+            "Enable the SAM bridge bitcoind connects to",
+        ),
+        ConfigSchema::new(
+            "sam.address",
+            "127.0.0.1",
+            ConfigType::Address,
+            ConfigCategory::I2pdSam,
+            "Address the SAM bridge listens on",
+        ),
+        ConfigSchema::new(
+            "sam.port",
+            "7656",
+            ConfigType::Int,
+            ConfigCategory::I2pdSam,
+            "Port the SAM bridge listens on",
+        ),
+        ConfigSchema::new(
+            "sam.singlethread",
+            "true",
+            ConfigType::Bool,
+            ConfigCategory::I2pdSam,
+            "Run SAM sessions in a single thread",
+        ),
+        // Other services
+        ConfigSchema::new(
+            "http.enabled",
+            "true",
+            ConfigType::Bool,
+            ConfigCategory::I2pdServices,
+            "Enable the web console",
+        ),
+        ConfigSchema::new(
+            "http.address",
+            "127.0.0.1",
+            ConfigType::Address,
+            ConfigCategory::I2pdServices,
+            "Web console address",
+        ),
+        ConfigSchema::new(
+            "http.port",
+            "7070",
+            ConfigType::Int,
+            ConfigCategory::I2pdServices,
+            "Web console port",
+        ),
+        ConfigSchema::new(
+            "httpproxy.enabled",
+            "true",
+            ConfigType::Bool,
+            ConfigCategory::I2pdServices,
+            "Enable the HTTP proxy",
+        ),
+        ConfigSchema::new(
+            "socksproxy.enabled",
+            "true",
+            ConfigType::Bool,
+            ConfigCategory::I2pdServices,
+            "Enable the SOCKS proxy",
+        ),
+    ]
+}
+
+/// Parses i2pd.conf content into schema-backed entries.
+#[must_use]
+pub fn parse_i2pd_str(content: &str) -> Vec<ConfigEntry> {
+    let mut entries: Vec<ConfigEntry> = get_i2pd_schema()
+        .into_iter()
+        .map(|schema| ConfigEntry {
+            key: schema.key.clone(),
+            value: schema.default.clone(),
+            enabled: false,
+            section: schema.key.split_once('.').map(|(s, _)| s.to_string()),
+            negated: false,
+            schema: Some(schema.into()),
+        })
+        .collect();
+
+    let mut section: Option<String> = None;
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = Some(name.trim().to_lowercase());
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let (key, value) = (key.trim(), value.trim());
+        let qualified = match &section {
+            Some(s) => format!("{s}.{key}"),
+            None => key.to_string(),
+        };
+
+        if let Some(entry) = entries.iter_mut().find(|e| e.key == qualified) {
+            entry.value = value.to_string();
+            entry.enabled = true;
+        } else {
+            entries.push(ConfigEntry {
+                key: qualified,
+                value: value.to_string(),
+                schema: None,
+                enabled: true,
+                section: section.clone(),
+                negated: false,
+            });
+        }
+    }
+
+    entries
+}
+
+/// Parse an i2pd.conf file
+///
+/// # Errors
+/// Returns an error if the file exists but cannot be read.
+/// A missing file yields schema defaults.
+pub fn parse_i2pd_config(path: &Path) -> Result<Vec<ConfigEntry>> {
+    let content = if path.exists() {
+        std::fs::read_to_string(path)?
+    } else {
+        String::new()
+    };
+    Ok(parse_i2pd_str(&content))
+}
+
+/// Writes enabled entries back in i2pd.conf format, with section options
+/// under their `[section]` header and without the `section.` prefix.
+///
+/// # Errors
+/// Returns an error if the file cannot be created or written.
+pub fn save_i2pd_config(path: &Path, entries: &[ConfigEntry]) -> Result<()> {
+    use std::collections::BTreeMap;
+    use std::io::Write;
+
+    let mut file = std::fs::File::create(path)?;
+    let mut sectioned: BTreeMap<&str, Vec<&ConfigEntry>> = BTreeMap::new();
+
+    for entry in entries.iter().filter(|e| e.enabled) {
+        match &entry.section {
+            None => writeln!(file, "{} = {}", entry.key, entry.value)?,
+            Some(s) => sectioned.entry(s).or_default().push(entry),
+        }
+    }
+
+    for (section, section_entries) in &sectioned {
+        writeln!(file, "\n[{section}]")?;
+        for entry in section_entries {
+            let key = entry
+                .key
+                .strip_prefix(*section)
+                .and_then(|k| k.strip_prefix('.'))
+                .unwrap_or(&entry.key);
+            writeln!(file, "{key} = {}", entry.value)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that the SAM bridge i2pd exposes is the one bitcoind's `i2psam`
+/// points at. Nothing is reported when bitcoind does not use I2P.
+#[must_use]
+pub fn cross_check(i2pd: &[ConfigEntry], bitcoin: &[ConfigEntry]) -> Vec<String> {
+    let mut issues = Vec::new();
+    let Some(i2psam) = enabled_value(bitcoin, "i2psam") else {
+        return issues;
+    };
+    let value = |key: &str| {
+        i2pd.iter()
+            .find(|e| e.key == key)
+            .map(|e| e.value.as_str())
+            .unwrap_or_default()
+    };
+
+    if matches!(value("sam.enabled"), "false" | "0") {
+        issues.push(format!(
+            "bitcoin.conf i2psam={i2psam} but the SAM bridge is disabled"
+        ));
+    }
+
+    let exposed = format!("{}:{}", value("sam.address"), value("sam.port"));
+    let (host, port) = i2psam.rsplit_once(':').unwrap_or((i2psam, "7656"));
+    let same_host = host == value("sam.address")
+        || (matches!(host, "127.0.0.1" | "localhost") && value("sam.address") == "0.0.0.0");
+    if !same_host || port != value("sam.port") {
+        issues.push(format!(
+            "bitcoin.conf i2psam={i2psam} does not match the SAM bridge at {exposed}"
+        ));
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "
+log = file
+loglevel = warn
+
+[sam]
+enabled = true
+address = 127.0.0.1
+port = 7656
+
+[http]
+port = 7071
+";
+
+    fn bitcoin(content: &str) -> (tempfile::TempDir, Vec<ConfigEntry>) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bitcoin.conf");
+        std::fs::write(&path, content).unwrap();
+        let entries = crate::bitcoin_config::parse_config(&path).unwrap();
+        (dir, entries)
+    }
+
+    #[test]
+    fn parse_qualifies_section_keys() {
+        let entries = parse_i2pd_str(SAMPLE);
+        let port = entries.iter().find(|e| e.key == "sam.port").unwrap();
+        assert!(port.enabled);
+        assert_eq!(port.value, "7656");
+        assert_eq!(port.section.as_deref(), Some("sam"));
+        let http = entries.iter().find(|e| e.key == "http.port").unwrap();
+        assert_eq!(http.value, "7071");
+    }
+
+    #[test]
+    fn save_writes_unprefixed_keys_under_sections() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("i2pd.conf");
+        save_i2pd_config(&path, &parse_i2pd_str(SAMPLE)).unwrap();
+        let saved = std::fs::read_to_string(&path).unwrap();
+        assert!(saved.starts_with("log = file\nloglevel = warn\n"));
+        assert!(saved.contains("[sam]\nenabled = true\naddress = 127.0.0.1\nport = 7656\n"));
+        assert!(!saved.contains("sam.port"));
+    }
+
+    #[test]
+    fn cross_check_is_silent_without_i2psam() {
+        let (_dir, btc) = bitcoin("server=1\n");
+        assert!(cross_check(&parse_i2pd_str(SAMPLE), &btc).is_empty());
+    }
+
+    #[test]
+    fn cross_check_accepts_matching_sam_address() {
+        let (_dir, btc) = bitcoin("i2psam=127.0.0.1:7656\n");
+        assert!(cross_check(&parse_i2pd_str(SAMPLE), &btc).is_empty());
+    }
+
+    #[test]
+    fn cross_check_reports_port_mismatch_and_disabled_bridge() {
+        let (_dir, btc) = bitcoin("i2psam=127.0.0.1:7657\n");
+        let i2pd = parse_i2pd_str("[sam]\nenabled = false\n");
+        let issues = cross_check(&i2pd, &btc);
+        assert!(issues.iter().any(|i| i.contains("disabled")));
+        assert!(issues.iter().any(|i| i.contains("does not match")));
+    }
+}
@@ -0,0 +1,286 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_config::{ConfigEntry, enabled_value};
+use crate::diagnostics::Diagnostic;
+use serde_json::Value;
+use std::time::{Duration, Instant};
+
+/// An optional index bitcoind can build.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Index {
+    /// Option that enables it
+    pub key: &'static str,
+    /// Name `getindexinfo` reports it under
+    pub name: &'static str,
+    /// Rough size on mainnet, in GB
+    pub mainnet_gb: u32,
+    /// Rough time to build from scratch on mainnet
+    pub mainnet_build: &'static str,
+}
+
+/// Indexes bitcoind can maintain, with what they cost on mainnet as of 2024.
+pub const INDEXES: &[Index] = &[
+    Index {
+        key: "txindex",
+        name: "txindex",
+        mainnet_gb: 60,
+        mainnet_build: "several hours",
+    },
+    Index {
+        key: "coinstatsindex",
+        name: "coinstatsindex",
+        mainnet_gb: 2,
+        mainnet_build: "a few hours",
+    },
+    Index {
+        key: "blockfilterindex",
+        name: "basic block filter index",
+        mainnet_gb: 13,
+        mainnet_build: "two to six hours",
+    },
+];
+
+/// The index `key` enables, if it is one.
+#[must_use]
+pub fn index_for(key: &str) -> Option<&'static Index> {
+    INDEXES.iter().find(|i| i.key == key)
+}
+
+/// Whether `value` turns an index option on; `blockfilterindex` also
+/// takes a filter type such as `basic`.
+#[must_use]
+pub fn enables(value: &str) -> bool {
+    !matches!(value.trim(), "" | "0")
+}
+
+/// What building `index` costs on `chain`.
+#[must_use]
+pub fn estimate(index: &Index, chain: &str) -> String {
+    if chain == "main" {
+        format!(
+            "Needs about {} GB more disk and {} to build",
+            index.mainnet_gb, index.mainnet_build
+        )
+    } else {
+        format!(
+            "Needs far less than the {} GB it takes on mainnet",
+            index.mainnet_gb
+        )
+    }
+}
+
+/// Indexes that clash with `prune`: bitcoind refuses to start with
+/// `txindex`, and builds the block filter index only from blocks it still
+/// has on disk.
+#[must_use]
+pub fn prune_conflicts(entries: &[ConfigEntry]) -> Vec<Diagnostic> {
+    let pruned = enabled_value(entries, "prune")
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .is_some_and(|mib| mib > 0);
+    if !pruned {
+        return Vec::new();
+    }
+    let on = |key| enabled_value(entries, key).is_some_and(enables);
+    let mut out = Vec::new();
+    if on("txindex") {
+        out.push(Diagnostic::error(
+            "txindex",
+            "cannot be used with prune; bitcoind refuses to start",
+        ));
+    }
+    if on("blockfilterindex") {
+        out.push(Diagnostic::warning(
+            "blockfilterindex",
+            "is built only from blocks still on disk; a node that has already pruned \
+             needs -reindex, which downloads the chain again",
+        ));
+    }
+    out
+}
+
+/// How often the Indexes tab asks bitcoind for `getindexinfo`.
+pub const INDEX_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// One entry of `getindexinfo`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexStatus {
+    pub name: String,
+    pub synced: bool,
+    pub best_block_height: u64,
+}
+
+/// Index states of the running bitcoind, refreshed while the Indexes tab
+/// is shown.
+#[derive(Debug, Clone, Default)]
+pub struct IndexMonitor {
+    pub indexes: Vec<IndexStatus>,
+    /// Chain height, from `getblockcount`
+    pub height: u64,
+    /// Why the last poll failed
+    pub error: Option<String>,
+    pub last_poll: Option<Instant>,
+}
+
+impl IndexMonitor {
+    /// Whether the next poll is due at `now`.
+    #[must_use]
+    pub fn due(&self, now: Instant) -> bool {
+        self.last_poll
+            .is_none_or(|last| now.duration_since(last) >= INDEX_POLL_INTERVAL)
+    }
+
+    /// Takes a `getindexinfo` result and the chain height.
+    pub fn update(&mut self, info: &Value, height: u64) {
+        self.indexes = parse_statuses(info);
+        self.height = height;
+        self.error = None;
+    }
+
+    /// What bitcoind reports for `index`, if it runs it.
+    #[must_use]
+    pub fn status(&self, index: &Index) -> Option<&IndexStatus> {
+        self.indexes.iter().find(|s| s.name == index.name)
+    }
+
+    /// Indexes `entries` enable that the node does not run, most likely
+    /// because it has not been restarted since.
+    #[must_use]
+    pub fn not_running(&self, entries: &[ConfigEntry]) -> Vec<&'static Index> {
+        INDEXES
+            .iter()
+            .filter(|i| enabled_value(entries, i.key).is_some_and(enables))
+            .filter(|i| self.status(i).is_none())
+            .collect()
+    }
+}
+
+/// The entries of a `getindexinfo` result.
+fn parse_statuses(info: &Value) -> Vec<IndexStatus> {
+    info.as_object()
+        .map(|indexes| {
+            indexes
+                .iter()
+                .map(|(name, state)| IndexStatus {
+                    name: name.clone(),
+                    synced: state.get("synced").and_then(Value::as_bool) == Some(true),
+                    best_block_height: state
+                        .get("best_block_height")
+                        .and_then(Value::as_u64)
+                        .unwrap_or(0),
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+impl IndexStatus {
+    /// `synced at block N`, or how much is built against the chain `height`.
+    #[must_use]
+    pub fn progress(&self, height: u64) -> String {
+        let best = self.best_block_height;
+        if self.synced {
+            format!("synced at block {best}")
+        } else {
+            format!(
+                "{:.1}% built (block {best} of {height})",
+                self.percent(height)
+            )
+        }
+    }
+
+    /// Share of the chain up to `height` the index covers.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)] // A percentage with one decimal
+    pub fn percent(&self, height: u64) -> f64 {
+        if self.synced {
+            100.0
+        } else if height == 0 {
+            0.0
+        } else {
+            self.best_block_height as f64 * 100.0 / height as f64
+        }
+    }
+}
+
+/// Build progress of each index in a `getindexinfo` result, against the
+/// chain `height`.
+#[must_use]
+pub fn index_progress(info: &Value, height: u64) -> Vec<String> {
+    parse_statuses(info)
+        .iter()
+        .map(|s| format!("{}: {}", s.name, s.progress(height)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(content: &str) -> Vec<ConfigEntry> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bitcoin.conf");
+        std::fs::write(&path, content).unwrap();
+        crate::bitcoin_config::parse_config(&path).unwrap()
+    }
+
+    #[test]
+    fn prune_rules_out_txindex_and_warns_about_filters() {
+        assert!(prune_conflicts(&entries("txindex=1\nblockfilterindex=1\n")).is_empty());
+        assert!(prune_conflicts(&entries("prune=0\ntxindex=1\n")).is_empty());
+
+        let found = prune_conflicts(&entries("prune=550\ntxindex=1\nblockfilterindex=basic\n"));
+        let keys: Vec<&str> = found.iter().map(|d| d.key.as_str()).collect();
+        assert_eq!(keys, ["txindex", "blockfilterindex"]);
+        assert!(prune_conflicts(&entries("prune=550\nblockfilterindex=0\n")).is_empty());
+    }
+
+    #[test]
+    fn progress_reads_getindexinfo() {
+        let info = serde_json::json!({
+            "txindex": {"synced": true, "best_block_height": 850_000},
+            "basic block filter index": {"synced": false, "best_block_height": 425_000},
+        });
+        assert_eq!(
+            index_progress(&info, 850_000),
+            [
+                "basic block filter index: 50.0% built (block 425000 of 850000)",
+                "txindex: synced at block 850000",
+            ]
+        );
+    }
+
+    #[test]
+    fn monitor_finds_enabled_indexes_the_node_does_not_run() {
+        let mut monitor = IndexMonitor::default();
+        monitor.update(
+            &serde_json::json!({"txindex": {"synced": false, "best_block_height": 10}}),
+            20,
+        );
+        assert_eq!(monitor.height, 20);
+        let txindex = index_for("txindex").unwrap();
+        assert_eq!(monitor.status(txindex).unwrap().best_block_height, 10);
+
+        let entries = entries(
+            "txindex=1
+blockfilterindex=basic
+coinstatsindex=0
+",
+        );
+        let keys: Vec<&str> = monitor
+            .not_running(&entries)
+            .iter()
+            .map(|i| i.key)
+            .collect();
+        assert_eq!(keys, ["blockfilterindex"]);
+    }
+
+    #[test]
+    fn estimates_depend_on_the_chain() {
+        let filters = index_for("blockfilterindex").unwrap();
+        assert!(estimate(filters, "main").contains("13 GB"));
+        assert!(estimate(filters, "signet").contains("far less"));
+        assert!(index_for("prune").is_none());
+    }
+}
@@ -0,0 +1,85 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::app::{BLOCKS_TAB, INDEXES_TAB, MEMPOOL_TAB, PEERS_TAB, REORGS_TAB, TRAFFIC_TAB};
+use std::time::{Duration, Instant};
+
+/// Command-line flag starting PDM as a kiosk.
+pub const KIOSK_FLAG: &str = "--kiosk";
+
+/// Bitcoin Status tabs the kiosk cycles through: those polling bitcoind
+/// on their own. Wallets stay off a screen anyone can read.
+pub const KIOSK_TABS: &[usize] = &[
+    PEERS_TAB,
+    MEMPOOL_TAB,
+    BLOCKS_TAB,
+    TRAFFIC_TAB,
+    INDEXES_TAB,
+    REORGS_TAB,
+];
+
+/// How long each tab stays up before the next.
+pub const ROTATE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A watch-only dashboard for a monitor next to the node: Bitcoin Status
+/// alone, turning its tabs over by itself. Nothing can be edited or
+/// written and only Ctrl-C quits.
+#[derive(Debug, Clone, Default)]
+pub struct Kiosk {
+    /// Position in `KIOSK_TABS` of the tab shown
+    pub position: usize,
+    /// When the tab shown came up; `None` before the first
+    pub last_switch: Option<Instant>,
+}
+
+impl Kiosk {
+    /// Bitcoin Status tab shown.
+    #[must_use]
+    pub fn tab(&self) -> usize {
+        KIOSK_TABS[self.position % KIOSK_TABS.len()]
+    }
+
+    /// Whether to turn to the next tab at `now`.
+    #[must_use]
+    pub fn due(&self, now: Instant) -> bool {
+        self.last_switch
+            .is_some_and(|last| now.duration_since(last) >= ROTATE_INTERVAL)
+    }
+
+    /// Turns to the next tab, or the previous one going `back`, and keeps
+    /// it up for a full interval from `now`.
+    pub fn turn(&mut self, back: bool, now: Instant) {
+        let count = KIOSK_TABS.len();
+        self.position = if back {
+            (self.position + count - 1) % count
+        } else {
+            (self.position + 1) % count
+        };
+        self.last_switch = Some(now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kiosk_turns_its_tabs_over_in_a_loop() {
+        let start = Instant::now();
+        let mut kiosk = Kiosk {
+            last_switch: Some(start),
+            ..Kiosk::default()
+        };
+        assert_eq!(kiosk.tab(), PEERS_TAB);
+        assert!(!kiosk.due(start + ROTATE_INTERVAL / 2));
+        assert!(kiosk.due(start + ROTATE_INTERVAL));
+
+        kiosk.turn(true, start + ROTATE_INTERVAL);
+        assert_eq!(kiosk.tab(), REORGS_TAB);
+        assert!(!kiosk.due(start + ROTATE_INTERVAL * 3 / 2));
+        kiosk.turn(false, start);
+        kiosk.turn(false, start);
+        assert_eq!(kiosk.tab(), MEMPOOL_TAB);
+    }
+}
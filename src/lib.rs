@@ -2,9 +2,78 @@
 //
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
+pub mod accessibility;
 pub mod app;
+pub mod appliance;
+pub mod asmap;
 pub mod bitcoin_config;
+pub mod bitcoind;
+pub mod block_filters;
+pub mod blocks;
+pub mod cln_config;
+pub mod clock_drift;
 pub mod components;
+pub mod conf_lock;
+pub mod config_management;
+pub mod control;
+pub mod cookie;
+pub mod datadir_move;
+pub mod debug_categories;
+pub mod descriptors;
+pub mod diag_bundle;
+pub mod diagnostics;
+pub mod docker_compose;
+pub mod download;
+pub mod edit_macro;
+pub mod electrs_config;
+pub mod fd_limit;
+pub mod git;
+pub mod history;
+pub mod hooks;
+pub mod hot_apply;
+pub mod i2pd_config;
+pub mod indexes;
+pub mod kiosk;
+pub mod lightning;
+pub mod lnd_config;
+pub mod memory_budget;
+pub mod mempool;
+pub mod mempool_accept;
+pub mod metrics;
+pub mod mining;
+pub mod nat;
+pub mod notify_scripts;
+pub mod onion_key;
+pub mod option_help;
 pub mod p2poolv2_config;
+pub mod peers;
+pub mod permissions;
+pub mod plugins;
+pub mod preflight;
+pub mod probe;
+pub mod process_args;
+pub mod prune_target;
+pub mod relay_policy;
+pub mod reorg_log;
+pub mod report;
+pub mod reverse_proxy;
+pub mod rpc;
+pub mod rpcwhitelist;
+pub mod schema_extensions;
+pub mod screenshot;
+pub mod secrets;
+pub mod services;
 pub mod settings;
+pub mod slider;
+pub mod support_export;
+pub mod template;
+pub mod torrc;
+pub mod traffic;
 pub mod ui;
+pub mod units;
+pub mod update_check;
+pub mod upload_budget;
+pub mod utxo;
+pub mod warnings;
+pub mod workspace;
+pub mod zmq;
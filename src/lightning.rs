@@ -0,0 +1,215 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_config::{ConfigEntry, effective_rpc_port, save_config};
+use crate::cln_config::{known_option_count, parse_cln_config, save_cln_config};
+use crate::lnd_config::parse_lnd_config;
+use anyhow::Result;
+use std::path::Path;
+
+/// Lightning implementation whose config file is loaded on the LN Config screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LnFlavor {
+    #[default]
+    Lnd,
+    CoreLightning,
+}
+
+impl LnFlavor {
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            LnFlavor::Lnd => "LND",
+            LnFlavor::CoreLightning => "Core Lightning",
+        }
+    }
+
+    /// Parse `path` using this flavor's config format.
+    ///
+    /// # Errors
+    /// Returns an error if the file exists but cannot be read.
+    pub fn parse(self, path: &Path) -> Result<Vec<ConfigEntry>> {
+        match self {
+            LnFlavor::Lnd => parse_lnd_config(path),
+            LnFlavor::CoreLightning => parse_cln_config(path),
+        }
+    }
+
+    /// Write `entries` to `path` using this flavor's config format.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be created or written.
+    pub fn save(self, path: &Path, entries: &[ConfigEntry]) -> Result<()> {
+        match self {
+            LnFlavor::Lnd => save_config(path, entries),
+            LnFlavor::CoreLightning => save_cln_config(path, entries),
+        }
+    }
+}
+
+/// Works out whether `path` is an lnd.conf or a Core Lightning config and
+/// parses it accordingly. Returns `Ok(None)` when the file sets options but
+/// none of them are known to either implementation.
+///
+/// # Errors
+/// Returns an error if the file exists but cannot be read.
+pub fn detect_and_parse(path: &Path) -> Result<Option<(LnFlavor, Vec<ConfigEntry>)>> {
+    let content = if path.exists() {
+        std::fs::read_to_string(path)?
+    } else {
+        String::new()
+    };
+    let lnd = LnFlavor::Lnd.parse(path)?;
+    let cln = LnFlavor::CoreLightning.parse(path)?;
+
+    let lnd_known = lnd
+        .iter()
+        .filter(|e| e.enabled && e.schema.is_some())
+        .count();
+    let cln_known = known_option_count(&cln);
+    let anything_set = lnd.iter().any(|e| e.enabled);
+
+    if anything_set && lnd_known == 0 && cln_known == 0 {
+        return Ok(None);
+    }
+
+    // lnd.conf uses [Section] headers, Core Lightning never does
+    let has_sections = content.lines().any(|l| l.trim_start().starts_with('['));
+    let by_name = path.file_name().and_then(|n| n.to_str()) == Some("config");
+
+    let flavor = if lnd_known != cln_known {
+        if lnd_known > cln_known {
+            LnFlavor::Lnd
+        } else {
+            LnFlavor::CoreLightning
+        }
+    } else if !has_sections && by_name {
+        LnFlavor::CoreLightning
+    } else {
+        LnFlavor::Lnd
+    };
+
+    let entries = match flavor {
+        LnFlavor::Lnd => lnd,
+        LnFlavor::CoreLightning => cln,
+    };
+    Ok(Some((flavor, entries)))
+}
+
+/// Returns the Lightning options (key, value) that point `flavor` at the
+/// bitcoind described by `bitcoin` (the loaded bitcoin.conf entries).
+#[must_use]
+pub fn bitcoind_rpc_settings(flavor: LnFlavor, bitcoin: &[ConfigEntry]) -> Vec<(String, String)> {
+    let enabled = |key: &str| {
+        bitcoin
+            .iter()
+            .find(|e| e.key == key && e.enabled)
+            .map(|e| e.value.clone())
+    };
+
+    let host = enabled("rpcconnect").unwrap_or_else(|| "127.0.0.1".to_string());
+    // The port bitcoind actually listens on, which follows the chain
+    let port = effective_rpc_port(bitcoin).to_string();
+    let user = enabled("rpcuser");
+    let password = enabled("rpcpassword");
+
+    let mut settings = Vec::new();
+    let mut push = |key: &str, value: Option<String>| {
+        if let Some(v) = value {
+            settings.push((key.to_string(), v));
+        }
+    };
+    match flavor {
+        LnFlavor::Lnd => {
+            push("bitcoind.rpchost", Some(format!("{host}:{port}")));
+            push("bitcoind.rpcuser", user);
+            push("bitcoind.rpcpass", password);
+        }
+        LnFlavor::CoreLightning => {
+            push("bitcoin-rpcconnect", Some(host));
+            push("bitcoin-rpcport", Some(port));
+            push("bitcoin-rpcuser", user);
+            push("bitcoin-rpcpassword", password);
+        }
+    }
+    settings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(name: &str, content: &str) -> (tempfile::TempDir, std::path::PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(name);
+        std::fs::write(&path, content).unwrap();
+        (dir, path)
+    }
+
+    #[test]
+    fn detects_lnd_by_keys_and_sections() {
+        let (_dir, path) = write("whatever.conf", "[Application Options]\nalias=a\n");
+        let (flavor, _) = detect_and_parse(&path).unwrap().unwrap();
+        assert_eq!(flavor, LnFlavor::Lnd);
+
+        let (_dir, path) = write("x.conf", "bitcoin.active=1\n");
+        assert_eq!(detect_and_parse(&path).unwrap().unwrap().0, LnFlavor::Lnd);
+    }
+
+    #[test]
+    fn detects_core_lightning_by_keys_and_name() {
+        let (_dir, path) = write("x.conf", "bitcoin-rpcuser=u\nnetwork=regtest\n");
+        assert_eq!(
+            detect_and_parse(&path).unwrap().unwrap().0,
+            LnFlavor::CoreLightning
+        );
+
+        let (_dir, path) = write("config", "alias=a\n");
+        assert_eq!(
+            detect_and_parse(&path).unwrap().unwrap().0,
+            LnFlavor::CoreLightning
+        );
+    }
+
+    #[test]
+    fn rejects_files_without_known_options() {
+        let (_dir, path) = write("x.conf", "foo=bar\n");
+        assert!(detect_and_parse(&path).unwrap().is_none());
+    }
+
+    #[test]
+    fn bitcoind_rpc_settings_maps_per_flavor() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bitcoin.conf");
+        std::fs::write(&path, "rpcuser=alice\nrpcpassword=secret\nrpcport=18443\n").unwrap();
+        let bitcoin = crate::bitcoin_config::parse_config(&path).unwrap();
+
+        let lnd = bitcoind_rpc_settings(LnFlavor::Lnd, &bitcoin);
+        assert!(lnd.contains(&("bitcoind.rpchost".into(), "127.0.0.1:18443".into())));
+        assert!(lnd.contains(&("bitcoind.rpcpass".into(), "secret".into())));
+
+        let cln = bitcoind_rpc_settings(LnFlavor::CoreLightning, &bitcoin);
+        assert!(cln.contains(&("bitcoin-rpcuser".into(), "alice".into())));
+        assert!(cln.contains(&("bitcoin-rpcport".into(), "18443".into())));
+    }
+
+    #[test]
+    fn bitcoind_rpc_settings_use_the_chain_default_port() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bitcoin.conf");
+        std::fs::write(
+            &path,
+            "chain=test
+rpcuser=alice
+",
+        )
+        .unwrap();
+        let bitcoin = crate::bitcoin_config::parse_config(&path).unwrap();
+
+        let lnd = bitcoind_rpc_settings(LnFlavor::Lnd, &bitcoin);
+        assert!(lnd.contains(&("bitcoind.rpchost".into(), "127.0.0.1:18332".into())));
+        let cln = bitcoind_rpc_settings(LnFlavor::CoreLightning, &bitcoin);
+        assert!(cln.contains(&("bitcoin-rpcport".into(), "18332".into())));
+    }
+}
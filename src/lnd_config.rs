@@ -0,0 +1,577 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_config::{ConfigCategory, ConfigEntry, ConfigSchema, ConfigType};
+use anyhow::Result;
+use std::path::Path;
+
+/// Section header used for options that appear before any `[Section]` line.
+pub const DEFAULT_SECTION: &str = "Application Options";
+
+/// Options lnd accepts more than once, each line adding a value.
+const MULTI_VALUE_OPTIONS: &[&str] = &[
+    "listen",
+    "rpclisten",
+    "restlisten",
+    "externalip",
+    "tlsextraip",
+    "tlsextradomain",
+];
+
+/// Returns the lnd.conf section header an option category is written under.
+#[must_use]
+pub fn section_for(category: ConfigCategory) -> &'static str {
+    match category {
+        ConfigCategory::LndBitcoin => "Bitcoin",
+        ConfigCategory::LndBitcoind => "Bitcoind",
+        ConfigCategory::LndWatchtower => "Watchtower",
+        ConfigCategory::LndTor => "Tor",
+        _ => DEFAULT_SECTION,
+    }
+}
+
+/// Returns the schema for the commonly used lnd.conf options
+#[must_use]
+#[allow(clippy::too_many_lines)]
+pub fn get_lnd_schema() -> Vec<ConfigSchema> {
+    vec![
+        // Application Options
+        ConfigSchema::new(
+            "alias",
+            "",
+            ConfigType::String,
+            ConfigCategory::LndApplication,
+            "Node alias shown to the network",
+        ),
+        ConfigSchema::new(
+            "color",
+            "#3399FF",
+            ConfigType::String,
+            ConfigCategory::LndApplication,
+            "Node color in hex",
+        ),
+        ConfigSchema::new(
+            "lnddir",
+            "",
+            ConfigType::Path,
+            ConfigCategory::LndApplication,
+            "Base directory for lnd data",
+        ),
+        ConfigSchema::new(
+            "logdir",
+            "",
+            ConfigType::Path,
+            ConfigCategory::LndApplication,
+            "Directory to log output",
+        ),
+        ConfigSchema::new(
+            "debuglevel",
+            "info",
+            ConfigType::String,
+            ConfigCategory::LndApplication,
+            "Logging level for all subsystems",
+        ),
+        ConfigSchema::new(
+            "listen",
+            "0.0.0.0:9735",
+            ConfigType::Address,
+            ConfigCategory::LndApplication,
+            "Peer-to-peer listen address",
+        ),
+        ConfigSchema::new(
+            "rpclisten",
+            "localhost:10009",
+            ConfigType::Address,
+            ConfigCategory::LndApplication,
+            "gRPC listen address",
+        ),
+        ConfigSchema::new(
+            "restlisten",
+            "localhost:8080",
+            ConfigType::Address,
+            ConfigCategory::LndApplication,
+            "REST listen address",
+        ),
+        ConfigSchema::new(
+            "externalip",
+            "",
+            ConfigType::Address,
+            ConfigCategory::LndApplication,
+            "Publicly reachable address to advertise",
+        ),
+        ConfigSchema::new(
+            "tlsextraip",
+            "",
+            ConfigType::Address,
+            ConfigCategory::LndApplication,
+            "Extra IP added to the TLS certificate",
+        ),
+        ConfigSchema::new(
+            "tlsextradomain",
+            "",
+            ConfigType::String,
+            ConfigCategory::LndApplication,
+            "Extra domain added to the TLS certificate",
+        ),
+        ConfigSchema::new(
+            "maxpendingchannels",
+            "1",
+            ConfigType::Int,
+            ConfigCategory::LndApplication,
+            "Maximum pending channels per peer",
+        ),
+        ConfigSchema::new(
+            "minchansize",
+            "20000",
+            ConfigType::Int,
+            ConfigCategory::LndApplication,
+            "Smallest accepted channel size in sat",
+        ),
+        ConfigSchema::new(
+            "maxchansize",
+            "",
+            ConfigType::Int,
+            ConfigCategory::LndApplication,
+            "Largest accepted channel size in sat",
+        ),
+        ConfigSchema::new(
+            "accept-keysend",
+            "0",
+            ConfigType::Bool,
+            ConfigCategory::LndApplication,
+            "Accept spontaneous keysend payments",
+        ),
+        ConfigSchema::new(
+            "accept-amp",
+            "0",
+            ConfigType::Bool,
+            ConfigCategory::LndApplication,
+            "Accept spontaneous AMP payments",
+        ),
+        ConfigSchema::new(
+            "wallet-unlock-password-file",
+            "",
+            ConfigType::Path,
+            ConfigCategory::LndApplication,
+            "File used to unlock the wallet on startup",
+        ),
+        ConfigSchema::new(
+            "norest",
+            "0",
+            ConfigType::Bool,
+            ConfigCategory::LndApplication,
+            "Disable the REST API",
+        ),
+        // Bitcoin
+        ConfigSchema::new(
+            "bitcoin.active",
+            "1",
+            ConfigType::Bool,
+            ConfigCategory::LndBitcoin,
+            "Use Bitcoin as the chain",
+        ),
+        ConfigSchema::new(
+            "bitcoin.mainnet",
+            "0",
+            ConfigType::Bool,
+            ConfigCategory::LndBitcoin,
+            "Use Bitcoin mainnet",
+        ),
+        ConfigSchema::new(
+            "bitcoin.testnet",
+            "0",
+            ConfigType::Bool,
+            ConfigCategory::LndBitcoin,
+            "Use Bitcoin testnet",
+        ),
+        ConfigSchema::new(
+            "bitcoin.signet",
+            "0",
+            ConfigType::Bool,
+            ConfigCategory::LndBitcoin,
+            "Use Bitcoin signet",
+        ),
+        ConfigSchema::new(
+            "bitcoin.regtest",
+            "0",
+            ConfigType::Bool,
+            ConfigCategory::LndBitcoin,
+            "Use Bitcoin regtest",
+        ),
+        ConfigSchema::new(
+            "bitcoin.node",
+            "btcd",
+            ConfigType::String,
+            ConfigCategory::LndBitcoin,
+            "Chain backend (btcd, bitcoind, neutrino)",
+        ),
+        ConfigSchema::new(
+            "bitcoin.defaultchanconfs",
+            "3",
+            ConfigType::Int,
+            ConfigCategory::LndBitcoin,
+            "Confirmations required for new channels",
+        ),
+        ConfigSchema::new(
+            "bitcoin.basefee",
+            "1000",
+            ConfigType::Int,
+            ConfigCategory::LndBitcoin,
+            "Base routing fee in msat",
+        ),
+        ConfigSchema::new(
+            "bitcoin.feerate",
+            "1",
+            ConfigType::Int,
+            ConfigCategory::LndBitcoin,
+            "Proportional routing fee in ppm",
+        ),
+        ConfigSchema::new(
+            "bitcoin.timelockdelta",
+            "80",
+            ConfigType::Int,
+            ConfigCategory::LndBitcoin,
+            "CLTV delta for forwarded payments",
+        ),
+        // Bitcoind backend
+        ConfigSchema::new(
+            "bitcoind.rpchost",
+            "localhost",
+            ConfigType::Address,
+            ConfigCategory::LndBitcoind,
+            "bitcoind RPC host[:port]",
+        ),
+        ConfigSchema::new(
+            "bitcoind.rpcuser",
+            "",
+            ConfigType::String,
+            ConfigCategory::LndBitcoind,
+            "bitcoind RPC username",
+        ),
+        ConfigSchema::new(
+            "bitcoind.rpcpass",
+            "",
+            ConfigType::String,
+            ConfigCategory::LndBitcoind,
+            "bitcoind RPC password",
+        ),
+        ConfigSchema::new(
+            "bitcoind.rpccookie",
+            "",
+            ConfigType::Path,
+            ConfigCategory::LndBitcoind,
+            "bitcoind RPC cookie file",
+        ),
+        ConfigSchema::new(
+            "bitcoind.zmqpubrawblock",
+            "",
+            ConfigType::Address,
+            ConfigCategory::LndBitcoind,
+            "bitcoind ZMQ raw block endpoint",
+        ),
+        ConfigSchema::new(
+            "bitcoind.zmqpubrawtx",
+            "",
+            ConfigType::Address,
+            ConfigCategory::LndBitcoind,
+            "bitcoind ZMQ raw tx endpoint",
+        ),
+        ConfigSchema::new(
+            "bitcoind.estimatemode",
+            "CONSERVATIVE",
+            ConfigType::String,
+            ConfigCategory::LndBitcoind,
+            "Fee estimate mode (ECONOMICAL, CONSERVATIVE)",
+        ),
+        // Watchtower
+        ConfigSchema::new(
+            "watchtower.active",
+            "0",
+            ConfigType::Bool,
+            ConfigCategory::LndWatchtower,
+            "Run a watchtower server",
+        ),
+        ConfigSchema::new(
+            "watchtower.listen",
+            "0.0.0.0:9911",
+            ConfigType::Address,
+            ConfigCategory::LndWatchtower,
+            "Watchtower listen address",
+        ),
+        ConfigSchema::new(
+            "watchtower.externalip",
+            "",
+            ConfigType::Address,
+            ConfigCategory::LndWatchtower,
+            "Watchtower address to advertise",
+        ),
+        ConfigSchema::new(
+            "watchtower.towerdir",
+            "",
+            ConfigType::Path,
+            ConfigCategory::LndWatchtower,
+            "Watchtower data directory",
+        ),
+        // Tor
+        ConfigSchema::new(
+            "tor.active",
+            "0",
+            ConfigType::Bool,
+            ConfigCategory::LndTor,
+            "Route outbound connections through Tor",
+        ),
+        ConfigSchema::new(
+            "tor.socks",
+            "localhost:9050",
+            ConfigType::Address,
+            ConfigCategory::LndTor,
+            "Tor SOCKS proxy address",
+        ),
+        ConfigSchema::new(
+            "tor.control",
+            "localhost:9051",
+            ConfigType::Address,
+            ConfigCategory::LndTor,
+            "Tor control port address",
+        ),
+        ConfigSchema::new(
+            "tor.password",
+            "",
+            ConfigType::String,
+            ConfigCategory::LndTor,
+            "Tor control port password",
+        ),
+        ConfigSchema::new(
+            "tor.v3",
+            "0",
+            ConfigType::Bool,
+            ConfigCategory::LndTor,
+            "Create a v3 onion service",
+        ),
+        ConfigSchema::new(
+            "tor.streamisolation",
+            "0",
+            ConfigType::Bool,
+            ConfigCategory::LndTor,
+            "Use a fresh circuit per connection",
+        ),
+        ConfigSchema::new(
+            "tor.skip-proxy-for-clearnet-targets",
+            "0",
+            ConfigType::Bool,
+            ConfigCategory::LndTor,
+            "Connect to clearnet peers directly",
+        ),
+    ]
+}
+
+/// Parses lnd.conf content into schema-backed entries.
+///
+/// lnd keys are fully qualified (`bitcoin.active`), so the `[Section]`
+/// headers are only tracked to write unknown keys back where they were found.
+#[must_use]
+pub fn parse_lnd_str(content: &str) -> Vec<ConfigEntry> {
+    let mut entries: Vec<ConfigEntry> = get_lnd_schema()
+        .into_iter()
+        .map(|schema| ConfigEntry {
+            key: schema.key.clone(),
+            value: schema.default.clone(),
+            enabled: false,
+            section: Some(section_for(schema.category).to_string()),
+            negated: false,
+            schema: Some(schema.into()),
+        })
+        .collect();
+
+    let mut current_section = DEFAULT_SECTION.to_string();
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            current_section = name.trim().to_string();
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let (key, value) = (key.trim(), value.trim());
+
+        // Repeatable options get an entry per line; for the others later
+        // assignments win, as in lnd itself
+        let multi = MULTI_VALUE_OPTIONS.contains(&key);
+        if let Some(i) = entries
+            .iter()
+            .position(|e| e.key == key && !(multi && e.enabled))
+        {
+            entries[i].value = value.to_string();
+            entries[i].enabled = true;
+        } else if let Some(last) = entries.iter().rposition(|e| e.key == key && multi) {
+            let repeat = ConfigEntry {
+                value: value.to_string(),
+                ..entries[last].clone()
+            };
+            entries.insert(last + 1, repeat);
+        } else {
+            entries.push(ConfigEntry {
+                key: key.to_string(),
+                value: value.to_string(),
+                schema: None,
+                enabled: true,
+                section: Some(current_section.clone()),
+                negated: false,
+            });
+        }
+    }
+
+    entries
+}
+
+/// Parse an lnd.conf file
+///
+/// # Errors
+/// Returns an error if the file exists but cannot be read.
+/// A missing file yields schema defaults.
+pub fn parse_lnd_config(path: &Path) -> Result<Vec<ConfigEntry>> {
+    let content = if path.exists() {
+        std::fs::read_to_string(path)?
+    } else {
+        String::new()
+    };
+    Ok(parse_lnd_str(&content))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitcoin_config::save_config;
+
+    const SAMPLE: &str = r"
+[Application Options]
+alias=my-node
+listen=0.0.0.0:9735
+
+[Bitcoin]
+bitcoin.active=1
+bitcoin.mainnet=1
+bitcoin.node=bitcoind
+
+[Bitcoind]
+bitcoind.rpcuser=lnd
+customkey=custom
+";
+
+    #[test]
+    fn schema_keys_are_unique() {
+        let schema = get_lnd_schema();
+        let mut keys: Vec<&str> = schema.iter().map(|s| s.key.as_str()).collect();
+        keys.sort_unstable();
+        keys.dedup();
+        assert_eq!(keys.len(), schema.len());
+    }
+
+    #[test]
+    fn schema_covers_every_section() {
+        let sections: Vec<&str> = get_lnd_schema()
+            .iter()
+            .map(|s| section_for(s.category))
+            .collect();
+        for expected in [
+            "Application Options",
+            "Bitcoin",
+            "Bitcoind",
+            "Watchtower",
+            "Tor",
+        ] {
+            assert!(sections.contains(&expected), "missing section {expected}");
+        }
+    }
+
+    #[test]
+    fn parse_sets_known_values_and_sections() {
+        let entries = parse_lnd_str(SAMPLE);
+
+        let alias = entries.iter().find(|e| e.key == "alias").unwrap();
+        assert_eq!(alias.value, "my-node");
+        assert!(alias.enabled);
+        assert_eq!(alias.section.as_deref(), Some("Application Options"));
+
+        let node = entries.iter().find(|e| e.key == "bitcoin.node").unwrap();
+        assert_eq!(node.value, "bitcoind");
+        assert_eq!(node.section.as_deref(), Some("Bitcoin"));
+
+        let tor = entries.iter().find(|e| e.key == "tor.active").unwrap();
+        assert!(!tor.enabled);
+        assert_eq!(tor.value, "0");
+    }
+
+    #[test]
+    fn parse_keeps_unknown_keys_in_their_section() {
+        let entries = parse_lnd_str(SAMPLE);
+        let custom = entries.iter().find(|e| e.key == "customkey").unwrap();
+        assert!(custom.schema.is_none());
+        assert_eq!(custom.section.as_deref(), Some("Bitcoind"));
+    }
+
+    #[test]
+    fn parse_later_assignment_wins() {
+        let entries = parse_lnd_str("alias=first\nalias=second\n");
+        let alias = entries.iter().find(|e| e.key == "alias").unwrap();
+        assert_eq!(alias.value, "second");
+    }
+
+    #[test]
+    fn parse_keeps_every_listen_address() {
+        let entries = parse_lnd_str(
+            "listen=0.0.0.0:9735\nlisten=[::]:9735\nexternalip=1.2.3.4\nexternalip=abc.onion\n",
+        );
+        let values = |key: &str| -> Vec<&str> {
+            entries
+                .iter()
+                .filter(|e| e.key == key && e.enabled)
+                .map(|e| e.value.as_str())
+                .collect()
+        };
+        assert_eq!(values("listen"), ["0.0.0.0:9735", "[::]:9735"]);
+        assert_eq!(values("externalip"), ["1.2.3.4", "abc.onion"]);
+        assert!(
+            entries
+                .iter()
+                .filter(|e| e.key == "listen")
+                .all(|e| e.schema.is_some() && e.section.as_deref() == Some(DEFAULT_SECTION))
+        );
+    }
+
+    #[test]
+    fn parse_missing_file_returns_defaults() {
+        let entries = parse_lnd_config(Path::new("/non/existent/lnd.conf")).unwrap();
+        assert!(!entries.is_empty());
+        assert!(entries.iter().all(|e| !e.enabled));
+    }
+
+    #[test]
+    fn save_roundtrip_preserves_values_and_sections() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lnd.conf");
+        let entries = parse_lnd_str(SAMPLE);
+
+        save_config(&path, &entries).unwrap();
+        let saved = std::fs::read_to_string(&path).unwrap();
+        assert!(saved.contains("[Application Options]\nalias=my-node"));
+        assert!(saved.contains("[Bitcoin]"));
+
+        let reparsed = parse_lnd_config(&path).unwrap();
+        let enabled = |list: &[ConfigEntry]| {
+            let mut v: Vec<(String, String)> = list
+                .iter()
+                .filter(|e| e.enabled)
+                .map(|e| (e.key.clone(), e.value.clone()))
+                .collect();
+            v.sort();
+            v
+        };
+        assert_eq!(enabled(&entries), enabled(&reparsed));
+    }
+}
@@ -0,0 +1,217 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_config::{ConfigEntry, enabled_value};
+use crate::diagnostics::Diagnostic;
+use crate::fd_limit::MAX_ADDNODE_CONNECTIONS;
+
+/// Share of the system RAM, in percent, bitcoind's expected peak may take
+/// when the settings name none.
+pub const DEFAULT_MEMORY_SHARE_PCT: u64 = 80;
+
+/// Memory bitcoind uses whatever the options: code, the block index and
+/// the signature and script caches.
+pub const BASE_MIB: u64 = 300;
+
+/// Stack of one script verification thread.
+pub const SCRIPT_THREAD_MIB: u64 = 8;
+
+/// Script verification threads bitcoind starts at most.
+pub const MAX_SCRIPT_THREADS: u64 = 15;
+
+/// Smallest values bitcoind accepts, the floor of what PDM suggests.
+pub const MIN_DBCACHE_MIB: u64 = 4;
+pub const MIN_MAXMEMPOOL_MB: u64 = 5;
+
+const MIB: u64 = 1024 * 1024;
+const DEFAULT_DBCACHE_MIB: u64 = 450;
+const DEFAULT_MAXMEMPOOL_MB: u64 = 300;
+const DEFAULT_MAX_CONNECTIONS: u64 = 125;
+const DEFAULT_MAX_RECEIVE_KB: u64 = 5000;
+const DEFAULT_MAX_SEND_KB: u64 = 1000;
+
+/// `MemTotal` and `MemAvailable` of a `/proc/meminfo` file, in bytes.
+#[must_use]
+pub fn parse_meminfo(meminfo: &str) -> Option<(u64, u64)> {
+    let field = |name: &str| {
+        meminfo
+            .lines()
+            .find_map(|l| l.strip_prefix(name)?.strip_prefix(':'))
+            .and_then(|v| v.split_whitespace().next()?.parse::<u64>().ok())
+            .map(|kib| kib * 1024)
+    };
+    Some((field("MemTotal")?, field("MemAvailable")?))
+}
+
+/// Expected peak memory of bitcoind, in bytes, by what takes it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryModel {
+    pub base: u64,
+    pub dbcache: u64,
+    pub mempool: u64,
+    /// Receive and send buffers of every connection, full
+    pub connections: u64,
+    pub script_threads: u64,
+    /// Connections counted in `connections`
+    pub peers: u64,
+    /// Bytes one connection's buffers take
+    pub per_peer: u64,
+}
+
+impl MemoryModel {
+    #[must_use]
+    pub fn total(&self) -> u64 {
+        self.base + self.dbcache + self.mempool + self.connections + self.script_threads
+    }
+}
+
+/// Script verification threads `par` starts on `cores` cores: 0 means
+/// one per core, a negative value leaves that many cores free. The
+/// thread validating the block counts as one.
+#[must_use]
+pub fn script_threads(par: i64, cores: u64) -> u64 {
+    let wanted = if par <= 0 {
+        i64::try_from(cores).unwrap_or(i64::MAX) + par
+    } else {
+        par
+    };
+    u64::try_from(wanted)
+        .unwrap_or(0)
+        .clamp(1, MAX_SCRIPT_THREADS)
+        - 1
+}
+
+/// The peak `entries` let bitcoind reach on a machine with `cores` cores.
+#[must_use]
+pub fn memory_model(entries: &[ConfigEntry], cores: u64) -> MemoryModel {
+    let number = |key: &str, default: u64| {
+        enabled_value(entries, key)
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(default)
+    };
+    let peers = number("maxconnections", DEFAULT_MAX_CONNECTIONS) + MAX_ADDNODE_CONNECTIONS;
+    let per_peer = (number("maxreceivebuffer", DEFAULT_MAX_RECEIVE_KB)
+        + number("maxsendbuffer", DEFAULT_MAX_SEND_KB))
+        * 1000;
+    let par = enabled_value(entries, "par")
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(0);
+    MemoryModel {
+        base: BASE_MIB * MIB,
+        dbcache: number("dbcache", DEFAULT_DBCACHE_MIB) * MIB,
+        mempool: number("maxmempool", DEFAULT_MAXMEMPOOL_MB) * 1_000_000,
+        connections: peers * per_peer,
+        script_threads: script_threads(par, cores) * SCRIPT_THREAD_MIB * MIB,
+        peers,
+        per_peer,
+    }
+}
+
+/// Values that bring `model` within `limit` bytes: `dbcache` first, as it
+/// only slows the initial sync, then `maxmempool`, then `maxconnections`.
+#[must_use]
+pub fn suggestions(model: &MemoryModel, limit: u64) -> Vec<String> {
+    let mut excess = model.total().saturating_sub(limit);
+    let mut out = Vec::new();
+    let mut shrink = |key: &str, current: u64, floor: u64, unit: u64| {
+        if excess == 0 || current <= floor * unit {
+            return;
+        }
+        let cut = excess.min(current - floor * unit);
+        excess -= cut;
+        out.push(format!("{key}={}", (current - cut) / unit));
+    };
+    shrink("dbcache", model.dbcache, MIN_DBCACHE_MIB, MIB);
+    shrink("maxmempool", model.mempool, MIN_MAXMEMPOOL_MB, 1_000_000);
+    if excess > 0 && model.per_peer > 0 {
+        let peers = model.peers.saturating_sub(excess.div_ceil(model.per_peer));
+        out.push(format!(
+            "maxconnections={}",
+            peers.saturating_sub(MAX_ADDNODE_CONNECTIONS)
+        ));
+    }
+    out
+}
+
+/// A warning when the expected peak of `entries` passes `share_pct`
+/// percent of `ram` bytes, where the kernel may kill bitcoind mid-flush.
+#[must_use]
+pub fn oom_warning(
+    entries: &[ConfigEntry],
+    ram: u64,
+    share_pct: u64,
+    cores: u64,
+) -> Option<Diagnostic> {
+    let model = memory_model(entries, cores);
+    let limit = ram / 100 * share_pct;
+    if model.total() <= limit {
+        return None;
+    }
+    Some(Diagnostic::warning(
+        "dbcache",
+        format!(
+            "bitcoind may peak at {} MiB (dbcache {}, mempool {}, {} connections {}, script \
+             threads {}, base {}), over {share_pct}% of the {} MiB of RAM; risk of being \
+             killed out of memory, try {}",
+            model.total() / MIB,
+            model.dbcache / MIB,
+            model.mempool / MIB,
+            model.peers,
+            model.connections / MIB,
+            model.script_threads / MIB,
+            model.base / MIB,
+            ram / MIB,
+            suggestions(&model, limit).join(" ")
+        ),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitcoin_config::{NodeFlavor, parse_config_str};
+
+    #[test]
+    fn model_adds_caches_buffers_and_threads() {
+        assert_eq!(script_threads(0, 4), 3);
+        assert_eq!(script_threads(-1, 4), 2);
+        assert_eq!(script_threads(64, 4), 14);
+        assert_eq!(script_threads(-8, 4), 0);
+
+        let entries = parse_config_str(
+            "dbcache=2000\nmaxmempool=500\nmaxconnections=20\nmaxreceivebuffer=1000\n\
+             maxsendbuffer=1000\npar=3\n",
+            NodeFlavor::Core,
+        )
+        .unwrap();
+        let model = memory_model(&entries, 8);
+        assert_eq!(model.dbcache, 2000 * MIB);
+        assert_eq!(model.mempool, 500_000_000);
+        assert_eq!(model.peers, 28);
+        assert_eq!(model.connections, 28 * 2_000_000);
+        assert_eq!(model.script_threads, 16 * MIB);
+        assert_eq!(model.total(), 2316 * MIB + 500_000_000 + 56_000_000);
+    }
+
+    #[test]
+    fn warning_suggests_values_that_fit() {
+        let entries = parse_config_str("dbcache=4000\n", NodeFlavor::Core).unwrap();
+        let ram = 4096 * MIB;
+        let warning = oom_warning(&entries, ram, 80, 4).unwrap();
+        assert!(
+            warning.message.contains("over 80% of the 4096 MiB"),
+            "{}",
+            warning.message
+        );
+        let model = memory_model(&entries, 4);
+        let limit = ram / 100 * 80;
+        let fixed = suggestions(&model, limit);
+        assert_eq!(fixed.len(), 1);
+        assert!(fixed[0].starts_with("dbcache="));
+        assert!(oom_warning(&entries, 64 * 1024 * MIB, 80, 4).is_none());
+
+        let tight = suggestions(&model, 300 * MIB);
+        assert_eq!(tight, ["dbcache=4", "maxmempool=5", "maxconnections=0"]);
+    }
+}
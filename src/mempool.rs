@@ -0,0 +1,205 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use serde_json::Value;
+use std::time::{Duration, Instant};
+
+/// How often the Mempool tab asks bitcoind for `getrawmempool`.
+pub const MEMPOOL_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How long a verbose `getrawmempool` may take; a full mainnet mempool
+/// runs to tens of megabytes of JSON.
+pub const MEMPOOL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Lower bounds of the feerate buckets, in sat/vB, as mempool.space draws
+/// them.
+pub const FEE_BUCKETS: &[u64] = &[
+    1, 2, 3, 4, 5, 6, 8, 10, 12, 15, 20, 30, 40, 50, 60, 70, 80, 90, 100, 125, 150, 175, 200, 250,
+    300, 350, 400, 500,
+];
+
+/// Virtual size of a block's worth of transactions.
+pub const BLOCK_VSIZE: u64 = 1_000_000;
+
+/// Projected blocks shown; the rest of the mempool is summed into the last.
+pub const PROJECTED_BLOCKS: usize = 4;
+
+/// One feerate bucket of the mempool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeBucket {
+    /// Lowest feerate in it, in sat/vB
+    pub min_feerate: u64,
+    pub vsize: u64,
+    pub txs: u64,
+}
+
+/// The transactions a miner would pick for one upcoming block, highest
+/// feerate first.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProjectedBlock {
+    pub min_feerate: f64,
+    pub median_feerate: f64,
+    pub max_feerate: f64,
+    pub vsize: u64,
+    pub txs: u64,
+}
+
+/// Mempool of the running bitcoind, refreshed while the Mempool tab is
+/// shown.
+#[derive(Debug, Clone, Default)]
+pub struct MempoolMonitor {
+    /// Non-empty feerate buckets, lowest first
+    pub histogram: Vec<FeeBucket>,
+    pub blocks: Vec<ProjectedBlock>,
+    pub txs: u64,
+    pub vsize: u64,
+    /// Why the last poll failed
+    pub error: Option<String>,
+    pub last_poll: Option<Instant>,
+}
+
+impl MempoolMonitor {
+    /// Whether the next poll is due at `now`.
+    #[must_use]
+    pub fn due(&self, now: Instant) -> bool {
+        self.last_poll
+            .is_none_or(|last| now.duration_since(last) >= MEMPOOL_POLL_INTERVAL)
+    }
+
+    /// Takes a verbose `getrawmempool` result.
+    pub fn update(&mut self, mempool: &Value) {
+        let mut txs: Vec<(f64, u64)> = mempool
+            .as_object()
+            .map(|txs| txs.values().filter_map(feerate).collect())
+            .unwrap_or_default();
+        txs.sort_by(|a, b| b.0.total_cmp(&a.0));
+        self.txs = txs.len() as u64;
+        self.vsize = txs.iter().map(|(_, vsize)| vsize).sum();
+        self.histogram = histogram(&txs);
+        self.blocks = project_blocks(&txs);
+        self.error = None;
+    }
+}
+
+/// Feerate in sat/vB and vsize of one `getrawmempool` entry, using the
+/// fee as modified by `prioritisetransaction` since that is what block
+/// templates sort by.
+#[allow(clippy::cast_precision_loss)] // Feerates are shown rounded
+fn feerate(tx: &Value) -> Option<(f64, u64)> {
+    let vsize = tx.get("vsize")?.as_u64().filter(|v| *v > 0)?;
+    let fee = tx.get("fees")?.get("modified")?.as_f64()?;
+    Some((fee * 100_000_000.0 / vsize as f64, vsize))
+}
+
+/// `txs` summed into [`FEE_BUCKETS`], dropping empty buckets.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)] // Bucket search only
+fn histogram(txs: &[(f64, u64)]) -> Vec<FeeBucket> {
+    let mut buckets: Vec<FeeBucket> = FEE_BUCKETS
+        .iter()
+        .map(|&min_feerate| FeeBucket {
+            min_feerate,
+            vsize: 0,
+            txs: 0,
+        })
+        .collect();
+    for &(rate, vsize) in txs {
+        let floor = rate.floor() as u64;
+        let i = FEE_BUCKETS
+            .iter()
+            .rposition(|&min| min <= floor)
+            .unwrap_or(0);
+        buckets[i].vsize += vsize;
+        buckets[i].txs += 1;
+    }
+    buckets.retain(|b| b.txs > 0);
+    buckets
+}
+
+/// Splits `txs`, sorted by feerate, into [`BLOCK_VSIZE`] blocks. Ancestor
+/// packages are ignored, so low-fee parents of high-fee children land in
+/// a later block than a miner would put them.
+fn project_blocks(txs: &[(f64, u64)]) -> Vec<ProjectedBlock> {
+    let mut blocks = Vec::new();
+    let mut start = 0;
+    while start < txs.len() {
+        let last_block = blocks.len() + 1 == PROJECTED_BLOCKS;
+        let mut end = start;
+        let mut vsize = 0;
+        while end < txs.len() && (last_block || vsize + txs[end].1 <= BLOCK_VSIZE || end == start) {
+            vsize += txs[end].1;
+            end += 1;
+        }
+        let block = &txs[start..end];
+        blocks.push(ProjectedBlock {
+            max_feerate: block[0].0,
+            median_feerate: block[block.len() / 2].0,
+            min_feerate: block[block.len() - 1].0,
+            vsize,
+            txs: block.len() as u64,
+        });
+        start = end;
+    }
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tx(sats: u64, vsize: u64) -> Value {
+        #[allow(clippy::cast_precision_loss)]
+        let btc = sats as f64 / 100_000_000.0;
+        serde_json::json!({"vsize": vsize, "fees": {"base": btc, "modified": btc}})
+    }
+
+    #[test]
+    fn histogram_buckets_by_feerate() {
+        let mut monitor = MempoolMonitor::default();
+        monitor.update(&serde_json::json!({
+            "a": tx(200, 200),
+            "b": tx(1_100, 100),
+            "c": tx(1_150, 100),
+            "d": tx(60_000, 100),
+        }));
+        assert_eq!(monitor.txs, 4);
+        assert_eq!(monitor.vsize, 500);
+        assert_eq!(
+            monitor.histogram,
+            [
+                FeeBucket {
+                    min_feerate: 1,
+                    vsize: 200,
+                    txs: 1
+                },
+                FeeBucket {
+                    min_feerate: 10,
+                    vsize: 200,
+                    txs: 2
+                },
+                FeeBucket {
+                    min_feerate: 500,
+                    vsize: 100,
+                    txs: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn projected_blocks_fill_by_feerate() {
+        let mut monitor = MempoolMonitor::default();
+        let mempool: serde_json::Map<String, Value> = (0..12)
+            .map(|i| (format!("{i}"), tx(100_000 * (i + 1), 400_000)))
+            .collect();
+        monitor.update(&Value::Object(mempool));
+        let blocks = &monitor.blocks;
+        assert_eq!(blocks.len(), PROJECTED_BLOCKS);
+        assert_eq!(blocks[0].txs, 2);
+        assert!((blocks[0].max_feerate - 3.0).abs() < 1e-9);
+        assert!((blocks[0].min_feerate - 2.75).abs() < 1e-9);
+        // The last block holds the rest of the mempool
+        assert_eq!(blocks[3].txs, 6);
+        assert_eq!(blocks[3].vsize, 2_400_000);
+    }
+}
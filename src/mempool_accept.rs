@@ -0,0 +1,190 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use serde_json::Value;
+
+/// Transactions `testmempoolaccept` takes at once, as a package.
+pub const MAX_PACKAGE: usize = 25;
+
+/// Reject reasons the relay-policy options decide, by prefix, with the
+/// option to look at.
+const POLICY_REASONS: &[(&str, &str)] = &[
+    ("min relay fee not met", "minrelaytxfee"),
+    ("mempool min fee not met", "maxmempool"),
+    ("dust", "dustrelayfee"),
+    ("datacarrier", "datacarrier"),
+    ("multi-op-return", "datacarrier"),
+    ("scriptpubkey", "datacarriersize / permitbaremultisig"),
+    ("bare-multisig", "permitbaremultisig"),
+    ("bad-txns-too-many-sigops", "bytespersigop"),
+    (
+        "too-long-mempool-chain",
+        "limitancestorcount / limitdescendantcount",
+    ),
+    ("insufficient fee", "incrementalrelayfee"),
+];
+
+/// The option whose setting made bitcoind give `reason`, if any.
+#[must_use]
+pub fn policy_option(reason: &str) -> Option<&'static str> {
+    POLICY_REASONS
+        .iter()
+        .find(|(prefix, _)| reason.starts_with(prefix))
+        .map(|(_, key)| *key)
+}
+
+/// The raw transactions in `input`, separated by whitespace or commas.
+///
+/// # Errors
+/// Returns what is wrong with the input: nothing pasted, a transaction
+/// that is not hex, or more than a package holds.
+pub fn parse_raw_txs(input: &str) -> Result<Vec<String>, String> {
+    let txs: Vec<String> = input
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|t| !t.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect();
+    if txs.is_empty() {
+        return Err("Paste a raw transaction in hex".to_string());
+    }
+    if txs.len() > MAX_PACKAGE {
+        return Err(format!(
+            "At most {MAX_PACKAGE} transactions can be tested together"
+        ));
+    }
+    for (i, tx) in txs.iter().enumerate() {
+        if tx.len() % 2 != 0 || !tx.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(format!("Transaction {} is not hex", i + 1));
+        }
+    }
+    Ok(txs)
+}
+
+/// What `testmempoolaccept` says about one transaction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AcceptResult {
+    pub txid: String,
+    pub allowed: bool,
+    /// Why bitcoind would not accept it, with details when it gives them
+    pub reject_reason: Option<String>,
+    pub vsize: Option<u64>,
+    /// Fee paid, in BTC
+    pub fee: Option<f64>,
+    /// Feerate counted with the package it is evaluated in, in BTC/kvB
+    pub effective_feerate: Option<f64>,
+}
+
+impl AcceptResult {
+    /// Reads one element of a `testmempoolaccept` result.
+    #[must_use]
+    pub fn from_json(result: &Value) -> Self {
+        let text = |key: &str| result.get(key).and_then(Value::as_str).map(str::to_string);
+        let fees = result.get("fees");
+        let fee = |key: &str| fees.and_then(|f| f.get(key)).and_then(Value::as_f64);
+        let reject_reason = text("reject-reason").map(|reason| match text("reject-details") {
+            Some(details) if details != reason => details,
+            _ => reason,
+        });
+        Self {
+            txid: text("txid").unwrap_or_default(),
+            allowed: result
+                .get("allowed")
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+            reject_reason,
+            vsize: result.get("vsize").and_then(Value::as_u64),
+            fee: fee("base"),
+            effective_feerate: fee("effective-feerate"),
+        }
+    }
+
+    /// Fee in satoshis.
+    #[must_use]
+    pub fn fee_sat(&self) -> Option<u64> {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        self.fee.map(|btc| (btc * 1e8).round() as u64)
+    }
+
+    /// Feerate in sat/vB: the effective one when bitcoind gives it,
+    /// otherwise the fee over the size.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn feerate(&self) -> Option<f64> {
+        self.effective_feerate.map(|kvb| kvb * 1e5).or_else(|| {
+            let vsize = self.vsize.filter(|v| *v > 0)?;
+            Some(self.fee_sat()? as f64 / vsize as f64)
+        })
+    }
+}
+
+/// The testmempoolaccept tool on the Bitcoin Status screen.
+#[derive(Debug, Clone, Default)]
+pub struct TxTester {
+    /// Hex being pasted, while the input is open
+    pub input: Option<String>,
+    /// Transactions last tested, to test again after a config change
+    pub txs: Vec<String>,
+    pub results: Vec<AcceptResult>,
+    /// Why the package as a whole was rejected
+    pub package_error: Option<String>,
+    /// Why the input or the call failed
+    pub error: Option<String>,
+}
+
+impl TxTester {
+    /// Takes a `testmempoolaccept` result.
+    pub fn update(&mut self, result: &Value) {
+        let items = result.as_array().map(Vec::as_slice).unwrap_or_default();
+        self.results = items.iter().map(AcceptResult::from_json).collect();
+        self.package_error = items
+            .iter()
+            .find_map(|r| r.get("package-error").and_then(Value::as_str))
+            .map(str::to_string);
+        self.error = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn raw_transactions_must_be_hex() {
+        assert_eq!(
+            parse_raw_txs(" 0200AB,\n0100 ").unwrap(),
+            ["0200ab", "0100"]
+        );
+        assert!(parse_raw_txs("  ").is_err());
+        assert_eq!(
+            parse_raw_txs("0200 02z0").unwrap_err(),
+            "Transaction 2 is not hex"
+        );
+        assert!(parse_raw_txs("020").is_err());
+        assert!(parse_raw_txs(&"00 ".repeat(MAX_PACKAGE + 1)).is_err());
+    }
+
+    #[test]
+    fn results_show_fees_and_reject_reasons() {
+        let mut tester = TxTester::default();
+        tester.update(&serde_json::json!([
+            {"txid": "aa", "wtxid": "aa", "allowed": true, "vsize": 141,
+             "fees": {"base": 0.0000141, "effective-feerate": 0.0001}},
+            {"txid": "bb", "wtxid": "bb", "allowed": false,
+             "reject-reason": "min relay fee not met",
+             "reject-details": "min relay fee not met, 100 < 141"},
+        ]));
+        let [accepted, rejected] = tester.results.as_slice() else {
+            panic!("two results");
+        };
+        assert!(accepted.allowed);
+        assert_eq!(accepted.fee_sat(), Some(1410));
+        assert_eq!(accepted.feerate(), Some(10.0));
+        assert!(!rejected.allowed);
+        let reason = rejected.reject_reason.as_deref().unwrap();
+        assert_eq!(reason, "min relay fee not met, 100 < 141");
+        assert_eq!(policy_option(reason), Some("minrelaytxfee"));
+        assert_eq!(policy_option("bad-txns-inputs-missingorspent"), None);
+        assert_eq!(tester.package_error, None);
+    }
+}
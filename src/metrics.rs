@@ -0,0 +1,291 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use serde_json::Value;
+use std::fmt::Write as _;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::{Duration, Instant};
+
+/// How often node metrics are refreshed while the exporter runs; about
+/// the default Prometheus scrape interval.
+pub const METRICS_POLL_INTERVAL: Duration = Duration::from_secs(15);
+
+/// How long a scraper may take to send its request.
+const REQUEST_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// Node metrics PDM collects for the exporter.
+#[derive(Debug, Clone, Default)]
+pub struct NodeMetrics {
+    /// Whether bitcoind answered the last poll
+    pub up: bool,
+    pub blocks: u64,
+    pub headers: u64,
+    pub verification_progress: f64,
+    pub initial_block_download: bool,
+    pub peers_in: u64,
+    pub peers_out: u64,
+    pub mempool_txs: u64,
+    /// Sum of the virtual sizes of mempool transactions
+    pub mempool_vsize: u64,
+    /// Memory the mempool takes
+    pub mempool_usage: u64,
+    pub bytes_recv: u64,
+    pub bytes_sent: u64,
+    pub last_poll: Option<Instant>,
+}
+
+impl NodeMetrics {
+    /// Whether the next poll is due at `now`.
+    #[must_use]
+    pub fn due(&self, now: Instant) -> bool {
+        self.last_poll
+            .is_none_or(|last| now.duration_since(last) >= METRICS_POLL_INTERVAL)
+    }
+
+    /// Takes `getblockchaininfo`, `getnetworkinfo`, `getmempoolinfo` and
+    /// `getnettotals` results.
+    pub fn update(&mut self, chain: &Value, network: &Value, mempool: &Value, totals: &Value) {
+        let number = |v: &Value, key: &str| v.get(key).and_then(Value::as_u64).unwrap_or(0);
+        self.up = true;
+        self.blocks = number(chain, "blocks");
+        self.headers = number(chain, "headers");
+        self.verification_progress = chain
+            .get("verificationprogress")
+            .and_then(Value::as_f64)
+            .unwrap_or(0.0);
+        self.initial_block_download =
+            chain.get("initialblockdownload").and_then(Value::as_bool) == Some(true);
+        self.peers_in = number(network, "connections_in");
+        self.peers_out = number(network, "connections_out");
+        self.mempool_txs = number(mempool, "size");
+        self.mempool_vsize = number(mempool, "bytes");
+        self.mempool_usage = number(mempool, "usage");
+        self.bytes_recv = number(totals, "totalbytesrecv");
+        self.bytes_sent = number(totals, "totalbytessent");
+    }
+
+    /// The metrics in the Prometheus text format. While bitcoind is down
+    /// only `pdm_node_up` is given, so stale values are not scraped.
+    #[must_use]
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, samples: &[(&str, String)]| {
+            let _ = writeln!(out, "# HELP {name} {help}\n# TYPE {name} {kind}");
+            for (labels, value) in samples {
+                let _ = writeln!(out, "{name}{labels} {value}");
+            }
+        };
+        metric(
+            "pdm_node_up",
+            "gauge",
+            "Whether bitcoind answered RPC at the last poll.",
+            &[("", u8::from(self.up).to_string())],
+        );
+        if !self.up {
+            return out;
+        }
+        metric(
+            "bitcoind_blocks",
+            "gauge",
+            "Height of the most-work fully validated chain.",
+            &[("", self.blocks.to_string())],
+        );
+        metric(
+            "bitcoind_headers",
+            "gauge",
+            "Height of the most-work header chain.",
+            &[("", self.headers.to_string())],
+        );
+        metric(
+            "bitcoind_verification_progress",
+            "gauge",
+            "Estimated fraction of the chain verified.",
+            &[("", self.verification_progress.to_string())],
+        );
+        metric(
+            "bitcoind_initial_block_download",
+            "gauge",
+            "Whether bitcoind is in initial block download.",
+            &[("", u8::from(self.initial_block_download).to_string())],
+        );
+        metric(
+            "bitcoind_peers",
+            "gauge",
+            "Connected peers.",
+            &[
+                ("{direction=\"inbound\"}", self.peers_in.to_string()),
+                ("{direction=\"outbound\"}", self.peers_out.to_string()),
+            ],
+        );
+        metric(
+            "bitcoind_mempool_transactions",
+            "gauge",
+            "Transactions in the mempool.",
+            &[("", self.mempool_txs.to_string())],
+        );
+        metric(
+            "bitcoind_mempool_vsize_bytes",
+            "gauge",
+            "Virtual size of the transactions in the mempool.",
+            &[("", self.mempool_vsize.to_string())],
+        );
+        metric(
+            "bitcoind_mempool_usage_bytes",
+            "gauge",
+            "Memory used by the mempool.",
+            &[("", self.mempool_usage.to_string())],
+        );
+        metric(
+            "bitcoind_network_bytes_total",
+            "counter",
+            "Bytes exchanged with peers since bitcoind started.",
+            &[
+                ("{direction=\"received\"}", self.bytes_recv.to_string()),
+                ("{direction=\"sent\"}", self.bytes_sent.to_string()),
+            ],
+        );
+        out
+    }
+}
+
+/// An HTTP endpoint serving [`NodeMetrics`] at `/metrics`. Accepting
+/// never blocks, so the event loop can serve scrapes every tick.
+#[derive(Debug)]
+pub struct MetricsServer {
+    listener: TcpListener,
+}
+
+impl MetricsServer {
+    /// Listens on `address`, such as `127.0.0.1:9332`.
+    ///
+    /// # Errors
+    /// Returns why `address` cannot be listened on.
+    pub fn bind(address: &str) -> Result<Self, String> {
+        let listener = TcpListener::bind(address)
+            .and_then(|l| l.set_nonblocking(true).map(|()| l))
+            .map_err(|e| format!("metrics cannot listen on {address}: {e}"))?;
+        Ok(Self { listener })
+    }
+
+    /// Port listened on.
+    #[must_use]
+    pub fn port(&self) -> u16 {
+        self.listener.local_addr().map_or(0, |a| a.port())
+    }
+
+    /// Answers every scrape waiting, with `metrics` for `GET /metrics`
+    /// and 404 for anything else.
+    pub fn serve(&self, metrics: &NodeMetrics) {
+        loop {
+            match self.listener.accept() {
+                Ok((stream, _)) => {
+                    // A scraper that hangs up early is its own problem
+                    let _ = respond(stream, metrics);
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(_) => break,
+            }
+        }
+    }
+}
+
+/// Reads one request from `stream` and replies to it.
+fn respond(mut stream: TcpStream, metrics: &NodeMetrics) -> std::io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut request = Vec::new();
+    let mut chunk = [0u8; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") && request.len() < 8192 {
+        let n = stream.read(&mut chunk)?;
+        if n == 0 {
+            break;
+        }
+        request.extend_from_slice(&chunk[..n]);
+    }
+    let request = String::from_utf8_lossy(&request);
+    let mut start = request.lines().next().unwrap_or("").split_whitespace();
+    let (status, body) = match (start.next(), start.next().map(|p| p.split('?').next())) {
+        (Some("GET"), Some(Some("/metrics"))) => ("200 OK", metrics.render()),
+        _ => (
+            "404 Not Found",
+            "Metrics are served at /metrics\n".to_string(),
+        ),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: text/plain; version=0.0.4; charset=utf-8\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scrape(port: u16, path: &str, server: &MetricsServer, metrics: &NodeMetrics) -> String {
+        let client = std::thread::spawn({
+            let path = path.to_string();
+            move || {
+                let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+                write!(stream, "GET {path} HTTP/1.1\r\nHost: pdm\r\n\r\n").unwrap();
+                let mut reply = String::new();
+                stream.read_to_string(&mut reply).unwrap();
+                reply
+            }
+        });
+        let start = Instant::now();
+        while !client.is_finished() && start.elapsed() < Duration::from_secs(5) {
+            server.serve(metrics);
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        client.join().unwrap()
+    }
+
+    #[test]
+    fn render_exports_node_metrics() {
+        let mut metrics = NodeMetrics::default();
+        assert_eq!(
+            metrics.render(),
+            "# HELP pdm_node_up Whether bitcoind answered RPC at the last poll.\n\
+             # TYPE pdm_node_up gauge\npdm_node_up 0\n"
+        );
+
+        metrics.update(
+            &serde_json::json!({"blocks": 850_000, "headers": 850_001,
+                "verificationprogress": 0.999_9, "initialblockdownload": false}),
+            &serde_json::json!({"connections_in": 3, "connections_out": 10}),
+            &serde_json::json!({"size": 4200, "bytes": 2_100_000, "usage": 9_000_000}),
+            &serde_json::json!({"totalbytesrecv": 5000, "totalbytessent": 7000}),
+        );
+        let text = metrics.render();
+        assert!(text.contains("pdm_node_up 1\n"));
+        assert!(text.contains("bitcoind_blocks 850000\n"));
+        assert!(text.contains("bitcoind_verification_progress 0.9999\n"));
+        assert!(text.contains("bitcoind_peers{direction=\"outbound\"} 10\n"));
+        assert!(text.contains("bitcoind_mempool_transactions 4200\n"));
+        assert!(text.contains("# TYPE bitcoind_network_bytes_total counter\n"));
+        assert!(text.contains("bitcoind_network_bytes_total{direction=\"sent\"} 7000\n"));
+    }
+
+    #[test]
+    fn server_answers_scrapes() {
+        let server = MetricsServer::bind("127.0.0.1:0").unwrap();
+        let port = server.port();
+        let metrics = NodeMetrics::default();
+
+        let reply = scrape(port, "/metrics", &server, &metrics);
+        assert!(reply.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(reply.ends_with("pdm_node_up 0\n"));
+
+        let reply = scrape(port, "/", &server, &metrics);
+        assert!(reply.starts_with("HTTP/1.1 404 Not Found\r\n"));
+
+        let err = MetricsServer::bind("not an address").unwrap_err();
+        assert!(err.starts_with("metrics cannot listen on not an address"));
+    }
+}
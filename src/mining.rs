@@ -0,0 +1,204 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_config::{ConfigEntry, enabled_value};
+use serde_json::Value;
+use std::time::{Duration, Instant};
+
+/// How often the Mining tab asks for a fresh template.
+pub const MINING_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How long `getblocktemplate` may take; bitcoind picks the transactions
+/// from the whole mempool.
+pub const TEMPLATE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// `blockmaxweight` when the config does not set it.
+pub const DEFAULT_BLOCK_MAX_WEIGHT: u64 = 3_996_000;
+
+/// `blockmintxfee` when the config does not set it, in BTC/kvB.
+pub const DEFAULT_BLOCK_MIN_TX_FEE: f64 = 0.000_01;
+
+/// The parts of `getmininginfo` the Mining tab shows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MiningInfo {
+    pub blocks: u64,
+    pub difficulty: f64,
+    /// Estimated hashes per second of the whole network
+    pub networkhashps: f64,
+    /// Transactions in the mempool
+    pub pooledtx: u64,
+}
+
+impl MiningInfo {
+    /// Reads a `getmininginfo` result.
+    #[must_use]
+    pub fn from_json(info: &Value) -> Self {
+        let float = |key: &str| info.get(key).and_then(Value::as_f64).unwrap_or(0.0);
+        let number = |key: &str| info.get(key).and_then(Value::as_u64).unwrap_or(0);
+        Self {
+            blocks: number("blocks"),
+            difficulty: float("difficulty"),
+            networkhashps: float("networkhashps"),
+            pooledtx: number("pooledtx"),
+        }
+    }
+}
+
+/// The block bitcoind would mine next, summed from `getblocktemplate`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateSummary {
+    pub height: u64,
+    pub txs: usize,
+    /// Fees of the transactions, in satoshis
+    pub fees: u64,
+    /// What the coinbase may claim beyond the fees, in satoshis
+    pub subsidy: u64,
+    /// Weight of the transactions, without header and coinbase
+    pub weight: u64,
+    pub sigops: u64,
+    /// Consensus limits the template reports
+    pub weight_limit: u64,
+    pub sigop_limit: u64,
+    /// Lowest feerate picked, in sat/vB
+    pub min_feerate: Option<f64>,
+}
+
+impl TemplateSummary {
+    /// Reads a `getblocktemplate` result.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)] // Feerates are shown rounded
+    pub fn from_json(template: &Value) -> Self {
+        let number = |v: &Value, key: &str| v.get(key).and_then(Value::as_u64).unwrap_or(0);
+        let txs = template
+            .get("transactions")
+            .and_then(Value::as_array)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let fees = txs.iter().map(|tx| number(tx, "fee")).sum();
+        let min_feerate = txs
+            .iter()
+            .filter_map(|tx| {
+                let weight = tx.get("weight")?.as_u64().filter(|w| *w > 0)?;
+                Some(number(tx, "fee") as f64 * 4.0 / weight as f64)
+            })
+            .min_by(f64::total_cmp);
+        Self {
+            height: number(template, "height"),
+            txs: txs.len(),
+            fees,
+            subsidy: number(template, "coinbasevalue").saturating_sub(fees),
+            weight: txs.iter().map(|tx| number(tx, "weight")).sum(),
+            sigops: txs.iter().map(|tx| number(tx, "sigops")).sum(),
+            weight_limit: number(template, "weightlimit"),
+            sigop_limit: number(template, "sigoplimit"),
+            min_feerate,
+        }
+    }
+}
+
+/// `blockmaxweight` as configured, or its default.
+#[must_use]
+pub fn block_max_weight(entries: &[ConfigEntry]) -> u64 {
+    enabled_value(entries, "blockmaxweight")
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(DEFAULT_BLOCK_MAX_WEIGHT)
+}
+
+/// `blockmintxfee` as configured, or its default, in sat/vB.
+#[must_use]
+pub fn block_min_feerate(entries: &[ConfigEntry]) -> f64 {
+    enabled_value(entries, "blockmintxfee")
+        .and_then(|v| v.trim().parse::<f64>().ok())
+        .unwrap_or(DEFAULT_BLOCK_MIN_TX_FEE)
+        * 100_000.0
+}
+
+/// `hashes` per second with an SI prefix, e.g. `612.3 EH/s`.
+#[must_use]
+pub fn format_hashrate(hashes: f64) -> String {
+    const PREFIXES: &[&str] = &["", "k", "M", "G", "T", "P", "E", "Z"];
+    let mut value = hashes;
+    let mut prefix = 0;
+    while value >= 1000.0 && prefix < PREFIXES.len() - 1 {
+        value /= 1000.0;
+        prefix += 1;
+    }
+    format!("{value:.1} {}H/s", PREFIXES[prefix])
+}
+
+/// Mining state of the running bitcoind, refreshed while the Mining tab
+/// is shown.
+#[derive(Debug, Clone, Default)]
+pub struct MiningMonitor {
+    pub info: Option<MiningInfo>,
+    pub template: Option<TemplateSummary>,
+    /// Why bitcoind gave no template, such as during initial sync
+    pub template_error: Option<String>,
+    /// Why the last poll failed
+    pub error: Option<String>,
+    pub last_poll: Option<Instant>,
+}
+
+impl MiningMonitor {
+    /// Whether the next poll is due at `now`.
+    #[must_use]
+    pub fn due(&self, now: Instant) -> bool {
+        self.last_poll
+            .is_none_or(|last| now.duration_since(last) >= MINING_POLL_INTERVAL)
+    }
+
+    /// Takes a `getmininginfo` result and the `getblocktemplate` one, or
+    /// why bitcoind refused it.
+    pub fn update(&mut self, info: &Value, template: Result<Value, String>) {
+        self.info = Some(MiningInfo::from_json(info));
+        match template {
+            Ok(template) => {
+                self.template = Some(TemplateSummary::from_json(&template));
+                self.template_error = None;
+            }
+            Err(e) => {
+                self.template = None;
+                self.template_error = Some(e);
+            }
+        }
+        self.error = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitcoin_config::{NodeFlavor, parse_config_str};
+
+    #[test]
+    fn template_sums_fees_weight_and_sigops() {
+        let template = TemplateSummary::from_json(&serde_json::json!({
+            "height": 850_001,
+            "coinbasevalue": 312_530_000_u64,
+            "weightlimit": 4_000_000,
+            "sigoplimit": 80_000,
+            "transactions": [
+                {"fee": 20_000, "weight": 800, "sigops": 4},
+                {"fee": 10_000, "weight": 1_000, "sigops": 1},
+            ],
+        }));
+        assert_eq!(template.txs, 2);
+        assert_eq!(template.fees, 30_000);
+        assert_eq!(template.subsidy, 312_500_000);
+        assert_eq!(template.weight, 1_800);
+        assert_eq!(template.sigops, 5);
+        assert_eq!(template.min_feerate, Some(40.0));
+        assert_eq!(template.weight_limit, 4_000_000);
+    }
+
+    #[test]
+    fn mining_options_fall_back_to_their_defaults() {
+        let entries = parse_config_str("blockmaxweight=2000000\n", NodeFlavor::Core).unwrap();
+        assert_eq!(block_max_weight(&entries), 2_000_000);
+        assert!((block_min_feerate(&entries) - 1.0).abs() < 1e-9);
+        assert_eq!(block_max_weight(&[]), DEFAULT_BLOCK_MAX_WEIGHT);
+        assert_eq!(format_hashrate(612.3e18), "612.3 EH/s");
+        assert_eq!(format_hashrate(12.0), "12.0 H/s");
+    }
+}
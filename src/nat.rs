@@ -0,0 +1,165 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_config::{ConfigEntry, enabled_value};
+use crate::bitcoind::{Feature, NodeVersion};
+use crate::indexes::enables;
+use serde_json::Value;
+use std::net::IpAddr;
+
+/// Options that ask the router to forward the P2P port.
+pub const NAT_OPTIONS: &[&str] = &["upnp", "natpmp"];
+
+/// First major version without UPnP; `natpmp` speaks PCP and NAT-PMP
+/// from then on.
+pub const UPNP_REMOVED: u32 = 29;
+
+/// Why `key` cannot map the port on `version`, if it cannot.
+#[must_use]
+pub fn unsupported(key: &str, version: &NodeVersion) -> Option<String> {
+    match key {
+        "upnp" if version.major >= UPNP_REMOVED => Some(format!(
+            "upnp was removed in v{UPNP_REMOVED} and {} ignores it; use natpmp=1",
+            version.label()
+        )),
+        "natpmp" if !version.supports(Feature::NatPmp) => Some(format!(
+            "natpmp needs v{}+, {} has only upnp",
+            Feature::NatPmp.since(),
+            version.label()
+        )),
+        _ => None,
+    }
+}
+
+/// The enabled option that maps the port on `version`, if the version
+/// is known.
+///
+/// # Errors
+/// Returns why no mapping will be attempted.
+pub fn mapping_option(
+    entries: &[ConfigEntry],
+    version: Option<&NodeVersion>,
+) -> Result<&'static str, String> {
+    if enabled_value(entries, "listen").is_some_and(|v| !enables(v)) {
+        return Err("listen=0: bitcoind accepts no connections, so nothing is mapped".into());
+    }
+    let enabled: Vec<&'static str> = NAT_OPTIONS
+        .iter()
+        .copied()
+        .filter(|key| enabled_value(entries, key).is_some_and(enables))
+        .collect();
+    if enabled.is_empty() {
+        return Err("neither upnp nor natpmp is on; set one and restart bitcoind".into());
+    }
+    let Some(version) = version else {
+        return Ok(enabled[0]);
+    };
+    let problems: Vec<String> = enabled
+        .iter()
+        .filter_map(|key| unsupported(key, version))
+        .collect();
+    enabled
+        .iter()
+        .copied()
+        .find(|key| unsupported(key, version).is_none())
+        .ok_or_else(|| problems.join("; "))
+}
+
+/// Whether peers on the internet could reach `ip`.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                // Carrier-grade NAT, 100.64.0.0/10
+                || (a == 100 && (64..128).contains(&b)))
+        }
+        IpAddr::V6(ip) => {
+            let first = ip.segments()[0];
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80)
+        }
+    }
+}
+
+/// Judges from `getnetworkinfo` whether the router mapped P2P `port`:
+/// bitcoind adds the external address it got to `localaddresses`.
+/// Addresses from `externalip` or `discover` show up there too.
+///
+/// # Errors
+/// Returns why no mapped address is seen.
+pub fn mapped_address(info: &Value, port: u16) -> Result<String, String> {
+    let addresses = info
+        .get("localaddresses")
+        .and_then(Value::as_array)
+        .ok_or_else(|| "getnetworkinfo has no localaddresses".to_string())?;
+    let public: Vec<String> = addresses
+        .iter()
+        .filter(|a| a.get("port").and_then(Value::as_u64) == Some(u64::from(port)))
+        .filter_map(|a| a.get("address").and_then(Value::as_str))
+        .filter(|a| a.parse().is_ok_and(is_public))
+        .map(str::to_string)
+        .collect();
+    if public.is_empty() {
+        Err(format!(
+            "no public address for port {port} in localaddresses; the router did not map \
+             it, check that UPnP, NAT-PMP or PCP is enabled there (mapping takes a few \
+             seconds after start)"
+        ))
+    } else {
+        Ok(format!(
+            "port {port} is mapped: bitcoind advertises {}",
+            public.join(", ")
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitcoin_config::{NodeFlavor, parse_config_str};
+
+    #[test]
+    fn mapping_option_follows_the_node_version() {
+        let v28 = NodeVersion::parse("Bitcoin Core version v28.1.0").unwrap();
+        let v29 = NodeVersion::parse("Bitcoin Core version v29.0.0").unwrap();
+        let v21 = NodeVersion::parse("Bitcoin Core version v21.2.0").unwrap();
+        let both = parse_config_str("upnp=1\nnatpmp=1\n", NodeFlavor::Core).unwrap();
+        assert_eq!(mapping_option(&both, Some(&v28)), Ok("upnp"));
+        assert_eq!(mapping_option(&both, Some(&v29)), Ok("natpmp"));
+        assert_eq!(mapping_option(&both, Some(&v21)), Ok("upnp"));
+        assert_eq!(mapping_option(&both, None), Ok("upnp"));
+
+        let upnp = parse_config_str("upnp=1\n", NodeFlavor::Core).unwrap();
+        assert_eq!(
+            mapping_option(&upnp, Some(&v29)).unwrap_err(),
+            "upnp was removed in v29 and v29.0.0 ignores it; use natpmp=1"
+        );
+        let off = parse_config_str("upnp=1\nlisten=0\n", NodeFlavor::Core).unwrap();
+        assert!(mapping_option(&off, Some(&v28)).is_err());
+        assert!(mapping_option(&[], Some(&v28)).is_err());
+    }
+
+    #[test]
+    fn only_public_addresses_on_the_port_count_as_mapped() {
+        let info = serde_json::json!({"localaddresses": [
+            {"address": "192.168.1.10", "port": 8333, "score": 1},
+            {"address": "100.72.1.1", "port": 8333, "score": 1},
+            {"address": "abcdefghijklmnopqrstuvwxyz234567abcdefghijklmnopqrstuvwx.onion",
+             "port": 8333, "score": 4},
+            {"address": "203.0.113.7", "port": 18333, "score": 1},
+        ]});
+        assert!(mapped_address(&info, 8333).is_err());
+        assert_eq!(
+            mapped_address(&info, 18333),
+            Ok("port 18333 is mapped: bitcoind advertises 203.0.113.7".to_string())
+        );
+        assert!(mapped_address(&serde_json::json!({}), 8333).is_err());
+    }
+}
@@ -0,0 +1,263 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::docker_compose::{HEADER, write_generated};
+use anyhow::Result;
+use std::io::Read as _;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// Options bitcoind runs a command for, replacing `%s` with what happened.
+pub const NOTIFY_OPTIONS: &[&str] = &["blocknotify", "walletnotify", "alertnotify"];
+
+/// How long a test fire may run before it is killed.
+pub const TEST_FIRE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Where a notify script sends its message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptKind {
+    /// A push notification through an ntfy server
+    Ntfy,
+    /// A mail through the local `sendmail`
+    Email,
+    /// A JSON POST to a URL
+    Webhook,
+}
+
+impl ScriptKind {
+    pub const ALL: [ScriptKind; 3] = [ScriptKind::Ntfy, ScriptKind::Email, ScriptKind::Webhook];
+
+    /// Name shown in the template picker.
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            ScriptKind::Ntfy => "ntfy push",
+            ScriptKind::Email => "Email",
+            ScriptKind::Webhook => "Webhook",
+        }
+    }
+
+    /// What the script needs before it works.
+    #[must_use]
+    pub fn description(self) -> &'static str {
+        match self {
+            ScriptKind::Ntfy => "Set NTFY_URL to your topic; needs curl",
+            ScriptKind::Email => "Set MAIL_TO; needs a working sendmail",
+            ScriptKind::Webhook => "Set WEBHOOK_URL; posts JSON with curl",
+        }
+    }
+
+    fn slug(self) -> &'static str {
+        match self {
+            ScriptKind::Ntfy => "ntfy",
+            ScriptKind::Email => "email",
+            ScriptKind::Webhook => "webhook",
+        }
+    }
+}
+
+/// File the `kind` script for `option` is written to.
+#[must_use]
+pub fn script_file(option: &str, kind: ScriptKind) -> String {
+    format!("{option}-{}.sh", kind.slug())
+}
+
+/// The message a script for `option` sends, in shell syntax, from the
+/// arguments the config value passes it.
+fn message(option: &str) -> &'static str {
+    match option {
+        "blocknotify" => "New block $1",
+        "walletnotify" => "Wallet ${2:-default} transaction $1",
+        _ => "bitcoind alert: $1",
+    }
+}
+
+/// The `kind` script for `option`, to be written at `path`. It starts
+/// with the PDM header rather than a shebang, so the config value runs it
+/// with `sh`.
+#[must_use]
+pub fn template(option: &str, kind: ScriptKind, path: &Path) -> String {
+    let send = match kind {
+        ScriptKind::Ntfy => {
+            "NTFY_URL=\"${NTFY_URL:-https://ntfy.sh/CHANGE-ME}\"\n\n\
+             curl -fsS -H \"Title: bitcoind $EVENT\" -d \"$MESSAGE\" \"$NTFY_URL\" >/dev/null\n"
+        }
+        ScriptKind::Email => {
+            "MAIL_TO=\"${MAIL_TO:-you@example.com}\"\n\n\
+             printf 'To: %s\\nSubject: bitcoind %s\\n\\n%s\\n' \"$MAIL_TO\" \"$EVENT\" \"$MESSAGE\" \\\n    \
+             | sendmail -t\n"
+        }
+        ScriptKind::Webhook => {
+            "WEBHOOK_URL=\"${WEBHOOK_URL:-https://example.com/hook}\"\n\n\
+             curl -fsS -X POST -H 'Content-Type: application/json' \\\n    \
+             -d \"{\\\"event\\\":\\\"$EVENT\\\",\\\"message\\\":\\\"$MESSAGE\\\"}\" \\\n    \
+             \"$WEBHOOK_URL\" >/dev/null\n"
+        }
+    };
+    format!(
+        "{HEADER}: {} script for {option}\n\
+         # bitcoind runs it as: {}\n\
+         set -eu\n\n\
+         EVENT={option}\n\
+         MESSAGE=\"{}\"\n\
+         {send}",
+        kind.label(),
+        config_value(option, path),
+        message(option),
+    )
+}
+
+/// The config value that runs `script` for `option`.
+#[must_use]
+pub fn config_value(option: &str, script: &Path) -> String {
+    let args = if option == "walletnotify" {
+        "%s %w"
+    } else {
+        "%s"
+    };
+    let script = script.display().to_string();
+    if script.contains(char::is_whitespace) {
+        format!("sh '{script}' {args}")
+    } else {
+        format!("sh {script} {args}")
+    }
+}
+
+/// Writes the `kind` script for `option` into `dir`, readable only by its
+/// owner since it may hold tokens, and returns the file.
+///
+/// # Errors
+/// Returns an error if a hand-written file of the same name exists or the
+/// file cannot be written.
+pub fn write_script(dir: &Path, option: &str, kind: ScriptKind) -> Result<PathBuf> {
+    let path = dir.join(script_file(option, kind));
+    write_generated(&path, &template(option, kind, &path))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o700))?;
+    }
+    Ok(path)
+}
+
+/// What bitcoind would put in place of `%s` for `option`.
+fn dummy_argument(option: &str) -> &'static str {
+    match option {
+        "blocknotify" | "walletnotify" => {
+            "0000000000000000000000000000000000000000000000000000000000000000"
+        }
+        _ => "PDM test alert",
+    }
+}
+
+/// Runs the `option` command `value` as bitcoind would, with a dummy
+/// `%s` and `%w`, and waits up to `timeout` for it.
+///
+/// # Errors
+/// Returns why the command failed, with the end of what it wrote to stderr.
+pub fn test_fire(option: &str, value: &str, timeout: Duration) -> Result<String, String> {
+    let command = value
+        .replace("%s", dummy_argument(option))
+        .replace("%w", "pdm-test");
+    let mut child = Command::new("sh")
+        .args(["-c", &command])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("{option} did not start: {e}"))?;
+    let start = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if start.elapsed() < timeout => std::thread::sleep(Duration::from_millis(50)),
+            Ok(None) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!(
+                    "{option} still running after {}s, killed",
+                    timeout.as_secs()
+                ));
+            }
+            Err(e) => return Err(format!("{option}: {e}")),
+        }
+    };
+    if status.success() {
+        return Ok(format!("{option} fired and exited cleanly"));
+    }
+    let mut stderr = String::new();
+    if let Some(mut pipe) = child.stderr.take() {
+        let _ = pipe.read_to_string(&mut stderr);
+    }
+    let last = stderr.lines().rev().find(|l| !l.trim().is_empty());
+    Err(match last {
+        Some(line) => format!("{option} failed ({status}): {}", line.trim()),
+        None => format!("{option} failed ({status})"),
+    })
+}
+
+/// Template picker for a notify option.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptPicker {
+    /// Entry the script is for
+    pub index: usize,
+    pub key: String,
+    /// Index into [`ScriptKind::ALL`]
+    pub cursor: usize,
+}
+
+impl ScriptPicker {
+    /// The kind under the cursor.
+    #[must_use]
+    pub fn kind(&self) -> ScriptKind {
+        ScriptKind::ALL[self.cursor.min(ScriptKind::ALL.len() - 1)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scripts_are_written_private_and_runnable() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_script(dir.path(), "walletnotify", ScriptKind::Webhook).unwrap();
+        assert_eq!(path, dir.path().join("walletnotify-webhook.sh"));
+        let script = std::fs::read_to_string(&path).unwrap();
+        assert!(script.starts_with(HEADER));
+        assert!(script.contains(&format!(
+            "# bitcoind runs it as: sh {} %s %w\n",
+            path.display()
+        )));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o700);
+        }
+        let syntax = Command::new("sh").arg("-n").arg(&path).status().unwrap();
+        assert!(syntax.success());
+
+        std::fs::write(&path, "hand written\n").unwrap();
+        assert!(write_script(dir.path(), "walletnotify", ScriptKind::Webhook).is_err());
+    }
+
+    #[test]
+    fn test_fire_passes_a_dummy_argument() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("out");
+        let value = format!("echo %s %w > {}", out.display());
+        assert!(test_fire("walletnotify", &value, TEST_FIRE_TIMEOUT).is_ok());
+        assert_eq!(
+            std::fs::read_to_string(&out).unwrap(),
+            format!("{} pdm-test\n", dummy_argument("walletnotify"))
+        );
+
+        let err = test_fire("alertnotify", "echo oops >&2; exit 3", TEST_FIRE_TIMEOUT);
+        assert!(err.unwrap_err().ends_with(": oops"));
+        let err = test_fire("blocknotify", "sleep 5", Duration::from_millis(100));
+        assert!(err.unwrap_err().contains("killed"));
+    }
+}
@@ -0,0 +1,154 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use anyhow::{Context, Result, bail};
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// File bitcoind keeps the private key of its onion service in, in the
+/// chain's data directory.
+pub const ONION_KEY_FILE: &str = "onion_v3_private_key";
+
+/// What the onion key is, for the backup screen.
+pub const ONION_KEY_NOTE: &str = "bitcoind creates this key the first time it publishes an onion \
+    service through the Tor control port (listenonion=1). The .onion address peers know your \
+    node by is derived from it: lose it and a reinstall comes up under a new address; leak it \
+    and someone else can claim yours. Services set up in torrc keep theirs in \
+    hs_ed25519_secret_key inside HiddenServiceDir instead.";
+
+/// The onion key in `datadir`, if bitcoind has made one.
+#[must_use]
+pub fn find_onion_key(datadir: &Path) -> Option<PathBuf> {
+    Some(datadir.join(ONION_KEY_FILE)).filter(|p| p.is_file())
+}
+
+/// An onion key about to be backed up, and the passphrase typed to
+/// encrypt it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OnionBackup {
+    pub key: PathBuf,
+    /// Empty for a plain copy
+    pub passphrase: String,
+}
+
+/// Copies `key` into `dir` under a timestamped name, readable only by its
+/// owner. With a `passphrase` the copy is encrypted with `gpg
+/// --symmetric`; `gpg --decrypt` restores it.
+///
+/// # Errors
+/// Returns an error if the key cannot be read, gpg fails, or the copy
+/// cannot be written.
+pub fn back_up_onion_key(key: &Path, dir: &Path, passphrase: Option<&str>) -> Result<PathBuf> {
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let name = format!("{ONION_KEY_FILE}-{stamp}.backup");
+    let path = match passphrase {
+        None => {
+            let path = dir.join(name);
+            std::fs::copy(key, &path).with_context(|| format!("cannot copy {}", key.display()))?;
+            path
+        }
+        Some(passphrase) => {
+            let path = dir.join(format!("{name}.gpg"));
+            encrypt(key, &path, passphrase)?;
+            path
+        }
+    };
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(path)
+}
+
+/// Encrypts `input` into `output` with AES-256 under `passphrase`, which
+/// gpg reads from stdin so it never shows in the process list.
+fn encrypt(input: &Path, output: &Path, passphrase: &str) -> Result<()> {
+    let mut child = Command::new("gpg")
+        .args([
+            "--batch",
+            "--yes",
+            "--quiet",
+            "--pinentry-mode",
+            "loopback",
+            "--passphrase-fd",
+            "0",
+            "--symmetric",
+            "--cipher-algo",
+            "AES256",
+            "--output",
+        ])
+        .arg(output)
+        .arg(input)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("cannot run gpg to encrypt the backup")?;
+    if let Some(mut stdin) = child.stdin.take() {
+        writeln!(stdin, "{passphrase}")?;
+    }
+    let out = child.wait_with_output()?;
+    if !out.status.success() {
+        bail!(
+            "gpg failed: {}",
+            String::from_utf8_lossy(&out.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backup_copies_the_key_privately() {
+        let datadir = tempfile::tempdir().unwrap();
+        assert_eq!(find_onion_key(datadir.path()), None);
+        let key = datadir.path().join(ONION_KEY_FILE);
+        std::fs::write(&key, "ED25519-V3:secret").unwrap();
+        assert_eq!(find_onion_key(datadir.path()), Some(key.clone()));
+
+        let dir = tempfile::tempdir().unwrap();
+        let copy = back_up_onion_key(&key, dir.path(), None).unwrap();
+        assert!(copy.starts_with(dir.path()));
+        assert_eq!(std::fs::read_to_string(&copy).unwrap(), "ED25519-V3:secret");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&copy).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn encrypted_backup_needs_the_passphrase() {
+        if Command::new("gpg").arg("--version").output().is_err() {
+            return;
+        }
+        let datadir = tempfile::tempdir().unwrap();
+        let key = datadir.path().join(ONION_KEY_FILE);
+        std::fs::write(&key, "ED25519-V3:secret").unwrap();
+        let home = tempfile::tempdir().unwrap();
+        // SAFETY: serialised by #[serial] — no concurrent mutation of GNUPGHOME.
+        unsafe { std::env::set_var("GNUPGHOME", home.path()) };
+
+        let copy = back_up_onion_key(&key, datadir.path(), Some("hunter2")).unwrap();
+        assert!(copy.to_string_lossy().ends_with(".backup.gpg"));
+        assert!(!std::fs::read(&copy).unwrap().starts_with(b"ED25519"));
+        let plain = Command::new("gpg")
+            .args(["--batch", "--quiet", "--pinentry-mode", "loopback"])
+            .args(["--passphrase", "hunter2", "--decrypt"])
+            .arg(&copy)
+            .output()
+            .unwrap();
+        assert_eq!(plain.stdout, b"ED25519-V3:secret");
+    }
+}
@@ -0,0 +1,117 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use anyhow::{Result, bail};
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+
+/// `bitcoind -help` output for the most common options, used until the
+/// installed binary has been asked for its own.
+const BUNDLED_HELP: &str = include_str!("bitcoind_help.txt");
+
+/// Long-form help text per bitcoind option, parsed from `bitcoind -help`.
+#[derive(Debug, Clone, Default)]
+pub struct OptionHelp {
+    /// option name (without the leading dash) -> help paragraph
+    entries: HashMap<String, String>,
+}
+
+impl OptionHelp {
+    /// Help parsed from the dump that ships with PDM.
+    #[must_use]
+    pub fn bundled() -> Self {
+        Self::parse(BUNDLED_HELP)
+    }
+
+    /// Parses `bitcoind -help` output: an option line such as
+    /// `  -dbcache=<n>` followed by its indented description.
+    #[must_use]
+    pub fn parse(text: &str) -> Self {
+        let mut entries = HashMap::new();
+        let mut current: Option<(String, String)> = None;
+        for line in text.lines() {
+            if let Some(option) = line.strip_prefix("  -") {
+                if let Some((key, help)) = current.take() {
+                    entries.insert(key, help);
+                }
+                let key = option
+                    .split(['=', ' ', '['])
+                    .next()
+                    .unwrap_or(option)
+                    .to_string();
+                current = Some((key, String::new()));
+            } else if line.starts_with("       ") {
+                if let Some((_, help)) = current.as_mut() {
+                    if !help.is_empty() {
+                        help.push(' ');
+                    }
+                    help.push_str(line.trim());
+                }
+            } else if let Some((key, help)) = current.take() {
+                entries.insert(key, help);
+            }
+        }
+        if let Some((key, help)) = current {
+            entries.insert(key, help);
+        }
+        Self { entries }
+    }
+
+    /// Asks `binary` for its help dump (`-help -help-debug`).
+    ///
+    /// # Errors
+    /// Returns an error if the binary cannot be run or prints no options.
+    pub fn from_binary(binary: &Path) -> Result<Self> {
+        let output = Command::new(binary)
+            .args(["-help", "-help-debug"])
+            .output()?;
+        let help = Self::parse(&String::from_utf8_lossy(&output.stdout));
+        if help.entries.is_empty() {
+            bail!("{} printed no option help", binary.display());
+        }
+        Ok(help)
+    }
+
+    /// Adds the options in `other`, replacing any already known.
+    pub fn merge(&mut self, other: OptionHelp) {
+        self.entries.extend(other.entries);
+    }
+
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries.get(key).map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_option_blocks() {
+        let help = OptionHelp::parse(
+            "Options:\n\n  -dbcache=<n>\n       Maximum database cache\n       size.\n\n  -txindex\n       Maintain an index\n",
+        );
+        assert_eq!(help.get("dbcache"), Some("Maximum database cache size."));
+        assert_eq!(help.get("txindex"), Some("Maintain an index"));
+        assert_eq!(help.get("Options:"), None);
+    }
+
+    #[test]
+    fn bundled_help_covers_common_options() {
+        let help = OptionHelp::bundled();
+        for key in ["dbcache", "prune", "rpcport", "bind", "zmqpubrawtx"] {
+            assert!(help.get(key).is_some(), "missing help for {key}");
+        }
+        assert!(help.get("port").unwrap().contains("testnet4: 48333"));
+    }
+
+    #[test]
+    fn merge_prefers_newer_text() {
+        let mut help = OptionHelp::parse("  -txindex\n       old\n");
+        help.merge(OptionHelp::parse("  -txindex\n       new\n"));
+        assert_eq!(help.get("txindex"), Some("new"));
+    }
+}
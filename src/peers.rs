@@ -0,0 +1,293 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use serde_json::Value;
+use std::time::{Duration, Instant};
+
+/// How often the Peers tab asks bitcoind for `getpeerinfo`.
+pub const PEER_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Ping samples kept per peer for its sparkline.
+pub const PING_HISTORY: usize = 30;
+
+/// One connected peer, from `getpeerinfo`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeerStats {
+    pub id: u64,
+    pub addr: String,
+    /// `ipv4`, `ipv6`, `onion`, `i2p`, `cjdns` or `not_publicly_routable`
+    pub network: String,
+    pub inbound: bool,
+    pub subver: String,
+    /// Unix time the connection was made
+    pub conntime: u64,
+    pub bytes_sent: u64,
+    pub bytes_recv: u64,
+    /// Last ping round trip, in seconds
+    pub ping: Option<f64>,
+    /// Past ping round trips, oldest first, in seconds
+    pub ping_history: Vec<f64>,
+    /// Autonomous system the peer's address maps to, when bitcoind runs
+    /// with `asmap`
+    pub mapped_as: Option<u64>,
+}
+
+impl PeerStats {
+    fn from_json(peer: &Value) -> Option<Self> {
+        let text = |key: &str| {
+            peer.get(key)
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string()
+        };
+        let number = |key: &str| peer.get(key).and_then(Value::as_u64).unwrap_or(0);
+        Some(Self {
+            id: peer.get("id")?.as_u64()?,
+            addr: text("addr"),
+            network: text("network"),
+            inbound: peer.get("inbound").and_then(Value::as_bool) == Some(true),
+            subver: text("subver"),
+            conntime: number("conntime"),
+            bytes_sent: number("bytessent"),
+            bytes_recv: number("bytesrecv"),
+            ping: peer.get("pingtime").and_then(Value::as_f64),
+            ping_history: Vec::new(),
+            mapped_as: peer.get("mapped_as").and_then(Value::as_u64),
+        })
+    }
+}
+
+/// Peers of the running bitcoind, refreshed while the Peers tab is shown.
+#[derive(Debug, Clone, Default)]
+pub struct PeerMonitor {
+    pub peers: Vec<PeerStats>,
+    /// Why the last poll failed
+    pub error: Option<String>,
+    pub last_poll: Option<Instant>,
+}
+
+impl PeerMonitor {
+    /// Whether the next poll is due at `now`.
+    #[must_use]
+    pub fn due(&self, now: Instant) -> bool {
+        self.last_poll
+            .is_none_or(|last| now.duration_since(last) >= PEER_POLL_INTERVAL)
+    }
+
+    /// Replaces the peer list with a `getpeerinfo` result, carrying over
+    /// the ping history of peers still connected.
+    pub fn update(&mut self, info: &Value) {
+        let mut peers: Vec<PeerStats> = info
+            .as_array()
+            .map(|peers| peers.iter().filter_map(PeerStats::from_json).collect())
+            .unwrap_or_default();
+        for peer in &mut peers {
+            if let Some(old) = self.peers.iter_mut().find(|p| p.id == peer.id) {
+                peer.ping_history = std::mem::take(&mut old.ping_history);
+            }
+            if let Some(ping) = peer.ping {
+                peer.ping_history.push(ping);
+                let excess = peer.ping_history.len().saturating_sub(PING_HISTORY);
+                peer.ping_history.drain(..excess);
+            }
+        }
+        self.peers = peers;
+        self.error = None;
+    }
+}
+
+/// How many peers share each label, most common first.
+fn count_by(peers: &[PeerStats], label: impl Fn(&PeerStats) -> String) -> Vec<(String, u64)> {
+    let mut counts: Vec<(String, u64)> = Vec::new();
+    for peer in peers {
+        let label = label(peer);
+        match counts.iter_mut().find(|(l, _)| *l == label) {
+            Some((_, n)) => *n += 1,
+            None => counts.push((label, 1)),
+        }
+    }
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
+
+/// Peers per network: `ipv4`, `ipv6`, `onion`, `i2p`, `cjdns`.
+#[must_use]
+pub fn by_network(peers: &[PeerStats]) -> Vec<(String, u64)> {
+    count_by(peers, |p| {
+        if p.network.is_empty() {
+            "unknown".to_string()
+        } else {
+            p.network.clone()
+        }
+    })
+}
+
+/// Peers per autonomous system, or `None` when bitcoind runs without an
+/// `asmap` and reports none. Peers without one, such as Tor peers, are
+/// counted as `none`.
+#[must_use]
+pub fn by_asn(peers: &[PeerStats]) -> Option<Vec<(String, u64)>> {
+    peers.iter().any(|p| p.mapped_as.is_some()).then(|| {
+        count_by(peers, |p| {
+            p.mapped_as
+                .map_or_else(|| "none".to_string(), |asn| format!("AS{asn}"))
+        })
+    })
+}
+
+/// The AS holding most outbound peers when it holds more than half of
+/// them: `(label, peers in it, outbound peers)`. Outbound connections are
+/// the ones an eclipse attacker must capture.
+#[must_use]
+pub fn outbound_concentration(peers: &[PeerStats]) -> Option<(String, u64, u64)> {
+    let outbound: Vec<PeerStats> = peers
+        .iter()
+        .filter(|p| !p.inbound && p.mapped_as.is_some())
+        .cloned()
+        .collect();
+    let total = outbound.len() as u64;
+    let (label, count) = by_asn(&outbound)?.into_iter().next()?;
+    (total >= 2 && count * 2 > total).then_some((label, count, total))
+}
+
+/// `bytes` in B, KiB, MiB or GiB.
+#[must_use]
+#[allow(clippy::cast_precision_loss)] // One decimal is shown
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+/// How long ago `since` (Unix seconds) was, e.g. `3d 4h`, `12m`.
+#[must_use]
+pub fn format_age(since: u64, now: u64) -> String {
+    let secs = now.saturating_sub(since);
+    let (d, h, m) = (secs / 86_400, secs % 86_400 / 3600, secs % 3600 / 60);
+    match (d, h, m) {
+        (0, 0, 0) => format!("{secs}s"),
+        (0, 0, m) => format!("{m}m"),
+        (0, h, m) => format!("{h}h {m}m"),
+        (d, h, _) => format!("{d}d {h}h"),
+    }
+}
+
+/// `samples` as a row of block characters scaled to their maximum.
+#[must_use]
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::cast_precision_loss
+)] // The index is clamped to 0..=7
+pub fn sparkline(samples: &[f64]) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let max = samples.iter().copied().fold(0.0, f64::max);
+    samples
+        .iter()
+        .map(|s| {
+            if max <= 0.0 {
+                BARS[0]
+            } else {
+                BARS[((s / max) * 7.0).round().clamp(0.0, 7.0) as usize]
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn update_keeps_ping_history_of_connected_peers() {
+        let mut monitor = PeerMonitor::default();
+        monitor.update(&serde_json::json!([
+            {"id": 1, "addr": "198.51.100.1:8333", "pingtime": 0.1, "bytessent": 10},
+            {"id": 2, "addr": "198.51.100.2:8333", "pingtime": 0.2},
+        ]));
+        monitor.update(&serde_json::json!([
+            {"id": 2, "addr": "198.51.100.2:8333", "pingtime": 0.3, "inbound": true},
+            {"id": 3, "addr": "198.51.100.3:8333"},
+        ]));
+        assert_eq!(monitor.peers.len(), 2);
+        assert_eq!(monitor.peers[0].ping_history, vec![0.2, 0.3]);
+        assert!(monitor.peers[0].inbound);
+        assert!(monitor.peers[1].ping_history.is_empty());
+
+        for _ in 0..PING_HISTORY {
+            monitor.update(&serde_json::json!([{"id": 2, "pingtime": 0.5}]));
+        }
+        assert_eq!(monitor.peers[0].ping_history.len(), PING_HISTORY);
+        assert!(monitor.peers[0].ping_history.iter().all(|p| *p == 0.5));
+    }
+
+    #[test]
+    fn formats_traffic_age_and_pings() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(3 * 1024 * 1024 * 1024), "3.0 GiB");
+
+        assert_eq!(format_age(100, 130), "30s");
+        assert_eq!(format_age(0, 3 * 3600 + 120), "3h 2m");
+        assert_eq!(format_age(0, 2 * 86_400 + 5 * 3600), "2d 5h");
+
+        assert_eq!(sparkline(&[0.0, 0.5, 1.0]), "▁▅█");
+        assert_eq!(sparkline(&[0.0, 0.0]), "▁▁");
+    }
+
+    #[test]
+    fn peers_are_grouped_by_network_and_asn() {
+        let mut monitor = PeerMonitor::default();
+        monitor.update(&serde_json::json!([
+            {"id": 1, "network": "ipv4", "mapped_as": 64500},
+            {"id": 2, "network": "ipv4", "mapped_as": 64500},
+            {"id": 3, "network": "ipv6", "mapped_as": 64501},
+            {"id": 4, "network": "onion", "inbound": true},
+        ]));
+        let peers = &monitor.peers;
+        assert_eq!(
+            by_network(peers),
+            vec![
+                ("ipv4".to_string(), 2),
+                ("ipv6".to_string(), 1),
+                ("onion".to_string(), 1)
+            ]
+        );
+        assert_eq!(
+            by_asn(peers).unwrap(),
+            vec![
+                ("AS64500".to_string(), 2),
+                ("AS64501".to_string(), 1),
+                ("none".to_string(), 1)
+            ]
+        );
+        assert_eq!(
+            outbound_concentration(peers),
+            Some(("AS64500".to_string(), 2, 3))
+        );
+
+        monitor.update(&serde_json::json!([{"id": 1, "network": "ipv4"}]));
+        assert_eq!(by_asn(&monitor.peers), None);
+        assert_eq!(outbound_concentration(&monitor.peers), None);
+    }
+
+    #[test]
+    fn poll_is_due_after_the_interval() {
+        let mut monitor = PeerMonitor::default();
+        let now = Instant::now();
+        assert!(monitor.due(now));
+        monitor.last_poll = Some(now);
+        assert!(!monitor.due(now));
+        assert!(monitor.due(now + PEER_POLL_INTERVAL));
+    }
+}
@@ -0,0 +1,358 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::diagnostics::{Host, classify_host, split_host_port};
+
+/// Options whose value is `[permissions@]address`.
+pub const PERMISSION_OPTIONS: &[&str] = &["whitebind", "whitelist"];
+
+/// Options whose value is an address and port bitcoind listens on:
+/// `bind=addr[:port][=onion]` and `whitebind=perm@addr:port`.
+pub const BIND_OPTIONS: &[&str] = &["bind", "whitebind"];
+
+/// Flags bitcoind accepts besides [`PERMISSION_FLAGS`].
+const OTHER_FLAGS: &[&str] = &["all", "implicit", "in", "out"];
+
+/// Permission flags `whitebind` and `whitelist` grant, with what each does.
+pub const PERMISSION_FLAGS: &[(&str, &str)] = &[
+    ("bloomfilter", "Allow BIP37 bloom filter requests"),
+    ("noban", "Never ban or disconnect for misbehaving"),
+    ("forcerelay", "Relay transactions already in the mempool"),
+    ("relay", "Relay transactions even in blocksonly mode"),
+    ("mempool", "Allow requests for the whole mempool"),
+    ("download", "Allow downloads past maxuploadtarget"),
+    ("addr", "Answer getaddr requests without caching"),
+];
+
+/// The flags and address of a `[permissions@]address` value. Flags PDM
+/// does not list, such as `all` or `in`, are kept as written.
+#[must_use]
+pub fn parse_permissions(value: &str) -> (Vec<&str>, &str) {
+    match value.split_once('@') {
+        Some((flags, address)) => (
+            flags
+                .split(',')
+                .map(str::trim)
+                .filter(|f| !f.is_empty())
+                .collect(),
+            address.trim(),
+        ),
+        None => (Vec::new(), value.trim()),
+    }
+}
+
+/// A row of the [`PermissionEditor`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditorRow {
+    /// The flag at this index of [`PERMISSION_FLAGS`]
+    Flag(usize),
+    /// The host of a bind, or the address or network of a `whitelist`
+    Address,
+    Port,
+    /// The `=onion` suffix of `bind`
+    Onion,
+}
+
+/// Editor of one `bind`, `whitebind` or `whitelist` value, one component
+/// per row: a checklist of [`PERMISSION_FLAGS`] (not for `bind`), the
+/// address, and for binds the port and the onion tag.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PermissionEditor {
+    /// Entry being edited
+    pub index: usize,
+    pub key: String,
+    /// Flags picked, in the order written
+    pub flags: Vec<String>,
+    /// Host of a bind, the whole address of a `whitelist`
+    pub address: String,
+    /// Port of a bind, as typed; empty for the network's default
+    pub port: String,
+    /// Whether a `bind` is tagged `=onion`
+    pub onion: bool,
+    /// Index into [`PermissionEditor::rows`]
+    pub cursor: usize,
+}
+
+impl PermissionEditor {
+    /// Editor for the entry at `index`, starting from its `value`. A bind
+    /// whose address cannot be split keeps it whole in the address row.
+    #[must_use]
+    pub fn new(index: usize, key: &str, value: &str) -> Self {
+        let (value, onion) = match value.trim().strip_suffix("=onion") {
+            Some(rest) if key == "bind" => (rest, true),
+            _ => (value, false),
+        };
+        let (flags, address) = if key == "bind" {
+            (Vec::new(), value.trim())
+        } else {
+            parse_permissions(value)
+        };
+        let (address, port) = match split_host_port(address) {
+            Ok((host, port)) if BIND_OPTIONS.contains(&key) => {
+                (host, port.map(|p| p.to_string()).unwrap_or_default())
+            }
+            _ => (address, String::new()),
+        };
+        Self {
+            index,
+            key: key.to_string(),
+            flags: flags.into_iter().map(str::to_string).collect(),
+            address: address.to_string(),
+            port,
+            onion,
+            cursor: 0,
+        }
+    }
+
+    /// The rows shown for this option, top to bottom.
+    #[must_use]
+    pub fn rows(&self) -> Vec<EditorRow> {
+        let mut rows = Vec::new();
+        if self.key != "bind" {
+            rows.extend((0..PERMISSION_FLAGS.len()).map(EditorRow::Flag));
+        }
+        rows.push(EditorRow::Address);
+        if self.is_bind() {
+            rows.push(EditorRow::Port);
+        }
+        if self.key == "bind" {
+            rows.push(EditorRow::Onion);
+        }
+        rows
+    }
+
+    /// The row under the cursor.
+    #[must_use]
+    pub fn row(&self) -> EditorRow {
+        let rows = self.rows();
+        rows[self.cursor.min(rows.len() - 1)]
+    }
+
+    /// Moves the cursor one row up, or down, staying on the rows.
+    pub fn step(&mut self, down: bool) {
+        self.cursor = if down {
+            (self.cursor + 1).min(self.rows().len() - 1)
+        } else {
+            self.cursor.saturating_sub(1)
+        };
+    }
+
+    /// Whether the cursor is on the address row.
+    #[must_use]
+    pub fn on_address(&self) -> bool {
+        self.row() == EditorRow::Address
+    }
+
+    /// Whether the row under the cursor takes typing rather than Space.
+    #[must_use]
+    pub fn on_text(&self) -> bool {
+        matches!(self.row(), EditorRow::Address | EditorRow::Port)
+    }
+
+    /// Whether the value is an address and port to listen on.
+    #[must_use]
+    pub fn is_bind(&self) -> bool {
+        BIND_OPTIONS.contains(&self.key.as_str())
+    }
+
+    /// Picks or drops the flag, or the onion tag, under the cursor.
+    pub fn toggle(&mut self) {
+        match self.row() {
+            EditorRow::Flag(i) => {
+                let flag = PERMISSION_FLAGS[i].0;
+                if let Some(i) = self.flags.iter().position(|f| f == flag) {
+                    self.flags.remove(i);
+                } else {
+                    self.flags.push(flag.to_string());
+                }
+            }
+            EditorRow::Onion => self.onion = !self.onion,
+            EditorRow::Address | EditorRow::Port => {}
+        }
+    }
+
+    /// Types `c` into the address or port row; a port takes only digits.
+    pub fn type_char(&mut self, c: char) {
+        match self.row() {
+            EditorRow::Address => self.address.push(c),
+            EditorRow::Port if c.is_ascii_digit() => self.port.push(c),
+            _ => {}
+        }
+    }
+
+    /// Deletes the last character of the address or port row.
+    pub fn backspace(&mut self) {
+        match self.row() {
+            EditorRow::Address => {
+                self.address.pop();
+            }
+            EditorRow::Port => {
+                self.port.pop();
+            }
+            _ => {}
+        }
+    }
+
+    /// The value to write: `flags@address`, or just the address when no
+    /// flag is picked, which bitcoind reads as the `implicit` defaults.
+    /// Binds put IPv6 hosts in brackets before the port.
+    #[must_use]
+    pub fn value(&self) -> String {
+        let mut address = if self.port.is_empty() {
+            self.address.clone()
+        } else if self.address.contains(':') {
+            format!("[{}]:{}", self.address, self.port)
+        } else {
+            format!("{}:{}", self.address, self.port)
+        };
+        if self.key == "bind" && self.onion {
+            address.push_str("=onion");
+        }
+        if self.flags.is_empty() {
+            address
+        } else {
+            format!("{}@{address}", self.flags.join(","))
+        }
+    }
+
+    /// What is wrong with each component, in row order; the value is
+    /// written only when there is nothing.
+    #[must_use]
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems: Vec<String> = self
+            .flags
+            .iter()
+            .filter(|f| {
+                !PERMISSION_FLAGS.iter().any(|(known, _)| known == f)
+                    && !OTHER_FLAGS.contains(&f.as_str())
+            })
+            .map(|f| format!("{f} is not a permission flag"))
+            .collect();
+        if self.is_bind() {
+            match classify_host(self.address.trim_matches(['[', ']'])) {
+                Ok(Host::Ip) => {}
+                Ok(_) => problems.push(format!(
+                    "{} is not an IP address; {} does not look names up",
+                    self.address, self.key
+                )),
+                Err(e) => problems.push(e),
+            }
+            match self.port.parse::<u16>() {
+                Ok(p) if p > 0 => {}
+                _ if self.port.is_empty() && self.key == "bind" => {}
+                _ if self.port.is_empty() => {
+                    problems.push("whitebind needs a port, e.g. 8335".to_string());
+                }
+                _ => problems.push(format!("{} is not a port number (1-65535)", self.port)),
+            }
+        } else if let Some(e) = network_error(&self.address) {
+            problems.push(e);
+        }
+        problems
+    }
+}
+
+/// Why `network`, an IP address or a network in CIDR or netmask form,
+/// is not one `whitelist` accepts.
+fn network_error(network: &str) -> Option<String> {
+    let (ip, mask) = match network.split_once('/') {
+        Some((ip, mask)) => (ip, Some(mask)),
+        None => (network, None),
+    };
+    let ip = match ip.parse::<std::net::IpAddr>() {
+        Ok(ip) => ip,
+        Err(_) => return Some(format!("{ip:?} is not an IP address")),
+    };
+    let max = if ip.is_ipv4() { 32 } else { 128 };
+    match mask {
+        None => None,
+        Some(bits) if bits.parse::<u8>().is_ok_and(|b| b <= max) => None,
+        Some(mask) if mask.parse::<std::net::IpAddr>().is_ok() => None,
+        Some(mask) => Some(format!(
+            "/{mask} is not a prefix length (0-{max}) or netmask"
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_split_into_flags_and_address() {
+        assert_eq!(
+            parse_permissions("noban, relay@127.0.0.1"),
+            (vec!["noban", "relay"], "127.0.0.1")
+        );
+        assert_eq!(parse_permissions("10.0.0.0/8"), (vec![], "10.0.0.0/8"));
+        assert_eq!(
+            parse_permissions("in,mempool@[::1]:8335"),
+            (vec!["in", "mempool"], "[::1]:8335")
+        );
+    }
+
+    #[test]
+    fn editor_combines_flags_with_the_address() {
+        let mut editor = PermissionEditor::new(3, "whitebind", "all@0.0.0.0:8335");
+        editor.cursor = 1;
+        editor.toggle();
+        assert_eq!(editor.value(), "all,noban@0.0.0.0:8335");
+
+        let mut editor = PermissionEditor::new(3, "whitelist", "noban@192.168.1.0/24");
+        editor.cursor = 1;
+        editor.toggle();
+        assert_eq!(editor.value(), "192.168.1.0/24");
+        editor.cursor = PERMISSION_FLAGS.len();
+        assert!(editor.on_address());
+        editor.toggle();
+        assert_eq!(editor.value(), "192.168.1.0/24");
+        assert!(editor.problems().is_empty());
+        editor.address = "192.168.1.0/33".to_string();
+        assert_eq!(editor.problems().len(), 1);
+    }
+
+    #[test]
+    fn binds_split_into_host_port_and_onion_tag() {
+        let editor = PermissionEditor::new(0, "bind", "[::1]:8334=onion");
+        assert_eq!(
+            editor.rows(),
+            [EditorRow::Address, EditorRow::Port, EditorRow::Onion]
+        );
+        assert_eq!(
+            (editor.address.as_str(), editor.port.as_str(), editor.onion),
+            ("::1", "8334", true)
+        );
+        assert_eq!(editor.value(), "[::1]:8334=onion");
+        assert!(editor.problems().is_empty());
+
+        let mut editor = PermissionEditor::new(0, "bind", "0.0.0.0");
+        assert_eq!(editor.value(), "0.0.0.0");
+        assert!(editor.problems().is_empty());
+        editor.step(true);
+        editor.type_char('x');
+        editor.type_char('9');
+        editor.step(true);
+        editor.toggle();
+        assert_eq!(editor.value(), "0.0.0.0:9=onion");
+
+        let mut editor = PermissionEditor::new(0, "whitebind", "noban@node.local");
+        assert_eq!(
+            editor.problems(),
+            [
+                "node.local is not an IP address; whitebind does not look names up",
+                "whitebind needs a port, e.g. 8335",
+            ]
+        );
+        editor.address = "127.0.0.1".to_string();
+        editor.port = "70000".to_string();
+        editor.flags.push("nope".to_string());
+        assert_eq!(
+            editor.problems(),
+            [
+                "nope is not a permission flag",
+                "70000 is not a port number (1-65535)",
+            ]
+        );
+    }
+}
@@ -0,0 +1,354 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{Receiver, SyncSender, TryRecvError, TrySendError, channel, sync_channel};
+
+/// Lines a plugin's screen keeps; the rest of a longer screen is dropped.
+pub const MAX_LINES: usize = 500;
+
+/// Messages waiting for a plugin to read them; a plugin further behind is
+/// stopped rather than left to block the UI.
+pub const INPUT_BACKLOG: usize = 64;
+
+/// A program adding a screen to PDM, set in a `[[plugins]]` table of
+/// settings.toml. PDM starts it with `sh -c` and talks to it in JSON, one
+/// object per line.
+///
+/// PDM writes to its stdin:
+///   - `{"event": "new_block", "detail": "<hash>"}` for each hook event
+///   - `{"event": "key", "detail": "x"}` for keys pressed on its screen
+///   - `{"id": 1, "result": ...}` or `{"id": 1, "error": "..."}` answering
+///     a call
+///
+/// and reads from its stdout:
+///   - `{"lines": ["...", ...]}` replacing the text of its screen
+///   - `{"id": 1, "method": "getblockcount", "params": []}` calling
+///     bitcoind with the credentials of the loaded bitcoin.conf
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PluginConfig {
+    /// Name on the plugin's tab
+    pub name: String,
+    pub command: String,
+    /// bitcoind RPC methods the plugin may call; none when empty
+    #[serde(default)]
+    pub rpc_methods: Vec<String>,
+}
+
+/// A bitcoind call a plugin asked for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RpcCall {
+    pub id: u64,
+    pub method: String,
+    pub params: Vec<Value>,
+}
+
+/// One line a plugin wrote.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PluginMessage {
+    /// New text for its screen
+    Render(Vec<String>),
+    Call(RpcCall),
+}
+
+/// Reads one line of a plugin's output.
+///
+/// # Errors
+/// Returns what is wrong with the line.
+pub fn parse_message(line: &str) -> Result<PluginMessage, String> {
+    let message: Value = serde_json::from_str(line).map_err(|e| format!("not JSON: {e}"))?;
+    if let Some(lines) = message.get("lines").and_then(Value::as_array) {
+        let lines = lines
+            .iter()
+            .take(MAX_LINES)
+            .map(|l| l.as_str().map_or_else(|| l.to_string(), str::to_string))
+            .collect();
+        return Ok(PluginMessage::Render(lines));
+    }
+    let (Some(id), Some(method)) = (
+        message.get("id").and_then(Value::as_u64),
+        message.get("method").and_then(Value::as_str),
+    ) else {
+        return Err("neither lines nor a call with an id and a method".to_string());
+    };
+    let params = match message.get("params") {
+        None | Some(Value::Null) => Vec::new(),
+        Some(Value::Array(params)) => params.clone(),
+        Some(_) => return Err(format!("params of {method} are not a list")),
+    };
+    Ok(PluginMessage::Call(RpcCall {
+        id,
+        method: method.to_string(),
+        params,
+    }))
+}
+
+/// The line telling a plugin about `event`.
+#[must_use]
+pub fn event_message(event: &str, detail: &str) -> String {
+    json!({"event": event, "detail": detail}).to_string()
+}
+
+/// The line answering call `id`.
+#[must_use]
+pub fn reply_message(id: u64, result: Result<Value, String>) -> String {
+    match result {
+        Ok(result) => json!({"id": id, "result": result}),
+        Err(error) => json!({"id": id, "error": error}),
+    }
+    .to_string()
+}
+
+/// The running program, with its input written and its output read by
+/// threads.
+#[derive(Debug)]
+struct Process {
+    child: Child,
+    input: SyncSender<String>,
+    output: Receiver<String>,
+}
+
+/// A plugin and what its screen shows.
+#[derive(Debug)]
+pub struct Plugin {
+    pub config: PluginConfig,
+    pub lines: Vec<String>,
+    /// Why it did not start, stopped or wrote something unreadable
+    pub error: Option<String>,
+    process: Option<Process>,
+}
+
+impl Plugin {
+    /// Starts the program of `config`; a plugin that does not start shows
+    /// why on its screen.
+    #[must_use]
+    pub fn start(config: PluginConfig) -> Self {
+        let mut plugin = Self {
+            config,
+            lines: Vec::new(),
+            error: None,
+            process: None,
+        };
+        match Command::new("sh")
+            .args(["-c", &plugin.config.command])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(mut child) => {
+                let (Some(mut stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take())
+                else {
+                    plugin.error = Some("no pipes to the plugin".to_string());
+                    return plugin;
+                };
+                let (input, messages) = sync_channel::<String>(INPUT_BACKLOG);
+                std::thread::spawn(move || {
+                    for message in messages {
+                        if writeln!(stdin, "{message}")
+                            .and_then(|()| stdin.flush())
+                            .is_err()
+                        {
+                            break;
+                        }
+                    }
+                });
+                let (sender, output) = channel();
+                std::thread::spawn(move || {
+                    for line in BufReader::new(stdout).lines() {
+                        let Ok(line) = line else { break };
+                        if sender.send(line).is_err() {
+                            break;
+                        }
+                    }
+                });
+                plugin.process = Some(Process {
+                    child,
+                    input,
+                    output,
+                });
+            }
+            Err(e) => plugin.error = Some(format!("did not start: {e}")),
+        }
+        plugin
+    }
+
+    /// Whether the program is still running.
+    #[must_use]
+    pub fn running(&self) -> bool {
+        self.process.is_some()
+    }
+
+    /// Queues `message` for the program without waiting on it; one that
+    /// no longer reads, or is [`INPUT_BACKLOG`] messages behind, is
+    /// stopped.
+    pub fn send(&mut self, message: &str) {
+        let Some(process) = &self.process else {
+            return;
+        };
+        match process.input.try_send(message.to_string()) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => self.stop("fell behind reading its input"),
+            Err(TrySendError::Disconnected(_)) => self.stop("stopped reading its input"),
+        }
+    }
+
+    /// Takes what the program wrote since the last poll: new screen text
+    /// is kept, calls of allowed methods are returned and the others
+    /// refused.
+    pub fn poll(&mut self) -> Vec<RpcCall> {
+        let mut calls = Vec::new();
+        let mut refused = Vec::new();
+        let Some(process) = &mut self.process else {
+            return calls;
+        };
+        let exited = loop {
+            match process.output.try_recv() {
+                Ok(line) => match parse_message(&line) {
+                    Ok(PluginMessage::Render(lines)) => self.lines = lines,
+                    Ok(PluginMessage::Call(call))
+                        if self.config.rpc_methods.contains(&call.method) =>
+                    {
+                        calls.push(call);
+                    }
+                    Ok(PluginMessage::Call(call)) => refused.push(call),
+                    Err(e) => self.error = Some(format!("unreadable output: {e}")),
+                },
+                Err(TryRecvError::Empty) => break false,
+                Err(TryRecvError::Disconnected) => break true,
+            }
+        };
+        for call in refused {
+            let error = format!("{} is not in the plugin's rpc_methods", call.method);
+            self.send(&reply_message(call.id, Err(error)));
+        }
+        if exited {
+            self.stop("exited");
+        }
+        calls
+    }
+
+    fn stop(&mut self, why: &str) {
+        if let Some(mut process) = self.process.take() {
+            let _ = process.child.kill();
+            let _ = process.child.wait();
+        }
+        self.error = Some(why.to_string());
+    }
+}
+
+impl Drop for Plugin {
+    fn drop(&mut self) {
+        if let Some(process) = &mut self.process {
+            let _ = process.child.kill();
+            let _ = process.child.wait();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn plugins_render_lines_and_call_bitcoind() {
+        assert_eq!(
+            parse_message(r#"{"lines": ["Channels: 3", 7]}"#),
+            Ok(PluginMessage::Render(vec![
+                "Channels: 3".to_string(),
+                "7".to_string()
+            ]))
+        );
+        assert_eq!(
+            parse_message(r#"{"id": 4, "method": "getblock", "params": ["00ab", 1]}"#),
+            Ok(PluginMessage::Call(RpcCall {
+                id: 4,
+                method: "getblock".to_string(),
+                params: vec![json!("00ab"), json!(1)],
+            }))
+        );
+        assert!(parse_message(r#"{"id": 4, "method": "getblock", "params": 1}"#).is_err());
+        assert!(parse_message(r#"{"method": "getblockcount"}"#).is_err());
+        assert!(parse_message("hello").is_err());
+        assert_eq!(
+            reply_message(4, Err("no".to_string())),
+            r#"{"error":"no","id":4}"#
+        );
+        assert_eq!(
+            event_message("new_block", "00ab"),
+            r#"{"detail":"00ab","event":"new_block"}"#
+        );
+    }
+
+    #[test]
+    fn plugin_answers_events_and_only_allowed_calls_pass() {
+        // Echoes each event as its screen, then asks for two calls
+        let script = r#"
+            echo '{"id": 1, "method": "stop"}'
+            echo '{"id": 2, "method": "getblockcount"}'
+            while read -r line; do echo "{\"lines\": [$(printf '%s' "$line" | sed 's/"/\\"/g; s/.*/"&"/')]}"; done
+        "#;
+        let mut plugin = Plugin::start(PluginConfig {
+            name: "echo".to_string(),
+            command: script.to_string(),
+            rpc_methods: vec!["getblockcount".to_string()],
+        });
+        assert!(plugin.running());
+        let start = Instant::now();
+        let mut calls = Vec::new();
+        while plugin.lines.is_empty() {
+            assert!(start.elapsed() < Duration::from_secs(5), "plugin silent");
+            calls.extend(plugin.poll());
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].method, "getblockcount");
+        // The refusal of `stop` was the first thing the plugin read
+        assert_eq!(
+            plugin.lines,
+            [r#"{"error":"stop is not in the plugin's rpc_methods","id":1}"#]
+        );
+        plugin.send(&event_message("key", "r"));
+        while plugin.lines[0].contains("error") {
+            assert!(start.elapsed() < Duration::from_secs(5), "event not echoed");
+            plugin.poll();
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(plugin.lines, [r#"{"detail":"r","event":"key"}"#]);
+
+        let mut done = Plugin::start(PluginConfig {
+            command: "exit 0".to_string(),
+            ..PluginConfig::default()
+        });
+        let start = Instant::now();
+        while done.running() {
+            assert!(start.elapsed() < Duration::from_secs(5), "exit not noticed");
+            done.poll();
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(done.error.as_deref(), Some("exited"));
+    }
+
+    #[test]
+    fn plugin_that_does_not_read_is_stopped_without_blocking() {
+        let mut plugin = Plugin::start(PluginConfig {
+            command: "sleep 30".to_string(),
+            ..PluginConfig::default()
+        });
+        let event = event_message("new_block", &"00".repeat(2048));
+        let start = Instant::now();
+        while plugin.running() {
+            assert!(start.elapsed() < Duration::from_secs(5), "send blocked");
+            plugin.send(&event);
+        }
+        assert_eq!(
+            plugin.error.as_deref(),
+            Some("fell behind reading its input")
+        );
+    }
+}
@@ -0,0 +1,365 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_config::{ConfigEntry, active_network, enabled_value};
+use crate::diagnostics::{listeners, ports_in_use};
+use crate::fd_limit::{fd_budget, process_limits};
+use crate::indexes::{INDEXES, enables};
+use crate::memory_budget::{memory_model, parse_meminfo};
+use crate::prune_target::{
+    CHAINSTATE_GB, DEFAULT_MARGIN_GB, MIN_PRUNE_MIB, blocks_dir, blocks_usage, chain_size_gb,
+    free_space,
+};
+use crate::rpc::net_datadir;
+use std::path::Path;
+use std::process::Command;
+
+const GB: u64 = 1_000_000_000;
+const MIB: u64 = 1024 * 1024;
+
+/// Outcome of one pre-flight check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl CheckStatus {
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            CheckStatus::Pass => "PASS",
+            CheckStatus::Warn => "WARN",
+            CheckStatus::Fail => "FAIL",
+        }
+    }
+}
+
+/// One host check run before starting bitcoind.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreflightCheck {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+impl PreflightCheck {
+    fn new(name: &'static str, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Bytes the block files, chainstate and indexes of `entries` grow to.
+#[must_use]
+pub fn disk_estimate(entries: &[ConfigEntry]) -> u64 {
+    let chain = active_network(entries);
+    let prune = enabled_value(entries, "prune")
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .filter(|mib| *mib >= MIN_PRUNE_MIB);
+    let indexes: u64 = INDEXES
+        .iter()
+        .filter(|i| enabled_value(entries, i.key).is_some_and(enables))
+        .map(|i| {
+            if chain == "main" {
+                u64::from(i.mainnet_gb)
+            } else {
+                1
+            }
+        })
+        .sum();
+    let blocks = match prune {
+        Some(mib) => mib * MIB,
+        None => chain_size_gb(chain) * GB,
+    };
+    blocks + (CHAINSTATE_GB + indexes) * GB
+}
+
+/// Judges `free` bytes against the `needed` ones, of which `used` are
+/// already on disk.
+#[must_use]
+pub fn disk_check(needed: u64, used: u64, free: Result<u64, String>) -> PreflightCheck {
+    let name = "Disk space";
+    let free = match free {
+        Ok(free) => free,
+        Err(e) => return PreflightCheck::new(name, CheckStatus::Warn, e),
+    };
+    let missing = needed.saturating_sub(used);
+    let detail = format!(
+        "{} GB free, {} GB more needed",
+        free / GB,
+        missing.div_ceil(GB)
+    );
+    let status = if free >= missing + DEFAULT_MARGIN_GB * GB {
+        CheckStatus::Pass
+    } else if free >= missing {
+        CheckStatus::Warn
+    } else {
+        CheckStatus::Fail
+    };
+    PreflightCheck::new(name, status, detail)
+}
+
+/// Judges the expected peak of `needed` bytes against the total and
+/// available memory, and against `share_pct` percent of the total.
+#[must_use]
+pub fn memory_check(needed: u64, memory: Option<(u64, u64)>, share_pct: u64) -> PreflightCheck {
+    let name = "Memory";
+    let Some((total, available)) = memory else {
+        return PreflightCheck::new(name, CheckStatus::Warn, "cannot read /proc/meminfo");
+    };
+    let detail = format!(
+        "bitcoind may peak at {} MiB; {} MiB available of {} MiB",
+        needed / MIB,
+        available / MIB,
+        total / MIB
+    );
+    let status = if needed > total {
+        CheckStatus::Fail
+    } else if needed > available || needed > total / 100 * share_pct {
+        CheckStatus::Warn
+    } else {
+        CheckStatus::Pass
+    };
+    PreflightCheck::new(name, status, detail)
+}
+
+/// Judges what `timedatectl` says about NTP synchronization.
+#[must_use]
+pub fn clock_check(synchronized: Option<bool>) -> PreflightCheck {
+    let name = "Clock";
+    match synchronized {
+        Some(true) => PreflightCheck::new(name, CheckStatus::Pass, "synchronized with NTP"),
+        Some(false) => PreflightCheck::new(
+            name,
+            CheckStatus::Warn,
+            "not synchronized with NTP; bitcoind rejects blocks over 2 hours ahead of it",
+        ),
+        None => PreflightCheck::new(
+            name,
+            CheckStatus::Warn,
+            "cannot tell whether the clock is synchronized (no timedatectl)",
+        ),
+    }
+}
+
+/// Judges the open file limits against what `entries` need.
+#[must_use]
+pub fn ulimit_check(entries: &[ConfigEntry], limits: Option<(u64, u64)>) -> PreflightCheck {
+    let name = "Open files";
+    let needed = fd_budget(entries).total();
+    let Some((soft, hard)) = limits else {
+        return PreflightCheck::new(name, CheckStatus::Warn, "cannot read the open file limit");
+    };
+    if hard < needed {
+        PreflightCheck::new(
+            name,
+            CheckStatus::Fail,
+            format!(
+                "{needed} needed but the hard limit is {hard}; raise it with ulimit -n {needed}"
+            ),
+        )
+    } else {
+        PreflightCheck::new(
+            name,
+            CheckStatus::Pass,
+            format!("{needed} needed; limit {soft}, raised by bitcoind up to {hard}"),
+        )
+    }
+}
+
+/// Judges whether bitcoind can create and write `dir`, and whether
+/// other users can read it.
+#[must_use]
+pub fn datadir_check(dir: &Path) -> PreflightCheck {
+    let name = "Data directory";
+    let shown = dir.display();
+    if !dir.exists() {
+        let parent = dir.ancestors().find(|p| p.exists());
+        return match parent {
+            Some(parent) if is_writable(parent) => {
+                PreflightCheck::new(name, CheckStatus::Pass, format!("{shown} will be created"))
+            }
+            _ => PreflightCheck::new(
+                name,
+                CheckStatus::Fail,
+                format!("{shown} does not exist and cannot be created"),
+            ),
+        };
+    }
+    if !dir.is_dir() {
+        return PreflightCheck::new(
+            name,
+            CheckStatus::Fail,
+            format!("{shown} is not a directory"),
+        );
+    }
+    if !is_writable(dir) {
+        return PreflightCheck::new(name, CheckStatus::Fail, format!("{shown} is not writable"));
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = dir.metadata().map(|m| m.permissions().mode()).unwrap_or(0);
+        if mode & 0o007 != 0 {
+            return PreflightCheck::new(
+                name,
+                CheckStatus::Warn,
+                format!(
+                    "{shown} is open to other users (mode {:o}); chmod o-rwx",
+                    mode & 0o777
+                ),
+            );
+        }
+    }
+    PreflightCheck::new(name, CheckStatus::Pass, format!("{shown} is writable"))
+}
+
+/// Cores of this machine, as script verification threads count them.
+#[must_use]
+pub fn cores() -> u64 {
+    std::thread::available_parallelism().map_or(1, |n| n.get() as u64)
+}
+
+/// Whether a file can be created in `dir`.
+#[must_use]
+pub fn is_writable(dir: &Path) -> bool {
+    let probe = dir.join(format!(".pdm-preflight-{}", std::process::id()));
+    let created = std::fs::write(&probe, b"").is_ok();
+    let _ = std::fs::remove_file(&probe);
+    created
+}
+
+/// Runs every check for `entries` against this machine; `proc_dir` is
+/// where memory and the open file limits are read from, and
+/// `memory_share_pct` the share of RAM bitcoind may take.
+#[must_use]
+pub fn run_checks(
+    entries: &[ConfigEntry],
+    proc_dir: &Path,
+    memory_share_pct: u64,
+) -> Vec<PreflightCheck> {
+    let chain = active_network(entries);
+    let blocks = blocks_dir(entries, chain);
+    let free = free_space(&blocks).map_err(|e| e.to_string());
+    let meminfo = std::fs::read_to_string(proc_dir.join("meminfo")).ok();
+    let synchronized = Command::new("timedatectl")
+        .args(["show", "--property=NTPSynchronized", "--value"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim() == "yes");
+    let taken = ports_in_use(entries);
+    let ports = if taken.is_empty() {
+        let mut ports: Vec<u16> = listeners(entries).iter().map(|l| l.port).collect();
+        ports.sort_unstable();
+        ports.dedup();
+        let ports: Vec<String> = ports.iter().map(u16::to_string).collect();
+        PreflightCheck::new(
+            "Ports",
+            CheckStatus::Pass,
+            format!("{} free", ports.join(", ")),
+        )
+    } else {
+        let taken: Vec<String> = taken
+            .iter()
+            .map(|d| format!("{} ({})", d.message, d.key))
+            .collect();
+        PreflightCheck::new("Ports", CheckStatus::Fail, taken.join("; "))
+    };
+    vec![
+        disk_check(disk_estimate(entries), blocks_usage(&blocks), free),
+        memory_check(
+            memory_model(entries, cores()).total(),
+            meminfo.as_deref().and_then(parse_meminfo),
+            memory_share_pct,
+        ),
+        clock_check(synchronized),
+        ports,
+        ulimit_check(entries, process_limits(proc_dir, None)),
+        datadir_check(&net_datadir(entries, chain)),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitcoin_config::{NodeFlavor, parse_config_str};
+
+    #[test]
+    fn disk_and_memory_are_judged_against_the_config() {
+        let pruned = parse_config_str("prune=10000\ndbcache=1000\n", NodeFlavor::Core).unwrap();
+        let needed = disk_estimate(&pruned);
+        assert_eq!(needed, 10_000 * MIB + CHAINSTATE_GB * GB);
+        assert_eq!(
+            disk_check(needed, 0, Ok(100 * GB)).status,
+            CheckStatus::Pass
+        );
+        assert_eq!(disk_check(needed, 0, Ok(30 * GB)).status, CheckStatus::Warn);
+        assert_eq!(disk_check(needed, 0, Ok(10 * GB)).status, CheckStatus::Fail);
+        assert_eq!(
+            disk_check(needed, needed, Ok(30 * GB)).status,
+            CheckStatus::Pass
+        );
+        assert_eq!(
+            disk_check(needed, 0, Err("df".into())).status,
+            CheckStatus::Warn
+        );
+
+        let full = parse_config_str("txindex=1\n", NodeFlavor::Core).unwrap();
+        assert_eq!(disk_estimate(&full), (720 + 15 + 60) * GB);
+
+        let ram = 2 << 30;
+        let check = |memory| memory_check(ram, memory, 80).status;
+        assert_eq!(check(Some((8 << 30, 4 << 30))), CheckStatus::Pass);
+        assert_eq!(check(Some((8 << 30, 1 << 30))), CheckStatus::Warn);
+        assert_eq!(check(Some((9 << 28, 9 << 28))), CheckStatus::Warn);
+        assert_eq!(check(Some((1 << 30, 1 << 30))), CheckStatus::Fail);
+        assert_eq!(check(None), CheckStatus::Warn);
+    }
+
+    #[test]
+    fn host_facts_are_read_and_judged() {
+        let meminfo =
+            "MemTotal:        8000000 kB\nMemFree:  100 kB\nMemAvailable:    4000000 kB\n";
+        assert_eq!(
+            parse_meminfo(meminfo),
+            Some((8_000_000 * 1024, 4_000_000 * 1024))
+        );
+        assert_eq!(parse_meminfo("MemTotal: 1 kB\n"), None);
+        assert_eq!(clock_check(Some(true)).status, CheckStatus::Pass);
+        assert_eq!(clock_check(None).status, CheckStatus::Warn);
+
+        let entries = parse_config_str("maxconnections=1000\n", NodeFlavor::Core).unwrap();
+        assert_eq!(
+            ulimit_check(&entries, Some((1024, 4096))).status,
+            CheckStatus::Pass
+        );
+        assert_eq!(
+            ulimit_check(&entries, Some((1024, 1024))).status,
+            CheckStatus::Fail
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(
+            datadir_check(&dir.path().join("new/bitcoin")).status,
+            CheckStatus::Pass
+        );
+        let file = dir.path().join("file");
+        std::fs::write(&file, "").unwrap();
+        assert_eq!(datadir_check(&file).status, CheckStatus::Fail);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(dir.path(), std::fs::Permissions::from_mode(0o755)).unwrap();
+            assert_eq!(datadir_check(dir.path()).status, CheckStatus::Warn);
+            std::fs::set_permissions(dir.path(), std::fs::Permissions::from_mode(0o700)).unwrap();
+            assert_eq!(datadir_check(dir.path()).status, CheckStatus::Pass);
+        }
+    }
+}
@@ -0,0 +1,417 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_config::{ConfigCategory, ConfigEntry};
+use crate::cookie::COOKIE_OPTIONS;
+use crate::diagnostics::{Host, classify_host, split_host_port, zmq_error};
+use crate::indexes::index_for;
+use crate::nat::NAT_OPTIONS;
+use crate::notify_scripts::NOTIFY_OPTIONS;
+use serde_json::Value;
+use std::io::{ErrorKind, Read};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Options that decide whether other nodes can connect to this one.
+pub const REACHABILITY_OPTIONS: &[&str] = &[
+    "bind",
+    "discover",
+    "externalip",
+    "listen",
+    "natpmp",
+    "port",
+    "upnp",
+];
+
+/// Options naming peers to connect to.
+pub const PEER_OPTIONS: &[&str] = &["addnode", "connect", "seednode"];
+
+/// Checks PDM can run against the running system for an option.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LiveTest {
+    /// Whether a `zmqpub*` endpoint can be bound
+    ZmqEndpoint,
+    /// Whether bitcoind answers RPC calls with the configured credentials
+    RpcConnection,
+    /// Whether the P2P port is reachable from the internet
+    P2pReachability,
+    /// How far bitcoind has built an index
+    IndexProgress,
+    /// Whether the peers named by `addnode`, `connect` or `seednode` resolve and accept connections
+    PeerEndpoints,
+    /// Whether a `*notify` command runs cleanly with a dummy argument
+    NotifyScript,
+    /// Where the RPC cookie is and whether it is readable, private and current
+    CookieFile,
+    /// Whether the router mapped the P2P port for `upnp` or `natpmp`
+    PortMapping,
+    /// How far the clock is from the peers' or NTP's
+    ClockDrift,
+}
+
+impl LiveTest {
+    /// Status bar label of the test.
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            LiveTest::ZmqEndpoint => "Test endpoint",
+            LiveTest::RpcConnection => "Test connection",
+            LiveTest::P2pReachability => "Test reachability",
+            LiveTest::PeerEndpoints => "Ping peers",
+            LiveTest::IndexProgress => "Index progress",
+            LiveTest::NotifyScript => "Test fire",
+            LiveTest::CookieFile => "Inspect cookie",
+            LiveTest::PortMapping => "Verify mapping",
+            LiveTest::ClockDrift => "Check clock",
+        }
+    }
+}
+
+/// The live test for `entry`, if it has one.
+#[must_use]
+pub fn live_test(entry: &ConfigEntry) -> Option<LiveTest> {
+    if entry.key.starts_with("zmqpub") {
+        return Some(LiveTest::ZmqEndpoint);
+    }
+    if index_for(&entry.key).is_some() {
+        return Some(LiveTest::IndexProgress);
+    }
+    if PEER_OPTIONS.contains(&entry.key.as_str()) {
+        return Some(LiveTest::PeerEndpoints);
+    }
+    if entry.key == "maxtimeadjustment" {
+        return Some(LiveTest::ClockDrift);
+    }
+    if NAT_OPTIONS.contains(&entry.key.as_str()) {
+        return Some(LiveTest::PortMapping);
+    }
+    if REACHABILITY_OPTIONS.contains(&entry.key.as_str()) {
+        return Some(LiveTest::P2pReachability);
+    }
+    if NOTIFY_OPTIONS.contains(&entry.key.as_str()) {
+        return Some(LiveTest::NotifyScript);
+    }
+    if COOKIE_OPTIONS.contains(&entry.key.as_str()) {
+        return Some(LiveTest::CookieFile);
+    }
+    match entry.schema.as_ref()?.category {
+        ConfigCategory::RPC => Some(LiveTest::RpcConnection),
+        _ => None,
+    }
+}
+
+/// Reads the ZMTP greeting a ZMQ socket sends on connect: `0xFF`, eight
+/// padding bytes and `0x7F`.
+fn is_zmq_socket(host: &str, port: u16, timeout: Duration) -> bool {
+    let Some(addr) = (host, port)
+        .to_socket_addrs()
+        .ok()
+        .and_then(|mut a| a.next())
+    else {
+        return false;
+    };
+    let Ok(mut stream) = TcpStream::connect_timeout(&addr, timeout) else {
+        return false;
+    };
+    let _ = stream.set_read_timeout(Some(timeout));
+    let mut greeting = [0u8; 10];
+    stream.read_exact(&mut greeting).is_ok() && greeting[0] == 0xFF && greeting[9] == 0x7F
+}
+
+/// Checks a `zmqpub*` endpoint before bitcoind restarts with it: that it is
+/// well-formed and that bitcoind can bind it, or that a ZMQ publisher
+/// (normally bitcoind itself) already has.
+///
+/// # Errors
+/// Returns why bitcoind would fail to publish on `value`.
+pub fn test_zmq_endpoint(value: &str, timeout: Duration) -> Result<String, String> {
+    if let Some(e) = zmq_error(value) {
+        return Err(e);
+    }
+    if let Some(path) = value.strip_prefix("ipc://") {
+        let path = Path::new(path);
+        if path.exists() {
+            return Ok(format!(
+                "{} exists, bitcoind is probably publishing",
+                path.display()
+            ));
+        }
+        return match path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            Some(dir) if !dir.is_dir() => Err(format!("{} does not exist", dir.display())),
+            Some(dir) if dir.metadata().is_ok_and(|m| m.permissions().readonly()) => {
+                Err(format!("{} is read-only", dir.display()))
+            }
+            _ => Ok(format!("bitcoind can create {}", path.display())),
+        };
+    }
+    let endpoint = value.strip_prefix("tcp://").unwrap_or(value);
+    let (host, port) = match split_host_port(endpoint)? {
+        (host, Some(port)) => (host, port),
+        (_, None) => return Err("tcp:// needs a port".to_string()),
+    };
+    let (bind_host, connect_host) = if host == "*" {
+        ("0.0.0.0", "127.0.0.1")
+    } else {
+        (host, host)
+    };
+    match TcpListener::bind((bind_host, port)) {
+        Ok(_) => Ok(format!("{host}:{port} is free for bitcoind to bind")),
+        Err(e) if e.kind() == ErrorKind::AddrInUse => {
+            if is_zmq_socket(connect_host, port, timeout) {
+                Ok(format!(
+                    "a ZMQ publisher, likely bitcoind, is already bound to {host}:{port}"
+                ))
+            } else {
+                Err(format!("port {port} is taken by something that is not ZMQ"))
+            }
+        }
+        Err(e) if e.kind() == ErrorKind::AddrNotAvailable => {
+            Err(format!("{host} is not an address of this machine"))
+        }
+        Err(e) if e.kind() == ErrorKind::PermissionDenied => Err(format!(
+            "not allowed to bind port {port}; ports below 1024 need root"
+        )),
+        Err(e) => Err(format!("cannot bind {host}:{port}: {e}")),
+    }
+}
+
+/// Judges from bitcoind's `getpeerinfo` whether its P2P `port` is
+/// reachable from the internet. Only inbound peers on IPv4 or IPv6 count:
+/// Tor, I2P and local peers come in through other paths. The `addrlocal`
+/// the peers report is how they see this node.
+///
+/// # Errors
+/// Returns why the port looks unreachable, or why it cannot be told yet.
+pub fn p2p_reachability(peers: &Value, port: u16) -> Result<String, String> {
+    let peers = peers
+        .as_array()
+        .ok_or_else(|| "getpeerinfo did not return a list".to_string())?;
+    if peers.is_empty() {
+        return Err("bitcoind has no peers yet; try again once it is connected".to_string());
+    }
+    let inbound = peers
+        .iter()
+        .filter(|p| p.get("inbound").and_then(Value::as_bool) == Some(true))
+        .filter(|p| {
+            matches!(
+                p.get("network").and_then(Value::as_str),
+                Some("ipv4" | "ipv6")
+            )
+        })
+        .count();
+    let mut seen_as: Vec<&str> = peers
+        .iter()
+        .filter_map(|p| p.get("addrlocal").and_then(Value::as_str))
+        .collect();
+    seen_as.sort_unstable();
+    seen_as.dedup();
+    let seen_as = match seen_as.as_slice() {
+        [] => String::new(),
+        addrs => format!("; peers see this node as {}", addrs.join(", ")),
+    };
+    if inbound > 0 {
+        Ok(format!(
+            "port {port} is reachable: {inbound} of {} peers connected in{seen_as}",
+            peers.len()
+        ))
+    } else {
+        Err(format!(
+            "no inbound peers among {}; port {port} is probably not reachable from the \
+             internet, check listen=1 and the router's port forwarding or upnp/natpmp{seen_as}",
+            peers.len()
+        ))
+    }
+}
+
+/// Outcome of resolving and connecting to one peer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PeerStatus {
+    /// Accepted a TCP connection after this long
+    Reachable(Duration),
+    /// Onion and I2P peers are only reachable through the proxy
+    Skipped(String),
+    /// DNS resolution or the connection failed
+    Failed(String),
+}
+
+/// Result of testing one `addnode`, `connect` or `seednode` value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerCheck {
+    pub value: String,
+    pub status: PeerStatus,
+}
+
+/// Resolves the peer `value` names and opens a TCP connection to it, on
+/// `default_port` unless the value has its own port.
+#[must_use]
+pub fn check_peer(value: &str, default_port: u16, timeout: Duration) -> PeerCheck {
+    let status = match peer_status(value, default_port, timeout) {
+        Ok(status) => status,
+        Err(e) => PeerStatus::Failed(e),
+    };
+    PeerCheck {
+        value: value.to_string(),
+        status,
+    }
+}
+
+fn peer_status(value: &str, default_port: u16, timeout: Duration) -> Result<PeerStatus, String> {
+    let (host, port) = split_host_port(value)?;
+    let port = port.unwrap_or(default_port);
+    match classify_host(host)? {
+        Host::Onion => {
+            return Ok(PeerStatus::Skipped(
+                "onion peer, reached through Tor".into(),
+            ));
+        }
+        Host::I2p => {
+            return Ok(PeerStatus::Skipped(
+                "I2P peer, reached through the SAM proxy".into(),
+            ));
+        }
+        Host::Ip | Host::Name => {}
+    }
+    let addrs: Vec<_> = (host, port)
+        .to_socket_addrs()
+        .map_err(|e| format!("DNS lookup failed: {e}"))?
+        .collect();
+    if addrs.is_empty() {
+        return Err(format!("{host} has no addresses"));
+    }
+    let mut last = None;
+    for addr in addrs {
+        let start = Instant::now();
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(_) => return Ok(PeerStatus::Reachable(start.elapsed())),
+            Err(e) => last = Some(format!("{addr}: {e}")),
+        }
+    }
+    Err(last.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const TIMEOUT: Duration = Duration::from_millis(200);
+
+    fn free_port() -> u16 {
+        TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port()
+    }
+
+    #[test]
+    fn malformed_endpoints_are_rejected() {
+        assert!(test_zmq_endpoint("127.0.0.1:28332", TIMEOUT).is_err());
+        assert!(test_zmq_endpoint("tcp://127.0.0.1", TIMEOUT).is_err());
+        assert!(test_zmq_endpoint("tcp://127.0.0.1:99999", TIMEOUT).is_err());
+    }
+
+    #[test]
+    fn free_port_is_bindable() {
+        let port = free_port();
+        let msg = test_zmq_endpoint(&format!("tcp://127.0.0.1:{port}"), TIMEOUT).unwrap();
+        assert!(msg.contains("free"), "{msg}");
+    }
+
+    #[test]
+    fn port_held_by_zmq_publisher_is_accepted() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream
+                .write_all(&[0xFF, 0, 0, 0, 0, 0, 0, 0, 1, 0x7F])
+                .unwrap();
+        });
+
+        let msg = test_zmq_endpoint(&format!("tcp://127.0.0.1:{port}"), TIMEOUT).unwrap();
+        assert!(msg.contains("already bound"), "{msg}");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn port_held_by_something_else_is_reported() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let err = test_zmq_endpoint(&format!("tcp://127.0.0.1:{port}"), TIMEOUT).unwrap_err();
+        assert!(err.contains("not ZMQ"), "{err}");
+    }
+
+    #[test]
+    fn foreign_address_is_reported() {
+        // TEST-NET-3, never assigned to a local interface
+        let err = test_zmq_endpoint("tcp://203.0.113.7:28332", TIMEOUT).unwrap_err();
+        assert!(err.contains("not an address of this machine"), "{err}");
+    }
+
+    #[test]
+    fn reachability_counts_clearnet_inbound_peers() {
+        let peers = serde_json::json!([
+            {"inbound": false, "network": "ipv4", "addrlocal": "198.51.100.4:8333"},
+            {"inbound": true, "network": "onion"},
+        ]);
+        let err = p2p_reachability(&peers, 8333).unwrap_err();
+        assert!(
+            err.starts_with("no inbound peers among 2; port 8333"),
+            "{err}"
+        );
+        assert!(
+            err.ends_with("peers see this node as 198.51.100.4:8333"),
+            "{err}"
+        );
+
+        let peers = serde_json::json!([
+            {"inbound": true, "network": "ipv6"},
+            {"inbound": false, "network": "ipv4"},
+        ]);
+        assert_eq!(
+            p2p_reachability(&peers, 8333).unwrap(),
+            "port 8333 is reachable: 1 of 2 peers connected in"
+        );
+
+        let err = p2p_reachability(&serde_json::json!([]), 8333).unwrap_err();
+        assert!(err.contains("no peers yet"), "{err}");
+    }
+
+    #[test]
+    fn peers_are_resolved_and_connected() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let check = check_peer("127.0.0.1", port, TIMEOUT);
+        assert_eq!(check.value, "127.0.0.1");
+        assert!(matches!(check.status, PeerStatus::Reachable(_)));
+        drop(listener);
+
+        let closed = check_peer(&format!("localhost:{}", free_port()), 8333, TIMEOUT);
+        assert!(matches!(closed.status, PeerStatus::Failed(_)), "{closed:?}");
+
+        let onion = format!("{}.onion", "a".repeat(56));
+        assert!(matches!(
+            check_peer(&onion, 8333, TIMEOUT).status,
+            PeerStatus::Skipped(_)
+        ));
+        assert!(matches!(
+            check_peer("bad host:8333", 8333, TIMEOUT).status,
+            PeerStatus::Failed(_)
+        ));
+    }
+
+    #[test]
+    fn ipc_socket_needs_an_existing_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let ok = format!("ipc://{}", dir.path().join("bitcoind.zmq").display());
+        assert!(test_zmq_endpoint(&ok, TIMEOUT).is_ok());
+
+        let missing = format!("ipc://{}", dir.path().join("nope/bitcoind.zmq").display());
+        let err = test_zmq_endpoint(&missing, TIMEOUT).unwrap_err();
+        assert!(err.contains("does not exist"), "{err}");
+    }
+}
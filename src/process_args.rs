@@ -0,0 +1,223 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_config::ConfigEntry;
+use crate::diagnostics::Diagnostic;
+use crate::rpc::default_datadir;
+use std::path::{Path, PathBuf};
+
+/// Where running processes are listed on Linux.
+pub const PROC_DIR: &str = "/proc";
+
+/// Arguments that pick which config file is read rather than set an
+/// option in it.
+const FILE_ARGS: &[&str] = &["conf", "includeconf"];
+
+/// A running bitcoind and the options on its command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeProcess {
+    pub pid: u32,
+    /// Config file it reads, whether or not it exists
+    pub conf: PathBuf,
+    /// `-key[=value]` arguments, in order
+    pub args: Vec<(String, String)>,
+}
+
+/// The options in `argv` as bitcoind reads them: a leading `-` or `--`
+/// is dropped, a bare `-key` means 1 and `-nokey` sets `key` to the
+/// negated value. Anything not starting with `-` is ignored.
+#[must_use]
+pub fn parse_args(argv: &[String]) -> Vec<(String, String)> {
+    argv.iter()
+        .filter_map(|arg| {
+            let arg = arg.strip_prefix("--").or_else(|| arg.strip_prefix('-'))?;
+            let (key, value) = arg.split_once('=').unwrap_or((arg, "1"));
+            if key.is_empty() {
+                return None;
+            }
+            Some(match key.strip_prefix("no") {
+                Some(key) if !key.is_empty() => {
+                    let value = if matches!(value, "0" | "false") {
+                        "1"
+                    } else {
+                        "0"
+                    };
+                    (key.to_string(), value.to_string())
+                }
+                _ => (key.to_string(), value.to_string()),
+            })
+        })
+        .collect()
+}
+
+/// The config file bitcoind started with `args` in `cwd` reads: `-conf`,
+/// relative to the datadir, or `bitcoin.conf` in the datadir.
+fn conf_file(args: &[(String, String)], cwd: &Path) -> Option<PathBuf> {
+    let last = |key: &str| {
+        args.iter()
+            .rev()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    };
+    let datadir = match last("datadir") {
+        Some(dir) => cwd.join(dir),
+        None => default_datadir()?,
+    };
+    Some(datadir.join(last("conf").unwrap_or("bitcoin.conf")))
+}
+
+/// Whether `a` and `b` name the same file, comparing the paths as given
+/// when either cannot be resolved.
+#[must_use]
+pub fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Every bitcoind under `proc_dir` whose command line can be read, by
+/// pid.
+#[must_use]
+pub fn running_nodes(proc_dir: &Path) -> Vec<NodeProcess> {
+    let Ok(read) = std::fs::read_dir(proc_dir) else {
+        return Vec::new();
+    };
+    let mut found: Vec<NodeProcess> = read
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let pid = entry.file_name().to_str()?.parse().ok()?;
+            let cmdline = std::fs::read(entry.path().join("cmdline")).ok()?;
+            let argv: Vec<String> = cmdline
+                .split(|b| *b == 0)
+                .filter(|a| !a.is_empty())
+                .map(|a| String::from_utf8_lossy(a).into_owned())
+                .collect();
+            let program = Path::new(argv.first()?).file_name()?;
+            if program != "bitcoind" && program != "bitcoin-qt" {
+                return None;
+            }
+            let cwd = std::fs::read_link(entry.path().join("cwd")).unwrap_or_default();
+            let args = parse_args(&argv[1..]);
+            let conf = conf_file(&args, &cwd)?;
+            Some(NodeProcess { pid, conf, args })
+        })
+        .collect();
+    found.sort_by_key(|p| p.pid);
+    found
+}
+
+/// The bitcoind under `proc_dir` that reads `conf`, if one runs and its
+/// command line can be read.
+#[must_use]
+pub fn find_node_process(proc_dir: &Path, conf: &Path) -> Option<NodeProcess> {
+    running_nodes(proc_dir)
+        .into_iter()
+        .find(|p| same_file(&p.conf, conf))
+}
+
+/// A warning for every option both `entries` and the command line of
+/// `process` set; bitcoind takes the command line's value.
+#[must_use]
+pub fn overrides(process: &NodeProcess, entries: &[ConfigEntry]) -> Vec<Diagnostic> {
+    let mut out: Vec<Diagnostic> = Vec::new();
+    for (key, value) in process.args.iter().rev() {
+        if FILE_ARGS.contains(&key.as_str()) || out.iter().any(|d| d.key == *key) {
+            continue;
+        }
+        let Some(entry) = entries.iter().find(|e| e.key == *key && e.enabled) else {
+            continue;
+        };
+        let message = if entry.value == *value {
+            format!(
+                "also set to {value} on the command line of bitcoind (pid {}), so edits \
+                 here have no effect",
+                process.pid
+            )
+        } else {
+            format!(
+                "bitcoind (pid {}) was started with -{key}={value}, which overrides {} here",
+                process.pid, entry.value
+            )
+        };
+        out.push(Diagnostic::warning(key, message));
+    }
+    out.reverse();
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitcoin_config::{NodeFlavor, parse_config_str};
+
+    fn args(line: &str) -> Vec<(String, String)> {
+        let argv: Vec<String> = line.split_whitespace().map(str::to_string).collect();
+        parse_args(&argv)
+    }
+
+    #[test]
+    fn arguments_parse_like_bitcoind_reads_them() {
+        let pair = |k: &str, v: &str| (k.to_string(), v.to_string());
+        assert_eq!(
+            args("-server --prune=550 -nolisten -nodnsseed=0 -daemon=0 stray -"),
+            [
+                pair("server", "1"),
+                pair("prune", "550"),
+                pair("listen", "0"),
+                pair("dnsseed", "1"),
+                pair("daemon", "0"),
+            ]
+        );
+    }
+
+    #[test]
+    fn only_options_the_file_sets_are_reported() {
+        let entries =
+            parse_config_str("prune=550\nserver=1\ndbcache=450\n", NodeFlavor::Core).unwrap();
+        let process = NodeProcess {
+            pid: 42,
+            conf: PathBuf::from("/etc/bitcoin.conf"),
+            args: args("-conf=/etc/bitcoin.conf -prune=0 -server -txindex -prune=1000"),
+        };
+        let found = overrides(&process, &entries);
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].key, "server");
+        assert!(found[0].message.contains("no effect"));
+        assert_eq!(found[1].key, "prune");
+        assert_eq!(
+            found[1].message,
+            "bitcoind (pid 42) was started with -prune=1000, which overrides 550 here"
+        );
+    }
+
+    #[test]
+    fn the_process_reading_the_file_is_found() {
+        let dir = tempfile::tempdir().unwrap();
+        let conf = dir.path().join("node.conf");
+        std::fs::write(&conf, "server=1\n").unwrap();
+        let proc_dir = dir.path().join("proc");
+        let spawn = |pid: &str, cmdline: String| {
+            let path = proc_dir.join(pid);
+            std::fs::create_dir_all(&path).unwrap();
+            std::fs::write(path.join("cmdline"), cmdline.replace(' ', "\0")).unwrap();
+        };
+        spawn("7", "/usr/bin/bitcoind -conf=/elsewhere.conf".to_string());
+        spawn("9", format!("vim {}", conf.display()));
+        spawn(
+            "12",
+            format!(
+                "/usr/local/bin/bitcoind -datadir={} -conf=node.conf -prune=0 ",
+                dir.path().display()
+            ),
+        );
+        std::fs::create_dir_all(proc_dir.join("self")).unwrap();
+
+        assert_eq!(running_nodes(&proc_dir).len(), 2);
+        let process = find_node_process(&proc_dir, &conf).unwrap();
+        assert_eq!(process.pid, 12);
+        assert_eq!(process.args.len(), 3);
+        assert!(find_node_process(&proc_dir, &dir.path().join("other.conf")).is_none());
+    }
+}
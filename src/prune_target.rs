@@ -0,0 +1,195 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_config::{ConfigEntry, enabled_value};
+use crate::rpc::{chain_subdir, net_datadir};
+use anyhow::{Context, Result, bail};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Smallest `prune` target bitcoind accepts, in MiB.
+pub const MIN_PRUNE_MIB: u64 = 550;
+
+/// Free space kept back when the settings name no margin, in GB.
+pub const DEFAULT_MARGIN_GB: u64 = 20;
+
+/// Chainstate and indexes stay whole on a pruned node, so their room is
+/// never handed to the block files.
+pub const CHAINSTATE_GB: u64 = 15;
+
+/// Growth of the mainnet block files in a year; a full node needs that
+/// much room on top of today's chain.
+pub const YEARLY_GROWTH_GB: u64 = 60;
+
+const GB: u64 = 1_000_000_000;
+const MIB: u64 = 1024 * 1024;
+
+/// Rough size of the block and undo files of `chain` as of 2026, in GB.
+#[must_use]
+pub fn chain_size_gb(chain: &str) -> u64 {
+    match chain {
+        "main" => 720,
+        "test" => 200,
+        "testnet4" => 30,
+        "signet" => 25,
+        _ => 1,
+    }
+}
+
+/// What the blocks filesystem allows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PruneAdvice {
+    /// The full chain and a year of growth fit; keep every block
+    FullChain,
+    /// Keep this many MiB of blocks
+    Prune(u64),
+    /// Not even the smallest target fits next to the margin
+    NoRoom,
+}
+
+/// The `prune` value for a disk with `free` bytes left, of which bitcoind
+/// already uses `blocks` for block files it would prune, keeping `margin`
+/// bytes free.
+#[must_use]
+pub fn recommend_prune(free: u64, blocks: u64, margin: u64, chain: &str) -> PruneAdvice {
+    let budget = (free + blocks).saturating_sub(margin + CHAINSTATE_GB * GB);
+    let growth = if chain == "main" { YEARLY_GROWTH_GB } else { 0 };
+    if budget >= (chain_size_gb(chain) + growth) * GB {
+        PruneAdvice::FullChain
+    } else if budget / MIB >= MIN_PRUNE_MIB {
+        PruneAdvice::Prune(budget / MIB)
+    } else {
+        PruneAdvice::NoRoom
+    }
+}
+
+/// Where bitcoind keeps the block files of `chain`: `blocksdir` plus the
+/// chain's subdirectory when set, the chain's datadir otherwise.
+#[must_use]
+pub fn blocks_dir(entries: &[ConfigEntry], chain: &str) -> PathBuf {
+    match enabled_value(entries, "blocksdir") {
+        Some(dir) => Path::new(dir).join(chain_subdir(chain)),
+        None => net_datadir(entries, chain),
+    }
+    .join("blocks")
+}
+
+/// Bytes in the `blk*.dat` and `rev*.dat` files of `dir`; 0 before the
+/// node has downloaded anything.
+#[must_use]
+pub fn blocks_usage(dir: &Path) -> u64 {
+    let Ok(read) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    read.filter_map(Result::ok)
+        .filter(|e| {
+            let name = e.file_name().to_string_lossy().into_owned();
+            (name.starts_with("blk") || name.starts_with("rev")) && name.ends_with(".dat")
+        })
+        .filter_map(|e| e.metadata().ok())
+        .map(|m| m.len())
+        .sum()
+}
+
+/// Bytes free to unprivileged users on the filesystem holding `path`,
+/// or its nearest existing parent, as `df` reports them.
+///
+/// # Errors
+/// Returns an error if `df` cannot be run or its output does not parse.
+pub fn free_space(path: &Path) -> Result<u64> {
+    let existing = path
+        .ancestors()
+        .find(|p| p.exists())
+        .unwrap_or(Path::new("/"));
+    let output = Command::new("df")
+        .arg("-Pk")
+        .arg(existing)
+        .output()
+        .context("cannot run df")?;
+    if !output.status.success() {
+        bail!(
+            "df {}: {}",
+            existing.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    parse_df(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// The available column of `df -P` output, in bytes.
+fn parse_df(output: &str) -> Result<u64> {
+    let kib = output
+        .lines()
+        .nth(1)
+        .and_then(|line| line.split_whitespace().nth(3))
+        .and_then(|n| n.parse::<u64>().ok())
+        .context("unexpected df output")?;
+    Ok(kib * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitcoin_config::{NodeFlavor, parse_config_str};
+
+    #[test]
+    fn advice_follows_the_room_left_for_blocks() {
+        let margin = DEFAULT_MARGIN_GB * GB;
+        assert_eq!(
+            recommend_prune(2_000 * GB, 0, margin, "main"),
+            PruneAdvice::FullChain
+        );
+        // Blocks already on disk count, since pruning frees them
+        assert_eq!(
+            recommend_prune(100 * GB, 720 * GB, margin, "main"),
+            PruneAdvice::FullChain
+        );
+        assert_eq!(
+            recommend_prune(135 * GB, 0, margin, "main"),
+            PruneAdvice::Prune(100 * GB / MIB)
+        );
+        assert_eq!(
+            recommend_prune(35 * GB, 0, margin, "main"),
+            PruneAdvice::NoRoom
+        );
+        assert_eq!(
+            recommend_prune(100 * GB, 0, margin, "signet"),
+            PruneAdvice::FullChain
+        );
+    }
+
+    #[test]
+    fn blocks_live_under_blocksdir_or_the_datadir() {
+        let entries =
+            parse_config_str("datadir=/data\nblocksdir=/hdd\n", NodeFlavor::Core).unwrap();
+        assert_eq!(blocks_dir(&entries, "main"), Path::new("/hdd/blocks"));
+        assert_eq!(
+            blocks_dir(&entries, "signet"),
+            Path::new("/hdd/signet/blocks")
+        );
+        let entries = parse_config_str("datadir=/data\n", NodeFlavor::Core).unwrap();
+        assert_eq!(
+            blocks_dir(&entries, "test"),
+            Path::new("/data/testnet3/blocks")
+        );
+    }
+
+    #[test]
+    fn usage_counts_block_and_undo_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("blk00000.dat"), [0; 100]).unwrap();
+        std::fs::write(dir.path().join("rev00000.dat"), [0; 20]).unwrap();
+        std::fs::write(dir.path().join("xor.dat"), [0; 8]).unwrap();
+        assert_eq!(blocks_usage(dir.path()), 120);
+        assert_eq!(blocks_usage(&dir.path().join("missing")), 0);
+    }
+
+    #[test]
+    fn df_available_column_is_read_in_kib() {
+        let output = "Filesystem 1024-blocks Used Available Capacity Mounted on\n\
+                      /dev/sda1 1000 400 600 40% /\n";
+        assert_eq!(parse_df(output).unwrap(), 600 * 1024);
+        assert!(parse_df("").is_err());
+    }
+}
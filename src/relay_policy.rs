@@ -0,0 +1,162 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_config::{ConfigEntry, reset_entry};
+
+/// A relay-policy option a preset sets, with its value under
+/// [`PolicyPreset::Restrictive`] and what choosing between the two costs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PolicyOption {
+    pub key: &'static str,
+    pub restrictive: &'static str,
+    pub tradeoff: &'static str,
+}
+
+/// The relay-policy cluster the presets cover.
+pub const POLICY_OPTIONS: &[PolicyOption] = &[
+    PolicyOption {
+        key: "datacarrier",
+        restrictive: "0",
+        tradeoff: "Off drops every transaction with an OP_RETURN output from your mempool. \
+            Miners still include them, so your fee estimates and compact block \
+            reconstruction get worse as they make up more of each block.",
+    },
+    PolicyOption {
+        key: "datacarriersize",
+        restrictive: "42",
+        tradeoff: "Smaller limits reject the larger OP_RETURN payloads token and data \
+            protocols use, while 42 bytes still fits a 32-byte hash plus a short tag. Only \
+            matters while datacarrier is on.",
+    },
+    PolicyOption {
+        key: "permitbaremultisig",
+        restrictive: "0",
+        tradeoff: "Bare multisig outputs bloat the UTXO set and are mostly used to embed data. \
+            Turning them off refuses that data, but also the few old wallets that still \
+            pay to them.",
+    },
+    PolicyOption {
+        key: "bytespersigop",
+        restrictive: "40",
+        tradeoff: "Counts each signature operation as more virtual bytes, so sigop-heavy \
+            transactions need a higher fee to be relayed. Unusual multisig spends may no \
+            longer propagate through your node.",
+    },
+    PolicyOption {
+        key: "minrelaytxfee",
+        restrictive: "0.00003",
+        tradeoff: "A higher floor keeps cheap transactions out of your mempool and off your \
+            bandwidth. When blocks are not full, miners still confirm them, and your node no \
+            longer sees or validates them ahead of time.",
+    },
+];
+
+/// A set of values for [`POLICY_OPTIONS`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolicyPreset {
+    /// What bitcoind does with none of the options set
+    CoreDefaults,
+    /// Relays less arbitrary data and fewer cheap transactions
+    Restrictive,
+}
+
+impl PolicyPreset {
+    pub const ALL: [PolicyPreset; 2] = [PolicyPreset::CoreDefaults, PolicyPreset::Restrictive];
+
+    /// Name shown in the preset picker.
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            PolicyPreset::CoreDefaults => "Core defaults",
+            PolicyPreset::Restrictive => "Restrictive",
+        }
+    }
+
+    /// What choosing the preset means for the node.
+    #[must_use]
+    pub fn description(self) -> &'static str {
+        match self {
+            PolicyPreset::CoreDefaults => {
+                "Relay what most of the network relays; the mempool matches what miners \
+                 are likely to mine"
+            }
+            PolicyPreset::Restrictive => {
+                "Refuse data-carrying and sigop-heavy transactions; less bandwidth and \
+                 storage, worse fee estimates"
+            }
+        }
+    }
+
+    /// The value `option` has under this preset; `None` when the option is
+    /// left unset so bitcoind uses its own default.
+    #[must_use]
+    pub fn value(self, option: &PolicyOption) -> Option<&'static str> {
+        match self {
+            PolicyPreset::CoreDefaults => None,
+            PolicyPreset::Restrictive => Some(option.restrictive),
+        }
+    }
+}
+
+/// Sets [`POLICY_OPTIONS`] in `entries` to `preset`; returns how many
+/// entries changed.
+pub fn apply_preset(entries: &mut [ConfigEntry], preset: PolicyPreset) -> usize {
+    let mut changed = 0;
+    for option in POLICY_OPTIONS {
+        let Some(entry) = entries.iter_mut().find(|e| e.key == option.key) else {
+            continue;
+        };
+        let before = (entry.enabled, entry.negated, entry.value.clone());
+        match preset.value(option) {
+            Some(value) => {
+                entry.value = value.to_string();
+                entry.enabled = true;
+                entry.negated = false;
+            }
+            None => reset_entry(entry),
+        }
+        if before != (entry.enabled, entry.negated, entry.value.clone()) {
+            changed += 1;
+        }
+    }
+    changed
+}
+
+/// Preset picker for the relay policy.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PresetPicker {
+    /// Index into [`PolicyPreset::ALL`]
+    pub cursor: usize,
+}
+
+impl PresetPicker {
+    /// The preset under the cursor.
+    #[must_use]
+    pub fn preset(&self) -> PolicyPreset {
+        PolicyPreset::ALL[self.cursor.min(PolicyPreset::ALL.len() - 1)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitcoin_config::{NodeFlavor, enabled_value as value, parse_config_str};
+
+    #[test]
+    fn presets_set_and_clear_the_policy_cluster() {
+        let mut entries = parse_config_str("datacarrier=1\nserver=1\n", NodeFlavor::Core).unwrap();
+        assert_eq!(apply_preset(&mut entries, PolicyPreset::Restrictive), 5);
+        assert_eq!(value(&entries, "datacarrier"), Some("0"));
+        assert_eq!(value(&entries, "minrelaytxfee"), Some("0.00003"));
+        assert_eq!(apply_preset(&mut entries, PolicyPreset::Restrictive), 0);
+
+        assert_eq!(apply_preset(&mut entries, PolicyPreset::CoreDefaults), 5);
+        assert!(
+            POLICY_OPTIONS
+                .iter()
+                .all(|o| value(&entries, o.key).is_none())
+        );
+        assert_eq!(value(&entries, "server"), Some("1"));
+    }
+}
@@ -0,0 +1,82 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::blocks::Reorg;
+use crate::settings::config_dir;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// On-disk log: a TOML file of `[[reorg]]` tables, appended to on every
+/// reorg seen, so they can be looked into after the fact.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Log {
+    #[serde(default)]
+    reorg: Vec<Reorg>,
+}
+
+/// The reorg log, next to the settings file.
+///
+/// # Errors
+/// Returns an error if the config directory cannot be determined.
+pub fn log_path() -> Result<PathBuf> {
+    Ok(config_dir()?.join("reorgs.toml"))
+}
+
+/// Appends `reorg` to the log at `path`.
+///
+/// # Errors
+/// Returns an error if the log cannot be written.
+pub fn append(path: &Path, reorg: &Reorg) -> Result<()> {
+    let content = toml::to_string(&Log {
+        reorg: vec![reorg.clone()],
+    })?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut log = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(log, "{content}")?;
+    Ok(())
+}
+
+/// Reorgs logged at `path`, oldest first; none when there is no log yet.
+///
+/// # Errors
+/// Returns an error if the log exists but cannot be read or parsed.
+pub fn load(path: &Path) -> Result<Vec<Reorg>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let log: Log = toml::from_str(&std::fs::read_to_string(path)?)?;
+    Ok(log.reorg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reorgs_are_appended_and_read_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("reorgs.toml");
+        assert!(load(&path).unwrap().is_empty());
+        let reorg = |depth: u64, height: Option<u64>| Reorg {
+            depth,
+            seen: 1_700_000_000,
+            disconnected: vec!["aa".to_string(); usize::try_from(depth).unwrap()],
+            connected: "bb".to_string(),
+            height,
+        };
+        append(&path, &reorg(1, Some(850_000))).unwrap();
+        append(&path, &reorg(3, None)).unwrap();
+        assert_eq!(
+            load(&path).unwrap(),
+            [reorg(1, Some(850_000)), reorg(3, None)]
+        );
+    }
+}
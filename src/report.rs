@@ -0,0 +1,157 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_config::{ConfigCategory, ConfigEntry, config_line};
+use crate::docker_compose::{HEADER, write_generated};
+use crate::secrets::is_secret;
+use anyhow::Result;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+/// File the report is written to, next to bitcoin.conf.
+pub const REPORT_FILE: &str = "bitcoin.conf.md";
+
+/// Makes `text` safe inside a Markdown table cell.
+fn cell(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace('|', "\\|")
+}
+
+/// Inline code for a table cell, or a placeholder when `value` is empty.
+fn code(value: &str) -> String {
+    if value.is_empty() {
+        "*none*".to_string()
+    } else {
+        format!("`{}`", cell(value).replace('`', "'"))
+    }
+}
+
+/// Renders the enabled options of `entries` as Markdown: one table per
+/// category, in schema order, with options PDM does not know at the end.
+/// Secret values are left out.
+#[must_use]
+pub fn generate_report(title: &str, source: &Path, entries: &[ConfigEntry]) -> String {
+    let mut categories: Vec<Option<ConfigCategory>> = Vec::new();
+    for e in entries.iter().filter(|e| e.enabled) {
+        let category = e.schema.as_ref().map(|s| s.category);
+        if !categories.contains(&category) {
+            categories.push(category);
+        }
+    }
+    categories.sort_by_key(Option::is_none);
+
+    let mut out = String::new();
+    let _ = writeln!(out, "{HEADER}: {title}\n");
+    let _ = writeln!(out, "Source: `{}`\n", source.display());
+    if categories.is_empty() {
+        out.push_str("No options are set; bitcoind runs with its defaults.\n");
+        return out;
+    }
+    for category in categories {
+        match category {
+            Some(category) => {
+                let _ = writeln!(out, "## {category:?}\n");
+            }
+            None => out.push_str("## Other\n\n"),
+        }
+        out.push_str("| Option | Value | Default | Description |\n");
+        out.push_str("| --- | --- | --- | --- |\n");
+        let rows = entries
+            .iter()
+            .filter(|e| e.enabled && e.schema.as_ref().map(|s| s.category) == category);
+        for e in rows {
+            let value = if is_secret(&e.key) {
+                "(hidden)".to_string()
+            } else if e.negated {
+                code(&config_line(e))
+            } else {
+                code(&e.value)
+            };
+            let (default, description) = e.schema.as_ref().map_or_else(
+                || ("*unknown*".to_string(), String::new()),
+                |s| (code(&s.default), cell(&s.description)),
+            );
+            let _ = writeln!(out, "| `{}` | {value} | {default} | {description} |", e.key);
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Writes the Markdown report next to `bitcoin_conf` and returns its path.
+///
+/// # Errors
+/// Returns an error if a hand-written file of the same name exists or the
+/// report cannot be written.
+pub fn export_report(bitcoin_conf: &Path, title: &str, entries: &[ConfigEntry]) -> Result<PathBuf> {
+    let path = bitcoin_conf
+        .parent()
+        .unwrap_or(Path::new("."))
+        .join(REPORT_FILE);
+    write_generated(&path, &generate_report(title, bitcoin_conf, entries))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(content: &str) -> Vec<ConfigEntry> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bitcoin.conf");
+        std::fs::write(&path, content).unwrap();
+        crate::bitcoin_config::parse_config(&path).unwrap()
+    }
+
+    #[test]
+    fn report_groups_enabled_options_by_category() {
+        let report = generate_report(
+            "Bitcoin Core configuration",
+            Path::new("/etc/bitcoin/bitcoin.conf"),
+            &entries("server=1\nrpcpassword=hunter2\ntxindex=1\nnolisten=1\nmystery=a|b\n"),
+        );
+
+        assert!(report.starts_with(HEADER));
+        assert!(report.contains("Source: `/etc/bitcoin/bitcoin.conf`"));
+        let core = report.find("## Core").unwrap();
+        let rpc = report.find("## RPC").unwrap();
+        let other = report.find("## Other").unwrap();
+        assert!(core < rpc && rpc < other, "unknown options come last");
+
+        assert!(report.contains("| `txindex` | `1` | `0` |"));
+        assert!(report.contains("| `listen` | `nolisten=1` | `1` |"));
+        assert!(report.contains("| `rpcpassword` | (hidden) |"));
+        assert!(!report.contains("hunter2"));
+        assert!(report.contains("| `mystery` | `a\\|b` | *unknown* |  |"));
+        // Disabled options are left out
+        assert!(!report.contains("`prune`"));
+    }
+
+    #[test]
+    fn empty_config_says_so() {
+        let report = generate_report("t", Path::new("bitcoin.conf"), &entries(""));
+        assert!(report.contains("No options are set"));
+        assert!(!report.contains("| Option |"));
+    }
+
+    #[test]
+    fn export_keeps_hand_written_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let conf = dir.path().join("bitcoin.conf");
+
+        let path = export_report(&conf, "t", &entries("txindex=1\n")).unwrap();
+        assert_eq!(path, dir.path().join(REPORT_FILE));
+        assert!(
+            std::fs::read_to_string(&path)
+                .unwrap()
+                .contains("`txindex`")
+        );
+
+        std::fs::write(&path, "# My notes\n").unwrap();
+        assert!(export_report(&conf, "t", &entries("txindex=1\n")).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "# My notes\n");
+    }
+}
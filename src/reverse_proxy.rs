@@ -0,0 +1,263 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_config::{ConfigEntry, effective_rpc_port, enabled_value};
+use crate::docker_compose::{HEADER, write_generated};
+use anyhow::{Result, bail};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+/// Reverse proxy flavors a snippet can be generated for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProxyKind {
+    Nginx,
+    Caddy,
+}
+
+impl ProxyKind {
+    pub const ALL: [ProxyKind; 2] = [ProxyKind::Nginx, ProxyKind::Caddy];
+
+    /// File name the snippet is written to, next to bitcoin.conf.
+    #[must_use]
+    pub fn file_name(self) -> &'static str {
+        match self {
+            ProxyKind::Nginx => "bitcoind-proxy.nginx.conf",
+            ProxyKind::Caddy => "bitcoind-proxy.Caddyfile",
+        }
+    }
+}
+
+/// What bitcoind serves over HTTP according to the loaded bitcoin.conf.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Exposure {
+    pub rpc: bool,
+    pub rest: bool,
+    pub port: u16,
+    /// `rpcallowip` entries, as CIDR ranges
+    pub allow: Vec<String>,
+}
+
+impl Exposure {
+    /// Returns `None` when neither RPC (`server=1`) nor REST (`rest=1`) is enabled.
+    #[must_use]
+    pub fn from_entries(entries: &[ConfigEntry]) -> Option<Self> {
+        let rpc = enabled_value(entries, "server") == Some("1");
+        let rest = enabled_value(entries, "rest") == Some("1");
+        if !rpc && !rest {
+            return None;
+        }
+        // rpcallowip may be given any number of times
+        let allow = entries
+            .iter()
+            .filter(|e| e.enabled && e.key == "rpcallowip")
+            .flat_map(|e| e.value.split([',', ' ']))
+            .filter(|s| !s.is_empty())
+            .map(to_cidr)
+            .collect();
+        Some(Self {
+            rpc,
+            rest,
+            port: effective_rpc_port(entries),
+            allow,
+        })
+    }
+}
+
+/// Converts an `rpcallowip` value into CIDR notation. bitcoind also accepts
+/// `1.2.3.0/255.255.255.0`, which neither nginx nor Caddy understand.
+fn to_cidr(allow: &str) -> String {
+    let Some((ip, mask)) = allow.split_once('/') else {
+        return allow.to_string();
+    };
+    match mask.parse::<std::net::Ipv4Addr>() {
+        Ok(netmask) => format!("{ip}/{}", u32::from(netmask).count_ones()),
+        Err(_) => allow.to_string(),
+    }
+}
+
+/// Renders a TLS-terminating reverse proxy snippet for `exposure`.
+///
+/// RPC requests are passed through untouched because bitcoind authenticates
+/// them with its own `Authorization` header; the unauthenticated REST
+/// interface is put behind HTTP basic auth instead.
+#[must_use]
+pub fn generate_proxy(kind: ProxyKind, exposure: &Exposure, server_name: &str) -> String {
+    let upstream = format!("127.0.0.1:{}", exposure.port);
+    let mut out = String::new();
+    let _ = writeln!(out, "{HEADER}: reverse proxy for bitcoind on {upstream}");
+    match kind {
+        ProxyKind::Nginx => {
+            out.push_str("server {\n");
+            out.push_str("    listen 443 ssl;\n");
+            let _ = writeln!(out, "    server_name {server_name};");
+            let _ = writeln!(
+                out,
+                "    ssl_certificate /etc/letsencrypt/live/{server_name}/fullchain.pem;"
+            );
+            let _ = writeln!(
+                out,
+                "    ssl_certificate_key /etc/letsencrypt/live/{server_name}/privkey.pem;"
+            );
+            out.push_str("    ssl_protocols TLSv1.2 TLSv1.3;\n");
+            if !exposure.allow.is_empty() {
+                out.push('\n');
+                for ip in &exposure.allow {
+                    let _ = writeln!(out, "    allow {ip};");
+                }
+                out.push_str("    deny all;\n");
+            }
+            if exposure.rest {
+                out.push_str("\n    location /rest/ {\n");
+                out.push_str("        auth_basic \"bitcoind REST\";\n");
+                out.push_str("        auth_basic_user_file /etc/nginx/bitcoind.htpasswd;\n");
+                out.push_str("        proxy_set_header Authorization \"\";\n");
+                let _ = writeln!(out, "        proxy_pass http://{upstream};");
+                out.push_str("    }\n");
+            }
+            if exposure.rpc {
+                out.push_str("\n    location = / {\n");
+                out.push_str("        limit_except POST { deny all; }\n");
+                let _ = writeln!(out, "        proxy_pass http://{upstream};");
+                out.push_str("    }\n");
+                out.push_str("\n    location /wallet/ {\n");
+                out.push_str("        limit_except POST { deny all; }\n");
+                let _ = writeln!(out, "        proxy_pass http://{upstream};");
+                out.push_str("    }\n");
+            }
+            out.push_str("}\n");
+        }
+        ProxyKind::Caddy => {
+            // Caddy obtains and renews the certificate for server_name itself
+            let _ = writeln!(out, "{server_name} {{");
+            if !exposure.allow.is_empty() {
+                let _ = writeln!(
+                    out,
+                    "    @denied not remote_ip {}",
+                    exposure.allow.join(" ")
+                );
+                out.push_str("    abort @denied\n");
+            }
+            if exposure.rest {
+                out.push_str("\n    handle /rest/* {\n");
+                out.push_str("        basic_auth {\n");
+                out.push_str("            # caddy hash-password --plaintext <password>\n");
+                out.push_str("            bitcoin <hashed-password>\n");
+                out.push_str("        }\n");
+                out.push_str("        request_header -Authorization\n");
+                let _ = writeln!(out, "        reverse_proxy {upstream}");
+                out.push_str("    }\n");
+            }
+            if exposure.rpc {
+                out.push_str("\n    @rpc {\n");
+                out.push_str("        method POST\n");
+                out.push_str("        path / /wallet/*\n");
+                out.push_str("    }\n");
+                let _ = writeln!(out, "    reverse_proxy @rpc {upstream}");
+            }
+            out.push_str("\n    respond 404\n");
+            out.push_str("}\n");
+        }
+    }
+    out
+}
+
+/// Writes nginx and Caddy snippets next to `bitcoin_conf` and returns their paths.
+///
+/// # Errors
+/// Returns an error if neither RPC nor REST is enabled, or if a snippet
+/// cannot be written.
+pub fn export_proxy(bitcoin_conf: &Path, entries: &[ConfigEntry]) -> Result<Vec<PathBuf>> {
+    let Some(exposure) = Exposure::from_entries(entries) else {
+        bail!("enable server=1 or rest=1 first");
+    };
+    let dir = bitcoin_conf.parent().unwrap_or(Path::new("."));
+    let mut written = Vec::new();
+    for kind in ProxyKind::ALL {
+        let path = dir.join(kind.file_name());
+        write_generated(&path, &generate_proxy(kind, &exposure, "node.example.com"))?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(content: &str) -> Vec<ConfigEntry> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bitcoin.conf");
+        std::fs::write(&path, content).unwrap();
+        crate::bitcoin_config::parse_config(&path).unwrap()
+    }
+
+    #[test]
+    fn exposure_requires_rpc_or_rest() {
+        assert!(Exposure::from_entries(&entries("txindex=1\n")).is_none());
+
+        let exposure =
+            Exposure::from_entries(&entries("rest=1\nrpcallowip=10.0.0.0/255.0.0.0\n")).unwrap();
+        assert!(exposure.rest);
+        assert!(!exposure.rpc);
+        assert_eq!(exposure.port, 8332);
+        assert_eq!(exposure.allow, vec!["10.0.0.0/8"]);
+    }
+
+    #[test]
+    fn exposure_allows_every_rpcallowip_entry() {
+        let mut found = entries("server=1\nrpcallowip=10.0.0.0/8\n");
+        let mut second = found
+            .iter()
+            .find(|e| e.key == "rpcallowip")
+            .unwrap()
+            .clone();
+        second.value = "192.168.1.0/255.255.255.0".to_string();
+        found.push(second.clone());
+        second.value = "172.16.0.1".to_string();
+        second.enabled = false;
+        found.push(second);
+
+        let exposure = Exposure::from_entries(&found).unwrap();
+        assert_eq!(exposure.allow, vec!["10.0.0.0/8", "192.168.1.0/24"]);
+    }
+
+    #[test]
+    fn nginx_snippet_mirrors_allow_list() {
+        let exposure = Exposure::from_entries(&entries(
+            "server=1\nrest=1\nrpcport=18443\nrpcallowip=192.168.1.0/24\n",
+        ))
+        .unwrap();
+        let conf = generate_proxy(ProxyKind::Nginx, &exposure, "node.example.com");
+
+        assert!(conf.starts_with(HEADER));
+        assert!(conf.contains("listen 443 ssl;"));
+        assert!(conf.contains("allow 192.168.1.0/24;\n    deny all;"));
+        assert!(conf.contains("auth_basic_user_file"));
+        assert!(conf.contains("proxy_pass http://127.0.0.1:18443;"));
+        assert!(conf.contains("location = / {"));
+    }
+
+    #[test]
+    fn caddy_snippet_only_routes_enabled_interfaces() {
+        let exposure = Exposure::from_entries(&entries("rest=1\n")).unwrap();
+        let conf = generate_proxy(ProxyKind::Caddy, &exposure, "node.example.com");
+
+        assert!(conf.contains("node.example.com {"));
+        assert!(conf.contains("basic_auth {"));
+        assert!(!conf.contains("@rpc"));
+        assert!(!conf.contains("remote_ip"));
+    }
+
+    #[test]
+    fn export_writes_both_snippets() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bitcoin.conf");
+
+        assert!(export_proxy(&path, &entries("")).is_err());
+
+        let written = export_proxy(&path, &entries("server=1\n")).unwrap();
+        assert_eq!(written.len(), 2);
+        assert!(written.iter().all(|p| p.exists()));
+    }
+}
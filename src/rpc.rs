@@ -0,0 +1,561 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_config::{CHAINS, ConfigEntry, default_rpc_port, enabled_value};
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde_json::Value;
+use std::fmt;
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Credentials for bitcoind's RPC server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RpcAuth {
+    /// `rpcuser` / `rpcpassword`
+    Password { user: String, password: String },
+    /// The `.cookie` file bitcoind writes when no password is configured
+    Cookie(PathBuf),
+}
+
+impl fmt::Display for RpcAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RpcAuth::Password { user, .. } => write!(f, "rpcuser {user}"),
+            RpcAuth::Cookie(path) => write!(f, "cookie {}", path.display()),
+        }
+    }
+}
+
+/// Where and how to reach bitcoind's JSON-RPC server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RpcTarget {
+    pub host: String,
+    pub port: u16,
+    pub auth: RpcAuth,
+}
+
+/// Why an RPC call failed, in terms a node runner can act on.
+#[derive(Debug)]
+pub enum RpcError {
+    /// Nothing accepted the connection
+    Connect(String, std::io::Error),
+    /// The cookie file could not be read
+    Cookie(PathBuf, std::io::Error),
+    /// bitcoind rejected the credentials (HTTP 401)
+    Unauthorized,
+    /// bitcoind answered with an RPC error
+    Rpc { code: i64, message: String },
+    /// The reply was not the JSON-RPC bitcoind sends
+    Protocol(String),
+}
+
+impl fmt::Display for RpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RpcError::Connect(endpoint, e) => write!(f, "cannot connect to {endpoint}: {e}"),
+            RpcError::Cookie(path, e) => write!(f, "cannot read {}: {e}", path.display()),
+            RpcError::Unauthorized => write!(f, "bitcoind rejected the RPC credentials"),
+            RpcError::Rpc { code, message } => write!(f, "RPC error {code}: {message}"),
+            RpcError::Protocol(msg) => write!(f, "unexpected reply: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for RpcError {}
+
+/// Directory under the datadir where `chain` keeps its files.
+#[must_use]
+pub fn chain_subdir(chain: &str) -> &'static str {
+    match chain {
+        "test" => "testnet3",
+        "testnet4" => "testnet4",
+        "signet" => "signet",
+        "regtest" => "regtest",
+        _ => "",
+    }
+}
+
+/// bitcoind's default datadir on this platform: `%APPDATA%\Bitcoin` on
+/// Windows, `~/Library/Application Support/Bitcoin` on macOS and
+/// `~/.bitcoin` elsewhere.
+#[must_use]
+pub fn default_datadir() -> Option<PathBuf> {
+    let dirs = directories::BaseDirs::new()?;
+    Some(if cfg!(any(target_os = "macos", target_os = "windows")) {
+        dirs.data_dir().join("Bitcoin")
+    } else {
+        dirs.home_dir().join(".bitcoin")
+    })
+}
+
+/// Where bitcoind keeps the files of `chain`: the configured `datadir`,
+/// or the platform default, plus the chain's subdirectory.
+#[must_use]
+pub fn net_datadir(entries: &[ConfigEntry], chain: &str) -> PathBuf {
+    enabled_value(entries, "datadir")
+        .map(PathBuf::from)
+        .or_else(default_datadir)
+        .unwrap_or_default()
+        .join(chain_subdir(chain))
+}
+
+/// The cookie file bitcoind writes on `chain`: `rpccookiefile`, relative
+/// to the chain's data directory, or `.cookie` there.
+#[must_use]
+pub fn cookie_path(entries: &[ConfigEntry], chain: &str) -> PathBuf {
+    let cookie = enabled_value(entries, "rpccookiefile").unwrap_or(".cookie");
+    net_datadir(entries, chain).join(cookie)
+}
+
+impl RpcTarget {
+    /// The RPC server `entries` describe on `chain`: `rpcconnect` (or
+    /// localhost), the chain's RPC port unless `rpcport` is set, and
+    /// `rpcuser`/`rpcpassword` or else the cookie file. `entries` must
+    /// hold real secrets, not keyring references.
+    #[must_use]
+    pub fn from_entries(entries: &[ConfigEntry], chain: &str) -> Self {
+        let host = enabled_value(entries, "rpcconnect").unwrap_or("127.0.0.1");
+        let port = enabled_value(entries, "rpcport")
+            .and_then(|p| p.parse().ok())
+            .unwrap_or_else(|| default_rpc_port(chain));
+        let auth = match (
+            enabled_value(entries, "rpcuser"),
+            enabled_value(entries, "rpcpassword"),
+        ) {
+            (Some(user), Some(password)) => RpcAuth::Password {
+                user: user.to_string(),
+                password: password.to_string(),
+            },
+            _ => RpcAuth::Cookie(cookie_path(entries, chain)),
+        };
+        Self {
+            host: host.to_string(),
+            port,
+            auth,
+        }
+    }
+
+    /// `host:port`, bracketing IPv6 addresses.
+    #[must_use]
+    pub fn endpoint(&self) -> String {
+        if self.host.contains(':') {
+            format!("[{}]:{}", self.host, self.port)
+        } else {
+            format!("{}:{}", self.host, self.port)
+        }
+    }
+
+    fn authorization(&self) -> Result<String, RpcError> {
+        let credentials = match &self.auth {
+            RpcAuth::Password { user, password } => format!("{user}:{password}"),
+            RpcAuth::Cookie(path) => read_cookie(path)?,
+        };
+        Ok(format!("Basic {}", BASE64.encode(credentials)))
+    }
+
+    /// Calls `method` without parameters and returns its `result`.
+    ///
+    /// # Errors
+    /// Returns an [`RpcError`] saying whether the connection, the
+    /// credentials or the call itself failed.
+    pub fn call(&self, method: &str, timeout: Duration) -> Result<Value, RpcError> {
+        self.call_with(method, &[], timeout)
+    }
+
+    /// Calls `method` with positional `params` and returns its `result`.
+    ///
+    /// # Errors
+    /// As for [`RpcTarget::call`].
+    pub fn call_with(
+        &self,
+        method: &str,
+        params: &[Value],
+        timeout: Duration,
+    ) -> Result<Value, RpcError> {
+        self.post("/", method, params, timeout)
+    }
+
+    /// Calls `method` on the loaded wallet `wallet`, for bitcoind with
+    /// several wallets loaded.
+    ///
+    /// # Errors
+    /// As for [`RpcTarget::call`].
+    pub fn call_wallet(
+        &self,
+        wallet: &str,
+        method: &str,
+        params: &[Value],
+        timeout: Duration,
+    ) -> Result<Value, RpcError> {
+        let name: String = wallet
+            .bytes()
+            .map(|b| {
+                if b.is_ascii_alphanumeric() || b"-._~".contains(&b) {
+                    char::from(b).to_string()
+                } else {
+                    format!("%{b:02X}")
+                }
+            })
+            .collect();
+        self.post(&format!("/wallet/{name}"), method, params, timeout)
+    }
+
+    /// Sends one JSON-RPC request to the URL `path` of bitcoind.
+    fn post(
+        &self,
+        path: &str,
+        method: &str,
+        params: &[Value],
+        timeout: Duration,
+    ) -> Result<Value, RpcError> {
+        let authorization = self.authorization()?;
+        let endpoint = self.endpoint();
+        let connect_error = |e| RpcError::Connect(endpoint.clone(), e);
+        let addrs: Vec<_> = endpoint.to_socket_addrs().map_err(connect_error)?.collect();
+        let mut last = std::io::Error::from(std::io::ErrorKind::AddrNotAvailable);
+        let mut stream = None;
+        for addr in addrs {
+            match TcpStream::connect_timeout(&addr, timeout) {
+                Ok(s) => {
+                    stream = Some(s);
+                    break;
+                }
+                Err(e) => last = e,
+            }
+        }
+        let mut stream = stream.ok_or_else(|| connect_error(last))?;
+        stream
+            .set_read_timeout(Some(timeout))
+            .map_err(connect_error)?;
+        stream
+            .set_write_timeout(Some(timeout))
+            .map_err(connect_error)?;
+
+        let body = serde_json::json!({
+            "jsonrpc": "1.0",
+            "id": "pdm",
+            "method": method,
+            "params": params,
+        })
+        .to_string();
+        // HTTP/1.0 keeps bitcoind from chunking the reply
+        let request = format!(
+            "POST {path} HTTP/1.0\r\nHost: {endpoint}\r\nAuthorization: {authorization}\r\n\
+             Content-Type: application/json\r\nContent-Length: {}\r\n\r\n{body}",
+            body.len()
+        );
+        stream
+            .write_all(request.as_bytes())
+            .map_err(connect_error)?;
+        let mut reply = String::new();
+        stream
+            .read_to_string(&mut reply)
+            .map_err(|e| RpcError::Protocol(e.to_string()))?;
+        parse_reply(&reply)
+    }
+}
+
+/// A `getblockchaininfo` round trip that succeeded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectionReport {
+    pub endpoint: String,
+    pub latency: Duration,
+    pub chain: String,
+    pub blocks: u64,
+}
+
+impl fmt::Display for ConnectionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "bitcoind at {} answered in {} ms ({}, block {})",
+            self.endpoint,
+            self.latency.as_millis(),
+            self.chain,
+            self.blocks
+        )
+    }
+}
+
+impl RpcTarget {
+    /// Calls `getblockchaininfo` and times the round trip.
+    ///
+    /// # Errors
+    /// Returns the [`RpcError`] of the call.
+    pub fn test_connection(&self, timeout: Duration) -> Result<ConnectionReport, RpcError> {
+        let start = Instant::now();
+        let info = self.call("getblockchaininfo", timeout)?;
+        Ok(ConnectionReport {
+            endpoint: self.endpoint(),
+            latency: start.elapsed(),
+            chain: info
+                .get("chain")
+                .and_then(Value::as_str)
+                .unwrap_or("unknown chain")
+                .to_string(),
+            blocks: info.get("blocks").and_then(Value::as_u64).unwrap_or(0),
+        })
+    }
+}
+
+/// Chain a running bitcoind reports, and where it answered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeChain {
+    pub endpoint: String,
+    /// `getblockchaininfo.chain`: `main`, `test`, `testnet4`, `signet` or `regtest`
+    pub chain: String,
+}
+
+/// Asks bitcoind which chain it runs, at the endpoint `entries` configure
+/// for `chain`. When nothing listens there and the host is local, the
+/// default RPC port of every other chain is tried too, so a node run from
+/// a different file is still found.
+#[must_use]
+pub fn probe_chain(entries: &[ConfigEntry], chain: &str, timeout: Duration) -> Option<NodeChain> {
+    let configured = RpcTarget::from_entries(entries, chain);
+    let local = matches!(configured.host.as_str(), "127.0.0.1" | "localhost" | "::1");
+    let mut targets = vec![configured.clone()];
+    if local {
+        for other in CHAINS.iter().filter(|c| **c != chain) {
+            let mut target = RpcTarget::from_entries(entries, other);
+            target.port = default_rpc_port(other);
+            if target.port != configured.port {
+                targets.push(target);
+            }
+        }
+    }
+    for target in targets {
+        match target.call("getblockchaininfo", timeout) {
+            Ok(info) => {
+                return info
+                    .get("chain")
+                    .and_then(Value::as_str)
+                    .map(|c| NodeChain {
+                        endpoint: target.endpoint(),
+                        chain: c.to_string(),
+                    });
+            }
+            Err(RpcError::Connect(..) | RpcError::Cookie(..)) => {}
+            // Something answered, but it will not say which chain
+            Err(_) => return None,
+        }
+    }
+    None
+}
+
+/// `user:password` from a cookie file.
+fn read_cookie(path: &Path) -> Result<String, RpcError> {
+    std::fs::read_to_string(path)
+        .map(|s| s.trim().to_string())
+        .map_err(|e| RpcError::Cookie(path.to_path_buf(), e))
+}
+
+/// The `result` of an HTTP reply from bitcoind.
+fn parse_reply(reply: &str) -> Result<Value, RpcError> {
+    let (head, body) = reply
+        .split_once("\r\n\r\n")
+        .ok_or_else(|| RpcError::Protocol("no HTTP headers".to_string()))?;
+    let status = head
+        .split_whitespace()
+        .nth(1)
+        .and_then(|s| s.parse::<u16>().ok())
+        .ok_or_else(|| RpcError::Protocol("no HTTP status".to_string()))?;
+    if status == 401 || status == 403 {
+        return Err(RpcError::Unauthorized);
+    }
+    // RPC errors come with a 404 or 500 status and a JSON body
+    let json: Value = serde_json::from_str(body)
+        .map_err(|_| RpcError::Protocol(format!("HTTP {status} without a JSON-RPC body")))?;
+    match json.get("error") {
+        Some(error) if !error.is_null() => Err(RpcError::Rpc {
+            code: error.get("code").and_then(Value::as_i64).unwrap_or(0),
+            message: error
+                .get("message")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+        }),
+        _ => json
+            .get("result")
+            .cloned()
+            .ok_or_else(|| RpcError::Protocol("no result".to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    fn entries(content: &str) -> Vec<ConfigEntry> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bitcoin.conf");
+        std::fs::write(&path, content).unwrap();
+        crate::bitcoin_config::parse_config(&path).unwrap()
+    }
+
+    /// Serves one HTTP reply on a free local port; returns the port and a
+    /// handle yielding the request it received.
+    fn serve_once(reply: &'static str) -> (u16, std::thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0; 4096];
+            let n = stream.read(&mut buf).unwrap();
+            stream.write_all(reply.as_bytes()).unwrap();
+            String::from_utf8_lossy(&buf[..n]).into_owned()
+        });
+        (port, handle)
+    }
+
+    #[test]
+    fn target_prefers_password_and_falls_back_to_cookie() {
+        let target = RpcTarget::from_entries(&entries("rpcuser=u\nrpcpassword=p\n"), "signet");
+        assert_eq!(target.endpoint(), "127.0.0.1:38332");
+        assert_eq!(
+            target.auth,
+            RpcAuth::Password {
+                user: "u".to_string(),
+                password: "p".to_string()
+            }
+        );
+
+        let target = RpcTarget::from_entries(
+            &entries("datadir=/srv/btc\nrpcport=9999\nrpcconnect=::1\n"),
+            "test",
+        );
+        assert_eq!(target.endpoint(), "[::1]:9999");
+        assert_eq!(
+            target.auth,
+            RpcAuth::Cookie(PathBuf::from("/srv/btc/testnet3/.cookie"))
+        );
+    }
+
+    #[test]
+    fn call_returns_result_and_sends_credentials() {
+        let (port, handle) = serve_once(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\r\n\
+             {\"result\":{\"chain\":\"regtest\"},\"error\":null,\"id\":\"pdm\"}\n",
+        );
+        let target = RpcTarget {
+            host: "127.0.0.1".to_string(),
+            port,
+            auth: RpcAuth::Password {
+                user: "u".to_string(),
+                password: "p".to_string(),
+            },
+        };
+        let result = target
+            .call("getblockchaininfo", Duration::from_secs(5))
+            .unwrap();
+        assert_eq!(result["chain"], "regtest");
+        let request = handle.join().unwrap();
+        assert!(request.contains("Authorization: Basic dTpw\r\n"));
+        assert!(request.contains("\"method\":\"getblockchaininfo\""));
+    }
+
+    #[test]
+    fn call_with_sends_params() {
+        let (port, handle) =
+            serve_once("HTTP/1.1 200 OK\r\n\r\n{\"result\":{\"txouts\":3},\"error\":null}");
+        let target = RpcTarget {
+            host: "127.0.0.1".to_string(),
+            port,
+            auth: RpcAuth::Cookie(PathBuf::from("/dev/null")),
+        };
+        let result = target
+            .call_with(
+                "gettxoutsetinfo",
+                &[Value::from("muhash")],
+                Duration::from_secs(5),
+            )
+            .unwrap();
+        assert_eq!(result["txouts"], 3);
+        assert!(handle.join().unwrap().contains("\"params\":[\"muhash\"]"));
+    }
+
+    #[test]
+    fn call_wallet_posts_to_the_wallet_url() {
+        let (port, handle) = serve_once("HTTP/1.1 200 OK\r\n\r\n{\"result\":[],\"error\":null}");
+        let target = RpcTarget {
+            host: "127.0.0.1".to_string(),
+            port,
+            auth: RpcAuth::Cookie(PathBuf::from("/dev/null")),
+        };
+        target
+            .call_wallet("cold store", "listdescriptors", &[], Duration::from_secs(5))
+            .unwrap();
+        assert!(
+            handle
+                .join()
+                .unwrap()
+                .starts_with("POST /wallet/cold%20store HTTP/1.0\r\n")
+        );
+    }
+
+    #[test]
+    fn probe_chain_reads_getblockchaininfo() {
+        let (port, handle) =
+            serve_once("HTTP/1.1 200 OK\r\n\r\n{\"result\":{\"chain\":\"main\"},\"error\":null}");
+        let entries = entries(&format!("rpcuser=u\nrpcpassword=p\nrpcport={port}\n"));
+        let node = probe_chain(&entries, "signet", Duration::from_secs(5)).unwrap();
+        handle.join().unwrap();
+        assert_eq!(node.chain, "main");
+        assert_eq!(node.endpoint, format!("127.0.0.1:{port}"));
+    }
+
+    #[test]
+    fn test_connection_reports_chain_and_height() {
+        let (port, handle) = serve_once(
+            "HTTP/1.1 200 OK\r\n\r\n{\"result\":{\"chain\":\"signet\",\"blocks\":210000},\"error\":null}",
+        );
+        let target = RpcTarget {
+            host: "127.0.0.1".to_string(),
+            port,
+            auth: RpcAuth::Cookie(PathBuf::from("/dev/null")),
+        };
+        let report = target.test_connection(Duration::from_secs(5)).unwrap();
+        handle.join().unwrap();
+        assert_eq!(report.chain, "signet");
+        assert_eq!(report.blocks, 210_000);
+        assert!(
+            report
+                .to_string()
+                .starts_with(&format!("bitcoind at 127.0.0.1:{port} answered in "))
+        );
+        assert_eq!(target.auth.to_string(), "cookie /dev/null");
+    }
+
+    #[test]
+    fn replies_map_to_errors() {
+        assert!(matches!(
+            parse_reply("HTTP/1.1 401 Unauthorized\r\n\r\n"),
+            Err(RpcError::Unauthorized)
+        ));
+        let err = parse_reply(
+            "HTTP/1.1 500 Internal Server Error\r\n\r\n\
+             {\"result\":null,\"error\":{\"code\":-28,\"message\":\"Loading block index…\"}}",
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "RPC error -28: Loading block index…");
+        assert!(matches!(
+            parse_reply("HTTP/1.1 200 OK\r\n\r\nnot json"),
+            Err(RpcError::Protocol(_))
+        ));
+
+        let missing = RpcTarget {
+            host: "127.0.0.1".to_string(),
+            port: 1,
+            auth: RpcAuth::Cookie(PathBuf::from("/nonexistent/.cookie")),
+        };
+        let err = missing
+            .call("getblockchaininfo", Duration::from_secs(1))
+            .unwrap_err();
+        assert!(matches!(err, RpcError::Cookie(..)));
+    }
+}
@@ -0,0 +1,457 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_config::Assignment;
+use std::collections::BTreeSet;
+
+/// RPC methods of Bitcoin Core 27, by the category `bitcoin-cli help`
+/// lists them under.
+pub const RPC_CATALOG: &[(&str, &[&str])] = &[
+    (
+        "Blockchain",
+        &[
+            "dumptxoutset",
+            "getbestblockhash",
+            "getblock",
+            "getblockchaininfo",
+            "getblockcount",
+            "getblockfilter",
+            "getblockfrompeer",
+            "getblockhash",
+            "getblockheader",
+            "getblockstats",
+            "getchainstates",
+            "getchaintips",
+            "getchaintxstats",
+            "getdeploymentinfo",
+            "getdifficulty",
+            "getmempoolancestors",
+            "getmempooldescendants",
+            "getmempoolentry",
+            "getmempoolinfo",
+            "getrawmempool",
+            "gettxout",
+            "gettxoutproof",
+            "gettxoutsetinfo",
+            "gettxspendingprevout",
+            "importmempool",
+            "loadtxoutset",
+            "preciousblock",
+            "pruneblockchain",
+            "savemempool",
+            "scanblocks",
+            "scantxoutset",
+            "verifychain",
+            "verifytxoutproof",
+        ],
+    ),
+    (
+        "Control",
+        &[
+            "getmemoryinfo",
+            "getrpcinfo",
+            "help",
+            "logging",
+            "stop",
+            "uptime",
+        ],
+    ),
+    (
+        "Mining",
+        &[
+            "getblocktemplate",
+            "getmininginfo",
+            "getnetworkhashps",
+            "getprioritisedtransactions",
+            "prioritisetransaction",
+            "submitblock",
+            "submitheader",
+        ],
+    ),
+    (
+        "Network",
+        &[
+            "addnode",
+            "clearbanned",
+            "disconnectnode",
+            "getaddednodeinfo",
+            "getaddrmaninfo",
+            "getconnectioncount",
+            "getnettotals",
+            "getnetworkinfo",
+            "getnodeaddresses",
+            "getpeerinfo",
+            "listbanned",
+            "ping",
+            "setban",
+            "setnetworkactive",
+        ],
+    ),
+    (
+        "Rawtransactions",
+        &[
+            "analyzepsbt",
+            "combinepsbt",
+            "combinerawtransaction",
+            "converttopsbt",
+            "createpsbt",
+            "createrawtransaction",
+            "decodepsbt",
+            "decoderawtransaction",
+            "decodescript",
+            "descriptorprocesspsbt",
+            "finalizepsbt",
+            "fundrawtransaction",
+            "getrawtransaction",
+            "joinpsbts",
+            "sendrawtransaction",
+            "signrawtransactionwithkey",
+            "submitpackage",
+            "testmempoolaccept",
+            "utxoupdatepsbt",
+        ],
+    ),
+    ("Signer", &["enumeratesigners"]),
+    (
+        "Util",
+        &[
+            "createmultisig",
+            "deriveaddresses",
+            "estimatesmartfee",
+            "getdescriptorinfo",
+            "getindexinfo",
+            "signmessagewithprivkey",
+            "validateaddress",
+            "verifymessage",
+        ],
+    ),
+    (
+        "Wallet",
+        &[
+            "abandontransaction",
+            "abortrescan",
+            "addmultisigaddress",
+            "backupwallet",
+            "bumpfee",
+            "createwallet",
+            "createwalletdescriptor",
+            "dumpprivkey",
+            "dumpwallet",
+            "encryptwallet",
+            "getaddressesbylabel",
+            "getaddressinfo",
+            "getbalance",
+            "getbalances",
+            "gethdkeys",
+            "getnewaddress",
+            "getrawchangeaddress",
+            "getreceivedbyaddress",
+            "getreceivedbylabel",
+            "gettransaction",
+            "getunconfirmedbalance",
+            "getwalletinfo",
+            "importaddress",
+            "importdescriptors",
+            "importmulti",
+            "importprivkey",
+            "importprunedfunds",
+            "importpubkey",
+            "importwallet",
+            "keypoolrefill",
+            "listaddressgroupings",
+            "listdescriptors",
+            "listlabels",
+            "listlockunspent",
+            "listreceivedbyaddress",
+            "listreceivedbylabel",
+            "listsinceblock",
+            "listtransactions",
+            "listunspent",
+            "listwalletdir",
+            "listwallets",
+            "loadwallet",
+            "lockunspent",
+            "migratewallet",
+            "newkeypool",
+            "psbtbumpfee",
+            "removeprunedfunds",
+            "rescanblockchain",
+            "restorewallet",
+            "send",
+            "sendall",
+            "sendmany",
+            "sendtoaddress",
+            "sethdseed",
+            "setlabel",
+            "settxfee",
+            "setwalletflag",
+            "signmessage",
+            "signrawtransactionwithwallet",
+            "simulaterawtransaction",
+            "unloadwallet",
+            "upgradewallet",
+            "walletcreatefundedpsbt",
+            "walletdisplayaddress",
+            "walletlock",
+            "walletpassphrase",
+            "walletpassphrasechange",
+            "walletprocesspsbt",
+        ],
+    ),
+    ("Zmq", &["getzmqnotifications"]),
+];
+
+/// One row of the builder: a category heading or one of its methods.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CatalogRow {
+    Category(&'static str, &'static [&'static str]),
+    Method(&'static str),
+}
+
+/// [`RPC_CATALOG`] flattened into rows, each category before its methods.
+#[must_use]
+pub fn catalog_rows() -> Vec<CatalogRow> {
+    RPC_CATALOG
+        .iter()
+        .flat_map(|&(category, methods)| {
+            std::iter::once(CatalogRow::Category(category, methods))
+                .chain(methods.iter().map(|&m| CatalogRow::Method(m)))
+        })
+        .collect()
+}
+
+/// The user and methods of an `rpcwhitelist` value, `user:method,method`.
+#[must_use]
+pub fn parse_whitelist(value: &str) -> Option<(&str, Vec<&str>)> {
+    let (user, methods) = value.split_once(':')?;
+    let methods = methods
+        .split(',')
+        .map(str::trim)
+        .filter(|m| !m.is_empty())
+        .collect();
+    Some((user.trim(), methods))
+}
+
+/// State of the rpcwhitelist builder: the RPC users of a bitcoin.conf and
+/// the methods picked for each.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WhitelistBuilder {
+    /// Users in file order, with the methods they may call
+    pub users: Vec<(String, BTreeSet<String>)>,
+    /// Index into `users` being edited
+    pub user: usize,
+    /// Index into [`catalog_rows`] under the cursor
+    pub cursor: usize,
+}
+
+impl WhitelistBuilder {
+    /// Builder for the users `rpcuser` and `rpcauth` lines define, and any
+    /// only `rpcwhitelist` mentions, with their current whitelists. Only
+    /// top-level lines count, as the builder writes them there. `None`
+    /// when the file defines no RPC user.
+    #[must_use]
+    pub fn from_assignments(assignments: &[Assignment]) -> Option<Self> {
+        let mut users: Vec<(String, BTreeSet<String>)> = Vec::new();
+        for a in assignments.iter().filter(|a| a.section.is_none()) {
+            let (name, methods) = match a.key.as_str() {
+                "rpcuser" if !a.value.is_empty() => (a.value.as_str(), Vec::new()),
+                "rpcauth" => match a.value.split_once(':') {
+                    Some((name, _)) => (name, Vec::new()),
+                    None => continue,
+                },
+                "rpcwhitelist" => match parse_whitelist(&a.value) {
+                    Some(whitelist) => whitelist,
+                    None => continue,
+                },
+                _ => continue,
+            };
+            let i = users
+                .iter()
+                .position(|(u, _)| u == name)
+                .unwrap_or_else(|| {
+                    users.push((name.to_string(), BTreeSet::new()));
+                    users.len() - 1
+                });
+            users[i].1.extend(methods.into_iter().map(str::to_string));
+        }
+        (!users.is_empty()).then_some(Self {
+            users,
+            user: 0,
+            cursor: 0,
+        })
+    }
+
+    /// Methods picked for the user being edited.
+    #[must_use]
+    pub fn methods(&self) -> &BTreeSet<String> {
+        &self.users[self.user].1
+    }
+
+    /// Moves to the previous (`forward` = false) or next user, wrapping.
+    pub fn switch_user(&mut self, forward: bool) {
+        let n = self.users.len();
+        self.user = if forward {
+            (self.user + 1) % n
+        } else {
+            (self.user + n - 1) % n
+        };
+    }
+
+    /// Toggles the method under the cursor. On a category heading, picks
+    /// all of its methods unless they all are already, then drops them.
+    pub fn toggle(&mut self) {
+        let methods = &mut self.users[self.user].1;
+        match catalog_rows()[self.cursor] {
+            CatalogRow::Method(method) => {
+                if !methods.remove(method) {
+                    methods.insert(method.to_string());
+                }
+            }
+            CatalogRow::Category(_, all) => {
+                if all.iter().all(|m| methods.contains(*m)) {
+                    for m in all {
+                        methods.remove(*m);
+                    }
+                } else {
+                    methods.extend(all.iter().map(|m| (*m).to_string()));
+                }
+            }
+        }
+    }
+
+    /// The `rpcwhitelist` lines for every user with methods picked.
+    #[must_use]
+    pub fn lines(&self) -> Vec<String> {
+        self.users
+            .iter()
+            .filter(|(_, methods)| !methods.is_empty())
+            .map(|(user, methods)| {
+                let methods: Vec<&str> = methods.iter().map(String::as_str).collect();
+                format!("rpcwhitelist={user}:{}", methods.join(","))
+            })
+            .collect()
+    }
+}
+
+/// `content` with its top-level `rpcwhitelist` lines replaced by `lines`,
+/// placed where the first of the old ones was, or else before the first
+/// network section.
+#[must_use]
+pub fn replace_whitelists(content: &str, lines: &[String]) -> String {
+    let assignments = crate::bitcoin_config::read_assignments(content);
+    let old: Vec<usize> = assignments
+        .iter()
+        .filter(|a| a.section.is_none() && a.key == "rpcwhitelist")
+        .map(|a| a.line)
+        .collect();
+    let first_section = content
+        .lines()
+        .position(|l| l.trim_start().starts_with('['))
+        .map(|i| i + 1);
+    let at = old.first().copied().or(first_section);
+
+    let mut out = String::with_capacity(content.len());
+    let push_lines = |out: &mut String| {
+        for line in lines {
+            out.push_str(line);
+            out.push('\n');
+        }
+    };
+    for (i, line) in content.lines().enumerate() {
+        if Some(i + 1) == at {
+            push_lines(&mut out);
+        }
+        if !old.contains(&(i + 1)) {
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+    if at.is_none() {
+        push_lines(&mut out);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitcoin_config::read_assignments;
+
+    const CONF: &str = "server=1\n\
+        rpcauth=alice:f7efda5c189b999524f151318c0c86$d5b51b3beffbc0\n\
+        rpcauth=bob:8a1b2c$0f0e\n\
+        rpcwhitelist=bob:getblockcount, getbestblockhash\n\
+        \n\
+        [test]\n\
+        rpcwhitelist=carol:stop\n";
+
+    #[test]
+    fn builder_reads_users_and_their_whitelists() {
+        let builder = WhitelistBuilder::from_assignments(&read_assignments(CONF)).unwrap();
+        let users: Vec<&str> = builder.users.iter().map(|(u, _)| u.as_str()).collect();
+        assert_eq!(users, ["alice", "bob"]);
+        assert!(builder.methods().is_empty());
+        assert_eq!(
+            builder.lines(),
+            ["rpcwhitelist=bob:getbestblockhash,getblockcount"]
+        );
+        assert!(WhitelistBuilder::from_assignments(&read_assignments("server=1\n")).is_none());
+    }
+
+    #[test]
+    fn toggling_picks_methods_and_whole_categories() {
+        let mut builder = WhitelistBuilder::from_assignments(&read_assignments(CONF)).unwrap();
+        let rows = catalog_rows();
+        builder.cursor = rows
+            .iter()
+            .position(|r| *r == CatalogRow::Method("getblockcount"))
+            .unwrap();
+        builder.toggle();
+        assert_eq!(builder.lines()[0], "rpcwhitelist=alice:getblockcount");
+        builder.toggle();
+        assert_eq!(builder.lines().len(), 1);
+
+        builder.cursor = rows
+            .iter()
+            .position(|r| matches!(r, CatalogRow::Category("Zmq", _)))
+            .unwrap();
+        builder.toggle();
+        assert!(builder.methods().contains("getzmqnotifications"));
+        builder.toggle();
+        assert!(builder.methods().is_empty());
+
+        builder.switch_user(false);
+        assert_eq!(builder.user, 1);
+        assert_eq!(builder.methods().len(), 2);
+    }
+
+    #[test]
+    fn whitelists_replace_the_old_lines() {
+        let lines = vec![
+            "rpcwhitelist=alice:getblockcount".to_string(),
+            "rpcwhitelist=bob:getpeerinfo".to_string(),
+        ];
+        let out = replace_whitelists(CONF, &lines);
+        assert_eq!(
+            out,
+            "server=1\n\
+            rpcauth=alice:f7efda5c189b999524f151318c0c86$d5b51b3beffbc0\n\
+            rpcauth=bob:8a1b2c$0f0e\n\
+            rpcwhitelist=alice:getblockcount\n\
+            rpcwhitelist=bob:getpeerinfo\n\
+            \n\
+            [test]\n\
+            rpcwhitelist=carol:stop\n"
+        );
+
+        // Without old lines they go before the first section
+        let out = replace_whitelists("rpcuser=u\n[main]\nport=1\n", &lines[..1]);
+        assert_eq!(
+            out,
+            "rpcuser=u\nrpcwhitelist=alice:getblockcount\n[main]\nport=1\n"
+        );
+        let out = replace_whitelists("rpcuser=u\n", &[]);
+        assert_eq!(out, "rpcuser=u\n");
+    }
+}
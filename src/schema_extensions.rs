@@ -0,0 +1,486 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::asmap::DOWNLOAD_TIMEOUT;
+use crate::bitcoin_config::{ConfigCategory, ConfigSchema, ConfigType, NodeFlavor};
+use crate::download::fetch;
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::thread::JoinHandle;
+
+/// Directory, inside the PDM config directory, extra schema files are
+/// read from.
+pub const SCHEMAS_DIR: &str = "schemas";
+
+/// Name, without extension, of the downloaded bundle in `SCHEMAS_DIR`.
+/// It sorts before the user's own files so they still override it.
+pub const BUNDLE_NAME: &str = "00-pdm-bundle";
+
+/// Extensions in use, merged into every schema.
+static REGISTERED: RwLock<Vec<SchemaExtension>> = RwLock::new(Vec::new());
+
+/// Bumped by every registration, so schemas merged before can tell they
+/// are stale.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// An option defined in a schema file, for the node flavor it names or
+/// for all of them.
+#[derive(Debug, Clone)]
+pub struct SchemaExtension {
+    pub flavor: Option<NodeFlavor>,
+    pub schema: ConfigSchema,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SchemaFile {
+    #[serde(default)]
+    option: Vec<OptionDef>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OptionDef {
+    key: String,
+    #[serde(rename = "type")]
+    config_type: String,
+    category: String,
+    description: String,
+    #[serde(default)]
+    default: String,
+    example: Option<String>,
+    flavor: Option<NodeFlavor>,
+}
+
+fn config_type(name: &str) -> Result<ConfigType> {
+    Ok(match name.to_ascii_lowercase().as_str() {
+        "bool" | "boolean" => ConfigType::Bool,
+        "int" | "integer" => ConfigType::Int,
+        "float" => ConfigType::Float,
+        "string" => ConfigType::String,
+        "path" => ConfigType::Path,
+        "address" => ConfigType::Address,
+        _ => bail!("unknown type {name}: use bool, int, float, string, path or address"),
+    })
+}
+
+fn category(name: &str) -> Result<ConfigCategory> {
+    Ok(match name.to_ascii_lowercase().as_str() {
+        "core" => ConfigCategory::Core,
+        "network" => ConfigCategory::Network,
+        "rpc" => ConfigCategory::RPC,
+        "wallet" => ConfigCategory::Wallet,
+        "debugging" | "debug" => ConfigCategory::Debugging,
+        "mining" => ConfigCategory::Mining,
+        "relay" => ConfigCategory::Relay,
+        "zmq" => ConfigCategory::ZMQ,
+        _ => bail!(
+            "unknown category {name}: use core, network, rpc, wallet, debugging, mining, \
+             relay or zmq"
+        ),
+    })
+}
+
+/// Parses a schema file: a list of `option` tables, each with `key`,
+/// `type`, `category`, `description` and optionally `default`,
+/// `example` and `flavor`. `json` picks JSON over TOML.
+///
+/// # Errors
+/// Returns an error if the file does not parse or an option has an empty
+/// key, an unknown type or an unknown category.
+pub fn parse_extensions(text: &str, json: bool) -> Result<Vec<SchemaExtension>> {
+    let file: SchemaFile = if json {
+        serde_json::from_str(text)?
+    } else {
+        toml::from_str(text)?
+    };
+    file.option
+        .into_iter()
+        .map(|def| {
+            let key = def.key.trim();
+            if key.is_empty() || key.contains(['=', ' ', '#']) {
+                bail!("invalid key {:?}", def.key);
+            }
+            let kind = config_type(&def.config_type).map_err(|e| e.context(key.to_string()))?;
+            let category = category(&def.category).map_err(|e| e.context(key.to_string()))?;
+            let mut schema = ConfigSchema::new(key, &def.default, kind, category, &def.description);
+            if def.example.is_some() {
+                schema.example = def.example;
+            }
+            Ok(SchemaExtension {
+                flavor: def.flavor,
+                schema,
+            })
+        })
+        .collect()
+}
+
+/// Reads every `.toml` and `.json` file in `dir`, in name order. Files
+/// that fail are left out and reported as `file: reason`; a missing
+/// directory is no error.
+#[must_use]
+pub fn load_extensions(dir: &Path) -> (Vec<SchemaExtension>, Vec<String>) {
+    let Ok(read) = std::fs::read_dir(dir) else {
+        return (Vec::new(), Vec::new());
+    };
+    let mut paths: Vec<_> = read
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| {
+            matches!(
+                p.extension().and_then(|e| e.to_str()),
+                Some("toml" | "json")
+            )
+        })
+        .collect();
+    paths.sort();
+    let mut extensions = Vec::new();
+    let mut errors = Vec::new();
+    for path in paths {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let json = path.extension().is_some_and(|e| e == "json");
+        match std::fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|text| parse_extensions(&text, json))
+        {
+            Ok(found) => extensions.extend(found),
+            Err(e) => errors.push(format!("{name}: {e:#}")),
+        }
+    }
+    (extensions, errors)
+}
+
+/// Makes `extensions` part of every schema from now on, in place of
+/// those registered before.
+pub fn register_extensions(extensions: Vec<SchemaExtension>) {
+    if let Ok(mut registered) = REGISTERED.write() {
+        *registered = extensions;
+        GENERATION.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Counts the registrations so far; a schema merged under another count
+/// must be merged again.
+#[must_use]
+pub fn generation() -> u64 {
+    GENERATION.load(Ordering::Relaxed)
+}
+
+/// Adds the registered extensions for `flavor` to `schema`, replacing
+/// built-in options of the same key.
+pub fn extend_schema(schema: &mut Vec<Arc<ConfigSchema>>, flavor: NodeFlavor) {
+    if let Ok(registered) = REGISTERED.read() {
+        merge(schema, &registered, flavor);
+    }
+}
+
+/// Fingerprints of the keys behind every good signature in gpg's
+/// `--status-fd` output: the signing key and its primary key.
+fn valid_signers(status: &str) -> Vec<String> {
+    status
+        .lines()
+        .filter_map(|line| line.strip_prefix("[GNUPG:] VALIDSIG "))
+        .flat_map(|rest| {
+            let fields: Vec<&str> = rest.split_whitespace().collect();
+            [fields.first().copied(), fields.get(9).copied()]
+        })
+        .flatten()
+        .map(str::to_ascii_uppercase)
+        .collect()
+}
+
+/// Checks with gpg that `signature` is a good detached signature over
+/// `data` by the key with `fingerprint`, which must be in the keyring at
+/// `gnupg_home`, or the user's when it is `None`.
+///
+/// # Errors
+/// Returns an error if gpg cannot be run, rejects the signature or the
+/// signature is by another key.
+pub fn verify_signature(
+    data: &Path,
+    signature: &Path,
+    fingerprint: &str,
+    gnupg_home: Option<&Path>,
+) -> Result<()> {
+    let mut gpg = Command::new("gpg");
+    if let Some(home) = gnupg_home {
+        gpg.env("GNUPGHOME", home);
+    }
+    let output = gpg
+        .args(["--batch", "--status-fd", "1", "--verify"])
+        .arg(signature)
+        .arg(data)
+        .output()
+        .context("cannot run gpg to check the signature")?;
+    let wanted: String = fingerprint
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_ascii_uppercase();
+    let signers = valid_signers(&String::from_utf8_lossy(&output.stdout));
+    if output.status.success() && signers.contains(&wanted) {
+        return Ok(());
+    }
+    if signers.is_empty() {
+        bail!(
+            "bad or unknown signature (is key {fingerprint} imported?): {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    bail!("signed by {}, not {fingerprint}", signers.join(", "))
+}
+
+/// Downloads the option bundle at `url` and its signature at `<url>.asc`
+/// and, once the signature checks out against `fingerprint` and every
+/// option parses, installs it in `dir` as `BUNDLE_NAME`. Returns the
+/// number of options in it.
+///
+/// # Errors
+/// Returns an error if no signing key is set, a download fails, the
+/// signature does not verify or the bundle does not parse. The bundle
+/// in use is left untouched then.
+pub fn update_bundle(url: &str, fingerprint: Option<&str>, dir: &Path) -> Result<usize> {
+    install_bundle(url, fingerprint, dir, None)
+}
+
+/// [`update_bundle`], checking the signature against the keyring at
+/// `gnupg_home`.
+fn install_bundle(
+    url: &str,
+    fingerprint: Option<&str>,
+    dir: &Path,
+    gnupg_home: Option<&Path>,
+) -> Result<usize> {
+    let Some(fingerprint) = fingerprint else {
+        bail!(
+            "set schema_signing_key in settings.toml to the fingerprint of the key that \
+             signs {url}"
+        );
+    };
+    let data = fetch(url, DOWNLOAD_TIMEOUT).with_context(|| format!("cannot download {url}"))?;
+    let signature = fetch(&format!("{url}.asc"), DOWNLOAD_TIMEOUT)
+        .with_context(|| format!("cannot download the signature {url}.asc"))?;
+    // A private directory of its own, removed when dropped
+    let staging = tempfile::tempdir()?;
+    let data_path = staging.path().join("bundle");
+    let signature_path = staging.path().join("bundle.asc");
+    std::fs::write(&data_path, &data)?;
+    std::fs::write(&signature_path, &signature)?;
+    verify_signature(&data_path, &signature_path, fingerprint, gnupg_home)?;
+
+    let json = Path::new(url).extension().is_some_and(|e| e == "json");
+    let text = String::from_utf8(data).context("bundle is not UTF-8")?;
+    let count = parse_extensions(&text, json)?.len();
+    std::fs::create_dir_all(dir)?;
+    let extension = if json { "json" } else { "toml" };
+    std::fs::write(dir.join(format!("{BUNDLE_NAME}.{extension}")), text)?;
+    Ok(count)
+}
+
+/// A bundle update running in the background, so the screen keeps
+/// drawing while the bundle downloads and gpg checks it.
+pub struct BundleUpdate {
+    /// Where the bundle is installed
+    pub dir: PathBuf,
+    handle: JoinHandle<Result<usize>>,
+}
+
+impl BundleUpdate {
+    /// Starts installing the bundle at `url` in `dir`, as
+    /// [`update_bundle`] does.
+    #[must_use]
+    pub fn start(url: &str, fingerprint: Option<&str>, dir: PathBuf) -> Self {
+        let url = url.to_string();
+        let fingerprint = fingerprint.map(str::to_string);
+        let target = dir.clone();
+        Self {
+            dir,
+            handle: std::thread::spawn(move || {
+                update_bundle(&url, fingerprint.as_deref(), &target)
+            }),
+        }
+    }
+
+    /// Whether the update is over.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// The number of options installed, once the update is over; waits
+    /// for it otherwise.
+    ///
+    /// # Errors
+    /// Returns what [`update_bundle`] returned.
+    pub fn finish(self) -> Result<usize> {
+        self.handle
+            .join()
+            .unwrap_or_else(|_| bail!("bundle update panicked"))
+    }
+}
+
+fn merge(schema: &mut Vec<Arc<ConfigSchema>>, extensions: &[SchemaExtension], flavor: NodeFlavor) {
+    for extension in extensions
+        .iter()
+        .filter(|e| e.flavor.is_none_or(|f| f == flavor))
+    {
+        let added = Arc::new(extension.schema.clone());
+        match schema.iter_mut().find(|s| s.key == extension.schema.key) {
+            Some(existing) => *existing = added,
+            None => schema.push(added),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOML: &str = r#"
+[[option]]
+key = "maxtipage"
+type = "int"
+category = "Core"
+description = "Maximum tip age in seconds"
+default = "86400"
+example = "3600"
+
+[[option]]
+key = "dbcache"
+type = "int"
+category = "core"
+description = "Database cache size in MiB (patched)"
+default = "1000"
+flavor = "knots"
+"#;
+
+    #[test]
+    fn toml_and_json_files_define_options() {
+        let found = parse_extensions(TOML, false).unwrap();
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].schema.key, "maxtipage");
+        assert_eq!(found[0].schema.config_type, ConfigType::Int);
+        assert_eq!(found[0].schema.example.as_deref(), Some("3600"));
+        assert_eq!(found[1].flavor, Some(NodeFlavor::Knots));
+
+        let json = r#"{"option": [{"key": "walletrbf", "type": "bool",
+            "category": "wallet", "description": "Opt into RBF"}]}"#;
+        let found = parse_extensions(json, true).unwrap();
+        assert_eq!(found[0].schema.category, ConfigCategory::Wallet);
+        assert_eq!(found[0].schema.default, "");
+
+        let bad =
+            "[[option]]\nkey = \"x\"\ntype = \"list\"\ncategory = \"core\"\ndescription = \"\"\n";
+        let err = parse_extensions(bad, false).unwrap_err();
+        assert!(format!("{err:#}").starts_with("x: unknown type list"));
+    }
+
+    #[test]
+    fn merge_adds_options_and_overrides_built_ins_per_flavor() {
+        let extensions = parse_extensions(TOML, false).unwrap();
+        let mut core = vec![Arc::new(ConfigSchema::new(
+            "dbcache",
+            "450",
+            ConfigType::Int,
+            ConfigCategory::Core,
+            "Database cache size in MiB",
+        ))];
+        let mut knots = core.clone();
+        merge(&mut core, &extensions, NodeFlavor::Core);
+        merge(&mut knots, &extensions, NodeFlavor::Knots);
+        assert_eq!(core.len(), 2);
+        assert_eq!(core[0].default, "450");
+        assert_eq!(knots[0].default, "1000");
+        assert_eq!(knots[1].key, "maxtipage");
+    }
+
+    #[test]
+    fn broken_files_are_reported_and_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.toml"), TOML).unwrap();
+        std::fs::write(dir.path().join("b.json"), "{").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+        let (found, errors) = load_extensions(dir.path());
+        assert_eq!(found.len(), 2);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("b.json: "));
+        assert!(load_extensions(&dir.path().join("missing")).1.is_empty());
+    }
+
+    #[test]
+    fn good_signatures_name_signing_and_primary_keys() {
+        let status = "[GNUPG:] NEWSIG\n\
+            [GNUPG:] GOODSIG 0123456789ABCDEF PDM\n\
+            [GNUPG:] VALIDSIG aaaa1111 2024-05-01 1714521600 0 4 0 22 10 00 bbbb2222\n";
+        assert_eq!(valid_signers(status), ["AAAA1111", "BBBB2222"]);
+        assert!(valid_signers("[GNUPG:] BADSIG 0123 PDM\n").is_empty());
+    }
+
+    #[test]
+    fn bundle_is_installed_only_with_a_good_signature() {
+        let dir = tempfile::tempdir().unwrap();
+        let schemas = dir.path().join(SCHEMAS_DIR);
+        let bundle = dir.path().join("options.toml");
+        std::fs::write(&bundle, TOML).unwrap();
+        let url = format!("file://{}", bundle.display());
+        let err = update_bundle(&url, None, &schemas).unwrap_err();
+        assert!(err.to_string().contains("schema_signing_key"));
+
+        let home = tempfile::tempdir().unwrap();
+        let gpg = |args: &[&str]| {
+            Command::new("gpg")
+                .env("GNUPGHOME", home.path())
+                .args(["--batch", "--passphrase", "", "--pinentry-mode", "loopback"])
+                .args(args)
+                .output()
+        };
+        let made = gpg(&["--quick-gen-key", "PDM test", "ed25519", "sign", "never"]);
+        if !made.is_ok_and(|o| o.status.success()) {
+            eprintln!(
+                "skipping bundle_is_installed_only_with_a_good_signature: gpg cannot make a key"
+            );
+            return;
+        }
+        let listing = gpg(&["--with-colons", "--list-keys"]).unwrap();
+        let listing = String::from_utf8_lossy(&listing.stdout);
+        let fingerprint = listing
+            .lines()
+            .find_map(|l| l.strip_prefix("fpr:"))
+            .map(|l| l.trim_matches(':').to_string())
+            .unwrap();
+        let signature = format!("{}.asc", bundle.display());
+        let signed = gpg(&[
+            "--yes",
+            "--armor",
+            "--output",
+            &signature,
+            "--detach-sign",
+            &bundle.display().to_string(),
+        ])
+        .unwrap();
+        assert!(signed.status.success());
+
+        let home = Some(home.path());
+        let err = install_bundle(&url, Some("0000"), &schemas, home).unwrap_err();
+        assert!(err.to_string().starts_with("signed by"));
+        assert!(!schemas.exists());
+        assert_eq!(
+            install_bundle(&url, Some(&fingerprint), &schemas, home).unwrap(),
+            2
+        );
+        let (found, errors) = load_extensions(&schemas);
+        assert_eq!((found.len(), errors.len()), (2, 0));
+
+        std::fs::write(&bundle, TOML.replace("86400", "1")).unwrap();
+        assert!(install_bundle(&url, Some(&fingerprint), &schemas, home).is_err());
+        assert!(
+            std::fs::read_to_string(schemas.join(format!("{BUNDLE_NAME}.toml")))
+                .unwrap()
+                .contains("86400")
+        );
+    }
+}
@@ -0,0 +1,179 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::app::App;
+use crate::ui;
+use anyhow::Result;
+use ratatui::Terminal;
+use ratatui::backend::TestBackend;
+use ratatui::buffer::{Buffer, Cell};
+use ratatui::style::{Color, Modifier};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use unicode_width::UnicodeWidthStr;
+
+/// Size rendered when the terminal's cannot be read.
+pub const FALLBACK_SIZE: (u16, u16) = (120, 40);
+
+/// Folder of the config directory screenshots are written to.
+pub const SCREENSHOTS_DIR: &str = "screenshots";
+
+/// Escape sequence resetting every attribute.
+const RESET: &str = "\x1b[0m";
+
+/// The screen `app` shows, drawn again off-screen at `width` x `height`.
+///
+/// # Errors
+/// Returns an error if the frame cannot be drawn.
+pub fn render_screen(app: &mut App, width: u16, height: u16) -> Result<Buffer> {
+    let mut terminal = Terminal::new(TestBackend::new(width, height))?;
+    terminal.draw(|f| ui::ui(f, app))?;
+    Ok(terminal.backend().buffer().clone())
+}
+
+/// Rows of `buffer`, as the cells to print; the cells a wide character
+/// covers are left out.
+fn rows(buffer: &Buffer) -> impl Iterator<Item = Vec<&Cell>> {
+    buffer
+        .content
+        .chunks(usize::from(buffer.area.width.max(1)))
+        .map(|cells| {
+            let mut skip = 0;
+            cells
+                .iter()
+                .filter(|cell| {
+                    let shown = skip == 0;
+                    skip = skip.max(cell.symbol().width()).saturating_sub(1);
+                    shown
+                })
+                .collect()
+        })
+}
+
+/// `buffer` as plain text, without trailing spaces.
+#[must_use]
+pub fn to_text(buffer: &Buffer) -> String {
+    let mut out = String::new();
+    for row in rows(buffer) {
+        let line: String = row.iter().map(|c| c.symbol()).collect();
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+/// SGR parameter of `color`, as a foreground or background.
+fn color_code(color: Color, background: bool) -> String {
+    let base = |n: u8| (if background { n + 10 } else { n }).to_string();
+    match color {
+        Color::Reset => base(39),
+        Color::Black => base(30),
+        Color::Red => base(31),
+        Color::Green => base(32),
+        Color::Yellow => base(33),
+        Color::Blue => base(34),
+        Color::Magenta => base(35),
+        Color::Cyan => base(36),
+        Color::Gray => base(37),
+        Color::DarkGray => base(90),
+        Color::LightRed => base(91),
+        Color::LightGreen => base(92),
+        Color::LightYellow => base(93),
+        Color::LightBlue => base(94),
+        Color::LightMagenta => base(95),
+        Color::LightCyan => base(96),
+        Color::White => base(97),
+        Color::Indexed(n) => format!("{};5;{n}", base(38)),
+        Color::Rgb(r, g, b) => format!("{};2;{r};{g};{b}", base(38)),
+    }
+}
+
+/// Escape sequence switching to the colors and modifiers of `cell`.
+fn style_sequence(cell: &Cell) -> String {
+    const MODIFIERS: &[(Modifier, &str)] = &[
+        (Modifier::BOLD, "1"),
+        (Modifier::DIM, "2"),
+        (Modifier::ITALIC, "3"),
+        (Modifier::UNDERLINED, "4"),
+        (Modifier::REVERSED, "7"),
+        (Modifier::CROSSED_OUT, "9"),
+    ];
+    let mut codes = vec!["0".to_string()];
+    codes.extend(
+        MODIFIERS
+            .iter()
+            .filter(|(m, _)| cell.modifier.contains(*m))
+            .map(|(_, code)| (*code).to_string()),
+    );
+    codes.push(color_code(cell.fg, false));
+    codes.push(color_code(cell.bg, true));
+    format!("\x1b[{}m", codes.join(";"))
+}
+
+/// `buffer` with its colors as ANSI escape sequences, for `cat` or
+/// `less -R`.
+#[must_use]
+pub fn to_ansi(buffer: &Buffer) -> String {
+    let mut out = String::new();
+    for row in rows(buffer) {
+        let mut style = None;
+        for cell in row {
+            let sequence = style_sequence(cell);
+            if style.as_ref() != Some(&sequence) {
+                out.push_str(&sequence);
+                style = Some(sequence);
+            }
+            out.push_str(cell.symbol());
+        }
+        let _ = writeln!(out, "{RESET}");
+    }
+    out
+}
+
+/// Writes `buffer` into `dir` as `pdm-screen-<stamp>.txt` and, with its
+/// colors, `.ans`. Returns the text file's path.
+///
+/// # Errors
+/// Returns an error if `dir` cannot be created or a file written.
+pub fn write_screenshot(dir: &Path, stamp: u64, buffer: &Buffer) -> Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let text = dir.join(format!("pdm-screen-{stamp}.txt"));
+    std::fs::write(&text, to_text(buffer))?;
+    std::fs::write(text.with_extension("ans"), to_ansi(buffer))?;
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::layout::Rect;
+    use ratatui::style::Style;
+
+    #[test]
+    fn screen_exports_as_text_and_ansi() {
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 6, 2));
+        buffer.set_string(0, 0, "ok", Style::default().fg(Color::Green));
+        buffer.set_string(3, 0, "界", Style::default().add_modifier(Modifier::BOLD));
+        assert_eq!(to_text(&buffer), "ok 界\n\n");
+        assert_eq!(
+            to_ansi(&buffer),
+            "\x1b[0;32;49mok\x1b[0;39;49m \x1b[0;1;39;49m界\x1b[0;39;49m \x1b[0m\n\
+             \x1b[0;39;49m      \x1b[0m\n"
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_screenshot(&dir.path().join("shots"), 7, &buffer).unwrap();
+        assert!(path.ends_with("pdm-screen-7.txt"));
+        assert!(path.with_extension("ans").is_file());
+    }
+
+    #[test]
+    fn current_screen_renders_off_screen() {
+        let mut app = App::new();
+        let buffer = render_screen(&mut app, 80, 24).unwrap();
+        let text = to_text(&buffer);
+        assert_eq!(text.lines().count(), 24);
+        assert!(text.contains("Bitcoin Config"));
+    }
+}
@@ -0,0 +1,236 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_config::ConfigEntry;
+use anyhow::{Result, bail};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// bitcoin.conf options whose values can be moved into the keyring.
+pub const SECRET_OPTIONS: &[&str] = &["rpcpassword", "torpassword"];
+
+/// Keyring service name PDM stores its secrets under.
+const SERVICE: &str = "pdm";
+
+/// Options whose values are never shown outside the editor: in the change
+/// journal, commit messages or exports.
+#[must_use]
+pub fn is_secret(key: &str) -> bool {
+    key.contains("password") || key.ends_with("rpcauth")
+}
+
+/// Somewhere to keep secrets outside the config files.
+pub trait SecretStore {
+    /// Stores `secret` under `account`, replacing any previous value.
+    ///
+    /// # Errors
+    /// Returns an error if the store is unavailable.
+    fn store(&self, account: &str, secret: &str) -> Result<()>;
+
+    /// Returns the secret stored under `account`.
+    ///
+    /// # Errors
+    /// Returns an error if the store is unavailable or holds no such secret.
+    fn lookup(&self, account: &str) -> Result<String>;
+}
+
+/// The operating system keyring, through `secret-tool` (libsecret) on Linux
+/// and `security` on macOS.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OsKeyring;
+
+impl SecretStore for OsKeyring {
+    fn store(&self, account: &str, secret: &str) -> Result<()> {
+        let (mut command, input) = if cfg!(target_os = "macos") {
+            // A trailing -w makes security prompt for the password, then
+            // for it again, on stdin
+            let mut command = Command::new("security");
+            command
+                .args(["add-generic-password", "-U", "-s", SERVICE, "-a", account])
+                .arg("-w");
+            (command, format!("{secret}\n{secret}\n"))
+        } else {
+            let mut command = Command::new("secret-tool");
+            command
+                .args(["store", "--label", &format!("PDM {account}")])
+                .args(["service", SERVICE, "account", account]);
+            (command, secret.to_string())
+        };
+        // Both tools read the secret from stdin, keeping it out of `ps`
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(input.as_bytes())?;
+        }
+        let status = child.wait()?;
+        if !status.success() {
+            bail!("the keyring refused to store {account}");
+        }
+        Ok(())
+    }
+
+    fn lookup(&self, account: &str) -> Result<String> {
+        let output = if cfg!(target_os = "macos") {
+            Command::new("security")
+                .args(["find-generic-password", "-s", SERVICE, "-a", account, "-w"])
+                .output()?
+        } else {
+            Command::new("secret-tool")
+                .args(["lookup", "service", SERVICE, "account", account])
+                .output()?
+        };
+        if !output.status.success() || output.stdout.is_empty() {
+            bail!("no secret for {account} in the keyring");
+        }
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .trim_end_matches(['\r', '\n'])
+            .to_string())
+    }
+}
+
+/// In-process store, for tests and machines without a keyring.
+#[derive(Debug, Default)]
+pub struct MemoryStore(RefCell<HashMap<String, String>>);
+
+impl SecretStore for MemoryStore {
+    fn store(&self, account: &str, secret: &str) -> Result<()> {
+        self.0
+            .borrow_mut()
+            .insert(account.to_string(), secret.to_string());
+        Ok(())
+    }
+
+    fn lookup(&self, account: &str) -> Result<String> {
+        match self.0.borrow().get(account) {
+            Some(secret) => Ok(secret.clone()),
+            None => bail!("no secret for {account}"),
+        }
+    }
+}
+
+/// Keyring account of `key` in the config file at `path`.
+#[must_use]
+pub fn account(path: &Path, key: &str) -> String {
+    format!("{}#{key}", path.display())
+}
+
+/// Placeholder kept in an entry whose value lives in the keyring.
+#[must_use]
+pub fn reference(account: &str) -> String {
+    format!("${{secret:{account}}}")
+}
+
+/// The keyring account an entry value refers to, if it is a placeholder.
+#[must_use]
+pub fn referenced_account(value: &str) -> Option<&str> {
+    value.strip_prefix("${secret:")?.strip_suffix('}')
+}
+
+/// Moves the value of `entry` into `store` and leaves a placeholder behind.
+///
+/// # Errors
+/// Returns an error if the option is not a secret, or the store fails.
+pub fn store_secret(store: &dyn SecretStore, path: &Path, entry: &mut ConfigEntry) -> Result<()> {
+    if !SECRET_OPTIONS.contains(&entry.key.as_str()) {
+        bail!("{} is not a secret option", entry.key);
+    }
+    if !entry.enabled || referenced_account(&entry.value).is_some() {
+        bail!("{} has no plaintext value to store", entry.key);
+    }
+    let account = account(path, &entry.key);
+    store.store(&account, &entry.value)?;
+    entry.value = reference(&account);
+    Ok(())
+}
+
+/// Replaces secret values that match the keyring with their placeholder, so
+/// a freshly loaded file shows no plaintext. Secrets the store does not know
+/// are left as they are.
+pub fn conceal(store: &dyn SecretStore, path: &Path, entries: &mut [ConfigEntry]) {
+    for entry in entries
+        .iter_mut()
+        .filter(|e| e.enabled && SECRET_OPTIONS.contains(&e.key.as_str()))
+    {
+        let account = account(path, &entry.key);
+        if store.lookup(&account).is_ok_and(|s| s == entry.value) {
+            entry.value = reference(&account);
+        }
+    }
+}
+
+/// Copy of `entries` with every placeholder replaced by its secret, ready
+/// to be written to disk.
+///
+/// # Errors
+/// Returns an error if a referenced secret cannot be read from the store.
+pub fn materialize(store: &dyn SecretStore, entries: &[ConfigEntry]) -> Result<Vec<ConfigEntry>> {
+    let mut out = entries.to_vec();
+    for entry in &mut out {
+        if let Some(account) = referenced_account(&entry.value) {
+            entry.value = store.lookup(account)?;
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(dir: &Path, content: &str) -> (std::path::PathBuf, Vec<ConfigEntry>) {
+        let path = dir.join("bitcoin.conf");
+        std::fs::write(&path, content).unwrap();
+        let entries = crate::bitcoin_config::parse_config(&path).unwrap();
+        (path, entries)
+    }
+
+    #[test]
+    fn stored_secret_round_trips_through_placeholder() {
+        let dir = tempfile::tempdir().unwrap();
+        let (path, mut entries) = entries(dir.path(), "rpcuser=u\nrpcpassword=hunter2\n");
+        let store = MemoryStore::default();
+        let index = entries.iter().position(|e| e.key == "rpcpassword").unwrap();
+
+        store_secret(&store, &path, &mut entries[index]).unwrap();
+        let placeholder = entries[index].value.clone();
+        assert_eq!(
+            referenced_account(&placeholder),
+            Some(account(&path, "rpcpassword").as_str())
+        );
+        // Storing twice has nothing left to store
+        assert!(store_secret(&store, &path, &mut entries[index]).is_err());
+
+        let written = materialize(&store, &entries).unwrap();
+        assert_eq!(written[index].value, "hunter2");
+
+        let (_, mut reloaded) = super::tests::entries(dir.path(), "rpcpassword=hunter2\n");
+        conceal(&store, &path, &mut reloaded);
+        let reloaded = reloaded.iter().find(|e| e.key == "rpcpassword").unwrap();
+        assert_eq!(reloaded.value, placeholder);
+    }
+
+    #[test]
+    fn only_secret_options_are_stored() {
+        let dir = tempfile::tempdir().unwrap();
+        let (path, mut entries) = entries(dir.path(), "rpcuser=u\n");
+        let index = entries.iter().position(|e| e.key == "rpcuser").unwrap();
+        assert!(store_secret(&MemoryStore::default(), &path, &mut entries[index]).is_err());
+        assert!(is_secret("rpcpassword"));
+        assert!(is_secret("rpcauth"));
+        assert!(!is_secret("rpcuser"));
+    }
+
+    #[test]
+    fn materialize_fails_for_unknown_secret() {
+        let dir = tempfile::tempdir().unwrap();
+        let (_, entries) = entries(dir.path(), "rpcpassword=${secret:missing}\n");
+        assert!(materialize(&MemoryStore::default(), &entries).is_err());
+    }
+}
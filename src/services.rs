@@ -0,0 +1,98 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_config::ConfigEntry;
+use crate::electrs_config::{
+    cross_check as electrs_cross_check, parse_electrs_config, save_electrs_config,
+};
+use crate::i2pd_config::{cross_check as i2pd_cross_check, parse_i2pd_config, save_i2pd_config};
+use crate::settings::Settings;
+use crate::torrc::{cross_check as torrc_cross_check, parse_torrc, save_torrc};
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+/// Auxiliary services whose config files are edited on the Services screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServiceKind {
+    Electrs,
+    Tor,
+    I2pd,
+}
+
+impl ServiceKind {
+    /// All services in tab order.
+    pub const ALL: &[ServiceKind] = &[ServiceKind::Electrs, ServiceKind::Tor, ServiceKind::I2pd];
+
+    /// Tab label
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            ServiceKind::Electrs => "Electrs",
+            ServiceKind::Tor => "Tor",
+            ServiceKind::I2pd => "I2P",
+        }
+    }
+
+    /// Name of the file the user is asked to pick
+    #[must_use]
+    pub fn file_hint(self) -> &'static str {
+        match self {
+            ServiceKind::Electrs => "electrs config.toml",
+            ServiceKind::Tor => "torrc",
+            ServiceKind::I2pd => "i2pd.conf",
+        }
+    }
+
+    /// Parse the service's config file.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read or parsed.
+    pub fn parse(self, path: &Path) -> Result<Vec<ConfigEntry>> {
+        match self {
+            ServiceKind::Electrs => parse_electrs_config(path),
+            ServiceKind::Tor => parse_torrc(path),
+            ServiceKind::I2pd => parse_i2pd_config(path),
+        }
+    }
+
+    /// Write entries back to the service's config file.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be written.
+    pub fn save(self, path: &Path, entries: &[ConfigEntry]) -> Result<()> {
+        match self {
+            ServiceKind::Electrs => save_electrs_config(path, entries),
+            ServiceKind::Tor => save_torrc(path, entries),
+            ServiceKind::I2pd => save_i2pd_config(path, entries),
+        }
+    }
+
+    /// Returns a message for every setting that disagrees with bitcoin.conf.
+    #[must_use]
+    pub fn cross_check(self, entries: &[ConfigEntry], bitcoin: &[ConfigEntry]) -> Vec<String> {
+        match self {
+            ServiceKind::Electrs => electrs_cross_check(entries, bitcoin),
+            ServiceKind::Tor => torrc_cross_check(entries, bitcoin),
+            ServiceKind::I2pd => i2pd_cross_check(entries, bitcoin),
+        }
+    }
+
+    /// Path persisted in settings for this service.
+    #[must_use]
+    pub fn settings_path(self, settings: &Settings) -> Option<&PathBuf> {
+        match self {
+            ServiceKind::Electrs => settings.electrs_conf_path.as_ref(),
+            ServiceKind::Tor => settings.torrc_path.as_ref(),
+            ServiceKind::I2pd => settings.i2pd_conf_path.as_ref(),
+        }
+    }
+
+    pub fn set_settings_path(self, settings: &mut Settings, path: Option<PathBuf>) {
+        match self {
+            ServiceKind::Electrs => settings.electrs_conf_path = path,
+            ServiceKind::Tor => settings.torrc_path = path,
+            ServiceKind::I2pd => settings.i2pd_conf_path = path,
+        }
+    }
+}
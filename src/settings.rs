@@ -2,6 +2,9 @@
 //
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
+use crate::bitcoin_config::NodeFlavor;
+use crate::hooks::Hooks;
+use crate::plugins::PluginConfig;
 use anyhow::Result;
 use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
@@ -26,10 +29,64 @@ pub struct Settings {
     pub ln_conf_path: Option<PathBuf>,
     /// Path to the Shares Market config file
     pub shares_market_conf_path: Option<PathBuf>,
+    /// Path to the electrs config file (config.toml)
+    pub electrs_conf_path: Option<PathBuf>,
+    /// Path to the Tor daemon config file (torrc)
+    pub torrc_path: Option<PathBuf>,
+    /// Path to the i2pd config file (i2pd.conf)
+    pub i2pd_conf_path: Option<PathBuf>,
+    /// Path to the bitcoind binary; looked up on `PATH` when unset
+    pub bitcoind_path: Option<PathBuf>,
+    /// Node implementation whose options the Bitcoin Config screen offers
+    #[serde(default)]
+    pub node_flavor: NodeFlavor,
+    /// Where to download the asmap file from; `DEFAULT_ASMAP_URL` when unset
+    pub asmap_url: Option<String>,
+    /// SHA-256 the downloaded asmap must have; when unset, the hash
+    /// published at `<asmap_url>.sha256` is used, which only catches a
+    /// corrupted download
+    pub asmap_sha256: Option<String>,
+    /// Where to download the option-schema bundle from; its signature is
+    /// at `<url>.asc`. No bundle is downloaded when unset
+    pub schema_bundle_url: Option<String>,
+    /// Fingerprint of the key that must sign the option-schema bundle;
+    /// the key itself has to be in the user's gpg keyring
+    pub schema_signing_key: Option<String>,
+    /// Bitcoin options pinned to the top of the editor, in pinning order
+    #[serde(default)]
+    pub favorite_options: Vec<String>,
     /// If set, the user-chosen directory where `settings.toml` is stored.
     /// the default location always holds a copy so the override is found
     /// on the next launch.
     pub settings_dir_override: Option<PathBuf>,
+    /// Shell commands to run on events PDM observes
+    #[serde(default)]
+    pub hooks: Hooks,
+    /// Programs that add a screen to the Plugins tab
+    #[serde(default)]
+    pub plugins: Vec<PluginConfig>,
+    /// Address to serve Prometheus metrics on, such as `127.0.0.1:9332`;
+    /// no endpoint when unset
+    pub metrics_listen: Option<String>,
+    /// Unix socket `pdm ctl` reaches the open session on; none when unset
+    pub control_socket: Option<PathBuf>,
+    /// Monthly data cap of the node's connection, in GB, that the Traffic
+    /// tab sizes `maxuploadtarget` to
+    pub data_cap_gb: Option<u64>,
+    /// Free space, in GB, to leave on the blocks filesystem when sizing
+    /// `prune`; `DEFAULT_MARGIN_GB` when unset
+    pub prune_margin_gb: Option<u64>,
+    /// Share of the system RAM, in percent, bitcoind's expected peak may
+    /// take before PDM warns; `DEFAULT_MEMORY_SHARE_PCT` when unset
+    pub memory_share_pct: Option<u64>,
+    /// Whether to look for a newer PDM release on startup; off unless
+    /// set, since it contacts GitHub
+    #[serde(default)]
+    pub check_for_updates: bool,
+    /// Plain text in place of emoji and symbols, selection shown by style
+    /// rather than color alone, and a high-contrast palette
+    #[serde(default)]
+    pub accessibility: bool,
 }
 
 /// Returns the directory where `settings.toml` is stored.
@@ -145,6 +202,28 @@ mod tests {
         assert!(loaded.ln_conf_path.is_none());
     }
 
+    #[test]
+    fn hooks_live_in_their_own_table() {
+        let settings: Settings =
+            toml::from_str("[hooks]\nnew_block = \"logger new block $PDM_DETAIL\"\n").unwrap();
+        assert_eq!(
+            settings.hooks.new_block.as_deref(),
+            Some("logger new block $PDM_DETAIL")
+        );
+        assert!(settings.hooks.node_down.is_none());
+
+        let content = toml::to_string_pretty(&settings).unwrap();
+        let loaded: Settings = toml::from_str(&content).unwrap();
+        assert_eq!(loaded.hooks, settings.hooks);
+        assert!(
+            toml::from_str::<Settings>("")
+                .unwrap()
+                .hooks
+                .new_block
+                .is_none()
+        );
+    }
+
     #[test]
     fn load_settings_returns_default_for_bad_toml() {
         let result: Result<Settings, _> = toml::from_str("not valid toml :::");
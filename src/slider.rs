@@ -0,0 +1,207 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+/// Values an integer option accepts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntRange {
+    pub min: i64,
+    pub max: i64,
+    /// Values below `min` that switch a mode rather than set an amount,
+    /// such as `prune=1` for manual pruning
+    pub special: &'static [i64],
+    /// Change per Left/Right press; PageUp/PageDown move ten times as far
+    pub step: i64,
+}
+
+/// Integer options with a well-known valid range, edited with a slider.
+pub const RANGES: &[(&str, IntRange)] = &[
+    (
+        "par",
+        IntRange {
+            min: 0,
+            max: 15,
+            special: &[],
+            step: 1,
+        },
+    ),
+    (
+        "prune",
+        IntRange {
+            min: 550,
+            // Past the size of the chain pruning keeps every block
+            max: 1_000_000,
+            special: &[0, 1],
+            step: 512,
+        },
+    ),
+    (
+        "rpcthreads",
+        IntRange {
+            min: 1,
+            max: 64,
+            special: &[],
+            step: 1,
+        },
+    ),
+    (
+        "blockmaxweight",
+        IntRange {
+            min: 4_000,
+            max: 3_996_000,
+            special: &[],
+            step: 4_000,
+        },
+    ),
+];
+
+/// Cells in the slider's bar.
+pub const BAR_WIDTH: usize = 24;
+
+/// The valid range of `key`, if it has a well-known one.
+#[must_use]
+pub fn range_for(key: &str) -> Option<IntRange> {
+    RANGES.iter().find(|(k, _)| *k == key).map(|(_, r)| *r)
+}
+
+/// A value picked within an [`IntRange`] for the entry at `index`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Slider {
+    /// Entry being edited
+    pub index: usize,
+    pub value: i64,
+    pub range: IntRange,
+}
+
+impl Slider {
+    /// Slider for the entry at `index`, starting from `value` moved into
+    /// the range, or from the range's minimum if it is no number.
+    #[must_use]
+    pub fn new(index: usize, value: &str, range: IntRange) -> Self {
+        let value = match value.trim().parse::<i64>() {
+            Ok(v) if range.special.contains(&v) => v,
+            Ok(v) => v.clamp(range.min, range.max),
+            Err(_) => range.min,
+        };
+        Self {
+            index,
+            value,
+            range,
+        }
+    }
+
+    /// Moves `steps` steps up, or down when negative. Special values are
+    /// one step each, below the range.
+    pub fn nudge(&mut self, steps: i64) {
+        let IntRange {
+            min,
+            max,
+            special,
+            step,
+        } = self.range;
+        if steps > 0 {
+            self.value = if self.value < min {
+                special
+                    .iter()
+                    .copied()
+                    .find(|&s| s > self.value)
+                    .unwrap_or(min)
+            } else {
+                self.value.saturating_add(steps * step).min(max)
+            };
+        } else if steps < 0 {
+            self.value = if self.value > min {
+                self.value.saturating_add(steps * step).max(min)
+            } else {
+                special
+                    .iter()
+                    .copied()
+                    .rev()
+                    .find(|&s| s < self.value)
+                    .unwrap_or(self.value)
+            };
+        }
+    }
+
+    /// Jumps to the lowest value, special or not, or to the highest.
+    pub fn jump(&mut self, to_max: bool) {
+        self.value = if to_max {
+            self.range.max
+        } else {
+            self.range
+                .special
+                .first()
+                .copied()
+                .unwrap_or(self.range.min)
+        };
+    }
+
+    /// The value as written to the config.
+    #[must_use]
+    pub fn value(&self) -> String {
+        self.value.to_string()
+    }
+
+    /// The bar with the knob where the value sits; empty below the range.
+    #[must_use]
+    pub fn bar(&self) -> String {
+        let IntRange { min, max, .. } = self.range;
+        let filled = if self.value < min || max <= min {
+            0
+        } else {
+            let span = u128::try_from(max - min).unwrap_or(1);
+            let at = u128::try_from(self.value - min).unwrap_or(0);
+            usize::try_from(at * (BAR_WIDTH as u128 - 1) / span).unwrap_or(0)
+        };
+        let mut bar = "━".repeat(filled);
+        bar.push(if self.value < min { '○' } else { '●' });
+        bar.push_str(&"─".repeat(BAR_WIDTH - 1 - filled));
+        bar
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slider_stays_within_the_range() {
+        let range = range_for("par").unwrap();
+        let mut slider = Slider::new(3, "99", range);
+        assert_eq!(slider.value, 15);
+        slider.nudge(1);
+        assert_eq!(slider.value(), "15");
+        slider.nudge(-10);
+        assert_eq!(slider.value, 5);
+        slider.nudge(-10);
+        assert_eq!(slider.value, 0);
+        slider.nudge(-1);
+        assert_eq!(slider.value, 0);
+        assert_eq!(Slider::new(3, "auto", range).value, 0);
+        assert_eq!(range_for("txindex"), None);
+    }
+
+    #[test]
+    fn prune_steps_through_its_special_values() {
+        let range = range_for("prune").unwrap();
+        let mut slider = Slider::new(0, "300", range);
+        assert_eq!(slider.value, 550);
+        slider.nudge(-1);
+        assert_eq!(slider.value, 1);
+        slider.nudge(-1);
+        assert_eq!(slider.value, 0);
+        slider.nudge(-1);
+        assert_eq!(slider.value, 0);
+        assert!(slider.bar().starts_with('○'));
+        slider.nudge(1);
+        slider.nudge(1);
+        assert_eq!(slider.value, 550);
+        slider.nudge(1);
+        assert_eq!(slider.value, 1062);
+        slider.jump(true);
+        assert!(slider.bar().ends_with('●'));
+        assert_eq!(slider.bar().chars().count(), BAR_WIDTH);
+        slider.jump(false);
+        assert_eq!(slider.value, 0);
+    }
+}
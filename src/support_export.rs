@@ -0,0 +1,166 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_config::{ConfigEntry, ConfigType, enabled_value, render_config};
+use crate::docker_compose::{HEADER, write_generated};
+use crate::rpc::default_datadir;
+use crate::secrets::is_secret;
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+/// File the shareable copy is written to, next to bitcoin.conf.
+pub const SUPPORT_FILE: &str = "bitcoin.conf.support";
+
+/// Stands in for every secret value.
+pub const REDACTED: &str = "<redacted>";
+
+/// Options that are not secrets but tell a reader who runs the node or
+/// where to reach it.
+const IDENTIFYING_OPTIONS: &[&str] = &["rpcuser", "externalip"];
+
+/// Where the copy's paths are made relative to.
+#[derive(Debug, Clone, Default)]
+pub struct PathContext {
+    pub datadir: Option<PathBuf>,
+    pub home: Option<PathBuf>,
+}
+
+impl PathContext {
+    /// The datadir `entries` set, or the platform default, and the user's
+    /// home directory.
+    #[must_use]
+    pub fn for_entries(entries: &[ConfigEntry]) -> Self {
+        Self {
+            datadir: enabled_value(entries, "datadir")
+                .map(PathBuf::from)
+                .or_else(default_datadir),
+            home: directories::BaseDirs::new().map(|d| d.home_dir().to_path_buf()),
+        }
+    }
+
+    /// `value` with the datadir written as `<datadir>` and the home
+    /// directory as `~`, wherever they appear in it.
+    #[must_use]
+    pub fn generalize(&self, value: &str) -> String {
+        let mut out = value.to_string();
+        for (dir, placeholder) in [(&self.datadir, "<datadir>"), (&self.home, "~")] {
+            let Some(dir) = dir.as_deref().and_then(Path::to_str) else {
+                continue;
+            };
+            let dir = dir.trim_end_matches(['/', '\\']);
+            if !dir.is_empty() {
+                out = out.replace(dir, placeholder);
+            }
+        }
+        out
+    }
+}
+
+/// A path outside the datadir and home directory, reduced to its last
+/// component.
+fn anonymize_path(value: &str) -> String {
+    let path = Path::new(value);
+    match path.file_name() {
+        Some(name) if path.is_absolute() => format!("<dir>/{}", name.to_string_lossy()),
+        _ => value.to_string(),
+    }
+}
+
+/// `entries` with secrets and identifying values replaced by placeholders
+/// and paths made relative to `paths`.
+#[must_use]
+pub fn sanitize(entries: &[ConfigEntry], paths: &PathContext) -> Vec<ConfigEntry> {
+    let mut out = entries.to_vec();
+    for entry in out.iter_mut().filter(|e| e.enabled) {
+        if is_secret(&entry.key) || IDENTIFYING_OPTIONS.contains(&entry.key.as_str()) {
+            entry.value = REDACTED.to_string();
+            continue;
+        }
+        let generalized = paths.generalize(&entry.value);
+        let is_path = entry
+            .schema
+            .as_ref()
+            .is_some_and(|s| s.config_type == ConfigType::Path);
+        entry.value = if is_path && generalized == entry.value {
+            anonymize_path(&entry.value)
+        } else {
+            generalized
+        };
+    }
+    out
+}
+
+/// The shareable copy of `entries`: bitcoin.conf lines under a header
+/// saying what was replaced.
+#[must_use]
+pub fn support_copy(entries: &[ConfigEntry], paths: &PathContext) -> String {
+    format!(
+        "{HEADER}: bitcoin.conf for sharing\n\
+         # Secrets and addresses read {REDACTED}; paths are relative to <datadir> or ~\n\n{}",
+        render_config(&sanitize(entries, paths))
+    )
+}
+
+/// Writes the shareable copy next to `bitcoin_conf` and returns its path.
+///
+/// # Errors
+/// Returns an error if a hand-written file of the same name exists or the
+/// copy cannot be written.
+pub fn export_support_copy(bitcoin_conf: &Path, entries: &[ConfigEntry]) -> Result<PathBuf> {
+    let path = bitcoin_conf
+        .parent()
+        .unwrap_or(Path::new("."))
+        .join(SUPPORT_FILE);
+    let paths = PathContext::for_entries(entries);
+    write_generated(&path, &support_copy(entries, &paths))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitcoin_config::{NodeFlavor, parse_config_str};
+
+    #[test]
+    fn secrets_and_paths_are_replaced() {
+        let entries = parse_config_str(
+            "datadir=/srv/btc\nrpcuser=alice\nrpcpassword=hunter2\n\
+             rpcauth=bob:salt$hash\nexternalip=203.0.113.7\n\
+             walletnotify=/home/alice/bin/notify.sh %s\nblocksdir=/mnt/hdd/blocks\n\
+             debuglogfile=/srv/btc/logs/debug.log\nrpccookiefile=.cookie\ndbcache=900\n",
+            NodeFlavor::Core,
+        )
+        .unwrap();
+        let paths = PathContext {
+            datadir: Some(PathBuf::from("/srv/btc")),
+            home: Some(PathBuf::from("/home/alice")),
+        };
+        let copy = support_copy(&entries, &paths);
+        assert!(copy.starts_with(HEADER));
+        for secret in ["alice", "hunter2", "bob", "203.0.113.7", "/srv", "/mnt"] {
+            assert!(!copy.contains(secret), "{secret} leaked:\n{copy}");
+        }
+        assert!(copy.contains("datadir=<datadir>\n"));
+        assert!(copy.contains("rpcpassword=<redacted>\n"));
+        assert!(copy.contains("walletnotify=~/bin/notify.sh %s\n"));
+        assert!(copy.contains("blocksdir=<dir>/blocks\n"));
+        assert!(copy.contains("debuglogfile=<datadir>/logs/debug.log\n"));
+        assert!(copy.contains("rpccookiefile=.cookie\n"));
+        assert!(copy.contains("dbcache=900\n"));
+    }
+
+    #[test]
+    fn export_keeps_hand_written_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let conf = dir.path().join("bitcoin.conf");
+        let entries = parse_config_str("rpcpassword=hunter2\n", NodeFlavor::Core).unwrap();
+
+        let path = export_support_copy(&conf, &entries).unwrap();
+        assert_eq!(path, dir.path().join(SUPPORT_FILE));
+        assert!(!std::fs::read_to_string(&path).unwrap().contains("hunter2"));
+
+        std::fs::write(&path, "my notes\n").unwrap();
+        assert!(export_support_copy(&conf, &entries).is_err());
+    }
+}
@@ -0,0 +1,663 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_config::{ConfigEntry, save_config};
+use anyhow::{Result, bail};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use toml_edit::{DocumentMut, Item};
+
+fn is_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// True when `value` holds at least one `${NAME}` placeholder.
+#[must_use]
+pub fn has_placeholders(value: &str) -> bool {
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        rest = &rest[start + 2..];
+        if rest.find('}').is_some_and(|end| is_name(&rest[..end])) {
+            return true;
+        }
+    }
+    false
+}
+
+/// Replaces every `${NAME}` in `value` with `lookup(NAME)`. Text that is not
+/// a valid placeholder, such as `${secret:...}`, is kept as written.
+///
+/// # Errors
+/// Returns an error naming the first placeholder `lookup` has no value for.
+pub fn expand(value: &str, lookup: impl Fn(&str) -> Option<String>) -> Result<String> {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        match after.find('}').map(|end| &after[..end]) {
+            Some(name) if is_name(name) => {
+                let Some(v) = lookup(name) else {
+                    bail!("{name} is not set");
+                };
+                out.push_str(&v);
+                rest = &after[name.len() + 1..];
+            }
+            _ => {
+                out.push_str("${");
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Expands environment variables in `value`.
+///
+/// # Errors
+/// Returns an error naming the first variable that is not set.
+pub fn expand_env(value: &str) -> Result<String> {
+    expand(value, |name| std::env::var(name).ok())
+}
+
+/// Unexpanded copy of a config whose values use placeholders, kept next to
+/// it as `<name>.template` because the config itself holds the expansion.
+#[must_use]
+pub fn template_path(conf: &Path) -> PathBuf {
+    let name = conf
+        .file_name()
+        .map_or_else(|| "config".into(), |n| n.to_string_lossy().into_owned());
+    conf.with_file_name(format!("{name}.template"))
+}
+
+/// File to load the entries of `conf` from: its template when it has one.
+#[must_use]
+pub fn editable_source(conf: &Path) -> PathBuf {
+    let template = template_path(conf);
+    if template.is_file() {
+        template
+    } else {
+        conf.to_path_buf()
+    }
+}
+
+/// Expands environment variables in every enabled entry, as written to disk.
+///
+/// # Errors
+/// Returns an error naming the entry and the variable that is not set.
+pub fn expand_entries(entries: &mut [ConfigEntry]) -> Result<()> {
+    for entry in entries.iter_mut().filter(|e| e.enabled) {
+        match expand_env(&entry.value) {
+            Ok(value) => entry.value = value,
+            Err(e) => bail!("{}: {e}", entry.key),
+        }
+    }
+    Ok(())
+}
+
+/// One concrete node generated from a template.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    /// Profile whose vars and options this one starts from
+    #[serde(default)]
+    pub inherits: Option<String>,
+    /// Only inherited from; no conf is generated for it
+    #[serde(default)]
+    pub base: bool,
+    /// Where the generated conf goes, relative to the template's directory;
+    /// `<name>/bitcoin.conf` by default
+    #[serde(default)]
+    pub output: Option<PathBuf>,
+    /// Values for the template's `${VAR}` placeholders
+    #[serde(default)]
+    pub vars: BTreeMap<String, String>,
+    /// Option values written over the template's, such as `dbcache = "300"`
+    #[serde(default)]
+    pub options: BTreeMap<String, String>,
+}
+
+/// Which table of a profile a value is set in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfileTable {
+    Options,
+    Vars,
+}
+
+impl ProfileTable {
+    fn key(self) -> &'static str {
+        match self {
+            ProfileTable::Options => "options",
+            ProfileTable::Vars => "vars",
+        }
+    }
+}
+
+/// Where a profile's value comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Origin {
+    /// Set in the profile itself
+    Own,
+    /// Taken unchanged from this ancestor
+    Inherited(String),
+}
+
+/// A value of a profile after inheritance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedValue {
+    pub value: String,
+    pub origin: Origin,
+    /// What an ancestor set before this value replaced it
+    pub overrides: Option<String>,
+}
+
+/// A profile with the vars and options of its ancestors merged in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedProfile {
+    pub name: String,
+    /// Ancestors, nearest first
+    pub ancestry: Vec<String>,
+    pub vars: BTreeMap<String, ResolvedValue>,
+    pub options: BTreeMap<String, ResolvedValue>,
+}
+
+fn merge_layer(
+    into: &mut BTreeMap<String, ResolvedValue>,
+    layer: &BTreeMap<String, String>,
+    origin: &Origin,
+) {
+    for (key, value) in layer {
+        let overrides = into.get(key).map(|r| r.value.clone());
+        into.insert(
+            key.clone(),
+            ResolvedValue {
+                value: value.clone(),
+                origin: origin.clone(),
+                overrides,
+            },
+        );
+    }
+}
+
+/// Resolves the profile `name` in `profiles`: its ancestors' vars and
+/// options, from the furthest down, with each profile's own values
+/// replacing the ones it inherits.
+///
+/// # Errors
+/// Returns an error if a profile in the chain does not exist or the chain
+/// loops back on itself.
+pub fn resolve_profile(profiles: &[Profile], name: &str) -> Result<ResolvedProfile> {
+    let mut chain: Vec<&Profile> = Vec::new();
+    let mut next = Some(name);
+    while let Some(current) = next {
+        if chain.iter().any(|p| p.name == current) {
+            bail!("{name}: inheritance loops back to {current}");
+        }
+        let Some(profile) = profiles.iter().find(|p| p.name == current) else {
+            match chain.last() {
+                Some(child) => bail!("{}: inherits from unknown profile {current}", child.name),
+                None => bail!("no profile {name}"),
+            }
+        };
+        chain.push(profile);
+        next = profile.inherits.as_deref();
+    }
+    let mut resolved = ResolvedProfile {
+        name: name.to_string(),
+        ancestry: chain[1..].iter().map(|p| p.name.clone()).collect(),
+        vars: BTreeMap::new(),
+        options: BTreeMap::new(),
+    };
+    for profile in chain.iter().rev() {
+        let origin = if profile.name == name {
+            Origin::Own
+        } else {
+            Origin::Inherited(profile.name.clone())
+        };
+        merge_layer(&mut resolved.vars, &profile.vars, &origin);
+        merge_layer(&mut resolved.options, &profile.options, &origin);
+    }
+    Ok(resolved)
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ProfileFile {
+    #[serde(default)]
+    profile: Vec<Profile>,
+}
+
+/// Profiles of `conf`, kept next to it as `<name>.profiles.toml`:
+///
+/// ```toml
+/// [[profile]]
+/// name = "alice"
+/// vars = { rpc_port = "18443", datadir_root = "/srv/regtest" }
+/// ```
+#[must_use]
+pub fn profiles_path(conf: &Path) -> PathBuf {
+    let name = conf
+        .file_name()
+        .map_or_else(|| "config".into(), |n| n.to_string_lossy().into_owned());
+    conf.with_file_name(format!("{name}.profiles.toml"))
+}
+
+/// Reads the profiles of `conf`.
+///
+/// # Errors
+/// Returns an error if the profiles file is missing, malformed or empty.
+pub fn load_profiles(conf: &Path) -> Result<Vec<Profile>> {
+    let path = profiles_path(conf);
+    let Ok(content) = std::fs::read_to_string(&path) else {
+        bail!("no profiles: create {}", path.display());
+    };
+    let file: ProfileFile = toml::from_str(&content)?;
+    if file.profile.is_empty() {
+        bail!("{} defines no [[profile]]", path.display());
+    }
+    Ok(file.profile)
+}
+
+/// Fills the template `entries` in with `profile`: its options replace
+/// the template's values, then placeholders are looked up in its vars,
+/// then `${profile}` (its name), then the environment.
+///
+/// # Errors
+/// Returns an error naming the entry and the placeholder without a value.
+pub fn render_profile(
+    entries: &[ConfigEntry],
+    profile: &ResolvedProfile,
+) -> Result<Vec<ConfigEntry>> {
+    let lookup = |name: &str| {
+        profile
+            .vars
+            .get(name)
+            .map(|v| v.value.clone())
+            .or_else(|| (name == "profile").then(|| profile.name.clone()))
+            .or_else(|| std::env::var(name).ok())
+    };
+    let mut out = entries.to_vec();
+    for (key, option) in &profile.options {
+        let position = out
+            .iter()
+            .position(|e| &e.key == key && e.enabled)
+            .or_else(|| out.iter().position(|e| &e.key == key));
+        match position {
+            Some(i) => {
+                out[i].value.clone_from(&option.value);
+                out[i].enabled = true;
+                out[i].negated = false;
+            }
+            None => out.push(ConfigEntry {
+                key: key.clone(),
+                value: option.value.clone(),
+                schema: None,
+                enabled: true,
+                section: None,
+                negated: false,
+            }),
+        }
+    }
+    for entry in out.iter_mut().filter(|e| e.enabled) {
+        match expand(&entry.value, lookup) {
+            Ok(value) => entry.value = value,
+            Err(e) => bail!("{}: {}: {e}", profile.name, entry.key),
+        }
+    }
+    Ok(out)
+}
+
+/// Writes one conf per profile of `conf` from the template `entries` and
+/// returns their paths. Nothing is written unless every profile renders.
+///
+/// # Errors
+/// Returns an error if the profiles cannot be read, a profile leaves a
+/// placeholder unfilled or would overwrite `conf`, or a file cannot be written.
+pub fn generate_profiles(conf: &Path, entries: &[ConfigEntry]) -> Result<Vec<PathBuf>> {
+    let dir = conf.parent().unwrap_or(Path::new("."));
+    let profiles = load_profiles(conf)?;
+    let mut rendered = Vec::new();
+    for profile in profiles.iter().filter(|p| !p.base) {
+        let output = dir.join(
+            profile
+                .output
+                .clone()
+                .unwrap_or_else(|| Path::new(&profile.name).join("bitcoin.conf")),
+        );
+        if output == conf {
+            bail!("profile {} would overwrite the template", profile.name);
+        }
+        let resolved = resolve_profile(&profiles, &profile.name)?;
+        rendered.push((output, render_profile(entries, &resolved)?));
+    }
+    for (output, entries) in &rendered {
+        if let Some(parent) = output.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        save_config(output, entries)?;
+    }
+    Ok(rendered.into_iter().map(|(output, _)| output).collect())
+}
+
+/// Removes `key` from the `table` of `profile` in the profiles file of
+/// `conf`, so the profile inherits it again. The rest of the file keeps
+/// its formatting.
+///
+/// # Errors
+/// Returns an error if the file cannot be read or written, or the profile
+/// does not set `key` itself.
+pub fn clear_override(conf: &Path, profile: &str, table: ProfileTable, key: &str) -> Result<()> {
+    let path = profiles_path(conf);
+    let mut doc: DocumentMut = std::fs::read_to_string(&path)?.parse()?;
+    let entry = doc
+        .get_mut("profile")
+        .and_then(Item::as_array_of_tables_mut)
+        .and_then(|profiles| {
+            profiles
+                .iter_mut()
+                .find(|p| p.get("name").and_then(Item::as_str) == Some(profile))
+        });
+    let removed = entry
+        .and_then(|p| p.get_mut(table.key()))
+        .and_then(Item::as_table_like_mut)
+        .and_then(|t| t.remove(key));
+    if removed.is_none() {
+        bail!("{profile} does not set {key} itself");
+    }
+    std::fs::write(&path, doc.to_string())?;
+    Ok(())
+}
+
+/// A row of the profile editor: one var or option of the selected profile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileRow {
+    pub table: ProfileTable,
+    pub key: String,
+    pub value: ResolvedValue,
+}
+
+/// Profiles of the loaded bitcoin.conf, browsed one at a time with their
+/// inherited and overridden values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileEditor {
+    pub profiles: Vec<Profile>,
+    /// Index into `profiles`
+    pub selected: usize,
+    /// Row under the cursor
+    pub cursor: usize,
+}
+
+impl ProfileEditor {
+    #[must_use]
+    pub fn new(profiles: Vec<Profile>) -> Self {
+        Self {
+            profiles,
+            selected: 0,
+            cursor: 0,
+        }
+    }
+
+    /// The profile shown.
+    #[must_use]
+    pub fn profile(&self) -> Option<&Profile> {
+        self.profiles.get(self.selected)
+    }
+
+    /// Options, then vars, of the profile shown after inheritance.
+    ///
+    /// # Errors
+    /// Returns why the profile does not resolve.
+    pub fn rows(&self) -> Result<Vec<ProfileRow>> {
+        let Some(profile) = self.profile() else {
+            return Ok(Vec::new());
+        };
+        let resolved = resolve_profile(&self.profiles, &profile.name)?;
+        let rows = |table, values: BTreeMap<String, ResolvedValue>| {
+            values
+                .into_iter()
+                .map(move |(key, value)| ProfileRow { table, key, value })
+        };
+        Ok(rows(ProfileTable::Options, resolved.options)
+            .chain(rows(ProfileTable::Vars, resolved.vars))
+            .collect())
+    }
+
+    /// Shows the next (`forward`) or previous profile.
+    pub fn switch(&mut self, forward: bool) {
+        let last = self.profiles.len().saturating_sub(1);
+        self.selected = if forward {
+            (self.selected + 1).min(last)
+        } else {
+            self.selected.saturating_sub(1)
+        };
+        self.cursor = 0;
+    }
+
+    /// The row under the cursor, when the profile sets it itself.
+    #[must_use]
+    pub fn own_row(&self) -> Option<ProfileRow> {
+        self.rows()
+            .ok()?
+            .into_iter()
+            .nth(self.cursor)
+            .filter(|r| r.value.origin == Origin::Own)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(name: &str) -> Option<String> {
+        match name {
+            "HOME" => Some("/home/satoshi".to_string()),
+            "BTC_RPC_PASS" => Some("hunter2".to_string()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn expands_known_placeholders() {
+        assert_eq!(
+            expand("${HOME}/.bitcoin", vars).unwrap(),
+            "/home/satoshi/.bitcoin"
+        );
+        assert_eq!(
+            expand("${BTC_RPC_PASS}-${HOME}", vars).unwrap(),
+            "hunter2-/home/satoshi"
+        );
+        assert_eq!(expand("no placeholders", vars).unwrap(), "no placeholders");
+    }
+
+    #[test]
+    fn leaves_other_dollar_text_alone() {
+        assert_eq!(
+            expand("${secret:/x#rpcpassword}", vars).unwrap(),
+            "${secret:/x#rpcpassword}"
+        );
+        assert_eq!(expand("cost $5 ${", vars).unwrap(), "cost $5 ${");
+        assert!(!has_placeholders("${secret:a}"));
+        assert!(has_placeholders("a${HOME}"));
+    }
+
+    #[test]
+    fn template_is_preferred_when_present() {
+        let dir = tempfile::tempdir().unwrap();
+        let conf = dir.path().join("bitcoin.conf");
+        assert_eq!(editable_source(&conf), conf);
+        std::fs::write(template_path(&conf), "datadir=${HOME}/btc\n").unwrap();
+        assert_eq!(
+            editable_source(&conf),
+            dir.path().join("bitcoin.conf.template")
+        );
+    }
+
+    #[test]
+    fn generates_one_conf_per_profile() {
+        let dir = tempfile::tempdir().unwrap();
+        let conf = dir.path().join("bitcoin.conf");
+        std::fs::write(
+            &conf,
+            "regtest=1\n[regtest]\nrpcport=${rpc_port}\ndatadir=${datadir_root}/${profile}\n",
+        )
+        .unwrap();
+        let entries = crate::bitcoin_config::parse_config(&conf).unwrap();
+        assert!(generate_profiles(&conf, &entries).is_err());
+
+        std::fs::write(
+            profiles_path(&conf),
+            r#"
+[[profile]]
+name = "alice"
+vars = { rpc_port = "18443", datadir_root = "/srv/regtest" }
+
+[[profile]]
+name = "bob"
+output = "nodes/bob.conf"
+vars = { rpc_port = "18543", datadir_root = "/srv/regtest" }
+"#,
+        )
+        .unwrap();
+        let written = generate_profiles(&conf, &entries).unwrap();
+        assert_eq!(
+            written,
+            vec![
+                dir.path().join("alice/bitcoin.conf"),
+                dir.path().join("nodes/bob.conf")
+            ]
+        );
+        let bob = std::fs::read_to_string(&written[1]).unwrap();
+        assert!(bob.contains("rpcport=18543"));
+        assert!(bob.contains("datadir=/srv/regtest/bob"));
+    }
+
+    #[test]
+    fn missing_profile_var_writes_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let conf = dir.path().join("bitcoin.conf");
+        std::fs::write(&conf, "rpcport=${rpc_port}\n").unwrap();
+        let entries = crate::bitcoin_config::parse_config(&conf).unwrap();
+        std::fs::write(
+            profiles_path(&conf),
+            "[[profile]]\nname = \"a\"\nvars = { rpc_port = \"1\" }\n[[profile]]\nname = \"b\"\n",
+        )
+        .unwrap();
+
+        let err = generate_profiles(&conf, &entries).unwrap_err();
+        assert_eq!(err.to_string(), "b: rpcport: rpc_port is not set");
+        assert!(!dir.path().join("a").exists());
+    }
+
+    const INHERITING: &str = r#"
+[[profile]]
+name = "base"
+base = true
+vars = { datadir_root = "/srv" }
+options = { dbcache = "1000", txindex = "1" }
+
+[[profile]]
+name = "mainnet-pi"
+inherits = "base"
+options = { dbcache = "300" }
+
+[[profile]]
+name = "signet-dev"
+inherits = "base"
+vars = { datadir_root = "/tmp" }
+options = { signet = "1" }
+"#;
+
+    #[test]
+    fn profiles_inherit_and_override_their_base() {
+        let dir = tempfile::tempdir().unwrap();
+        let conf = dir.path().join("bitcoin.conf");
+        std::fs::write(
+            &conf,
+            "dbcache=450
+datadir=${datadir_root}/${profile}
+",
+        )
+        .unwrap();
+        std::fs::write(profiles_path(&conf), INHERITING).unwrap();
+        let profiles = load_profiles(&conf).unwrap();
+
+        let pi = resolve_profile(&profiles, "mainnet-pi").unwrap();
+        assert_eq!(pi.ancestry, ["base"]);
+        let dbcache = &pi.options["dbcache"];
+        assert_eq!(dbcache.value, "300");
+        assert_eq!(dbcache.origin, Origin::Own);
+        assert_eq!(dbcache.overrides.as_deref(), Some("1000"));
+        assert_eq!(
+            pi.options["txindex"].origin,
+            Origin::Inherited("base".to_string())
+        );
+
+        let entries = crate::bitcoin_config::parse_config(&conf).unwrap();
+        let written = generate_profiles(&conf, &entries).unwrap();
+        assert_eq!(written.len(), 2, "the base profile is not written");
+        let pi = std::fs::read_to_string(&written[0]).unwrap();
+        assert!(pi.contains("dbcache=300"));
+        assert!(pi.contains("txindex=1"));
+        assert!(pi.contains("datadir=/srv/mainnet-pi"));
+        let dev = std::fs::read_to_string(&written[1]).unwrap();
+        assert!(dev.contains("dbcache=1000"));
+        assert!(dev.contains("datadir=/tmp/signet-dev"));
+    }
+
+    #[test]
+    fn broken_inheritance_is_reported() {
+        let profile = |name: &str, inherits: &str| Profile {
+            name: name.to_string(),
+            inherits: Some(inherits.to_string()),
+            base: false,
+            output: None,
+            vars: BTreeMap::new(),
+            options: BTreeMap::new(),
+        };
+        let looped = [profile("a", "b"), profile("b", "a")];
+        let err = resolve_profile(&looped, "a").unwrap_err();
+        assert_eq!(err.to_string(), "a: inheritance loops back to a");
+        let orphan = [profile("a", "gone")];
+        let err = resolve_profile(&orphan, "a").unwrap_err();
+        assert_eq!(err.to_string(), "a: inherits from unknown profile gone");
+    }
+
+    #[test]
+    fn clearing_an_override_inherits_the_value_again() {
+        let dir = tempfile::tempdir().unwrap();
+        let conf = dir.path().join("bitcoin.conf");
+        std::fs::write(profiles_path(&conf), INHERITING).unwrap();
+        let mut editor = ProfileEditor::new(load_profiles(&conf).unwrap());
+        editor.switch(true);
+        let row = editor.own_row().unwrap();
+        assert_eq!(
+            (row.table, row.key.as_str()),
+            (ProfileTable::Options, "dbcache")
+        );
+        editor.cursor = 1;
+        assert_eq!(editor.own_row(), None, "txindex is inherited");
+
+        clear_override(&conf, "mainnet-pi", ProfileTable::Options, "dbcache").unwrap();
+        let profiles = load_profiles(&conf).unwrap();
+        let pi = resolve_profile(&profiles, "mainnet-pi").unwrap();
+        assert_eq!(pi.options["dbcache"].value, "1000");
+        assert!(
+            std::fs::read_to_string(profiles_path(&conf))
+                .unwrap()
+                .contains("inherits = \"base\"")
+        );
+        assert!(clear_override(&conf, "mainnet-pi", ProfileTable::Options, "dbcache").is_err());
+    }
+
+    #[test]
+    fn unset_variable_is_an_error() {
+        let err = expand("${NOPE}/x", vars).unwrap_err();
+        assert_eq!(err.to_string(), "NOPE is not set");
+    }
+}
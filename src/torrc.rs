@@ -0,0 +1,634 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_config::{
+    CHAINS, ConfigCategory, ConfigEntry, ConfigSchema, ConfigType, active_network,
+    default_p2p_port, enabled_value,
+};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Option that starts a new onion service block
+const SERVICE_DIR: &str = "HiddenServiceDir";
+
+/// Returns the schema for the global torrc options relevant to bitcoind
+#[must_use]
+pub fn get_torrc_schema() -> Vec<ConfigSchema> {
+    vec![
+        // Client and control port
+        ConfigSchema::new(
+            "SOCKSPort",
+            "9050",
+            ConfigType::Address,
+            ConfigCategory::TorGeneral,
+            "Port for the SOCKS proxy bitcoind connects through",
+        ),
+        ConfigSchema::new(
+            "ControlPort",
+            "9051",
+            ConfigType::Address,
+            ConfigCategory::TorGeneral,
+            "Control port bitcoind uses to create onion services",
+        ),
+        ConfigSchema::new(
+            "CookieAuthentication",
+            "0",
+            ConfigType::Bool,
+            ConfigCategory::TorGeneral,
+            "Authenticate control connections with a cookie file",
+        ),
+        ConfigSchema::new(
+            "CookieAuthFileGroupReadable",
+            "0",
+            ConfigType::Bool,
+            ConfigCategory::TorGeneral,
+            "Let the tor group (e.g. bitcoin user) read the cookie",
+        ),
+        ConfigSchema::new(
+            "HashedControlPassword",
+            "",
+            ConfigType::String,
+            ConfigCategory::TorGeneral,
+            "Hashed password for control connections (tor --hash-password)",
+        ),
+        ConfigSchema::new(
+            "DataDirectory",
+            "",
+            ConfigType::Path,
+            ConfigCategory::TorGeneral,
+            "Tor data directory",
+        ),
+        ConfigSchema::new(
+            "DataDirectoryGroupReadable",
+            "0",
+            ConfigType::Bool,
+            ConfigCategory::TorGeneral,
+            "Let the tor group read the data directory",
+        ),
+        ConfigSchema::new(
+            "RunAsDaemon",
+            "0",
+            ConfigType::Bool,
+            ConfigCategory::TorGeneral,
+            "Run tor in the background",
+        ),
+        ConfigSchema::new(
+            "Log",
+            "",
+            ConfigType::String,
+            ConfigCategory::TorGeneral,
+            "Log destination, e.g. notice file /var/log/tor/notices.log",
+        ),
+    ]
+}
+
+/// Returns the schema for the options inside a `HiddenServiceDir` block
+#[must_use]
+pub fn get_hidden_service_schema() -> Vec<ConfigSchema> {
+    vec![
+        // Onion service block
+        ConfigSchema::new(
+            "HiddenServiceDir",
+            "/var/lib/tor/bitcoin-service/",
+            ConfigType::Path,
+            ConfigCategory::TorHiddenService,
+            "Directory holding the onion service keys and hostname",
+        ),
+        ConfigSchema::new(
+            "HiddenServicePort",
+            "8333 127.0.0.1:8334",
+            ConfigType::String,
+            ConfigCategory::TorHiddenService,
+            "Virtual port and local target, e.g. 8333 127.0.0.1:8334",
+        ),
+        ConfigSchema::new(
+            "HiddenServiceVersion",
+            "3",
+            ConfigType::Int,
+            ConfigCategory::TorHiddenService,
+            "Onion service protocol version",
+        ),
+    ]
+}
+
+/// Finds the schema for `key`, ignoring case as tor does.
+fn lookup(schema: &[ConfigSchema], key: &str) -> Option<ConfigSchema> {
+    schema
+        .iter()
+        .find(|s| s.key.eq_ignore_ascii_case(key))
+        .cloned()
+}
+
+/// Section a `key` line belongs to inside `block`: global options stay
+/// global wherever they appear, anything else belongs to the block.
+fn line_section(key: &str, block: Option<&str>) -> Option<String> {
+    let global = lookup(&get_torrc_schema(), key).is_some()
+        && lookup(&get_hidden_service_schema(), key).is_none();
+    if global {
+        None
+    } else {
+        block.map(str::to_string)
+    }
+}
+
+/// Splits a torrc line into key and value; `None` for blank lines and
+/// comments.
+fn split_line(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    Some(
+        line.split_once(char::is_whitespace)
+            .map_or((line, ""), |(k, v)| (k, v.trim())),
+    )
+}
+
+/// Builds entries for one onion service block named by its directory.
+fn hidden_service_block(dir: &str, enabled: bool) -> Vec<ConfigEntry> {
+    get_hidden_service_schema()
+        .into_iter()
+        .map(|schema| ConfigEntry {
+            key: schema.key.clone(),
+            value: if schema.key == SERVICE_DIR {
+                dir.to_string()
+            } else {
+                schema.default.clone()
+            },
+            enabled: enabled && schema.key == SERVICE_DIR,
+            section: Some(dir.to_string()),
+            negated: false,
+            schema: Some(schema.into()),
+        })
+        .collect()
+}
+
+/// Parses torrc content into entries.
+///
+/// Global options have no section. Each `HiddenServiceDir` starts a block
+/// whose entries carry the directory as their section. A key given more
+/// than once, such as `SOCKSPort` or `HiddenServicePort`, yields one entry
+/// per line. When the file has no onion service yet, a disabled block for
+/// bitcoind is offered as a template.
+#[must_use]
+pub fn parse_torrc_str(content: &str) -> Vec<ConfigEntry> {
+    let global_schema = get_torrc_schema();
+    let block_schema = get_hidden_service_schema();
+    let mut entries: Vec<ConfigEntry> = global_schema
+        .iter()
+        .map(|schema| ConfigEntry {
+            key: schema.key.clone(),
+            value: schema.default.clone(),
+            enabled: false,
+            section: None,
+            negated: false,
+            schema: Some(schema.clone().into()),
+        })
+        .collect();
+
+    // Template entries already filled from a line; a repeat gets its own
+    let mut filled = vec![false; entries.len()];
+    let mut current_block: Option<String> = None;
+    for (key, value) in content.lines().filter_map(split_line) {
+        if key.eq_ignore_ascii_case(SERVICE_DIR) {
+            entries.extend(hidden_service_block(value, true));
+            filled.resize(entries.len(), false);
+            current_block = Some(value.to_string());
+            continue;
+        }
+
+        let section = line_section(key, current_block.as_deref());
+        let schema = match section {
+            Some(_) => lookup(&block_schema, key),
+            None => lookup(&global_schema, key),
+        };
+        let slot = schema.as_ref().and_then(|s| {
+            entries
+                .iter()
+                .position(|e| e.key == s.key && e.section == section)
+        });
+        match slot {
+            Some(i) if !filled[i] => {
+                entries[i].value = value.to_string();
+                entries[i].enabled = true;
+                filled[i] = true;
+            }
+            _ => {
+                entries.push(ConfigEntry {
+                    key: schema.as_ref().map_or(key, |s| s.key.as_str()).to_string(),
+                    value: value.to_string(),
+                    schema: schema.map(Into::into),
+                    enabled: true,
+                    section,
+                    negated: false,
+                });
+                filled.push(true);
+            }
+        }
+    }
+
+    if !entries.iter().any(|e| e.key == SERVICE_DIR) {
+        let template = hidden_service_block(&block_schema[0].default, false);
+        entries.extend(template);
+    }
+
+    entries
+}
+
+/// Parse a torrc file
+///
+/// # Errors
+/// Returns an error if the file exists but cannot be read.
+/// A missing file yields schema defaults.
+pub fn parse_torrc(path: &Path) -> Result<Vec<ConfigEntry>> {
+    let content = if path.exists() {
+        std::fs::read_to_string(path)?
+    } else {
+        String::new()
+    };
+    Ok(parse_torrc_str(&content))
+}
+
+/// Applies `entries` to the torrc text `old` line by line. The n-th line
+/// of a key in a block stands for the n-th entry of that key there: it is
+/// kept as written while its value is unchanged, rewritten when edited and
+/// dropped when disabled, as is every line of a block whose
+/// `HiddenServiceDir` is disabled. Comments and blank lines stay put.
+/// Enabled entries with no line yet go after the last line of their
+/// block, and new blocks at the end.
+#[must_use]
+pub fn update_torrc_str(old: &str, entries: &[ConfigEntry]) -> String {
+    // The directory value may have been edited; the section still names the block
+    let block_enabled = |dir: &str| {
+        entries
+            .iter()
+            .any(|e| e.key == SERVICE_DIR && e.enabled && e.section.as_deref() == Some(dir))
+    };
+    let mut lines: Vec<String> = Vec::new();
+    let mut written = vec![false; entries.len()];
+    let mut seen: HashMap<(Option<String>, String), usize> = HashMap::new();
+    // Where the last kept line of each section ends
+    let mut ends: HashMap<Option<String>, usize> = HashMap::new();
+    let mut first_block = None;
+    let mut block: Option<String> = None;
+
+    for line in old.lines() {
+        let Some((key, value)) = split_line(line) else {
+            lines.push(line.to_string());
+            continue;
+        };
+        if key.eq_ignore_ascii_case(SERVICE_DIR) {
+            block = Some(value.to_string());
+            first_block.get_or_insert(lines.len());
+        }
+        let section = line_section(key, block.as_deref());
+        let nth = seen
+            .entry((section.clone(), key.to_ascii_lowercase()))
+            .or_insert(0);
+        let found = entries
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| e.section == section && e.key.eq_ignore_ascii_case(key))
+            .nth(*nth)
+            .map(|(i, _)| i);
+        *nth += 1;
+        let Some(i) = found else {
+            continue;
+        };
+        written[i] = true;
+        let entry = &entries[i];
+        if entry.enabled && section.as_deref().is_none_or(block_enabled) {
+            lines.push(if entry.value == value {
+                line.to_string()
+            } else {
+                format!("{} {}", entry.key, entry.value)
+            });
+            ends.insert(section, lines.len());
+        }
+    }
+
+    // Entries still to write, grouped by section in the order they come
+    let mut pending: Vec<(Option<String>, Vec<String>)> = Vec::new();
+    for (entry, _) in entries
+        .iter()
+        .zip(&written)
+        .filter(|(e, w)| !**w && e.enabled && e.section.as_deref().is_none_or(block_enabled))
+    {
+        let line = format!("{} {}", entry.key, entry.value);
+        match pending.iter_mut().find(|(s, _)| *s == entry.section) {
+            Some((_, group)) => group.push(line),
+            None => pending.push((entry.section.clone(), vec![line])),
+        }
+    }
+
+    let mut inserts = Vec::new();
+    for (section, mut group) in pending {
+        if let Some(&end) = ends.get(&section) {
+            inserts.push((end, group));
+        } else if let (None, Some(at)) = (&section, first_block) {
+            group.push(String::new());
+            inserts.push((at, group));
+        } else {
+            if section.is_some() && lines.last().is_some_and(|l| !l.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.extend(group);
+        }
+    }
+    inserts.sort_by_key(|(at, _)| std::cmp::Reverse(*at));
+    for (at, group) in inserts {
+        lines.splice(at..at, group);
+    }
+
+    let mut out = lines.join("\n");
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out
+}
+
+/// Writes `entries` into the torrc at `path`, editing the lines already
+/// there (see [`update_torrc_str`]).
+///
+/// # Errors
+/// Returns an error if the file exists but cannot be read, or cannot be
+/// written.
+pub fn save_torrc(path: &Path, entries: &[ConfigEntry]) -> Result<()> {
+    let old = if path.exists() {
+        std::fs::read_to_string(path)?
+    } else {
+        String::new()
+    };
+    std::fs::write(path, update_torrc_str(&old, entries))?;
+    Ok(())
+}
+
+fn port_of(addr: &str) -> Option<u16> {
+    addr.rsplit(':').next().and_then(|p| p.trim().parse().ok())
+}
+
+/// Compares the torrc with the Tor-related options in bitcoin.conf and
+/// returns a message for each inconsistency.
+#[must_use]
+pub fn cross_check(torrc: &[ConfigEntry], bitcoin: &[ConfigEntry]) -> Vec<String> {
+    let mut issues = Vec::new();
+    let global = |key: &str| {
+        torrc
+            .iter()
+            .find(|e| e.key == key && e.section.is_none())
+            .filter(|e| e.enabled)
+            .map(|e| e.value.as_str())
+    };
+
+    // bitcoind sends outbound Tor traffic through onion= (or proxy=)
+    let socks_port = global("SOCKSPort").map_or(Some(9050), port_of);
+    for key in ["onion", "proxy"] {
+        if let Some(addr) = enabled_value(bitcoin, key).filter(|a| port_of(a) != socks_port) {
+            issues.push(format!(
+                "bitcoin.conf {key}={addr} does not match SOCKSPort {}",
+                global("SOCKSPort").unwrap_or("9050")
+            ));
+        }
+    }
+
+    let listenonion = !matches!(enabled_value(bitcoin, "listenonion"), Some("0" | "false"));
+    if listenonion {
+        let torcontrol = enabled_value(bitcoin, "torcontrol").unwrap_or("127.0.0.1:9051");
+        match global("ControlPort") {
+            None => issues
+                .push("ControlPort is not set but bitcoind listenonion=1 needs it".to_string()),
+            Some(control) if port_of(control) != port_of(torcontrol) => issues.push(format!(
+                "ControlPort {control} does not match bitcoin.conf torcontrol={torcontrol}"
+            )),
+            Some(_) => {}
+        }
+
+        let cookie = matches!(global("CookieAuthentication"), Some("1"));
+        let password = enabled_value(bitcoin, "torpassword").is_some();
+        if !cookie && !password {
+            issues.push(
+                "Set CookieAuthentication 1 or torpassword so bitcoind can use the control port"
+                    .to_string(),
+            );
+        }
+        if password && global("HashedControlPassword").is_none() {
+            issues.push("torpassword is set but HashedControlPassword is not".to_string());
+        }
+    }
+
+    // An explicit bind=...=onion must be the target of an onion service.
+    // With listenonion=0 the service is only expected when it is clearly
+    // wanted: bitcoind reaches Tor and the torrc or an .onion externalip
+    // already publishes a Bitcoin P2P port.
+    let onion_bind = bitcoin
+        .iter()
+        .filter(|e| e.key == "bind" && e.enabled)
+        .find_map(|e| e.value.strip_suffix("=onion"));
+    let default_target = format!(
+        "127.0.0.1:{}",
+        default_p2p_port(active_network(bitcoin)) + 1
+    );
+    let uses_tor = ["onion", "proxy"]
+        .iter()
+        .any(|key| enabled_value(bitcoin, key).is_some());
+    let publishes_p2p = torrc
+        .iter()
+        .filter(|e| e.key == "HiddenServicePort" && e.enabled)
+        .filter_map(|e| e.value.split_whitespace().next()?.parse::<u16>().ok())
+        .any(|port| CHAINS.iter().any(|c| default_p2p_port(c) == port));
+    let onion_address = bitcoin
+        .iter()
+        .any(|e| e.key == "externalip" && e.enabled && e.value.ends_with(".onion"));
+    let service_wanted = !listenonion && uses_tor && (publishes_p2p || onion_address);
+    if let Some(bind) = onion_bind.or(service_wanted.then_some(default_target.as_str())) {
+        let targeted = torrc
+            .iter()
+            .filter(|e| e.key == "HiddenServicePort" && e.enabled)
+            .any(|e| e.value.split_whitespace().nth(1) == Some(bind));
+        if !targeted {
+            issues.push(format!(
+                "No HiddenServicePort points at bitcoind's onion bind {bind}"
+            ));
+        }
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "
+SOCKSPort 9050
+ControlPort 9051
+CookieAuthentication 1
+
+HiddenServiceDir /var/lib/tor/bitcoin-service/
+HiddenServicePort 8333 127.0.0.1:8334
+
+HiddenServiceDir /var/lib/tor/electrs/
+HiddenServicePort 50001 127.0.0.1:50001
+";
+
+    fn bitcoin(content: &str) -> (tempfile::TempDir, Vec<ConfigEntry>) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bitcoin.conf");
+        std::fs::write(&path, content).unwrap();
+        let entries = crate::bitcoin_config::parse_config(&path).unwrap();
+        (dir, entries)
+    }
+
+    #[test]
+    fn parse_groups_hidden_service_blocks() {
+        let entries = parse_torrc_str(SAMPLE);
+        let ports: Vec<(&str, &str)> = entries
+            .iter()
+            .filter(|e| e.key == "HiddenServicePort" && e.enabled)
+            .map(|e| (e.section.as_deref().unwrap(), e.value.as_str()))
+            .collect();
+        assert_eq!(
+            ports,
+            vec![
+                ("/var/lib/tor/bitcoin-service/", "8333 127.0.0.1:8334"),
+                ("/var/lib/tor/electrs/", "50001 127.0.0.1:50001"),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_matches_keys_case_insensitively() {
+        let entries = parse_torrc_str("socksport 9150\n");
+        let socks = entries.iter().find(|e| e.key == "SOCKSPort").unwrap();
+        assert!(socks.enabled);
+        assert_eq!(socks.value, "9150");
+    }
+
+    #[test]
+    fn parse_offers_disabled_template_block() {
+        let entries = parse_torrc_str("SOCKSPort 9050\n");
+        let dir = entries.iter().find(|e| e.key == SERVICE_DIR).unwrap();
+        assert!(!dir.enabled);
+        assert_eq!(dir.value, "/var/lib/tor/bitcoin-service/");
+    }
+
+    #[test]
+    fn save_roundtrip_keeps_blocks() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("torrc");
+        save_torrc(&path, &parse_torrc_str(SAMPLE)).unwrap();
+        let saved = std::fs::read_to_string(&path).unwrap();
+        assert!(saved.starts_with("SOCKSPort 9050\nControlPort 9051\n"));
+        assert!(saved.contains(
+            "HiddenServiceDir /var/lib/tor/electrs/\nHiddenServicePort 50001 127.0.0.1:50001\n"
+        ));
+    }
+
+    #[test]
+    fn save_skips_disabled_template() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("torrc");
+        save_torrc(&path, &parse_torrc_str("SOCKSPort 9050\n")).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "SOCKSPort 9050\n");
+    }
+
+    #[test]
+    fn repeated_keys_are_separate_entries_saved_in_place() {
+        let old = "\
+# Tor for bitcoind
+SOCKSPort 9050
+SOCKSPort 9150 IsolateDestAddr
+
+HiddenServiceDir /var/lib/tor/bitcoin-service/
+# mainnet and testnet
+HiddenServicePort 8333 127.0.0.1:8334
+HiddenServicePort 18333 127.0.0.1:18334
+";
+        let mut entries = parse_torrc_str(old);
+        let socks: Vec<&str> = entries
+            .iter()
+            .filter(|e| e.key == "SOCKSPort")
+            .map(|e| e.value.as_str())
+            .collect();
+        assert_eq!(socks, ["9050", "9150 IsolateDestAddr"]);
+        assert_eq!(
+            entries
+                .iter()
+                .filter(|e| e.key == "HiddenServicePort" && e.enabled)
+                .count(),
+            2
+        );
+        assert_eq!(update_torrc_str(old, &entries), old);
+
+        let second = entries
+            .iter()
+            .rposition(|e| e.key == "HiddenServicePort")
+            .unwrap();
+        entries[second].value = "48333 127.0.0.1:48334".to_string();
+        let first = entries.iter().position(|e| e.key == "SOCKSPort").unwrap();
+        entries[first].enabled = false;
+        let control = entries.iter().position(|e| e.key == "ControlPort").unwrap();
+        entries[control].enabled = true;
+        assert_eq!(
+            update_torrc_str(old, &entries),
+            "\
+# Tor for bitcoind
+SOCKSPort 9150 IsolateDestAddr
+ControlPort 9051
+
+HiddenServiceDir /var/lib/tor/bitcoin-service/
+# mainnet and testnet
+HiddenServicePort 8333 127.0.0.1:8334
+HiddenServicePort 48333 127.0.0.1:48334
+"
+        );
+    }
+
+    #[test]
+    fn cross_check_accepts_consistent_setup() {
+        let (_dir, btc) = bitcoin("onion=127.0.0.1:9050\nlistenonion=1\n");
+        assert!(cross_check(&parse_torrc_str(SAMPLE), &btc).is_empty());
+    }
+
+    #[test]
+    fn cross_check_reports_socks_control_and_auth_problems() {
+        let (_dir, btc) = bitcoin("onion=127.0.0.1:9150\ntorcontrol=127.0.0.1:9151\n");
+        let issues = cross_check(&parse_torrc_str("ControlPort 9051\n"), &btc);
+        assert!(
+            issues
+                .iter()
+                .any(|i| i.contains("does not match SOCKSPort"))
+        );
+        assert!(
+            issues
+                .iter()
+                .any(|i| i.contains("does not match bitcoin.conf torcontrol"))
+        );
+        assert!(issues.iter().any(|i| i.contains("CookieAuthentication")));
+    }
+
+    #[test]
+    fn cross_check_requires_onion_service_when_listenonion_off() {
+        let (_dir, btc) = bitcoin("listenonion=0\nonion=127.0.0.1:9050\n");
+        let torrc = parse_torrc_str(
+            "SOCKSPort 9050\nHiddenServiceDir /var/lib/tor/bitcoin/\nHiddenServicePort 8333 127.0.0.1:18334\n",
+        );
+        let issues = cross_check(&torrc, &btc);
+        assert!(issues.iter().any(|i| i.contains("127.0.0.1:8334")));
+    }
+
+    #[test]
+    fn cross_check_allows_listenonion_off_without_an_onion_service() {
+        // Tor for outbound connections only
+        let (_dir, btc) = bitcoin("listenonion=0\nproxy=127.0.0.1:9050\n");
+        assert!(cross_check(&parse_torrc_str("SOCKSPort 9050\n"), &btc).is_empty());
+        // An onion service for something else
+        let torrc = "SOCKSPort 9050\nHiddenServiceDir /var/lib/tor/electrs/\nHiddenServicePort 50001 127.0.0.1:50001\n";
+        assert!(cross_check(&parse_torrc_str(torrc), &btc).is_empty());
+        // No Tor at all
+        let (_dir, btc) = bitcoin("listenonion=0\n");
+        assert!(cross_check(&parse_torrc_str(SAMPLE), &btc).is_empty());
+    }
+}
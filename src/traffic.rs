@@ -0,0 +1,217 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::upload_budget::Usage;
+use serde_json::Value;
+use std::time::{Duration, Instant};
+
+/// How often the Traffic tab asks bitcoind for `getnettotals`.
+pub const TRAFFIC_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Rate samples kept for the chart: ten minutes at one per poll.
+pub const TRAFFIC_HISTORY: usize = 120;
+
+/// One interval between two polls, in bytes per second.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrafficRate {
+    pub recv: f64,
+    pub sent: f64,
+}
+
+/// The `uploadtarget` of `getnettotals`, when `maxuploadtarget` is set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UploadTarget {
+    /// Length of a cycle, in seconds
+    pub timeframe: u64,
+    /// Bytes bitcoind may upload per cycle
+    pub target: u64,
+    pub target_reached: bool,
+    /// Whether bitcoind still serves blocks older than a week
+    pub serve_historical_blocks: bool,
+    pub bytes_left_in_cycle: u64,
+    /// Seconds until the cycle resets
+    pub time_left_in_cycle: u64,
+}
+
+impl UploadTarget {
+    fn from_json(target: &Value) -> Option<Self> {
+        let number = |key: &str| target.get(key).and_then(Value::as_u64).unwrap_or(0);
+        let flag = |key: &str| target.get(key).and_then(Value::as_bool) == Some(true);
+        let limit = number("target");
+        (limit > 0).then(|| Self {
+            timeframe: number("timeframe"),
+            target: limit,
+            target_reached: flag("target_reached"),
+            serve_historical_blocks: flag("serve_historical_blocks"),
+            bytes_left_in_cycle: number("bytes_left_in_cycle"),
+            time_left_in_cycle: number("time_left_in_cycle"),
+        })
+    }
+
+    /// Bytes uploaded so far this cycle.
+    #[must_use]
+    pub fn used(&self) -> u64 {
+        self.target.saturating_sub(self.bytes_left_in_cycle)
+    }
+
+    /// Upload rate, in bytes per second, that spends the target exactly
+    /// over a cycle.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)] // Only drawn
+    pub fn budget_rate(&self) -> f64 {
+        if self.timeframe == 0 {
+            0.0
+        } else {
+            self.target as f64 / self.timeframe as f64
+        }
+    }
+}
+
+/// Bandwidth of the running bitcoind, refreshed while the Traffic tab is
+/// shown.
+#[derive(Debug, Clone, Default)]
+pub struct TrafficMonitor {
+    /// Rates between consecutive polls, oldest first
+    pub rates: Vec<TrafficRate>,
+    pub total_recv: u64,
+    pub total_sent: u64,
+    /// `timemillis` of the last poll
+    pub time_millis: u64,
+    pub upload_target: Option<UploadTarget>,
+    /// Seconds bitcoind has run, from `uptime`
+    pub uptime: Option<u64>,
+    /// Monthly data cap being typed, in GB
+    pub cap_input: Option<String>,
+    /// Outcome of the last budget change
+    pub message: Option<String>,
+    /// Why the last poll failed
+    pub error: Option<String>,
+    pub last_poll: Option<Instant>,
+}
+
+impl TrafficMonitor {
+    /// Whether the next poll is due at `now`.
+    #[must_use]
+    pub fn due(&self, now: Instant) -> bool {
+        self.last_poll
+            .is_none_or(|last| now.duration_since(last) >= TRAFFIC_POLL_INTERVAL)
+    }
+
+    /// Takes a `getnettotals` result, adding the rate since the previous
+    /// one. Counters that went backwards mean bitcoind restarted, so the
+    /// history starts over.
+    #[allow(clippy::cast_precision_loss)] // Rates are only drawn
+    pub fn update(&mut self, totals: &Value) {
+        let number = |key: &str| totals.get(key).and_then(Value::as_u64).unwrap_or(0);
+        let (recv, sent, millis) = (
+            number("totalbytesrecv"),
+            number("totalbytessent"),
+            number("timemillis"),
+        );
+        if recv < self.total_recv || sent < self.total_sent {
+            self.rates.clear();
+        } else if self.time_millis > 0 && millis > self.time_millis {
+            let secs = (millis - self.time_millis) as f64 / 1000.0;
+            self.rates.push(TrafficRate {
+                recv: (recv - self.total_recv) as f64 / secs,
+                sent: (sent - self.total_sent) as f64 / secs,
+            });
+            let excess = self.rates.len().saturating_sub(TRAFFIC_HISTORY);
+            self.rates.drain(..excess);
+        }
+        self.total_recv = recv;
+        self.total_sent = sent;
+        self.time_millis = millis;
+        self.upload_target = totals.get("uploadtarget").and_then(UploadTarget::from_json);
+        self.error = None;
+    }
+
+    /// What bitcoind moved since it started, once its uptime is known.
+    #[must_use]
+    pub fn usage(&self) -> Option<Usage> {
+        self.uptime.map(|uptime| Usage {
+            recv: self.total_recv,
+            sent: self.total_sent,
+            uptime,
+        })
+    }
+}
+
+/// `rate` bytes per second in B/s, KiB/s or MiB/s.
+#[must_use]
+pub fn format_rate(rate: f64) -> String {
+    if rate >= 1024.0 * 1024.0 {
+        format!("{:.1} MiB/s", rate / (1024.0 * 1024.0))
+    } else if rate >= 1024.0 {
+        format!("{:.1} KiB/s", rate / 1024.0)
+    } else {
+        format!("{rate:.0} B/s")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn totals(recv: u64, sent: u64, millis: u64) -> Value {
+        serde_json::json!({
+            "totalbytesrecv": recv,
+            "totalbytessent": sent,
+            "timemillis": millis,
+            "uploadtarget": {"timeframe": 86_400, "target": 0},
+        })
+    }
+
+    #[test]
+    fn update_turns_totals_into_rates() {
+        let mut monitor = TrafficMonitor::default();
+        monitor.update(&totals(1000, 500, 10_000));
+        assert!(monitor.rates.is_empty());
+        monitor.update(&totals(11_000, 2500, 15_000));
+        assert_eq!(
+            monitor.rates,
+            [TrafficRate {
+                recv: 2000.0,
+                sent: 400.0
+            }]
+        );
+        assert_eq!(monitor.upload_target, None);
+
+        // bitcoind restarted
+        monitor.update(&totals(100, 100, 20_000));
+        assert!(monitor.rates.is_empty());
+        assert_eq!(monitor.total_recv, 100);
+
+        for i in 1..=TRAFFIC_HISTORY as u64 + 5 {
+            monitor.update(&totals(100 + i, 100, 20_000 + i * 1000));
+        }
+        assert_eq!(monitor.rates.len(), TRAFFIC_HISTORY);
+    }
+
+    #[test]
+    fn upload_target_reports_the_budget() {
+        let mut monitor = TrafficMonitor::default();
+        monitor.update(&serde_json::json!({
+            "timemillis": 1,
+            "uploadtarget": {
+                "timeframe": 86_400,
+                "target": 5_242_880_000_u64,
+                "target_reached": false,
+                "serve_historical_blocks": true,
+                "bytes_left_in_cycle": 1_242_880_000_u64,
+                "time_left_in_cycle": 3600,
+            },
+        }));
+        let target = monitor.upload_target.unwrap();
+        assert_eq!(target.used(), 4_000_000_000);
+        assert!((target.budget_rate() - 60_681.48).abs() < 0.01);
+    }
+
+    #[test]
+    fn rates_are_formatted_by_size() {
+        assert_eq!(format_rate(512.0), "512 B/s");
+        assert_eq!(format_rate(1536.0), "1.5 KiB/s");
+        assert_eq!(format_rate(3.0 * 1024.0 * 1024.0), "3.0 MiB/s");
+    }
+}
@@ -2,18 +2,20 @@
 //
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
+use crate::accessibility;
 use crate::app;
 use crate::app::{App, CurrentScreen};
 use crate::components::{
     bitcoin_config_view::BitcoinConfigView, bitcoin_status_view::BitcoinStatusView,
-    file_explorer::FileExplorer, home_view::HomeView, ln_config_view::LNConfigView,
-    ln_status_view::LNStatusView, p2pool_config_view::P2PoolConfigView,
-    p2pool_status_view::P2PoolStatusView, settings_view::SettingsView,
-    shares_market_view::SharesMarketView, status_bar::StatusBar,
+    file_explorer::FileExplorer, history_view::HistoryView, home_view::HomeView,
+    ln_config_view::LNConfigView, ln_status_view::LNStatusView,
+    p2pool_config_view::P2PoolConfigView, p2pool_status_view::P2PoolStatusView,
+    plugins_view::PluginsView, preflight_view::PreflightView, services_view::ServicesView,
+    settings_view::SettingsView, shares_market_view::SharesMarketView, status_bar::StatusBar,
 };
 use ratatui::{
     prelude::*,
-    widgets::{Block, Borders, List, ListItem, ListState},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
 };
 
 pub fn ui(f: &mut Frame, app: &mut App) {
@@ -49,6 +51,11 @@ pub fn ui(f: &mut Frame, app: &mut App) {
     // Dim the sidebar when the user has moved focus into a content panel
     let sidebar_focused = match app.current_screen {
         CurrentScreen::BitcoinConfig => app.bitcoin_config_view.sidebar_focused,
+        CurrentScreen::LNConfig => app.ln_config_view.sidebar_focused,
+        CurrentScreen::Services => app.service(app.current_service()).view.sidebar_focused,
+        CurrentScreen::Preflight => app.preflight_view.sidebar_focused,
+        CurrentScreen::Plugins => app.plugins_view.sidebar_focused,
+        CurrentScreen::History => app.history_view.sidebar_focused,
         CurrentScreen::Settings => app.settings_view.sidebar_focused,
         _ => true,
     };
@@ -62,15 +69,61 @@ pub fn ui(f: &mut Frame, app: &mut App) {
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title(" PDM ")
+                .title(if app.kiosk.is_some() {
+                    " PDM kiosk "
+                } else {
+                    " PDM "
+                })
                 .border_style(sidebar_border_style),
         )
         .highlight_style(Style::default().bg(Color::Gray).fg(Color::Black));
 
     f.render_stateful_widget(sidebar, chunks[0], &mut state);
 
-    // Main Content
-    let main_area = chunks[1];
+    // Main Content, below a banner while bitcoind reports warnings, a reorg
+    // was seen, a hook failed, the metrics endpoint is down or a screenshot
+    // was just taken
+    let banner: Vec<Line> = app
+        .node_warnings
+        .warnings
+        .iter()
+        .map(|w| Line::from(format!(" ⚠ bitcoind: {w}")))
+        .chain(app.reorg_alert.iter().map(|reorg| {
+            Line::from(format!(
+                " ⚠ {}; see Bitcoin Status › Reorgs",
+                reorg.summary()
+            ))
+        }))
+        .chain(
+            app.hook_error
+                .iter()
+                .chain(&app.reorg_log_error)
+                .chain(&app.metrics_error)
+                .chain(&app.control_error)
+                .map(|e| Line::from(format!(" ⚠ {e}"))),
+        )
+        .chain(
+            app.screenshot_message
+                .iter()
+                .map(|m| Line::from(format!(" {m}"))),
+        )
+        .collect();
+    let width = usize::from(chunks[1].width.max(1));
+    let banner_height: usize = banner.iter().map(|l| l.width().div_ceil(width)).sum();
+    let [banner_area, main_area] = Layout::vertical([
+        Constraint::Length(u16::try_from(banner_height).unwrap_or(u16::MAX)),
+        Constraint::Min(0),
+    ])
+    .areas(chunks[1]);
+    f.render_widget(
+        Paragraph::new(banner).wrap(Wrap { trim: false }).style(
+            Style::default()
+                .fg(Color::Black)
+                .bg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        ),
+        banner_area,
+    );
 
     match app.current_screen {
         CurrentScreen::Home => {
@@ -97,6 +150,18 @@ pub fn ui(f: &mut Frame, app: &mut App) {
         CurrentScreen::SharesMarket => {
             SharesMarketView::render(f, app, main_area);
         }
+        CurrentScreen::Services => {
+            ServicesView::render(f, app, main_area);
+        }
+        CurrentScreen::Preflight => {
+            PreflightView::render(f, app, main_area);
+        }
+        CurrentScreen::Plugins => {
+            PluginsView::render(f, app, main_area);
+        }
+        CurrentScreen::History => {
+            HistoryView::render(f, app, main_area);
+        }
         CurrentScreen::FileExplorer => {
             FileExplorer::render(f, app, main_area);
         }
@@ -106,6 +171,10 @@ pub fn ui(f: &mut Frame, app: &mut App) {
     }
 
     StatusBar::render(f, app, status_bar_area);
+
+    if app.settings.accessibility {
+        accessibility::apply(f.buffer_mut());
+    }
 }
 
 #[cfg(test)]
@@ -127,6 +196,31 @@ mod tests {
         insta::assert_debug_snapshot!(terminal.backend());
     }
 
+    #[test]
+    fn test_home_screen_running_node_render() {
+        let mut terminal = make_terminal();
+        let mut app = App::new();
+        app.running_node = Some(crate::process_args::NodeProcess {
+            pid: 4242,
+            conf: "/etc/bitcoin/bitcoin.conf".into(),
+            args: Vec::new(),
+        });
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        insta::assert_debug_snapshot!(terminal.backend());
+    }
+
+    #[test]
+    fn test_home_screen_update_notice_render() {
+        let mut terminal = make_terminal();
+        let mut app = App::new();
+        app.update_notice = Some(crate::update_check::Release {
+            version: "9.9.9".to_string(),
+            url: "https://github.com/p2poolv2/pdm/releases/tag/v9.9.9".to_string(),
+        });
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        insta::assert_debug_snapshot!(terminal.backend());
+    }
+
     #[test]
     fn test_bitcoin_config_screen_render() {
         let mut terminal = make_terminal();
@@ -137,6 +231,82 @@ mod tests {
         insta::assert_debug_snapshot!(terminal.backend());
     }
 
+    #[test]
+    fn test_bitcoin_config_parse_errors_render() {
+        let mut terminal = make_terminal();
+        let mut app = App::new();
+        app.bitcoin_parse_errors = Some((
+            std::path::PathBuf::from("/tmp/bitcoin.conf"),
+            vec![crate::bitcoin_config::ParseError {
+                line: 2,
+                message: "expected key=value, e.g. txindex=1".to_string(),
+            }],
+        ));
+        app.sidebar_index = 1;
+        app.toggle_menu();
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        insta::assert_debug_snapshot!(terminal.backend());
+    }
+
+    #[test]
+    fn test_bitcoin_config_rpc_whitelist_render() {
+        let mut terminal = make_terminal();
+        let mut app = App::new();
+        app.bitcoin_conf_path = Some(std::path::PathBuf::from("/tmp/bitcoin.conf"));
+        let assignments =
+            crate::bitcoin_config::read_assignments("rpcauth=alice:00$11\nrpcuser=bob\n");
+        let mut builder =
+            crate::rpcwhitelist::WhitelistBuilder::from_assignments(&assignments).unwrap();
+        builder.cursor = 2;
+        builder.toggle();
+        app.bitcoin_config_view.rpc_whitelist = Some(builder);
+        app.sidebar_index = 1;
+        app.toggle_menu();
+        app.bitcoin_config_view.sidebar_focused = false;
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        insta::assert_debug_snapshot!(terminal.backend());
+    }
+
+    #[test]
+    fn test_bitcoin_config_profiles_render() {
+        let mut terminal = make_terminal();
+        let mut app = App::new();
+        app.bitcoin_conf_path = Some(std::path::PathBuf::from("/tmp/bitcoin.conf"));
+        app.bitcoin_data = crate::bitcoin_config::parse_config_str(
+            "dbcache=450\n",
+            crate::bitcoin_config::NodeFlavor::Core,
+        )
+        .unwrap();
+        let profile = |name: &str, inherits: Option<&str>, options: &[(&str, &str)]| {
+            crate::template::Profile {
+                name: name.to_string(),
+                inherits: inherits.map(str::to_string),
+                base: inherits.is_none(),
+                output: None,
+                vars: std::collections::BTreeMap::new(),
+                options: options
+                    .iter()
+                    .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
+                    .collect(),
+            }
+        };
+        let mut editor = crate::template::ProfileEditor::new(vec![
+            profile("base", None, &[("dbcache", "1000"), ("txindex", "1")]),
+            profile(
+                "mainnet-pi",
+                Some("base"),
+                &[("dbcache", "300"), ("prune", "550")],
+            ),
+        ]);
+        editor.switch(true);
+        app.bitcoin_config_view.profiles = Some(editor);
+        app.sidebar_index = 1;
+        app.toggle_menu();
+        app.bitcoin_config_view.sidebar_focused = false;
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        insta::assert_debug_snapshot!(terminal.backend());
+    }
+
     #[test]
     fn test_bitcoin_status_screen_render() {
         let mut terminal = make_terminal();
@@ -180,6 +350,299 @@ mod tests {
         insta::assert_debug_snapshot!(terminal.backend());
     }
 
+    #[test]
+    fn test_bitcoin_status_tab_peers_table_render() {
+        let mut terminal = make_terminal();
+        let mut app = App::new();
+        app.sidebar_index = 2;
+        app.toggle_menu();
+        app.bitcoin_status_tab = 3;
+        let an_hour_ago = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            - 3600;
+        for ping in [0.05, 0.1, 0.2] {
+            app.peer_monitor.update(&serde_json::json!([{
+                "id": 7,
+                "addr": "198.51.100.7:8333",
+                "network": "ipv4",
+                "inbound": false,
+                "conntime": an_hour_ago,
+                "bytessent": 123_456,
+                "bytesrecv": 98_765_432,
+                "pingtime": ping,
+                "mapped_as": 64500,
+            }]));
+        }
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        insta::assert_debug_snapshot!(terminal.backend());
+    }
+
+    #[test]
+    fn test_bitcoin_status_tab_indexes_render() {
+        let mut terminal = make_terminal();
+        let mut app = App::new();
+        app.sidebar_index = 2;
+        app.toggle_menu();
+        app.bitcoin_status_tab = 4;
+        app.bitcoin_data = crate::bitcoin_config::parse_config_str(
+            "txindex=1\nblockfilterindex=1\n",
+            crate::bitcoin_config::NodeFlavor::Core,
+        )
+        .unwrap();
+        app.index_monitor.update(
+            &serde_json::json!({"txindex": {"synced": false, "best_block_height": 600_000}}),
+            800_000,
+        );
+        app.index_monitor.last_poll = Some(std::time::Instant::now());
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        insta::assert_debug_snapshot!(terminal.backend());
+    }
+
+    #[test]
+    fn test_bitcoin_status_tab_block_filter_render() {
+        let mut terminal = make_terminal();
+        let mut app = App::new();
+        app.sidebar_index = 2;
+        app.toggle_menu();
+        app.bitcoin_status_tab = 4;
+        app.bitcoin_data = crate::bitcoin_config::parse_config_str(
+            "blockfilterindex=1\npeerblockfilters=1\n",
+            crate::bitcoin_config::NodeFlavor::Core,
+        )
+        .unwrap();
+        app.index_monitor.update(
+            &serde_json::json!({"basic block filter index": {"synced": true, "best_block_height": 800_000}}),
+            800_000,
+        );
+        app.index_monitor.last_poll = Some(std::time::Instant::now());
+        app.filter_tester.result = Some(crate::block_filters::FilterMatch {
+            block_hash: "00000000000000000002a7c4".to_string(),
+            target: "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4".to_string(),
+            matched: true,
+            filter_size: 21_000,
+            header: "7f3c".to_string(),
+        });
+        app.filter_tester.input = Some("800000 bc1q".to_string());
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        insta::assert_debug_snapshot!(terminal.backend());
+    }
+
+    #[test]
+    fn test_bitcoin_status_tab_utxo_render() {
+        let mut terminal = make_terminal();
+        let mut app = App::new();
+        app.sidebar_index = 2;
+        app.toggle_menu();
+        app.bitcoin_status_tab = 5;
+        app.utxo_monitor.update(
+            &serde_json::json!({
+                "height": 850_000,
+                "txouts": 179_000_000,
+                "bogosize": 13_500_000_000_u64,
+                "disk_size": 11_000_000_000_u64,
+                "total_amount": 19_718_750.5,
+            }),
+            std::time::Duration::from_secs(95),
+        );
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        insta::assert_debug_snapshot!(terminal.backend());
+    }
+
+    #[test]
+    fn test_bitcoin_status_tab_traffic_render() {
+        let mut terminal = make_terminal();
+        let mut app = App::new();
+        app.sidebar_index = 2;
+        app.toggle_menu();
+        app.bitcoin_status_tab = 6;
+        for (i, sent) in [0_u64, 400_000, 900_000, 1_100_000].into_iter().enumerate() {
+            let i = i as u64;
+            app.traffic_monitor.update(&serde_json::json!({
+                "totalbytesrecv": i * 2_000_000,
+                "totalbytessent": sent,
+                "timemillis": 1_000 + i * 5_000,
+                "uploadtarget": {
+                    "timeframe": 86_400,
+                    "target": 5_242_880_000_u64,
+                    "serve_historical_blocks": true,
+                    "bytes_left_in_cycle": 1_242_880_000_u64,
+                    "time_left_in_cycle": 7_260,
+                },
+            }));
+        }
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        insta::assert_debug_snapshot!(terminal.backend());
+    }
+
+    #[test]
+    fn test_bitcoin_status_tab_traffic_data_cap_render() {
+        let mut terminal = make_terminal();
+        let mut app = App::new();
+        app.sidebar_index = 2;
+        app.toggle_menu();
+        app.bitcoin_status_tab = 6;
+        app.settings.data_cap_gb = Some(250);
+        app.traffic_monitor.update(&serde_json::json!({
+            "totalbytesrecv": 6_000_000_000_u64,
+            "totalbytessent": 9_000_000_000_u64,
+            "timemillis": 1_000,
+        }));
+        app.traffic_monitor.uptime = Some(3 * 86_400);
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        insta::assert_debug_snapshot!(terminal.backend());
+    }
+
+    #[test]
+    fn test_bitcoin_status_tab_mempool_render() {
+        let mut terminal = make_terminal();
+        let mut app = App::new();
+        app.sidebar_index = 2;
+        app.toggle_menu();
+        app.bitcoin_status_tab = 7;
+        let mempool: serde_json::Map<String, serde_json::Value> = (0_u64..40)
+            .map(|i| {
+                let sats = 150 * (i % 9 + 1) * (i % 9 + 1) * 1000;
+                let btc = sats as f64 / 100_000_000.0;
+                let tx = serde_json::json!({"vsize": 150_000, "fees": {"modified": btc}});
+                (format!("{i:064x}"), tx)
+            })
+            .collect();
+        app.mempool_monitor
+            .update(&serde_json::Value::Object(mempool));
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        insta::assert_debug_snapshot!(terminal.backend());
+    }
+
+    #[test]
+    fn test_bitcoin_status_tab_blocks_render() {
+        let mut terminal = make_terminal();
+        let mut app = App::new();
+        app.sidebar_index = 2;
+        app.toggle_menu();
+        app.bitcoin_status_tab = 8;
+        // Timestamps far in the past keep the "Mined" column stable
+        for (height, time) in [(100_u64, 0_u64), (101, 540), (102, 3_600), (103, 3_900)] {
+            app.block_monitor.insert(
+                crate::blocks::BlockInfo::from_stats(&serde_json::json!({
+                    "blockhash": format!("{height:064x}"),
+                    "height": height,
+                    "time": time,
+                    "total_size": 1_500_000,
+                    "total_weight": 3_993_000,
+                    "txs": 3_000,
+                }))
+                .unwrap(),
+            );
+        }
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        insta::assert_debug_snapshot!(terminal.backend());
+    }
+
+    #[test]
+    fn test_bitcoin_status_tab_wallets_render() {
+        let mut terminal = make_terminal();
+        let mut app = App::new();
+        app.sidebar_index = 2;
+        app.toggle_menu();
+        app.bitcoin_status_tab = 9;
+        app.wallet_monitor
+            .set_wallets(&serde_json::json!(["hot", "cold"]));
+        app.wallet_monitor.update(&serde_json::json!({
+            "descriptors": [
+                {"desc": "wpkh(02f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9)#8zl0zxma",
+                 "timestamp": 0, "active": true, "internal": false},
+                {"desc": "raw(deadbeef)#00000000", "timestamp": 0, "active": false},
+                {"desc": "raw(deadbeef)", "timestamp": 0, "active": true, "internal": true,
+                 "range": [0, 999]},
+            ],
+        }));
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        insta::assert_debug_snapshot!(terminal.backend());
+    }
+
+    #[test]
+    fn test_bitcoin_status_tab_mining_render() {
+        let mut terminal = make_terminal();
+        let mut app = App::new();
+        app.sidebar_index = 2;
+        app.toggle_menu();
+        app.bitcoin_status_tab = 10;
+        app.mining_monitor.update(
+            &serde_json::json!({
+                "blocks": 850_000,
+                "difficulty": 79_351_500_000_000.0,
+                "networkhashps": 612.3e18,
+                "pooledtx": 41_250,
+            }),
+            Ok(serde_json::json!({
+                "height": 850_001,
+                "coinbasevalue": 318_500_000_u64,
+                "weightlimit": 4_000_000,
+                "sigoplimit": 80_000,
+                "transactions": [
+                    {"fee": 4_000_000, "weight": 2_000_000, "sigops": 6_000},
+                    {"fee": 2_000_000, "weight": 1_993_000, "sigops": 2_000},
+                ],
+            })),
+        );
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        insta::assert_debug_snapshot!(terminal.backend());
+    }
+
+    #[test]
+    fn test_bitcoin_status_tab_test_tx_render() {
+        let mut terminal = make_terminal();
+        let mut app = App::new();
+        app.sidebar_index = 2;
+        app.toggle_menu();
+        app.bitcoin_status_tab = 11;
+        app.tx_tester.txs = vec!["0200".to_string()];
+        app.tx_tester.update(&serde_json::json!([
+            {"txid": "a1".repeat(32), "allowed": false, "vsize": 141,
+             "reject-reason": "min relay fee not met",
+             "reject-details": "min relay fee not met, 100 < 141"},
+        ]));
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        insta::assert_debug_snapshot!(terminal.backend());
+    }
+
+    #[test]
+    fn test_bitcoin_status_tab_reorgs_render() {
+        let mut terminal = make_terminal();
+        let mut app = App::new();
+        app.sidebar_index = 2;
+        app.toggle_menu();
+        app.bitcoin_status_tab = 12;
+        for (depth, height) in [(1, Some(849_990)), (2, Some(850_000)), (1, None)] {
+            app.reorg_history.push(crate::blocks::Reorg {
+                depth,
+                seen: 1_700_000_000 + depth * 600,
+                disconnected: vec!["0000000000000000000a1b2c3d".to_string()],
+                connected: "0000000000000000000f9e8d7c".to_string(),
+                height,
+            });
+        }
+        app.reorg_alert = app.reorg_history.last().cloned();
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        insta::assert_debug_snapshot!(terminal.backend());
+    }
+
+    #[test]
+    fn test_node_warning_banner_render() {
+        let mut terminal = make_terminal();
+        let mut app = App::new();
+        app.node_warnings.update(&[serde_json::json!({
+            "warnings": [
+                "Disk space is low!",
+                "Unknown new rules activated (versionbit 28) - this node may be out of date"
+            ]
+        })]);
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        insta::assert_debug_snapshot!(terminal.backend());
+    }
+
     #[test]
     fn test_p2pool_config_screen_render() {
         let mut terminal = make_terminal();
@@ -230,6 +693,120 @@ mod tests {
         insta::assert_debug_snapshot!(terminal.backend());
     }
 
+    #[test]
+    fn test_services_screen_render() {
+        let mut terminal = make_terminal();
+        let mut app = App::new();
+        app.sidebar_index = 8;
+        app.toggle_menu();
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        insta::assert_debug_snapshot!(terminal.backend());
+    }
+
+    #[test]
+    fn test_history_screen_render() {
+        let mut terminal = make_terminal();
+        let mut app = App::new();
+        app.history = vec![(
+            std::path::PathBuf::from("/tmp/bitcoin.conf"),
+            crate::history::Change {
+                timestamp: 1_709_210_096,
+                key: "prune".to_string(),
+                old: None,
+                new: Some("550".to_string()),
+            },
+        )];
+        app.sidebar_index = 11;
+        app.toggle_menu();
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        insta::assert_debug_snapshot!(terminal.backend());
+    }
+
+    #[test]
+    fn test_preflight_screen_render() {
+        use crate::preflight::{CheckStatus, PreflightCheck};
+        let mut terminal = make_terminal();
+        let mut app = App::new();
+        app.sidebar_index = 9;
+        app.toggle_menu();
+        app.preflight_view.sidebar_focused = false;
+        app.preflight_view.selected_index = 1;
+        app.preflight_view.checks = vec![
+            PreflightCheck {
+                name: "Disk space",
+                status: CheckStatus::Pass,
+                detail: "812 GB free, 795 GB more needed".to_string(),
+            },
+            PreflightCheck {
+                name: "Memory",
+                status: CheckStatus::Warn,
+                detail: "dbcache + maxmempool take 4286 MiB; 3900 MiB available of 7800 MiB"
+                    .to_string(),
+            },
+            PreflightCheck {
+                name: "Ports",
+                status: CheckStatus::Fail,
+                detail: "port 8333 is already in use on this machine (port)".to_string(),
+            },
+        ];
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        insta::assert_debug_snapshot!(terminal.backend());
+    }
+
+    #[test]
+    fn test_plugins_screen_render() {
+        use crate::plugins::{Plugin, PluginConfig};
+        let mut terminal = make_terminal();
+        let mut app = App::new();
+        app.sidebar_index = 10;
+        app.toggle_menu();
+        app.plugins_view.sidebar_focused = false;
+        app.plugins = ["Channels", "JoinMarket"]
+            .into_iter()
+            .map(|name| {
+                Plugin::start(PluginConfig {
+                    name: name.to_string(),
+                    command: "sleep 60".to_string(),
+                    rpc_methods: Vec::new(),
+                })
+            })
+            .collect();
+        app.plugins[0].lines = vec![
+            "Open channels: 3".to_string(),
+            "  ACINQ      2,000,000 sat  active".to_string(),
+        ];
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        insta::assert_debug_snapshot!(terminal.backend());
+    }
+
+    #[test]
+    fn test_kiosk_render() {
+        let mut terminal = make_terminal();
+        let mut app = App::new();
+        app.sidebar_index = 2;
+        app.toggle_menu();
+        app.bitcoin_status_tab = crate::app::PEERS_TAB;
+        app.kiosk = Some(crate::kiosk::Kiosk::default());
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        insta::assert_debug_snapshot!(terminal.backend());
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_accessible_settings_render() {
+        unsafe { std::env::set_var("PDM_CONFIG_DIR", "/pdm/test-config") };
+        let mut terminal = make_terminal();
+        let mut app = App::new();
+        app.settings.accessibility = true;
+        app.sidebar_index = 12; // Settings
+        app.toggle_menu();
+        app.settings_view.sidebar_focused = false;
+        app.settings_view.selected_index = 6;
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        unsafe { std::env::remove_var("PDM_CONFIG_DIR") };
+        insta::assert_debug_snapshot!(terminal.backend());
+    }
+
     #[test]
     #[serial_test::serial]
     fn test_settings_screen_render() {
@@ -238,7 +815,7 @@ mod tests {
         unsafe { std::env::set_var("PDM_CONFIG_DIR", "/pdm/test-config") };
         let mut terminal = make_terminal();
         let mut app = App::new();
-        app.sidebar_index = 8; // Settings
+        app.sidebar_index = 12; // Settings
         app.toggle_menu();
         terminal.draw(|f| ui(f, &mut app)).unwrap();
         unsafe { std::env::remove_var("PDM_CONFIG_DIR") };
@@ -0,0 +1,175 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+/// The unit a numeric option is counted in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    Bytes,
+    /// 1000 bytes
+    Kilobytes,
+    /// 1 000 000 bytes
+    Megabytes,
+    /// 1024 × 1024 bytes
+    Mebibytes,
+    /// Mebibytes sent per 24 hours
+    MebibytesPerDay,
+    Milliseconds,
+    Seconds,
+    Hours,
+}
+
+/// Units of the options whose number means little without one.
+const UNITS: &[(&str, Unit)] = &[
+    ("dbcache", Unit::Mebibytes),
+    ("prune", Unit::Mebibytes),
+    ("maxmempool", Unit::Megabytes),
+    ("mempoolexpiry", Unit::Hours),
+    ("maxreceivebuffer", Unit::Kilobytes),
+    ("maxsendbuffer", Unit::Kilobytes),
+    ("maxuploadtarget", Unit::MebibytesPerDay),
+    ("timeout", Unit::Milliseconds),
+    ("maxtimeadjustment", Unit::Seconds),
+    ("bantime", Unit::Seconds),
+    ("datacarriersize", Unit::Bytes),
+];
+
+const KIB: f64 = 1024.0;
+const HOUR_MS: f64 = 3_600_000.0;
+
+/// The unit `key` is counted in, if it has one worth showing.
+#[must_use]
+pub fn unit_for(key: &str) -> Option<Unit> {
+    UNITS.iter().find(|(k, _)| *k == key).map(|(_, u)| *u)
+}
+
+impl Unit {
+    /// Short label shown after values.
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Bytes => "bytes",
+            Self::Kilobytes => "kB",
+            Self::Megabytes => "MB",
+            Self::Mebibytes => "MiB",
+            Self::MebibytesPerDay => "MiB/day",
+            Self::Milliseconds => "ms",
+            Self::Seconds => "s",
+            Self::Hours => "h",
+        }
+    }
+
+    /// Bytes or milliseconds in one of the unit.
+    fn base(self) -> f64 {
+        match self {
+            Self::Bytes => 1.0,
+            Self::Kilobytes => 1000.0,
+            Self::Megabytes => 1_000_000.0,
+            Self::Mebibytes | Self::MebibytesPerDay => KIB * KIB,
+            Self::Milliseconds => 1.0,
+            Self::Seconds => 1000.0,
+            Self::Hours => HOUR_MS,
+        }
+    }
+
+    fn is_duration(self) -> bool {
+        matches!(self, Self::Milliseconds | Self::Seconds | Self::Hours)
+    }
+}
+
+/// Bytes in one of the size `suffix`: single letters and `KiB`-style
+/// names are binary, `kB`-style names decimal.
+fn size_suffix(suffix: &str) -> Option<f64> {
+    Some(match suffix {
+        "b" | "byte" | "bytes" => 1.0,
+        "k" | "kib" => KIB,
+        "m" | "mib" => KIB * KIB,
+        "g" | "gib" => KIB * KIB * KIB,
+        "t" | "tib" => KIB * KIB * KIB * KIB,
+        "kb" => 1e3,
+        "mb" => 1e6,
+        "gb" => 1e9,
+        "tb" => 1e12,
+        _ => return None,
+    })
+}
+
+/// Milliseconds in one of the duration `suffix`.
+fn duration_suffix(suffix: &str) -> Option<f64> {
+    Some(match suffix {
+        "ms" => 1.0,
+        "s" | "sec" | "secs" | "second" | "seconds" => 1000.0,
+        "m" | "min" | "mins" | "minute" | "minutes" => 60_000.0,
+        "h" | "hr" | "hrs" | "hour" | "hours" => HOUR_MS,
+        "d" | "day" | "days" => 24.0 * HOUR_MS,
+        "w" | "week" | "weeks" => 7.0 * 24.0 * HOUR_MS,
+        _ => return None,
+    })
+}
+
+/// `input` such as `2g`, `48h` or `1 week` counted in `unit`, rounded to
+/// a whole number; `None` for a bare number, which is already in the
+/// unit, or input that does not read as a size or duration.
+#[must_use]
+pub fn convert(input: &str, unit: Unit) -> Option<String> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .filter(|&i| i > 0)?;
+    let (number, suffix) = input.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let suffix = suffix.trim().to_ascii_lowercase();
+    let per_suffix = if unit.is_duration() {
+        duration_suffix(&suffix)
+    } else {
+        size_suffix(&suffix)
+    }?;
+    let value = (number * per_suffix / unit.base()).round();
+    // Anything larger than bitcoind reads is a typo, not a setting
+    (value < 1e15).then(|| format!("{value:.0}"))
+}
+
+/// `value` with its unit, e.g. `4000 MiB`. 0 and 1 are often switches,
+/// such as `prune=1` for manual pruning, and stay bare, as does anything
+/// that is not a number.
+#[must_use]
+pub fn with_unit(value: &str, unit: Option<Unit>) -> String {
+    match unit {
+        Some(unit) if value.parse::<u64>().is_ok_and(|n| n > 1) => {
+            format!("{value} {}", unit.label())
+        }
+        _ => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn human_input_converts_to_the_option_unit() {
+        assert_eq!(convert("2g", Unit::Mebibytes).as_deref(), Some("2048"));
+        assert_eq!(convert("2 GB", Unit::Megabytes).as_deref(), Some("2000"));
+        assert_eq!(convert("1.5GiB", Unit::Mebibytes).as_deref(), Some("1536"));
+        assert_eq!(convert("48h", Unit::Hours).as_deref(), Some("48"));
+        assert_eq!(convert("1 week", Unit::Hours).as_deref(), Some("168"));
+        assert_eq!(convert("2d", Unit::Seconds).as_deref(), Some("172800"));
+        assert_eq!(convert("30s", Unit::Milliseconds).as_deref(), Some("30000"));
+        assert_eq!(convert("5m", Unit::Seconds).as_deref(), Some("300"));
+        assert_eq!(convert("5m", Unit::Mebibytes).as_deref(), Some("5"));
+        assert_eq!(convert("4000", Unit::Mebibytes), None);
+        assert_eq!(convert("1 week", Unit::Mebibytes), None);
+        assert_eq!(convert("g", Unit::Mebibytes), None);
+        assert_eq!(convert("2 lightyears", Unit::Hours), None);
+    }
+
+    #[test]
+    fn numbers_show_their_unit() {
+        assert_eq!(unit_for("dbcache"), Some(Unit::Mebibytes));
+        assert_eq!(unit_for("txindex"), None);
+        assert_eq!(with_unit("4000", unit_for("dbcache")), "4000 MiB");
+        assert_eq!(with_unit("1", unit_for("prune")), "1");
+        assert_eq!(with_unit("${CACHE}", unit_for("dbcache")), "${CACHE}");
+        assert_eq!(with_unit("86400", None), "86400");
+    }
+}
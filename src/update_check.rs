@@ -0,0 +1,147 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::download::fetch;
+use anyhow::{Context, Result, bail};
+use serde_json::Value;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// GitHub API answer naming the newest published PDM release.
+pub const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/p2poolv2/pdm/releases/latest";
+
+/// Version of the running binary.
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Time the check may take; a slow network must not hold it up for the
+/// whole session.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A release newer than the running binary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Release {
+    /// Version without the tag's leading `v`
+    pub version: String,
+    /// Page with the release's changelog
+    pub url: String,
+}
+
+/// The numeric parts of `version`, such as `v0.2.1`; a pre-release or
+/// build suffix is dropped.
+fn version_parts(version: &str) -> Option<Vec<u64>> {
+    let version = version.trim().trim_start_matches('v');
+    let core = version.split(['-', '+']).next()?;
+    core.split('.').map(|n| n.parse().ok()).collect()
+}
+
+/// Whether `latest` is a later version than `current`.
+#[must_use]
+pub fn is_newer(latest: &str, current: &str) -> bool {
+    match (version_parts(latest), version_parts(current)) {
+        (Some(latest), Some(current)) => latest > current,
+        _ => false,
+    }
+}
+
+/// The release the `releases/latest` answer `body` names, if it is newer
+/// than `current`.
+///
+/// # Errors
+/// Returns an error if `body` is not a release.
+pub fn newer_release(body: &[u8], current: &str) -> Result<Option<Release>> {
+    let release: Value = serde_json::from_slice(body).context("release is not JSON")?;
+    let tag = release
+        .get("tag_name")
+        .and_then(Value::as_str)
+        .context("release has no tag_name")?;
+    if !is_newer(tag, current) {
+        return Ok(None);
+    }
+    let url = release
+        .get("html_url")
+        .and_then(Value::as_str)
+        .unwrap_or("https://github.com/p2poolv2/pdm/releases")
+        .to_string();
+    Ok(Some(Release {
+        version: tag.trim_start_matches('v').to_string(),
+        url,
+    }))
+}
+
+/// A check for a newer release running in the background, so startup
+/// does not wait on the network.
+pub struct UpdateCheck {
+    handle: JoinHandle<Result<Option<Release>>>,
+}
+
+impl UpdateCheck {
+    /// Starts asking `url` for the latest release.
+    #[must_use]
+    pub fn start(url: &str) -> Self {
+        let url = url.to_string();
+        Self {
+            handle: std::thread::spawn(move || {
+                newer_release(&fetch(&url, FETCH_TIMEOUT)?, CURRENT_VERSION)
+            }),
+        }
+    }
+
+    /// Whether the answer is in.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// The newer release, once the check is finished; waits for it
+    /// otherwise.
+    ///
+    /// # Errors
+    /// Returns an error if the release could not be fetched or read.
+    pub fn finish(self) -> Result<Option<Release>> {
+        self.handle
+            .join()
+            .unwrap_or_else(|_| bail!("update check panicked"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_compare_numerically() {
+        assert!(is_newer("v0.2.0", "0.1.0"));
+        assert!(is_newer("0.10.0", "0.9.3"));
+        assert!(is_newer("v1.0.0-rc1", "0.9.0"));
+        assert!(!is_newer("v0.1.0", "0.1.0"));
+        assert!(!is_newer("v0.0.9", "0.1.0"));
+        assert!(!is_newer("nightly", "0.1.0"));
+    }
+
+    #[test]
+    fn release_answer_names_the_changelog() {
+        let body = br#"{"tag_name": "v0.3.0",
+            "html_url": "https://github.com/p2poolv2/pdm/releases/tag/v0.3.0"}"#;
+        assert_eq!(
+            newer_release(body, "0.1.0").unwrap(),
+            Some(Release {
+                version: "0.3.0".to_string(),
+                url: "https://github.com/p2poolv2/pdm/releases/tag/v0.3.0".to_string(),
+            })
+        );
+        assert_eq!(newer_release(body, "0.3.0").unwrap(), None);
+        assert!(newer_release(b"{}", "0.1.0").is_err());
+        assert!(newer_release(b"Not Found", "0.1.0").is_err());
+    }
+
+    #[test]
+    fn check_reads_a_local_release() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("latest.json");
+        std::fs::write(&path, r#"{"tag_name": "v99.0.0", "html_url": "x"}"#).unwrap();
+        let check = UpdateCheck::start(&format!("file://{}", path.display()));
+        let release = check.finish().unwrap().unwrap();
+        assert_eq!(release.version, "99.0.0");
+    }
+}
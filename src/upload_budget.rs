@@ -0,0 +1,91 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+/// Smallest `maxuploadtarget` worth setting, in MiB: the upload of one
+/// day's worth of blocks, which bitcoind keeps serving past the target.
+pub const MIN_UPLOAD_TARGET_MIB: u64 = 144;
+
+/// What a listening node receives a day when bitcoind has not run long
+/// enough to tell: new blocks plus transaction relay.
+pub const TYPICAL_RECV_PER_DAY: u64 = 500_000_000;
+
+/// Data caps are sold in decimal gigabytes.
+pub const GB: u64 = 1_000_000_000;
+
+const MIB: u64 = 1024 * 1024;
+const DAY: u64 = 86_400;
+const MONTH: u64 = DAY * 365 / 12;
+
+/// What bitcoind moved since it started, from `getnettotals` and
+/// `uptime`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Usage {
+    pub recv: u64,
+    pub sent: u64,
+    /// Seconds bitcoind has run
+    pub uptime: u64,
+}
+
+impl Usage {
+    /// `bytes` moved over the uptime, scaled to `period` seconds.
+    fn scaled(&self, bytes: u64, period: u64) -> Option<u64> {
+        // Under an hour says more about the initial sync than the month
+        (self.uptime >= 3600).then(|| {
+            u64::try_from(u128::from(bytes) * u128::from(period) / u128::from(self.uptime))
+                .unwrap_or(u64::MAX)
+        })
+    }
+
+    /// Bytes received a day at the rate so far.
+    #[must_use]
+    pub fn recv_per_day(&self) -> Option<u64> {
+        self.scaled(self.recv, DAY)
+    }
+
+    /// Bytes moved both ways in a month at the rate so far.
+    #[must_use]
+    pub fn projected_month(&self) -> Option<u64> {
+        self.scaled(self.recv.saturating_add(self.sent), MONTH)
+    }
+}
+
+/// The `maxuploadtarget`, in MiB a day, that keeps a node within a
+/// monthly cap of `cap_gb`. Downloads count against the cap too but
+/// bitcoind cannot limit them, so what they are expected to take comes
+/// off first and the rest is left for uploads.
+#[must_use]
+pub fn recommend_target(cap_gb: u64, usage: Option<&Usage>) -> u64 {
+    let cap = u128::from(cap_gb) * u128::from(GB);
+    let per_day = u64::try_from(cap * u128::from(DAY) / u128::from(MONTH)).unwrap_or(u64::MAX);
+    let recv = usage
+        .and_then(Usage::recv_per_day)
+        .unwrap_or(TYPICAL_RECV_PER_DAY);
+    (per_day.saturating_sub(recv) / MIB).max(MIN_UPLOAD_TARGET_MIB)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn target_leaves_room_for_downloads() {
+        // 1 TB a month is about 32.9 GB a day; 0.5 GB of it is received
+        assert_eq!(recommend_target(1000, None), 30_876);
+        let usage = Usage {
+            recv: 4 * GB,
+            sent: 8 * GB,
+            uptime: 2 * DAY,
+        };
+        assert_eq!(usage.recv_per_day(), Some(2 * GB));
+        assert_eq!(usage.projected_month(), Some(182_500_000_000));
+        assert_eq!(recommend_target(1000, Some(&usage)), 29_446);
+        assert_eq!(recommend_target(20, Some(&usage)), MIN_UPLOAD_TARGET_MIB);
+
+        let fresh = Usage {
+            uptime: 60,
+            ..usage
+        };
+        assert_eq!(fresh.projected_month(), None);
+    }
+}
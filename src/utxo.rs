@@ -0,0 +1,137 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_config::{ConfigEntry, enabled_value};
+use crate::indexes::enables;
+use serde_json::Value;
+use std::time::{Duration, Instant};
+
+/// How often the UTXO tab refreshes when `coinstatsindex` answers
+/// `gettxoutsetinfo` from its index.
+pub const UTXO_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How long a `gettxoutsetinfo` that scans the whole chainstate may take.
+pub const UTXO_SCAN_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
+/// Why `coinstatsindex` matters for this screen.
+pub const COINSTATSINDEX_NOTE: &str = "Without coinstatsindex, bitcoind answers gettxoutsetinfo \
+     by reading the whole chainstate, which takes minutes on mainnet. coinstatsindex keeps \
+     these numbers per block, so they come back at once and can be asked for any past height.";
+
+/// The parts of a `gettxoutsetinfo` result the UTXO tab shows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UtxoStats {
+    pub height: u64,
+    /// Unspent outputs
+    pub txouts: u64,
+    /// Bitcoin held in them
+    pub total_amount: f64,
+    /// Size of the serialized set, independent of the database
+    pub bogosize: u64,
+    /// Size of the chainstate on disk, reported only when bitcoind scans it
+    pub disk_size: Option<u64>,
+    /// MuHash of the set, when asked for
+    pub muhash: Option<String>,
+}
+
+impl UtxoStats {
+    /// Reads a `gettxoutsetinfo` result.
+    #[must_use]
+    pub fn from_json(info: &Value) -> Self {
+        let number = |key: &str| info.get(key).and_then(Value::as_u64);
+        let text = |key: &str| info.get(key).and_then(Value::as_str).map(str::to_string);
+        Self {
+            height: number("height").unwrap_or(0),
+            txouts: number("txouts").unwrap_or(0),
+            total_amount: info
+                .get("total_amount")
+                .and_then(Value::as_f64)
+                .unwrap_or(0.0),
+            bogosize: number("bogosize").unwrap_or(0),
+            disk_size: number("disk_size"),
+            muhash: text("muhash"),
+        }
+    }
+}
+
+/// Whether `entries` enable `coinstatsindex`.
+#[must_use]
+pub fn coinstatsindex_enabled(entries: &[ConfigEntry]) -> bool {
+    enabled_value(entries, "coinstatsindex").is_some_and(enables)
+}
+
+/// The `hash_type` to ask `gettxoutsetinfo` for: `muhash` when
+/// `coinstatsindex` keeps it, otherwise `none`, which spares the scan
+/// from hashing every output.
+#[must_use]
+pub fn hash_type(entries: &[ConfigEntry]) -> &'static str {
+    if coinstatsindex_enabled(entries) {
+        "muhash"
+    } else {
+        "none"
+    }
+}
+
+/// UTXO set statistics of the running bitcoind.
+#[derive(Debug, Clone, Default)]
+pub struct UtxoMonitor {
+    pub stats: Option<UtxoStats>,
+    /// How long bitcoind took to answer
+    pub took: Duration,
+    /// Why the last request failed
+    pub error: Option<String>,
+    pub last_poll: Option<Instant>,
+}
+
+impl UtxoMonitor {
+    /// Whether the next poll is due at `now`.
+    #[must_use]
+    pub fn due(&self, now: Instant) -> bool {
+        self.last_poll
+            .is_none_or(|last| now.duration_since(last) >= UTXO_POLL_INTERVAL)
+    }
+
+    /// Takes a `gettxoutsetinfo` result and how long it took.
+    pub fn update(&mut self, info: &Value, took: Duration) {
+        self.stats = Some(UtxoStats::from_json(info));
+        self.took = took;
+        self.error = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats_read_gettxoutsetinfo() {
+        let stats = UtxoStats::from_json(&serde_json::json!({
+            "height": 850_000,
+            "txouts": 179_000_000,
+            "bogosize": 13_500_000_000_u64,
+            "muhash": "ab12",
+            "total_amount": 19_718_750.123_456_78,
+        }));
+        assert_eq!(stats.height, 850_000);
+        assert_eq!(stats.txouts, 179_000_000);
+        assert_eq!(stats.bogosize, 13_500_000_000);
+        assert_eq!(stats.disk_size, None);
+        assert_eq!(stats.muhash.as_deref(), Some("ab12"));
+        assert_eq!(format!("{:.8}", stats.total_amount), "19718750.12345678");
+    }
+
+    #[test]
+    fn muhash_is_asked_for_only_with_coinstatsindex() {
+        let entries = |content: &str| {
+            crate::bitcoin_config::parse_config_str(
+                content,
+                crate::bitcoin_config::NodeFlavor::Core,
+            )
+            .unwrap()
+        };
+        assert_eq!(hash_type(&entries("coinstatsindex=1\n")), "muhash");
+        assert_eq!(hash_type(&entries("coinstatsindex=0\n")), "none");
+        assert_eq!(hash_type(&entries("txindex=1\n")), "none");
+    }
+}
@@ -0,0 +1,76 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use serde_json::Value;
+use std::time::{Duration, Instant};
+
+/// How often bitcoind is asked for its warnings, whatever screen is shown.
+pub const WARNING_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Warnings bitcoind raises about itself: unknown versionbits, a test
+/// build, low disk space and the like.
+#[derive(Debug, Clone, Default)]
+pub struct NodeWarnings {
+    pub warnings: Vec<String>,
+    pub last_poll: Option<Instant>,
+}
+
+impl NodeWarnings {
+    /// Whether the next poll is due at `now`.
+    #[must_use]
+    pub fn due(&self, now: Instant) -> bool {
+        self.last_poll
+            .is_none_or(|last| now.duration_since(last) >= WARNING_POLL_INTERVAL)
+    }
+
+    /// Takes the `getblockchaininfo` and `getnetworkinfo` results, which
+    /// often repeat the same warning.
+    pub fn update(&mut self, results: &[Value]) {
+        self.warnings.clear();
+        for warning in results.iter().flat_map(parse_warnings) {
+            if !self.warnings.contains(&warning) {
+                self.warnings.push(warning);
+            }
+        }
+    }
+}
+
+/// The `warnings` of an RPC result: a list since bitcoind 28, a single
+/// string before, or with `-deprecatedrpc=warnings`.
+#[must_use]
+pub fn parse_warnings(result: &Value) -> Vec<String> {
+    let text = |w: &Value| {
+        w.as_str()
+            .map(str::trim)
+            .filter(|w| !w.is_empty())
+            .map(str::to_string)
+    };
+    match result.get("warnings") {
+        Some(Value::Array(warnings)) => warnings.iter().filter_map(text).collect(),
+        Some(warning) => text(warning).into_iter().collect(),
+        None => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn warnings_are_read_in_both_formats_and_deduplicated() {
+        let chain = serde_json::json!({"warnings": [
+            "This is a pre-release test build - use at your own risk",
+            "Unknown new rules activated (versionbit 28)",
+        ]});
+        let network = serde_json::json!({
+            "warnings": "This is a pre-release test build - use at your own risk"
+        });
+        let mut warnings = NodeWarnings::default();
+        warnings.update(&[chain, network]);
+        assert_eq!(warnings.warnings.len(), 2);
+
+        warnings.update(&[serde_json::json!({"warnings": ""}), serde_json::json!({})]);
+        assert!(warnings.warnings.is_empty());
+    }
+}
@@ -0,0 +1,325 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::history::journal_path;
+use crate::settings::Settings;
+use crate::template::{profiles_path, template_path};
+use anyhow::{Context, Result, bail};
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+
+/// Names the directory inside the archive.
+pub const WORKSPACE_PREFIX: &str = "pdm-workspace";
+
+/// Part of the archive holding the config directory: settings with the
+/// pinned options and hooks, schema extensions and the reorg log.
+const CONFIG_PART: &str = "config";
+
+/// Part of the archive holding the files PDM keeps next to the configs,
+/// by absolute path.
+const FILES_PART: &str = "files";
+
+/// Suffix a file the import replaces is kept under.
+pub const PRE_IMPORT_SUFFIX: &str = "pre-import";
+
+/// The configs `settings` point at.
+#[must_use]
+pub fn configured_paths(settings: &Settings) -> Vec<&Path> {
+    [
+        &settings.bitcoin_conf_path,
+        &settings.p2pool_conf_path,
+        &settings.ln_conf_path,
+        &settings.shares_market_conf_path,
+        &settings.electrs_conf_path,
+        &settings.torrc_path,
+        &settings.i2pd_conf_path,
+    ]
+    .into_iter()
+    .filter_map(Option::as_deref)
+    .collect()
+}
+
+/// Where PDM keeps things next to each config in `confs`: profiles,
+/// template and change journal. The configs themselves stay out.
+#[must_use]
+pub fn side_file_paths(confs: &[&Path]) -> Vec<PathBuf> {
+    confs
+        .iter()
+        .flat_map(|conf| [profiles_path(conf), template_path(conf), journal_path(conf)])
+        .collect()
+}
+
+/// The side files of `confs` that exist.
+#[must_use]
+pub fn side_files(confs: &[&Path]) -> Vec<PathBuf> {
+    side_file_paths(confs)
+        .into_iter()
+        .filter(|p| p.is_file())
+        .collect()
+}
+
+/// Where `path` is kept under the files part: its components below the
+/// root.
+fn archived_name(path: &Path) -> PathBuf {
+    path.components()
+        .filter_map(|c| match c {
+            Component::Normal(name) => Some(name),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Copies the files under `from` into `to`, keeping a file already at
+/// `to` with other content as `<name>.pre-import`. Symlinks and anything
+/// else that is not a plain file or directory are refused. Returns the
+/// files written.
+fn copy_tree(from: &Path, to: &Path, written: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in
+        std::fs::read_dir(from).with_context(|| format!("cannot read {}", from.display()))?
+    {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_tree(&entry.path(), &target, written)?;
+        } else {
+            copy_file(&entry.path(), &target, written)?;
+        }
+    }
+    Ok(())
+}
+
+/// Copies the plain file `from` to `target` the way [`copy_tree`] does.
+fn copy_file(from: &Path, target: &Path, written: &mut Vec<PathBuf>) -> Result<()> {
+    if !std::fs::symlink_metadata(from)?.file_type().is_file() {
+        bail!("refusing {}: not a regular file", from.display());
+    }
+    if let Some(to) = target.parent() {
+        std::fs::create_dir_all(to).with_context(|| format!("cannot create {}", to.display()))?;
+    }
+    let content = std::fs::read(from)?;
+    match std::fs::read(target) {
+        Ok(old) if old == content => return Ok(()),
+        Ok(_) => {
+            let mut kept = target.as_os_str().to_owned();
+            kept.push(format!(".{PRE_IMPORT_SUFFIX}"));
+            std::fs::rename(target, &kept)
+                .with_context(|| format!("cannot keep {}", target.display()))?;
+        }
+        Err(_) => {}
+    }
+    std::fs::write(target, content)
+        .with_context(|| format!("cannot write {}", target.display()))?;
+    written.push(target.to_path_buf());
+    Ok(())
+}
+
+/// Runs `tar` with `args`.
+fn tar(args: &[&std::ffi::OsStr]) -> Result<()> {
+    let output = Command::new("tar")
+        .args(args)
+        .output()
+        .context("cannot run tar")?;
+    if !output.status.success() {
+        bail!("tar: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(())
+}
+
+/// A fresh staging directory under the system's temporary one, readable
+/// only by the user and removed when dropped.
+fn staging_dir(what: &str) -> Result<tempfile::TempDir> {
+    tempfile::Builder::new()
+        .prefix(&format!("{WORKSPACE_PREFIX}-{what}-"))
+        .tempdir()
+        .context("cannot create a staging directory")
+}
+
+/// Writes the config directory `config_dir` and the side files `files`
+/// into the `tar.gz` archive `archive`. Returns how many files it holds.
+///
+/// # Errors
+/// Returns an error if the files cannot be staged or `tar` fails.
+pub fn export_workspace(config_dir: &Path, files: &[PathBuf], archive: &Path) -> Result<usize> {
+    let staging = staging_dir("export")?;
+    let inner = staging.path().join(WORKSPACE_PREFIX);
+    let mut written = Vec::new();
+    std::fs::create_dir_all(inner.join(CONFIG_PART))?;
+    if config_dir.is_dir() {
+        copy_tree(config_dir, &inner.join(CONFIG_PART), &mut written)?;
+    }
+    for file in files {
+        let target = inner.join(FILES_PART).join(archived_name(file));
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::copy(file, &target).with_context(|| format!("cannot read {}", file.display()))?;
+        written.push(target);
+    }
+    tar(&[
+        "-czf".as_ref(),
+        archive.as_os_str(),
+        "-C".as_ref(),
+        staging.path().as_os_str(),
+        WORKSPACE_PREFIX.as_ref(),
+    ])?;
+    Ok(written.len())
+}
+
+/// Unpacks a workspace `archive` into `config_dir`, and restores those of
+/// its side files named in `allowed` to their paths under `root`; any
+/// other file in the archive is left out. Files it replaces are kept as
+/// `<name>.pre-import`. Returns the files written.
+///
+/// # Errors
+/// Returns an error if `tar` fails, the archive is not a PDM workspace,
+/// holds a symlink or other special file, or a file cannot be written.
+pub fn import_workspace(
+    archive: &Path,
+    config_dir: &Path,
+    root: &Path,
+    allowed: &[PathBuf],
+) -> Result<Vec<PathBuf>> {
+    let staging = staging_dir("import")?;
+    tar(&[
+        "-xzf".as_ref(),
+        archive.as_os_str(),
+        "-C".as_ref(),
+        staging.path().as_os_str(),
+    ])?;
+    let inner = staging.path().join(WORKSPACE_PREFIX);
+    if !inner.join(CONFIG_PART).is_dir() {
+        bail!("{} is not a PDM workspace", archive.display());
+    }
+    let mut written = Vec::new();
+    copy_tree(&inner.join(CONFIG_PART), config_dir, &mut written)?;
+    for file in allowed {
+        let name = archived_name(file);
+        let from = inner.join(FILES_PART).join(&name);
+        if std::fs::symlink_metadata(&from).is_ok() {
+            copy_file(&from, &root.join(name), &mut written)?;
+        }
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn side_files_are_those_next_to_the_configs() {
+        let dir = tempfile::tempdir().unwrap();
+        let conf = dir.path().join("bitcoin.conf");
+        std::fs::write(&conf, "server=1\n").unwrap();
+        std::fs::write(profiles_path(&conf), "[[profile]]\nname = \"a\"\n").unwrap();
+        std::fs::write(journal_path(&conf), "").unwrap();
+        let settings = Settings {
+            bitcoin_conf_path: Some(conf.clone()),
+            torrc_path: Some(dir.path().join("torrc")),
+            ..Default::default()
+        };
+        let confs = configured_paths(&settings);
+        assert_eq!(confs.len(), 2);
+        assert_eq!(
+            side_files(&confs),
+            [profiles_path(&conf), journal_path(&conf)]
+        );
+        assert_eq!(
+            archived_name(Path::new("/etc/bitcoin/bitcoin.conf.history")),
+            PathBuf::from("etc/bitcoin/bitcoin.conf.history")
+        );
+    }
+
+    #[test]
+    fn workspace_moves_to_another_machine() {
+        let old = tempfile::tempdir().unwrap();
+        let config = old.path().join("config");
+        std::fs::create_dir_all(config.join("schemas")).unwrap();
+        std::fs::write(
+            config.join("settings.toml"),
+            "favorite_options = [\"dbcache\"]\n",
+        )
+        .unwrap();
+        std::fs::write(config.join("schemas/knots.toml"), "[[option]]\n").unwrap();
+        let profiles = old.path().join("node/bitcoin.conf.profiles.toml");
+        std::fs::create_dir_all(profiles.parent().unwrap()).unwrap();
+        std::fs::write(&profiles, "[[profile]]\n").unwrap();
+        let archive = old.path().join("workspace.tar.gz");
+        let count = export_workspace(&config, std::slice::from_ref(&profiles), &archive).unwrap();
+        assert_eq!(count, 3);
+
+        let new = tempfile::tempdir().unwrap();
+        let config = new.path().join("config");
+        std::fs::create_dir(&config).unwrap();
+        std::fs::write(config.join("settings.toml"), "check_for_updates = true\n").unwrap();
+        let root = new.path().join("root");
+        let allowed = [profiles.clone()];
+        let written = import_workspace(&archive, &config, &root, &allowed).unwrap();
+        assert_eq!(written.len(), 3);
+        assert_eq!(
+            std::fs::read_to_string(config.join("settings.toml")).unwrap(),
+            "favorite_options = [\"dbcache\"]\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(config.join("settings.toml.pre-import")).unwrap(),
+            "check_for_updates = true\n"
+        );
+        assert!(config.join("schemas/knots.toml").is_file());
+        assert!(root.join(archived_name(&profiles)).is_file());
+
+        // Importing the same workspace again changes nothing
+        assert!(
+            import_workspace(&archive, &config, &root, &allowed)
+                .unwrap()
+                .is_empty()
+        );
+        std::fs::write(old.path().join("other.tar.gz"), "").unwrap();
+        assert!(
+            import_workspace(&old.path().join("other.tar.gz"), &config, &root, &allowed).is_err()
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn import_restores_only_known_plain_files() {
+        let old = tempfile::tempdir().unwrap();
+        let config = old.path().join("config");
+        std::fs::create_dir(&config).unwrap();
+        let profiles = old.path().join("node/bitcoin.conf.profiles.toml");
+        let stray = old.path().join("home/.ssh/authorized_keys");
+        for file in [&profiles, &stray] {
+            std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+            std::fs::write(file, "x\n").unwrap();
+        }
+        let archive = old.path().join("workspace.tar.gz");
+        export_workspace(&config, &[profiles.clone(), stray.clone()], &archive).unwrap();
+
+        let new = tempfile::tempdir().unwrap();
+        let root = new.path().join("root");
+        let written = import_workspace(
+            &archive,
+            &new.path().join("config"),
+            &root,
+            std::slice::from_ref(&profiles),
+        )
+        .unwrap();
+        assert_eq!(written, [root.join(archived_name(&profiles))]);
+        assert!(!root.join(archived_name(&stray)).exists());
+
+        // tar keeps a symlink as a link; the import must not follow it
+        let inner = old.path().join(WORKSPACE_PREFIX).join(CONFIG_PART);
+        std::fs::create_dir_all(&inner).unwrap();
+        std::os::unix::fs::symlink("/etc/passwd", inner.join("settings.toml")).unwrap();
+        tar(&[
+            "-czf".as_ref(),
+            archive.as_os_str(),
+            "-C".as_ref(),
+            old.path().as_os_str(),
+            WORKSPACE_PREFIX.as_ref(),
+        ])
+        .unwrap();
+        let err = import_workspace(&archive, &new.path().join("config"), &root, &[]).unwrap_err();
+        assert!(err.to_string().contains("not a regular file"), "{err}");
+    }
+}
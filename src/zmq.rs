@@ -0,0 +1,239 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::diagnostics::split_host_port;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+/// Frame flag: more frames of the same message follow.
+const MORE: u8 = 0x01;
+/// Frame flag: the size takes eight bytes instead of one.
+const LONG: u8 = 0x02;
+/// Frame flag: the frame is a command, not message data.
+const COMMAND: u8 = 0x04;
+
+/// A ZMQ SUB socket speaking ZMTP 3.0 over TCP with the NULL mechanism,
+/// which is all bitcoind's `zmqpub*` publishers need. Reads never block,
+/// so the event loop can poll it every tick.
+#[derive(Debug)]
+pub struct Subscriber {
+    stream: TcpStream,
+    /// Bytes read but not yet parsed into frames
+    buffer: Vec<u8>,
+    /// Frames of a message whose last frame has not arrived
+    parts: Vec<Vec<u8>>,
+}
+
+/// Our side of the greeting: signature, version 3.0, the NULL mechanism
+/// and the client role.
+fn greeting() -> [u8; 64] {
+    let mut greeting = [0u8; 64];
+    greeting[0] = 0xFF;
+    greeting[9] = 0x7F;
+    greeting[10] = 3;
+    greeting[12..16].copy_from_slice(b"NULL");
+    greeting
+}
+
+/// `body` framed as a short frame with `flags`.
+fn frame(flags: u8, body: &[u8]) -> Vec<u8> {
+    let mut out = vec![flags, u8::try_from(body.len()).unwrap_or(u8::MAX)];
+    out.extend_from_slice(body);
+    out
+}
+
+/// The first complete frame in `buffer`: its flags, body and length
+/// including the header.
+fn parse_frame(buffer: &[u8]) -> Option<(u8, &[u8], usize)> {
+    let flags = *buffer.first()?;
+    let (size, header): (usize, usize) = if flags & LONG == 0 {
+        (usize::from(*buffer.get(1)?), 2)
+    } else {
+        let bytes: [u8; 8] = buffer.get(1..9)?.try_into().ok()?;
+        (usize::try_from(u64::from_be_bytes(bytes)).ok()?, 9)
+    };
+    let body = buffer.get(header..header.checked_add(size)?)?;
+    Some((flags, body, header + size))
+}
+
+impl Subscriber {
+    /// Connects to a `tcp://` publisher `endpoint` and subscribes to
+    /// `topic`. A wildcard host such as `0.0.0.0` means this machine.
+    ///
+    /// # Errors
+    /// Returns why the endpoint could not be reached or did not complete
+    /// the ZMTP handshake.
+    pub fn connect(endpoint: &str, topic: &str, timeout: Duration) -> Result<Self, String> {
+        let Some(address) = endpoint.strip_prefix("tcp://") else {
+            return Err(format!("{endpoint} is not a tcp:// endpoint"));
+        };
+        let (host, port) = match split_host_port(address)? {
+            (host, Some(port)) => (host, port),
+            (_, None) => return Err("tcp:// needs a port".to_string()),
+        };
+        let host = match host {
+            "*" | "0.0.0.0" => "127.0.0.1",
+            "::" => "::1",
+            host => host,
+        };
+        let addr = (host, port)
+            .to_socket_addrs()
+            .map_err(|e| format!("cannot resolve {host}: {e}"))?
+            .next()
+            .ok_or_else(|| format!("cannot resolve {host}"))?;
+        let mut stream = TcpStream::connect_timeout(&addr, timeout)
+            .map_err(|e| format!("cannot connect to {endpoint}: {e}"))?;
+        let io = |e: std::io::Error| format!("ZMQ handshake with {endpoint} failed: {e}");
+        stream.set_read_timeout(Some(timeout)).map_err(io)?;
+        stream.set_write_timeout(Some(timeout)).map_err(io)?;
+
+        stream.write_all(&greeting()).map_err(io)?;
+        let mut theirs = [0u8; 64];
+        stream.read_exact(&mut theirs).map_err(io)?;
+        if theirs[0] != 0xFF || theirs[9] != 0x7F || theirs[10] < 3 {
+            return Err(format!("{endpoint} does not speak ZMTP 3"));
+        }
+        let mut ready = b"\x05READY\x0bSocket-Type".to_vec();
+        ready.extend_from_slice(&3u32.to_be_bytes());
+        ready.extend_from_slice(b"SUB");
+        stream.write_all(&frame(COMMAND, &ready)).map_err(io)?;
+
+        // Wait for the publisher's READY before subscribing
+        let mut subscriber = Self {
+            stream,
+            buffer: Vec::new(),
+            parts: Vec::new(),
+        };
+        loop {
+            let mut chunk = [0u8; 256];
+            let n = subscriber.stream.read(&mut chunk).map_err(io)?;
+            if n == 0 {
+                return Err(format!("{endpoint} closed the connection"));
+            }
+            subscriber.buffer.extend_from_slice(&chunk[..n]);
+            if let Some((flags, body, len)) = parse_frame(&subscriber.buffer) {
+                if flags & COMMAND == 0 || !body.starts_with(b"\x05READY") {
+                    return Err(format!("{endpoint} is not a ZMQ publisher"));
+                }
+                subscriber.buffer.drain(..len);
+                break;
+            }
+        }
+
+        // ZMTP 3.0 subscribes with a message: 1 and the topic prefix
+        let mut subscribe = vec![1];
+        subscribe.extend_from_slice(topic.as_bytes());
+        subscriber
+            .stream
+            .write_all(&frame(0, &subscribe))
+            .map_err(io)?;
+        subscriber.stream.set_nonblocking(true).map_err(io)?;
+        Ok(subscriber)
+    }
+
+    /// Messages received since the last call, each as its frames.
+    ///
+    /// # Errors
+    /// Returns an error once the publisher has gone away.
+    pub fn poll(&mut self) -> Result<Vec<Vec<Vec<u8>>>, String> {
+        let mut chunk = [0u8; 4096];
+        loop {
+            match self.stream.read(&mut chunk) {
+                Ok(0) => return Err("the ZMQ publisher closed the connection".to_string()),
+                Ok(n) => self.buffer.extend_from_slice(&chunk[..n]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+        let mut messages = Vec::new();
+        let mut consumed = 0;
+        while let Some((flags, body, len)) = parse_frame(&self.buffer[consumed..]) {
+            consumed += len;
+            if flags & COMMAND != 0 {
+                continue;
+            }
+            self.parts.push(body.to_vec());
+            if flags & MORE == 0 {
+                messages.push(std::mem::take(&mut self.parts));
+            }
+        }
+        self.buffer.drain(..consumed);
+        Ok(messages)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::time::Instant;
+
+    /// Reads one short frame from `stream`.
+    fn read_frame(stream: &mut TcpStream) -> (u8, Vec<u8>) {
+        let mut header = [0u8; 2];
+        stream.read_exact(&mut header).unwrap();
+        let mut body = vec![0; usize::from(header[1])];
+        stream.read_exact(&mut body).unwrap();
+        (header[0], body)
+    }
+
+    #[test]
+    fn subscriber_receives_multipart_messages() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let publisher = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut theirs = [0u8; 64];
+            stream.read_exact(&mut theirs).unwrap();
+            stream.write_all(&greeting()).unwrap();
+            let (flags, ready) = read_frame(&mut stream);
+            assert_eq!(flags, COMMAND);
+            assert!(ready.ends_with(b"Socket-Type\0\0\0\x03SUB"));
+            stream
+                .write_all(&frame(COMMAND, b"\x05READY\x0bSocket-Type\0\0\0\x03PUB"))
+                .unwrap();
+            let (_, subscribe) = read_frame(&mut stream);
+            assert_eq!(subscribe, b"\x01sequence");
+
+            let mut message = frame(MORE, b"sequence");
+            message.extend(frame(MORE, &[0xAB; 33]));
+            // The last frame split across two writes
+            let last = frame(0, &7u32.to_le_bytes());
+            message.extend_from_slice(&last[..3]);
+            stream.write_all(&message).unwrap();
+            stream.flush().unwrap();
+            std::thread::sleep(Duration::from_millis(50));
+            stream.write_all(&last[3..]).unwrap();
+            std::thread::sleep(Duration::from_millis(200));
+        });
+
+        let mut subscriber = Subscriber::connect(
+            &format!("tcp://0.0.0.0:{port}"),
+            "sequence",
+            Duration::from_secs(5),
+        )
+        .unwrap();
+        let start = Instant::now();
+        let mut messages = Vec::new();
+        while messages.is_empty() && start.elapsed() < Duration::from_secs(5) {
+            messages = subscriber.poll().unwrap();
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        publisher.join().unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0][0], b"sequence");
+        assert_eq!(messages[0][1], [0xAB; 33]);
+        assert_eq!(messages[0][2], 7u32.to_le_bytes());
+    }
+
+    #[test]
+    fn connect_rejects_other_endpoints() {
+        let err = Subscriber::connect("ipc:///tmp/x", "sequence", Duration::from_secs(1));
+        assert!(err.unwrap_err().contains("not a tcp:// endpoint"));
+        let err = Subscriber::connect("tcp://127.0.0.1", "sequence", Duration::from_secs(1));
+        assert_eq!(err.unwrap_err(), "tcp:// needs a port");
+    }
+}